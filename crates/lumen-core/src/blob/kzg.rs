@@ -0,0 +1,372 @@
+use crate::types::beacon::ExecutionPayloadHeader;
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+/// Size of a single blob in bytes (4096 field elements * 32 bytes).
+pub const BYTES_PER_BLOB: usize = 131_072;
+
+/// Gas consumed per blob, as accounted in the execution header's `blob_gas_used`.
+pub const GAS_PER_BLOB: u64 = 131_072;
+
+/// Version byte prefixed to sha256(commitment) to form a versioned hash.
+pub const VERSIONED_HASH_VERSION_KZG: u8 = 0x01;
+
+/// Errors during blob sidecar verification.
+/// Every variant names the offending sidecar so a bad peer or API can be pinpointed.
+#[derive(Debug, Error)]
+pub enum BlobError {
+    #[error("Blob {index} has invalid length: expected {expected} bytes, got {got}")]
+    InvalidBlobLength {
+        index: u64,
+        expected: usize,
+        got: usize,
+    },
+
+    #[error("Blob count mismatch: header accounts for {expected} blobs, got {got} versioned hashes")]
+    BlobCountMismatch { expected: u64, got: usize },
+
+    #[error("Sidecar index {index} is out of range for {count} versioned hashes")]
+    IndexOutOfRange { index: u64, count: usize },
+
+    #[error("Versioned hash mismatch for blob {index}: commitment hashes to {computed}, block commits to {expected}")]
+    VersionedHashMismatch {
+        index: u64,
+        computed: String,
+        expected: String,
+    },
+
+    #[error("Unsupported versioned hash version 0x{version:02x} for blob {index}")]
+    UnsupportedVersion { index: u64, version: u8 },
+
+    #[error("Invalid KZG proof for blob {index}: the blob does not match its commitment")]
+    InvalidProof { index: u64 },
+
+    #[error("KZG error for blob {index}: {reason}")]
+    KzgError { index: u64, reason: String },
+
+    #[error("Cannot check the KZG proof of blob {index}: built without the `kzg` feature")]
+    KzgUnavailable { index: u64 },
+
+    #[error("Header blob_gas_used {blob_gas_used} is not a multiple of {GAS_PER_BLOB}")]
+    InvalidBlobGasUsed { blob_gas_used: u64 },
+
+    #[error("Got {got} sidecars for {expected} versioned hashes")]
+    SidecarCountMismatch { expected: usize, got: usize },
+
+    #[error("Duplicate sidecar for blob {index}")]
+    DuplicateSidecar { index: u64 },
+
+    #[error("Blob {index} is of block {block_root}, not of the verified header {header_root}")]
+    HeaderMismatch {
+        index: u64,
+        block_root: String,
+        header_root: String,
+    },
+
+    #[error("Invalid inclusion proof for blob {index}: its commitment is not in the block body")]
+    InvalidInclusionProof { index: u64 },
+}
+
+/// A blob sidecar as served by the beacon API / gossip.
+/// Only the fields needed for availability verification are kept.
+#[derive(Clone, Debug)]
+pub struct BlobSidecar {
+    /// Index of the blob within its block.
+    pub index: u64,
+    /// The raw blob data (131072 bytes).
+    pub blob: Vec<u8>,
+    /// KZG commitment to the blob polynomial (compressed G1 point).
+    pub kzg_commitment: [u8; 48],
+    /// KZG proof that the blob matches the commitment (compressed G1 point).
+    pub kzg_proof: [u8; 48],
+}
+
+/// Result of verifying a block's blob sidecars.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VerifiedBlobs {
+    /// Execution block number the blobs belong to.
+    pub block_number: u64,
+    /// Versioned hashes that were proven available, by blob index.
+    pub versioned_hashes: Vec<[u8; 32]>,
+}
+
+/// Compute the EIP-4844 versioned hash of a KZG commitment.
+/// versioned_hash = 0x01 || sha256(commitment)[1..]
+pub fn kzg_to_versioned_hash(commitment: &[u8; 48]) -> [u8; 32] {
+    let digest = Sha256::digest(commitment);
+    let mut hash = [0u8; 32];
+    hash.copy_from_slice(&digest);
+    hash[0] = VERSIONED_HASH_VERSION_KZG;
+    hash
+}
+
+/// Verify a single blob sidecar against the versioned hash the block commits to.
+///
+/// Two checks, both required:
+/// 1. The commitment hashes to `expected_versioned_hash` (binds the sidecar to the block)
+/// 2. The KZG proof shows the blob is the polynomial behind the commitment
+pub fn verify_blob_sidecar(
+    sidecar: &BlobSidecar,
+    expected_versioned_hash: &[u8; 32],
+) -> Result<(), BlobError> {
+    if expected_versioned_hash[0] != VERSIONED_HASH_VERSION_KZG {
+        return Err(BlobError::UnsupportedVersion {
+            index: sidecar.index,
+            version: expected_versioned_hash[0],
+        });
+    }
+
+    if sidecar.blob.len() != BYTES_PER_BLOB {
+        return Err(BlobError::InvalidBlobLength {
+            index: sidecar.index,
+            expected: BYTES_PER_BLOB,
+            got: sidecar.blob.len(),
+        });
+    }
+
+    let computed = kzg_to_versioned_hash(&sidecar.kzg_commitment);
+    if computed != *expected_versioned_hash {
+        return Err(BlobError::VersionedHashMismatch {
+            index: sidecar.index,
+            computed: hex::encode(computed),
+            expected: hex::encode(expected_versioned_hash),
+        });
+    }
+
+    verify_kzg_proof(sidecar)
+}
+
+/// Check that the sidecar's KZG proof shows its blob is the polynomial
+/// behind its commitment.
+#[cfg(feature = "kzg")]
+pub(crate) fn verify_kzg_proof(sidecar: &BlobSidecar) -> Result<(), BlobError> {
+    let kzg_error = |e: c_kzg::Error| BlobError::KzgError {
+        index: sidecar.index,
+        reason: format!("{:?}", e),
+    };
+
+    let blob = c_kzg::Blob::from_bytes(&sidecar.blob).map_err(kzg_error)?;
+    let commitment = c_kzg::Bytes48::new(sidecar.kzg_commitment);
+    let proof = c_kzg::Bytes48::new(sidecar.kzg_proof);
+
+    let valid = c_kzg::KzgProof::verify_blob_kzg_proof(
+        &blob,
+        &commitment,
+        &proof,
+        c_kzg::ethereum_kzg_settings(),
+    )
+    .map_err(kzg_error)?;
+
+    if !valid {
+        return Err(BlobError::InvalidProof {
+            index: sidecar.index,
+        });
+    }
+
+    Ok(())
+}
+
+/// Without the `kzg` feature no blob can be shown to match its commitment.
+#[cfg(not(feature = "kzg"))]
+pub(crate) fn verify_kzg_proof(sidecar: &BlobSidecar) -> Result<(), BlobError> {
+    Err(BlobError::KzgUnavailable { index: sidecar.index })
+}
+
+/// Verify all blob sidecars of a block against its verified execution header.
+///
+/// `versioned_hashes` are the blob versioned hashes of the block's type-3
+/// transactions, in block order. **They are trusted input**: nothing here
+/// ties them to the block beyond their count, so the caller must take them
+/// from transactions proven against the header's `transactions_root` (see
+/// [`crate::execution::transaction::verify_transaction_proof`]). The header's
+/// `blob_gas_used` must be a whole number of blobs and account for exactly
+/// that many.
+///
+/// There must be exactly one sidecar per blob, for each index in
+/// `0..versioned_hashes.len()`, in any order. Each is matched to the
+/// versioned hash at its own index; the result lists them by index.
+pub fn verify_blob_sidecars(
+    header: &ExecutionPayloadHeader,
+    versioned_hashes: &[[u8; 32]],
+    sidecars: &[BlobSidecar],
+) -> Result<VerifiedBlobs, BlobError> {
+    if !header.blob_gas_used.is_multiple_of(GAS_PER_BLOB) {
+        return Err(BlobError::InvalidBlobGasUsed {
+            blob_gas_used: header.blob_gas_used,
+        });
+    }
+    let expected_blobs = header.blob_gas_used / GAS_PER_BLOB;
+    if expected_blobs != versioned_hashes.len() as u64 {
+        return Err(BlobError::BlobCountMismatch {
+            expected: expected_blobs,
+            got: versioned_hashes.len(),
+        });
+    }
+    if sidecars.len() != versioned_hashes.len() {
+        return Err(BlobError::SidecarCountMismatch {
+            expected: versioned_hashes.len(),
+            got: sidecars.len(),
+        });
+    }
+
+    // As many sidecars as hashes, each index in range and none repeated:
+    // every blob is covered exactly once. Checked before any KZG work.
+    let mut seen = vec![false; versioned_hashes.len()];
+    for sidecar in sidecars {
+        let index = sidecar.index;
+        let Some(seen) = seen.get_mut(index as usize) else {
+            return Err(BlobError::IndexOutOfRange {
+                index,
+                count: versioned_hashes.len(),
+            });
+        };
+        if std::mem::replace(seen, true) {
+            return Err(BlobError::DuplicateSidecar { index });
+        }
+    }
+
+    for sidecar in sidecars {
+        verify_blob_sidecar(sidecar, &versioned_hashes[sidecar.index as usize])?;
+    }
+
+    Ok(VerifiedBlobs {
+        block_number: header.block_number,
+        versioned_hashes: versioned_hashes.to_vec(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::uint::U256;
+
+    /// Commitment and proof for the all-zero blob: the G1 point at infinity.
+    fn zero_blob_sidecar(index: u64) -> BlobSidecar {
+        let mut infinity = [0u8; 48];
+        infinity[0] = 0xc0;
+        BlobSidecar {
+            index,
+            blob: vec![0u8; BYTES_PER_BLOB],
+            kzg_commitment: infinity,
+            kzg_proof: infinity,
+        }
+    }
+
+    fn make_header(blob_gas_used: u64) -> ExecutionPayloadHeader {
+        ExecutionPayloadHeader {
+            parent_hash: [0; 32],
+            fee_recipient: [0; 20],
+            state_root: [0; 32],
+            receipts_root: [0; 32],
+            block_number: 100,
+            gas_limit: 30_000_000,
+            gas_used: 0,
+            timestamp: 0,
+            base_fee_per_gas: U256::from_u64(7),
+            block_hash: [0; 32],
+            transactions_root: [0; 32],
+            withdrawals_root: [0; 32],
+            blob_gas_used,
+            excess_blob_gas: 0,
+        }
+    }
+
+    #[test]
+    fn test_versioned_hash_has_version_prefix() {
+        let hash = kzg_to_versioned_hash(&[0xAB; 48]);
+        assert_eq!(hash[0], VERSIONED_HASH_VERSION_KZG);
+        let digest = Sha256::digest([0xAB; 48]);
+        assert_eq!(hash[1..], digest[1..]);
+    }
+
+    #[test]
+    #[cfg(feature = "kzg")]
+    fn test_zero_blob_verifies() {
+        let sidecar = zero_blob_sidecar(0);
+        let hash = kzg_to_versioned_hash(&sidecar.kzg_commitment);
+        verify_blob_sidecar(&sidecar, &hash).unwrap();
+    }
+
+    #[test]
+    fn test_wrong_versioned_hash_rejected() {
+        let sidecar = zero_blob_sidecar(0);
+        let mut hash = [0x11; 32];
+        hash[0] = VERSIONED_HASH_VERSION_KZG;
+        assert!(matches!(
+            verify_blob_sidecar(&sidecar, &hash),
+            Err(BlobError::VersionedHashMismatch { index: 0, .. })
+        ));
+    }
+
+    #[test]
+    #[cfg(feature = "kzg")]
+    fn test_tampered_blob_rejected() {
+        let mut sidecar = zero_blob_sidecar(0);
+        sidecar.blob[31] = 1; // still a canonical field element
+        let hash = kzg_to_versioned_hash(&sidecar.kzg_commitment);
+        assert!(matches!(
+            verify_blob_sidecar(&sidecar, &hash),
+            Err(BlobError::InvalidProof { index: 0 })
+        ));
+    }
+
+    #[test]
+    fn test_invalid_blob_length_rejected() {
+        let mut sidecar = zero_blob_sidecar(0);
+        sidecar.blob.truncate(100);
+        let hash = kzg_to_versioned_hash(&sidecar.kzg_commitment);
+        assert!(matches!(
+            verify_blob_sidecar(&sidecar, &hash),
+            Err(BlobError::InvalidBlobLength { got: 100, .. })
+        ));
+    }
+
+    #[test]
+    #[cfg(feature = "kzg")]
+    fn test_sidecars_checked_against_header_blob_gas() {
+        let sidecars = vec![zero_blob_sidecar(1), zero_blob_sidecar(0)];
+        let hash = kzg_to_versioned_hash(&sidecars[0].kzg_commitment);
+
+        let verified = verify_blob_sidecars(&make_header(2 * GAS_PER_BLOB), &[hash, hash], &sidecars).unwrap();
+        assert_eq!(verified.block_number, 100);
+        assert_eq!(verified.versioned_hashes, vec![hash, hash]);
+
+        let result = verify_blob_sidecars(&make_header(3 * GAS_PER_BLOB), &[hash, hash], &sidecars);
+        assert!(matches!(
+            result,
+            Err(BlobError::BlobCountMismatch { expected: 3, got: 2 })
+        ));
+    }
+
+    #[test]
+    fn test_sidecars_must_cover_each_blob_once() {
+        let hash = kzg_to_versioned_hash(&zero_blob_sidecar(0).kzg_commitment);
+        let header = make_header(2 * GAS_PER_BLOB);
+        let check = |indices: &[u64]| {
+            let sidecars: Vec<_> = indices.iter().map(|&index| zero_blob_sidecar(index)).collect();
+            verify_blob_sidecars(&header, &[hash, hash], &sidecars)
+        };
+
+        assert!(matches!(check(&[]), Err(BlobError::SidecarCountMismatch { expected: 2, got: 0 })));
+        assert!(matches!(check(&[0]), Err(BlobError::SidecarCountMismatch { expected: 2, got: 1 })));
+        assert!(matches!(check(&[0, 1, 1]), Err(BlobError::SidecarCountMismatch { expected: 2, got: 3 })));
+        assert!(matches!(check(&[0, 0]), Err(BlobError::DuplicateSidecar { index: 0 })));
+        assert!(matches!(check(&[0, 2]), Err(BlobError::IndexOutOfRange { index: 2, count: 2 })));
+    }
+
+    #[test]
+    fn test_partial_blob_gas_rejected() {
+        let sidecars = vec![zero_blob_sidecar(0)];
+        let hash = kzg_to_versioned_hash(&sidecars[0].kzg_commitment);
+        let result = verify_blob_sidecars(&make_header(GAS_PER_BLOB + 1), &[hash], &sidecars);
+        assert!(matches!(result, Err(BlobError::InvalidBlobGasUsed { blob_gas_used }) if blob_gas_used == GAS_PER_BLOB + 1));
+    }
+
+    #[test]
+    #[cfg(not(feature = "kzg"))]
+    fn test_kzg_unavailable_without_feature() {
+        let sidecar = zero_blob_sidecar(0);
+        let hash = kzg_to_versioned_hash(&sidecar.kzg_commitment);
+        assert!(matches!(verify_blob_sidecar(&sidecar, &hash), Err(BlobError::KzgUnavailable { index: 0 })));
+    }
+}
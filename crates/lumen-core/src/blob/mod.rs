@@ -0,0 +1,5 @@
+pub mod kzg;
+pub mod sidecar;
+
+pub use kzg::*;
+pub use sidecar::*;
@@ -0,0 +1,215 @@
+use crate::blob::kzg::{kzg_to_versioned_hash, verify_kzg_proof, BlobError, BlobSidecar, BYTES_PER_BLOB};
+use crate::consensus::ssz::SszError;
+use crate::consensus::sync_committee::{hash_beacon_block_header, verify_merkle_branch};
+use crate::types::beacon::BeaconBlockHeader;
+use sha2::{Digest, Sha256};
+
+/// Depth of a commitment's inclusion proof in the block body
+/// (`KZG_COMMITMENT_INCLUSION_PROOF_DEPTH`): 4 for the body's fields, 1 for
+/// the list's length and 12 for its commitments.
+pub const KZG_COMMITMENT_INCLUSION_PROOF_DEPTH: usize = 17;
+
+/// Limit of the body's `blob_kzg_commitments` list.
+pub const MAX_BLOB_COMMITMENTS_PER_BLOCK: u64 = 4096;
+
+/// Index of `blob_kzg_commitments` among the body's fields.
+const BLOB_KZG_COMMITMENTS_INDEX: u64 = 11;
+
+/// index + blob + commitment + proof + signed block header + inclusion proof.
+const BLOB_SIDECAR_LEN: usize = 8 + BYTES_PER_BLOB + 48 + 48 + (112 + 96) + 32 * KZG_COMMITMENT_INCLUSION_PROOF_DEPTH;
+
+/// A blob sidecar as gossiped on `blob_sidecar_{subnet}`: the blob with
+/// the header of its block and a proof that its commitment is in that
+/// block's body. The header's proposer signature is not kept.
+#[derive(Clone, Debug)]
+pub struct DecodedBlobSidecar {
+    pub sidecar: BlobSidecar,
+    pub header: BeaconBlockHeader,
+    pub inclusion_proof: Vec<[u8; 32]>,
+}
+
+impl DecodedBlobSidecar {
+    /// The root of the block the blob is of.
+    pub fn block_root(&self) -> [u8; 32] {
+        hash_beacon_block_header(&self.header)
+    }
+
+    /// Check that this blob is available for the block `verified` names:
+    /// the sidecar is of that block, its commitment is in the block's body,
+    /// and its KZG proof binds the blob to the commitment. `verified` must
+    /// have passed sync committee verification. Returns the blob's
+    /// versioned hash.
+    pub fn verify_for(&self, verified: &BeaconBlockHeader) -> Result<[u8; 32], BlobError> {
+        let index = self.sidecar.index;
+        let block_root = self.block_root();
+        let header_root = hash_beacon_block_header(verified);
+        if block_root != header_root {
+            return Err(BlobError::HeaderMismatch {
+                index,
+                block_root: hex::encode(block_root),
+                header_root: hex::encode(header_root),
+            });
+        }
+        if !verify_kzg_commitment_inclusion(&self.sidecar, &self.inclusion_proof, &verified.body_root) {
+            return Err(BlobError::InvalidInclusionProof { index });
+        }
+        verify_kzg_proof(&self.sidecar)?;
+        Ok(kzg_to_versioned_hash(&self.sidecar.kzg_commitment))
+    }
+}
+
+/// Verify that `sidecar`'s commitment is at its index in the
+/// `blob_kzg_commitments` of the block body with root `body_root`.
+pub fn verify_kzg_commitment_inclusion(sidecar: &BlobSidecar, proof: &[[u8; 32]], body_root: &[u8; 32]) -> bool {
+    if sidecar.index >= MAX_BLOB_COMMITMENTS_PER_BLOCK {
+        return false;
+    }
+    // Commitment at `index` of the list's data, the left of its length.
+    let subtree_index = (BLOB_KZG_COMMITMENTS_INDEX << 13) | sidecar.index;
+    let leaf = commitment_root(&sidecar.kzg_commitment);
+    verify_merkle_branch(&leaf, proof, KZG_COMMITMENT_INCLUSION_PROOF_DEPTH, subtree_index, body_root)
+}
+
+/// Decode an SSZ `BlobSidecar`.
+pub fn decode_blob_sidecar(bytes: &[u8]) -> Result<DecodedBlobSidecar, SszError> {
+    const CONTAINER: &str = "BlobSidecar";
+    if bytes.len() != BLOB_SIDECAR_LEN {
+        return Err(if bytes.len() < BLOB_SIDECAR_LEN {
+            SszError::TooShort { container: CONTAINER, expected: BLOB_SIDECAR_LEN, found: bytes.len() }
+        } else {
+            SszError::InvalidField { container: CONTAINER, field: "length" }
+        });
+    }
+
+    let (index, rest) = bytes.split_at(8);
+    let (blob, rest) = rest.split_at(BYTES_PER_BLOB);
+    let (kzg_commitment, rest) = rest.split_at(48);
+    let (kzg_proof, rest) = rest.split_at(48);
+    let (header, rest) = rest.split_at(112);
+    let (_signature, proof) = rest.split_at(96);
+
+    let u64_at = |at: usize| u64::from_le_bytes(header[at..at + 8].try_into().expect("8 bytes"));
+    let bytes32_at = |at: usize| -> [u8; 32] { header[at..at + 32].try_into().expect("32 bytes") };
+    Ok(DecodedBlobSidecar {
+        sidecar: BlobSidecar {
+            index: u64::from_le_bytes(index.try_into().expect("8 bytes")),
+            blob: blob.to_vec(),
+            kzg_commitment: kzg_commitment.try_into().expect("48 bytes"),
+            kzg_proof: kzg_proof.try_into().expect("48 bytes"),
+        },
+        header: BeaconBlockHeader {
+            slot: u64_at(0),
+            proposer_index: u64_at(8),
+            parent_root: bytes32_at(16),
+            state_root: bytes32_at(48),
+            body_root: bytes32_at(80),
+        },
+        inclusion_proof: proof.chunks_exact(32).map(|node| node.try_into().expect("32 bytes")).collect(),
+    })
+}
+
+/// `hash_tree_root` of a 48-byte commitment: its two zero-padded chunks.
+fn commitment_root(commitment: &[u8; 48]) -> [u8; 32] {
+    let mut chunks = [0u8; 64];
+    chunks[..48].copy_from_slice(commitment);
+    Sha256::digest(chunks).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pair(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update(left);
+        hasher.update(right);
+        hasher.finalize().into()
+    }
+
+    /// Merkle root of `leaves`, a power of two of them, and the proof of the one at `index`.
+    fn tree(leaves: Vec<[u8; 32]>, mut index: usize) -> ([u8; 32], Vec<[u8; 32]>) {
+        let (mut layer, mut proof) = (leaves, Vec::new());
+        while layer.len() > 1 {
+            proof.push(layer[index ^ 1]);
+            layer = layer.chunks_exact(2).map(|nodes| pair(&nodes[0], &nodes[1])).collect();
+            index /= 2;
+        }
+        (layer[0], proof)
+    }
+
+    /// The all-zero blob at `index` of a block body built the long way:
+    /// its commitments list, then the body around it.
+    fn sidecar_in_block(index: u64) -> DecodedBlobSidecar {
+        let mut infinity = [0u8; 48];
+        infinity[0] = 0xc0;
+        let blob = vec![0u8; BYTES_PER_BLOB];
+        let sidecar = BlobSidecar { index, blob, kzg_commitment: infinity, kzg_proof: infinity };
+
+        let count = index as usize + 2;
+        let mut commitments = vec![[0u8; 32]; MAX_BLOB_COMMITMENTS_PER_BLOCK as usize];
+        commitments[..count].fill(commitment_root(&infinity));
+        let (data_root, mut proof) = tree(commitments, index as usize);
+        let mut length = [0u8; 32];
+        length[..8].copy_from_slice(&(count as u64).to_le_bytes());
+        proof.push(length);
+
+        let mut body = vec![[0x33; 32]; 16];
+        body[BLOB_KZG_COMMITMENTS_INDEX as usize] = pair(&data_root, &length);
+        let (body_root, body_proof) = tree(body, BLOB_KZG_COMMITMENTS_INDEX as usize);
+        proof.extend(body_proof);
+
+        let header =
+            BeaconBlockHeader { slot: 9000, proposer_index: 7, parent_root: [1; 32], state_root: [2; 32], body_root };
+        DecodedBlobSidecar { sidecar, header, inclusion_proof: proof }
+    }
+
+    fn encode(decoded: &DecodedBlobSidecar) -> Vec<u8> {
+        let mut out = decoded.sidecar.index.to_le_bytes().to_vec();
+        out.extend_from_slice(&decoded.sidecar.blob);
+        out.extend_from_slice(&decoded.sidecar.kzg_commitment);
+        out.extend_from_slice(&decoded.sidecar.kzg_proof);
+        let header = &decoded.header;
+        out.extend_from_slice(&header.slot.to_le_bytes());
+        out.extend_from_slice(&header.proposer_index.to_le_bytes());
+        out.extend_from_slice(&header.parent_root);
+        out.extend_from_slice(&header.state_root);
+        out.extend_from_slice(&header.body_root);
+        out.extend_from_slice(&[0xAA; 96]);
+        decoded.inclusion_proof.iter().for_each(|node| out.extend_from_slice(node));
+        out
+    }
+
+    #[test]
+    #[cfg(feature = "kzg")]
+    fn test_sidecar_available_for_verified_header() {
+        let built = sidecar_in_block(3);
+        let decoded = decode_blob_sidecar(&encode(&built)).unwrap();
+        assert_eq!(decoded.header, built.header);
+        assert_eq!(decoded.inclusion_proof.len(), KZG_COMMITMENT_INCLUSION_PROOF_DEPTH);
+
+        let hash = decoded.verify_for(&built.header).unwrap();
+        assert_eq!(hash, kzg_to_versioned_hash(&decoded.sidecar.kzg_commitment));
+
+        let other = BeaconBlockHeader { slot: 9001, ..built.header.clone() };
+        assert!(matches!(decoded.verify_for(&other), Err(BlobError::HeaderMismatch { index: 3, .. })));
+
+        // Claiming another index moves the commitment out from under the
+        // proof: past the list's five commitments, its sibling is a zero leaf.
+        let mut moved = decoded.clone();
+        moved.sidecar.index = 6;
+        assert!(matches!(moved.verify_for(&built.header), Err(BlobError::InvalidInclusionProof { index: 6 })));
+
+        let mut tampered = decoded;
+        tampered.sidecar.blob[0] = 1;
+        assert!(tampered.verify_for(&built.header).is_err());
+    }
+
+    #[test]
+    fn test_sidecar_length() {
+        let bytes = encode(&sidecar_in_block(0));
+        assert!(matches!(decode_blob_sidecar(&bytes[1..]), Err(SszError::TooShort { .. })));
+        let mut longer = bytes;
+        longer.push(0);
+        assert!(matches!(decode_blob_sidecar(&longer), Err(SszError::InvalidField { .. })));
+    }
+}
@@ -0,0 +1,275 @@
+use crate::consensus::sync_committee::{aggregate_pubkeys, aggregation_error, deserialize_pubkey, VerificationError};
+use crate::types::beacon::{BlsPublicKey, SyncAggregate, SyncCommittee};
+use blst::min_pk::{AggregatePublicKey, PublicKey};
+use sha2::{Digest, Sha256};
+
+/// Default cap on cached aggregates. Each is a 96-byte point plus its
+/// 64-byte bitfield, so the cache stays a few KB.
+pub const DEFAULT_MAX_AGGREGATES: usize = 16;
+
+/// Most keys added to a cached aggregate to reach a new bitfield; past
+/// this, aggregating from scratch is about as cheap.
+pub const MAX_EXTENSION: usize = 64;
+
+/// Cache of aggregated sync committee public keys, keyed by committee and
+/// participation bitfield.
+///
+/// Consecutive updates are usually signed by the same participants, or by
+/// the same ones plus a few stragglers, yet every verification deserializes
+/// and sums ~500 keys. An exact bitfield match reuses the cached aggregate;
+/// a bitfield that adds at most [`MAX_EXTENSION`] participants to a cached
+/// one extends that aggregate with the new keys only.
+///
+/// An aggregate is a pure function of the committee's keys and the bitfield,
+/// so a cached entry cannot make a bad signature verify. Committees are
+/// identified by a hash of all their keys, never by their (unverified)
+/// `aggregate_pubkey`. The least recently used entry is evicted when full.
+#[derive(Clone, Debug)]
+pub struct AggregatePubkeyCache {
+    /// Least recently used first.
+    entries: Vec<Entry>,
+    max_entries: usize,
+    hits: u64,
+    extensions: u64,
+    misses: u64,
+}
+
+#[derive(Clone, Debug)]
+struct Entry {
+    committee: [u8; 32],
+    bits: Vec<u8>,
+    aggregate: AggregatePublicKey,
+}
+
+impl Default for AggregatePubkeyCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_AGGREGATES)
+    }
+}
+
+impl AggregatePubkeyCache {
+    /// Create an empty cache holding at most `max_entries` aggregates.
+    pub fn new(max_entries: usize) -> Self {
+        Self {
+            entries: Vec::new(),
+            max_entries,
+            hits: 0,
+            extensions: 0,
+            misses: 0,
+        }
+    }
+
+    /// The aggregate public key of `committee`'s members that participated
+    /// in `sync_aggregate`, from the cache when possible.
+    pub(crate) fn aggregate(
+        &mut self,
+        committee: &SyncCommittee,
+        sync_aggregate: &SyncAggregate,
+    ) -> Result<PublicKey, VerificationError> {
+        let committee_id = committee_id(committee);
+        let bits = &sync_aggregate.sync_committee_bits;
+
+        if let Some(index) = self
+            .entries
+            .iter()
+            .position(|e| e.committee == committee_id && e.bits == *bits)
+        {
+            self.hits += 1;
+            let entry = self.entries.remove(index);
+            let aggregate = entry.aggregate.to_public_key();
+            self.entries.push(entry);
+            return Ok(aggregate);
+        }
+
+        let base = self
+            .entries
+            .iter()
+            .filter(|e| e.committee == committee_id)
+            .filter_map(|e| added_participants(&e.bits, bits).map(|added| (e.aggregate, added)))
+            .filter(|(_, added)| added.len() <= MAX_EXTENSION)
+            .min_by_key(|(_, added)| added.len());
+
+        let aggregate = match base {
+            Some((mut aggregate, added)) => {
+                self.extensions += 1;
+                for index in added {
+                    let pubkey = deserialize_pubkey(index, &committee.pubkeys[index])?;
+                    aggregate.add_public_key(&pubkey, false).map_err(aggregation_error)?;
+                }
+                aggregate
+            }
+            None => {
+                self.misses += 1;
+                let participants: Vec<&BlsPublicKey> = sync_aggregate
+                    .participant_indices()
+                    .into_iter()
+                    .map(|i| &committee.pubkeys[i])
+                    .collect();
+                AggregatePublicKey::from_public_key(&aggregate_pubkeys(&participants)?)
+            }
+        };
+
+        if self.max_entries > 0 {
+            if self.entries.len() == self.max_entries {
+                self.entries.remove(0);
+            }
+            self.entries.push(Entry {
+                committee: committee_id,
+                bits: bits.clone(),
+                aggregate,
+            });
+        }
+        Ok(aggregate.to_public_key())
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Most aggregates kept before the least recently used is evicted.
+    pub fn capacity(&self) -> usize {
+        self.max_entries
+    }
+
+    /// Approximate memory held by the cached aggregates and their keys.
+    pub fn approx_bytes(&self) -> usize {
+        self.entries
+            .iter()
+            .map(|e| std::mem::size_of::<Entry>() + e.bits.len())
+            .sum()
+    }
+
+    /// Verifications that reused a cached aggregate as is.
+    pub fn hits(&self) -> u64 {
+        self.hits
+    }
+
+    /// Verifications that extended a cached aggregate with a few keys.
+    pub fn extensions(&self) -> u64 {
+        self.extensions
+    }
+
+    /// Verifications that aggregated every participant.
+    pub fn misses(&self) -> u64 {
+        self.misses
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+/// sha256 over a committee's concatenated public keys.
+fn committee_id(committee: &SyncCommittee) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    for pubkey in &committee.pubkeys {
+        hasher.update(pubkey.0);
+    }
+    hasher.finalize().into()
+}
+
+/// Indices set in `new` but not in `cached`, or None if `cached` has a
+/// participant `new` lacks (removing keys would need a subtraction).
+fn added_participants(cached: &[u8], new: &[u8]) -> Option<Vec<usize>> {
+    if cached.len() != new.len() || cached.iter().zip(new).any(|(c, n)| c & !n != 0) {
+        return None;
+    }
+    Some(
+        (0..new.len() * 8)
+            .filter(|&i| (new[i / 8] & !cached[i / 8]) >> (i % 8) & 1 == 1)
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::beacon::{BlsSignature, SYNC_COMMITTEE_SIZE};
+    use blst::min_pk::SecretKey;
+
+    fn committee() -> SyncCommittee {
+        let pubkeys = (0..SYNC_COMMITTEE_SIZE)
+            .map(|i| {
+                let ikm = [(i % 256) as u8, (i / 256) as u8].repeat(16);
+                BlsPublicKey(SecretKey::key_gen(&ikm, &[]).unwrap().sk_to_pk().compress())
+            })
+            .collect();
+        SyncCommittee {
+            pubkeys,
+            aggregate_pubkey: BlsPublicKey([0; 48]),
+        }
+    }
+
+    /// Participation of the first `count` members.
+    fn first(count: usize) -> SyncAggregate {
+        let mut bits = vec![0u8; SYNC_COMMITTEE_SIZE / 8];
+        for i in 0..count {
+            bits[i / 8] |= 1 << (i % 8);
+        }
+        SyncAggregate {
+            sync_committee_bits: bits,
+            sync_committee_signature: BlsSignature([0; 96]),
+        }
+    }
+
+    fn uncached(committee: &SyncCommittee, sync_aggregate: &SyncAggregate) -> PublicKey {
+        let participants: Vec<&BlsPublicKey> = sync_aggregate
+            .participant_indices()
+            .into_iter()
+            .map(|i| &committee.pubkeys[i])
+            .collect();
+        aggregate_pubkeys(&participants).unwrap()
+    }
+
+    #[test]
+    fn test_hits_and_extensions_match_full_aggregation() {
+        let committee = committee();
+        let mut cache = AggregatePubkeyCache::default();
+
+        let full = cache.aggregate(&committee, &first(400)).unwrap();
+        assert_eq!(full, uncached(&committee, &first(400)));
+        assert_eq!(cache.aggregate(&committee, &first(400)).unwrap(), full);
+
+        // A few more signers extend the cached aggregate.
+        let extended = cache.aggregate(&committee, &first(420)).unwrap();
+        assert_eq!(extended, uncached(&committee, &first(420)));
+        // Fewer signers cannot be derived by addition.
+        cache.aggregate(&committee, &first(390)).unwrap();
+        assert_eq!((cache.hits(), cache.extensions(), cache.misses()), (1, 1, 2));
+
+        // A different committee never shares entries.
+        let mut other = committee.clone();
+        other.pubkeys.swap(0, 1);
+        cache.aggregate(&other, &first(400)).unwrap();
+        assert_eq!(cache.misses(), 3);
+    }
+
+    #[test]
+    fn test_least_recently_used_is_evicted() {
+        let committee = committee();
+        let mut cache = AggregatePubkeyCache::new(2);
+        cache.aggregate(&committee, &first(350)).unwrap();
+        cache.aggregate(&committee, &first(300)).unwrap();
+        cache.aggregate(&committee, &first(350)).unwrap();
+        cache.aggregate(&committee, &first(200)).unwrap();
+        assert_eq!(cache.len(), 2);
+
+        // 350 was used more recently than 300, so 300 was dropped.
+        cache.aggregate(&committee, &first(350)).unwrap();
+        assert_eq!(cache.hits(), 2);
+        cache.aggregate(&committee, &first(300)).unwrap();
+        assert_eq!(cache.hits(), 2);
+    }
+
+    #[test]
+    fn test_added_participants() {
+        assert_eq!(added_participants(&[0b0101], &[0b0111]), Some(vec![1]));
+        assert_eq!(added_participants(&[0b0101], &[0b0101, 0]), None);
+        assert_eq!(added_participants(&[0b0101], &[0b0011]), None);
+        assert_eq!(added_participants(&[0, 0], &[0, 0b1000_0000]), Some(vec![15]));
+    }
+}
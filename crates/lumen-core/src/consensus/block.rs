@@ -0,0 +1,487 @@
+//! Execution payload headers from full beacon blocks.
+//!
+//! A light client update proves the execution header of the block it
+//! describes with an execution branch into that block's `body_root`. A
+//! full `SignedBeaconBlock`, as gossiped on `beacon_block`, carries the
+//! whole execution payload instead: decoding it yields the block's header
+//! (with `body_root` merkleized from the body), the payload's header and
+//! the same branch, built here rather than sent.
+//!
+//! Nothing in a block is trusted by itself — Lumen does not check proposer
+//! signatures. Its execution header is used only once the block is the one
+//! a sync-committee-verified header describes, and its execution branch
+//! verifies against that header's `body_root`: see
+//! [`DecodedBeaconBlock::execution_for`]. Only the Electra layout is decoded.
+
+use crate::consensus::ssz::SszError;
+use crate::consensus::sync_committee::{hash_beacon_block_header, merkle_layer, verify_merkle_branch, VerificationError};
+use crate::types::beacon::{BeaconBlockHeader, ExecutionPayloadHeader};
+use crate::types::uint::U256;
+use ssz_rs::prelude::*;
+
+/// Depth of the execution payload branch within the beacon block body
+/// (`EXECUTION_PAYLOAD_GINDEX` = 25).
+pub const EXECUTION_BRANCH_DEPTH: usize = 4;
+/// Index of `execution_payload` among the body's fields.
+pub const EXECUTION_PAYLOAD_INDEX: u64 = 9;
+
+/// Leaves of the body's merkle tree: 13 fields, padded to a power of two.
+const BODY_LEAVES: usize = 1 << EXECUTION_BRANCH_DEPTH;
+
+const MAX_VALIDATORS_PER_SLOT: usize = 2048 * 64;
+const MAX_BYTES_PER_TRANSACTION: usize = 1 << 30;
+const MAX_TRANSACTIONS_PER_PAYLOAD: usize = 1 << 20;
+
+/// A beacon block reduced to what a light client keeps of it.
+#[derive(Clone, Debug)]
+pub struct DecodedBeaconBlock {
+    /// The block's header, with `body_root` computed from its body.
+    pub header: BeaconBlockHeader,
+    pub execution: ExecutionPayloadHeader,
+    /// `hash_tree_root` of the execution payload.
+    pub execution_root: [u8; 32],
+    /// Proof of `execution_root` in `header.body_root`.
+    pub execution_branch: Vec<[u8; 32]>,
+}
+
+impl DecodedBeaconBlock {
+    /// The block root: `hash_tree_root` of its header.
+    pub fn root(&self) -> [u8; 32] {
+        hash_beacon_block_header(&self.header)
+    }
+
+    /// This block's execution header, if it is the block `verified`
+    /// describes and its execution branch verifies against `verified`'s
+    /// `body_root`. `verified` must have passed sync committee verification.
+    pub fn execution_for(&self, verified: &BeaconBlockHeader) -> Result<&ExecutionPayloadHeader, VerificationError> {
+        let block_root = self.root();
+        let header_root = hash_beacon_block_header(verified);
+        if block_root != header_root {
+            return Err(VerificationError::BlockRootMismatch {
+                block_root: hex::encode(block_root),
+                header_root: hex::encode(header_root),
+            });
+        }
+        if !verify_execution_branch(&self.execution_root, &self.execution_branch, &verified.body_root) {
+            return Err(VerificationError::InvalidExecutionBranch);
+        }
+        Ok(&self.execution)
+    }
+}
+
+/// Verify that `execution_root` is the execution payload of the block body
+/// with root `body_root`.
+pub fn verify_execution_branch(execution_root: &[u8; 32], branch: &[[u8; 32]], body_root: &[u8; 32]) -> bool {
+    verify_merkle_branch(execution_root, branch, EXECUTION_BRANCH_DEPTH, EXECUTION_PAYLOAD_INDEX, body_root)
+}
+
+/// The `body_root` that `branch` proves `execution_root` in, for building
+/// light client headers in tests.
+#[cfg(any(test, feature = "test-utils"))]
+pub fn execution_body_root(execution_root: &[u8; 32], branch: &[[u8; 32]]) -> [u8; 32] {
+    use crate::consensus::sync_committee::sha256_pair;
+    branch.iter().enumerate().fold(*execution_root, |node, (depth, sibling)| {
+        if (EXECUTION_PAYLOAD_INDEX >> depth) & 1 == 1 {
+            sha256_pair(sibling, &node)
+        } else {
+            sha256_pair(&node, sibling)
+        }
+    })
+}
+
+/// `hash_tree_root` of an SSZ `ExecutionPayloadHeader`, as a light client
+/// header carries it. It is the root of the payload the header is of.
+pub fn execution_header_root(bytes: &[u8]) -> Result<[u8; 32], SszError> {
+    let mut header = SszExecutionPayloadHeader::deserialize(bytes).map_err(|e| SszError::Malformed {
+        container: "ExecutionPayloadHeader",
+        reason: e.to_string(),
+    })?;
+    root(&mut header)
+}
+
+/// Decode an SSZ `SignedBeaconBlock`. The signature is read but not checked.
+pub fn decode_signed_beacon_block(bytes: &[u8]) -> Result<DecodedBeaconBlock, SszError> {
+    let mut block = SignedBeaconBlock::deserialize(bytes).map_err(|e| malformed(e.to_string()))?;
+    let block = &mut block.message;
+
+    let leaves = body_leaves(&mut block.body)?;
+    let execution_root = leaves[EXECUTION_PAYLOAD_INDEX as usize];
+    let mut layer = leaves.to_vec();
+    let mut execution_branch = Vec::with_capacity(EXECUTION_BRANCH_DEPTH);
+    for depth in 0..EXECUTION_BRANCH_DEPTH {
+        execution_branch.push(layer[(EXECUTION_PAYLOAD_INDEX >> depth) as usize ^ 1]);
+        layer = merkle_layer(&layer);
+    }
+
+    let payload = &mut block.body.execution_payload;
+    let mut base_fee_per_gas = payload.base_fee_per_gas;
+    base_fee_per_gas.reverse(); // SSZ uint256 is little-endian
+    let execution = ExecutionPayloadHeader {
+        parent_hash: payload.parent_hash,
+        fee_recipient: payload.fee_recipient,
+        state_root: payload.state_root,
+        receipts_root: payload.receipts_root,
+        block_number: payload.block_number,
+        gas_limit: payload.gas_limit,
+        gas_used: payload.gas_used,
+        timestamp: payload.timestamp,
+        base_fee_per_gas: U256::from_be_bytes(base_fee_per_gas),
+        block_hash: payload.block_hash,
+        transactions_root: root(&mut payload.transactions)?,
+        withdrawals_root: root(&mut payload.withdrawals)?,
+        blob_gas_used: payload.blob_gas_used,
+        excess_blob_gas: payload.excess_blob_gas,
+    };
+
+    Ok(DecodedBeaconBlock {
+        header: BeaconBlockHeader {
+            slot: block.slot,
+            proposer_index: block.proposer_index,
+            parent_root: block.parent_root,
+            state_root: block.state_root,
+            body_root: layer[0],
+        },
+        execution,
+        execution_root,
+        execution_branch,
+    })
+}
+
+/// The roots of the body's fields, in order, then zero leaves.
+fn body_leaves(body: &mut BeaconBlockBody) -> Result<[[u8; 32]; BODY_LEAVES], SszError> {
+    Ok([
+        root(&mut body.randao_reveal)?,
+        root(&mut body.eth1_data)?,
+        root(&mut body.graffiti)?,
+        root(&mut body.proposer_slashings)?,
+        root(&mut body.attester_slashings)?,
+        root(&mut body.attestations)?,
+        root(&mut body.deposits)?,
+        root(&mut body.voluntary_exits)?,
+        root(&mut body.sync_aggregate)?,
+        root(&mut body.execution_payload)?,
+        root(&mut body.bls_to_execution_changes)?,
+        root(&mut body.blob_kzg_commitments)?,
+        root(&mut body.execution_requests)?,
+        [0u8; 32],
+        [0u8; 32],
+        [0u8; 32],
+    ])
+}
+
+fn root<T: Merkleized>(value: &mut T) -> Result<[u8; 32], SszError> {
+    let node = value.hash_tree_root().map_err(|e| malformed(e.to_string()))?;
+    let mut out = [0u8; 32];
+    out.copy_from_slice(node.as_ref());
+    Ok(out)
+}
+
+fn malformed(reason: String) -> SszError {
+    SszError::Malformed { container: "SignedBeaconBlock", reason }
+}
+
+// --- Electra containers, as the consensus specs define them ---
+
+type Bytes32 = [u8; 32];
+type BlsPubkey = Vector<u8, 48>;
+type BlsSignature = Vector<u8, 96>;
+type Transaction = List<u8, MAX_BYTES_PER_TRANSACTION>;
+
+#[derive(Default, Debug, SimpleSerialize)]
+struct SignedBeaconBlock {
+    message: BeaconBlock,
+    signature: BlsSignature,
+}
+
+#[derive(Default, Debug, SimpleSerialize)]
+struct BeaconBlock {
+    slot: u64,
+    proposer_index: u64,
+    parent_root: Bytes32,
+    state_root: Bytes32,
+    body: BeaconBlockBody,
+}
+
+#[derive(Default, Debug, SimpleSerialize)]
+struct BeaconBlockBody {
+    randao_reveal: BlsSignature,
+    eth1_data: Eth1Data,
+    graffiti: Bytes32,
+    proposer_slashings: List<ProposerSlashing, 16>,
+    attester_slashings: List<AttesterSlashing, 1>,
+    attestations: List<Attestation, 8>,
+    deposits: List<Deposit, 16>,
+    voluntary_exits: List<SignedVoluntaryExit, 16>,
+    sync_aggregate: SyncAggregate,
+    execution_payload: ExecutionPayload,
+    bls_to_execution_changes: List<SignedBlsToExecutionChange, 16>,
+    blob_kzg_commitments: List<Vector<u8, 48>, 4096>,
+    execution_requests: ExecutionRequests,
+}
+
+#[derive(Default, Debug, SimpleSerialize)]
+struct Eth1Data {
+    deposit_root: Bytes32,
+    deposit_count: u64,
+    block_hash: Bytes32,
+}
+
+#[derive(Default, Debug, SimpleSerialize)]
+struct ProposerSlashing {
+    signed_header_1: SignedBeaconBlockHeader,
+    signed_header_2: SignedBeaconBlockHeader,
+}
+
+#[derive(Default, Debug, SimpleSerialize)]
+struct SignedBeaconBlockHeader {
+    message: SszBeaconBlockHeader,
+    signature: BlsSignature,
+}
+
+#[derive(Default, Debug, SimpleSerialize)]
+struct SszBeaconBlockHeader {
+    slot: u64,
+    proposer_index: u64,
+    parent_root: Bytes32,
+    state_root: Bytes32,
+    body_root: Bytes32,
+}
+
+#[derive(Default, Debug, SimpleSerialize)]
+struct Checkpoint {
+    epoch: u64,
+    root: Bytes32,
+}
+
+#[derive(Default, Debug, SimpleSerialize)]
+struct AttestationData {
+    slot: u64,
+    index: u64,
+    beacon_block_root: Bytes32,
+    source: Checkpoint,
+    target: Checkpoint,
+}
+
+#[derive(Default, Debug, SimpleSerialize)]
+struct IndexedAttestation {
+    attesting_indices: List<u64, MAX_VALIDATORS_PER_SLOT>,
+    data: AttestationData,
+    signature: BlsSignature,
+}
+
+#[derive(Default, Debug, SimpleSerialize)]
+struct AttesterSlashing {
+    attestation_1: IndexedAttestation,
+    attestation_2: IndexedAttestation,
+}
+
+#[derive(Default, Debug, SimpleSerialize)]
+struct Attestation {
+    aggregation_bits: Bitlist<MAX_VALIDATORS_PER_SLOT>,
+    data: AttestationData,
+    signature: BlsSignature,
+    committee_bits: Bitvector<64>,
+}
+
+#[derive(Default, Debug, SimpleSerialize)]
+struct DepositData {
+    pubkey: BlsPubkey,
+    withdrawal_credentials: Bytes32,
+    amount: u64,
+    signature: BlsSignature,
+}
+
+#[derive(Default, Debug, SimpleSerialize)]
+struct Deposit {
+    proof: Vector<Bytes32, 33>,
+    data: DepositData,
+}
+
+#[derive(Default, Debug, SimpleSerialize)]
+struct VoluntaryExit {
+    epoch: u64,
+    validator_index: u64,
+}
+
+#[derive(Default, Debug, SimpleSerialize)]
+struct SignedVoluntaryExit {
+    message: VoluntaryExit,
+    signature: BlsSignature,
+}
+
+#[derive(Default, Debug, SimpleSerialize)]
+struct SyncAggregate {
+    sync_committee_bits: Bitvector<512>,
+    sync_committee_signature: BlsSignature,
+}
+
+#[derive(Default, Debug, SimpleSerialize)]
+struct Withdrawal {
+    index: u64,
+    validator_index: u64,
+    address: [u8; 20],
+    amount: u64,
+}
+
+#[derive(Default, Debug, SimpleSerialize)]
+struct ExecutionPayload {
+    parent_hash: Bytes32,
+    fee_recipient: [u8; 20],
+    state_root: Bytes32,
+    receipts_root: Bytes32,
+    logs_bloom: Vector<u8, 256>,
+    prev_randao: Bytes32,
+    block_number: u64,
+    gas_limit: u64,
+    gas_used: u64,
+    timestamp: u64,
+    extra_data: List<u8, 32>,
+    /// uint256, little-endian; it merkleizes as the same single chunk.
+    base_fee_per_gas: Bytes32,
+    block_hash: Bytes32,
+    transactions: List<Transaction, MAX_TRANSACTIONS_PER_PAYLOAD>,
+    withdrawals: List<Withdrawal, 16>,
+    blob_gas_used: u64,
+    excess_blob_gas: u64,
+}
+
+/// The payload with its transactions and withdrawals replaced by their roots.
+#[derive(Default, Debug, SimpleSerialize)]
+struct SszExecutionPayloadHeader {
+    parent_hash: Bytes32,
+    fee_recipient: [u8; 20],
+    state_root: Bytes32,
+    receipts_root: Bytes32,
+    logs_bloom: Vector<u8, 256>,
+    prev_randao: Bytes32,
+    block_number: u64,
+    gas_limit: u64,
+    gas_used: u64,
+    timestamp: u64,
+    extra_data: List<u8, 32>,
+    base_fee_per_gas: Bytes32,
+    block_hash: Bytes32,
+    transactions_root: Bytes32,
+    withdrawals_root: Bytes32,
+    blob_gas_used: u64,
+    excess_blob_gas: u64,
+}
+
+#[derive(Default, Debug, SimpleSerialize)]
+struct BlsToExecutionChange {
+    validator_index: u64,
+    from_bls_pubkey: BlsPubkey,
+    to_execution_address: [u8; 20],
+}
+
+#[derive(Default, Debug, SimpleSerialize)]
+struct SignedBlsToExecutionChange {
+    message: BlsToExecutionChange,
+    signature: BlsSignature,
+}
+
+#[derive(Default, Debug, SimpleSerialize)]
+struct DepositRequest {
+    pubkey: BlsPubkey,
+    withdrawal_credentials: Bytes32,
+    amount: u64,
+    signature: BlsSignature,
+    index: u64,
+}
+
+#[derive(Default, Debug, SimpleSerialize)]
+struct WithdrawalRequest {
+    source_address: [u8; 20],
+    validator_pubkey: BlsPubkey,
+    amount: u64,
+}
+
+#[derive(Default, Debug, SimpleSerialize)]
+struct ConsolidationRequest {
+    source_address: [u8; 20],
+    source_pubkey: BlsPubkey,
+    target_pubkey: BlsPubkey,
+}
+
+#[derive(Default, Debug, SimpleSerialize)]
+struct ExecutionRequests {
+    deposits: List<DepositRequest, 8192>,
+    withdrawals: List<WithdrawalRequest, 16>,
+    consolidations: List<ConsolidationRequest, 2>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block(slot: u64) -> SignedBeaconBlock {
+        let mut block = SignedBeaconBlock::default();
+        block.message.slot = slot;
+        block.message.proposer_index = 7;
+        block.message.parent_root = [1; 32];
+        block.message.state_root = [2; 32];
+        let payload = &mut block.message.body.execution_payload;
+        payload.block_number = 21_000_000;
+        payload.state_root = [0x12; 32];
+        payload.block_hash = [0x14; 32];
+        payload.base_fee_per_gas[0] = 0x64; // 100 wei, little-endian
+        payload.transactions.push(Transaction::try_from(vec![0x02, 0xf8, 0x70]).unwrap());
+        payload.withdrawals.push(Withdrawal { index: 1, validator_index: 2, address: [3; 20], amount: 4 });
+        block.message.body.blob_kzg_commitments.push(Vector::try_from(vec![0xc0; 48]).unwrap());
+        block
+    }
+
+    #[test]
+    fn test_decoded_block_proves_its_execution_header() {
+        let mut signed = block(100);
+        let decoded = decode_signed_beacon_block(&serialize(&signed).unwrap()).unwrap();
+
+        // The body root and execution root agree with ssz_rs merkleizing the whole containers.
+        let body = &mut signed.message.body;
+        assert_eq!(decoded.header.body_root, root(body).unwrap());
+        assert_eq!(decoded.execution_root, root(&mut body.execution_payload).unwrap());
+        assert!(verify_execution_branch(&decoded.execution_root, &decoded.execution_branch, &decoded.header.body_root));
+        assert_eq!(execution_body_root(&decoded.execution_root, &decoded.execution_branch), decoded.header.body_root);
+        assert_eq!(decoded.header.slot, 100);
+        assert_eq!(decoded.execution.block_number, 21_000_000);
+        assert_eq!(decoded.execution.state_root, [0x12; 32]);
+        assert_eq!(decoded.execution.base_fee_per_gas, U256::from_u64(100));
+        assert_eq!(decoded.execution.transactions_root, root(&mut body.execution_payload.transactions).unwrap());
+
+        // The payload's header, as a light client header carries it, has the payload's root.
+        let payload = &body.execution_payload;
+        let header = SszExecutionPayloadHeader {
+            state_root: payload.state_root,
+            block_number: payload.block_number,
+            base_fee_per_gas: payload.base_fee_per_gas,
+            block_hash: payload.block_hash,
+            transactions_root: decoded.execution.transactions_root,
+            withdrawals_root: decoded.execution.withdrawals_root,
+            ..Default::default()
+        };
+        assert_eq!(execution_header_root(&serialize(&header).unwrap()).unwrap(), decoded.execution_root);
+        assert!(execution_header_root(&[0u8; 16]).is_err());
+    }
+
+    #[test]
+    fn test_execution_only_for_the_verified_header() {
+        let decoded = decode_signed_beacon_block(&serialize(&block(100)).unwrap()).unwrap();
+        let verified = decoded.header.clone();
+        assert_eq!(decoded.execution_for(&verified).unwrap().block_number, 21_000_000);
+
+        let other = BeaconBlockHeader { state_root: [9; 32], ..verified.clone() };
+        assert!(matches!(decoded.execution_for(&other), Err(VerificationError::BlockRootMismatch { .. })));
+
+        let mut forged = decoded.clone();
+        forged.execution_root = [0xee; 32];
+        assert!(matches!(forged.execution_for(&verified), Err(VerificationError::InvalidExecutionBranch)));
+    }
+
+    #[test]
+    fn test_malformed_block() {
+        assert!(matches!(decode_signed_beacon_block(&[0u8; 64]), Err(SszError::Malformed { .. })));
+        let mut bytes = serialize(&block(1)).unwrap();
+        bytes.truncate(bytes.len() - 1);
+        assert!(decode_signed_beacon_block(&bytes).is_err());
+    }
+}
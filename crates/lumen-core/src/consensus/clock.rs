@@ -0,0 +1,135 @@
+use crate::consensus::sync_committee::VerificationError;
+use crate::types::beacon::{LightClientUpdate, SLOTS_PER_EPOCH};
+
+pub const SECONDS_PER_SLOT: u64 = 12;
+
+/// How far a peer's clock may run ahead of ours before its messages count
+/// as from the future (the consensus spec's `MAXIMUM_GOSSIP_CLOCK_DISPARITY`).
+pub const MAX_CLOCK_DISPARITY_MS: u64 = 500;
+
+/// How many slots the finalized head may trail the wall clock before it is
+/// stale. Finality normally lags by two epochs; four means it has stalled
+/// or the client stopped receiving updates.
+pub const STALE_HEAD_SLOTS: u64 = 4 * SLOTS_PER_EPOCH;
+
+/// Wall-clock slot math for one network.
+///
+/// Every method takes the time as an argument instead of reading a clock,
+/// so the checks are deterministic and can be replayed from recorded
+/// fixtures at the time they were captured.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SlotClock {
+    /// Unix time (seconds) of genesis.
+    pub genesis_time: u64,
+}
+
+impl SlotClock {
+    pub fn new(genesis_time: u64) -> Self {
+        Self { genesis_time }
+    }
+
+    /// The slot in progress at `unix_ms`; 0 before genesis.
+    pub fn slot_at(&self, unix_ms: u64) -> u64 {
+        (unix_ms / 1000).saturating_sub(self.genesis_time) / SECONDS_PER_SLOT
+    }
+
+    /// Unix time (milliseconds) at which `slot` starts.
+    pub fn slot_start_ms(&self, slot: u64) -> u64 {
+        (self.genesis_time + slot * SECONDS_PER_SLOT) * 1000
+    }
+
+    /// The latest slot a correct clock could be in at `unix_ms`, allowing
+    /// for [`MAX_CLOCK_DISPARITY_MS`].
+    pub fn latest_possible_slot(&self, unix_ms: u64) -> u64 {
+        self.slot_at(unix_ms + MAX_CLOCK_DISPARITY_MS)
+    }
+
+    /// Reject an update signed after the current slot, as the light client
+    /// spec does (`current_slot >= update.signature_slot`). No honest
+    /// committee signs the future, so such an update is at best premature.
+    pub fn check_not_future(&self, update: &LightClientUpdate, unix_ms: u64) -> Result<(), VerificationError> {
+        let current_slot = self.latest_possible_slot(unix_ms);
+        if update.signature_slot > current_slot {
+            return Err(VerificationError::FutureSlot {
+                signature_slot: update.signature_slot,
+                current_slot,
+            });
+        }
+        Ok(())
+    }
+
+    /// How many slots `head_slot` trails the wall clock at `unix_ms`.
+    pub fn head_lag(&self, head_slot: u64, unix_ms: u64) -> u64 {
+        self.slot_at(unix_ms).saturating_sub(head_slot)
+    }
+
+    /// Whether `head_slot` trails the wall clock by more than [`STALE_HEAD_SLOTS`].
+    pub fn is_stale(&self, head_slot: u64, unix_ms: u64) -> bool {
+        self.head_lag(head_slot, unix_ms) > STALE_HEAD_SLOTS
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::beacon::*;
+
+    const GENESIS: u64 = 1_606_824_023;
+
+    fn update(signature_slot: u64) -> LightClientUpdate {
+        let header = BeaconBlockHeader {
+            slot: signature_slot.saturating_sub(1),
+            proposer_index: 0,
+            parent_root: [0; 32],
+            state_root: [0; 32],
+            body_root: [0; 32],
+        };
+        LightClientUpdate {
+            attested_header: header.clone(),
+            next_sync_committee: None,
+            next_sync_committee_branch: Vec::new(),
+            finalized_header: header,
+            finality_branch: Vec::new(),
+            sync_aggregate: SyncAggregate {
+                sync_committee_bits: vec![0xFF; 64],
+                sync_committee_signature: BlsSignature([0; 96]),
+            },
+            signature_slot,
+        }
+    }
+
+    #[test]
+    fn test_slot_math() {
+        let clock = SlotClock::new(GENESIS);
+        assert_eq!(clock.slot_at(0), 0);
+        assert_eq!(clock.slot_at(GENESIS * 1000), 0);
+        assert_eq!(clock.slot_at((GENESIS + 11) * 1000 + 999), 0);
+        assert_eq!(clock.slot_at((GENESIS + 12) * 1000), 1);
+        assert_eq!(clock.slot_start_ms(10_000_000), (GENESIS + 120_000_000) * 1000);
+        assert_eq!(clock.slot_at(clock.slot_start_ms(10_000_000)), 10_000_000);
+    }
+
+    #[test]
+    fn test_future_updates_are_rejected() {
+        let clock = SlotClock::new(GENESIS);
+        let now = clock.slot_start_ms(100) + 5_000;
+        assert!(clock.check_not_future(&update(100), now).is_ok());
+        assert!(matches!(
+            clock.check_not_future(&update(101), now),
+            Err(VerificationError::FutureSlot { signature_slot: 101, current_slot: 100 })
+        ));
+        // Within the allowed disparity of the next slot starting.
+        let almost = clock.slot_start_ms(101) - MAX_CLOCK_DISPARITY_MS;
+        assert!(clock.check_not_future(&update(101), almost).is_ok());
+    }
+
+    #[test]
+    fn test_staleness() {
+        let clock = SlotClock::new(GENESIS);
+        let now = clock.slot_start_ms(1_000);
+        assert_eq!(clock.head_lag(936, now), 64);
+        assert!(!clock.is_stale(1_000 - STALE_HEAD_SLOTS, now));
+        assert!(clock.is_stale(1_000 - STALE_HEAD_SLOTS - 1, now));
+        assert_eq!(clock.head_lag(2_000, now), 0);
+    }
+}
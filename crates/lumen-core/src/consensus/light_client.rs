@@ -1,6 +1,7 @@
+use crate::consensus::aggregate_cache::AggregatePubkeyCache;
 use crate::consensus::sync_committee::{
     hash_beacon_block_header, verify_merkle_branch,
-    verify_sync_committee_signature, VerificationError,
+    verify_sync_committee_signature, verify_sync_committee_signature_cached, VerificationError,
 };
 use crate::types::beacon::*;
 
@@ -33,10 +34,32 @@ const NEXT_SYNC_COMMITTEE_DEPTH: usize = 6;
 ///
 /// Returns the updated state if valid, error if any verification step fails.
 pub fn process_light_client_update(
+    state: &mut LightClientState,
+    update: &LightClientUpdate,
+    current_slot: u64,
+    genesis_validators_root: [u8; 32],
+) -> Result<(), VerificationError> {
+    update_inner(state, update, current_slot, genesis_validators_root, None)
+}
+
+/// Like [`process_light_client_update`], reusing aggregate public keys from
+/// `cache`: in steady state most finality updates share their signers.
+pub fn process_light_client_update_cached(
+    state: &mut LightClientState,
+    update: &LightClientUpdate,
+    current_slot: u64,
+    genesis_validators_root: [u8; 32],
+    cache: &mut AggregatePubkeyCache,
+) -> Result<(), VerificationError> {
+    update_inner(state, update, current_slot, genesis_validators_root, Some(cache))
+}
+
+fn update_inner(
     state: &mut LightClientState,
     update: &LightClientUpdate,
     _current_slot: u64,
     genesis_validators_root: [u8; 32],
+    cache: Option<&mut AggregatePubkeyCache>,
 ) -> Result<(), VerificationError> {
     // 1. The update must advance us forward — no replaying old updates
     if update.finalized_header.slot <= state.finalized_header.slot {
@@ -69,12 +92,21 @@ pub fn process_light_client_update(
     };
 
     // 3. Verify the sync committee BLS signature — THE CORE TRUST OPERATION
-    verify_sync_committee_signature(
-        update,
-        sync_committee,
-        genesis_validators_root,
-        state.fork_version,
-    )?;
+    match cache {
+        Some(cache) => verify_sync_committee_signature_cached(
+            update,
+            sync_committee,
+            genesis_validators_root,
+            state.fork_version,
+            cache,
+        )?,
+        None => verify_sync_committee_signature(
+            update,
+            sync_committee,
+            genesis_validators_root,
+            state.fork_version,
+        )?,
+    }
 
     // 4. Verify finality branch — proves the finalized header is committed to in the attested state
     if !update.finality_branch.is_empty() {
@@ -128,6 +160,71 @@ pub fn process_light_client_update(
     Ok(())
 }
 
+/// What a committee (period) update did to the state.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CommitteeUpdateOutcome {
+    /// The finalized header advanced (and committees rotated if it crossed a period).
+    Advanced,
+    /// Finality did not advance, but the next sync committee was learned.
+    NextCommitteeStored,
+    /// Nothing new: the update neither advances finality nor carries an
+    /// unknown committee.
+    AlreadyKnown,
+}
+
+/// Apply an update from `GET /eth/v1/beacon/light_client/updates`, the
+/// per-period updates used to backfill sync committees.
+///
+/// Unlike [`process_light_client_update`], an update that does not advance
+/// finality is still useful here: a client bootstrapped mid-period only holds
+/// the current committee, and the period's best update may finalize a slot
+/// before the bootstrap. Such an update is accepted when it is signed by the
+/// current committee and carries the next committee with a valid branch —
+/// that is what lets the client cross into the next period.
+pub fn process_committee_update(
+    state: &mut LightClientState,
+    update: &LightClientUpdate,
+    genesis_validators_root: [u8; 32],
+) -> Result<CommitteeUpdateOutcome, VerificationError> {
+    let next_committee = update.next_sync_committee.as_ref().ok_or_else(|| {
+        VerificationError::BlsError("Committee update has no next sync committee".into())
+    })?;
+    if update.next_sync_committee_branch.is_empty() {
+        return Err(VerificationError::InvalidNextSyncCommitteeBranch);
+    }
+
+    if update.finalized_header.slot > state.finalized_header.slot {
+        let current_slot = state.finalized_header.slot;
+        process_light_client_update(state, update, current_slot, genesis_validators_root)?;
+        return Ok(CommitteeUpdateOutcome::Advanced);
+    }
+
+    let update_period = update.attested_header.slot / SLOTS_PER_SYNC_COMMITTEE_PERIOD;
+    if update_period != state.current_period() || state.next_sync_committee.is_some() {
+        return Ok(CommitteeUpdateOutcome::AlreadyKnown);
+    }
+
+    verify_sync_committee_signature(
+        update,
+        &state.current_sync_committee,
+        genesis_validators_root,
+        state.fork_version,
+    )?;
+    let is_valid = verify_merkle_branch(
+        &hash_sync_committee(next_committee),
+        &update.next_sync_committee_branch,
+        NEXT_SYNC_COMMITTEE_DEPTH,
+        NEXT_SYNC_COMMITTEE_GINDEX,
+        &update.attested_header.state_root,
+    );
+    if !is_valid {
+        return Err(VerificationError::InvalidNextSyncCommitteeBranch);
+    }
+
+    state.next_sync_committee = Some(next_committee.clone());
+    Ok(CommitteeUpdateOutcome::NextCommitteeStored)
+}
+
 /// Compute a simplified hash of a sync committee for Merkle branch verification.
 /// In production, this would be the SSZ hash_tree_root of the SyncCommittee.
 fn hash_sync_committee(committee: &SyncCommittee) -> [u8; 32] {
@@ -137,9 +234,9 @@ fn hash_sync_committee(committee: &SyncCommittee) -> [u8; 32] {
 
     // Hash all pubkeys
     for pk in &committee.pubkeys {
-        hasher.update(&pk.0);
+        hasher.update(pk.0);
     }
-    hasher.update(&committee.aggregate_pubkey.0);
+    hasher.update(committee.aggregate_pubkey.0);
 
     let result = hasher.finalize();
     let mut output = [0u8; 32];
@@ -233,6 +330,64 @@ mod tests {
         assert_eq!(state.last_updated_slot, 1000);
     }
 
+    fn make_committee_update(attested_slot: u64, finalized_slot: u64) -> LightClientUpdate {
+        LightClientUpdate {
+            attested_header: make_test_header(attested_slot),
+            finalized_header: make_test_header(finalized_slot),
+            finality_branch: vec![],
+            sync_aggregate: SyncAggregate {
+                sync_committee_bits: vec![0xFF; 64],
+                sync_committee_signature: BlsSignature([0u8; 96]),
+            },
+            signature_slot: attested_slot + 1,
+            next_sync_committee: Some(make_test_committee()),
+            next_sync_committee_branch: vec![[0; 32]; NEXT_SYNC_COMMITTEE_DEPTH],
+        }
+    }
+
+    #[test]
+    fn test_committee_update_requires_next_committee() {
+        let bootstrap = LightClientBootstrap {
+            header: make_test_header(1000),
+            current_sync_committee: make_test_committee(),
+            current_sync_committee_branch: vec![],
+        };
+        let mut state = initialize_from_bootstrap(&bootstrap, [0xaa; 32], [0x05, 0, 0, 0]).unwrap();
+
+        let mut update = make_committee_update(900, 800);
+        update.next_sync_committee = None;
+        assert!(process_committee_update(&mut state, &update, [0xaa; 32]).is_err());
+
+        let mut update = make_committee_update(900, 800);
+        update.next_sync_committee_branch.clear();
+        assert!(matches!(
+            process_committee_update(&mut state, &update, [0xaa; 32]),
+            Err(VerificationError::InvalidNextSyncCommitteeBranch)
+        ));
+    }
+
+    #[test]
+    fn test_committee_update_skips_known_periods() {
+        let bootstrap = LightClientBootstrap {
+            header: make_test_header(SLOTS_PER_SYNC_COMMITTEE_PERIOD * 3 + 10),
+            current_sync_committee: make_test_committee(),
+            current_sync_committee_branch: vec![],
+        };
+        let mut state = initialize_from_bootstrap(&bootstrap, [0xaa; 32], [0x05, 0, 0, 0]).unwrap();
+
+        // An older period teaches us nothing, and is not signature-checked.
+        let old = make_committee_update(SLOTS_PER_SYNC_COMMITTEE_PERIOD * 2 + 5, SLOTS_PER_SYNC_COMMITTEE_PERIOD * 2);
+        assert_eq!(
+            process_committee_update(&mut state, &old, [0xaa; 32]).unwrap(),
+            CommitteeUpdateOutcome::AlreadyKnown
+        );
+
+        // A current-period update must be signed by the current committee.
+        let current = make_committee_update(SLOTS_PER_SYNC_COMMITTEE_PERIOD * 3 + 8, SLOTS_PER_SYNC_COMMITTEE_PERIOD * 3);
+        assert!(process_committee_update(&mut state, &current, [0xaa; 32]).is_err());
+        assert!(state.next_sync_committee.is_none());
+    }
+
     #[test]
     fn test_initialize_rejects_invalid_committee_size() {
         let bootstrap = LightClientBootstrap {
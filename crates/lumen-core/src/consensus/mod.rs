@@ -1,7 +1,17 @@
+pub mod aggregate_cache;
+pub mod block;
 pub mod sync_committee;
 pub mod light_client;
 pub mod checkpoint;
+pub mod clock;
+pub mod snapshot;
+pub mod ssz;
 
+pub use aggregate_cache::*;
+pub use block::*;
 pub use sync_committee::*;
 pub use light_client::*;
 pub use checkpoint::*;
+pub use clock::*;
+pub use snapshot::*;
+pub use ssz::*;
@@ -0,0 +1,311 @@
+use crate::execution::proof::keccak256;
+use crate::types::beacon::{ExecutionPayloadHeader, LightClientState, SYNC_COMMITTEE_SIZE};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// Leading bytes of every encoded state snapshot.
+pub const SNAPSHOT_MAGIC: [u8; 4] = *b"LUMN";
+
+/// Current snapshot format version. Bump when `LightClientState` changes
+/// shape, and keep decoding the older versions that are still in the wild.
+pub const SNAPSHOT_VERSION: u16 = 1;
+
+/// Leading bytes of a snapshot shared between clients with [`encode_shared`].
+pub const SHARED_SNAPSHOT_MAGIC: [u8; 4] = *b"LUMS";
+
+/// Current shared snapshot format version.
+pub const SHARED_SNAPSHOT_VERSION: u16 = 1;
+
+/// Shortest key [`encode_shared`] accepts.
+pub const MIN_SHARING_KEY_LEN: usize = 16;
+
+/// magic (4) + version (2) + keccak256 checksum or tag of the payload (32).
+const HEADER_LEN: usize = 4 + 2 + 32;
+
+/// Errors decoding a persisted light client state.
+#[derive(Debug, Error)]
+pub enum SnapshotError {
+    #[error("Not a Lumen state snapshot (bad magic bytes)")]
+    BadMagic,
+
+    #[error("Unsupported snapshot version {found} (this build reads up to {supported})")]
+    UnsupportedVersion { found: u16, supported: u16 },
+
+    #[error("Snapshot checksum mismatch: the stored state is corrupted")]
+    ChecksumMismatch,
+
+    #[error("Snapshot tag mismatch: not exported with this key, or altered since")]
+    BadTag,
+
+    #[error("Sharing key must be at least {MIN_SHARING_KEY_LEN} bytes")]
+    WeakKey,
+
+    #[error("Malformed snapshot: {reason}")]
+    Malformed { reason: String },
+}
+
+/// A verified state plus the recent execution headers that go with it,
+/// as passed between clients (e.g. browser tabs) by [`encode_shared`].
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SharedSnapshot {
+    pub state: LightClientState,
+    /// Ascending by block number, ending with the state's latest header.
+    pub execution_headers: Vec<ExecutionPayloadHeader>,
+}
+
+/// Serialize a verified light client state for persistence.
+///
+/// Layout: `"LUMN" || version (u16 BE) || keccak256(payload) || payload`,
+/// where the payload is the state's JSON encoding. The checksum catches
+/// storage corruption; it is not a defence against someone who can rewrite
+/// the stored bytes, who could equally rewrite the checksum.
+pub fn encode_state(state: &LightClientState) -> Vec<u8> {
+    let payload = serde_json::to_vec(state).expect("LightClientState serializes to JSON");
+    frame(SNAPSHOT_MAGIC, SNAPSHOT_VERSION, keccak256(&payload), &payload)
+}
+
+fn frame(magic: [u8; 4], version: u16, digest: [u8; 32], payload: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(HEADER_LEN + payload.len());
+    out.extend_from_slice(&magic);
+    out.extend_from_slice(&version.to_be_bytes());
+    out.extend_from_slice(&digest);
+    out.extend_from_slice(payload);
+    out
+}
+
+/// Check the magic and version of a framed snapshot, returning its stored
+/// digest and payload.
+fn unframe(bytes: &[u8], magic: [u8; 4], supported: u16) -> Result<(&[u8], &[u8]), SnapshotError> {
+    if bytes.len() < HEADER_LEN || bytes[..4] != magic {
+        return Err(SnapshotError::BadMagic);
+    }
+    let version = u16::from_be_bytes([bytes[4], bytes[5]]);
+    if version == 0 || version > supported {
+        return Err(SnapshotError::UnsupportedVersion { found: version, supported });
+    }
+    Ok((&bytes[6..HEADER_LEN], &bytes[HEADER_LEN..]))
+}
+
+/// Keyed tag over a shared snapshot's payload. Keccak is a sponge, so a
+/// secret prefix is a sound MAC: there is no length extension to exploit.
+fn tag(key: &[u8], payload: &[u8]) -> [u8; 32] {
+    let mut input = Vec::with_capacity(key.len() + payload.len());
+    input.extend_from_slice(key);
+    input.extend_from_slice(payload);
+    keccak256(&input)
+}
+
+/// A full-size current (and, if present, next) sync committee: the only
+/// kind this crate ever produces.
+fn check_committees(state: &LightClientState) -> Result<(), SnapshotError> {
+    let committees = std::iter::once(&state.current_sync_committee).chain(state.next_sync_committee.as_ref());
+    for committee in committees {
+        if committee.pubkeys.len() != SYNC_COMMITTEE_SIZE {
+            return Err(SnapshotError::Malformed {
+                reason: format!(
+                    "sync committee has {} members, expected {}",
+                    committee.pubkeys.len(),
+                    SYNC_COMMITTEE_SIZE
+                ),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Serialize a verified state and its recent execution headers for another
+/// client holding the same `key` to pick up, e.g. a second browser tab
+/// receiving it over a BroadcastChannel.
+///
+/// Layout: `"LUMS" || version (u16 BE) || keccak256(key || payload) || payload`,
+/// where the payload is the JSON encoding of a [`SharedSnapshot`]. The tag
+/// shows the snapshot came from a client holding `key` and was not altered
+/// in transit; it does not make the state any more verified than the
+/// exporting client's was.
+pub fn encode_shared(snapshot: &SharedSnapshot, key: &[u8]) -> Result<Vec<u8>, SnapshotError> {
+    if key.len() < MIN_SHARING_KEY_LEN {
+        return Err(SnapshotError::WeakKey);
+    }
+    let payload = serde_json::to_vec(snapshot).expect("SharedSnapshot serializes to JSON");
+    Ok(frame(SHARED_SNAPSHOT_MAGIC, SHARED_SNAPSHOT_VERSION, tag(key, &payload), &payload))
+}
+
+/// Restore a snapshot written by [`encode_shared`] under the same `key`.
+///
+/// Besides the tag, the contents are checked structurally: full-size sync
+/// committees, and execution headers in ascending order ending with the
+/// state's latest one. No signature is re-verified.
+pub fn decode_shared(bytes: &[u8], key: &[u8]) -> Result<SharedSnapshot, SnapshotError> {
+    if key.len() < MIN_SHARING_KEY_LEN {
+        return Err(SnapshotError::WeakKey);
+    }
+    let (stored_tag, payload) = unframe(bytes, SHARED_SNAPSHOT_MAGIC, SHARED_SNAPSHOT_VERSION)?;
+    if tag(key, payload) != stored_tag {
+        return Err(SnapshotError::BadTag);
+    }
+    let snapshot: SharedSnapshot = serde_json::from_slice(payload).map_err(|e| SnapshotError::Malformed {
+        reason: e.to_string(),
+    })?;
+    check_committees(&snapshot.state)?;
+
+    let headers = &snapshot.execution_headers;
+    if headers.windows(2).any(|pair| pair[0].block_number >= pair[1].block_number) {
+        return Err(SnapshotError::Malformed {
+            reason: "execution headers are not in ascending block order".to_string(),
+        });
+    }
+    if headers.last() != snapshot.state.latest_execution_payload_header.as_ref() {
+        return Err(SnapshotError::Malformed {
+            reason: "newest execution header is not the state's latest".to_string(),
+        });
+    }
+    Ok(snapshot)
+}
+
+/// Restore a light client state written by [`encode_state`].
+///
+/// Beyond the format checks, the restored state must be one this crate
+/// could have produced: a full-size current (and, if present, next) sync
+/// committee. Anything else is rejected rather than resumed from.
+pub fn decode_state(bytes: &[u8]) -> Result<LightClientState, SnapshotError> {
+    let (checksum, payload) = unframe(bytes, SNAPSHOT_MAGIC, SNAPSHOT_VERSION)?;
+    if keccak256(payload) != checksum {
+        return Err(SnapshotError::ChecksumMismatch);
+    }
+
+    let state: LightClientState =
+        serde_json::from_slice(payload).map_err(|e| SnapshotError::Malformed {
+            reason: e.to_string(),
+        })?;
+    check_committees(&state)?;
+    Ok(state)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::beacon::*;
+
+    fn make_test_state() -> LightClientState {
+        LightClientState {
+            finalized_header: BeaconBlockHeader {
+                slot: 9_000_000,
+                proposer_index: 7,
+                parent_root: [0x01; 32],
+                state_root: [0x02; 32],
+                body_root: [0x03; 32],
+            },
+            current_sync_committee: SyncCommittee {
+                pubkeys: vec![BlsPublicKey([0x11; 48]); SYNC_COMMITTEE_SIZE],
+                aggregate_pubkey: BlsPublicKey([0x22; 48]),
+            },
+            next_sync_committee: None,
+            latest_execution_payload_header: None,
+            genesis_validators_root: [0xAA; 32],
+            fork_version: [0x05, 0x00, 0x00, 0x00],
+            last_updated_slot: 9_000_000,
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let state = make_test_state();
+        let bytes = encode_state(&state);
+        assert_eq!(&bytes[..4], b"LUMN");
+
+        let restored = decode_state(&bytes).unwrap();
+        assert_eq!(restored.finalized_header, state.finalized_header);
+        assert_eq!(restored.current_sync_committee.pubkeys, state.current_sync_committee.pubkeys);
+        assert_eq!(restored.genesis_validators_root, state.genesis_validators_root);
+        assert_eq!(restored.fork_version, state.fork_version);
+    }
+
+    #[test]
+    fn test_rejects_bad_header() {
+        let bytes = encode_state(&make_test_state());
+
+        assert!(matches!(decode_state(b"nope"), Err(SnapshotError::BadMagic)));
+
+        let mut future = bytes.clone();
+        future[4..6].copy_from_slice(&(SNAPSHOT_VERSION + 1).to_be_bytes());
+        assert!(matches!(
+            decode_state(&future),
+            Err(SnapshotError::UnsupportedVersion { .. })
+        ));
+
+        let mut corrupted = bytes;
+        let last = corrupted.len() - 2;
+        corrupted[last] ^= 0x01;
+        assert!(matches!(decode_state(&corrupted), Err(SnapshotError::ChecksumMismatch)));
+    }
+
+    #[test]
+    fn test_rejects_truncated_committee() {
+        let mut state = make_test_state();
+        state.current_sync_committee.pubkeys.truncate(10);
+        assert!(matches!(
+            decode_state(&encode_state(&state)),
+            Err(SnapshotError::Malformed { .. })
+        ));
+    }
+
+    fn execution_header(block_number: u64) -> ExecutionPayloadHeader {
+        ExecutionPayloadHeader {
+            parent_hash: [0; 32],
+            fee_recipient: [0; 20],
+            state_root: [block_number as u8; 32],
+            receipts_root: [0; 32],
+            block_number,
+            gas_limit: 30_000_000,
+            gas_used: 0,
+            timestamp: 0,
+            base_fee_per_gas: crate::types::uint::U256::from_u64(7),
+            block_hash: [0; 32],
+            transactions_root: [0; 32],
+            withdrawals_root: [0; 32],
+            blob_gas_used: 0,
+            excess_blob_gas: 0,
+        }
+    }
+
+    #[test]
+    fn test_shared_snapshot_round_trip_and_tag() {
+        let key = [0x42; 32];
+        let mut state = make_test_state();
+        state.latest_execution_payload_header = Some(execution_header(300));
+        let snapshot = SharedSnapshot {
+            state,
+            execution_headers: vec![execution_header(100), execution_header(200), execution_header(300)],
+        };
+        let bytes = encode_shared(&snapshot, &key).unwrap();
+        assert_eq!(&bytes[..4], b"LUMS");
+
+        let restored = decode_shared(&bytes, &key).unwrap();
+        assert_eq!(restored.execution_headers, snapshot.execution_headers);
+        assert_eq!(restored.state.finalized_header, snapshot.state.finalized_header);
+
+        assert!(matches!(decode_shared(&bytes, &[0x43; 32]), Err(SnapshotError::BadTag)));
+        assert!(matches!(encode_shared(&snapshot, b"short"), Err(SnapshotError::WeakKey)));
+        // A plain export is not a shared one, and the other way round.
+        assert!(matches!(decode_shared(&encode_state(&snapshot.state), &key), Err(SnapshotError::BadMagic)));
+        assert!(matches!(decode_state(&bytes), Err(SnapshotError::BadMagic)));
+    }
+
+    #[test]
+    fn test_shared_snapshot_checks_header_order() {
+        let key = [0x42; 32];
+        let mut state = make_test_state();
+        state.latest_execution_payload_header = Some(execution_header(300));
+
+        let unordered = SharedSnapshot {
+            state: state.clone(),
+            execution_headers: vec![execution_header(200), execution_header(100), execution_header(300)],
+        };
+        let bytes = encode_shared(&unordered, &key).unwrap();
+        assert!(matches!(decode_shared(&bytes, &key), Err(SnapshotError::Malformed { .. })));
+
+        let not_latest = SharedSnapshot { state, execution_headers: vec![execution_header(200)] };
+        let bytes = encode_shared(&not_latest, &key).unwrap();
+        assert!(matches!(decode_shared(&bytes, &key), Err(SnapshotError::Malformed { .. })));
+    }
+}
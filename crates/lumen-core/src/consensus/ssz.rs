@@ -0,0 +1,668 @@
+//! SSZ decoding for the light client containers a beacon node serves with
+//! `Accept: application/octet-stream`, and peers gossip.
+//!
+//! Only the Electra layouts are decoded, matching the generalized indices the
+//! verifier uses: a 7-deep finality branch, 6-deep current and next sync
+//! committee branches and `LightClientHeader { beacon, execution, execution_branch }`.
+//! Decoding is structural — every field still goes through the same
+//! verification as the JSON path — except that a header's execution payload
+//! header must verify against its beacon header's `body_root` with its
+//! execution branch, as nothing after decoding can check it.
+
+use crate::consensus::block::{execution_header_root, verify_execution_branch, EXECUTION_BRANCH_DEPTH};
+use crate::types::beacon::*;
+use crate::types::uint::U256;
+use thiserror::Error;
+
+/// Depth of the finality branch (`FINALIZED_ROOT_GINDEX` in Electra).
+const FINALITY_BRANCH_DEPTH: usize = 7;
+/// Depth of the next sync committee branch (`NEXT_SYNC_COMMITTEE_GINDEX` in Electra).
+const NEXT_SYNC_COMMITTEE_BRANCH_DEPTH: usize = 6;
+/// Depth of the current sync committee branch (`CURRENT_SYNC_COMMITTEE_GINDEX` in Electra).
+const CURRENT_SYNC_COMMITTEE_BRANCH_DEPTH: usize = 6;
+
+/// slot + proposer_index + parent_root + state_root + body_root.
+const BEACON_HEADER_LEN: usize = 8 + 8 + 32 * 3;
+/// beacon header + execution offset + execution branch.
+const LIGHT_CLIENT_HEADER_FIXED_LEN: usize = BEACON_HEADER_LEN + 4 + 32 * EXECUTION_BRANCH_DEPTH;
+/// Fixed part of `ExecutionPayloadHeader` (Deneb and later); `extra_data` follows it.
+const EXECUTION_HEADER_FIXED_LEN: usize = 584;
+/// Longest `extra_data` the execution layer allows.
+const MAX_EXTRA_DATA_BYTES: usize = 32;
+const SYNC_COMMITTEE_LEN: usize = (SYNC_COMMITTEE_SIZE + 1) * BLS_PUBKEY_LEN;
+const SYNC_AGGREGATE_LEN: usize = SYNC_COMMITTEE_SIZE / 8 + BLS_SIGNATURE_LEN;
+const FINALITY_UPDATE_FIXED_LEN: usize = 4 + 4 + 32 * FINALITY_BRANCH_DEPTH + SYNC_AGGREGATE_LEN + 8;
+const OPTIMISTIC_UPDATE_FIXED_LEN: usize = 4 + SYNC_AGGREGATE_LEN + 8;
+const BOOTSTRAP_FIXED_LEN: usize = 4 + SYNC_COMMITTEE_LEN + 32 * CURRENT_SYNC_COMMITTEE_BRANCH_DEPTH;
+/// Length prefix and fork digest in front of each object of a list response.
+const RESPONSE_CHUNK_PREFIX_LEN: usize = 8 + 4;
+const UPDATE_FIXED_LEN: usize = 4
+    + SYNC_COMMITTEE_LEN
+    + 32 * NEXT_SYNC_COMMITTEE_BRANCH_DEPTH
+    + 4
+    + 32 * FINALITY_BRANCH_DEPTH
+    + SYNC_AGGREGATE_LEN
+    + 8;
+
+/// Errors decoding SSZ-encoded light client data.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum SszError {
+    #[error("{container}: expected at least {expected} bytes, got {found}")]
+    TooShort {
+        container: &'static str,
+        expected: usize,
+        found: usize,
+    },
+
+    #[error("{container}: invalid offset {offset}")]
+    InvalidOffset { container: &'static str, offset: usize },
+
+    #[error("{container}: invalid {field}")]
+    InvalidField {
+        container: &'static str,
+        field: &'static str,
+    },
+
+    #[error("{container}: {reason}")]
+    Malformed { container: &'static str, reason: String },
+}
+
+/// An update decoded from SSZ, with the execution payload header of its
+/// finalized header — the part the JSON path reads from `finalized_header.execution`.
+#[derive(Clone, Debug)]
+pub struct DecodedUpdate {
+    pub update: LightClientUpdate,
+    pub finalized_execution: ExecutionPayloadHeader,
+}
+
+/// An optimistic update decoded from SSZ: the attested header and the sync
+/// committee signature over it, with no finality proof.
+#[derive(Clone, Debug)]
+pub struct DecodedOptimisticUpdate {
+    pub attested_header: BeaconBlockHeader,
+    pub attested_execution: ExecutionPayloadHeader,
+    pub sync_aggregate: SyncAggregate,
+    pub signature_slot: u64,
+}
+
+/// A bootstrap decoded from SSZ, with the execution payload header of its
+/// header — the part the JSON path reads from `header.execution`.
+#[derive(Clone, Debug)]
+pub struct DecodedBootstrap {
+    pub bootstrap: LightClientBootstrap,
+    pub execution: ExecutionPayloadHeader,
+}
+
+/// Decode an SSZ `LightClientBootstrap`.
+pub fn decode_bootstrap(bytes: &[u8]) -> Result<DecodedBootstrap, SszError> {
+    const CONTAINER: &str = "LightClientBootstrap";
+    let mut reader = Reader::new(CONTAINER, bytes, BOOTSTRAP_FIXED_LEN)?;
+    let header_offset = reader.offset()?;
+    let current_sync_committee = reader.sync_committee()?;
+    let current_sync_committee_branch = reader.branch(CURRENT_SYNC_COMMITTEE_BRANCH_DEPTH)?;
+
+    let [header] = variable_parts(CONTAINER, bytes, BOOTSTRAP_FIXED_LEN, [header_offset])?;
+    let (header, execution) = decode_light_client_header(header)?;
+
+    Ok(DecodedBootstrap {
+        bootstrap: LightClientBootstrap {
+            header,
+            current_sync_committee,
+            current_sync_committee_branch,
+        },
+        execution,
+    })
+}
+
+/// One object of a list response, with the fork digest it was sent under.
+pub type ResponseChunk<'a> = ([u8; 4], &'a [u8]);
+
+/// Split a list response such as `light_client/updates` into its objects.
+///
+/// Each object is prefixed with its length (`u64`, little-endian) and the
+/// fork digest of its fork; the length counts the digest and the object.
+pub fn response_chunks(bytes: &[u8]) -> Result<Vec<ResponseChunk<'_>>, SszError> {
+    const CONTAINER: &str = "response chunk";
+    let mut chunks = Vec::new();
+    let mut rest = bytes;
+    while !rest.is_empty() {
+        let mut reader = Reader::new(CONTAINER, rest, RESPONSE_CHUNK_PREFIX_LEN)?;
+        let length = reader.u64()?;
+        let fork_digest = reader.array::<4>()?;
+        let end = usize::try_from(length)
+            .ok()
+            .and_then(|length| length.checked_add(8))
+            .filter(|end| (RESPONSE_CHUNK_PREFIX_LEN..=rest.len()).contains(end))
+            .ok_or(SszError::InvalidField {
+                container: CONTAINER,
+                field: "length",
+            })?;
+        chunks.push((fork_digest, &rest[RESPONSE_CHUNK_PREFIX_LEN..end]));
+        rest = &rest[end..];
+    }
+    Ok(chunks)
+}
+
+/// Decode the SSZ body of `light_client/updates`: a list of `LightClientUpdate`s.
+pub fn decode_updates(bytes: &[u8]) -> Result<Vec<DecodedUpdate>, SszError> {
+    response_chunks(bytes)?
+        .into_iter()
+        .map(|(_, update)| decode_update(update))
+        .collect()
+}
+
+/// Decode an SSZ `LightClientFinalityUpdate`.
+pub fn decode_finality_update(bytes: &[u8]) -> Result<DecodedUpdate, SszError> {
+    const CONTAINER: &str = "LightClientFinalityUpdate";
+    let mut reader = Reader::new(CONTAINER, bytes, FINALITY_UPDATE_FIXED_LEN)?;
+    let attested_offset = reader.offset()?;
+    let finalized_offset = reader.offset()?;
+    let finality_branch = reader.branch(FINALITY_BRANCH_DEPTH)?;
+    let sync_aggregate = reader.sync_aggregate()?;
+    let signature_slot = reader.u64()?;
+
+    let [attested, finalized] = variable_parts(CONTAINER, bytes, FINALITY_UPDATE_FIXED_LEN, [attested_offset, finalized_offset])?;
+    let (attested_header, _) = decode_light_client_header(attested)?;
+    let (finalized_header, finalized_execution) = decode_light_client_header(finalized)?;
+
+    Ok(DecodedUpdate {
+        update: LightClientUpdate {
+            attested_header,
+            next_sync_committee: None,
+            next_sync_committee_branch: Vec::new(),
+            finalized_header,
+            finality_branch,
+            sync_aggregate,
+            signature_slot,
+        },
+        finalized_execution,
+    })
+}
+
+/// Decode an SSZ `LightClientOptimisticUpdate`.
+pub fn decode_optimistic_update(bytes: &[u8]) -> Result<DecodedOptimisticUpdate, SszError> {
+    const CONTAINER: &str = "LightClientOptimisticUpdate";
+    let mut reader = Reader::new(CONTAINER, bytes, OPTIMISTIC_UPDATE_FIXED_LEN)?;
+    let attested_offset = reader.offset()?;
+    let sync_aggregate = reader.sync_aggregate()?;
+    let signature_slot = reader.u64()?;
+
+    let [attested] = variable_parts(CONTAINER, bytes, OPTIMISTIC_UPDATE_FIXED_LEN, [attested_offset])?;
+    let (attested_header, attested_execution) = decode_light_client_header(attested)?;
+
+    Ok(DecodedOptimisticUpdate {
+        attested_header,
+        attested_execution,
+        sync_aggregate,
+        signature_slot,
+    })
+}
+
+/// Decode an SSZ `LightClientUpdate`, as served per period by
+/// `light_client/updates`.
+///
+/// SSZ has no optional fields: an update that does not carry the next sync
+/// committee has a zeroed branch, which is decoded as no committee at all.
+pub fn decode_update(bytes: &[u8]) -> Result<DecodedUpdate, SszError> {
+    const CONTAINER: &str = "LightClientUpdate";
+    let mut reader = Reader::new(CONTAINER, bytes, UPDATE_FIXED_LEN)?;
+    let attested_offset = reader.offset()?;
+    let next_sync_committee = reader.sync_committee()?;
+    let next_sync_committee_branch = reader.branch(NEXT_SYNC_COMMITTEE_BRANCH_DEPTH)?;
+    let finalized_offset = reader.offset()?;
+    let finality_branch = reader.branch(FINALITY_BRANCH_DEPTH)?;
+    let sync_aggregate = reader.sync_aggregate()?;
+    let signature_slot = reader.u64()?;
+
+    let [attested, finalized] = variable_parts(CONTAINER, bytes, UPDATE_FIXED_LEN, [attested_offset, finalized_offset])?;
+    let (attested_header, _) = decode_light_client_header(attested)?;
+    let (finalized_header, finalized_execution) = decode_light_client_header(finalized)?;
+
+    let (next_sync_committee, next_sync_committee_branch) =
+        if next_sync_committee_branch.iter().all(|node| *node == [0u8; 32]) {
+            (None, Vec::new())
+        } else {
+            (Some(next_sync_committee), next_sync_committee_branch)
+        };
+
+    Ok(DecodedUpdate {
+        update: LightClientUpdate {
+            attested_header,
+            next_sync_committee,
+            next_sync_committee_branch,
+            finalized_header,
+            finality_branch,
+            sync_aggregate,
+            signature_slot,
+        },
+        finalized_execution,
+    })
+}
+
+/// Decode a `LightClientHeader` into its beacon header and execution payload
+/// header, once the execution branch proves the latter in the former's body.
+fn decode_light_client_header(bytes: &[u8]) -> Result<(BeaconBlockHeader, ExecutionPayloadHeader), SszError> {
+    const CONTAINER: &str = "LightClientHeader";
+    let mut reader = Reader::new(CONTAINER, bytes, LIGHT_CLIENT_HEADER_FIXED_LEN)?;
+    let beacon = reader.beacon_header()?;
+    let execution_offset = reader.offset()?;
+    let execution_branch = reader.branch(EXECUTION_BRANCH_DEPTH)?;
+
+    let [execution] = variable_parts(CONTAINER, bytes, LIGHT_CLIENT_HEADER_FIXED_LEN, [execution_offset])?;
+    let header = decode_execution_header(execution)?;
+    if !verify_execution_branch(&execution_header_root(execution)?, &execution_branch, &beacon.body_root) {
+        return Err(SszError::InvalidField { container: CONTAINER, field: "execution_branch" });
+    }
+    Ok((beacon, header))
+}
+
+fn decode_execution_header(bytes: &[u8]) -> Result<ExecutionPayloadHeader, SszError> {
+    const CONTAINER: &str = "ExecutionPayloadHeader";
+    let mut reader = Reader::new(CONTAINER, bytes, EXECUTION_HEADER_FIXED_LEN)?;
+    let parent_hash = reader.bytes32()?;
+    let fee_recipient = reader.array::<20>()?;
+    let state_root = reader.bytes32()?;
+    let receipts_root = reader.bytes32()?;
+    reader.take(256)?; // logs_bloom
+    reader.take(32)?; // prev_randao
+    let block_number = reader.u64()?;
+    let gas_limit = reader.u64()?;
+    let gas_used = reader.u64()?;
+    let timestamp = reader.u64()?;
+    let extra_data_offset = reader.offset()?;
+    let mut base_fee_per_gas = reader.bytes32()?;
+    base_fee_per_gas.reverse(); // SSZ uint256 is little-endian
+    let block_hash = reader.bytes32()?;
+    let transactions_root = reader.bytes32()?;
+    let withdrawals_root = reader.bytes32()?;
+    let blob_gas_used = reader.u64()?;
+    let excess_blob_gas = reader.u64()?;
+
+    let [extra_data] = variable_parts(CONTAINER, bytes, EXECUTION_HEADER_FIXED_LEN, [extra_data_offset])?;
+    if extra_data.len() > MAX_EXTRA_DATA_BYTES {
+        return Err(SszError::InvalidField {
+            container: CONTAINER,
+            field: "extra_data length",
+        });
+    }
+
+    Ok(ExecutionPayloadHeader {
+        parent_hash,
+        fee_recipient,
+        state_root,
+        receipts_root,
+        block_number,
+        gas_limit,
+        gas_used,
+        timestamp,
+        base_fee_per_gas: U256::from_be_bytes(base_fee_per_gas),
+        block_hash,
+        transactions_root,
+        withdrawals_root,
+        blob_gas_used,
+        excess_blob_gas,
+    })
+}
+
+/// Split the variable-size part of a container at its offsets.
+///
+/// The first offset must point just past the fixed part, and each one must
+/// be no smaller than the one before it; the last part runs to the end.
+fn variable_parts<'a, const N: usize>(
+    container: &'static str,
+    bytes: &'a [u8],
+    fixed_len: usize,
+    offsets: [usize; N],
+) -> Result<[&'a [u8]; N], SszError> {
+    if offsets[0] != fixed_len {
+        return Err(SszError::InvalidOffset { container, offset: offsets[0] });
+    }
+    let mut parts = [&bytes[..0]; N];
+    for i in 0..N {
+        let start = offsets[i];
+        let end = offsets.get(i + 1).copied().unwrap_or(bytes.len());
+        if start > end || end > bytes.len() {
+            return Err(SszError::InvalidOffset { container, offset: end });
+        }
+        parts[i] = &bytes[start..end];
+    }
+    Ok(parts)
+}
+
+/// Sequential reader over the fixed part of a container.
+struct Reader<'a> {
+    container: &'static str,
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(container: &'static str, bytes: &'a [u8], fixed_len: usize) -> Result<Self, SszError> {
+        if bytes.len() < fixed_len {
+            return Err(SszError::TooShort {
+                container,
+                expected: fixed_len,
+                found: bytes.len(),
+            });
+        }
+        Ok(Self { container, bytes, pos: 0 })
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], SszError> {
+        let end = self.pos + len;
+        if end > self.bytes.len() {
+            return Err(SszError::TooShort {
+                container: self.container,
+                expected: end,
+                found: self.bytes.len(),
+            });
+        }
+        let slice = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(slice)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], SszError> {
+        let mut out = [0u8; N];
+        out.copy_from_slice(self.take(N)?);
+        Ok(out)
+    }
+
+    fn bytes32(&mut self) -> Result<[u8; 32], SszError> {
+        self.array::<32>()
+    }
+
+    fn u64(&mut self) -> Result<u64, SszError> {
+        self.array::<8>().map(u64::from_le_bytes)
+    }
+
+    fn offset(&mut self) -> Result<usize, SszError> {
+        self.array::<4>().map(|b| u32::from_le_bytes(b) as usize)
+    }
+
+    fn branch(&mut self, depth: usize) -> Result<Vec<[u8; 32]>, SszError> {
+        (0..depth).map(|_| self.bytes32()).collect()
+    }
+
+    fn beacon_header(&mut self) -> Result<BeaconBlockHeader, SszError> {
+        Ok(BeaconBlockHeader {
+            slot: self.u64()?,
+            proposer_index: self.u64()?,
+            parent_root: self.bytes32()?,
+            state_root: self.bytes32()?,
+            body_root: self.bytes32()?,
+        })
+    }
+
+    fn pubkey(&mut self) -> Result<BlsPublicKey, SszError> {
+        let container = self.container;
+        BlsPublicKey::from_bytes(self.take(BLS_PUBKEY_LEN)?)
+            .map_err(|_| SszError::InvalidField { container, field: "public key" })
+    }
+
+    fn sync_committee(&mut self) -> Result<SyncCommittee, SszError> {
+        let pubkeys = (0..SYNC_COMMITTEE_SIZE)
+            .map(|_| self.pubkey())
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(SyncCommittee {
+            pubkeys,
+            aggregate_pubkey: self.pubkey()?,
+        })
+    }
+
+    fn sync_aggregate(&mut self) -> Result<SyncAggregate, SszError> {
+        let sync_committee_bits = self.take(SYNC_COMMITTEE_SIZE / 8)?.to_vec();
+        let container = self.container;
+        let sync_committee_signature = BlsSignature::from_bytes(self.take(BLS_SIGNATURE_LEN)?)
+            .map_err(|_| SszError::InvalidField { container, field: "signature" })?;
+        Ok(SyncAggregate {
+            sync_committee_bits,
+            sync_committee_signature,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::consensus::block::execution_body_root;
+
+    fn beacon_header(slot: u64, body_root: &[u8; 32]) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend_from_slice(&slot.to_le_bytes());
+        out.extend_from_slice(&7u64.to_le_bytes());
+        out.extend_from_slice(&[1u8; 32]);
+        out.extend_from_slice(&[2u8; 32]);
+        out.extend_from_slice(body_root);
+        out
+    }
+
+    /// The root of a block body with `execution` at its payload's index and
+    /// `[0x20; 32]` for every node of the execution branch.
+    fn body_root(execution: &[u8]) -> [u8; 32] {
+        execution_body_root(&execution_header_root(execution).unwrap(), &[[0x20; 32]; EXECUTION_BRANCH_DEPTH])
+    }
+
+    fn execution_header(block_number: u64, extra_data: &[u8]) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend_from_slice(&[0x10; 32]); // parent_hash
+        out.extend_from_slice(&[0x11; 20]); // fee_recipient
+        out.extend_from_slice(&[0x12; 32]); // state_root
+        out.extend_from_slice(&[0x13; 32]); // receipts_root
+        out.extend_from_slice(&[0u8; 256 + 32]); // logs_bloom, prev_randao
+        out.extend_from_slice(&block_number.to_le_bytes());
+        out.extend_from_slice(&30_000_000u64.to_le_bytes());
+        out.extend_from_slice(&12_000_000u64.to_le_bytes());
+        out.extend_from_slice(&1_700_000_000u64.to_le_bytes());
+        out.extend_from_slice(&(EXECUTION_HEADER_FIXED_LEN as u32).to_le_bytes());
+        let mut base_fee = [0u8; 32];
+        base_fee[0] = 0x64; // 100 wei, little-endian
+        out.extend_from_slice(&base_fee);
+        out.extend_from_slice(&[0x14; 32]); // block_hash
+        out.extend_from_slice(&[0x15; 32]); // transactions_root
+        out.extend_from_slice(&[0x16; 32]); // withdrawals_root
+        out.extend_from_slice(&131_072u64.to_le_bytes());
+        out.extend_from_slice(&0u64.to_le_bytes());
+        assert_eq!(out.len(), EXECUTION_HEADER_FIXED_LEN);
+        out.extend_from_slice(extra_data);
+        out
+    }
+
+    fn light_client_header(slot: u64, block_number: u64) -> Vec<u8> {
+        let execution = execution_header(block_number, b"lumen");
+        let mut out = beacon_header(slot, &body_root(&execution));
+        out.extend_from_slice(&(LIGHT_CLIENT_HEADER_FIXED_LEN as u32).to_le_bytes());
+        out.extend_from_slice(&[0x20; 32 * EXECUTION_BRANCH_DEPTH]);
+        out.extend_from_slice(&execution);
+        out
+    }
+
+    fn sync_aggregate() -> Vec<u8> {
+        let mut out = vec![0xFF; SYNC_COMMITTEE_SIZE / 8];
+        out.extend_from_slice(&[0xAA; BLS_SIGNATURE_LEN]);
+        out
+    }
+
+    fn finality_update(attested_slot: u64, finalized_slot: u64) -> Vec<u8> {
+        let attested = light_client_header(attested_slot, 1);
+        let finalized = light_client_header(finalized_slot, 2);
+        let mut out = Vec::new();
+        out.extend_from_slice(&(FINALITY_UPDATE_FIXED_LEN as u32).to_le_bytes());
+        out.extend_from_slice(&((FINALITY_UPDATE_FIXED_LEN + attested.len()) as u32).to_le_bytes());
+        out.extend_from_slice(&[0x30; 32 * FINALITY_BRANCH_DEPTH]);
+        out.extend_from_slice(&sync_aggregate());
+        out.extend_from_slice(&(attested_slot + 1).to_le_bytes());
+        out.extend_from_slice(&attested);
+        out.extend_from_slice(&finalized);
+        out
+    }
+
+    fn optimistic_update(attested_slot: u64) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend_from_slice(&(OPTIMISTIC_UPDATE_FIXED_LEN as u32).to_le_bytes());
+        out.extend_from_slice(&sync_aggregate());
+        out.extend_from_slice(&(attested_slot + 1).to_le_bytes());
+        out.extend_from_slice(&light_client_header(attested_slot, 5));
+        out
+    }
+
+    fn period_update(next_branch: [u8; 32]) -> Vec<u8> {
+        let attested = light_client_header(8300, 1);
+        let finalized = light_client_header(8256, 2);
+        let mut out = Vec::new();
+        out.extend_from_slice(&(UPDATE_FIXED_LEN as u32).to_le_bytes());
+        out.extend_from_slice(&vec![0x40; SYNC_COMMITTEE_LEN]);
+        for _ in 0..NEXT_SYNC_COMMITTEE_BRANCH_DEPTH {
+            out.extend_from_slice(&next_branch);
+        }
+        out.extend_from_slice(&((UPDATE_FIXED_LEN + attested.len()) as u32).to_le_bytes());
+        out.extend_from_slice(&[0x30; 32 * FINALITY_BRANCH_DEPTH]);
+        out.extend_from_slice(&sync_aggregate());
+        out.extend_from_slice(&8301u64.to_le_bytes());
+        out.extend_from_slice(&attested);
+        out.extend_from_slice(&finalized);
+        out
+    }
+
+    #[test]
+    fn test_decode_finality_update() {
+        let decoded = decode_finality_update(&finality_update(200, 128)).unwrap();
+        let update = &decoded.update;
+        assert_eq!(update.attested_header.slot, 200);
+        assert_eq!(update.finalized_header.slot, 128);
+        assert_eq!(update.finalized_header.proposer_index, 7);
+        assert_eq!(update.finalized_header.body_root, body_root(&execution_header(2, b"lumen")));
+        assert_eq!(update.finality_branch, vec![[0x30; 32]; FINALITY_BRANCH_DEPTH]);
+        assert_eq!(update.sync_aggregate.num_participants(), SYNC_COMMITTEE_SIZE);
+        assert_eq!(update.signature_slot, 201);
+        assert!(update.next_sync_committee.is_none());
+
+        let exec = &decoded.finalized_execution;
+        assert_eq!(exec.block_number, 2);
+        assert_eq!(exec.state_root, [0x12; 32]);
+        assert_eq!(exec.fee_recipient, [0x11; 20]);
+        assert_eq!(exec.base_fee_per_gas, U256::from_u64(100));
+        assert_eq!(exec.blob_gas_used, 131_072);
+        assert_eq!(exec.withdrawals_root, [0x16; 32]);
+    }
+
+    #[test]
+    fn test_decode_optimistic_update() {
+        let decoded = decode_optimistic_update(&optimistic_update(300)).unwrap();
+        assert_eq!(decoded.attested_header.slot, 300);
+        assert_eq!(decoded.attested_execution.block_number, 5);
+        assert_eq!(decoded.sync_aggregate.num_participants(), SYNC_COMMITTEE_SIZE);
+        assert_eq!(decoded.signature_slot, 301);
+
+        // A finality update is not an optimistic one: its first offset is larger.
+        assert!(matches!(
+            decode_optimistic_update(&finality_update(200, 128)),
+            Err(SszError::InvalidOffset { .. })
+        ));
+    }
+
+    #[test]
+    fn test_tampered_execution_header_rejected() {
+        let execution_at = OPTIMISTIC_UPDATE_FIXED_LEN + LIGHT_CLIENT_HEADER_FIXED_LEN;
+        let rejected = SszError::InvalidField { container: "LightClientHeader", field: "execution_branch" };
+
+        // Another state root, extra data the decoded header does not keep,
+        // or another branch node: none is the payload the body commits to.
+        for at in [execution_at + 52, execution_at + EXECUTION_HEADER_FIXED_LEN, execution_at - 1] {
+            let mut tampered = optimistic_update(300);
+            tampered[at] ^= 1;
+            assert_eq!(decode_optimistic_update(&tampered).unwrap_err(), rejected);
+        }
+    }
+
+    #[test]
+    fn test_decode_update_next_committee() {
+        let decoded = decode_update(&period_update([0x50; 32])).unwrap();
+        let committee = decoded.update.next_sync_committee.unwrap();
+        assert_eq!(committee.pubkeys.len(), SYNC_COMMITTEE_SIZE);
+        assert_eq!(committee.aggregate_pubkey.0, [0x40; BLS_PUBKEY_LEN]);
+        assert_eq!(decoded.update.next_sync_committee_branch.len(), NEXT_SYNC_COMMITTEE_BRANCH_DEPTH);
+
+        // A zeroed branch means the update carries no committee.
+        let decoded = decode_update(&period_update([0u8; 32])).unwrap();
+        assert!(decoded.update.next_sync_committee.is_none());
+        assert!(decoded.update.next_sync_committee_branch.is_empty());
+    }
+
+    fn bootstrap(slot: u64) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend_from_slice(&(BOOTSTRAP_FIXED_LEN as u32).to_le_bytes());
+        out.extend_from_slice(&vec![0x40; SYNC_COMMITTEE_LEN]);
+        out.extend_from_slice(&[0x60; 32 * CURRENT_SYNC_COMMITTEE_BRANCH_DEPTH]);
+        out.extend_from_slice(&light_client_header(slot, 3));
+        out
+    }
+
+    fn chunk(object: &[u8]) -> Vec<u8> {
+        let mut out = ((object.len() + 4) as u64).to_le_bytes().to_vec();
+        out.extend_from_slice(&[0x6a, 0x95, 0xa1, 0xa9]);
+        out.extend_from_slice(object);
+        out
+    }
+
+    #[test]
+    fn test_decode_bootstrap() {
+        let decoded = decode_bootstrap(&bootstrap(8192)).unwrap();
+        assert_eq!(decoded.bootstrap.header.slot, 8192);
+        assert_eq!(decoded.bootstrap.current_sync_committee.pubkeys.len(), SYNC_COMMITTEE_SIZE);
+        assert_eq!(
+            decoded.bootstrap.current_sync_committee_branch,
+            vec![[0x60; 32]; CURRENT_SYNC_COMMITTEE_BRANCH_DEPTH]
+        );
+        assert_eq!(decoded.execution.block_number, 3);
+        assert!(decode_bootstrap(&bootstrap(8192)[..BOOTSTRAP_FIXED_LEN]).is_err());
+    }
+
+    #[test]
+    fn test_decode_updates_list() {
+        let mut body = chunk(&period_update([0x50; 32]));
+        body.extend_from_slice(&chunk(&period_update([0u8; 32])));
+        let updates = decode_updates(&body).unwrap();
+        assert_eq!(updates.len(), 2);
+        assert!(updates[0].update.next_sync_committee.is_some());
+        assert!(updates[1].update.next_sync_committee.is_none());
+        assert_eq!(response_chunks(&body).unwrap()[1].0, [0x6a, 0x95, 0xa1, 0xa9]);
+
+        assert!(decode_updates(&[]).unwrap().is_empty());
+        // A length running past the end of the body.
+        assert!(decode_updates(&body[..body.len() - 1]).is_err());
+        // A length too short to hold the fork digest.
+        let mut bad = body.clone();
+        bad[..8].copy_from_slice(&2u64.to_le_bytes());
+        assert!(matches!(response_chunks(&bad), Err(SszError::InvalidField { field: "length", .. })));
+    }
+
+    #[test]
+    fn test_malformed_input_rejected() {
+        let bytes = finality_update(200, 128);
+        assert!(matches!(
+            decode_finality_update(&bytes[..100]),
+            Err(SszError::TooShort { expected: FINALITY_UPDATE_FIXED_LEN, .. })
+        ));
+        // Cut inside the finalized header's execution payload.
+        assert!(decode_finality_update(&bytes[..bytes.len() - 10]).is_err());
+
+        // The first offset must point just past the fixed part.
+        let mut bad = bytes.clone();
+        bad[..4].copy_from_slice(&1u32.to_le_bytes());
+        assert_eq!(
+            decode_finality_update(&bad).unwrap_err(),
+            SszError::InvalidOffset {
+                container: "LightClientFinalityUpdate",
+                offset: 1
+            }
+        );
+
+        // Offsets past the end of the input.
+        let mut bad = bytes;
+        bad[4..8].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(matches!(decode_finality_update(&bad), Err(SszError::InvalidOffset { .. })));
+
+        assert!(decode_update(&finality_update(200, 128)).is_err());
+    }
+}
@@ -1,3 +1,4 @@
+use crate::consensus::aggregate_cache::AggregatePubkeyCache;
 use crate::types::beacon::*;
 use sha2::{Digest, Sha256};
 use thiserror::Error;
@@ -36,12 +37,24 @@ pub enum VerificationError {
     #[error("Invalid Merkle branch for next sync committee: branch verification failed")]
     InvalidNextSyncCommitteeBranch,
 
+    #[error("Invalid Merkle branch for execution payload: branch verification failed")]
+    InvalidExecutionBranch,
+
+    #[error("Block {block_root} is not the verified header {header_root}")]
+    BlockRootMismatch { block_root: String, header_root: String },
+
     #[error("Update slot {update_slot} is not newer than current state slot {current_slot}")]
     UpdateNotNewer {
         update_slot: u64,
         current_slot: u64,
     },
 
+    #[error("Update signed at slot {signature_slot}, after the current slot {current_slot}")]
+    FutureSlot {
+        signature_slot: u64,
+        current_slot: u64,
+    },
+
     #[error("Sync committee bits length mismatch: expected 64 bytes, got {got}")]
     InvalidSyncCommitteeBitsLength { got: usize },
 
@@ -74,6 +87,14 @@ pub fn compute_domain(
     domain
 }
 
+/// The 4-byte fork digest that names a fork on the p2p network, in gossip
+/// topics and in front of req/resp chunks: fork_data_root[:4].
+pub fn compute_fork_digest(fork_version: &[u8; 4], genesis_validators_root: &[u8; 32]) -> [u8; 4] {
+    let mut digest = [0u8; 4];
+    digest.copy_from_slice(&compute_fork_data_root(fork_version, genesis_validators_root)[..4]);
+    digest
+}
+
 /// Compute the fork data root from fork version and genesis validators root.
 fn compute_fork_data_root(
     fork_version: &[u8; 4],
@@ -105,19 +126,31 @@ pub fn hash_beacon_block_header(header: &BeaconBlockHeader) -> [u8; 32] {
 
     // Merkleize: 5 leaves -> pad to 8 (next power of 2)
     let zero = [0u8; 32];
+    let leaves = [slot_leaf, proposer_leaf, parent_leaf, state_leaf, body_leaf, zero, zero, zero];
 
-    // Layer 0 (leaves): [slot, proposer, parent, state, body, 0, 0, 0]
-    let h01 = sha256_pair(&slot_leaf, &proposer_leaf);
-    let h23 = sha256_pair(&parent_leaf, &state_leaf);
-    let h45 = sha256_pair(&body_leaf, &zero);
-    let h67 = sha256_pair(&zero, &zero);
-
-    // Layer 1
-    let h0123 = sha256_pair(&h01, &h23);
-    let h4567 = sha256_pair(&h45, &h67);
+    // Layer 0 (leaves) -> layer 1 -> root
+    let layer = merkle_layer(&leaves);
+    let layer = merkle_layer(&layer);
+    merkle_layer(&layer)[0]
+}
 
-    // Root
-    sha256_pair(&h0123, &h4567)
+/// Hash adjacent pairs of `nodes`: one level up an SSZ merkle tree.
+pub(crate) fn merkle_layer(nodes: &[[u8; 32]]) -> Vec<[u8; 32]> {
+    #[cfg(feature = "simd")]
+    {
+        let pairs: Vec<[u8; 64]> = nodes
+            .chunks_exact(2)
+            .map(|pair| {
+                let mut data = [0u8; 64];
+                data[..32].copy_from_slice(&pair[0]);
+                data[32..].copy_from_slice(&pair[1]);
+                data
+            })
+            .collect();
+        crate::simd::sha256_64_batch(&pairs)
+    }
+    #[cfg(not(feature = "simd"))]
+    nodes.chunks_exact(2).map(|pair| sha256_pair(&pair[0], &pair[1])).collect()
 }
 
 /// Verify a sync committee signature against a beacon block header.
@@ -130,6 +163,28 @@ pub fn verify_sync_committee_signature(
     current_sync_committee: &SyncCommittee,
     genesis_validators_root: [u8; 32],
     fork_version: [u8; 4],
+) -> Result<(), VerificationError> {
+    signature_inner(update, current_sync_committee, genesis_validators_root, fork_version, None)
+}
+
+/// Like [`verify_sync_committee_signature`], with the participants' aggregate
+/// public key taken from (or added to) `cache`.
+pub fn verify_sync_committee_signature_cached(
+    update: &LightClientUpdate,
+    current_sync_committee: &SyncCommittee,
+    genesis_validators_root: [u8; 32],
+    fork_version: [u8; 4],
+    cache: &mut AggregatePubkeyCache,
+) -> Result<(), VerificationError> {
+    signature_inner(update, current_sync_committee, genesis_validators_root, fork_version, Some(cache))
+}
+
+fn signature_inner(
+    update: &LightClientUpdate,
+    current_sync_committee: &SyncCommittee,
+    genesis_validators_root: [u8; 32],
+    fork_version: [u8; 4],
+    cache: Option<&mut AggregatePubkeyCache>,
 ) -> Result<(), VerificationError> {
     // Validate sync committee bits length
     if update.sync_aggregate.sync_committee_bits.len() != SYNC_COMMITTEE_SIZE / 8 {
@@ -172,16 +227,23 @@ pub fn verify_sync_committee_signature(
     // Compute the signing root (what the committee actually signed)
     let signing_root = compute_signing_root(&update.attested_header, &domain);
 
-    // Collect the public keys of participating committee members
-    let participant_indices = update.sync_aggregate.participant_indices();
-    let participant_pubkeys: Vec<&BlsPublicKey> = participant_indices
-        .iter()
-        .map(|&i| &current_sync_committee.pubkeys[i])
-        .collect();
+    // Aggregate the public keys of participating committee members
+    let aggregate_pubkey = match cache {
+        Some(cache) => cache.aggregate(current_sync_committee, &update.sync_aggregate)?,
+        None => {
+            let participant_pubkeys: Vec<&BlsPublicKey> = update
+                .sync_aggregate
+                .participant_indices()
+                .iter()
+                .map(|&i| &current_sync_committee.pubkeys[i])
+                .collect();
+            aggregate_pubkeys(&participant_pubkeys)?
+        }
+    };
 
     // Verify the aggregate BLS signature
     verify_aggregate_bls_signature(
-        &participant_pubkeys,
+        &aggregate_pubkey,
         &signing_root,
         &update.sync_aggregate.sync_committee_signature,
     )?;
@@ -192,50 +254,23 @@ pub fn verify_sync_committee_signature(
 /// Verify an aggregate BLS12-381 signature.
 /// Uses the blst library for actual cryptographic verification.
 fn verify_aggregate_bls_signature(
-    pubkeys: &[&BlsPublicKey],
+    agg_pk_final: &blst::min_pk::PublicKey,
     message: &[u8; 32],
     signature: &BlsSignature,
 ) -> Result<(), VerificationError> {
-    use blst::min_pk::{AggregatePublicKey, PublicKey, Signature};
+    use blst::min_pk::Signature;
     use blst::BLST_ERROR;
 
-    if pubkeys.is_empty() {
-        return Err(VerificationError::InsufficientParticipation {
-            participants: 0,
-            required: MIN_SYNC_COMMITTEE_PARTICIPANTS,
-        });
-    }
-
     // Deserialize the signature
     let sig = Signature::from_bytes(&signature.0).map_err(|e| {
         VerificationError::BlsError(format!("Failed to deserialize signature: {:?}", e))
     })?;
 
-    // Deserialize all public keys
-    let pks: Vec<PublicKey> = pubkeys
-        .iter()
-        .enumerate()
-        .map(|(i, pk)| {
-            PublicKey::from_bytes(&pk.0).map_err(|e| VerificationError::InvalidPublicKey {
-                index: i,
-                reason: format!("{:?}", e),
-            })
-        })
-        .collect::<Result<Vec<_>, _>>()?;
-
-    // Aggregate the public keys
-    let pk_refs: Vec<&PublicKey> = pks.iter().collect();
-    let agg_pk = AggregatePublicKey::aggregate(&pk_refs, false).map_err(|e| {
-        VerificationError::BlsError(format!("Failed to aggregate public keys: {:?}", e))
-    })?;
-
-    let agg_pk_final = agg_pk.to_public_key();
-
     // DST (domain separation tag) for Ethereum BLS signatures
     let dst = b"BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_POP_";
 
     // Verify the signature
-    let result = sig.verify(false, message, dst, &[], &agg_pk_final, false);
+    let result = sig.verify(false, message, dst, &[], agg_pk_final, false);
     if result != BLST_ERROR::BLST_SUCCESS {
         return Err(VerificationError::InvalidSignature);
     }
@@ -243,6 +278,66 @@ fn verify_aggregate_bls_signature(
     Ok(())
 }
 
+/// Keys per aggregation task with the `threads` feature: large enough that
+/// each task outweighs its scheduling cost.
+#[cfg(feature = "threads")]
+const AGGREGATION_CHUNK: usize = 64;
+
+pub(crate) fn deserialize_pubkey(
+    index: usize,
+    pubkey: &BlsPublicKey,
+) -> Result<blst::min_pk::PublicKey, VerificationError> {
+    blst::min_pk::PublicKey::from_bytes(&pubkey.0).map_err(|e| VerificationError::InvalidPublicKey {
+        index,
+        reason: format!("{:?}", e),
+    })
+}
+
+pub(crate) fn aggregation_error(e: blst::BLST_ERROR) -> VerificationError {
+    VerificationError::BlsError(format!("Failed to aggregate public keys: {:?}", e))
+}
+
+/// Deserialize and aggregate the participants' public keys: ~500
+/// decompressions, the bulk of the time spent before the pairing check.
+#[cfg(not(feature = "threads"))]
+pub(crate) fn aggregate_pubkeys(pubkeys: &[&BlsPublicKey]) -> Result<blst::min_pk::PublicKey, VerificationError> {
+    use blst::min_pk::{AggregatePublicKey, PublicKey};
+
+    let pks: Vec<PublicKey> = pubkeys
+        .iter()
+        .enumerate()
+        .map(|(i, pk)| deserialize_pubkey(i, pk))
+        .collect::<Result<Vec<_>, _>>()?;
+    let pk_refs: Vec<&PublicKey> = pks.iter().collect();
+    let agg_pk = AggregatePublicKey::aggregate(&pk_refs, false).map_err(aggregation_error)?;
+    Ok(agg_pk.to_public_key())
+}
+
+/// Deserialize and aggregate the participants' public keys on the rayon
+/// thread pool: keys are decompressed in parallel, then summed in chunks
+/// whose partial aggregates are added together.
+#[cfg(feature = "threads")]
+pub(crate) fn aggregate_pubkeys(pubkeys: &[&BlsPublicKey]) -> Result<blst::min_pk::PublicKey, VerificationError> {
+    use blst::min_pk::{AggregatePublicKey, PublicKey};
+    use rayon::prelude::*;
+
+    let pks: Vec<PublicKey> = pubkeys
+        .par_iter()
+        .enumerate()
+        .map(|(i, pk)| deserialize_pubkey(i, pk))
+        .collect::<Result<Vec<_>, _>>()?;
+    let agg_pk = pks
+        .par_chunks(AGGREGATION_CHUNK)
+        .map(|chunk| AggregatePublicKey::aggregate(&chunk.iter().collect::<Vec<_>>(), false))
+        .try_reduce_with(|mut total, part| {
+            total.add_aggregate(&part);
+            Ok(total)
+        })
+        .ok_or_else(|| VerificationError::BlsError("No public keys to aggregate".to_string()))?
+        .map_err(aggregation_error)?;
+    Ok(agg_pk.to_public_key())
+}
+
 /// Verify a Merkle branch (SSZ proof) against an expected root.
 /// Used to verify finality proofs and sync committee proofs within beacon state.
 pub fn verify_merkle_branch(
@@ -281,7 +376,7 @@ fn sha256_hash(data: &[u8]) -> [u8; 32] {
 }
 
 /// SHA256 hash of two 32-byte values concatenated.
-fn sha256_pair(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
+pub(crate) fn sha256_pair(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
     let mut data = [0u8; 64];
     data[..32].copy_from_slice(a);
     data[32..].copy_from_slice(b);
@@ -338,6 +433,16 @@ mod tests {
         assert_eq!(domain, domain2);
     }
 
+    #[test]
+    fn test_compute_fork_digest() {
+        let mainnet = crate::types::network::NetworkConfig::mainnet();
+        // Mainnet Electra.
+        assert_eq!(
+            compute_fork_digest(&mainnet.fork_version, &mainnet.genesis_validators_root),
+            [0xad, 0x53, 0x2c, 0xeb]
+        );
+    }
+
     #[test]
     fn test_verify_merkle_branch_trivial() {
         // Single-depth branch: leaf with one sibling
@@ -350,6 +455,30 @@ mod tests {
         assert!(!verify_merkle_branch(&leaf, &[sibling], 1, 1, &root));
     }
 
+    #[test]
+    fn test_aggregate_pubkeys() {
+        use blst::min_pk::{AggregatePublicKey, SecretKey};
+
+        let keys: Vec<_> = (0..200u8)
+            .map(|i| SecretKey::key_gen(&[i; 32], &[]).unwrap().sk_to_pk())
+            .collect();
+        let expected = AggregatePublicKey::aggregate(&keys.iter().collect::<Vec<_>>(), false)
+            .unwrap()
+            .to_public_key();
+
+        let encoded: Vec<BlsPublicKey> = keys.iter().map(|pk| BlsPublicKey(pk.compress())).collect();
+        let refs: Vec<&BlsPublicKey> = encoded.iter().collect();
+        assert_eq!(aggregate_pubkeys(&refs).unwrap(), expected);
+
+        let mut encoded = encoded;
+        encoded[150] = BlsPublicKey([0x11; 48]);
+        let refs: Vec<&BlsPublicKey> = encoded.iter().collect();
+        assert!(matches!(
+            aggregate_pubkeys(&refs),
+            Err(VerificationError::InvalidPublicKey { index: 150, .. })
+        ));
+    }
+
     #[test]
     fn test_sync_aggregate_participation() {
         let mut bits = vec![0u8; 64]; // 512 bits
@@ -373,8 +502,8 @@ mod tests {
         // Create an update with only 100 participants (less than 342 required)
         let mut bits = vec![0u8; 64];
         // Set first 100 bits
-        for i in 0..12 {
-            bits[i] = 0xFF; // 12 * 8 = 96 participants
+        for byte in bits.iter_mut().take(12) {
+            *byte = 0xFF; // 12 * 8 = 96 participants
         }
         bits[12] = 0x0F; // 4 more = 100
 
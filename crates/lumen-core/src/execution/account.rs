@@ -1,5 +1,6 @@
-use crate::execution::proof::{verify_account_proof, verify_storage_proof, ProofError};
+use crate::execution::proof::{verify_account_proof, verify_storage_proofs, ProofError};
 use crate::types::execution::*;
+use crate::types::uint::U256;
 
 /// Verify an account's full state including selected storage slots.
 /// This is the high-level API that combines account proof and storage proof verification.
@@ -7,6 +8,10 @@ use crate::types::execution::*;
 /// The state_root must come from a verified execution payload header in our light client state.
 /// The proof data can come from any source (including untrusted RPCs) — we verify it
 /// cryptographically regardless of source.
+///
+/// Only the account proof is fatal. Storage proofs are verified independently
+/// and reported per slot, so one malformed slot proof does not discard the
+/// slots that did verify.
 pub fn verify_full_account_state(
     state_root: [u8; 32],
     proof_response: &EthGetProofResponse,
@@ -19,65 +24,80 @@ pub fn verify_full_account_state(
     )?;
 
     // Then verify each storage proof against the account's storage root
-    let mut verified_storage: Vec<VerifiedStorageSlot> = Vec::new();
-
-    for storage_proof in &proof_response.storage_proofs {
-        let value = verify_storage_proof(account.storage_root, storage_proof.key, storage_proof)?;
-
-        verified_storage.push(VerifiedStorageSlot {
-            key: storage_proof.key,
-            value,
-        });
-    }
+    let results = verify_storage_proofs(account.storage_root, &proof_response.storage_proofs);
+    let storage_slots = proof_response
+        .storage_proofs
+        .iter()
+        .zip(results)
+        .map(|(proof, result)| StorageSlotOutcome {
+            key: proof.key,
+            result,
+        })
+        .collect();
 
     Ok(VerifiedAccountState {
         address: proof_response.account_proof.address,
         account,
-        storage_slots: verified_storage,
+        storage_slots,
     })
 }
 
-/// A fully verified account state with verified storage slots.
-/// Every field in this struct has been cryptographically verified against
-/// the beacon chain sync committee consensus.
+/// A fully verified account state with per-slot storage outcomes.
+/// The account fields have been cryptographically verified against the
+/// beacon chain sync committee consensus; each storage slot carries either
+/// its verified value or the reason its proof was rejected.
 #[derive(Clone, Debug)]
 pub struct VerifiedAccountState {
     /// The Ethereum address.
     pub address: [u8; 20],
     /// The verified account state (nonce, balance, storage root, code hash).
     pub account: AccountState,
-    /// Verified storage slot values.
-    pub storage_slots: Vec<VerifiedStorageSlot>,
+    /// Storage slot outcomes, in the order the proofs were supplied.
+    pub storage_slots: Vec<StorageSlotOutcome>,
 }
 
-/// A single verified storage slot.
+/// The outcome of verifying a single storage slot.
 #[derive(Clone, Debug)]
-pub struct VerifiedStorageSlot {
+pub struct StorageSlotOutcome {
     /// The storage key (slot number).
     pub key: [u8; 32],
-    /// The verified storage value.
-    pub value: [u8; 32],
+    /// The verified storage value, or why the slot's proof failed.
+    pub result: Result<U256, ProofError>,
 }
 
-impl VerifiedAccountState {
-    /// Get the balance as a hex string (without 0x prefix).
-    pub fn balance_hex(&self) -> String {
-        hex::encode(self.account.balance)
-            .trim_start_matches('0')
-            .to_string()
+impl StorageSlotOutcome {
+    /// True if this slot's proof verified.
+    pub fn is_verified(&self) -> bool {
+        self.result.is_ok()
     }
+}
 
+impl VerifiedAccountState {
     /// Check if this is a contract account.
     pub fn is_contract(&self) -> bool {
         self.account.is_contract()
     }
 
     /// Look up a verified storage slot value by key.
-    pub fn get_storage(&self, key: &[u8; 32]) -> Option<&[u8; 32]> {
+    /// Returns None if the slot was not requested or its proof failed.
+    pub fn get_storage(&self, key: &[u8; 32]) -> Option<&U256> {
         self.storage_slots
             .iter()
             .find(|s| &s.key == key)
-            .map(|s| &s.value)
+            .and_then(|s| s.result.as_ref().ok())
+    }
+
+    /// Look up why a storage slot failed verification, if it did.
+    pub fn storage_error(&self, key: &[u8; 32]) -> Option<&ProofError> {
+        self.storage_slots
+            .iter()
+            .find(|s| &s.key == key)
+            .and_then(|s| s.result.as_ref().err())
+    }
+
+    /// True if every requested storage slot verified.
+    pub fn all_storage_verified(&self) -> bool {
+        self.storage_slots.iter().all(StorageSlotOutcome::is_verified)
     }
 }
 
@@ -86,47 +106,56 @@ mod tests {
     use super::*;
 
     #[test]
-    fn test_verified_account_state_balance_hex() {
+    fn test_verified_account_state_balance() {
         let state = VerifiedAccountState {
             address: [0; 20],
             account: AccountState {
                 nonce: 0,
-                balance: {
-                    let mut b = [0u8; 32];
-                    b[31] = 100; // 100 wei
-                    b
-                },
+                balance: U256::from_u64(100), // 100 wei
                 storage_root: AccountState::EMPTY_STORAGE_ROOT,
                 code_hash: AccountState::EMPTY_CODE_HASH,
             },
             storage_slots: vec![],
         };
 
-        assert_eq!(state.balance_hex(), "64"); // 100 in hex
+        assert_eq!(state.account.balance.to_hex(), "0x64");
+        assert_eq!(state.account.balance.to_string(), "100");
         assert!(!state.is_contract());
+        assert!(state.all_storage_verified());
     }
 
     #[test]
     fn test_get_storage_lookup() {
         let key1 = [0x01; 32];
         let key2 = [0x02; 32];
-        let value1 = [0xAA; 32];
+        let key3 = [0x03; 32];
+        let value1 = U256::from_be_bytes([0xAA; 32]);
 
         let state = VerifiedAccountState {
             address: [0; 20],
             account: AccountState {
                 nonce: 0,
-                balance: [0; 32],
+                balance: U256::ZERO,
                 storage_root: [0; 32],
                 code_hash: AccountState::EMPTY_CODE_HASH,
             },
-            storage_slots: vec![VerifiedStorageSlot {
-                key: key1,
-                value: value1,
-            }],
+            storage_slots: vec![
+                StorageSlotOutcome {
+                    key: key1,
+                    result: Ok(value1),
+                },
+                StorageSlotOutcome {
+                    key: key3,
+                    result: Err(ProofError::EmptyProof),
+                },
+            ],
         };
 
         assert_eq!(state.get_storage(&key1), Some(&value1));
         assert_eq!(state.get_storage(&key2), None);
+        assert_eq!(state.get_storage(&key3), None);
+        assert!(matches!(state.storage_error(&key3), Some(ProofError::EmptyProof)));
+        assert!(state.storage_error(&key1).is_none());
+        assert!(!state.all_storage_verified());
     }
 }
@@ -0,0 +1,324 @@
+use crate::execution::proof::{decode_rlp_list, keccak256, ProofError};
+use crate::execution::rlp;
+use crate::types::uint::U256;
+
+/// An execution layer block header, as hashed into the block hash.
+///
+/// The beacon chain only commits to the execution block hash (and a subset
+/// of header fields via the payload header). Any other header — an ancestor
+/// of the finalized block, or fields the payload header leaves out — is
+/// verified by hashing its RLP encoding and comparing against a hash we
+/// already trust.
+///
+/// Fields added by later forks are `None` for headers from before the fork.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExecutionBlockHeader {
+    pub parent_hash: [u8; 32],
+    pub ommers_hash: [u8; 32],
+    pub beneficiary: [u8; 20],
+    pub state_root: [u8; 32],
+    pub transactions_root: [u8; 32],
+    pub receipts_root: [u8; 32],
+    pub logs_bloom: [u8; 256],
+    pub difficulty: U256,
+    pub number: u64,
+    pub gas_limit: u64,
+    pub gas_used: u64,
+    pub timestamp: u64,
+    pub extra_data: Vec<u8>,
+    pub mix_hash: [u8; 32],
+    pub nonce: [u8; 8],
+    /// London (EIP-1559).
+    pub base_fee_per_gas: Option<U256>,
+    /// Shanghai (EIP-4895).
+    pub withdrawals_root: Option<[u8; 32]>,
+    /// Cancun (EIP-4844).
+    pub blob_gas_used: Option<u64>,
+    /// Cancun (EIP-4844).
+    pub excess_blob_gas: Option<u64>,
+    /// Cancun (EIP-4788).
+    pub parent_beacon_block_root: Option<[u8; 32]>,
+    /// Prague (EIP-7685).
+    pub requests_hash: Option<[u8; 32]>,
+}
+
+/// Number of fields in a pre-London header.
+const BASE_FIELDS: usize = 15;
+/// Number of fields in a Prague header, the most any fork defines.
+const MAX_FIELDS: usize = 21;
+
+impl ExecutionBlockHeader {
+    /// Decode a header from its RLP encoding (as returned by `debug_getRawHeader`).
+    pub fn decode(data: &[u8]) -> Result<Self, ProofError> {
+        let items = decode_rlp_list(data).map_err(|reason| ProofError::InvalidHeaderEncoding { reason })?;
+        if !(BASE_FIELDS..=MAX_FIELDS).contains(&items.len()) {
+            return Err(ProofError::InvalidHeaderEncoding {
+                reason: format!(
+                    "Header should have {} to {} fields, got {}",
+                    BASE_FIELDS,
+                    MAX_FIELDS,
+                    items.len()
+                ),
+            });
+        }
+
+        let optional = |i: usize| items.get(i).map(|item| item.as_slice());
+
+        Ok(Self {
+            parent_hash: fixed(&items[0], "parentHash")?,
+            ommers_hash: fixed(&items[1], "sha3Uncles")?,
+            beneficiary: fixed(&items[2], "miner")?,
+            state_root: fixed(&items[3], "stateRoot")?,
+            transactions_root: fixed(&items[4], "transactionsRoot")?,
+            receipts_root: fixed(&items[5], "receiptsRoot")?,
+            logs_bloom: fixed(&items[6], "logsBloom")?,
+            difficulty: uint256(&items[7], "difficulty")?,
+            number: uint64(&items[8], "number")?,
+            gas_limit: uint64(&items[9], "gasLimit")?,
+            gas_used: uint64(&items[10], "gasUsed")?,
+            timestamp: uint64(&items[11], "timestamp")?,
+            extra_data: items[12].clone(),
+            mix_hash: fixed(&items[13], "mixHash")?,
+            nonce: fixed(&items[14], "nonce")?,
+            base_fee_per_gas: optional(15).map(|b| uint256(b, "baseFeePerGas")).transpose()?,
+            withdrawals_root: optional(16).map(|b| fixed(b, "withdrawalsRoot")).transpose()?,
+            blob_gas_used: optional(17).map(|b| uint64(b, "blobGasUsed")).transpose()?,
+            excess_blob_gas: optional(18).map(|b| uint64(b, "excessBlobGas")).transpose()?,
+            parent_beacon_block_root: optional(19)
+                .map(|b| fixed(b, "parentBeaconBlockRoot"))
+                .transpose()?,
+            requests_hash: optional(20).map(|b| fixed(b, "requestsHash")).transpose()?,
+        })
+    }
+
+    /// RLP-encode the header. Optional fields are written up to the last one
+    /// present, matching how each fork extends the header.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut items = vec![
+            rlp::encode_bytes(&self.parent_hash),
+            rlp::encode_bytes(&self.ommers_hash),
+            rlp::encode_bytes(&self.beneficiary),
+            rlp::encode_bytes(&self.state_root),
+            rlp::encode_bytes(&self.transactions_root),
+            rlp::encode_bytes(&self.receipts_root),
+            rlp::encode_bytes(&self.logs_bloom),
+            rlp::encode_u256(self.difficulty),
+            rlp::encode_u64(self.number),
+            rlp::encode_u64(self.gas_limit),
+            rlp::encode_u64(self.gas_used),
+            rlp::encode_u64(self.timestamp),
+            rlp::encode_bytes(&self.extra_data),
+            rlp::encode_bytes(&self.mix_hash),
+            rlp::encode_bytes(&self.nonce),
+        ];
+        let optional = [
+            self.base_fee_per_gas.map(rlp::encode_u256),
+            self.withdrawals_root.map(|r| rlp::encode_bytes(&r)),
+            self.blob_gas_used.map(rlp::encode_u64),
+            self.excess_blob_gas.map(rlp::encode_u64),
+            self.parent_beacon_block_root.map(|r| rlp::encode_bytes(&r)),
+            self.requests_hash.map(|r| rlp::encode_bytes(&r)),
+        ];
+        items.extend(optional.into_iter().map_while(|field| field));
+        rlp::encode_list(&items)
+    }
+
+    /// The block hash: keccak256 of the RLP-encoded header.
+    pub fn hash(&self) -> [u8; 32] {
+        keccak256(&self.encode())
+    }
+}
+
+/// Decode a raw header and check that it hashes to `expected_hash`.
+///
+/// The hash is taken over the bytes exactly as received, so a header that
+/// decodes but is not the canonical encoding cannot match.
+pub fn verify_block_header(
+    raw_header: &[u8],
+    expected_hash: [u8; 32],
+) -> Result<ExecutionBlockHeader, ProofError> {
+    let header = ExecutionBlockHeader::decode(raw_header)?;
+    let computed = keccak256(raw_header);
+    if computed != expected_hash {
+        return Err(ProofError::HeaderHashMismatch {
+            number: header.number,
+            computed: hex::encode(computed),
+            expected: hex::encode(expected_hash),
+        });
+    }
+    Ok(header)
+}
+
+/// Verify a run of ancestor headers back from a trusted block hash.
+///
+/// `raw_headers[0]` must hash to `anchor_hash`, and each following header
+/// must hash to the `parent_hash` of the one before it. Returns the decoded
+/// headers in the same (descending) order.
+pub fn verify_header_chain(
+    anchor_hash: [u8; 32],
+    raw_headers: &[Vec<u8>],
+) -> Result<Vec<ExecutionBlockHeader>, ProofError> {
+    let mut expected = anchor_hash;
+    let mut headers = Vec::with_capacity(raw_headers.len());
+    for raw in raw_headers {
+        let header = verify_block_header(raw, expected)?;
+        expected = header.parent_hash;
+        headers.push(header);
+    }
+    Ok(headers)
+}
+
+/// EIP-1559: the gas target is this fraction of the gas limit.
+pub const ELASTICITY_MULTIPLIER: u64 = 2;
+
+/// EIP-1559: the base fee moves by at most 1/8 from one block to the next.
+pub const BASE_FEE_MAX_CHANGE_DENOMINATOR: u64 = 8;
+
+/// The base fee of the block after one with `base_fee`, `gas_used` and
+/// `gas_limit`, per EIP-1559.
+pub fn next_base_fee(base_fee: U256, gas_used: u64, gas_limit: u64) -> U256 {
+    let target = gas_limit / ELASTICITY_MULTIPLIER;
+    if target == 0 || gas_used == target {
+        return base_fee;
+    }
+    // base_fee * delta / target / 8, saturating: no real base fee comes near 2^192.
+    let change = |delta: u64| {
+        base_fee
+            .checked_mul(&U256::from_u64(delta))
+            .and_then(|v| v.checked_div(&U256::from_u64(target)))
+            .and_then(|v| v.checked_div(&U256::from_u64(BASE_FEE_MAX_CHANGE_DENOMINATOR)))
+            .unwrap_or(U256::MAX)
+    };
+    if gas_used > target {
+        let increase = change(gas_used - target).max(U256::from_u64(1));
+        base_fee.checked_add(&increase).unwrap_or(U256::MAX)
+    } else {
+        base_fee.saturating_sub(&change(target - gas_used))
+    }
+}
+
+fn fixed<const N: usize>(bytes: &[u8], field: &str) -> Result<[u8; N], ProofError> {
+    bytes.try_into().map_err(|_| ProofError::InvalidHeaderEncoding {
+        reason: format!("{} should be {} bytes, got {}", field, N, bytes.len()),
+    })
+}
+
+fn uint64(bytes: &[u8], field: &str) -> Result<u64, ProofError> {
+    let value = U256::from_be_slice(bytes).and_then(|v| v.to_u64());
+    value.ok_or_else(|| ProofError::InvalidHeaderEncoding {
+        reason: format!("{} does not fit in 64 bits", field),
+    })
+}
+
+fn uint256(bytes: &[u8], field: &str) -> Result<U256, ProofError> {
+    U256::from_be_slice(bytes).ok_or_else(|| ProofError::InvalidHeaderEncoding {
+        reason: format!("{} does not fit in 256 bits", field),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_header(number: u64, parent_hash: [u8; 32]) -> ExecutionBlockHeader {
+        ExecutionBlockHeader {
+            parent_hash,
+            ommers_hash: [0x1d; 32],
+            beneficiary: [0xFE; 20],
+            state_root: [0x01; 32],
+            transactions_root: [0x02; 32],
+            receipts_root: [0x03; 32],
+            logs_bloom: [0; 256],
+            difficulty: U256::ZERO,
+            number,
+            gas_limit: 30_000_000,
+            gas_used: 12_345_678,
+            timestamp: 1_700_000_000 + number * 12,
+            extra_data: b"lumen".to_vec(),
+            mix_hash: [0x04; 32],
+            nonce: [0; 8],
+            base_fee_per_gas: Some(U256::from_u64(7_000_000_000)),
+            withdrawals_root: Some([0x05; 32]),
+            blob_gas_used: Some(131_072),
+            excess_blob_gas: Some(0),
+            parent_beacon_block_root: Some([0x06; 32]),
+            requests_hash: None,
+        }
+    }
+
+    #[test]
+    fn test_header_round_trip() {
+        let header = test_header(100, [0xAA; 32]);
+        let encoded = header.encode();
+        assert_eq!(ExecutionBlockHeader::decode(&encoded).unwrap(), header);
+        assert_eq!(verify_block_header(&encoded, header.hash()).unwrap(), header);
+
+        // Pre-London headers stop after the nonce.
+        let legacy = ExecutionBlockHeader {
+            base_fee_per_gas: None,
+            withdrawals_root: None,
+            blob_gas_used: None,
+            excess_blob_gas: None,
+            parent_beacon_block_root: None,
+            ..header
+        };
+        let encoded = legacy.encode();
+        assert_eq!(decode_rlp_list(&encoded).unwrap().len(), BASE_FIELDS);
+        assert_eq!(ExecutionBlockHeader::decode(&encoded).unwrap(), legacy);
+    }
+
+    #[test]
+    fn test_header_hash_mismatch() {
+        let header = test_header(100, [0xAA; 32]);
+        let mut tampered = header.clone();
+        tampered.timestamp += 1;
+        assert!(matches!(
+            verify_block_header(&tampered.encode(), header.hash()),
+            Err(ProofError::HeaderHashMismatch { number: 100, .. })
+        ));
+    }
+
+    #[test]
+    fn test_header_chain() {
+        let grandparent = test_header(98, [0x00; 32]);
+        let parent = test_header(99, grandparent.hash());
+        let head = test_header(100, parent.hash());
+        let raw = vec![head.encode(), parent.encode(), grandparent.encode()];
+
+        let verified = verify_header_chain(head.hash(), &raw).unwrap();
+        assert_eq!(verified.iter().map(|h| h.number).collect::<Vec<_>>(), vec![100, 99, 98]);
+
+        // A header that is not the parent of the one before it breaks the chain.
+        let stranger = test_header(99, [0xBB; 32]);
+        let raw = vec![head.encode(), stranger.encode()];
+        assert!(matches!(
+            verify_header_chain(head.hash(), &raw),
+            Err(ProofError::HeaderHashMismatch { number: 99, .. })
+        ));
+    }
+
+    #[test]
+    fn test_next_base_fee() {
+        let base_fee = U256::from_u64(1_000_000_000);
+        assert_eq!(next_base_fee(base_fee, 15_000_000, 30_000_000), base_fee);
+        // Full block: +12.5%. Empty block: -12.5%.
+        assert_eq!(next_base_fee(base_fee, 30_000_000, 30_000_000), U256::from_u64(1_125_000_000));
+        assert_eq!(next_base_fee(base_fee, 0, 30_000_000), U256::from_u64(875_000_000));
+        // Above target, the base fee rises by at least 1 wei.
+        assert_eq!(next_base_fee(U256::from_u64(7), 15_000_001, 30_000_000), U256::from_u64(8));
+    }
+
+    #[test]
+    fn test_header_rejects_malformed_fields() {
+        let mut header = test_header(1, [0; 32]);
+        header.extra_data = vec![];
+        let mut items = decode_rlp_list(&header.encode()).unwrap();
+        items[0] = vec![0xAA; 31];
+        let encoded = rlp::encode_list(&items.iter().map(|i| rlp::encode_bytes(i)).collect::<Vec<_>>());
+        assert!(matches!(
+            ExecutionBlockHeader::decode(&encoded),
+            Err(ProofError::InvalidHeaderEncoding { .. })
+        ));
+        assert!(ExecutionBlockHeader::decode(&rlp::encode_list(&[])).is_err());
+    }
+}
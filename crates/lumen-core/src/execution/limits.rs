@@ -0,0 +1,124 @@
+use crate::execution::proof::ProofError;
+
+/// Size caps applied to a Merkle-Patricia proof before any node is decoded or hashed.
+///
+/// Proofs arrive from untrusted RPCs and peers. Without caps, a malicious
+/// source can hand the client megabytes of junk nodes and make it hash and
+/// decode all of them before the proof is finally rejected. Real proofs are
+/// small and bounded by the trie's shape, so anything past these limits is
+/// rejected up front with a typed error.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ProofLimits {
+    /// Maximum number of nodes in one proof.
+    pub max_nodes: usize,
+    /// Maximum size of any single RLP-encoded node, in bytes.
+    pub max_node_size: usize,
+    /// Maximum combined size of all nodes, in bytes.
+    pub max_total_bytes: usize,
+}
+
+impl ProofLimits {
+    /// Limits for state and storage tries.
+    ///
+    /// Keys are 32-byte hashes, so a path has at most 64 nibbles plus the
+    /// leaf. A full branch node is under 600 bytes and account leaves are
+    /// around 110, so 1 KiB per node leaves ample headroom.
+    pub const STATE: ProofLimits = ProofLimits {
+        max_nodes: 65,
+        max_node_size: 1024,
+        max_total_bytes: 65 * 1024,
+    };
+
+    /// Limits for the receipts trie.
+    ///
+    /// Keys are RLP-encoded transaction indices (at most a few bytes), so
+    /// proofs are shallow — but a receipt leaf carries every log the
+    /// transaction emitted and can legitimately be large.
+    pub const RECEIPTS: ProofLimits = ProofLimits {
+        max_nodes: 16,
+        max_node_size: 4 * 1024 * 1024,
+        max_total_bytes: 4 * 1024 * 1024 + 16 * 1024,
+    };
+
+    /// Limits for the transactions trie: keyed like the receipts trie, with
+    /// leaves as large as a block's worth of calldata.
+    pub const TRANSACTIONS: ProofLimits = ProofLimits::RECEIPTS;
+
+    /// Check `nodes` against these limits.
+    pub fn check(&self, nodes: &[Vec<u8>]) -> Result<(), ProofError> {
+        if nodes.len() > self.max_nodes {
+            return Err(ProofError::TooManyProofNodes {
+                count: nodes.len(),
+                max: self.max_nodes,
+            });
+        }
+
+        let mut total: usize = 0;
+        for (index, node) in nodes.iter().enumerate() {
+            if node.len() > self.max_node_size {
+                return Err(ProofError::ProofNodeTooLarge {
+                    index,
+                    size: node.len(),
+                    max: self.max_node_size,
+                });
+            }
+            total += node.len();
+        }
+
+        if total > self.max_total_bytes {
+            return Err(ProofError::ProofTooLarge {
+                total,
+                max: self.max_total_bytes,
+            });
+        }
+        Ok(())
+    }
+}
+
+impl Default for ProofLimits {
+    fn default() -> Self {
+        Self::STATE
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SMALL: ProofLimits = ProofLimits {
+        max_nodes: 3,
+        max_node_size: 10,
+        max_total_bytes: 20,
+    };
+
+    #[test]
+    fn test_within_limits() {
+        assert!(SMALL.check(&[vec![0; 10], vec![0; 10]]).is_ok());
+        assert!(SMALL.check(&[]).is_ok());
+    }
+
+    #[test]
+    fn test_too_many_nodes() {
+        let nodes = vec![vec![0u8; 1]; 4];
+        assert!(matches!(
+            SMALL.check(&nodes),
+            Err(ProofError::TooManyProofNodes { count: 4, max: 3 })
+        ));
+    }
+
+    #[test]
+    fn test_node_too_large() {
+        assert!(matches!(
+            SMALL.check(&[vec![0; 1], vec![0; 11]]),
+            Err(ProofError::ProofNodeTooLarge { index: 1, size: 11, max: 10 })
+        ));
+    }
+
+    #[test]
+    fn test_total_too_large() {
+        assert!(matches!(
+            SMALL.check(&[vec![0; 10], vec![0; 10], vec![0; 1]]),
+            Err(ProofError::ProofTooLarge { total: 21, max: 20 })
+        ));
+    }
+}
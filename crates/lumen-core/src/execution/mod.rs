@@ -1,7 +1,19 @@
 pub mod proof;
 pub mod account;
 pub mod receipt;
+pub mod transaction;
+pub mod header;
+pub mod node_cache;
+pub mod limits;
+pub mod rlp;
+pub mod trie_builder;
+pub mod user_operation;
 
 pub use proof::*;
 pub use account::*;
 pub use receipt::*;
+pub use transaction::*;
+pub use header::*;
+pub use node_cache::*;
+pub use limits::*;
+pub use user_operation::*;
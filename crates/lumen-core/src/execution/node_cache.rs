@@ -0,0 +1,136 @@
+use std::collections::HashMap;
+
+/// Default cap on cached nodes (~4k nodes is a few hundred KB of RLP).
+pub const DEFAULT_MAX_CACHED_NODES: usize = 4096;
+
+/// Cache of trie nodes whose keccak256 hash has already been checked.
+///
+/// Proofs for accounts (and slots) in the same block share their upper trie
+/// nodes, so verifying many of them re-hashes the same branch nodes over and
+/// over. The cache maps a node hash to the exact bytes that were verified to
+/// hash to it: a later proof presenting identical bytes under the same hash
+/// reference is accepted with a byte comparison instead of a keccak256.
+///
+/// Nodes are content-addressed, so a cached entry can never make an invalid
+/// proof pass — different bytes under a cached hash are hashed as usual.
+/// The cache is bound to one state root and clears itself when proofs for a
+/// different root arrive, keeping memory proportional to one block's working set.
+#[derive(Clone, Debug)]
+pub struct TrieNodeCache {
+    state_root: Option<[u8; 32]>,
+    nodes: HashMap<[u8; 32], Vec<u8>>,
+    max_nodes: usize,
+    hits: u64,
+    misses: u64,
+}
+
+impl Default for TrieNodeCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_CACHED_NODES)
+    }
+}
+
+impl TrieNodeCache {
+    /// Create an empty cache holding at most `max_nodes` nodes.
+    /// Once full, further nodes are verified normally but not cached.
+    pub fn new(max_nodes: usize) -> Self {
+        Self {
+            state_root: None,
+            nodes: HashMap::new(),
+            max_nodes,
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    /// Bind the cache to `state_root`, discarding entries from any other root.
+    pub fn bind_root(&mut self, state_root: [u8; 32]) {
+        if self.state_root != Some(state_root) {
+            self.nodes.clear();
+            self.state_root = Some(state_root);
+        }
+    }
+
+    /// The state root the cached nodes were verified under, if any.
+    pub fn state_root(&self) -> Option<[u8; 32]> {
+        self.state_root
+    }
+
+    /// True if `node` was previously verified to hash to `hash`.
+    pub(crate) fn lookup(&mut self, hash: &[u8; 32], node: &[u8]) -> bool {
+        let hit = self.nodes.get(hash).is_some_and(|cached| cached == node);
+        if hit {
+            self.hits += 1;
+        } else {
+            self.misses += 1;
+        }
+        hit
+    }
+
+    /// Record that `node` hashes to `hash`. Only call after checking the hash.
+    pub(crate) fn insert(&mut self, hash: [u8; 32], node: &[u8]) {
+        if self.nodes.len() < self.max_nodes {
+            self.nodes.insert(hash, node.to_vec());
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Number of node hashes skipped thanks to the cache.
+    pub fn hits(&self) -> u64 {
+        self.hits
+    }
+
+    /// Number of nodes that had to be hashed.
+    pub fn misses(&self) -> u64 {
+        self.misses
+    }
+
+    pub fn clear(&mut self) {
+        self.nodes.clear();
+        self.state_root = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lookup_requires_identical_bytes() {
+        let mut cache = TrieNodeCache::default();
+        cache.insert([1; 32], &[0xC1, 0x80]);
+        assert!(cache.lookup(&[1; 32], &[0xC1, 0x80]));
+        assert!(!cache.lookup(&[1; 32], &[0xC1, 0x81]));
+        assert!(!cache.lookup(&[2; 32], &[0xC1, 0x80]));
+        assert_eq!(cache.hits(), 1);
+        assert_eq!(cache.misses(), 2);
+    }
+
+    #[test]
+    fn test_bind_root_clears_on_change() {
+        let mut cache = TrieNodeCache::default();
+        cache.bind_root([0xAA; 32]);
+        cache.insert([1; 32], &[0x01]);
+        cache.bind_root([0xAA; 32]);
+        assert_eq!(cache.len(), 1);
+        cache.bind_root([0xBB; 32]);
+        assert!(cache.is_empty());
+        assert_eq!(cache.state_root(), Some([0xBB; 32]));
+    }
+
+    #[test]
+    fn test_capacity_limit() {
+        let mut cache = TrieNodeCache::new(2);
+        for i in 0..5u8 {
+            cache.insert([i; 32], &[i]);
+        }
+        assert_eq!(cache.len(), 2);
+    }
+}
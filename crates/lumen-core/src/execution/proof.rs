@@ -1,10 +1,13 @@
 use crate::types::execution::*;
+use crate::execution::limits::ProofLimits;
+use crate::execution::node_cache::TrieNodeCache;
+use crate::types::uint::U256;
 use thiserror::Error;
 use tiny_keccak::{Hasher, Keccak};
 
 /// Errors during Merkle-Patricia trie proof verification.
 /// Each variant is specific enough to diagnose exactly what went wrong.
-#[derive(Debug, Error)]
+#[derive(Clone, Debug, Error)]
 pub enum ProofError {
     #[error("Empty proof: no trie nodes provided")]
     EmptyProof,
@@ -32,6 +35,30 @@ pub enum ProofError {
 
     #[error("Invalid value encoding: {reason}")]
     InvalidValueEncoding { reason: String },
+
+    #[error("Unsupported receipt type 0x{type_byte:02x}: not a known EIP-2718 transaction type")]
+    UnsupportedReceiptType { type_byte: u8 },
+
+    #[error("Unsupported transaction type 0x{type_byte:02x}: not a known EIP-2718 transaction type")]
+    UnsupportedTransactionType { type_byte: u8 },
+
+    #[error("Proof has {count} nodes, more than the limit of {max}")]
+    TooManyProofNodes { count: usize, max: usize },
+
+    #[error("Proof node {index} is {size} bytes, more than the limit of {max}")]
+    ProofNodeTooLarge { index: usize, size: usize, max: usize },
+
+    #[error("Proof is {total} bytes in total, more than the limit of {max}")]
+    ProofTooLarge { total: usize, max: usize },
+
+    #[error("Proof has {count} unused nodes after the end of the key path")]
+    UnusedProofNodes { count: usize },
+
+    #[error("Invalid block header RLP encoding: {reason}")]
+    InvalidHeaderEncoding { reason: String },
+
+    #[error("Block header #{number} hashes to {computed}, expected {expected}")]
+    HeaderHashMismatch { number: u64, computed: String, expected: String },
 }
 
 /// Compute keccak256 hash of data.
@@ -53,6 +80,39 @@ pub fn verify_account_proof(
     state_root: [u8; 32],
     address: [u8; 20],
     proof: &AccountProof,
+) -> Result<AccountState, ProofError> {
+    account_proof_inner(state_root, address, proof, &ProofLimits::STATE, None)
+}
+
+/// Like [`verify_account_proof`], with caller-chosen proof size limits.
+pub fn verify_account_proof_with_limits(
+    state_root: [u8; 32],
+    address: [u8; 20],
+    proof: &AccountProof,
+    limits: &ProofLimits,
+) -> Result<AccountState, ProofError> {
+    account_proof_inner(state_root, address, proof, limits, None)
+}
+
+/// Like [`verify_account_proof`], but skips re-hashing trie nodes already
+/// verified through `cache`. The cache is bound to `state_root`, so it is
+/// cleared automatically when proofs for a new block arrive.
+pub fn verify_account_proof_cached(
+    state_root: [u8; 32],
+    address: [u8; 20],
+    proof: &AccountProof,
+    cache: &mut TrieNodeCache,
+) -> Result<AccountState, ProofError> {
+    cache.bind_root(state_root);
+    account_proof_inner(state_root, address, proof, &ProofLimits::STATE, Some(cache))
+}
+
+fn account_proof_inner(
+    state_root: [u8; 32],
+    address: [u8; 20],
+    proof: &AccountProof,
+    limits: &ProofLimits,
+    cache: Option<&mut TrieNodeCache>,
 ) -> Result<AccountState, ProofError> {
     if proof.proof.is_empty() {
         return Err(ProofError::EmptyProof);
@@ -62,7 +122,7 @@ pub fn verify_account_proof(
     let key = keccak256(&address);
 
     // Verify the proof path against the state root
-    let value_bytes = verify_merkle_patricia_proof(&state_root, &key, &proof.proof)?;
+    let value_bytes = verify_merkle_patricia_proof(&state_root, &key, &proof.proof, limits, cache)?;
 
     match value_bytes {
         Some(rlp_bytes) => {
@@ -84,11 +144,43 @@ pub fn verify_storage_proof(
     storage_root: [u8; 32],
     slot: [u8; 32],
     proof: &StorageProof,
-) -> Result<[u8; 32], ProofError> {
+) -> Result<U256, ProofError> {
+    storage_proof_inner(storage_root, slot, proof, &ProofLimits::STATE, None)
+}
+
+/// Like [`verify_storage_proof`], with caller-chosen proof size limits.
+pub fn verify_storage_proof_with_limits(
+    storage_root: [u8; 32],
+    slot: [u8; 32],
+    proof: &StorageProof,
+    limits: &ProofLimits,
+) -> Result<U256, ProofError> {
+    storage_proof_inner(storage_root, slot, proof, limits, None)
+}
+
+/// Like [`verify_storage_proof`], but skips re-hashing trie nodes already
+/// verified through `cache`. Storage tries live under the state root the
+/// cache is bound to, so this does not rebind it.
+pub fn verify_storage_proof_cached(
+    storage_root: [u8; 32],
+    slot: [u8; 32],
+    proof: &StorageProof,
+    cache: &mut TrieNodeCache,
+) -> Result<U256, ProofError> {
+    storage_proof_inner(storage_root, slot, proof, &ProofLimits::STATE, Some(cache))
+}
+
+fn storage_proof_inner(
+    storage_root: [u8; 32],
+    slot: [u8; 32],
+    proof: &StorageProof,
+    limits: &ProofLimits,
+    cache: Option<&mut TrieNodeCache>,
+) -> Result<U256, ProofError> {
     if proof.proof.is_empty() {
         // Empty proof with empty storage root means the slot is zero
         if storage_root == AccountState::EMPTY_STORAGE_ROOT {
-            return Ok([0u8; 32]);
+            return Ok(U256::ZERO);
         }
         return Err(ProofError::EmptyProof);
     }
@@ -96,7 +188,7 @@ pub fn verify_storage_proof(
     // The key in the storage trie is keccak256(slot)
     let key = keccak256(&slot);
 
-    let value_bytes = verify_merkle_patricia_proof(&storage_root, &key, &proof.proof)?;
+    let value_bytes = verify_merkle_patricia_proof(&storage_root, &key, &proof.proof, limits, cache)?;
 
     match value_bytes {
         Some(rlp_bytes) => {
@@ -105,133 +197,124 @@ pub fn verify_storage_proof(
         }
         None => {
             // Slot not in trie — value is zero (valid proof of non-existence)
-            Ok([0u8; 32])
+            Ok(U256::ZERO)
         }
     }
 }
 
-/// Core Merkle-Patricia trie proof verification.
+/// Verify many storage slots of the same contract in one call.
+///
+/// Each proof is checked against `storage_root` using its own `key`, and
+/// independently of the others: result `i` belongs to `proofs[i]`, so one
+/// malformed proof does not prevent the remaining slots from verifying.
+/// Trie nodes shared between the proofs are only hashed once.
+pub fn verify_storage_proofs(
+    storage_root: [u8; 32],
+    proofs: &[StorageProof],
+) -> Vec<Result<U256, ProofError>> {
+    let mut cache = TrieNodeCache::default();
+    proofs
+        .iter()
+        .map(|proof| verify_storage_proof_cached(storage_root, proof.key, proof, &mut cache))
+        .collect()
+}
+
+/// Core Merkle-Patricia trie proof verification, shared by the state,
+/// storage and receipts tries.
 ///
-/// Walks the trie from root to leaf following the proof nodes.
-/// At each step, verifies that the hash of the current node matches
-/// what the parent node claims.
+/// Walks the trie from the root following `key`'s nibbles. Every node reached
+/// through a hash reference must be the next proof node and must hash to that
+/// reference (the root included, whatever its size); nodes shorter than 32
+/// bytes are embedded in their parent and are decoded in place. A proof that
+/// carries nodes beyond the end of the path is rejected.
 ///
 /// Returns Some(value) if the key exists, None for proof of non-existence.
-fn verify_merkle_patricia_proof(
+pub(crate) fn verify_merkle_patricia_proof(
     expected_root: &[u8; 32],
-    key: &[u8; 32],
+    key: &[u8],
     proof_nodes: &[Vec<u8>],
+    limits: &ProofLimits,
+    mut cache: Option<&mut TrieNodeCache>,
 ) -> Result<Option<Vec<u8>>, ProofError> {
     if proof_nodes.is_empty() {
         return Err(ProofError::EmptyProof);
     }
+    limits.check(proof_nodes)?;
 
-    // Convert key to nibbles (each byte = 2 nibbles)
+    // The empty trie: its root is keccak256(rlp("")) and nothing is in it.
+    if proof_nodes[0] == [EMPTY_STRING_CODE] {
+        check_node_hash(&proof_nodes[0], expected_root, None, cache.as_deref_mut())?;
+        return finish(None, 1, proof_nodes.len());
+    }
+
+    let node_hashes = prehash_nodes(proof_nodes, cache.is_some());
     let nibbles = bytes_to_nibbles(key);
     let mut nibble_index: usize = 0;
+    let mut next_node: usize = 0;
+    let mut reference = NodeRef::Hash(*expected_root);
+
+    // Each step consumes at least one nibble, so depth is bounded by the key length.
+    for depth in 0..=nibbles.len() {
+        let node: &[u8] = match reference {
+            NodeRef::Hash(hash) => {
+                let node = proof_nodes
+                    .get(next_node)
+                    .ok_or(ProofError::IncompleteProof { depth })?;
+                let computed = node_hashes.as_ref().map(|hashes| hashes[next_node]);
+                check_node_hash(node, &hash, computed, cache.as_deref_mut())?;
+                next_node += 1;
+                node
+            }
+            NodeRef::Embedded(node) => node,
+        };
+        let index = next_node.saturating_sub(1);
 
-    // Verify the first node hashes to the expected root
-    let first_hash = keccak256(&proof_nodes[0]);
-    // For very short nodes (< 32 bytes), the node is embedded directly, not hashed
-    if proof_nodes[0].len() >= 32 && first_hash != *expected_root {
-        return Err(ProofError::RootMismatch {
-            computed: hex::encode(first_hash),
-            expected: hex::encode(expected_root),
-        });
-    }
-
-    for (depth, node_rlp) in proof_nodes.iter().enumerate() {
-        let items = decode_rlp_list(node_rlp).map_err(|e| ProofError::InvalidRlp {
-            index: depth,
-            reason: e,
-        })?;
+        let items = split_rlp_list(node).map_err(|e| ProofError::InvalidRlp { index, reason: e })?;
+        let remaining = &nibbles[nibble_index..];
 
-        match items.len() {
+        let child = match items.len() {
             17 => {
                 // Branch node: 16 children + value
-                if nibble_index >= nibbles.len() {
+                let Some((&child_index, _)) = remaining.split_first() else {
                     // We've consumed all nibbles — the value is in position 16
-                    let value = &items[16];
-                    if value.is_empty() {
-                        return Ok(None);
-                    }
-                    return Ok(Some(value.clone()));
-                }
-
-                let child_index = nibbles[nibble_index] as usize;
+                    let value = decode_rlp_bytes(items[16])
+                        .map_err(|e| ProofError::InvalidRlp { index, reason: e })?;
+                    let value = (!value.is_empty()).then_some(value);
+                    return finish(value, next_node, proof_nodes.len());
+                };
                 nibble_index += 1;
-
-                if depth + 1 < proof_nodes.len() {
-                    // Verify the child hash matches
-                    let child = &items[child_index];
-                    if child.len() == 32 {
-                        let next_hash = keccak256(&proof_nodes[depth + 1]);
-                        if proof_nodes[depth + 1].len() >= 32 {
-                            let mut expected = [0u8; 32];
-                            expected.copy_from_slice(child);
-                            if next_hash != expected {
-                                return Err(ProofError::RootMismatch {
-                                    computed: hex::encode(next_hash),
-                                    expected: hex::encode(expected),
-                                });
-                            }
-                        }
-                    }
-                } else {
-                    // Last node in proof — check the child reference
-                    let child = &items[child_index];
-                    if child.is_empty() {
-                        return Ok(None); // Key not in trie
-                    }
-                    // The child contains the value inline
-                    return Ok(Some(child.clone()));
-                }
+                items[child_index as usize]
             }
             2 => {
                 // Extension or leaf node
+                let path = decode_rlp_bytes(items[0])
+                    .map_err(|e| ProofError::InvalidRlp { index, reason: e })?;
                 let (prefix_nibbles, is_leaf) =
-                    decode_compact_path(&items[0]).map_err(|e| ProofError::InvalidRlp {
-                        index: depth,
-                        reason: e,
-                    })?;
+                    decode_compact_path(&path).map_err(|e| ProofError::InvalidRlp { index, reason: e })?;
 
                 if is_leaf {
-                    // Leaf node: check if remaining nibbles match
-                    let remaining = &nibbles[nibble_index..];
-                    if remaining == prefix_nibbles.as_slice() {
-                        let value = &items[1];
-                        if value.is_empty() {
-                            return Ok(None);
-                        }
-                        return Ok(Some(value.clone()));
-                    } else {
-                        // Key doesn't match — proof of non-existence
-                        return Ok(None);
-                    }
-                } else {
-                    // Extension node: consume the shared prefix
-                    let remaining = &nibbles[nibble_index..];
-                    if !remaining.starts_with(&prefix_nibbles) {
-                        return Ok(None); // Path diverges — key not in trie
-                    }
-                    nibble_index += prefix_nibbles.len();
-
-                    // Verify the next node hash
-                    if depth + 1 < proof_nodes.len() {
-                        let child_ref = &items[1];
-                        if child_ref.len() == 32 && proof_nodes[depth + 1].len() >= 32 {
-                            let next_hash = keccak256(&proof_nodes[depth + 1]);
-                            let mut expected = [0u8; 32];
-                            expected.copy_from_slice(child_ref);
-                            if next_hash != expected {
-                                return Err(ProofError::RootMismatch {
-                                    computed: hex::encode(next_hash),
-                                    expected: hex::encode(expected),
-                                });
-                            }
-                        }
+                    // Leaf node: the key exists only if the remaining nibbles match exactly
+                    if remaining != prefix_nibbles.as_slice() {
+                        return finish(None, next_node, proof_nodes.len());
                     }
+                    let value = decode_rlp_bytes(items[1])
+                        .map_err(|e| ProofError::InvalidRlp { index, reason: e })?;
+                    let value = (!value.is_empty()).then_some(value);
+                    return finish(value, next_node, proof_nodes.len());
                 }
+
+                // Extension node: consume the shared prefix
+                if prefix_nibbles.is_empty() {
+                    return Err(ProofError::InvalidNodeType {
+                        depth,
+                        node_type: "extension with empty path".to_string(),
+                    });
+                }
+                if !remaining.starts_with(&prefix_nibbles) {
+                    return finish(None, next_node, proof_nodes.len()); // Path diverges
+                }
+                nibble_index += prefix_nibbles.len();
+                items[1]
             }
             _ => {
                 return Err(ProofError::InvalidNodeType {
@@ -239,7 +322,20 @@ fn verify_merkle_patricia_proof(
                     node_type: format!("{}-element list", items.len()),
                 });
             }
-        }
+        };
+
+        reference = match NodeRef::decode(child) {
+            Some(next) => next,
+            None if child == [EMPTY_STRING_CODE] => {
+                return finish(None, next_node, proof_nodes.len()); // Key not in trie
+            }
+            None => {
+                return Err(ProofError::InvalidRlp {
+                    index,
+                    reason: format!("Invalid child reference of {} bytes", child.len()),
+                });
+            }
+        };
     }
 
     Err(ProofError::IncompleteProof {
@@ -247,6 +343,110 @@ fn verify_merkle_patricia_proof(
     })
 }
 
+/// RLP encoding of the empty string, used for empty trie slots.
+const EMPTY_STRING_CODE: u8 = 0x80;
+
+/// How a trie node refers to a child.
+enum NodeRef<'a> {
+    /// By keccak256 hash; the child is the next proof node.
+    Hash([u8; 32]),
+    /// Inline: the child's RLP is shorter than 32 bytes and embedded in the parent.
+    Embedded(&'a [u8]),
+}
+
+impl<'a> NodeRef<'a> {
+    /// Classify a raw RLP child item. Returns None for anything that is
+    /// neither a 32-byte hash nor an embedded list shorter than 32 bytes.
+    fn decode(item: &'a [u8]) -> Option<Self> {
+        match item.first() {
+            Some(0xA0) if item.len() == 33 => {
+                let mut hash = [0u8; 32];
+                hash.copy_from_slice(&item[1..]);
+                Some(NodeRef::Hash(hash))
+            }
+            Some(&prefix) if prefix >= 0xC0 && item.len() < 32 => Some(NodeRef::Embedded(item)),
+            _ => None,
+        }
+    }
+}
+
+/// Return the traversal result, rejecting proofs with nodes past the end of the path.
+fn finish(
+    value: Option<Vec<u8>>,
+    used: usize,
+    total: usize,
+) -> Result<Option<Vec<u8>>, ProofError> {
+    if used < total {
+        return Err(ProofError::UnusedProofNodes {
+            count: total - used,
+        });
+    }
+    Ok(value)
+}
+
+/// Split an RLP list into the raw encodings of its items (prefixes kept),
+/// so strings and embedded lists can be told apart.
+fn split_rlp_list(data: &[u8]) -> Result<Vec<&[u8]>, String> {
+    if data.is_empty() {
+        return Err("Empty RLP data".to_string());
+    }
+    let (payload, consumed) = decode_rlp_list_payload(data)?;
+    if consumed != data.len() {
+        return Err(format!(
+            "Trailing bytes after node: {} of {} used",
+            consumed,
+            data.len()
+        ));
+    }
+
+    let mut items = Vec::new();
+    let mut offset = 0;
+    while offset < payload.len() {
+        let (_, len) = decode_rlp_item(&payload[offset..])?;
+        items.push(&payload[offset..offset + len]);
+        offset += len;
+    }
+    Ok(items)
+}
+
+/// Hashes of every proof node, computed up front two at a time with the
+/// `simd` feature. Not worth it with a node cache, which skips most hashes.
+#[cfg(feature = "simd")]
+fn prehash_nodes(proof_nodes: &[Vec<u8>], cached: bool) -> Option<Vec<[u8; 32]>> {
+    (!cached).then(|| crate::simd::keccak256_batch(proof_nodes))
+}
+
+#[cfg(not(feature = "simd"))]
+fn prehash_nodes(_proof_nodes: &[Vec<u8>], _cached: bool) -> Option<Vec<[u8; 32]>> {
+    None
+}
+
+/// Check that `node` hashes to `expected`, consulting the node cache first.
+/// `computed` is the node's hash if it is already known.
+fn check_node_hash(
+    node: &[u8],
+    expected: &[u8; 32],
+    computed: Option<[u8; 32]>,
+    mut cache: Option<&mut TrieNodeCache>,
+) -> Result<(), ProofError> {
+    if let Some(cache) = cache.as_deref_mut() {
+        if cache.lookup(expected, node) {
+            return Ok(());
+        }
+    }
+    let computed = computed.unwrap_or_else(|| keccak256(node));
+    if computed != *expected {
+        return Err(ProofError::RootMismatch {
+            computed: hex::encode(computed),
+            expected: hex::encode(expected),
+        });
+    }
+    if let Some(cache) = cache {
+        cache.insert(computed, node);
+    }
+    Ok(())
+}
+
 /// Decode an Ethereum account from RLP encoding.
 /// Account is RLP([nonce, balance, storageRoot, codeHash])
 fn decode_account_from_rlp(rlp_bytes: &[u8]) -> Result<AccountState, ProofError> {
@@ -261,7 +461,10 @@ fn decode_account_from_rlp(rlp_bytes: &[u8]) -> Result<AccountState, ProofError>
     }
 
     let nonce = decode_rlp_uint64(&items[0]);
-    let balance = decode_rlp_u256(&items[1]);
+    let balance =
+        decode_rlp_u256(&items[1]).ok_or_else(|| ProofError::InvalidAccountEncoding {
+            reason: format!("Balance too long: {} bytes", items[1].len()),
+        })?;
 
     let mut storage_root = [0u8; 32];
     if items[2].len() == 32 {
@@ -294,28 +497,21 @@ fn decode_account_from_rlp(rlp_bytes: &[u8]) -> Result<AccountState, ProofError>
 }
 
 /// Decode a storage value from RLP.
-fn decode_storage_value(rlp_bytes: &[u8]) -> Result<[u8; 32], ProofError> {
+fn decode_storage_value(rlp_bytes: &[u8]) -> Result<U256, ProofError> {
     let value = decode_rlp_bytes(rlp_bytes).map_err(|e| ProofError::InvalidValueEncoding {
         reason: e,
     })?;
 
-    let mut result = [0u8; 32];
-    if value.len() <= 32 {
-        // Right-align the value in 32 bytes (big-endian)
-        result[32 - value.len()..].copy_from_slice(&value);
-    } else {
-        return Err(ProofError::InvalidValueEncoding {
-            reason: format!("Storage value too long: {} bytes", value.len()),
-        });
-    }
-    Ok(result)
+    U256::from_be_slice(&value).ok_or_else(|| ProofError::InvalidValueEncoding {
+        reason: format!("Storage value too long: {} bytes", value.len()),
+    })
 }
 
 // --- RLP Decoding Helpers ---
 
-/// Convert a 32-byte array to nibbles (4 bits each).
-fn bytes_to_nibbles(bytes: &[u8; 32]) -> Vec<u8> {
-    let mut nibbles = Vec::with_capacity(64);
+/// Convert bytes to nibbles (4 bits each).
+pub fn bytes_to_nibbles(bytes: &[u8]) -> Vec<u8> {
+    let mut nibbles = Vec::with_capacity(bytes.len() * 2);
     for byte in bytes {
         nibbles.push(byte >> 4);
         nibbles.push(byte & 0x0F);
@@ -325,7 +521,7 @@ fn bytes_to_nibbles(bytes: &[u8; 32]) -> Vec<u8> {
 
 /// Decode compact (hex-prefix) encoding used in trie nodes.
 /// Returns (nibbles, is_leaf).
-fn decode_compact_path(encoded: &[u8]) -> Result<(Vec<u8>, bool), String> {
+pub fn decode_compact_path(encoded: &[u8]) -> Result<(Vec<u8>, bool), String> {
     if encoded.is_empty() {
         return Ok((vec![], false));
     }
@@ -521,16 +717,9 @@ fn decode_rlp_uint64(data: &[u8]) -> u64 {
     result
 }
 
-/// Decode RLP-encoded U256 as 32-byte big-endian.
-fn decode_rlp_u256(data: &[u8]) -> [u8; 32] {
-    let mut result = [0u8; 32];
-    if data.is_empty() {
-        return result;
-    }
-    let start = 32usize.saturating_sub(data.len());
-    let len = data.len().min(32);
-    result[start..start + len].copy_from_slice(&data[..len]);
-    result
+/// Decode an RLP-encoded U256. Returns None if the payload exceeds 32 bytes.
+fn decode_rlp_u256(data: &[u8]) -> Option<U256> {
+    U256::from_be_slice(data)
 }
 
 #[cfg(test)]
@@ -629,13 +818,10 @@ mod tests {
 
     #[test]
     fn test_rlp_decode_u256() {
-        let result = decode_rlp_u256(&[0x01]);
-        assert_eq!(result[31], 1);
-        assert_eq!(result[30], 0);
-
-        let result = decode_rlp_u256(&[0x01, 0x00]);
-        assert_eq!(result[31], 0);
-        assert_eq!(result[30], 1);
+        assert_eq!(decode_rlp_u256(&[]), Some(U256::ZERO));
+        assert_eq!(decode_rlp_u256(&[0x01]), Some(U256::from_u64(1)));
+        assert_eq!(decode_rlp_u256(&[0x01, 0x00]), Some(U256::from_u64(256)));
+        assert_eq!(decode_rlp_u256(&[0x01; 33]), None);
     }
 
     #[test]
@@ -648,4 +834,159 @@ mod tests {
         assert_eq!(items[1], vec![0x02]);
         assert_eq!(items[2], vec![0x03]);
     }
+
+    #[test]
+    fn test_verify_storage_proofs_per_slot_results() {
+        let slot = |key: u8| StorageProof {
+            key: [key; 32],
+            value: U256::ZERO,
+            proof: vec![],
+        };
+        let proofs = vec![slot(1), slot(2)];
+
+        // Empty storage trie: every slot proves to zero without proof nodes.
+        let results = verify_storage_proofs(AccountState::EMPTY_STORAGE_ROOT, &proofs);
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().all(|r| matches!(r, Ok(v) if v.is_zero())));
+
+        // Non-empty trie: a missing proof fails for that slot only.
+        let mut proofs = proofs;
+        proofs[1].proof = vec![vec![0xC0]];
+        let results = verify_storage_proofs([0xAB; 32], &proofs);
+        assert!(matches!(results[0], Err(ProofError::EmptyProof)));
+        assert!(results[1].is_err());
+        assert!(!matches!(results[1], Err(ProofError::EmptyProof)));
+    }
+
+    #[test]
+    fn test_cached_account_proofs_skip_shared_nodes() {
+        use crate::execution::trie_builder::TrieBuilder;
+
+        let mut trie = TrieBuilder::new();
+        for i in 1..=32u8 {
+            let account = AccountState {
+                nonce: i as u64,
+                balance: U256::from_u64(i as u64),
+                storage_root: AccountState::EMPTY_STORAGE_ROOT,
+                code_hash: AccountState::EMPTY_CODE_HASH,
+            };
+            trie.insert_account(&[i; 20], &account);
+        }
+        let root = trie.root();
+
+        let mut cache = TrieNodeCache::default();
+        for i in 1..=32u8 {
+            let proof = trie.account_proof(&[i; 20]);
+            let account = verify_account_proof_cached(root, [i; 20], &proof, &mut cache).unwrap();
+            assert_eq!(account.nonce, i as u64);
+        }
+        // Every proof after the first reuses at least the root node.
+        assert!(cache.hits() >= 31);
+
+        // A tampered node under a cached hash is still rejected.
+        let mut proof = trie.account_proof(&[5; 20]);
+        let len = proof.proof[0].len();
+        proof.proof[0][len - 1] ^= 0x01;
+        assert!(matches!(
+            verify_account_proof_cached(root, [5; 20], &proof, &mut cache),
+            Err(ProofError::RootMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn test_oversized_proof_rejected_before_hashing() {
+        let proof = AccountProof {
+            address: [0; 20],
+            proof: vec![vec![0xC0]; ProofLimits::STATE.max_nodes + 1],
+            account: None,
+        };
+        assert!(matches!(
+            verify_account_proof([0; 32], [0; 20], &proof),
+            Err(ProofError::TooManyProofNodes { .. })
+        ));
+
+        let proof = StorageProof {
+            key: [0; 32],
+            value: U256::ZERO,
+            proof: vec![vec![0u8; ProofLimits::STATE.max_node_size + 1]],
+        };
+        assert!(matches!(
+            verify_storage_proof([1; 32], [0; 32], &proof),
+            Err(ProofError::ProofNodeTooLarge { index: 0, .. })
+        ));
+    }
+
+    #[test]
+    fn test_embedded_nodes_verify_in_place() {
+        use crate::execution::trie_builder::TrieBuilder;
+
+        // Tiny keys and values: every leaf is embedded in the root branch.
+        let mut trie = TrieBuilder::new();
+        for i in 1..=5u8 {
+            trie.insert(&[i << 4], &[i]);
+        }
+        let root = trie.root();
+        for i in 1..=5u8 {
+            let proof = trie.proof(&[i << 4]);
+            assert_eq!(proof.len(), 1, "leaves should be embedded in the root");
+            let value =
+                verify_merkle_patricia_proof(&root, &[i << 4], &proof, &ProofLimits::STATE, None)
+                    .unwrap();
+            assert_eq!(value, Some(vec![i]));
+        }
+
+        let absent = trie.proof(&[0xF0]);
+        assert_eq!(
+            verify_merkle_patricia_proof(&root, &[0xF0], &absent, &ProofLimits::STATE, None)
+                .unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_short_root_node_still_hash_checked() {
+        use crate::execution::trie_builder::TrieBuilder;
+
+        let mut trie = TrieBuilder::new();
+        trie.insert(&[0x01], &[0x02]);
+        let proof = trie.proof(&[0x01]);
+        assert!(proof[0].len() < 32);
+        assert!(matches!(
+            verify_merkle_patricia_proof(&[0xAB; 32], &[0x01], &proof, &ProofLimits::STATE, None),
+            Err(ProofError::RootMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn test_trailing_proof_nodes_rejected() {
+        use crate::execution::trie_builder::TrieBuilder;
+
+        let mut trie = TrieBuilder::new();
+        for i in 1..=8u8 {
+            trie.insert_storage(&[i; 32], U256::from_u64(i as u64));
+        }
+        let mut proof = trie.storage_proof(&[1; 32], U256::from_u64(1));
+        proof.proof.push(proof.proof[0].clone());
+        assert!(matches!(
+            verify_storage_proof(trie.root(), [1; 32], &proof),
+            Err(ProofError::UnusedProofNodes { count: 1 })
+        ));
+    }
+
+    #[test]
+    fn test_truncated_proof_is_incomplete() {
+        use crate::execution::trie_builder::TrieBuilder;
+
+        let mut trie = TrieBuilder::new();
+        for i in 1..=64u8 {
+            trie.insert_storage(&[i; 32], U256::from_u64(i as u64));
+        }
+        let mut proof = trie.storage_proof(&[9; 32], U256::from_u64(9));
+        assert!(proof.proof.len() > 1);
+        proof.proof.pop();
+        assert!(matches!(
+            verify_storage_proof(trie.root(), [9; 32], &proof),
+            Err(ProofError::IncompleteProof { .. })
+        ));
+    }
 }
@@ -1,4 +1,8 @@
-use crate::execution::proof::{keccak256, ProofError};
+use crate::execution::limits::ProofLimits;
+use crate::execution::proof::{verify_merkle_patricia_proof, ProofError};
+use crate::execution::rlp;
+use crate::execution::transaction::verify_transaction_proof;
+use crate::execution::trie_builder::TrieBuilder;
 use crate::types::execution::*;
 
 /// Verify a transaction receipt proof against a known receipts root.
@@ -14,20 +18,27 @@ pub fn verify_receipt_proof(
     receipts_root: [u8; 32],
     tx_index: u64,
     proof: &ReceiptProof,
+) -> Result<TransactionReceipt, ProofError> {
+    verify_receipt_proof_with_limits(receipts_root, tx_index, proof, &ProofLimits::RECEIPTS)
+}
+
+/// Like [`verify_receipt_proof`], with caller-chosen proof size limits.
+pub fn verify_receipt_proof_with_limits(
+    receipts_root: [u8; 32],
+    tx_index: u64,
+    proof: &ReceiptProof,
+    limits: &ProofLimits,
 ) -> Result<TransactionReceipt, ProofError> {
     if proof.proof.is_empty() {
         return Err(ProofError::EmptyProof);
     }
 
-    // The key in the receipts trie is RLP(tx_index)
-    let key_bytes = rlp_encode_uint(tx_index);
-    let _key_hash = keccak256(&key_bytes);
-
-    // For the receipts trie, the key is the RLP-encoded transaction index.
-    // We verify the Merkle proof against the receipts root.
-    // Since we're using the same MPT structure, we can reuse the proof logic.
+    // The key in the receipts trie is RLP(tx_index), used directly (not hashed).
+    // Otherwise the receipts trie is an ordinary MPT, so the same hardened
+    // traversal as the state trie applies.
+    let key_bytes = rlp::encode_u64(tx_index);
     let value_bytes =
-        verify_receipt_merkle_proof(&receipts_root, &key_bytes, &proof.proof)?;
+        verify_merkle_patricia_proof(&receipts_root, &key_bytes, &proof.proof, limits, None)?;
 
     match value_bytes {
         Some(rlp_bytes) => decode_receipt_from_rlp(&rlp_bytes),
@@ -37,127 +48,187 @@ pub fn verify_receipt_proof(
     }
 }
 
-/// Verify a Merkle proof in the receipts trie.
-/// The receipts trie uses the RLP-encoded tx index as the key (not hashed).
-fn verify_receipt_merkle_proof(
-    expected_root: &[u8; 32],
-    key: &[u8],
-    proof_nodes: &[Vec<u8>],
-) -> Result<Option<Vec<u8>>, ProofError> {
-    if proof_nodes.is_empty() {
-        return Err(ProofError::EmptyProof);
-    }
-
-    // Verify root hash
-    let first_hash = keccak256(&proof_nodes[0]);
-    if proof_nodes[0].len() >= 32 && first_hash != *expected_root {
-        return Err(ProofError::RootMismatch {
-            computed: hex::encode(first_hash),
-            expected: hex::encode(expected_root),
-        });
-    }
-
-    // Convert key to nibbles for trie traversal
-    let nibbles: Vec<u8> = key
+/// Encode a receipt as stored in the receipts trie: the EIP-2718 type byte
+/// (omitted for legacy receipts) followed by
+/// `RLP([status, cumulativeGasUsed, logsBloom, [[address, topics, data], ...]])`.
+pub fn encode_receipt(receipt: &TransactionReceipt) -> Vec<u8> {
+    let logs: Vec<Vec<u8>> = receipt
+        .logs
         .iter()
-        .flat_map(|b| vec![b >> 4, b & 0x0F])
+        .map(|log| {
+            let topics: Vec<Vec<u8>> = log.topics.iter().map(|t| rlp::encode_bytes(t)).collect();
+            rlp::encode_list(&[
+                rlp::encode_bytes(&log.address),
+                rlp::encode_list(&topics),
+                rlp::encode_bytes(&log.data),
+            ])
+        })
         .collect();
-    let mut nibble_index: usize = 0;
-
-    for (depth, node_rlp) in proof_nodes.iter().enumerate() {
-        let items =
-            crate::execution::proof::decode_rlp_list(node_rlp).map_err(|e| ProofError::InvalidRlp {
-                index: depth,
-                reason: e,
-            })?;
-
-        match items.len() {
-            17 => {
-                // Branch node
-                if nibble_index >= nibbles.len() {
-                    let value = &items[16];
-                    if value.is_empty() {
-                        return Ok(None);
-                    }
-                    return Ok(Some(value.clone()));
-                }
 
-                let child_index = nibbles[nibble_index] as usize;
-                nibble_index += 1;
-
-                if depth + 1 >= proof_nodes.len() {
-                    let child = &items[child_index];
-                    if child.is_empty() {
-                        return Ok(None);
-                    }
-                    return Ok(Some(child.clone()));
-                }
-            }
-            2 => {
-                // Extension or leaf
-                let (prefix_nibbles, is_leaf) = decode_compact_path_receipt(&items[0])?;
-
-                if is_leaf {
-                    let remaining = &nibbles[nibble_index..];
-                    if remaining == prefix_nibbles.as_slice() {
-                        return Ok(Some(items[1].clone()));
-                    } else {
-                        return Ok(None);
-                    }
-                } else {
-                    let remaining = &nibbles[nibble_index..];
-                    if !remaining.starts_with(&prefix_nibbles) {
-                        return Ok(None);
-                    }
-                    nibble_index += prefix_nibbles.len();
-                }
-            }
-            _ => {
-                return Err(ProofError::InvalidNodeType {
-                    depth,
-                    node_type: format!("{}-element list", items.len()),
-                });
-            }
+    let body = rlp::encode_list(&[
+        rlp::encode_u64(receipt.status as u64),
+        rlp::encode_u64(receipt.cumulative_gas_used),
+        rlp::encode_bytes(&receipt.logs_bloom),
+        rlp::encode_list(&logs),
+    ]);
+
+    match receipt.tx_type {
+        TransactionType::Legacy => body,
+        other => {
+            let mut out = Vec::with_capacity(body.len() + 1);
+            out.push(other.type_byte());
+            out.extend(body);
+            out
         }
     }
-
-    Err(ProofError::IncompleteProof {
-        depth: proof_nodes.len(),
-    })
 }
 
-/// Decode compact path for receipt trie nodes.
-fn decode_compact_path_receipt(encoded: &[u8]) -> Result<(Vec<u8>, bool), ProofError> {
-    if encoded.is_empty() {
-        return Ok((vec![], false));
+/// Rebuild a block's receipts trie from all of its receipts, in block order.
+///
+/// Returns the trie root and a proof for `tx_index`. The receipts usually
+/// come from an untrusted RPC (`eth_getBlockReceipts`), so the root must be
+/// compared against a verified `receipts_root` before the proof means anything.
+/// Returns `None` if `tx_index` is outside the block.
+pub fn build_receipt_proof(
+    receipts: &[TransactionReceipt],
+    tx_index: u64,
+) -> Option<([u8; 32], ReceiptProof)> {
+    if tx_index >= receipts.len() as u64 {
+        return None;
+    }
+
+    let mut trie = TrieBuilder::new();
+    for (index, receipt) in receipts.iter().enumerate() {
+        trie.insert(&rlp::encode_u64(index as u64), &encode_receipt(receipt));
     }
 
-    let first_nibble = encoded[0] >> 4;
-    let is_leaf = first_nibble >= 2;
-    let is_odd = first_nibble % 2 == 1;
+    let proof = ReceiptProof {
+        tx_index,
+        proof: trie.proof(&rlp::encode_u64(tx_index)),
+    };
+    Some((trie.root(), proof))
+}
 
-    let mut nibbles = Vec::new();
+/// Proofs needed to enrich a verified receipt.
+///
+/// Everything here is proven before it is used: the transaction against the
+/// block's transactions root, the earlier receipts against the same receipts
+/// root as the receipt itself. A value whose proofs are missing is left out
+/// of the [`VerifiedReceipt`] rather than taken on trust.
+#[derive(Clone, Debug, Default)]
+pub struct ReceiptContext<'a> {
+    /// The block's `transactionsRoot`, from the same verified execution
+    /// header as the receipts root, and a proof of the transaction at the
+    /// receipt's index against it. Gives the transaction hash.
+    pub transaction: Option<([u8; 32], &'a TransactionProof)>,
+    /// Proofs of every earlier receipt in the block, for indices
+    /// `0..tx_index` in order. The last gives the cumulative gas before this
+    /// transaction, and their logs the index of this receipt's first log.
+    /// Not needed for the first transaction (tx_index 0).
+    pub earlier_receipts: Option<&'a [ReceiptProof]>,
+}
 
-    if is_odd {
-        nibbles.push(encoded[0] & 0x0F);
-    }
+/// Verify a receipt proof and enrich it with transaction hash, log indices and
+/// per-transaction gas used, matching the standard RPC receipt shape.
+pub fn verify_receipt_proof_with_context(
+    receipts_root: [u8; 32],
+    tx_index: u64,
+    proof: &ReceiptProof,
+    context: &ReceiptContext,
+) -> Result<VerifiedReceipt, ProofError> {
+    let receipt = verify_receipt_proof(receipts_root, tx_index, proof)?;
+
+    let transaction_hash = match context.transaction {
+        Some((transactions_root, tx_proof)) => {
+            let tx = verify_transaction_proof(transactions_root, tx_index, tx_proof)?;
+            if tx.transaction.tx_type != receipt.tx_type {
+                return Err(ProofError::InvalidValueEncoding {
+                    reason: format!(
+                        "Transaction type 0x{:02x} does not match receipt type 0x{:02x}",
+                        tx.transaction.tx_type.type_byte(),
+                        receipt.tx_type.type_byte()
+                    ),
+                });
+            }
+            Some(tx.transaction_hash)
+        }
+        None => None,
+    };
 
-    for &byte in &encoded[1..] {
-        nibbles.push(byte >> 4);
-        nibbles.push(byte & 0x0F);
-    }
+    // (cumulative gas, log count) of the block before this transaction.
+    let before = match (tx_index, context.earlier_receipts) {
+        (0, _) => Some((0, 0)),
+        (_, Some(earlier)) => {
+            if earlier.len() as u64 != tx_index {
+                return Err(ProofError::InvalidValueEncoding {
+                    reason: format!("Expected {} earlier receipt proofs, got {}", tx_index, earlier.len()),
+                });
+            }
+            let mut before = (0, 0);
+            for (index, earlier_proof) in earlier.iter().enumerate() {
+                let earlier = verify_receipt_proof(receipts_root, index as u64, earlier_proof)?;
+                before = (earlier.cumulative_gas_used, before.1 + earlier.logs.len() as u64);
+            }
+            Some(before)
+        }
+        (_, None) => None,
+    };
 
-    Ok((nibbles, is_leaf))
+    let gas_used = match before {
+        Some((prev, _)) => Some(receipt.cumulative_gas_used.checked_sub(prev).ok_or_else(|| {
+            ProofError::InvalidValueEncoding {
+                reason: format!(
+                    "Cumulative gas {} is below previous receipt's {}",
+                    receipt.cumulative_gas_used, prev
+                ),
+            }
+        })?),
+        None => None,
+    };
+
+    let logs = before.map(|(_, first_log_index)| {
+        receipt
+            .logs
+            .iter()
+            .enumerate()
+            .map(|(i, log)| IndexedLog {
+                log_index: first_log_index + i as u64,
+                log: log.clone(),
+            })
+            .collect()
+    });
+
+    Ok(VerifiedReceipt {
+        receipt,
+        transaction_index: tx_index,
+        transaction_hash,
+        gas_used,
+        logs,
+    })
 }
 
 /// Decode a transaction receipt from RLP encoding.
-/// Post-EIP-2718, receipts may be typed (prefixed with a type byte).
+///
+/// Post-EIP-2718, receipts are either a legacy RLP list (first byte >= 0xC0)
+/// or `type_byte || rlp(receipt)`. Every assigned type shares the same
+/// 4-field payload; unassigned type bytes are rejected rather than guessed at.
 fn decode_receipt_from_rlp(data: &[u8]) -> Result<TransactionReceipt, ProofError> {
-    let rlp_data = if !data.is_empty() && data[0] <= 0x7F {
-        // Typed receipt: skip the type byte
-        &data[1..]
+    let first = *data.first().ok_or_else(|| ProofError::InvalidValueEncoding {
+        reason: "Empty receipt".to_string(),
+    })?;
+
+    let (tx_type, rlp_data) = if first >= 0xC0 {
+        (TransactionType::Legacy, data)
+    } else if first <= 0x7F {
+        // 0x00 is never a valid envelope (legacy receipts are bare lists)
+        let tx_type = TransactionType::from_type_byte(first)
+            .filter(|t| *t != TransactionType::Legacy)
+            .ok_or(ProofError::UnsupportedReceiptType { type_byte: first })?;
+        (tx_type, &data[1..])
     } else {
-        data
+        return Err(ProofError::InvalidValueEncoding {
+            reason: format!("Receipt must be a list or typed envelope, got prefix 0x{:02x}", first),
+        });
     };
 
     let items =
@@ -191,6 +262,7 @@ fn decode_receipt_from_rlp(data: &[u8]) -> Result<TransactionReceipt, ProofError
     let logs = decode_logs(&items[3])?;
 
     Ok(TransactionReceipt {
+        tx_type,
         status,
         cumulative_gas_used,
         logs_bloom,
@@ -258,26 +330,6 @@ fn decode_logs(data: &[u8]) -> Result<Vec<Log>, ProofError> {
 }
 
 /// RLP encode a uint.
-fn rlp_encode_uint(value: u64) -> Vec<u8> {
-    if value == 0 {
-        return vec![0x80]; // RLP empty string for zero
-    }
-    if value < 128 {
-        return vec![value as u8];
-    }
-
-    let bytes = value.to_be_bytes();
-    let start = bytes.iter().position(|&b| b != 0).unwrap_or(7);
-    let significant = &bytes[start..];
-    let len = significant.len();
-
-    let mut result = Vec::with_capacity(1 + len);
-    result.push(0x80 + len as u8);
-    result.extend_from_slice(significant);
-    result
-}
-
-/// Convert bytes to u64 (big-endian).
 fn bytes_to_u64(bytes: &[u8]) -> u64 {
     let mut result: u64 = 0;
     for &b in bytes {
@@ -289,14 +341,260 @@ fn bytes_to_u64(bytes: &[u8]) -> u64 {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::execution::proof::keccak256;
+
+    fn encode_test_receipt() -> Vec<u8> {
+        let mut payload = vec![0x01, 0x82, 0x52, 0x08, 0xB9, 0x01, 0x00];
+        payload.extend_from_slice(&[0u8; 256]);
+        payload.push(0xC0);
+        let mut encoded = vec![0xF9, (payload.len() >> 8) as u8, payload.len() as u8];
+        encoded.extend_from_slice(&payload);
+        encoded
+    }
+
+    #[test]
+    fn test_decode_legacy_receipt() {
+        let receipt = decode_receipt_from_rlp(&encode_test_receipt()).unwrap();
+        assert_eq!(receipt.tx_type, TransactionType::Legacy);
+        assert_eq!(receipt.status, 1);
+        assert_eq!(receipt.cumulative_gas_used, 21000);
+        assert!(receipt.logs.is_empty());
+    }
 
     #[test]
-    fn test_rlp_encode_uint() {
-        assert_eq!(rlp_encode_uint(0), vec![0x80]);
-        assert_eq!(rlp_encode_uint(1), vec![0x01]);
-        assert_eq!(rlp_encode_uint(127), vec![0x7F]);
-        assert_eq!(rlp_encode_uint(128), vec![0x81, 0x80]);
-        assert_eq!(rlp_encode_uint(256), vec![0x82, 0x01, 0x00]);
+    fn test_decode_typed_receipts() {
+        for (byte, expected) in [
+            (0x01, TransactionType::AccessList),
+            (0x02, TransactionType::DynamicFee),
+            (0x03, TransactionType::Blob),
+            (0x04, TransactionType::SetCode),
+        ] {
+            let mut data = vec![byte];
+            data.extend_from_slice(&encode_test_receipt());
+            let receipt = decode_receipt_from_rlp(&data).unwrap();
+            assert_eq!(receipt.tx_type, expected);
+            assert_eq!(receipt.tx_type.type_byte(), byte);
+            assert_eq!(receipt.cumulative_gas_used, 21000);
+        }
+    }
+
+    #[test]
+    fn test_decode_unknown_receipt_type_rejected() {
+        for byte in [0x00, 0x05, 0x7F] {
+            let mut data = vec![byte];
+            data.extend_from_slice(&encode_test_receipt());
+            assert!(matches!(
+                decode_receipt_from_rlp(&data),
+                Err(ProofError::UnsupportedReceiptType { type_byte }) if type_byte == byte
+            ));
+        }
+    }
+
+    /// Single-receipt receipts trie: the root node is a leaf for key rlp(0).
+    fn single_receipt_proof(receipt: &[u8]) -> ([u8; 32], ReceiptProof) {
+        let mut value = vec![0xB9, (receipt.len() >> 8) as u8, receipt.len() as u8];
+        value.extend_from_slice(receipt);
+        let mut payload = vec![0x82, 0x20, 0x80];
+        payload.extend_from_slice(&value);
+        let mut node = vec![0xF9, (payload.len() >> 8) as u8, payload.len() as u8];
+        node.extend_from_slice(&payload);
+        (
+            keccak256(&node),
+            ReceiptProof {
+                tx_index: 0,
+                proof: vec![node],
+            },
+        )
+    }
+
+    /// A block of three EIP-1559 transfers, 21000 gas each, the second
+    /// with two logs and the third with one: its transactions and receipts
+    /// roots, envelopes and receipts.
+    fn transfer_block() -> ([u8; 32], Vec<Vec<u8>>, [u8; 32], Vec<TransactionReceipt>) {
+        use crate::execution::transaction::build_transaction_proof;
+        let envelopes: Vec<Vec<u8>> = (0..3u64)
+            .map(|nonce| {
+                let mut envelope = vec![0x02];
+                envelope.extend(rlp::encode_list(&[
+                    rlp::encode_u64(1),
+                    rlp::encode_u64(nonce),
+                    rlp::encode_u64(1_000_000_000),
+                    rlp::encode_u64(30_000_000_000),
+                    rlp::encode_u64(21_000),
+                    rlp::encode_bytes(&[0x11; 20]),
+                    rlp::encode_u64(5),
+                    rlp::encode_bytes(&[]),
+                    rlp::encode_list(&[]),
+                    rlp::encode_u64(1),
+                    rlp::encode_bytes(&[0xAA; 32]),
+                    rlp::encode_bytes(&[0xBB; 32]),
+                ]));
+                envelope
+            })
+            .collect();
+        let log = Log { address: [0xAA; 20], topics: vec![[0x01; 32]], data: vec![] };
+        let receipts: Vec<TransactionReceipt> = [0, 2, 1]
+            .iter()
+            .enumerate()
+            .map(|(i, &logs)| TransactionReceipt {
+                tx_type: TransactionType::DynamicFee,
+                status: 1,
+                cumulative_gas_used: 21000 * (i as u64 + 1),
+                logs_bloom: [0; 256],
+                logs: vec![log.clone(); logs],
+            })
+            .collect();
+        let transactions_root = build_transaction_proof(&envelopes, 0).unwrap().0;
+        let receipts_root = build_receipt_proof(&receipts, 0).unwrap().0;
+        (transactions_root, envelopes, receipts_root, receipts)
+    }
+
+    #[test]
+    fn test_receipt_context_enrichment() {
+        use crate::execution::transaction::build_transaction_proof;
+        let (transactions_root, envelopes, receipts_root, receipts) = transfer_block();
+        let (_, proof) = build_receipt_proof(&receipts, 2).unwrap();
+        let (_, tx_proof) = build_transaction_proof(&envelopes, 2).unwrap();
+        let earlier: Vec<ReceiptProof> = (0..2).map(|i| build_receipt_proof(&receipts, i).unwrap().1).collect();
+
+        let context = ReceiptContext {
+            transaction: Some((transactions_root, &tx_proof)),
+            earlier_receipts: Some(&earlier),
+        };
+        let verified = verify_receipt_proof_with_context(receipts_root, 2, &proof, &context).unwrap();
+        assert_eq!(verified.transaction_index, 2);
+        assert_eq!(verified.transaction_hash, Some(keccak256(&envelopes[2])));
+        assert_eq!(verified.gas_used, Some(21000));
+        let logs = verified.logs.unwrap();
+        assert_eq!(logs.iter().map(|log| log.log_index).collect::<Vec<_>>(), [2]);
+
+        // Without proofs only the first receipt's gas and log indices are known.
+        let bare = verify_receipt_proof_with_context(receipts_root, 2, &proof, &ReceiptContext::default()).unwrap();
+        assert_eq!((bare.transaction_hash, bare.gas_used, bare.logs), (None, None, None));
+        let (_, first) = build_receipt_proof(&receipts, 0).unwrap();
+        let first = verify_receipt_proof_with_context(receipts_root, 0, &first, &ReceiptContext::default()).unwrap();
+        assert_eq!((first.gas_used, first.logs), (Some(21000), Some(vec![])));
+    }
+
+    #[test]
+    fn test_receipt_context_rejects_unproven_inputs() {
+        use crate::execution::transaction::build_transaction_proof;
+        let (transactions_root, envelopes, receipts_root, receipts) = transfer_block();
+        let (_, proof) = build_receipt_proof(&receipts, 2).unwrap();
+        let check = |context: &ReceiptContext| verify_receipt_proof_with_context(receipts_root, 2, &proof, context);
+
+        // The transaction at another index, or against another root.
+        let (_, other_tx) = build_transaction_proof(&envelopes, 1).unwrap();
+        assert!(check(&ReceiptContext { transaction: Some((transactions_root, &other_tx)), ..Default::default() })
+            .is_err());
+        let (_, tx_proof) = build_transaction_proof(&envelopes, 2).unwrap();
+        assert!(check(&ReceiptContext { transaction: Some(([0xEE; 32], &tx_proof)), ..Default::default() }).is_err());
+        // A receipt whose type is not its transaction's.
+        let mut legacy = receipts.clone();
+        legacy[2].tx_type = TransactionType::Legacy;
+        let (legacy_root, legacy_proof) = build_receipt_proof(&legacy, 2).unwrap();
+        let context = ReceiptContext { transaction: Some((transactions_root, &tx_proof)), ..Default::default() };
+        assert!(verify_receipt_proof_with_context(legacy_root, 2, &legacy_proof, &context).is_err());
+
+        // Earlier receipts out of order, missing one, or of another block.
+        let earlier: Vec<ReceiptProof> = (0..2).map(|i| build_receipt_proof(&receipts, i).unwrap().1).collect();
+        let swapped = [earlier[1].clone(), earlier[0].clone()];
+        assert!(check(&ReceiptContext { earlier_receipts: Some(&swapped), ..Default::default() }).is_err());
+        assert!(check(&ReceiptContext { earlier_receipts: Some(&earlier[1..]), ..Default::default() }).is_err());
+        let mut other_block = receipts.clone();
+        other_block[0].cumulative_gas_used = 1;
+        let forged: Vec<ReceiptProof> = (0..2).map(|i| build_receipt_proof(&other_block, i).unwrap().1).collect();
+        assert!(check(&ReceiptContext { earlier_receipts: Some(&forged), ..Default::default() }).is_err());
+    }
+
+    #[test]
+    fn test_receipt_proof_limits_enforced() {
+        let (root, proof) = single_receipt_proof(&encode_test_receipt());
+        assert!(verify_receipt_proof(root, 0, &proof).is_ok());
+
+        let tight = ProofLimits {
+            max_node_size: proof.proof[0].len() - 1,
+            ..ProofLimits::RECEIPTS
+        };
+        assert!(matches!(
+            verify_receipt_proof_with_limits(root, 0, &proof, &tight),
+            Err(ProofError::ProofNodeTooLarge { index: 0, .. })
+        ));
+    }
+
+    #[test]
+    fn test_receipt_proofs_from_multi_receipt_trie() {
+        // Receipts trie keys are rlp(index), which puts index 0 (0x80) after 1..=127.
+        let mut trie = TrieBuilder::new();
+        for i in 0..40u64 {
+            let mut typed = vec![0x02];
+            typed.extend_from_slice(&encode_test_receipt());
+            let receipt = if i % 2 == 0 { encode_test_receipt() } else { typed };
+            trie.insert(&rlp::encode_u64(i), &receipt);
+        }
+        let root = trie.root();
+
+        for i in 0..40u64 {
+            let proof = ReceiptProof {
+                tx_index: i,
+                proof: trie.proof(&rlp::encode_u64(i)),
+            };
+            let receipt = verify_receipt_proof(root, i, &proof).unwrap();
+            let expected = if i % 2 == 0 {
+                TransactionType::Legacy
+            } else {
+                TransactionType::DynamicFee
+            };
+            assert_eq!(receipt.tx_type, expected);
+        }
+
+        // A proof for one index cannot be replayed for another.
+        let proof = ReceiptProof {
+            tx_index: 3,
+            proof: trie.proof(&rlp::encode_u64(3)),
+        };
+        assert!(verify_receipt_proof(root, 4, &proof).is_err());
+    }
+
+    #[test]
+    fn test_encode_receipt_round_trip() {
+        let receipt = TransactionReceipt {
+            tx_type: TransactionType::DynamicFee,
+            status: 0,
+            cumulative_gas_used: 1_234_567,
+            logs_bloom: [0x11; 256],
+            logs: vec![Log {
+                address: [0xAA; 20],
+                topics: vec![[0x01; 32], [0x02; 32]],
+                data: vec![0xDE, 0xAD],
+            }],
+        };
+        let encoded = encode_receipt(&receipt);
+        assert_eq!(encoded[0], 0x02);
+        assert_eq!(decode_receipt_from_rlp(&encoded).unwrap(), receipt);
+
+        let legacy = decode_receipt_from_rlp(&encode_test_receipt()).unwrap();
+        assert_eq!(encode_receipt(&legacy), encode_test_receipt());
+    }
+
+    #[test]
+    fn test_build_receipt_proof() {
+        let base = decode_receipt_from_rlp(&encode_test_receipt()).unwrap();
+        let receipts: Vec<TransactionReceipt> = (0..5u64)
+            .map(|i| TransactionReceipt {
+                cumulative_gas_used: 21000 * (i + 1),
+                ..base.clone()
+            })
+            .collect();
+
+        let (root, proof) = build_receipt_proof(&receipts, 3).unwrap();
+        assert_eq!(verify_receipt_proof(root, 3, &proof).unwrap(), receipts[3]);
+        assert!(build_receipt_proof(&receipts, 5).is_none());
+
+        // Any change to any receipt in the block changes the root.
+        let mut tampered = receipts.clone();
+        tampered[0].status = 0;
+        assert_ne!(build_receipt_proof(&tampered, 3).unwrap().0, root);
     }
 
     #[test]
@@ -0,0 +1,78 @@
+//! RLP encoding for trie nodes, receipts and headers.
+//!
+//! Decoding lives next to the proof verifier, which needs it on every node;
+//! encoding is only needed when rebuilding a structure from untrusted parts
+//! and checking that it hashes to a verified root.
+
+use crate::types::uint::U256;
+
+/// RLP-encode a byte string.
+pub fn encode_bytes(bytes: &[u8]) -> Vec<u8> {
+    if bytes.len() == 1 && bytes[0] < 0x80 {
+        return bytes.to_vec();
+    }
+    let mut out = length_prefix(bytes.len(), 0x80);
+    out.extend_from_slice(bytes);
+    out
+}
+
+/// RLP-encode a list from already-encoded items.
+pub fn encode_list(items: &[Vec<u8>]) -> Vec<u8> {
+    let payload: Vec<u8> = items.concat();
+    let mut out = length_prefix(payload.len(), 0xC0);
+    out.extend(payload);
+    out
+}
+
+/// RLP-encode an integer as a minimal big-endian byte string (zero is `0x80`).
+pub fn encode_u64(value: u64) -> Vec<u8> {
+    encode_bytes(trim_leading_zeros(&value.to_be_bytes()))
+}
+
+/// RLP-encode a 256-bit integer as a minimal big-endian byte string.
+pub fn encode_u256(value: U256) -> Vec<u8> {
+    encode_bytes(trim_leading_zeros(&value.to_be_bytes()))
+}
+
+pub(crate) fn trim_leading_zeros(bytes: &[u8]) -> &[u8] {
+    let start = bytes.iter().position(|&b| b != 0).unwrap_or(bytes.len());
+    &bytes[start..]
+}
+
+fn length_prefix(len: usize, short_base: u8) -> Vec<u8> {
+    if len <= 55 {
+        vec![short_base + len as u8]
+    } else {
+        let len_bytes = trim_leading_zeros(&len.to_be_bytes()).to_vec();
+        let mut out = vec![short_base + 55 + len_bytes.len() as u8];
+        out.extend(len_bytes);
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_u64() {
+        assert_eq!(encode_u64(0), vec![0x80]);
+        assert_eq!(encode_u64(1), vec![0x01]);
+        assert_eq!(encode_u64(127), vec![0x7F]);
+        assert_eq!(encode_u64(128), vec![0x81, 0x80]);
+        assert_eq!(encode_u64(256), vec![0x82, 0x01, 0x00]);
+    }
+
+    #[test]
+    fn test_encode_long_string_and_list() {
+        let long = vec![0xAB; 56];
+        let encoded = encode_bytes(&long);
+        assert_eq!(&encoded[..2], &[0xB8, 56]);
+        assert_eq!(encoded.len(), 58);
+
+        assert_eq!(encode_list(&[]), vec![0xC0]);
+        let list = encode_list(std::slice::from_ref(&encoded));
+        assert_eq!(&list[..2], &[0xF8, 58]);
+        assert_eq!(&list[2..], encoded.as_slice());
+    }
+}
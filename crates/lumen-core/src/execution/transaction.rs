@@ -0,0 +1,361 @@
+use crate::execution::limits::ProofLimits;
+use crate::execution::proof::{decode_rlp_list, keccak256, verify_merkle_patricia_proof, ProofError};
+use crate::execution::rlp;
+use crate::execution::trie_builder::TrieBuilder;
+use crate::types::execution::*;
+use crate::types::uint::U256;
+
+/// Verify that a transaction is at `tx_index` in the block whose
+/// transactions root is `transactions_root`, and decode it.
+///
+/// The transactions root is the execution block header's `transactionsRoot`
+/// (a Merkle-Patricia root), not the payload header's SSZ
+/// `transactions_root`, so the header must first be verified against a
+/// trusted block hash.
+pub fn verify_transaction_proof(
+    transactions_root: [u8; 32],
+    tx_index: u64,
+    proof: &TransactionProof,
+) -> Result<VerifiedTransaction, ProofError> {
+    if proof.proof.is_empty() {
+        return Err(ProofError::EmptyProof);
+    }
+
+    // Keyed by RLP(tx_index), unhashed, exactly like the receipts trie.
+    let key_bytes = rlp::encode_u64(tx_index);
+    let envelope =
+        verify_merkle_patricia_proof(&transactions_root, &key_bytes, &proof.proof, &ProofLimits::TRANSACTIONS, None)?
+            .ok_or_else(|| ProofError::InvalidValueEncoding {
+                reason: format!("Transaction not found for tx_index {}", tx_index),
+            })?;
+
+    Ok(VerifiedTransaction {
+        transaction: decode_transaction(&envelope)?,
+        transaction_index: tx_index,
+        transaction_hash: keccak256(&envelope),
+    })
+}
+
+/// Rebuild a block's transactions trie from its EIP-2718 envelopes, in
+/// block order, returning the root and a proof for `tx_index`.
+/// Returns `None` if `tx_index` is outside the block.
+pub fn build_transaction_proof(
+    envelopes: &[Vec<u8>],
+    tx_index: u64,
+) -> Option<([u8; 32], TransactionProof)> {
+    if tx_index >= envelopes.len() as u64 {
+        return None;
+    }
+
+    let mut trie = TrieBuilder::new();
+    for (index, envelope) in envelopes.iter().enumerate() {
+        trie.insert(&rlp::encode_u64(index as u64), envelope);
+    }
+
+    let proof = TransactionProof {
+        tx_index,
+        proof: trie.proof(&rlp::encode_u64(tx_index)),
+    };
+    Some((trie.root(), proof))
+}
+
+/// Decode an EIP-2718 transaction envelope: a legacy RLP list, or
+/// `type_byte || rlp(payload)`.
+///
+/// Field positions per type (before the access list and signature):
+/// - legacy: `[nonce, gasPrice, gasLimit, to, value, data, v, r, s]`
+/// - 0x01: `[chainId, nonce, gasPrice, gasLimit, to, value, data, ...]`
+/// - 0x02, 0x03, 0x04: `[chainId, nonce, maxPriorityFeePerGas, maxFeePerGas, gasLimit, to, value, data, ...]`
+pub fn decode_transaction(envelope: &[u8]) -> Result<Transaction, ProofError> {
+    let first = *envelope.first().ok_or_else(|| invalid("Empty transaction".to_string()))?;
+
+    let (tx_type, payload) = if first >= 0xC0 {
+        (TransactionType::Legacy, envelope)
+    } else if first <= 0x7F {
+        let tx_type = TransactionType::from_type_byte(first)
+            .filter(|t| *t != TransactionType::Legacy)
+            .ok_or(ProofError::UnsupportedTransactionType { type_byte: first })?;
+        (tx_type, &envelope[1..])
+    } else {
+        return Err(invalid(format!(
+            "Transaction must be a list or typed envelope, got prefix 0x{:02x}",
+            first
+        )));
+    };
+
+    let items = decode_rlp_list(payload).map_err(|e| invalid(format!("Invalid transaction RLP: {}", e)))?;
+    let expected = match tx_type {
+        TransactionType::Legacy => 9,
+        TransactionType::AccessList => 11,
+        TransactionType::DynamicFee => 12,
+        TransactionType::SetCode => 13,
+        TransactionType::Blob => 14,
+    };
+    if items.len() != expected {
+        return Err(invalid(format!(
+            "Type 0x{:02x} transaction should have {} items, got {}",
+            tx_type.type_byte(),
+            expected,
+            items.len()
+        )));
+    }
+
+    // Typed transactions lead with the chain id; shift past it.
+    let (chain_id, fields) = match tx_type {
+        TransactionType::Legacy => (legacy_chain_id(&items[6])?, &items[..]),
+        _ => (Some(uint64(&items[0], "chainId")?), &items[1..]),
+    };
+
+    let (gas_price, max_priority_fee_per_gas, max_fee_per_gas, rest) = match tx_type {
+        TransactionType::Legacy | TransactionType::AccessList => {
+            (Some(uint256(&fields[1], "gasPrice")?), None, None, &fields[2..])
+        }
+        _ => (
+            None,
+            Some(uint256(&fields[1], "maxPriorityFeePerGas")?),
+            Some(uint256(&fields[2], "maxFeePerGas")?),
+            &fields[3..],
+        ),
+    };
+
+    let to = match rest[1].len() {
+        0 => None,
+        20 => Some(rest[1][..].try_into().expect("length checked")),
+        n => return Err(invalid(format!("to should be 20 bytes or empty, got {}", n))),
+    };
+    if to.is_none() && matches!(tx_type, TransactionType::Blob | TransactionType::SetCode) {
+        return Err(invalid(format!(
+            "Type 0x{:02x} transactions cannot create contracts",
+            tx_type.type_byte()
+        )));
+    }
+
+    Ok(Transaction {
+        tx_type,
+        chain_id,
+        nonce: uint64(&fields[0], "nonce")?,
+        gas_price,
+        max_fee_per_gas,
+        max_priority_fee_per_gas,
+        gas_limit: uint64(&rest[0], "gasLimit")?,
+        to,
+        value: uint256(&rest[2], "value")?,
+        input: rest[3].clone(),
+    })
+}
+
+/// Recover the address that signed an EIP-2718 transaction envelope.
+///
+/// The signed message is the envelope without its signature:
+/// `type_byte || rlp(fields)` for typed transactions, and for legacy ones
+/// `rlp([nonce, gasPrice, gasLimit, to, value, data])`, extended with
+/// `[chainId, 0, 0]` under EIP-155. Signatures with `s` in the upper half of
+/// the curve order are rejected, as EIP-2 requires.
+pub fn recover_sender(envelope: &[u8]) -> Result<[u8; 20], ProofError> {
+    use k256::ecdsa::{RecoveryId, Signature, VerifyingKey};
+
+    let tx = decode_transaction(envelope)?;
+    let (prefix, payload) = match tx.tx_type {
+        TransactionType::Legacy => (None, envelope),
+        _ => (Some(envelope[0]), &envelope[1..]),
+    };
+    let items = decode_rlp_list(payload).map_err(|e| invalid(format!("Invalid transaction RLP: {}", e)))?;
+    let (fields, signature) = items.split_at(items.len() - 3);
+
+    // Nested lists come back from decoding still encoded; everything else is re-encoded.
+    let list_fields: &[usize] = match tx.tx_type {
+        TransactionType::Legacy => &[],
+        TransactionType::AccessList => &[7],
+        TransactionType::DynamicFee => &[8],
+        TransactionType::SetCode => &[8, 9],
+        TransactionType::Blob => &[8, 10],
+    };
+    let mut unsigned: Vec<Vec<u8>> = fields
+        .iter()
+        .enumerate()
+        .map(|(i, field)| if list_fields.contains(&i) { field.clone() } else { rlp::encode_bytes(field) })
+        .collect();
+    let y_parity = match (tx.tx_type, tx.chain_id) {
+        (TransactionType::Legacy, Some(chain_id)) => {
+            unsigned.extend([rlp::encode_u64(chain_id), rlp::encode_u64(0), rlp::encode_u64(0)]);
+            uint64(&signature[0], "v")? - 35 - 2 * chain_id
+        }
+        (TransactionType::Legacy, None) => uint64(&signature[0], "v")? - 27,
+        _ => uint64(&signature[0], "yParity")?,
+    };
+    let mut message: Vec<u8> = prefix.into_iter().collect();
+    message.extend(rlp::encode_list(&unsigned));
+
+    let scalar = |bytes: &[u8], name: &str| -> Result<[u8; 32], ProofError> {
+        Ok(uint256(bytes, name)?.to_be_bytes())
+    };
+    let signature = Signature::from_scalars(scalar(&signature[1], "r")?, scalar(&signature[2], "s")?)
+        .map_err(|_| invalid("Signature r or s is zero or not below the curve order".to_string()))?;
+    if signature.normalize_s().is_some() {
+        return Err(invalid("Signature s is in the upper half of the curve order (EIP-2)".to_string()));
+    }
+    let recovery_id = u8::try_from(y_parity)
+        .ok()
+        .and_then(RecoveryId::from_byte)
+        .filter(|id| !id.is_x_reduced())
+        .ok_or_else(|| invalid(format!("Invalid signature y parity {}", y_parity)))?;
+    let key = VerifyingKey::recover_from_prehash(&keccak256(&message), &signature, recovery_id)
+        .map_err(|_| invalid("No public key recovers from the signature".to_string()))?;
+    let hash = keccak256(&key.to_encoded_point(false).as_bytes()[1..]);
+    Ok(hash[12..].try_into().expect("20 bytes"))
+}
+
+/// EIP-155: `v = chain_id * 2 + 35 + y_parity`; 27 or 28 means unprotected.
+fn legacy_chain_id(v: &[u8]) -> Result<Option<u64>, ProofError> {
+    match uint64(v, "v")? {
+        27 | 28 => Ok(None),
+        v if v >= 35 => Ok(Some((v - 35) / 2)),
+        v => Err(invalid(format!("Invalid legacy signature v {}", v))),
+    }
+}
+
+fn uint64(bytes: &[u8], field: &str) -> Result<u64, ProofError> {
+    U256::from_be_slice(bytes)
+        .and_then(|v| v.to_u64())
+        .ok_or_else(|| invalid(format!("{} does not fit in 64 bits", field)))
+}
+
+fn uint256(bytes: &[u8], field: &str) -> Result<U256, ProofError> {
+    U256::from_be_slice(bytes).ok_or_else(|| invalid(format!("{} does not fit in 256 bits", field)))
+}
+
+fn invalid(reason: String) -> ProofError {
+    ProofError::InvalidValueEncoding { reason }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An EIP-1559 transfer of `value` wei to 0x11..11, with a dummy signature.
+    fn dynamic_fee_transfer(nonce: u64, value: u64) -> Vec<u8> {
+        let mut envelope = vec![0x02];
+        envelope.extend(rlp::encode_list(&[
+            rlp::encode_u64(1),
+            rlp::encode_u64(nonce),
+            rlp::encode_u64(1_000_000_000),
+            rlp::encode_u64(30_000_000_000),
+            rlp::encode_u64(21_000),
+            rlp::encode_bytes(&[0x11; 20]),
+            rlp::encode_u64(value),
+            rlp::encode_bytes(&[]),
+            rlp::encode_list(&[]),
+            rlp::encode_u64(1),
+            rlp::encode_bytes(&[0xAA; 32]),
+            rlp::encode_bytes(&[0xBB; 32]),
+        ]));
+        envelope
+    }
+
+    fn legacy_creation(v: u64) -> Vec<u8> {
+        rlp::encode_list(&[
+            rlp::encode_u64(7),
+            rlp::encode_u64(20_000_000_000),
+            rlp::encode_u64(100_000),
+            rlp::encode_bytes(&[]),
+            rlp::encode_u64(0),
+            rlp::encode_bytes(&[0x60, 0x80]),
+            rlp::encode_u64(v),
+            rlp::encode_bytes(&[0xAA; 32]),
+            rlp::encode_bytes(&[0xBB; 32]),
+        ])
+    }
+
+    #[test]
+    fn test_decode_transactions() {
+        let tx = decode_transaction(&dynamic_fee_transfer(3, 5)).unwrap();
+        assert_eq!(tx.tx_type, TransactionType::DynamicFee);
+        assert_eq!(tx.chain_id, Some(1));
+        assert_eq!(tx.nonce, 3);
+        assert_eq!(tx.gas_price, None);
+        assert_eq!(tx.max_priority_fee_per_gas, Some(U256::from_u64(1_000_000_000)));
+        assert_eq!(tx.max_fee_per_gas, Some(U256::from_u64(30_000_000_000)));
+        assert_eq!(tx.gas_limit, 21_000);
+        assert_eq!(tx.to, Some([0x11; 20]));
+        assert_eq!(tx.value, U256::from_u64(5));
+
+        let legacy = decode_transaction(&legacy_creation(37)).unwrap();
+        assert_eq!(legacy.tx_type, TransactionType::Legacy);
+        assert_eq!(legacy.chain_id, Some(1));
+        assert_eq!(legacy.gas_price, Some(U256::from_u64(20_000_000_000)));
+        assert_eq!(legacy.to, None);
+        assert_eq!(legacy.input, vec![0x60, 0x80]);
+        assert_eq!(decode_transaction(&legacy_creation(27)).unwrap().chain_id, None);
+        assert!(decode_transaction(&legacy_creation(30)).is_err());
+
+        let mut unknown = dynamic_fee_transfer(0, 0);
+        unknown[0] = 0x05;
+        assert!(matches!(
+            decode_transaction(&unknown),
+            Err(ProofError::UnsupportedTransactionType { type_byte: 0x05 })
+        ));
+    }
+
+    #[test]
+    fn test_recover_sender() {
+        // The EIP-155 example transaction, signed with key 0x4646...46.
+        let signed = hex::decode(concat!(
+            "f86c098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a7640000",
+            "8025a028ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276",
+            "a067cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83"
+        ))
+        .unwrap();
+        assert_eq!(
+            hex::encode(recover_sender(&signed).unwrap()),
+            "9d8a62f656a8d1615c1294fd71e9cfb3e4855a4f"
+        );
+
+        // A typed transaction signed here, so the address is known.
+        let key = k256::ecdsa::SigningKey::from_slice(&[0x46; 32]).unwrap();
+        let unsigned = rlp::encode_list(&[
+            rlp::encode_u64(1),
+            rlp::encode_u64(3),
+            rlp::encode_u64(1_000_000_000),
+            rlp::encode_u64(30_000_000_000),
+            rlp::encode_u64(21_000),
+            rlp::encode_bytes(&[0x11; 20]),
+            rlp::encode_u64(5),
+            rlp::encode_bytes(&[]),
+            rlp::encode_list(&[rlp::encode_list(&[rlp::encode_bytes(&[0x22; 20]), rlp::encode_list(&[])])]),
+        ]);
+        let (signature, recovery_id) =
+            key.sign_prehash_recoverable(&keccak256(&[&[0x02][..], &unsigned].concat())).unwrap();
+        let (r, s) = signature.split_bytes();
+        let mut items = crate::execution::proof::decode_rlp_list(&unsigned).unwrap();
+        let access_list = items.pop().unwrap();
+        let mut fields: Vec<Vec<u8>> = items.iter().map(|item| rlp::encode_bytes(item)).collect();
+        fields.extend([
+            access_list,
+            rlp::encode_u64(recovery_id.to_byte() as u64),
+            rlp::encode_bytes(rlp::trim_leading_zeros(&r)),
+            rlp::encode_bytes(rlp::trim_leading_zeros(&s)),
+        ]);
+        let mut envelope = vec![0x02];
+        envelope.extend(rlp::encode_list(&fields));
+        assert_eq!(recover_sender(&envelope).unwrap(), recover_sender(&signed).unwrap());
+
+        // The dummy signatures above are not valid signatures.
+        assert!(recover_sender(&dynamic_fee_transfer(0, 0)).is_err());
+    }
+
+    #[test]
+    fn test_verify_transaction_proof() {
+        let envelopes: Vec<Vec<u8>> = (0..20).map(|i| dynamic_fee_transfer(i, i * 10)).collect();
+        let (root, proof) = build_transaction_proof(&envelopes, 17).unwrap();
+
+        let verified = verify_transaction_proof(root, 17, &proof).unwrap();
+        assert_eq!(verified.transaction_index, 17);
+        assert_eq!(verified.transaction_hash, keccak256(&envelopes[17]));
+        assert_eq!(verified.transaction.nonce, 17);
+        assert_eq!(verified.transaction.value, U256::from_u64(170));
+
+        assert!(verify_transaction_proof(root, 16, &proof).is_err());
+        assert!(verify_transaction_proof([0xEE; 32], 17, &proof).is_err());
+        assert!(build_transaction_proof(&envelopes, 20).is_none());
+    }
+}
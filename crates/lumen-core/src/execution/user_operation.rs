@@ -0,0 +1,170 @@
+use crate::execution::proof::keccak256;
+use crate::types::execution::Log;
+use crate::types::uint::U256;
+
+/// The ERC-4337 EntryPoint deployments: v0.6, v0.7 and v0.8, at the same
+/// address on every chain.
+pub const ENTRY_POINTS: [[u8; 20]; 3] = [
+    hex_address(b"5ff137d4b0fdcd49dca30c7cf57e578a026d2789"),
+    hex_address(b"0000000071727de22e5e9d8baf0edac6f37da032"),
+    hex_address(b"4337084d9e255ff0702461cf8895ce9e3b5ff108"),
+];
+
+/// Signature of the event every EntryPoint version emits once per handled
+/// user operation.
+pub const USER_OPERATION_EVENT: &str =
+    "UserOperationEvent(bytes32,address,address,uint256,bool,uint256,uint256)";
+
+const fn hex_address(hex: &[u8; 40]) -> [u8; 20] {
+    const fn nibble(c: u8) -> u8 {
+        match c {
+            b'0'..=b'9' => c - b'0',
+            b'a'..=b'f' => c - b'a' + 10,
+            _ => panic!("not lowercase hex"),
+        }
+    }
+    let mut out = [0u8; 20];
+    let mut i = 0;
+    while i < 20 {
+        out[i] = nibble(hex[2 * i]) << 4 | nibble(hex[2 * i + 1]);
+        i += 1;
+    }
+    out
+}
+
+/// A decoded `UserOperationEvent`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UserOperationEvent {
+    pub user_op_hash: [u8; 32],
+    /// The EntryPoint that emitted the event.
+    pub entry_point: [u8; 20],
+    pub sender: [u8; 20],
+    /// Zero when the operation paid for itself.
+    pub paymaster: [u8; 20],
+    pub nonce: U256,
+    /// False if the operation's call reverted. Its gas was still paid.
+    pub success: bool,
+    pub actual_gas_cost: U256,
+    pub actual_gas_used: U256,
+    /// Position of the event among the receipt's logs.
+    pub log_index: usize,
+}
+
+/// Find the `UserOperationEvent` for `user_op_hash` among a receipt's
+/// `logs`, counting only events emitted by one of `entry_points`: any
+/// contract can emit a log with the same signature.
+///
+/// Returns `Ok(None)` if there is none, and an error if the matching log is
+/// malformed.
+pub fn find_user_operation(
+    logs: &[Log],
+    user_op_hash: &[u8; 32],
+    entry_points: &[[u8; 20]],
+) -> Result<Option<UserOperationEvent>, String> {
+    let signature = keccak256(USER_OPERATION_EVENT.as_bytes());
+    let Some((log_index, log)) = logs.iter().enumerate().find(|(_, log)| {
+        entry_points.contains(&log.address)
+            && log.topics.first() == Some(&signature)
+            && log.topics.get(1) == Some(user_op_hash)
+    }) else {
+        return Ok(None);
+    };
+
+    if log.topics.len() != 4 || log.data.len() != 4 * 32 {
+        return Err(format!(
+            "UserOperationEvent has {} topics and {} data bytes, expected 4 and 128",
+            log.topics.len(),
+            log.data.len()
+        ));
+    }
+    let word = |i: usize| -> [u8; 32] { log.data[i * 32..(i + 1) * 32].try_into().expect("32 bytes") };
+    let address = |topic: &[u8; 32]| -> Result<[u8; 20], String> {
+        if topic[..12] != [0; 12] {
+            return Err("UserOperationEvent address topic is not a padded address".to_string());
+        }
+        Ok(topic[12..].try_into().expect("20 bytes"))
+    };
+    let success = match U256::from_be_bytes(word(1)).to_u64() {
+        Some(0) => false,
+        Some(1) => true,
+        _ => return Err("UserOperationEvent success is not a bool".to_string()),
+    };
+
+    Ok(Some(UserOperationEvent {
+        user_op_hash: *user_op_hash,
+        entry_point: log.address,
+        sender: address(&log.topics[2])?,
+        paymaster: address(&log.topics[3])?,
+        nonce: U256::from_be_bytes(word(0)),
+        success,
+        actual_gas_cost: U256::from_be_bytes(word(2)),
+        actual_gas_used: U256::from_be_bytes(word(3)),
+        log_index,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn padded(address: [u8; 20]) -> [u8; 32] {
+        let mut topic = [0u8; 32];
+        topic[12..].copy_from_slice(&address);
+        topic
+    }
+
+    fn event_log(emitter: [u8; 20], user_op_hash: [u8; 32], success: u64) -> Log {
+        let mut data = Vec::new();
+        for value in [7, success, 1_000_000, 50_000] {
+            data.extend_from_slice(&U256::from_u64(value).to_be_bytes());
+        }
+        Log {
+            address: emitter,
+            topics: vec![
+                keccak256(USER_OPERATION_EVENT.as_bytes()),
+                user_op_hash,
+                padded([0x5E; 20]),
+                padded([0; 20]),
+            ],
+            data,
+        }
+    }
+
+    #[test]
+    fn test_event_signature_and_entry_points() {
+        assert_eq!(
+            hex::encode(keccak256(USER_OPERATION_EVENT.as_bytes())),
+            "49628fd1471006c1482da88028e9ce4dbb080b815c9b0344d39e5a8e6ec1419f"
+        );
+        assert_eq!(hex::encode(ENTRY_POINTS[1]), "0000000071727de22e5e9d8baf0edac6f37da032");
+    }
+
+    #[test]
+    fn test_find_user_operation() {
+        let hash = [0xAB; 32];
+        let logs = vec![
+            // Same event from a contract that is not an EntryPoint: ignored.
+            event_log([0x66; 20], hash, 1),
+            event_log(ENTRY_POINTS[1], [0xCD; 32], 1),
+            event_log(ENTRY_POINTS[1], hash, 0),
+        ];
+        let event = find_user_operation(&logs, &hash, &ENTRY_POINTS).unwrap().unwrap();
+        assert_eq!(event.log_index, 2);
+        assert_eq!(event.entry_point, ENTRY_POINTS[1]);
+        assert_eq!(event.sender, [0x5E; 20]);
+        assert_eq!(event.paymaster, [0; 20]);
+        assert_eq!(event.nonce, U256::from_u64(7));
+        assert!(!event.success);
+        assert_eq!(event.actual_gas_used, U256::from_u64(50_000));
+
+        assert_eq!(find_user_operation(&logs, &[0xEE; 32], &ENTRY_POINTS), Ok(None));
+        // A caller-supplied EntryPoint list replaces the defaults.
+        assert_eq!(find_user_operation(&logs, &hash, &[[0x66; 20]]).unwrap().unwrap().log_index, 0);
+
+        let mut truncated = event_log(ENTRY_POINTS[0], hash, 1);
+        truncated.data.pop();
+        assert!(find_user_operation(&[truncated], &hash, &ENTRY_POINTS).is_err());
+        let not_bool = event_log(ENTRY_POINTS[0], hash, 2);
+        assert!(find_user_operation(&[not_bool], &hash, &ENTRY_POINTS).is_err());
+    }
+}
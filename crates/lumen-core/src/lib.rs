@@ -16,6 +16,10 @@
 //!   Merkle-Patricia trie proofs for account state, storage, and receipts.
 //!   Zero trust assumptions beyond the verified state root.
 //!
+//! - **Blob verification** (`blob` module): Verifies EIP-4844 KZG proofs for
+//!   blob sidecars against the versioned hashes a verified block commits to.
+//!   Trusts the KZG ceremony's trusted setup (1-of-N honest participants).
+//!
 //! ## Usage
 //!
 //! ```ignore
@@ -23,19 +27,37 @@
 //! use lumen_core::execution::proof::verify_account_proof;
 //! ```
 
+pub mod blob;
 pub mod consensus;
 pub mod execution;
+#[cfg(feature = "simd")]
+pub mod simd;
 pub mod types;
 
 // Re-export commonly used types for convenience
+pub use blob::{
+    decode_blob_sidecar, kzg_to_versioned_hash, verify_blob_sidecars, BlobError, BlobSidecar, DecodedBlobSidecar,
+};
 pub use consensus::{
     checkpoint::{verify_checkpoint_consensus, CheckpointError, VerifiedCheckpoint},
-    light_client::{initialize_from_bootstrap, process_light_client_update},
-    sync_committee::{verify_sync_committee_signature, VerificationError},
+    aggregate_cache::AggregatePubkeyCache,
+    clock::SlotClock,
+    light_client::{initialize_from_bootstrap, process_light_client_update, process_light_client_update_cached},
+    sync_committee::{verify_sync_committee_signature, verify_sync_committee_signature_cached, VerificationError},
 };
 pub use execution::{
-    account::{verify_full_account_state, VerifiedAccountState},
-    proof::{keccak256, verify_account_proof, verify_storage_proof, ProofError},
-    receipt::verify_receipt_proof,
+    account::{verify_full_account_state, StorageSlotOutcome, VerifiedAccountState},
+    limits::ProofLimits,
+    node_cache::TrieNodeCache,
+    proof::{
+        keccak256, verify_account_proof, verify_account_proof_cached,
+        verify_account_proof_with_limits, verify_storage_proof, verify_storage_proof_cached,
+        verify_storage_proof_with_limits, verify_storage_proofs, ProofError,
+    },
+    receipt::{
+        verify_receipt_proof, verify_receipt_proof_with_context, verify_receipt_proof_with_limits,
+        ReceiptContext,
+    },
+    transaction::{decode_transaction, verify_transaction_proof},
 };
-pub use types::{beacon::*, execution::*};
+pub use types::{beacon::*, execution::*, network::NetworkConfig, uint::U256};
@@ -0,0 +1,365 @@
+//! Multi-buffer keccak256 and sha256 (`simd` feature).
+//!
+//! A single keccak or sha256 stream is a chain of dependent rounds that SIMD
+//! does little for, but verification has plenty of independent hashes: every
+//! node of an MPT proof, every pair of chunks in an SSZ merkle layer. Here
+//! those are hashed side by side, one message per vector lane: two keccak
+//! states in `u64x2`, four sha256 states in `u32x4`.
+//!
+//! On wasm32 built with `-C target-feature=+simd128` the lanes are `v128`
+//! registers. Everywhere else they are plain arrays, which compilers
+//! auto-vectorize where they can and which keep the same code testable
+//! natively against the scalar crates.
+
+#[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+mod lanes {
+    use core::arch::wasm32::*;
+
+    #[derive(Clone, Copy)]
+    pub struct U64x2(v128);
+
+    impl U64x2 {
+        pub fn splat(value: u64) -> Self {
+            Self(u64x2_splat(value))
+        }
+        pub fn new(lanes: [u64; 2]) -> Self {
+            Self(u64x2(lanes[0], lanes[1]))
+        }
+        pub fn lanes(self) -> [u64; 2] {
+            [u64x2_extract_lane::<0>(self.0), u64x2_extract_lane::<1>(self.0)]
+        }
+        pub fn xor(self, other: Self) -> Self {
+            Self(v128_xor(self.0, other.0))
+        }
+        /// `!self & other`
+        pub fn not_and(self, other: Self) -> Self {
+            Self(v128_andnot(other.0, self.0))
+        }
+        pub fn rotl(self, n: u32) -> Self {
+            // Shift counts are taken mod 64, so n == 0 gives self | self.
+            Self(v128_or(i64x2_shl(self.0, n), u64x2_shr(self.0, 64 - n)))
+        }
+    }
+
+    #[derive(Clone, Copy)]
+    pub struct U32x4(v128);
+
+    impl U32x4 {
+        pub fn splat(value: u32) -> Self {
+            Self(u32x4_splat(value))
+        }
+        pub fn new(lanes: [u32; 4]) -> Self {
+            Self(u32x4(lanes[0], lanes[1], lanes[2], lanes[3]))
+        }
+        pub fn lanes(self) -> [u32; 4] {
+            [
+                u32x4_extract_lane::<0>(self.0),
+                u32x4_extract_lane::<1>(self.0),
+                u32x4_extract_lane::<2>(self.0),
+                u32x4_extract_lane::<3>(self.0),
+            ]
+        }
+        pub fn add(self, other: Self) -> Self {
+            Self(i32x4_add(self.0, other.0))
+        }
+        pub fn xor(self, other: Self) -> Self {
+            Self(v128_xor(self.0, other.0))
+        }
+        pub fn and(self, other: Self) -> Self {
+            Self(v128_and(self.0, other.0))
+        }
+        /// `!self & other`
+        pub fn not_and(self, other: Self) -> Self {
+            Self(v128_andnot(other.0, self.0))
+        }
+        pub fn rotr(self, n: u32) -> Self {
+            Self(v128_or(u32x4_shr(self.0, n), i32x4_shl(self.0, 32 - n)))
+        }
+        pub fn shr(self, n: u32) -> Self {
+            Self(u32x4_shr(self.0, n))
+        }
+    }
+}
+
+#[cfg(not(all(target_arch = "wasm32", target_feature = "simd128")))]
+mod lanes {
+    #[derive(Clone, Copy)]
+    pub struct U64x2([u64; 2]);
+
+    impl U64x2 {
+        pub fn splat(value: u64) -> Self {
+            Self([value; 2])
+        }
+        pub fn new(lanes: [u64; 2]) -> Self {
+            Self(lanes)
+        }
+        pub fn lanes(self) -> [u64; 2] {
+            self.0
+        }
+        pub fn xor(self, other: Self) -> Self {
+            Self([self.0[0] ^ other.0[0], self.0[1] ^ other.0[1]])
+        }
+        /// `!self & other`
+        pub fn not_and(self, other: Self) -> Self {
+            Self([!self.0[0] & other.0[0], !self.0[1] & other.0[1]])
+        }
+        pub fn rotl(self, n: u32) -> Self {
+            Self(self.0.map(|lane| lane.rotate_left(n)))
+        }
+    }
+
+    #[derive(Clone, Copy)]
+    pub struct U32x4([u32; 4]);
+
+    impl U32x4 {
+        pub fn splat(value: u32) -> Self {
+            Self([value; 4])
+        }
+        pub fn new(lanes: [u32; 4]) -> Self {
+            Self(lanes)
+        }
+        pub fn lanes(self) -> [u32; 4] {
+            self.0
+        }
+        fn zip(self, other: Self, f: impl Fn(u32, u32) -> u32) -> Self {
+            Self(std::array::from_fn(|i| f(self.0[i], other.0[i])))
+        }
+        pub fn add(self, other: Self) -> Self {
+            self.zip(other, u32::wrapping_add)
+        }
+        pub fn xor(self, other: Self) -> Self {
+            self.zip(other, |a, b| a ^ b)
+        }
+        pub fn and(self, other: Self) -> Self {
+            self.zip(other, |a, b| a & b)
+        }
+        /// `!self & other`
+        pub fn not_and(self, other: Self) -> Self {
+            self.zip(other, |a, b| !a & b)
+        }
+        pub fn rotr(self, n: u32) -> Self {
+            Self(self.0.map(|lane| lane.rotate_right(n)))
+        }
+        pub fn shr(self, n: u32) -> Self {
+            Self(self.0.map(|lane| lane >> n))
+        }
+    }
+}
+
+use lanes::{U32x4, U64x2};
+
+// --- keccak256 ---
+
+/// keccak256 rate in bytes (1600 - 2 * 256 bits).
+const KECCAK_RATE: usize = 136;
+
+const KECCAK_RC: [u64; 24] = [
+    0x0000000000000001, 0x0000000000008082, 0x800000000000808a, 0x8000000080008000,
+    0x000000000000808b, 0x0000000080000001, 0x8000000080008081, 0x8000000000008009,
+    0x000000000000008a, 0x0000000000000088, 0x0000000080008009, 0x000000008000000a,
+    0x000000008000808b, 0x800000000000008b, 0x8000000000008089, 0x8000000000008003,
+    0x8000000000008002, 0x8000000000000080, 0x000000000000800a, 0x800000008000000a,
+    0x8000000080008081, 0x8000000000008080, 0x0000000080000001, 0x8000000080008008,
+];
+
+/// Rotation offset of lane `x + 5 * y`.
+const KECCAK_RHO: [u32; 25] = [
+    0, 1, 62, 28, 27, //
+    36, 44, 6, 55, 20, //
+    3, 10, 43, 25, 39, //
+    41, 45, 15, 21, 8, //
+    18, 2, 61, 56, 14,
+];
+
+/// keccak-f[1600] on two states at once.
+fn keccak_f(a: &mut [U64x2; 25]) {
+    for rc in KECCAK_RC {
+        // θ
+        let c: [U64x2; 5] =
+            std::array::from_fn(|x| a[x].xor(a[x + 5]).xor(a[x + 10]).xor(a[x + 15]).xor(a[x + 20]));
+        for x in 0..5 {
+            let d = c[(x + 4) % 5].xor(c[(x + 1) % 5].rotl(1));
+            for y in 0..5 {
+                a[x + 5 * y] = a[x + 5 * y].xor(d);
+            }
+        }
+        // ρ and π
+        let mut b = [U64x2::splat(0); 25];
+        for x in 0..5 {
+            for y in 0..5 {
+                b[y + 5 * ((2 * x + 3 * y) % 5)] = a[x + 5 * y].rotl(KECCAK_RHO[x + 5 * y]);
+            }
+        }
+        // χ
+        for y in 0..5 {
+            for x in 0..5 {
+                a[x + 5 * y] = b[x + 5 * y].xor(b[(x + 1) % 5 + 5 * y].not_and(b[(x + 2) % 5 + 5 * y]));
+            }
+        }
+        // ι
+        a[0] = a[0].xor(U64x2::splat(rc));
+    }
+}
+
+/// `message` with keccak padding, a whole number of rate-sized blocks.
+fn keccak_pad(message: &[u8]) -> Vec<u8> {
+    let mut padded = message.to_vec();
+    padded.push(0x01);
+    padded.resize(padded.len().div_ceil(KECCAK_RATE) * KECCAK_RATE, 0);
+    *padded.last_mut().expect("padding is never empty") |= 0x80;
+    padded
+}
+
+fn keccak256_x2(messages: [&[u8]; 2]) -> [[u8; 32]; 2] {
+    let padded = messages.map(keccak_pad);
+    let blocks = [padded[0].len() / KECCAK_RATE, padded[1].len() / KECCAK_RATE];
+    let mut state = [U64x2::splat(0); 25];
+    let mut digests = [[0u8; 32]; 2];
+
+    // Both lanes permute in step; a lane that runs out of blocks keeps
+    // permuting garbage after its digest has been read out.
+    for block in 0..blocks[0].max(blocks[1]) {
+        for (i, lane) in state.iter_mut().take(KECCAK_RATE / 8).enumerate() {
+            let word = |message: usize| -> u64 {
+                if block >= blocks[message] {
+                    return 0;
+                }
+                let offset = block * KECCAK_RATE + i * 8;
+                u64::from_le_bytes(padded[message][offset..offset + 8].try_into().expect("8 bytes"))
+            };
+            *lane = lane.xor(U64x2::new([word(0), word(1)]));
+        }
+        keccak_f(&mut state);
+        for (lane, digest) in digests.iter_mut().enumerate() {
+            if block + 1 == blocks[lane] {
+                for (i, word) in state.iter().take(4).enumerate() {
+                    digest[i * 8..i * 8 + 8].copy_from_slice(&word.lanes()[lane].to_le_bytes());
+                }
+            }
+        }
+    }
+    digests
+}
+
+/// keccak256 of every input, two at a time.
+pub fn keccak256_batch<T: AsRef<[u8]>>(inputs: &[T]) -> Vec<[u8; 32]> {
+    let mut digests = Vec::with_capacity(inputs.len());
+    for pair in inputs.chunks(2) {
+        match pair {
+            [a, b] => digests.extend(keccak256_x2([a.as_ref(), b.as_ref()])),
+            [a] => digests.push(keccak256_x2([a.as_ref(), &[]])[0]),
+            _ => unreachable!("chunks(2) yields one or two items"),
+        }
+    }
+    digests
+}
+
+// --- sha256 ---
+
+const SHA256_K: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+const SHA256_H0: [u32; 8] = [
+    0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+];
+
+/// The second block of every 64-byte message: 0x80, zeros, then the bit length.
+const SHA256_PAD_64: [u32; 16] = [0x8000_0000, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 512];
+
+/// One sha256 compression of four states with four blocks.
+fn sha256_compress(state: &mut [U32x4; 8], block: [U32x4; 16]) {
+    let mut w = [U32x4::splat(0); 64];
+    w[..16].copy_from_slice(&block);
+    for t in 16..64 {
+        let s0 = w[t - 15].rotr(7).xor(w[t - 15].rotr(18)).xor(w[t - 15].shr(3));
+        let s1 = w[t - 2].rotr(17).xor(w[t - 2].rotr(19)).xor(w[t - 2].shr(10));
+        w[t] = w[t - 16].add(s0).add(w[t - 7]).add(s1);
+    }
+
+    let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = *state;
+    for t in 0..64 {
+        let s1 = e.rotr(6).xor(e.rotr(11)).xor(e.rotr(25));
+        let ch = e.and(f).xor(e.not_and(g));
+        let temp1 = h.add(s1).add(ch).add(U32x4::splat(SHA256_K[t])).add(w[t]);
+        let s0 = a.rotr(2).xor(a.rotr(13)).xor(a.rotr(22));
+        let maj = a.and(b).xor(a.and(c)).xor(b.and(c));
+        let temp2 = s0.add(maj);
+        h = g;
+        g = f;
+        f = e;
+        e = d.add(temp1);
+        d = c;
+        c = b;
+        b = a;
+        a = temp1.add(temp2);
+    }
+    for (word, add) in state.iter_mut().zip([a, b, c, d, e, f, g, h]) {
+        *word = word.add(add);
+    }
+}
+
+fn sha256_64_x4(messages: [&[u8; 64]; 4]) -> [[u8; 32]; 4] {
+    let mut state = SHA256_H0.map(U32x4::splat);
+    let block = std::array::from_fn(|i| {
+        U32x4::new(messages.map(|m| u32::from_be_bytes(m[i * 4..i * 4 + 4].try_into().expect("4 bytes"))))
+    });
+    sha256_compress(&mut state, block);
+    sha256_compress(&mut state, SHA256_PAD_64.map(U32x4::splat));
+
+    let mut digests = [[0u8; 32]; 4];
+    for (i, word) in state.iter().enumerate() {
+        for (lane, digest) in digests.iter_mut().enumerate() {
+            digest[i * 4..i * 4 + 4].copy_from_slice(&word.lanes()[lane].to_be_bytes());
+        }
+    }
+    digests
+}
+
+/// sha256 of every 64-byte input (two concatenated SSZ chunks), four at a time.
+pub fn sha256_64_batch(inputs: &[[u8; 64]]) -> Vec<[u8; 32]> {
+    const EMPTY: [u8; 64] = [0; 64];
+    let mut digests = Vec::with_capacity(inputs.len());
+    for group in inputs.chunks(4) {
+        let lanes = std::array::from_fn(|i| group.get(i).unwrap_or(&EMPTY));
+        digests.extend_from_slice(&sha256_64_x4(lanes)[..group.len()]);
+    }
+    digests
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sha2::{Digest, Sha256};
+
+    #[test]
+    fn test_keccak_matches_scalar() {
+        // Lengths around the rate boundary, paired with others of different block counts.
+        let inputs: Vec<Vec<u8>> = [0, 1, 135, 136, 137, 272, 532, 1000, 3]
+            .iter()
+            .map(|&len| (0..len).map(|i| (i * 7 + len) as u8).collect())
+            .collect();
+        let digests = keccak256_batch(&inputs);
+        assert_eq!(digests.len(), inputs.len());
+        for (input, digest) in inputs.iter().zip(&digests) {
+            assert_eq!(*digest, crate::execution::proof::keccak256(input), "len {}", input.len());
+        }
+    }
+
+    #[test]
+    fn test_sha256_matches_scalar() {
+        let inputs: Vec<[u8; 64]> = (0..7u8).map(|n| std::array::from_fn(|i| n.wrapping_mul(31) ^ i as u8)).collect();
+        let digests = sha256_64_batch(&inputs);
+        assert_eq!(digests.len(), 7);
+        for (input, digest) in inputs.iter().zip(&digests) {
+            assert_eq!(digest[..], Sha256::digest(input)[..]);
+        }
+    }
+}
@@ -1,6 +1,8 @@
 use serde::{Deserialize, Serialize};
 use hex;
 
+use super::uint::U256;
+
 /// Number of validators in the Ethereum beacon chain sync committee.
 pub const SYNC_COMMITTEE_SIZE: usize = 512;
 
@@ -31,7 +33,7 @@ pub struct BlsPublicKey(pub [u8; BLS_PUBKEY_LEN]);
 
 impl Serialize for BlsPublicKey {
     fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
-        serializer.serialize_str(&hex::encode(&self.0))
+        serializer.serialize_str(&hex::encode(self.0))
     }
 }
 
@@ -61,7 +63,7 @@ pub struct BlsSignature(pub [u8; BLS_SIGNATURE_LEN]);
 
 impl Serialize for BlsSignature {
     fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
-        serializer.serialize_str(&hex::encode(&self.0))
+        serializer.serialize_str(&hex::encode(self.0))
     }
 }
 
@@ -216,13 +218,17 @@ pub struct ExecutionPayloadHeader {
     /// Block timestamp.
     pub timestamp: u64,
     /// Base fee per gas.
-    pub base_fee_per_gas: u64,
+    pub base_fee_per_gas: U256,
     /// Hash of the execution block.
     pub block_hash: [u8; 32],
     /// Root of the transactions trie.
     pub transactions_root: [u8; 32],
     /// Root of the withdrawals trie.
     pub withdrawals_root: [u8; 32],
+    /// Total blob gas consumed by the block's blob transactions (Deneb+).
+    pub blob_gas_used: u64,
+    /// Running excess blob gas, used for blob base fee pricing (Deneb+).
+    pub excess_blob_gas: u64,
 }
 
 /// The verified state of the light client.
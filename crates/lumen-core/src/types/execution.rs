@@ -1,13 +1,15 @@
 use serde::{Deserialize, Serialize};
 
+use super::uint::U256;
+
 /// An Ethereum account as stored in the state trie.
 /// Verified via Merkle-Patricia trie proofs against a known state root.
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct AccountState {
     /// Number of transactions sent from this account.
     pub nonce: u64,
-    /// Balance in wei (stored as big-endian bytes to avoid precision loss).
-    pub balance: [u8; 32],
+    /// Balance in wei.
+    pub balance: U256,
     /// Root hash of the account's storage trie.
     /// For externally owned accounts (EOAs), this is the empty trie root.
     pub storage_root: [u8; 32],
@@ -40,17 +42,6 @@ impl AccountState {
     pub fn has_storage(&self) -> bool {
         self.storage_root != Self::EMPTY_STORAGE_ROOT
     }
-
-    /// Get balance as a hex string (no 0x prefix, leading zeros stripped).
-    pub fn balance_hex(&self) -> String {
-        let hex_str = hex::encode(self.balance);
-        let trimmed = hex_str.trim_start_matches('0');
-        if trimmed.is_empty() {
-            "0".to_string()
-        } else {
-            trimmed.to_string()
-        }
-    }
 }
 
 /// A Merkle-Patricia trie proof for an account.
@@ -71,7 +62,7 @@ pub struct StorageProof {
     /// The storage key (slot) this proof is for.
     pub key: [u8; 32],
     /// The storage value at this key.
-    pub value: [u8; 32],
+    pub value: U256,
     /// RLP-encoded trie nodes forming the proof path.
     pub proof: Vec<Vec<u8>>,
 }
@@ -85,9 +76,53 @@ pub struct ReceiptProof {
     pub proof: Vec<Vec<u8>>,
 }
 
+/// EIP-2718 transaction envelope type.
+/// Receipts carry the same type byte as the transaction that produced them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TransactionType {
+    /// Pre-EIP-2718 transaction (untyped RLP list).
+    Legacy,
+    /// EIP-2930 access list transaction (type 0x01).
+    AccessList,
+    /// EIP-1559 dynamic fee transaction (type 0x02).
+    DynamicFee,
+    /// EIP-4844 blob transaction (type 0x03).
+    Blob,
+    /// EIP-7702 set-code transaction (type 0x04).
+    SetCode,
+}
+
+impl TransactionType {
+    /// Map an EIP-2718 type byte to a known transaction type.
+    /// Returns None for type bytes no fork has assigned yet.
+    pub fn from_type_byte(byte: u8) -> Option<Self> {
+        match byte {
+            0x00 => Some(Self::Legacy),
+            0x01 => Some(Self::AccessList),
+            0x02 => Some(Self::DynamicFee),
+            0x03 => Some(Self::Blob),
+            0x04 => Some(Self::SetCode),
+            _ => None,
+        }
+    }
+
+    /// The EIP-2718 type byte (0x00 for legacy).
+    pub fn type_byte(&self) -> u8 {
+        match self {
+            Self::Legacy => 0x00,
+            Self::AccessList => 0x01,
+            Self::DynamicFee => 0x02,
+            Self::Blob => 0x03,
+            Self::SetCode => 0x04,
+        }
+    }
+}
+
 /// A verified transaction receipt.
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct TransactionReceipt {
+    /// Envelope type of the transaction this receipt belongs to.
+    pub tx_type: TransactionType,
     /// Whether the transaction succeeded (1) or failed (0).
     pub status: u8,
     /// Cumulative gas used in the block up to and including this transaction.
@@ -136,6 +171,73 @@ pub struct Log {
     pub data: Vec<u8>,
 }
 
+/// A log annotated with its position in the block.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexedLog {
+    /// Index of this log among all logs emitted in the block.
+    pub log_index: u64,
+    /// The log itself.
+    pub log: Log,
+}
+
+/// A verified receipt enriched with the fields the standard
+/// `eth_getTransactionReceipt` response carries but the receipt trie does not.
+/// Each of those is derived from proven data only, and is absent when the
+/// proofs it needs were not supplied.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VerifiedReceipt {
+    /// The receipt as committed to by the receipts root.
+    pub receipt: TransactionReceipt,
+    /// Index of the transaction in the block.
+    pub transaction_index: u64,
+    /// keccak256 of the transaction envelope, if it was proven at the same index.
+    pub transaction_hash: Option<[u8; 32]>,
+    /// Gas used by this transaction alone, if the earlier receipts were proven.
+    pub gas_used: Option<u64>,
+    /// The receipt's logs with block-level log indices, if the earlier
+    /// receipts were proven. The logs themselves are in `receipt`.
+    pub logs: Option<Vec<IndexedLog>>,
+}
+
+/// A proof for a transaction in the transactions trie.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TransactionProof {
+    /// The transaction index in the block.
+    pub tx_index: u64,
+    /// RLP-encoded trie nodes forming the proof path.
+    pub proof: Vec<Vec<u8>>,
+}
+
+/// The fields of a transaction envelope that every type shares, decoded.
+/// Signatures, access lists, blob hashes and authorizations are not decoded;
+/// they are still committed to through the transaction hash.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Transaction {
+    pub tx_type: TransactionType,
+    /// None for legacy transactions signed without EIP-155 replay protection.
+    pub chain_id: Option<u64>,
+    pub nonce: u64,
+    /// Legacy and EIP-2930 transactions.
+    pub gas_price: Option<U256>,
+    /// EIP-1559 and later transactions.
+    pub max_fee_per_gas: Option<U256>,
+    pub max_priority_fee_per_gas: Option<U256>,
+    pub gas_limit: u64,
+    /// None for contract creation.
+    pub to: Option<[u8; 20]>,
+    pub value: U256,
+    pub input: Vec<u8>,
+}
+
+/// A transaction proven to be at `transaction_index` under a transactions root.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VerifiedTransaction {
+    pub transaction: Transaction,
+    pub transaction_index: u64,
+    /// keccak256 of the envelope, as stored in the trie.
+    pub transaction_hash: [u8; 32],
+}
+
 /// Full proof response from eth_getProof — contains account proof and storage proofs.
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct EthGetProofResponse {
@@ -1,5 +1,9 @@
 pub mod beacon;
 pub mod execution;
+pub mod network;
+pub mod uint;
 
 pub use beacon::*;
 pub use execution::*;
+pub use network::NetworkConfig;
+pub use uint::*;
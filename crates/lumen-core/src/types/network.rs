@@ -0,0 +1,161 @@
+use serde::{Deserialize, Serialize};
+
+/// The chain parameters a light client needs to verify a network.
+///
+/// `genesis_validators_root` and `fork_version` go into the signing domain,
+/// so a client configured for one network rejects sync committee signatures
+/// from every other — there is no way to accidentally follow the wrong chain.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NetworkConfig {
+    /// Human-readable network name ("mainnet", "sepolia", ...).
+    pub name: String,
+    /// EIP-155 chain id of the execution layer.
+    pub chain_id: u64,
+    /// Root of the genesis validator set (hex-encoded for serde).
+    #[serde(with = "hex_array")]
+    pub genesis_validators_root: [u8; 32],
+    /// Fork version used for the sync committee signing domain (hex-encoded for serde).
+    #[serde(with = "hex_array")]
+    pub fork_version: [u8; 4],
+    /// Unix time (seconds) of the beacon chain genesis, for wall-clock slot
+    /// math. 0 if unknown, which disables checks against the clock.
+    #[serde(default)]
+    pub genesis_time: u64,
+}
+
+impl NetworkConfig {
+    /// Ethereum mainnet.
+    pub fn mainnet() -> Self {
+        Self {
+            name: "mainnet".to_string(),
+            chain_id: 1,
+            genesis_validators_root: hex_literal(
+                "4b363db94e286120d76eb905340fdd4e54bfe9f06bf33ff6cf5ad27f511bfe95",
+            ),
+            // Electra
+            fork_version: [0x05, 0x00, 0x00, 0x00],
+            genesis_time: 1_606_824_023,
+        }
+    }
+
+    /// Sepolia testnet.
+    pub fn sepolia() -> Self {
+        Self {
+            name: "sepolia".to_string(),
+            chain_id: 11_155_111,
+            genesis_validators_root: hex_literal(
+                "d8ea171f3c94aea21ebc42a1ed61052acf3f9209c00e4efbaaddac09ed9b8078",
+            ),
+            // Electra
+            fork_version: [0x90, 0x00, 0x00, 0x74],
+            genesis_time: 1_655_733_600,
+        }
+    }
+
+    /// Holesky testnet.
+    pub fn holesky() -> Self {
+        Self {
+            name: "holesky".to_string(),
+            chain_id: 17_000,
+            genesis_validators_root: hex_literal(
+                "9143aa7c615a7f7115e2b6aac319c03529df8242ae705fba9df39b79c59fa8b1",
+            ),
+            // Electra
+            fork_version: [0x06, 0x01, 0x70, 0x00],
+            genesis_time: 1_695_902_400,
+        }
+    }
+
+    /// All built-in presets.
+    pub fn presets() -> Vec<Self> {
+        vec![Self::mainnet(), Self::sepolia(), Self::holesky()]
+    }
+
+    /// Look up a preset by name (case-insensitive).
+    pub fn preset(name: &str) -> Option<Self> {
+        Self::presets()
+            .into_iter()
+            .find(|n| n.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Look up the preset whose genesis validators root matches, if any.
+    pub fn for_genesis_validators_root(root: &[u8; 32]) -> Option<Self> {
+        Self::presets()
+            .into_iter()
+            .find(|n| &n.genesis_validators_root == root)
+    }
+}
+
+impl Default for NetworkConfig {
+    fn default() -> Self {
+        Self::mainnet()
+    }
+}
+
+fn hex_literal<const N: usize>(s: &str) -> [u8; N] {
+    let bytes = hex::decode(s).expect("preset hex is valid");
+    bytes.try_into().expect("preset hex has the right length")
+}
+
+mod hex_array {
+    use serde::{self, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S, const N: usize>(bytes: &[u8; N], serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&format!("0x{}", hex::encode(bytes)))
+    }
+
+    pub fn deserialize<'de, D, const N: usize>(deserializer: D) -> Result<[u8; N], D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        let s = s.strip_prefix("0x").unwrap_or(&s);
+        let bytes = hex::decode(s).map_err(serde::de::Error::custom)?;
+        let len = bytes.len();
+        bytes
+            .try_into()
+            .map_err(|_| serde::de::Error::custom(format!("expected {} bytes, got {}", N, len)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_presets() {
+        assert_eq!(NetworkConfig::preset("Sepolia").unwrap().chain_id, 11_155_111);
+        assert_eq!(NetworkConfig::preset("holesky").unwrap().chain_id, 17_000);
+        assert!(NetworkConfig::preset("ropsten").is_none());
+
+        let mainnet = NetworkConfig::default();
+        assert_eq!(mainnet.genesis_validators_root[..2], [0x4b, 0x36]);
+        assert_eq!(
+            NetworkConfig::for_genesis_validators_root(&mainnet.genesis_validators_root),
+            Some(mainnet)
+        );
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        let json = r#"{
+            "name": "devnet",
+            "chain_id": 1337,
+            "genesis_validators_root": "0x0101010101010101010101010101010101010101010101010101010101010101",
+            "fork_version": "0x10000038"
+        }"#;
+        let config: NetworkConfig = serde_json::from_str(json).unwrap();
+        assert_eq!(config.genesis_validators_root, [0x01; 32]);
+        assert_eq!(config.fork_version, [0x10, 0x00, 0x00, 0x38]);
+        assert_eq!(config.genesis_time, 0);
+
+        let back: NetworkConfig = serde_json::from_value(serde_json::to_value(&config).unwrap()).unwrap();
+        assert_eq!(back, config);
+
+        let short = json.replace("0x10000038", "0x1000");
+        assert!(serde_json::from_str::<NetworkConfig>(&short).is_err());
+    }
+}
@@ -0,0 +1,241 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// A 256-bit unsigned integer, stored as 32 big-endian bytes.
+///
+/// Used for balances, storage values and base fees — anything the EVM treats
+/// as a uint256. Byte order matches the RLP/trie encoding, so values decoded
+/// from proofs can be wrapped without conversion. Arithmetic is checked:
+/// a verified value silently wrapping around is never acceptable.
+#[derive(Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct U256([u8; 32]);
+
+impl U256 {
+    /// The value 0.
+    pub const ZERO: U256 = U256([0u8; 32]);
+
+    /// The largest representable value (2^256 - 1).
+    pub const MAX: U256 = U256([0xFF; 32]);
+
+    /// Wrap 32 big-endian bytes.
+    pub const fn from_be_bytes(bytes: [u8; 32]) -> Self {
+        Self(bytes)
+    }
+
+    /// Big-endian bytes, right-aligned in 32 bytes (storage slot layout).
+    pub const fn to_be_bytes(&self) -> [u8; 32] {
+        self.0
+    }
+
+    /// Parse up to 32 big-endian bytes (as found in RLP-encoded integers).
+    /// Returns None if the slice is longer than 32 bytes.
+    pub fn from_be_slice(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() > 32 {
+            return None;
+        }
+        let mut arr = [0u8; 32];
+        arr[32 - bytes.len()..].copy_from_slice(bytes);
+        Some(Self(arr))
+    }
+
+    pub fn from_u64(value: u64) -> Self {
+        let mut arr = [0u8; 32];
+        arr[24..].copy_from_slice(&value.to_be_bytes());
+        Self(arr)
+    }
+
+    /// Convert to u64, returning None if the value does not fit.
+    pub fn to_u64(&self) -> Option<u64> {
+        if self.0[..24].iter().any(|&b| b != 0) {
+            return None;
+        }
+        let mut arr = [0u8; 8];
+        arr.copy_from_slice(&self.0[24..]);
+        Some(u64::from_be_bytes(arr))
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.0 == [0u8; 32]
+    }
+
+    /// Parse a hex quantity ("0x1a", "1a") or a decimal string ("26").
+    /// Hex is recognised by the 0x prefix only — bare digits are decimal,
+    /// matching the beacon API (decimal) and JSON-RPC (0x-hex) conventions.
+    pub fn from_str_auto(s: &str) -> Result<Self, String> {
+        match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+            Some(hex_digits) => Self::from_hex_str(hex_digits),
+            None => Self::from_dec_str(s),
+        }
+    }
+
+    /// Parse a hex string (with or without 0x prefix, any length up to 64 digits).
+    pub fn from_hex_str(s: &str) -> Result<Self, String> {
+        let s = s.strip_prefix("0x").unwrap_or(s);
+        if s.is_empty() {
+            return Ok(Self::ZERO);
+        }
+        alloy_primitives::U256::from_str_radix(s, 16)
+            .map(Self::from_alloy)
+            .map_err(|e| format!("invalid hex uint256 {:?}: {}", s, e))
+    }
+
+    /// Parse a decimal string.
+    pub fn from_dec_str(s: &str) -> Result<Self, String> {
+        alloy_primitives::U256::from_str_radix(s, 10)
+            .map(Self::from_alloy)
+            .map_err(|e| format!("invalid decimal uint256 {:?}: {}", s, e))
+    }
+
+    /// Minimal 0x-prefixed hex quantity ("0x0", "0x64"), as used by JSON-RPC.
+    pub fn to_hex(&self) -> String {
+        format!("0x{:x}", self.to_alloy())
+    }
+
+    /// Decimal representation.
+    pub fn to_dec_string(&self) -> String {
+        self.to_alloy().to_string()
+    }
+
+    pub fn checked_add(&self, other: &U256) -> Option<U256> {
+        self.to_alloy().checked_add(other.to_alloy()).map(Self::from_alloy)
+    }
+
+    pub fn checked_sub(&self, other: &U256) -> Option<U256> {
+        self.to_alloy().checked_sub(other.to_alloy()).map(Self::from_alloy)
+    }
+
+    pub fn checked_mul(&self, other: &U256) -> Option<U256> {
+        self.to_alloy().checked_mul(other.to_alloy()).map(Self::from_alloy)
+    }
+
+    /// Integer division; None on division by zero.
+    pub fn checked_div(&self, other: &U256) -> Option<U256> {
+        self.to_alloy().checked_div(other.to_alloy()).map(Self::from_alloy)
+    }
+
+    pub fn saturating_sub(&self, other: &U256) -> U256 {
+        Self::from_alloy(self.to_alloy().saturating_sub(other.to_alloy()))
+    }
+
+    fn to_alloy(self) -> alloy_primitives::U256 {
+        alloy_primitives::U256::from_be_bytes(self.0)
+    }
+
+    fn from_alloy(value: alloy_primitives::U256) -> Self {
+        Self(value.to_be_bytes::<32>())
+    }
+}
+
+impl From<u64> for U256 {
+    fn from(value: u64) -> Self {
+        Self::from_u64(value)
+    }
+}
+
+impl fmt::Display for U256 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_dec_string())
+    }
+}
+
+impl fmt::Debug for U256 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "U256({})", self.to_hex())
+    }
+}
+
+impl fmt::LowerHex for U256 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::LowerHex::fmt(&self.to_alloy(), f)
+    }
+}
+
+/// Serialized as a 0x-prefixed hex quantity; deserializes hex or decimal strings.
+impl Serialize for U256 {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_hex())
+    }
+}
+
+impl<'de> Deserialize<'de> for U256 {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        Self::from_str_auto(&s).map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hex_formatting() {
+        assert_eq!(U256::ZERO.to_hex(), "0x0");
+        assert_eq!(U256::from_u64(100).to_hex(), "0x64");
+        assert_eq!(U256::MAX.to_hex(), format!("0x{}", "f".repeat(64)));
+    }
+
+    #[test]
+    fn test_decimal_formatting() {
+        assert_eq!(U256::ZERO.to_string(), "0");
+        assert_eq!(U256::from_u64(1_000_000_007).to_string(), "1000000007");
+        let one_ether = U256::from_dec_str("1000000000000000000").unwrap();
+        assert_eq!(one_ether.to_hex(), "0xde0b6b3a7640000");
+    }
+
+    #[test]
+    fn test_parse_auto() {
+        assert_eq!(U256::from_str_auto("0x64").unwrap(), U256::from_u64(100));
+        assert_eq!(U256::from_str_auto("100").unwrap(), U256::from_u64(100));
+        assert_eq!(U256::from_str_auto("0x").unwrap(), U256::ZERO);
+        assert!(U256::from_str_auto("0xzz").is_err());
+        assert!(U256::from_hex_str(&"1".repeat(65)).is_err());
+    }
+
+    #[test]
+    fn test_be_slice_round_trip() {
+        let value = U256::from_be_slice(&[0x01, 0x00]).unwrap();
+        assert_eq!(value, U256::from_u64(256));
+        assert_eq!(value.to_be_bytes()[30..], [0x01, 0x00]);
+        assert!(U256::from_be_slice(&[0u8; 33]).is_none());
+        assert_eq!(U256::from_be_slice(&[]).unwrap(), U256::ZERO);
+    }
+
+    #[test]
+    fn test_ordering_is_numeric() {
+        assert!(U256::from_u64(256) > U256::from_u64(255));
+        assert!(U256::MAX > U256::from_u64(u64::MAX));
+        assert!(U256::ZERO < U256::from_u64(1));
+    }
+
+    #[test]
+    fn test_checked_arithmetic() {
+        let a = U256::from_u64(10);
+        let b = U256::from_u64(3);
+        assert_eq!(a.checked_add(&b), Some(U256::from_u64(13)));
+        assert_eq!(a.checked_sub(&b), Some(U256::from_u64(7)));
+        assert_eq!(b.checked_sub(&a), None);
+        assert_eq!(a.checked_mul(&b), Some(U256::from_u64(30)));
+        assert_eq!(a.checked_div(&b), Some(U256::from_u64(3)));
+        assert_eq!(a.checked_div(&U256::ZERO), None);
+        assert_eq!(U256::MAX.checked_add(&U256::from_u64(1)), None);
+        assert_eq!(b.saturating_sub(&a), U256::ZERO);
+    }
+
+    #[test]
+    fn test_u64_conversion() {
+        assert_eq!(U256::from_u64(u64::MAX).to_u64(), Some(u64::MAX));
+        assert_eq!(U256::MAX.to_u64(), None);
+    }
+
+    #[test]
+    fn test_serde_round_trip() {
+        let value = U256::from_u64(0xdead);
+        let json = serde_json::to_string(&value).unwrap();
+        assert_eq!(json, "\"0xdead\"");
+        let back: U256 = serde_json::from_str(&json).unwrap();
+        assert_eq!(back, value);
+        let from_dec: U256 = serde_json::from_str("\"57005\"").unwrap();
+        assert_eq!(from_dec, value);
+    }
+}
@@ -0,0 +1,328 @@
+//! Bytes moved, per peer and per transport.
+//!
+//! [`build_transport`](crate::transport::build_transport) wraps each
+//! connection's muxer in a [`BandwidthMeter`], which counts what every
+//! substream reads and writes, after Noise and Yamux framing are taken off.
+//! The service samples the meter into `TransportStats`, so developers can
+//! see what the light client actually moves, and spot a peer flooding us.
+//!
+//! Relayed connections run inside the connection to the relay, so their
+//! bytes are counted against the relay. Peers are remembered for the
+//! session once their connections close, up to [`MAX_METERED_PEERS`]; past
+//! that the one that moved least is forgotten.
+
+use crate::transport::TransportType;
+use futures::io::{AsyncRead, AsyncWrite, IoSlice, IoSliceMut};
+use futures::ready;
+use libp2p::core::muxing::{StreamMuxer, StreamMuxerBox, StreamMuxerEvent, SubstreamBox};
+use libp2p::PeerId;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+
+/// Most peers whose bytes are kept once they disconnect.
+pub const MAX_METERED_PEERS: usize = 256;
+
+/// Bytes sent and received.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Bandwidth {
+    pub bytes_sent: u64,
+    pub bytes_received: u64,
+}
+
+impl Bandwidth {
+    pub fn total(&self) -> u64 {
+        self.bytes_sent.saturating_add(self.bytes_received)
+    }
+
+    fn add(&mut self, other: Bandwidth) {
+        self.bytes_sent = self.bytes_sent.saturating_add(other.bytes_sent);
+        self.bytes_received = self.bytes_received.saturating_add(other.bytes_received);
+    }
+}
+
+/// What one peer moved this session.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PeerBandwidth {
+    pub peer_id: String,
+    /// The transport of its latest connection.
+    pub transport: TransportType,
+    pub bandwidth: Bandwidth,
+    /// Whether it is still connected.
+    pub connected: bool,
+}
+
+/// A sample of the [`BandwidthMeter`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BandwidthSample {
+    pub total: Bandwidth,
+    pub by_transport: Vec<(TransportType, Bandwidth)>,
+    /// Peers that received the most from us or sent the most to us first.
+    pub by_peer: Vec<PeerBandwidth>,
+}
+
+/// One connection's counters, shared by its muxer and substreams.
+#[derive(Debug, Default)]
+struct Counters {
+    sent: AtomicU64,
+    received: AtomicU64,
+}
+
+impl Counters {
+    fn bandwidth(&self) -> Bandwidth {
+        Bandwidth {
+            bytes_sent: self.sent.load(Ordering::Relaxed),
+            bytes_received: self.received.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Metered {
+    peer: PeerId,
+    transport: TransportType,
+    counters: Arc<Counters>,
+}
+
+#[derive(Debug, Default)]
+struct MeterState {
+    open: Vec<Metered>,
+    /// What closed connections moved, per peer, with the transport of the
+    /// latest.
+    closed: HashMap<PeerId, (TransportType, Bandwidth)>,
+    closed_by_transport: Vec<(TransportType, Bandwidth)>,
+}
+
+/// See the module docs. Clones count into the same totals.
+#[derive(Clone, Debug, Default)]
+pub struct BandwidthMeter(Arc<Mutex<MeterState>>);
+
+impl BandwidthMeter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Count what `muxer`, a connection to `peer` over `transport`, moves.
+    pub fn meter(&self, peer: PeerId, transport: TransportType, muxer: StreamMuxerBox) -> StreamMuxerBox {
+        StreamMuxerBox::new(MeteredMuxer { inner: muxer, counters: self.open(peer, transport) })
+    }
+
+    /// Sum up what every connection has moved so far.
+    pub fn sample(&self) -> BandwidthSample {
+        let mut state = self.0.lock().expect("bandwidth lock");
+        state.fold_closed();
+        let mut by_transport = state.closed_by_transport.clone();
+        let mut by_peer: HashMap<PeerId, PeerBandwidth> = state
+            .closed
+            .iter()
+            .map(|(peer, (transport, bandwidth))| {
+                let peer_id = peer.to_string();
+                (*peer, PeerBandwidth { peer_id, transport: *transport, bandwidth: *bandwidth, connected: false })
+            })
+            .collect();
+        for metered in &state.open {
+            let bandwidth = metered.counters.bandwidth();
+            add_to(&mut by_transport, metered.transport, bandwidth);
+            let peer = by_peer.entry(metered.peer).or_insert_with(|| PeerBandwidth {
+                peer_id: metered.peer.to_string(),
+                transport: metered.transport,
+                bandwidth: Bandwidth::default(),
+                connected: true,
+            });
+            peer.transport = metered.transport;
+            peer.bandwidth.add(bandwidth);
+            peer.connected = true;
+        }
+        let mut total = Bandwidth::default();
+        for (_, bandwidth) in &by_transport {
+            total.add(*bandwidth);
+        }
+        let mut by_peer: Vec<PeerBandwidth> = by_peer.into_values().collect();
+        by_peer.sort_by(|a, b| b.bandwidth.total().cmp(&a.bandwidth.total()).then(a.peer_id.cmp(&b.peer_id)));
+        BandwidthSample { total, by_transport, by_peer }
+    }
+
+    fn open(&self, peer: PeerId, transport: TransportType) -> Arc<Counters> {
+        let counters = Arc::new(Counters::default());
+        let mut state = self.0.lock().expect("bandwidth lock");
+        state.fold_closed();
+        state.open.push(Metered { peer, transport, counters: counters.clone() });
+        counters
+    }
+}
+
+impl MeterState {
+    /// Move connections whose muxer and substreams are all gone into the
+    /// closed totals.
+    fn fold_closed(&mut self) {
+        let (closed, open) = std::mem::take(&mut self.open)
+            .into_iter()
+            .partition(|metered| Arc::strong_count(&metered.counters) == 1);
+        self.open = open;
+        for metered in closed {
+            let bandwidth = metered.counters.bandwidth();
+            add_to(&mut self.closed_by_transport, metered.transport, bandwidth);
+            let closed = self.closed.entry(metered.peer).or_insert((metered.transport, Bandwidth::default()));
+            closed.0 = metered.transport;
+            closed.1.add(bandwidth);
+        }
+        while self.closed.len() > MAX_METERED_PEERS {
+            let least = self.closed.iter().min_by_key(|(_, (_, bandwidth))| bandwidth.total()).map(|(peer, _)| *peer);
+            if let Some(peer) = least {
+                self.closed.remove(&peer);
+            }
+        }
+    }
+}
+
+fn add_to(by_transport: &mut Vec<(TransportType, Bandwidth)>, transport: TransportType, bandwidth: Bandwidth) {
+    match by_transport.iter_mut().find(|(t, _)| *t == transport) {
+        Some((_, total)) => total.add(bandwidth),
+        None => by_transport.push((transport, bandwidth)),
+    }
+}
+
+/// A connection's muxer, handing out counted substreams.
+struct MeteredMuxer {
+    inner: StreamMuxerBox,
+    counters: Arc<Counters>,
+}
+
+impl StreamMuxer for MeteredMuxer {
+    type Substream = MeteredStream<SubstreamBox>;
+    type Error = io::Error;
+
+    fn poll_inbound(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<Self::Substream, Self::Error>> {
+        let this = self.get_mut();
+        let inner = ready!(Pin::new(&mut this.inner).poll_inbound(cx))?;
+        Poll::Ready(Ok(MeteredStream::new(inner, this.counters.clone())))
+    }
+
+    fn poll_outbound(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<Self::Substream, Self::Error>> {
+        let this = self.get_mut();
+        let inner = ready!(Pin::new(&mut this.inner).poll_outbound(cx))?;
+        Poll::Ready(Ok(MeteredStream::new(inner, this.counters.clone())))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Pin::new(&mut self.get_mut().inner).poll_close(cx)
+    }
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<StreamMuxerEvent, Self::Error>> {
+        Pin::new(&mut self.get_mut().inner).poll(cx)
+    }
+}
+
+/// A substream that counts what it reads and writes.
+struct MeteredStream<S> {
+    inner: S,
+    counters: Arc<Counters>,
+}
+
+impl<S> MeteredStream<S> {
+    fn new(inner: S, counters: Arc<Counters>) -> Self {
+        Self { inner, counters }
+    }
+}
+
+impl<S: AsyncRead + Unpin> AsyncRead for MeteredStream<S> {
+    fn poll_read(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut [u8]) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        let read = ready!(Pin::new(&mut this.inner).poll_read(cx, buf))?;
+        this.counters.received.fetch_add(read as u64, Ordering::Relaxed);
+        Poll::Ready(Ok(read))
+    }
+
+    fn poll_read_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &mut [IoSliceMut<'_>],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        let read = ready!(Pin::new(&mut this.inner).poll_read_vectored(cx, bufs))?;
+        this.counters.received.fetch_add(read as u64, Ordering::Relaxed);
+        Poll::Ready(Ok(read))
+    }
+}
+
+impl<S: AsyncWrite + Unpin> AsyncWrite for MeteredStream<S> {
+    fn poll_write(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        let written = ready!(Pin::new(&mut this.inner).poll_write(cx, buf))?;
+        this.counters.sent.fetch_add(written as u64, Ordering::Relaxed);
+        Poll::Ready(Ok(written))
+    }
+
+    fn poll_write_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        let written = ready!(Pin::new(&mut this.inner).poll_write_vectored(cx, bufs))?;
+        this.counters.sent.fetch_add(written as u64, Ordering::Relaxed);
+        Poll::Ready(Ok(written))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_flush(cx)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_close(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::io::{AsyncReadExt, AsyncWriteExt, Cursor};
+
+    #[tokio::test]
+    async fn test_substreams_are_counted() {
+        let meter = BandwidthMeter::new();
+        let peer = PeerId::random();
+        let counters = meter.open(peer, TransportType::WebSocket);
+        let mut stream = MeteredStream::new(Cursor::new(vec![7u8; 100]), counters.clone());
+        let mut buf = [0u8; 60];
+        stream.read_exact(&mut buf).await.unwrap();
+        stream.write_all(&[1u8; 25]).await.unwrap();
+
+        let sample = meter.sample();
+        let expected = Bandwidth { bytes_sent: 25, bytes_received: 60 };
+        assert_eq!(sample.total, expected);
+        assert_eq!(sample.by_transport, [(TransportType::WebSocket, expected)]);
+        assert_eq!(sample.by_peer.len(), 1);
+        assert!(sample.by_peer[0].connected);
+
+        // Once closed, the peer's bytes are kept, and its next connection
+        // adds to them.
+        drop((stream, counters));
+        let counters = meter.open(peer, TransportType::WebTransport);
+        let mut stream = MeteredStream::new(Cursor::new(vec![0u8; 10]), counters);
+        stream.write_all(&[1u8; 5]).await.unwrap();
+        let sample = meter.sample();
+        assert_eq!(sample.total, Bandwidth { bytes_sent: 30, bytes_received: 60 });
+        assert_eq!(sample.by_transport.len(), 2);
+        let peer = &sample.by_peer[0];
+        assert_eq!(peer.transport, TransportType::WebTransport);
+        assert_eq!((peer.bandwidth.bytes_sent, peer.connected), (30, true));
+    }
+
+    #[test]
+    fn test_quiet_peers_are_forgotten_first() {
+        let meter = BandwidthMeter::new();
+        for i in 0..MAX_METERED_PEERS as u64 + 1 {
+            let counters = meter.open(PeerId::random(), TransportType::WebSocket);
+            counters.received.store(i + 1, Ordering::Relaxed);
+        }
+        let sample = meter.sample();
+        assert_eq!(sample.by_peer.len(), MAX_METERED_PEERS);
+        assert!(sample.by_peer.iter().all(|peer| !peer.connected && peer.bandwidth.bytes_received > 1));
+        assert_eq!(sample.by_peer[0].bandwidth.bytes_received, MAX_METERED_PEERS as u64 + 1);
+    }
+}
@@ -1,33 +1,177 @@
 //! Beacon chain gossip topic subscription and message handling.
 //!
 //! Subscribes to Ethereum beacon chain gossip topics to receive
-//! light client updates in real time.
+//! light client updates in real time. Topics name the fork they are for by
+//! its fork digest, so they are built at runtime from a [`ForkSchedule`],
+//! which also says when to move to the next fork's.
 //!
 //! All received messages are raw SSZ bytes that must be passed to
 //! lumen-core for cryptographic verification. This module does NOT
 //! interpret or trust any data — it only handles transport.
 
+use lumen_core::blob::sidecar::DecodedBlobSidecar;
+use lumen_core::consensus::block::DecodedBeaconBlock;
+use lumen_core::consensus::clock::SlotClock;
+use lumen_core::consensus::sync_committee::{compute_fork_digest, hash_beacon_block_header, VerificationError};
+use lumen_core::types::beacon::{BeaconBlockHeader, ExecutionPayloadHeader, SLOTS_PER_EPOCH};
+use lumen_core::types::network::NetworkConfig;
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::time::Duration;
 
-/// The gossip topic for light client finality updates.
+/// The topic name of light client finality updates.
 /// This is the main feed of new verified chain heads.
-///
-/// Topic format: /eth2/{fork_digest}/light_client_finality_update/ssz_snappy
-/// fork_digest for mainnet Deneb: b5303f2a
-pub const LIGHT_CLIENT_FINALITY_UPDATE_TOPIC: &str =
-    "/eth2/b5303f2a/light_client_finality_update/ssz_snappy";
+pub const LIGHT_CLIENT_FINALITY_UPDATE: &str = "light_client_finality_update";
 
 /// Optimistic updates arrive faster (before finality) — useful for lower latency.
 /// These are verified with the same sync committee signatures but represent
 /// a less-certain view of the chain head.
-pub const LIGHT_CLIENT_OPTIMISTIC_UPDATE_TOPIC: &str =
-    "/eth2/b5303f2a/light_client_optimistic_update/ssz_snappy";
+pub const LIGHT_CLIENT_OPTIMISTIC_UPDATE: &str = "light_client_optimistic_update";
+
+/// The topic name of full beacon blocks, not subscribed to by default:
+/// `LumenP2pService::subscribe_topic(BEACON_BLOCK)` opts in. A block's
+/// execution payload header can stand in for the one a finality update
+/// carries (see [`RecentBlocks`]), but Lumen cannot check a block's
+/// proposer signature, so blocks are never forwarded: report them
+/// `GossipValidation::Ignore`.
+pub const BEACON_BLOCK: &str = "beacon_block";
+
+/// Blob sidecar subnets (`BLOB_SIDECAR_SUBNET_COUNT_ELECTRA`).
+pub const BLOB_SIDECAR_SUBNET_COUNT: u64 = 9;
+
+/// The topic name of blob sidecars on `subnet`: `blob_sidecar_{subnet}`.
+/// Not subscribed to by default: `LumenP2pService::subscribe_blob_sidecars`
+/// opts in to every subnet. As with blocks, the proposer signature of a
+/// sidecar's header is not checked, so sidecars are never forwarded:
+/// report them `GossipValidation::Ignore`, and see [`PendingBlobSidecars`]
+/// for when one can be trusted.
+pub fn blob_sidecar_topic_name(subnet: u64) -> String {
+    format!("blob_sidecar_{}", subnet)
+}
+
+/// The subnet the sidecar of the blob at `index` is gossiped on.
+pub fn blob_sidecar_subnet(index: u64) -> u64 {
+    index % BLOB_SIDECAR_SUBNET_COUNT
+}
+
+/// The names of the beacon gossip topics Lumen subscribes to from the
+/// start. More can be added with `LumenP2pService::subscribe_topic`.
+pub const TOPIC_NAMES: &[&str] = &[LIGHT_CLIENT_FINALITY_UPDATE, LIGHT_CLIENT_OPTIMISTIC_UPDATE];
+
+/// Epochs before a fork that its topics are subscribed to, and after it
+/// that the previous fork's are kept, so no gossip is missed while peers
+/// switch over (the p2p spec suggests two).
+pub const FORK_TOPIC_OVERLAP_EPOCHS: u64 = 2;
+
+/// The topic `name` for the fork with `fork_digest`:
+/// `/eth2/{fork_digest}/{name}/ssz_snappy`.
+pub fn gossip_topic(fork_digest: [u8; 4], name: &str) -> String {
+    format!("/eth2/{}/{}/ssz_snappy", hex::encode(fork_digest), name)
+}
+
+/// The name in a topic `/eth2/{fork_digest}/{name}/{encoding}`.
+pub fn topic_name(topic: &str) -> Option<&str> {
+    let mut parts = topic.strip_prefix("/eth2/")?.split('/');
+    let (_digest, name, _encoding) = (parts.next()?, parts.next()?, parts.next()?);
+    (parts.next().is_none() && !name.is_empty()).then_some(name)
+}
+
+/// All the topics of [`TOPIC_NAMES`] for the fork with `fork_digest`.
+pub fn light_client_topics(fork_digest: [u8; 4]) -> Vec<String> {
+    TOPIC_NAMES.iter().map(|name| gossip_topic(fork_digest, name)).collect()
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ScheduledFork {
+    pub epoch: u64,
+    pub fork_version: [u8; 4],
+}
 
-/// All beacon gossip topics that Lumen subscribes to.
-pub const ALL_TOPICS: &[&str] = &[
-    LIGHT_CLIENT_FINALITY_UPDATE_TOPIC,
-    LIGHT_CLIENT_OPTIMISTIC_UPDATE_TOPIC,
-];
+/// The forks a network goes through, for the fork digests in its topics.
+///
+/// A schedule starts with the fork in effect (its epoch does not matter)
+/// and lists upcoming ones with [`ForkSchedule::with_fork`], so a fork
+/// announced after a release can be followed by configuring it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ForkSchedule {
+    pub genesis_validators_root: [u8; 32],
+    /// Unix time (seconds) of genesis. 0 if unknown, which keeps the
+    /// schedule at its first fork.
+    pub genesis_time: u64,
+    /// In ascending order of epoch.
+    pub forks: Vec<ScheduledFork>,
+}
+
+impl ForkSchedule {
+    /// The schedule of `network`, with only its current fork.
+    pub fn new(network: &NetworkConfig) -> Self {
+        Self {
+            genesis_validators_root: network.genesis_validators_root,
+            genesis_time: network.genesis_time,
+            forks: vec![ScheduledFork { epoch: 0, fork_version: network.fork_version }],
+        }
+    }
+
+    /// Add the fork to `fork_version` at `epoch`.
+    pub fn with_fork(mut self, epoch: u64, fork_version: [u8; 4]) -> Self {
+        let at = self.forks.partition_point(|fork| fork.epoch <= epoch);
+        self.forks.insert(at, ScheduledFork { epoch, fork_version });
+        self
+    }
+
+    /// The epoch in progress at `unix_ms`, if genesis is known.
+    pub fn epoch_at(&self, unix_ms: u64) -> Option<u64> {
+        (self.genesis_time != 0).then(|| SlotClock::new(self.genesis_time).slot_at(unix_ms) / SLOTS_PER_EPOCH)
+    }
+
+    /// Unix time (milliseconds) at which `epoch` starts.
+    pub fn epoch_start_ms(&self, epoch: u64) -> u64 {
+        SlotClock::new(self.genesis_time).slot_start_ms(epoch.saturating_mul(SLOTS_PER_EPOCH))
+    }
+
+    /// Index of the fork in effect at `epoch`: the first before any.
+    fn index_at(&self, epoch: u64) -> usize {
+        self.forks.partition_point(|fork| fork.epoch <= epoch).saturating_sub(1)
+    }
+
+    fn digest(&self, index: usize) -> [u8; 4] {
+        compute_fork_digest(&self.forks[index].fork_version, &self.genesis_validators_root)
+    }
+
+    /// The fork digest in effect at `epoch`.
+    pub fn fork_digest_at(&self, epoch: u64) -> [u8; 4] {
+        self.digest(self.index_at(epoch))
+    }
+
+    /// The fork digests whose topics to be subscribed to at `epoch`: the
+    /// one in effect, then the previous fork's for
+    /// [`FORK_TOPIC_OVERLAP_EPOCHS`] after it ended and the next fork's as
+    /// long before it starts.
+    pub fn topic_digests_at(&self, epoch: u64) -> Vec<[u8; 4]> {
+        let index = self.index_at(epoch);
+        let mut digests = vec![self.digest(index)];
+        if index > 0 && epoch < self.forks[index].epoch.saturating_add(FORK_TOPIC_OVERLAP_EPOCHS) {
+            digests.push(self.digest(index - 1));
+        }
+        if self.forks.get(index + 1).is_some_and(|next| epoch.saturating_add(FORK_TOPIC_OVERLAP_EPOCHS) >= next.epoch) {
+            digests.push(self.digest(index + 1));
+        }
+        digests.dedup();
+        digests
+    }
+
+    /// The first epoch after `epoch` at which [`Self::topic_digests_at`]
+    /// changes, if any.
+    pub fn next_topic_change(&self, epoch: u64) -> Option<u64> {
+        let overlap = FORK_TOPIC_OVERLAP_EPOCHS;
+        self.forks
+            .iter()
+            .skip(1)
+            .flat_map(|fork| [fork.epoch.saturating_sub(overlap), fork.epoch.saturating_add(overlap)])
+            .filter(|&change| change > epoch)
+            .min()
+    }
+}
 
 /// A message received from a beacon chain gossip topic.
 /// Contains raw SSZ bytes that need to be deserialized and verified.
@@ -43,6 +187,211 @@ pub struct GossipMessage {
     pub message_id: Vec<u8>,
 }
 
+/// What checking a gossip message found, reported back to gossipsub: it
+/// forwards only accepted messages, and scores the peer that sent one by
+/// it (see `LumenP2pService::report_validation`).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GossipValidation {
+    /// Valid: forward it.
+    Accept,
+    /// Invalid: drop it and penalize the sender.
+    Reject,
+    /// Drop it without penalty, e.g. a stale update.
+    Ignore,
+}
+
+impl GossipValidation {
+    /// The validation for what lumen-core's verification of an update
+    /// returned. Updates that are not newer than ours, or signed in the
+    /// future by our clock, are ignored as the p2p spec says; so are those
+    /// our state cannot check, such as one from a period whose committee we
+    /// do not know yet. Every other failure is the sender's.
+    pub fn from_verification(result: &Result<(), VerificationError>) -> Self {
+        match result {
+            Ok(()) => Self::Accept,
+            Err(VerificationError::UpdateNotNewer { .. })
+            | Err(VerificationError::FutureSlot { .. })
+            | Err(VerificationError::BlsError(_)) => Self::Ignore,
+            Err(_) => Self::Reject,
+        }
+    }
+}
+
+/// How long a verified message's outcome is remembered: long after
+/// gossipsub's own duplicate cache, a minute, has forgotten it.
+pub const VERIFIED_MESSAGE_TTL: Duration = Duration::from_secs(5 * 60);
+
+/// Most message outcomes remembered; the least recently seen go first.
+pub const VERIFIED_MESSAGE_CAPACITY: usize = 1024;
+
+/// The outcomes of messages already verified, by message id, so one that
+/// arrives again is answered from here instead of being verified again.
+/// Only accepted and rejected messages are kept: an ignored one may be
+/// worth checking later.
+#[derive(Debug, Default)]
+pub struct VerifiedMessages {
+    /// Outcomes, and when they expire (Unix milliseconds).
+    outcomes: HashMap<Vec<u8>, (GossipValidation, u64)>,
+    /// Message ids, least recently seen first.
+    order: VecDeque<Vec<u8>>,
+}
+
+impl VerifiedMessages {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The outcome of the message with `id`, if it was verified and has not
+    /// expired. Marks it as recently seen.
+    pub fn get(&mut self, id: &[u8], now_ms: u64) -> Option<GossipValidation> {
+        let (validation, expires_ms) = *self.outcomes.get(id)?;
+        self.order.retain(|seen| seen != id);
+        if now_ms >= expires_ms {
+            self.outcomes.remove(id);
+            return None;
+        }
+        self.order.push_back(id.to_vec());
+        Some(validation)
+    }
+
+    /// Remember the outcome of verifying the message with `id`.
+    pub fn insert(&mut self, id: Vec<u8>, validation: GossipValidation, now_ms: u64) {
+        if validation == GossipValidation::Ignore {
+            return;
+        }
+        self.order.retain(|seen| *seen != id);
+        while self.order.len() >= VERIFIED_MESSAGE_CAPACITY {
+            if let Some(oldest) = self.order.pop_front() {
+                self.outcomes.remove(&oldest);
+            }
+        }
+        let expires_ms = now_ms.saturating_add(VERIFIED_MESSAGE_TTL.as_millis() as u64);
+        self.outcomes.insert(id.clone(), (validation, expires_ms));
+        self.order.push_back(id);
+    }
+
+    pub fn len(&self) -> usize {
+        self.outcomes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.outcomes.is_empty()
+    }
+}
+
+/// Blocks kept for [`RecentBlocks`]: four epochs, enough for a finalized
+/// header to still find its block when the finality update names it.
+pub const RECENT_BLOCKS_CAPACITY: usize = 4 * SLOTS_PER_EPOCH as usize;
+
+/// Decoded `beacon_block` gossip, by block root, until a verified header
+/// names one of them. Its execution payload header is then taken from the
+/// block, proven by the block's execution branch against the verified
+/// header's `body_root`, instead of trusted from an update. The oldest
+/// blocks go first.
+#[derive(Debug, Default)]
+pub struct RecentBlocks {
+    blocks: HashMap<[u8; 32], DecodedBeaconBlock>,
+    /// Block roots, oldest first.
+    order: VecDeque<[u8; 32]>,
+}
+
+impl RecentBlocks {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, block: DecodedBeaconBlock) {
+        let root = block.root();
+        if self.blocks.insert(root, block).is_some() {
+            return;
+        }
+        self.order.push_back(root);
+        while self.order.len() > RECENT_BLOCKS_CAPACITY {
+            if let Some(oldest) = self.order.pop_front() {
+                self.blocks.remove(&oldest);
+            }
+        }
+    }
+
+    /// The execution payload header of the block `verified` names, if it
+    /// arrived and its execution branch verifies. `verified` must have
+    /// passed sync committee verification.
+    pub fn execution_for(&self, verified: &BeaconBlockHeader) -> Option<ExecutionPayloadHeader> {
+        let block = self.blocks.get(&hash_beacon_block_header(verified))?;
+        block.execution_for(verified).ok().cloned()
+    }
+
+    pub fn len(&self) -> usize {
+        self.blocks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.blocks.is_empty()
+    }
+}
+
+/// Sidecars kept for [`PendingBlobSidecars`]. Each holds a 128 KiB blob,
+/// so only a few blocks' worth.
+pub const PENDING_BLOB_SIDECARS_CAPACITY: usize = 64;
+
+/// Decoded `blob_sidecar_{subnet}` gossip, until a verified header names
+/// the block of some of them. Those are then checked with lumen-core's
+/// [`DecodedBlobSidecar::verify_for`] (inclusion proof against the
+/// verified `body_root`, then the KZG proof), and the blobs that pass are
+/// available: a trustless signal straight from gossip. The oldest
+/// sidecars go first. Without the `kzg` feature no blob passes.
+#[derive(Debug, Default)]
+pub struct PendingBlobSidecars {
+    /// Sidecars with their block roots, oldest first.
+    sidecars: VecDeque<([u8; 32], DecodedBlobSidecar)>,
+}
+
+impl PendingBlobSidecars {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, sidecar: DecodedBlobSidecar) {
+        let root = sidecar.block_root();
+        let index = sidecar.sidecar.index;
+        if self.sidecars.iter().any(|(r, pending)| *r == root && pending.sidecar.index == index) {
+            return;
+        }
+        self.sidecars.push_back((root, sidecar));
+        while self.sidecars.len() > PENDING_BLOB_SIDECARS_CAPACITY {
+            self.sidecars.pop_front();
+        }
+    }
+
+    /// Take out the sidecars of the block `verified` names, and return the
+    /// index and versioned hash of each blob that verifies, by index. Those
+    /// that do not are dropped. `verified` must have passed sync committee
+    /// verification.
+    pub fn take_available(&mut self, verified: &BeaconBlockHeader) -> Vec<(u64, [u8; 32])> {
+        let root = hash_beacon_block_header(verified);
+        let mut available = Vec::new();
+        self.sidecars.retain(|(block_root, sidecar)| {
+            if *block_root != root {
+                return true;
+            }
+            if let Ok(versioned_hash) = sidecar.verify_for(verified) {
+                available.push((sidecar.sidecar.index, versioned_hash));
+            }
+            false
+        });
+        available.sort_by_key(|(index, _)| *index);
+        available
+    }
+
+    pub fn len(&self) -> usize {
+        self.sidecars.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sidecars.is_empty()
+    }
+}
+
 /// The type of gossip message received.
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum GossipMessageType {
@@ -52,6 +401,10 @@ pub enum GossipMessageType {
     /// An optimistic update — a new block has been attested but not finalized.
     /// Lower latency but slightly weaker guarantee.
     OptimisticUpdate,
+    /// A full beacon block, trusted only once a verified header names it.
+    BeaconBlock,
+    /// A blob sidecar, on any subnet; likewise.
+    BlobSidecar,
     /// Unknown topic.
     Unknown(String),
 }
@@ -63,6 +416,13 @@ impl GossipMessageType {
             Self::FinalityUpdate
         } else if topic.contains("light_client_optimistic_update") {
             Self::OptimisticUpdate
+        } else if topic_name(topic) == Some(BEACON_BLOCK) {
+            Self::BeaconBlock
+        } else if topic_name(topic)
+            .and_then(|name| name.strip_prefix("blob_sidecar_"))
+            .is_some_and(|subnet| subnet.parse::<u64>().is_ok())
+        {
+            Self::BlobSidecar
         } else {
             Self::Unknown(topic.to_string())
         }
@@ -89,19 +449,53 @@ pub struct GossipStats {
     pub finality_updates: u64,
     /// Optimistic updates received.
     pub optimistic_updates: u64,
+    /// Beacon blocks received.
+    #[serde(default)]
+    pub beacon_blocks: u64,
+    /// Blob sidecars received, on every subnet.
+    #[serde(default)]
+    pub blob_sidecars: u64,
+    /// The mesh of each topic subscribed to, by topic. Sampled every
+    /// second.
+    #[serde(default)]
+    pub mesh: BTreeMap<String, TopicMeshStats>,
+}
+
+/// The gossipsub mesh of one topic, to tell why its messages stopped
+/// coming: no peers on the topic, peers but none in the mesh, or a mesh
+/// that keeps losing them.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TopicMeshStats {
+    /// Peers in our mesh for the topic, which send us its messages in full.
+    pub mesh_peers: usize,
+    /// Connected peers subscribed to the topic, in the mesh or not.
+    pub topic_peers: usize,
+    /// Peers added to the mesh, by our GRAFT or theirs.
+    pub grafts: u64,
+    /// Peers dropped from the mesh: by their PRUNE or ours, or for
+    /// leaving, scoring too low or being one too many.
+    pub prunes: u64,
+    /// Messages asked for with IWANT after a peer's IHAVE announced them:
+    /// ones the mesh did not bring. Gossipsub does not count IHAVEs.
+    pub iwant_requests: u64,
 }
 
 impl GossipStats {
     /// Log a summary of gossip statistics.
     pub fn summary(&self) -> String {
         format!(
-            "Gossip: {} received ({} valid, {} invalid, {} duplicate) | {} finality, {} optimistic",
+            "Gossip: {} received ({} valid, {} invalid, {} duplicate) | {} finality, {} optimistic, {} blocks, \
+             {} blobs | mesh peers on {} of {} topics",
             self.messages_received,
             self.messages_valid,
             self.messages_invalid,
             self.messages_duplicate,
             self.finality_updates,
             self.optimistic_updates,
+            self.beacon_blocks,
+            self.blob_sidecars,
+            self.mesh.values().filter(|topic| topic.mesh_peers > 0).count(),
+            self.mesh.len(),
         )
     }
 }
@@ -110,22 +504,170 @@ impl GossipStats {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_verified_messages_expire_and_evict() {
+        let mut verified = VerifiedMessages::new();
+        verified.insert(vec![1], GossipValidation::Accept, 0);
+        verified.insert(vec![2], GossipValidation::Reject, 0);
+        verified.insert(vec![3], GossipValidation::Ignore, 0);
+        assert_eq!(verified.len(), 2);
+        assert_eq!(verified.get(&[2], 1_000), Some(GossipValidation::Reject));
+        assert_eq!(verified.get(&[3], 1_000), None);
+
+        let ttl = VERIFIED_MESSAGE_TTL.as_millis() as u64;
+        assert_eq!(verified.get(&[1], ttl), None);
+        assert_eq!(verified.len(), 1);
+
+        // Full, the least recently seen go first: 2 was seen again, so 0, 1
+        // and 3 go before it.
+        let mut verified = VerifiedMessages::new();
+        for id in 0..VERIFIED_MESSAGE_CAPACITY as u32 {
+            verified.insert(id.to_le_bytes().to_vec(), GossipValidation::Accept, 0);
+        }
+        verified.get(&2u32.to_le_bytes(), 0);
+        verified.insert(vec![0xFF], GossipValidation::Accept, 0);
+        verified.insert(vec![0xFE], GossipValidation::Accept, 0);
+        verified.insert(vec![0xFD], GossipValidation::Accept, 0);
+        assert_eq!(verified.len(), VERIFIED_MESSAGE_CAPACITY);
+        assert!(verified.get(&2u32.to_le_bytes(), 0).is_some());
+        assert!(verified.get(&3u32.to_le_bytes(), 0).is_none());
+        assert!(verified.get(&4u32.to_le_bytes(), 0).is_some());
+    }
+
+    #[test]
+    fn test_validation_from_verification() {
+        assert_eq!(GossipValidation::from_verification(&Ok(())), GossipValidation::Accept);
+        let stale = VerificationError::UpdateNotNewer { update_slot: 10, current_slot: 10 };
+        assert_eq!(GossipValidation::from_verification(&Err(stale)), GossipValidation::Ignore);
+        let unknown_committee = VerificationError::BlsError("no next sync committee".to_string());
+        assert_eq!(GossipValidation::from_verification(&Err(unknown_committee)), GossipValidation::Ignore);
+        let forged = VerificationError::InvalidSignature;
+        assert_eq!(GossipValidation::from_verification(&Err(forged)), GossipValidation::Reject);
+    }
+
+    /// Mainnet Electra.
+    const ELECTRA: [u8; 4] = [0xad, 0x53, 0x2c, 0xeb];
+
     #[test]
     fn test_message_type_from_topic() {
         assert_eq!(
-            GossipMessageType::from_topic(LIGHT_CLIENT_FINALITY_UPDATE_TOPIC),
-            GossipMessageType::FinalityUpdate
-        );
-        assert_eq!(
-            GossipMessageType::from_topic(LIGHT_CLIENT_OPTIMISTIC_UPDATE_TOPIC),
-            GossipMessageType::OptimisticUpdate
+            gossip_topic(ELECTRA, LIGHT_CLIENT_FINALITY_UPDATE),
+            "/eth2/ad532ceb/light_client_finality_update/ssz_snappy"
         );
+        let topics = light_client_topics(ELECTRA);
+        assert_eq!(GossipMessageType::from_topic(&topics[0]), GossipMessageType::FinalityUpdate);
+        assert_eq!(GossipMessageType::from_topic(&topics[1]), GossipMessageType::OptimisticUpdate);
+        assert!(matches!(
+            GossipMessageType::from_topic("/eth2/ad532ceb/unknown"),
+            GossipMessageType::Unknown(_)
+        ));
+        let blob_topic = gossip_topic(ELECTRA, &blob_sidecar_topic_name(blob_sidecar_subnet(10)));
+        assert_eq!(blob_topic, "/eth2/ad532ceb/blob_sidecar_1/ssz_snappy");
+        assert_eq!(GossipMessageType::from_topic(&blob_topic), GossipMessageType::BlobSidecar);
         assert!(matches!(
-            GossipMessageType::from_topic("/eth2/b5303f2a/unknown"),
+            GossipMessageType::from_topic("/eth2/ad532ceb/blob_sidecar_x/ssz_snappy"),
             GossipMessageType::Unknown(_)
         ));
     }
 
+    /// The all-zero blob at `index`, with a header whose body root its
+    /// inclusion proof leads to. Blobs 0 and 1 share a block: each is the
+    /// other's sibling.
+    #[cfg(feature = "kzg")]
+    fn blob_sidecar(index: u64, slot: u64) -> DecodedBlobSidecar {
+        use lumen_core::blob::BlobSidecar;
+        use sha2::{Digest, Sha256};
+        let mut infinity = [0u8; 48];
+        infinity[0] = 0xc0;
+        let mut chunks = [0u8; 64];
+        chunks[..48].copy_from_slice(&infinity);
+        let mut node: [u8; 32] = Sha256::digest(chunks).into();
+        let mut proof = vec![[0x33; 32]; 17];
+        proof[0] = node;
+        let path = (11 << 13) | index; // blob_kzg_commitments, then the list's data
+        for (depth, sibling) in proof.iter().enumerate() {
+            let (left, right) = if (path >> depth) & 1 == 1 { (sibling, &node) } else { (&node, sibling) };
+            node = Sha256::new().chain_update(left).chain_update(right).finalize().into();
+        }
+        DecodedBlobSidecar {
+            sidecar: BlobSidecar {
+                index,
+                blob: vec![0u8; lumen_core::blob::BYTES_PER_BLOB],
+                kzg_commitment: infinity,
+                kzg_proof: infinity,
+            },
+            header: BeaconBlockHeader {
+                slot,
+                proposer_index: 7,
+                parent_root: [1; 32],
+                state_root: [2; 32],
+                body_root: node,
+            },
+            inclusion_proof: proof,
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "kzg")]
+    fn test_pending_blob_sidecars_for_verified_header() {
+        let mut pending = PendingBlobSidecars::new();
+        pending.insert(blob_sidecar(1, 9000));
+        pending.insert(blob_sidecar(1, 9000));
+        pending.insert(blob_sidecar(0, 9000));
+        pending.insert(blob_sidecar(0, 9001));
+        // Claims blob 2 of the same block, which its proof does not lead to.
+        let mut forged = blob_sidecar(2, 9000);
+        forged.header = blob_sidecar(0, 9000).header;
+        pending.insert(forged);
+        assert_eq!(pending.len(), 4);
+
+        let verified = blob_sidecar(0, 9000).header;
+        let available = pending.take_available(&verified);
+        let indices: Vec<u64> = available.iter().map(|(index, _)| *index).collect();
+        assert_eq!(indices, [0, 1]);
+        assert!(available.iter().all(|(_, hash)| hash[0] == 0x01));
+        // The forged sidecar was dropped; the other block's waits.
+        assert_eq!(pending.len(), 1);
+        assert!(pending.take_available(&verified).is_empty());
+
+        for slot in 0..PENDING_BLOB_SIDECARS_CAPACITY as u64 + 1 {
+            pending.insert(blob_sidecar(0, slot));
+        }
+        assert_eq!(pending.len(), PENDING_BLOB_SIDECARS_CAPACITY);
+        assert!(pending.take_available(&blob_sidecar(0, 9001).header).is_empty());
+    }
+
+    #[test]
+    fn test_topic_name() {
+        assert_eq!(topic_name(&gossip_topic(ELECTRA, "beacon_block")), Some("beacon_block"));
+        assert_eq!(topic_name("/eth2/ad532ceb/unknown"), None);
+        assert_eq!(topic_name("/eth2/ad532ceb//ssz_snappy"), None);
+        assert_eq!(topic_name("/meshsub/beacon_block/ssz_snappy"), None);
+    }
+
+    #[test]
+    fn test_fork_schedule_overlaps_topics() {
+        let schedule = ForkSchedule::new(&NetworkConfig::mainnet()).with_fork(1000, [0x06, 0, 0, 0]);
+        let next = schedule.fork_digest_at(1000);
+        assert_eq!(schedule.fork_digest_at(999), ELECTRA);
+        assert_ne!(next, ELECTRA);
+
+        assert_eq!(schedule.topic_digests_at(500), vec![ELECTRA]);
+        assert_eq!(schedule.topic_digests_at(998), vec![ELECTRA, next]);
+        assert_eq!(schedule.topic_digests_at(1001), vec![next, ELECTRA]);
+        assert_eq!(schedule.topic_digests_at(1002), vec![next]);
+
+        assert_eq!(schedule.next_topic_change(500), Some(998));
+        assert_eq!(schedule.next_topic_change(998), Some(1002));
+        assert_eq!(schedule.next_topic_change(1002), None);
+
+        let start = schedule.epoch_start_ms(1000);
+        assert_eq!(schedule.epoch_at(start), Some(1000));
+        assert_eq!(schedule.epoch_at(start - 1), Some(999));
+        let unknown_genesis = ForkSchedule { genesis_time: 0, ..schedule };
+        assert_eq!(unknown_genesis.epoch_at(start), None);
+    }
+
     #[test]
     fn test_gossip_stats_summary() {
         let stats = GossipStats {
@@ -135,9 +677,16 @@ mod tests {
             messages_duplicate: 2,
             finality_updates: 10,
             optimistic_updates: 85,
+            beacon_blocks: 0,
+            blob_sidecars: 0,
+            mesh: BTreeMap::from([
+                ("finality".to_string(), TopicMeshStats { mesh_peers: 4, topic_peers: 6, ..TopicMeshStats::default() }),
+                ("optimistic".to_string(), TopicMeshStats { topic_peers: 2, ..TopicMeshStats::default() }),
+            ]),
         };
         let summary = stats.summary();
         assert!(summary.contains("100 received"));
+        assert!(summary.contains("mesh peers on 1 of 2 topics"));
         assert!(summary.contains("95 valid"));
     }
 }
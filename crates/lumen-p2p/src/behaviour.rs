@@ -4,15 +4,44 @@
 //! - GossipSub for beacon chain topic subscription
 //! - Identify for peer identification
 //! - Ping for connection keep-alive
+//! - The Status handshake, to tell peers on our chain from the rest
+//! - MetaData and beacon Ping, answered so full nodes keep us
+//! - Peer exchange with Lumen relays and nodes, for browser-dialable peers
+//! - A circuit relay client, to be reachable through a relay while
+//!   bootstrapping
+//! - Optionally, Kademlia among Lumen nodes, for peer routing
+//! - The light client request/response protocols, one behaviour each
+//! - Portal content queries over Lumen's overlay, one behaviour per subnetwork
 //!
 //! The behaviour handles peer scoring to deprioritize peers that
 //! send invalid data (as determined by lumen-core verification).
+//! Gossipsub holds each message until its validation is reported, and
+//! scores peers by it with [`light_client_topic_score_params`] on the
+//! light client topics, pruning those that send invalid updates from its
+//! mesh; [`PeerScore::application_score`] feeds in the rest of what we
+//! know of a peer. Ping round trips are smoothed into
+//! [`PeerScore::avg_latency_ms`], to send requests to the fastest peers.
+//! Its GRAFTs, PRUNEs and IWANTs raise no events, so are read back out of
+//! its metrics ([`GossipsubMetrics`]).
 
+use crate::beacon_gossip::TopicMeshStats;
+use crate::dht::kademlia_behaviour;
+use crate::discovery::{peer_exchange_behaviour, PeerExchangeRequest, PeerRecord};
+use crate::metadata::{peer_rpc_behaviour, PeerRpcCodec};
+use crate::portal::{FindContent, PortalCodec, PortalNetwork};
+use crate::req_resp::{LightClientCodec, LightClientProtocol, LightClientRequest};
+use crate::status::{status_behaviour, StatusCodec};
 use libp2p::{
-    gossipsub, identify, ping,
-    swarm::NetworkBehaviour,
+    gossipsub, identify, kad, ping, relay,
+    request_response::{self, OutboundRequestId},
+    swarm::{behaviour::toggle::Toggle, NetworkBehaviour},
+    PeerId,
 };
+use prometheus_client::encoding::text::encode;
+use prometheus_client::registry::Registry;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::Duration;
 
 /// The composite network behaviour for Lumen.
 ///
@@ -30,8 +59,157 @@ pub struct LumenBehaviour {
 
     /// Ping for keeping connections alive and measuring latency.
     pub ping: ping::Behaviour,
+
+    /// Status exchanged with every new peer, both ways.
+    pub status: request_response::Behaviour<StatusCodec>,
+
+    /// MetaData and beacon Ping, answered only.
+    pub peer_rpc: request_response::Behaviour<PeerRpcCodec>,
+
+    /// Peer exchange, asked of peers that announce it and answered.
+    pub peer_exchange: request_response::json::Behaviour<PeerExchangeRequest, Vec<PeerRecord>>,
+
+    /// Circuit relay client, for reservations on Lumen's relays.
+    pub relay_client: relay::client::Behaviour,
+
+    /// Lumen's Kademlia DHT, if enabled.
+    pub kademlia: Toggle<kad::Behaviour<kad::store::MemoryStore>>,
+
+    /// `light_client_bootstrap` requests.
+    pub light_client_bootstrap: request_response::Behaviour<LightClientCodec>,
+
+    /// `light_client_updates_by_range` requests.
+    pub light_client_updates_by_range: request_response::Behaviour<LightClientCodec>,
+
+    /// `light_client_finality_update` requests.
+    pub light_client_finality_update: request_response::Behaviour<LightClientCodec>,
+
+    /// `light_client_optimistic_update` requests.
+    pub light_client_optimistic_update: request_response::Behaviour<LightClientCodec>,
+
+    /// Portal history network queries, asked only.
+    pub portal_history: request_response::Behaviour<PortalCodec>,
+
+    /// Portal state network queries, asked only.
+    pub portal_state: request_response::Behaviour<PortalCodec>,
+}
+
+impl LumenBehaviour {
+    /// Create the behaviour for a node with identity `keypair`, in Lumen's
+    /// DHT if `kademlia`. `relay_client` comes from the `SwarmBuilder`,
+    /// paired with its relay transport. Gossipsub counts into `metrics`.
+    pub fn new(
+        keypair: &libp2p::identity::Keypair,
+        relay_client: relay::client::Behaviour,
+        kademlia: bool,
+        metrics: &mut GossipsubMetrics,
+    ) -> Result<Self, String> {
+        // Beacon chain gossip is unsigned: authenticity comes from the
+        // BLS signatures inside each message, checked by lumen-core.
+        let mut gossipsub = gossipsub::Behaviour::new_with_metrics(
+            gossipsub::MessageAuthenticity::Anonymous,
+            create_gossipsub_config(),
+            &mut metrics.registry,
+            gossipsub::MetricsConfig::default(),
+        )
+        .map_err(|e| format!("Invalid gossipsub configuration: {}", e))?;
+        gossipsub
+            .with_peer_score(create_peer_score_params(), create_peer_score_thresholds())
+            .map_err(|e| format!("Invalid gossipsub peer scoring: {}", e))?;
+        Ok(Self {
+            gossipsub,
+            identify: identify::Behaviour::new(create_identify_config(keypair.public())),
+            ping: ping::Behaviour::default(),
+            status: status_behaviour(),
+            peer_rpc: peer_rpc_behaviour(),
+            peer_exchange: peer_exchange_behaviour(),
+            relay_client,
+            kademlia: Toggle::from(kademlia.then(|| kademlia_behaviour(keypair.public().to_peer_id()))),
+            light_client_bootstrap: LightClientProtocol::Bootstrap.behaviour(),
+            light_client_updates_by_range: LightClientProtocol::UpdatesByRange.behaviour(),
+            light_client_finality_update: LightClientProtocol::FinalityUpdate.behaviour(),
+            light_client_optimistic_update: LightClientProtocol::OptimisticUpdate.behaviour(),
+            portal_history: PortalNetwork::History.behaviour(),
+            portal_state: PortalNetwork::State.behaviour(),
+        })
+    }
+
+    /// Send `request` to `peer` on its protocol. Request ids are only
+    /// unique per protocol.
+    pub fn send_light_client_request(&mut self, peer: &PeerId, request: LightClientRequest) -> OutboundRequestId {
+        match request.protocol() {
+            LightClientProtocol::Bootstrap => self.light_client_bootstrap.send_request(peer, request),
+            LightClientProtocol::UpdatesByRange => self.light_client_updates_by_range.send_request(peer, request),
+            LightClientProtocol::FinalityUpdate => self.light_client_finality_update.send_request(peer, request),
+            LightClientProtocol::OptimisticUpdate => self.light_client_optimistic_update.send_request(peer, request),
+        }
+    }
+
+    /// Send `request` to `peer` on `network`'s protocol. Request ids are
+    /// only unique per network.
+    pub fn send_portal_request(
+        &mut self,
+        network: PortalNetwork,
+        peer: &PeerId,
+        request: FindContent,
+    ) -> OutboundRequestId {
+        match network {
+            PortalNetwork::History => self.portal_history.send_request(peer, request),
+            PortalNetwork::State => self.portal_state.send_request(peer, request),
+        }
+    }
+}
+
+/// The registry gossipsub keeps its metrics in. Mesh changes and IWANTs
+/// are only counted there, so [`GossipsubMetrics::topic_counters`] reads
+/// them back out of its OpenMetrics text.
+#[derive(Debug, Default)]
+pub struct GossipsubMetrics {
+    registry: Registry,
+}
+
+impl GossipsubMetrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// GRAFTs, PRUNEs and IWANTs so far, by topic. Only those fields of
+    /// each [`TopicMeshStats`] are set.
+    pub fn topic_counters(&self) -> HashMap<String, TopicMeshStats> {
+        let mut text = String::new();
+        match encode(&mut text, &self.registry) {
+            Ok(()) => read_topic_counters(&text),
+            Err(_) => HashMap::new(),
+        }
+    }
+}
+
+/// Sum the per-topic counters in gossipsub's OpenMetrics `text`, whose
+/// samples read `<name>_total{hash="<topic>",...} <value>`.
+fn read_topic_counters(text: &str) -> HashMap<String, TopicMeshStats> {
+    let mut topics: HashMap<String, TopicMeshStats> = HashMap::new();
+    for line in text.lines().filter(|line| !line.starts_with('#')) {
+        let Some((name, rest)) = line.split_once('{') else { continue };
+        let Some((labels, value)) = rest.rsplit_once("} ") else { continue };
+        let Some(topic) = labels.split_once("hash=\"").and_then(|(_, rest)| rest.split_once('"')).map(|t| t.0) else {
+            continue;
+        };
+        let Ok(value) = value.trim().parse::<u64>() else { continue };
+        let counter: fn(&mut TopicMeshStats) -> &mut u64 = match name {
+            "mesh_peer_inclusion_events_total" => |stats: &mut TopicMeshStats| &mut stats.grafts,
+            "mesh_peer_churn_events_total" => |stats: &mut TopicMeshStats| &mut stats.prunes,
+            "topic_iwant_msgs_total" => |stats: &mut TopicMeshStats| &mut stats.iwant_requests,
+            _ => continue,
+        };
+        *counter(topics.entry(topic.to_string()).or_default()) += value;
+    }
+    topics
 }
 
+/// The weight of each new ping in [`PeerScore::avg_latency_ms`]: roughly
+/// the last ten count.
+pub const LATENCY_EWMA_ALPHA: f64 = 0.2;
+
 /// Peer scoring — track which peers give us valid vs invalid data.
 /// Peers that consistently send invalid updates get lower priority.
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -42,10 +220,17 @@ pub struct PeerScore {
     pub updates_valid: u64,
     /// Updates that failed verification.
     pub updates_invalid: u64,
-    /// Average latency in milliseconds.
+    /// Ping round trip in milliseconds, smoothed with
+    /// [`LATENCY_EWMA_ALPHA`]. 0 until the first ping comes back.
     pub avg_latency_ms: f64,
 }
 
+impl Default for PeerScore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl PeerScore {
     pub fn new() -> Self {
         Self {
@@ -70,13 +255,86 @@ impl PeerScore {
         // Disconnect if more than 50% of updates are invalid and we have enough data
         self.updates_received >= 10 && self.reputation() < 0.5
     }
+
+    /// A ping to this peer came back after `rtt`.
+    pub fn record_latency(&mut self, rtt: Duration) {
+        let sample = rtt.as_secs_f64() * 1000.0;
+        self.avg_latency_ms =
+            self.latency_ms().map_or(sample, |average| average + LATENCY_EWMA_ALPHA * (sample - average));
+    }
+
+    /// The smoothed ping round trip, once there is one.
+    pub fn latency_ms(&self) -> Option<f64> {
+        (self.avg_latency_ms > 0.0).then_some(self.avg_latency_ms)
+    }
+
+    /// The score to give gossipsub for this peer's application-specific
+    /// part: from -10 for a peer whose updates all failed to 10 for one
+    /// whose updates all passed, 0 for a new peer.
+    pub fn application_score(&self) -> f64 {
+        (self.reputation() - 0.5) * 20.0
+    }
+}
+
+/// Gossipsub's peer scoring: decayed once a slot, with the application
+/// score taken as it is. Topic scores are set per topic, as they are
+/// subscribed to.
+pub fn create_peer_score_params() -> gossipsub::PeerScoreParams {
+    gossipsub::PeerScoreParams {
+        app_specific_weight: 1.0,
+        decay_interval: std::time::Duration::from_secs(12),
+        ..gossipsub::PeerScoreParams::default()
+    }
+}
+
+/// Thresholds for a peer's gossipsub score. Invalid messages on a light
+/// client topic (see [`light_client_topic_score_params`]) cost 50 times
+/// their count squared: after four we stop gossiping with a peer, after six
+/// we ignore it.
+pub fn create_peer_score_thresholds() -> gossipsub::PeerScoreThresholds {
+    gossipsub::PeerScoreThresholds {
+        gossip_threshold: -500.0,
+        publish_threshold: -1000.0,
+        graylist_threshold: -1500.0,
+        accept_px_threshold: 10.0,
+        opportunistic_graft_threshold: 5.0,
+    }
+}
+
+/// Score parameters for a light client topic. Updates come once or twice a
+/// slot, too few to hold mesh peers to a delivery rate, so only first
+/// deliveries, time in the mesh and invalid messages count. Invalid ones
+/// weigh the most, squared as gossipsub does, and decay over about an hour.
+pub fn light_client_topic_score_params() -> gossipsub::TopicScoreParams {
+    gossipsub::TopicScoreParams {
+        topic_weight: 0.5,
+        time_in_mesh_weight: 0.03,
+        time_in_mesh_quantum: std::time::Duration::from_secs(12),
+        time_in_mesh_cap: 300.0,
+        first_message_deliveries_weight: 1.0,
+        first_message_deliveries_decay: 0.9,
+        first_message_deliveries_cap: 50.0,
+        mesh_message_deliveries_weight: 0.0,
+        mesh_failure_penalty_weight: 0.0,
+        invalid_message_deliveries_weight: -100.0,
+        invalid_message_deliveries_decay: 0.99,
+        ..gossipsub::TopicScoreParams::default()
+    }
 }
 
 /// Create a GossipSub configuration tuned for Ethereum beacon chain topics.
 pub fn create_gossipsub_config() -> gossipsub::Config {
     gossipsub::ConfigBuilder::default()
         .heartbeat_interval(std::time::Duration::from_secs(1))
-        .validation_mode(gossipsub::ValidationMode::Strict)
+        // Ethereum's consensus gossip carries no libp2p signatures, author or
+        // sequence number (the spec's StrictNoSign), which only anonymous
+        // validation accepts.
+        .validation_mode(gossipsub::ValidationMode::Anonymous)
+        // Forward nothing until lumen-core has verified it.
+        .validate_messages()
+        .message_id_fn(|message| {
+            gossipsub::MessageId(crate::ssz_snappy::gossip_message_id(message.topic.as_str(), &message.data))
+        })
         .max_transmit_size(10 * 1024 * 1024) // 10MB — beacon blocks can be large
         .build()
         .expect("Valid gossipsub config")
@@ -116,5 +374,54 @@ mod tests {
         score.updates_invalid = 6;
 
         assert!(score.should_disconnect()); // 40% valid < 50% threshold
+        assert!((score.application_score() + 2.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_latency_is_smoothed() {
+        let mut score = PeerScore::new();
+        assert_eq!(score.latency_ms(), None);
+        score.record_latency(Duration::from_millis(100));
+        assert_eq!(score.latency_ms(), Some(100.0));
+        score.record_latency(Duration::from_millis(200));
+        assert!((score.avg_latency_ms - 120.0).abs() < 1e-9);
+        // One slow ping moves it a fifth of the way.
+        score.record_latency(Duration::from_millis(1120));
+        assert!((score.avg_latency_ms - 320.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_topic_counters_are_read_from_the_metrics() {
+        let text = "# TYPE mesh_peer_inclusion_events counter\n\
+            mesh_peer_inclusion_events_total{hash=\"/eth2/a/x\",reason=\"Subscribed\"} 2\n\
+            mesh_peer_inclusion_events_total{hash=\"/eth2/a/x\",reason=\"Random\"} 1\n\
+            mesh_peer_churn_events_total{hash=\"/eth2/a/x\",reason=\"Prune\"} 1\n\
+            topic_iwant_msgs_total{hash=\"/eth2/a/y\"} 5\n\
+            topic_msg_recv_counts_total{hash=\"/eth2/a/y\"} 9\n\
+            heartbeat_duration_count 3\n\
+            # EOF\n";
+        let topics = read_topic_counters(text);
+        assert_eq!(topics["/eth2/a/x"], TopicMeshStats { grafts: 3, prunes: 1, ..TopicMeshStats::default() });
+        assert_eq!(topics["/eth2/a/y"], TopicMeshStats { iwant_requests: 5, ..TopicMeshStats::default() });
+
+        // Gossipsub registers them under these names.
+        let mut metrics = GossipsubMetrics::new();
+        let relay = relay::client::new(PeerId::random()).1;
+        let keypair = libp2p::identity::Keypair::generate_ed25519();
+        let mut behaviour = LumenBehaviour::new(&keypair, relay, false, &mut metrics).unwrap();
+        behaviour.gossipsub.subscribe(&gossipsub::IdentTopic::new("/eth2/a/x")).unwrap();
+        let mut text = String::new();
+        encode(&mut text, &metrics.registry).unwrap();
+        for name in ["mesh_peer_inclusion_events", "mesh_peer_churn_events", "topic_iwant_msgs"] {
+            assert!(text.contains(&format!("# TYPE {} counter", name)), "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_score_params_are_valid() {
+        create_peer_score_params().validate().unwrap();
+        create_peer_score_thresholds().validate().unwrap();
+        light_client_topic_score_params().validate().unwrap();
+        assert_eq!(PeerScore::new().application_score(), 0.0);
     }
 }
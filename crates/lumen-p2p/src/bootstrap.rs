@@ -4,8 +4,34 @@
 //! These bootnodes are trusted ONLY for peer introductions, not for data.
 //! All data received from peers discovered via bootnodes is cryptographically
 //! verified independently.
+//!
+//! While the node has fewer than `min_peers` connections, a
+//! [`BootnodeDialer`] dials the bootnodes in rounds of
+//! [`BOOTNODE_SUBSET_SIZE`], rotating through the list so a dead subset
+//! does not hold the rest back. A round's dials are
+//! [`BOOTNODE_DIAL_STAGGER`] apart, and the next round waits
+//! [`BOOTNODE_RETRY_DELAY`], doubling each round up to
+//! [`MAX_BOOTNODE_RETRY_DELAY`] and jittered by up to half, so clients
+//! that lost their peers together do not come back in step. Which
+//! bootnodes failed and why is kept in [`BootstrapState::failures`].
 
+use crate::peer_manager::{MAX_CONCURRENT_DIALS, MAX_DIALS_PER_ROUND};
+use libp2p::Multiaddr;
 use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::time::Duration;
+
+/// How many bootnodes each round dials.
+pub const BOOTNODE_SUBSET_SIZE: usize = 3;
+
+/// The gap between the dials of one round.
+pub const BOOTNODE_DIAL_STAGGER: Duration = Duration::from_millis(250);
+
+/// How long the first round waits before the next, before jitter.
+pub const BOOTNODE_RETRY_DELAY: Duration = Duration::from_secs(5);
+
+/// The longest a round waits, however many came before it.
+pub const MAX_BOOTNODE_RETRY_DELAY: Duration = Duration::from_secs(2 * 60);
 
 /// Ethereum mainnet bootnodes with WebTransport support.
 ///
@@ -61,6 +87,33 @@ pub struct BootstrapConfig {
 
     /// Maximum number of peers to maintain.
     pub max_peers: usize,
+
+    /// HTTPS peer directories to fetch dialable peers from (see
+    /// [`crate::discovery`]).
+    #[serde(default)]
+    pub peer_directories: Vec<String>,
+
+    /// Join Lumen's Kademlia DHT to find peers (see [`crate::dht`]).
+    #[serde(default)]
+    pub enable_kademlia: bool,
+
+    /// Most outbound dials in progress at once (see
+    /// [`crate::peer_manager`]).
+    #[serde(default = "default_max_concurrent_dials")]
+    pub max_concurrent_dials: usize,
+
+    /// Most dials started in one bootnode round, or between heartbeats
+    /// once the node has `min_peers`.
+    #[serde(default = "default_max_dials_per_round")]
+    pub max_dials_per_round: usize,
+}
+
+fn default_max_concurrent_dials() -> usize {
+    MAX_CONCURRENT_DIALS
+}
+
+fn default_max_dials_per_round() -> usize {
+    MAX_DIALS_PER_ROUND
 }
 
 impl Default for BootstrapConfig {
@@ -77,6 +130,10 @@ impl Default for BootstrapConfig {
             direct_timeout_ms: 3000,
             min_peers: 3,
             max_peers: 10,
+            peer_directories: Vec::new(),
+            enable_kademlia: false,
+            max_concurrent_dials: MAX_CONCURRENT_DIALS,
+            max_dials_per_round: MAX_DIALS_PER_ROUND,
         }
     }
 }
@@ -94,6 +151,39 @@ pub struct BootstrapState {
     pub connection_failures: usize,
     /// Whether we've fallen back to relay.
     pub using_relay: bool,
+    /// Rounds of bootnode dials since the node was last short of peers.
+    #[serde(default)]
+    pub rounds: u32,
+    /// When the next round may start, in Unix milliseconds, while short of
+    /// peers.
+    #[serde(default)]
+    pub next_retry_ms: Option<u64>,
+    /// Bootnodes whose last dial failed, in the order they first did.
+    #[serde(default)]
+    pub failures: Vec<BootnodeFailure>,
+}
+
+/// A bootnode whose last dial failed.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BootnodeFailure {
+    pub address: String,
+    pub reason: DialFailure,
+    /// Failed dials since it last connected.
+    pub attempts: u32,
+    /// Unix milliseconds.
+    pub last_failed_ms: u64,
+}
+
+/// Why a bootnode could not be dialed.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DialFailure {
+    /// The configured address does not parse, so it is never dialed.
+    InvalidAddress { error: String },
+    /// No transport reached it: refused, timed out, unresolved, or not
+    /// one this build can dial.
+    Unreachable { error: String },
+    /// Something answered, but not the peer the address names.
+    WrongPeerId { obtained: String },
 }
 
 /// Phases of the bootstrap process.
@@ -111,6 +201,12 @@ pub enum BootstrapPhase {
     Failed { reason: String },
 }
 
+impl Default for BootstrapState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl BootstrapState {
     pub fn new() -> Self {
         Self {
@@ -119,6 +215,21 @@ impl BootstrapState {
             peers_connected: 0,
             connection_failures: 0,
             using_relay: false,
+            rounds: 0,
+            next_retry_ms: None,
+            failures: Vec::new(),
+        }
+    }
+
+    /// Record that dialing the bootnode at `address` failed for `reason`.
+    pub fn record_failure(&mut self, address: String, reason: DialFailure, now_ms: u64) {
+        match self.failures.iter_mut().find(|failure| failure.address == address) {
+            Some(failure) => {
+                failure.reason = reason;
+                failure.attempts += 1;
+                failure.last_failed_ms = now_ms;
+            }
+            None => self.failures.push(BootnodeFailure { address, reason, attempts: 1, last_failed_ms: now_ms }),
         }
     }
 
@@ -154,3 +265,181 @@ impl BootstrapState {
         }
     }
 }
+
+/// Dials the bootnodes in staggered, rotating rounds with jittered
+/// backoff; see the module docs. Like the peer manager it never touches
+/// the Swarm: the event loop asks it what to dial and tells it what
+/// happened. Times are Unix milliseconds.
+#[derive(Debug)]
+pub struct BootnodeDialer {
+    bootnodes: Vec<Multiaddr>,
+    /// Where the next round starts in `bootnodes`.
+    next: usize,
+    /// Rounds since the node was last short of peers.
+    round: u32,
+    /// Dials of the current round not made yet, and when they are due.
+    pending: VecDeque<(u64, Multiaddr)>,
+    /// When the next round may start, while short of peers.
+    next_round_ms: Option<u64>,
+    /// xorshift state for the jitter.
+    rng: u64,
+}
+
+impl BootnodeDialer {
+    /// A dialer for `bootnodes`, jittering from `seed`.
+    pub fn new(bootnodes: Vec<Multiaddr>, seed: u64) -> Self {
+        Self {
+            bootnodes,
+            next: 0,
+            round: 0,
+            pending: VecDeque::new(),
+            next_round_ms: None,
+            rng: seed.max(1),
+        }
+    }
+
+    /// Whether [`Self::due`] would start a new round.
+    pub fn round_due(&self, now_ms: u64, short: bool) -> bool {
+        let waited = self.next_round_ms.is_none_or(|at| now_ms >= at);
+        short && self.pending.is_empty() && waited && !self.bootnodes.is_empty()
+    }
+
+    /// The bootnodes to dial now, at most `max` of them; the rest wait for
+    /// the next call. `short` is whether the node has fewer peers than it
+    /// wants; once it has enough, the round in progress is dropped and the
+    /// next time it is short starts over without delay.
+    pub fn due(&mut self, now_ms: u64, short: bool, max: usize, state: &mut BootstrapState) -> Vec<Multiaddr> {
+        if !short {
+            self.pending.clear();
+            self.round = 0;
+            self.next_round_ms = None;
+            state.next_retry_ms = None;
+            return Vec::new();
+        }
+        if self.round_due(now_ms, short) {
+            self.start_round(now_ms, state);
+        }
+        let mut due = Vec::new();
+        while due.len() < max && self.pending.front().is_some_and(|(at, _)| *at <= now_ms) {
+            due.extend(self.pending.pop_front().map(|(_, address)| address));
+        }
+        due
+    }
+
+    /// When there is next something to dial, if a round is under way or
+    /// waiting to start. `None` until the node is first found short.
+    pub fn next_wake_ms(&self) -> Option<u64> {
+        self.pending.front().map(|(at, _)| *at).or(self.next_round_ms)
+    }
+
+    /// Record a failed dial of `address`, if it is a bootnode.
+    pub fn failed(&self, address: &Multiaddr, reason: DialFailure, now_ms: u64, state: &mut BootstrapState) {
+        if self.bootnodes.contains(address) {
+            state.record_failure(address.to_string(), reason, now_ms);
+        }
+    }
+
+    /// Clear `address`'s failures once a connection to it is open.
+    pub fn connected(&self, address: &Multiaddr, state: &mut BootstrapState) {
+        let address = address.to_string();
+        state.failures.retain(|failure| failure.address != address);
+    }
+
+    /// Queue the next subset's dials, staggered from `now_ms`, and set
+    /// when the round after may start.
+    fn start_round(&mut self, now_ms: u64, state: &mut BootstrapState) {
+        let stagger = BOOTNODE_DIAL_STAGGER.as_millis() as u64;
+        let size = BOOTNODE_SUBSET_SIZE.min(self.bootnodes.len());
+        for i in 0..size {
+            let address = self.bootnodes[(self.next + i) % self.bootnodes.len()].clone();
+            self.pending.push_back((now_ms + i as u64 * stagger, address));
+        }
+        self.next = (self.next + size) % self.bootnodes.len();
+
+        let delay = BOOTNODE_RETRY_DELAY.saturating_mul(1 << self.round.min(16)).min(MAX_BOOTNODE_RETRY_DELAY);
+        let delay = delay.as_millis() as u64;
+        let jittered = delay - self.next_random() % (delay / 2 + 1);
+        self.next_round_ms = Some(now_ms + (size as u64 - 1) * stagger + jittered);
+        self.round += 1;
+        state.rounds = self.round;
+        state.next_retry_ms = self.next_round_ms;
+    }
+
+    fn next_random(&mut self) -> u64 {
+        self.rng ^= self.rng << 13;
+        self.rng ^= self.rng >> 7;
+        self.rng ^= self.rng << 17;
+        self.rng
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bootnodes(count: u16) -> Vec<Multiaddr> {
+        (0..count).map(|i| format!("/dns4/boot.example/tcp/{}/wss", 9000 + i).parse().unwrap()).collect()
+    }
+
+    #[test]
+    fn test_rounds_are_staggered_rotated_and_backed_off() {
+        let all = bootnodes(5);
+        let mut dialer = BootnodeDialer::new(all.clone(), 42);
+        let mut state = BootstrapState::new();
+        let stagger = BOOTNODE_DIAL_STAGGER.as_millis() as u64;
+
+        assert_eq!(dialer.due(0, true, usize::MAX, &mut state), all[..1]);
+        assert_eq!(dialer.next_wake_ms(), Some(stagger));
+        assert_eq!(dialer.due(2 * stagger, true, usize::MAX, &mut state), all[1..3]);
+        assert_eq!(state.rounds, 1);
+
+        // The next round waits out the jittered delay, then takes the next
+        // subset, wrapping around.
+        let first_wait = state.next_retry_ms.unwrap() - 2 * stagger;
+        let base = BOOTNODE_RETRY_DELAY.as_millis() as u64;
+        assert!((base / 2..=base).contains(&first_wait));
+        let next_round = state.next_retry_ms.unwrap();
+        assert!(dialer.due(next_round - 1, true, usize::MAX, &mut state).is_empty());
+        assert_eq!(dialer.due(next_round, true, usize::MAX, &mut state), all[3..4]);
+        let wrapped = [all[4].clone(), all[0].clone()];
+        assert_eq!(dialer.due(next_round + 2 * stagger, true, usize::MAX, &mut state), wrapped);
+        let second_wait = state.next_retry_ms.unwrap() - next_round - 2 * stagger;
+        assert!((base..=2 * base).contains(&second_wait));
+
+        // With enough peers, rounds stop and start over when short again.
+        assert!(dialer.due(next_round + 3 * stagger, false, usize::MAX, &mut state).is_empty());
+        assert_eq!(state.next_retry_ms, None);
+        assert_eq!(dialer.next_wake_ms(), None);
+        assert!(dialer.round_due(next_round + 4 * stagger, true));
+        assert_eq!(dialer.due(next_round + 4 * stagger, true, usize::MAX, &mut state), all[1..2]);
+        assert_eq!(state.rounds, 1);
+
+        // Dials over the limit wait for the next call.
+        assert!(dialer.due(next_round + 6 * stagger, true, 0, &mut state).is_empty());
+        assert!(!dialer.round_due(next_round + 6 * stagger, true));
+        assert_eq!(dialer.due(next_round + 6 * stagger, true, 1, &mut state), all[2..3]);
+        assert_eq!(dialer.due(next_round + 6 * stagger, true, 1, &mut state), all[3..4]);
+    }
+
+    #[test]
+    fn test_failures_are_reported_per_bootnode() {
+        let all = bootnodes(2);
+        let dialer = BootnodeDialer::new(all.clone(), 7);
+        let mut state = BootstrapState::new();
+        let refused = DialFailure::Unreachable { error: "connection refused".to_string() };
+        dialer.failed(&all[0], refused.clone(), 10, &mut state);
+        dialer.failed(&all[0], refused.clone(), 20, &mut state);
+        dialer.failed(&all[1], DialFailure::WrongPeerId { obtained: "peer".to_string() }, 30, &mut state);
+        // Not a bootnode.
+        dialer.failed(&bootnodes(3)[2], refused.clone(), 40, &mut state);
+
+        assert_eq!(state.failures.len(), 2);
+        let expected =
+            BootnodeFailure { address: all[0].to_string(), reason: refused, attempts: 2, last_failed_ms: 20 };
+        assert_eq!(state.failures[0], expected);
+
+        dialer.connected(&all[0], &mut state);
+        assert_eq!(state.failures.len(), 1);
+        assert_eq!(state.failures[0].address, all[1].to_string());
+    }
+}
@@ -0,0 +1,49 @@
+//! An optional Kademlia DHT among Lumen nodes, for peer routing.
+//!
+//! Ethereum's own DHT is discv5, which browsers cannot reach, so this is a
+//! separate one under [`LUMEN_KAD_PROTOCOL`], served by Lumen's relays and
+//! any other node that runs it. A node that serves light client data
+//! announces itself as a provider of [`light_client_providers_key`] for its
+//! network; Lumen looks those providers up, at most every
+//! [`PROVIDER_LOOKUP_INTERVAL`], and dials them. The routing table outlives
+//! any one bootnode, so once filled it keeps finding peers when the
+//! bootnodes are unreachable.
+//!
+//! A browser cannot be dialed, so Lumen only ever takes part as a client:
+//! it queries, and neither stores records nor answers queries. Enabled by
+//! `BootstrapConfig::enable_kademlia`.
+
+use libp2p::kad::{self, store::MemoryStore, Mode};
+use libp2p::{PeerId, StreamProtocol};
+use std::time::Duration;
+
+pub const LUMEN_KAD_PROTOCOL: &str = "/lumen/kad/1.0.0";
+
+/// How often, at most, to look up light client data providers.
+pub const PROVIDER_LOOKUP_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// The DHT key whose providers serve light client data for the network with
+/// `genesis_validators_root`. Forks do not change it.
+pub fn light_client_providers_key(genesis_validators_root: &[u8; 32]) -> kad::RecordKey {
+    kad::RecordKey::new(&format!("/lumen/light-client/{}", hex::encode(genesis_validators_root)))
+}
+
+/// A Kademlia behaviour on [`LUMEN_KAD_PROTOCOL`], in client mode.
+pub fn kademlia_behaviour(local_peer_id: PeerId) -> kad::Behaviour<MemoryStore> {
+    let config = kad::Config::new(StreamProtocol::new(LUMEN_KAD_PROTOCOL));
+    let mut kademlia = kad::Behaviour::with_config(local_peer_id, MemoryStore::new(local_peer_id), config);
+    kademlia.set_mode(Some(Mode::Client));
+    kademlia
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_providers_key_is_per_network() {
+        let mainnet = light_client_providers_key(&[0x4b; 32]);
+        assert_eq!(mainnet.as_ref(), format!("/lumen/light-client/{}", "4b".repeat(32)).as_bytes());
+        assert_ne!(mainnet, light_client_providers_key(&[0xd8; 32]));
+    }
+}
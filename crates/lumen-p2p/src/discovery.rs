@@ -0,0 +1,249 @@
+//! Peer discovery for browsers, which cannot run discv5: it is UDP only.
+//!
+//! Candidates come from two places instead. Peer directories are HTTPS
+//! endpoints, listed in [`BootstrapConfig::peer_directories`], that answer
+//! with peers a browser can dial, as JSON:
+//! `{"peers": [{"peer_id": "...", "addrs": ["/dns4/.../wss/p2p/..."]}]}`.
+//! And peers that speak [`PEER_EXCHANGE_PROTOCOL`], Lumen's relays and
+//! other Lumen nodes, are asked whom else they know. Lumen answers that
+//! question too, with the addresses its other peers announced over
+//! Identify.
+//!
+//! Like bootnodes, both sources are trusted for introductions only. Only
+//! addresses the enabled transports can dial are kept (see
+//! [`TransportConfig::can_dial`]), and the service dials candidates while
+//! it has fewer than `max_peers` connections, or somewhat fewer when peers
+//! can dial it.
+//!
+//! [`BootstrapConfig::peer_directories`]: crate::bootstrap::BootstrapConfig::peer_directories
+
+use crate::transport::TransportConfig;
+use libp2p::request_response::{self, ProtocolSupport};
+use libp2p::{Multiaddr, PeerId, StreamProtocol};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+
+pub const PEER_EXCHANGE_PROTOCOL: &str = "/lumen/peer-exchange/1.0.0/json";
+
+/// Most records a peer exchange asks for or answers with.
+pub const MAX_PEER_EXCHANGE_RECORDS: usize = 16;
+
+/// Most candidates queued for dialing; more are dropped until the queue
+/// drains.
+pub const MAX_CANDIDATES: usize = 64;
+
+/// A peer and the addresses it can be dialed at.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PeerRecord {
+    pub peer_id: String,
+    pub addrs: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct PeerDirectory {
+    peers: Vec<PeerRecord>,
+}
+
+/// Parse a peer directory's answer.
+pub fn parse_directory(json: &[u8]) -> Result<Vec<PeerRecord>, String> {
+    serde_json::from_slice::<PeerDirectory>(json)
+        .map(|directory| directory.peers)
+        .map_err(|e| format!("Invalid peer directory: {}", e))
+}
+
+/// Fetch the peer directory at `url`.
+#[cfg(target_arch = "wasm32")]
+pub async fn fetch_directory(url: &str) -> Result<Vec<PeerRecord>, String> {
+    use wasm_bindgen::JsCast;
+    use wasm_bindgen_futures::JsFuture;
+
+    let init = web_sys::RequestInit::new();
+    init.set_method("GET");
+    init.set_mode(web_sys::RequestMode::Cors);
+    let request = web_sys::Request::new_with_str_and_init(url, &init).map_err(|e| format!("{:?}", e))?;
+    // A window, or the Web Worker the service runs in.
+    let global = js_sys::global();
+    let promise = match web_sys::window() {
+        Some(window) => window.fetch_with_request(&request),
+        None => global
+            .dyn_ref::<web_sys::WorkerGlobalScope>()
+            .ok_or("No fetch in this global scope")?
+            .fetch_with_request(&request),
+    };
+    let response: web_sys::Response = JsFuture::from(promise)
+        .await
+        .map_err(|e| format!("{:?}", e))?
+        .dyn_into()
+        .map_err(|_| "Not a Response".to_string())?;
+    if !response.ok() {
+        return Err(format!("HTTP {} {}", response.status(), response.status_text()));
+    }
+    let text = JsFuture::from(response.text().map_err(|e| format!("{:?}", e))?)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    parse_directory(text.as_string().unwrap_or_default().as_bytes())
+}
+
+/// Fetch the peer directory at `url`. Natively there is no HTTP client,
+/// so this always fails.
+#[cfg(not(target_arch = "wasm32"))]
+pub async fn fetch_directory(url: &str) -> Result<Vec<PeerRecord>, String> {
+    Err(format!("Cannot fetch {}: peer directories are only fetched in the browser", url))
+}
+
+/// A peer exchange request.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PeerExchangeRequest {
+    /// Most records wanted, at most [`MAX_PEER_EXCHANGE_RECORDS`].
+    pub max_records: usize,
+}
+
+/// A request-response behaviour for [`PEER_EXCHANGE_PROTOCOL`], asking and
+/// answering.
+pub fn peer_exchange_behaviour() -> request_response::json::Behaviour<PeerExchangeRequest, Vec<PeerRecord>> {
+    request_response::json::Behaviour::new(
+        [(StreamProtocol::new(PEER_EXCHANGE_PROTOCOL), ProtocolSupport::Full)],
+        request_response::Config::default().with_request_timeout(crate::req_resp::REQUEST_TIMEOUT),
+    )
+}
+
+/// Peers to dial, and the addresses of connected ones to pass on.
+#[derive(Debug)]
+pub struct Discovery {
+    transport: TransportConfig,
+    candidates: VecDeque<(PeerId, Vec<Multiaddr>)>,
+    /// Dialable addresses connected peers announced.
+    known: HashMap<PeerId, Vec<Multiaddr>>,
+}
+
+impl Discovery {
+    pub fn new(transport: TransportConfig) -> Self {
+        Self { transport, candidates: VecDeque::new(), known: HashMap::new() }
+    }
+
+    /// Queue the peers of `records` that have a dialable address, except
+    /// `local` and those already queued. Returns how many were queued.
+    /// Records that do not parse are skipped.
+    pub fn add(&mut self, records: Vec<PeerRecord>, local: &PeerId) -> usize {
+        let mut added = 0;
+        for record in records {
+            if self.candidates.len() >= MAX_CANDIDATES {
+                break;
+            }
+            let Ok(peer) = record.peer_id.parse::<PeerId>() else {
+                continue;
+            };
+            let addrs: Vec<Multiaddr> = record
+                .addrs
+                .iter()
+                .filter_map(|addr| addr.parse().ok())
+                .filter(|addr| self.transport.can_dial(addr))
+                .collect();
+            if peer == *local || addrs.is_empty() || self.candidates.iter().any(|(queued, _)| *queued == peer) {
+                continue;
+            }
+            self.candidates.push_back((peer, addrs));
+            added += 1;
+        }
+        added
+    }
+
+    /// The next candidate to dial.
+    pub fn next_candidate(&mut self) -> Option<(PeerId, Vec<Multiaddr>)> {
+        self.candidates.pop_front()
+    }
+
+    pub fn candidates(&self) -> usize {
+        self.candidates.len()
+    }
+
+    /// Record the addresses a connected `peer` announced over Identify.
+    pub fn identified(&mut self, peer: PeerId, addrs: Vec<Multiaddr>) {
+        let addrs: Vec<Multiaddr> = addrs.into_iter().filter(|addr| self.transport.can_dial(addr)).collect();
+        if addrs.is_empty() {
+            self.known.remove(&peer);
+        } else {
+            self.known.insert(peer, addrs);
+        }
+    }
+
+    /// The dialable addresses a connected `peer` announced.
+    pub fn addresses(&self, peer: &PeerId) -> &[Multiaddr] {
+        self.known.get(peer).map_or(&[], Vec::as_slice)
+    }
+
+    /// Forget a peer that disconnected.
+    pub fn forget(&mut self, peer: &PeerId) {
+        self.known.remove(peer);
+    }
+
+    /// What to answer `requester`'s peer exchange with: up to `max` of the
+    /// other peers we know addresses for.
+    pub fn records_for(&self, requester: &PeerId, max: usize) -> Vec<PeerRecord> {
+        self.known
+            .iter()
+            .filter(|(peer, _)| *peer != requester)
+            .take(max.min(MAX_PEER_EXCHANGE_RECORDS))
+            .map(|(peer, addrs)| PeerRecord {
+                peer_id: peer.to_string(),
+                addrs: addrs.iter().map(|addr| addr.to_string()).collect(),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(peer: &PeerId, addrs: &[&str]) -> PeerRecord {
+        PeerRecord { peer_id: peer.to_string(), addrs: addrs.iter().map(|addr| addr.to_string()).collect() }
+    }
+
+    #[test]
+    fn test_parse_directory() {
+        let json = br#"{"peers": [{"peer_id": "12D3KooWReaFkMnb7YJZK9fqDFskLJiVcZpjxdKcNih3vRCCFGPr",
+            "addrs": ["/dns4/relay.lumen.dev/tcp/443/wss"]}]}"#;
+        let records = parse_directory(json).unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].addrs, ["/dns4/relay.lumen.dev/tcp/443/wss"]);
+        assert!(parse_directory(b"[]").is_err());
+    }
+
+    #[test]
+    fn test_only_dialable_peers_are_queued() {
+        let (local, a, b, c) = (PeerId::random(), PeerId::random(), PeerId::random(), PeerId::random());
+        let mut discovery = Discovery::new(TransportConfig::browser());
+        let records = vec![
+            record(&a, &["/ip4/1.2.3.4/tcp/9000", "/ip4/1.2.3.4/udp/443/quic-v1/webtransport"]),
+            record(&b, &["/ip4/1.2.3.4/tcp/9000"]),
+            record(&c, &["/dns4/relay.lumen.dev/tcp/443/wss/p2p-circuit"]),
+            record(&local, &["/dns4/me.example/tcp/443/wss"]),
+            PeerRecord { peer_id: "not a peer id".to_string(), addrs: vec!["/dns4/x/tcp/443/wss".to_string()] },
+            record(&a, &["/dns4/a.example/tcp/443/wss"]),
+        ];
+        assert_eq!(discovery.add(records, &local), 1);
+        let (peer, addrs) = discovery.next_candidate().unwrap();
+        assert_eq!(peer, a);
+        assert_eq!(addrs, ["/ip4/1.2.3.4/udp/443/quic-v1/webtransport".parse::<Multiaddr>().unwrap()]);
+        assert_eq!(discovery.next_candidate(), None);
+
+        let no_websocket = TransportConfig { enable_websocket: false, ..TransportConfig::browser() };
+        let mut discovery = Discovery::new(no_websocket);
+        assert_eq!(discovery.add(vec![record(&a, &["/dns4/a.example/tcp/443/wss"])], &local), 0);
+    }
+
+    #[test]
+    fn test_exchange_passes_on_other_peers() {
+        let (a, b) = (PeerId::random(), PeerId::random());
+        let mut discovery = Discovery::new(TransportConfig::browser());
+        discovery.identified(a, vec!["/dns4/a.example/tcp/443/wss".parse().unwrap()]);
+        discovery.identified(b, vec!["/ip4/10.0.0.1/tcp/9000".parse().unwrap()]);
+
+        // b announced nothing a browser can dial.
+        assert_eq!(discovery.records_for(&b, 10), vec![record(&a, &["/dns4/a.example/tcp/443/wss"])]);
+        assert!(discovery.records_for(&a, 10).is_empty());
+        discovery.forget(&a);
+        assert!(discovery.records_for(&b, 10).is_empty());
+    }
+}
@@ -0,0 +1,59 @@
+//! Network lifecycle events for `LumenP2pService::events`.
+//!
+//! What the service only recorded in its `ServiceStatus` is also told as
+//! it happens, so a host such as lumen-wasm can pass it on as JS events
+//! without reaching into libp2p. Events describe what the network did,
+//! not what it is worth: gossip is reported as received, unverified.
+
+use crate::bootstrap::BootstrapPhase;
+use crate::relay::ConnectionMode;
+use crate::transport::TransportType;
+use serde::Serialize;
+
+/// Events buffered per subscriber. One that falls further behind misses
+/// events until it catches up; the service never waits for it.
+pub const EVENT_CHANNEL_CAPACITY: usize = 256;
+
+/// An event, serialized as `{ type, ...fields }`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum P2pEvent {
+    /// The first connection to `peer_id` opened.
+    PeerConnected { peer_id: String, transport: TransportType, inbound: bool },
+    /// The last connection to `peer_id` closed.
+    PeerDisconnected { peer_id: String },
+    /// A gossip message was delivered for verification. `message_id` is
+    /// hex.
+    GossipReceived { topic: String, message_id: String, source_peer: Option<String> },
+    ModeChanged { mode: ConnectionMode },
+    BootstrapPhaseChanged { phase: BootstrapPhase },
+    /// Something failed, as also recorded in `ServiceStatus::last_error`.
+    Error { message: String },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_event_shape() {
+        let json = serde_json::to_value(P2pEvent::PeerConnected {
+            peer_id: "12D3KooW".to_string(),
+            transport: TransportType::WebTransport,
+            inbound: false,
+        })
+        .unwrap();
+        let expected = serde_json::json!({
+            "type": "peer_connected",
+            "peer_id": "12D3KooW",
+            "transport": "WebTransport",
+            "inbound": false,
+        });
+        assert_eq!(json, expected);
+
+        let json = serde_json::to_value(P2pEvent::BootstrapPhaseChanged { phase: BootstrapPhase::ConnectingRelay })
+            .unwrap();
+        assert_eq!(json["type"], "bootstrap_phase_changed");
+        assert_eq!(json["phase"], "ConnectingRelay");
+    }
+}
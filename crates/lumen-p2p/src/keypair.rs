@@ -0,0 +1,76 @@
+//! The node's libp2p identity, kept across sessions.
+//!
+//! A fresh Ed25519 keypair each session would make Lumen a new peer every
+//! time: full nodes' gossipsub scores and our relay reservations are tied
+//! to the [`PeerId`](libp2p::PeerId), and both would start over.
+//! [`load_or_generate_keypair`] generates the keypair once, has the host
+//! keep it through a [`KeypairStorage`], and takes it from there
+//! afterwards, for `LumenP2pService::new`.
+//!
+//! The keypair is stored in libp2p's protobuf encoding. Storage that holds
+//! anything else, or a key of another type, is overwritten with a new
+//! keypair rather than failing the start: a new identity costs no more
+//! than the first session did.
+
+use libp2p::identity::Keypair;
+
+/// Where the host keeps the node's keypair between sessions, such as
+/// `localStorage` or a file. Whoever can read it can act as the node, so
+/// it should be kept no less privately than the client's other state.
+pub trait KeypairStorage {
+    /// The bytes last saved, if any.
+    fn load(&self) -> Option<Vec<u8>>;
+    fn save(&self, bytes: &[u8]);
+}
+
+/// The Ed25519 keypair `storage` holds, or a new one, saved to it.
+pub fn load_or_generate_keypair(storage: &impl KeypairStorage) -> Keypair {
+    if let Some(keypair) = storage.load().and_then(|bytes| decode_keypair(&bytes).ok()) {
+        return keypair;
+    }
+    let keypair = Keypair::generate_ed25519();
+    storage.save(&keypair.to_protobuf_encoding().expect("Ed25519 keypairs encode"));
+    keypair
+}
+
+/// Read an Ed25519 keypair saved by [`load_or_generate_keypair`].
+pub fn decode_keypair(bytes: &[u8]) -> Result<Keypair, String> {
+    let keypair = Keypair::from_protobuf_encoding(bytes).map_err(|e| format!("Invalid stored keypair: {}", e))?;
+    keypair.clone().try_into_ed25519().map_err(|_| "Stored keypair is not Ed25519".to_string())?;
+    Ok(keypair)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+
+    #[derive(Default)]
+    struct MemoryStorage(RefCell<Option<Vec<u8>>>);
+
+    impl KeypairStorage for MemoryStorage {
+        fn load(&self) -> Option<Vec<u8>> {
+            self.0.borrow().clone()
+        }
+
+        fn save(&self, bytes: &[u8]) {
+            *self.0.borrow_mut() = Some(bytes.to_vec());
+        }
+    }
+
+    #[test]
+    fn test_keypair_is_kept_across_sessions() {
+        let storage = MemoryStorage::default();
+        let peer_id = |keypair: Keypair| keypair.public().to_peer_id();
+        let first = peer_id(load_or_generate_keypair(&storage));
+        assert_eq!(peer_id(decode_keypair(&storage.load().unwrap()).unwrap()), first);
+        assert_eq!(peer_id(load_or_generate_keypair(&storage)), first);
+
+        // Garbage, or a key of another type, is replaced with a new identity.
+        storage.save(b"not a keypair");
+        let second = peer_id(load_or_generate_keypair(&storage));
+        assert_ne!(second, first);
+        assert_eq!(peer_id(load_or_generate_keypair(&storage)), second);
+        assert!(decode_keypair(b"not a keypair").is_err());
+    }
+}
@@ -22,12 +22,40 @@
 //! never for actual data.
 
 pub mod transport;
+pub mod bandwidth;
 pub mod behaviour;
 pub mod bootstrap;
 pub mod relay;
 pub mod beacon_gossip;
+pub mod ssz_snappy;
+pub mod req_resp;
+pub mod status;
+pub mod metadata;
+pub mod discovery;
+pub mod dht;
+pub mod portal;
+pub mod peer_manager;
+pub mod peer_store;
+pub mod keypair;
+pub mod reachability;
+pub mod events;
+pub mod service;
 
 pub use bootstrap::*;
 pub use behaviour::*;
 pub use relay::*;
 pub use beacon_gossip::*;
+pub use ssz_snappy::*;
+pub use req_resp::*;
+pub use status::*;
+pub use metadata::*;
+pub use discovery::*;
+pub use dht::*;
+pub use portal::*;
+pub use peer_manager::*;
+pub use peer_store::*;
+pub use keypair::*;
+pub use reachability::*;
+pub use bandwidth::*;
+pub use events::*;
+pub use service::*;
@@ -0,0 +1,225 @@
+//! The consensus layer's MetaData and Ping protocols, answered for peers.
+//!
+//! Full nodes ping each peer periodically with their MetaData sequence
+//! number, and ask for its MetaData — the attestation and sync committee
+//! subnets it serves — when the number it pings back changes. A peer that
+//! answers neither looks broken, and is scored down and dropped. Lumen
+//! serves no subnets, so its MetaData is all zeros and never changes:
+//! [`LOCAL_METADATA`], with sequence number 0.
+//!
+//! Only the answering side is implemented; Lumen has no use for its peers'
+//! subnets. Responses are single chunks without a fork digest, like
+//! Status.
+
+use crate::req_resp::{
+    encode_payload, encode_plain_response, invalid_data, read_payload, read_plain_response, MAX_PAYLOAD_SIZE,
+};
+use async_trait::async_trait;
+use futures::{AsyncRead, AsyncWrite, AsyncWriteExt};
+use libp2p::request_response::{self, ProtocolSupport};
+use libp2p::StreamProtocol;
+use std::io;
+
+pub const PING_PROTOCOL: &str = "/eth2/beacon_chain/req/ping/1/ssz_snappy";
+
+/// MetaData before Altair: no sync committee subnets.
+pub const METADATA_V1_PROTOCOL: &str = "/eth2/beacon_chain/req/metadata/1/ssz_snappy";
+
+pub const METADATA_V2_PROTOCOL: &str = "/eth2/beacon_chain/req/metadata/2/ssz_snappy";
+
+/// A node's MetaData (v2).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MetaData {
+    /// Bumped whenever the rest changes.
+    pub seq_number: u64,
+    /// `Bitvector[ATTESTATION_SUBNET_COUNT]`: the 64 attestation subnets
+    /// subscribed to long-lived.
+    pub attnets: [u8; 8],
+    /// `Bitvector[SYNC_COMMITTEE_SUBNET_COUNT]`: the 4 sync committee
+    /// subnets, in the low bits.
+    pub syncnets: u8,
+}
+
+/// Lumen's MetaData: no subnets, never changed.
+pub const LOCAL_METADATA: MetaData = MetaData { seq_number: 0, attnets: [0; 8], syncnets: 0 };
+
+impl MetaData {
+    /// The v2 encoding, or v1's, which stops before `syncnets`.
+    pub fn to_ssz(&self, version: u8) -> Vec<u8> {
+        let mut out = self.seq_number.to_le_bytes().to_vec();
+        out.extend_from_slice(&self.attnets);
+        if version >= 2 {
+            out.push(self.syncnets);
+        }
+        out
+    }
+
+    pub fn from_ssz(version: u8, ssz: &[u8]) -> Result<Self, String> {
+        let len = if version >= 2 { 17 } else { 16 };
+        if ssz.len() != len {
+            return Err(format!("MetaData v{} is {} bytes, not {}", version, ssz.len(), len));
+        }
+        Ok(Self {
+            seq_number: u64::from_le_bytes(ssz[..8].try_into().expect("8 bytes")),
+            attnets: ssz[8..16].try_into().expect("8 bytes"),
+            syncnets: ssz.get(16).copied().unwrap_or(0),
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PeerRpcRequest {
+    /// A ping, with the sender's MetaData sequence number.
+    Ping(u64),
+    /// A request for our MetaData, in the given version.
+    MetaData(u8),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PeerRpcResponse {
+    /// The answer to a ping: our MetaData sequence number.
+    Pong(u64),
+    MetaData(MetaData),
+}
+
+impl PeerRpcRequest {
+    /// What Lumen answers.
+    pub fn response(&self) -> PeerRpcResponse {
+        match self {
+            Self::Ping(_) => PeerRpcResponse::Pong(LOCAL_METADATA.seq_number),
+            Self::MetaData(_) => PeerRpcResponse::MetaData(LOCAL_METADATA),
+        }
+    }
+}
+
+/// Which of the protocols `protocol` is: ping, or the MetaData version.
+fn metadata_version(protocol: &StreamProtocol) -> io::Result<Option<u8>> {
+    match protocol.as_ref() {
+        PING_PROTOCOL => Ok(None),
+        METADATA_V1_PROTOCOL => Ok(Some(1)),
+        METADATA_V2_PROTOCOL => Ok(Some(2)),
+        other => Err(invalid_data(format!("Unknown protocol {}", other))),
+    }
+}
+
+/// A request-response behaviour answering Ping and both MetaData versions.
+/// It sends nothing, so one behaviour serves all three.
+pub fn peer_rpc_behaviour() -> request_response::Behaviour<PeerRpcCodec> {
+    let protocols = [PING_PROTOCOL, METADATA_V2_PROTOCOL, METADATA_V1_PROTOCOL]
+        .map(|id| (StreamProtocol::new(id), ProtocolSupport::Inbound));
+    request_response::Behaviour::new(
+        protocols,
+        request_response::Config::default().with_request_timeout(crate::req_resp::REQUEST_TIMEOUT),
+    )
+}
+
+/// The `ssz_snappy` codec for Ping and MetaData.
+#[derive(Clone, Debug, Default)]
+pub struct PeerRpcCodec;
+
+#[async_trait]
+impl request_response::Codec for PeerRpcCodec {
+    type Protocol = StreamProtocol;
+    type Request = PeerRpcRequest;
+    type Response = PeerRpcResponse;
+
+    async fn read_request<T>(&mut self, protocol: &StreamProtocol, io: &mut T) -> io::Result<PeerRpcRequest>
+    where
+        T: AsyncRead + Unpin + Send,
+    {
+        // MetaData requests have no body.
+        if let Some(version) = metadata_version(protocol)? {
+            return Ok(PeerRpcRequest::MetaData(version));
+        }
+        let ssz = read_payload(io, MAX_PAYLOAD_SIZE).await?;
+        let seq_number = ssz.try_into().map_err(|_| invalid_data("Ping is not 8 bytes".to_string()))?;
+        Ok(PeerRpcRequest::Ping(u64::from_le_bytes(seq_number)))
+    }
+
+    async fn read_response<T>(&mut self, protocol: &StreamProtocol, io: &mut T) -> io::Result<PeerRpcResponse>
+    where
+        T: AsyncRead + Unpin + Send,
+    {
+        let ssz = read_plain_response(io).await?;
+        match metadata_version(protocol)? {
+            Some(version) => Ok(PeerRpcResponse::MetaData(MetaData::from_ssz(version, &ssz).map_err(invalid_data)?)),
+            None => {
+                let seq_number = ssz.try_into().map_err(|_| invalid_data("Pong is not 8 bytes".to_string()))?;
+                Ok(PeerRpcResponse::Pong(u64::from_le_bytes(seq_number)))
+            }
+        }
+    }
+
+    async fn write_request<T>(&mut self, _: &StreamProtocol, io: &mut T, request: PeerRpcRequest) -> io::Result<()>
+    where
+        T: AsyncWrite + Unpin + Send,
+    {
+        match request {
+            PeerRpcRequest::Ping(seq_number) => io.write_all(&encode_payload(&seq_number.to_le_bytes())).await,
+            PeerRpcRequest::MetaData(_) => Ok(()),
+        }
+    }
+
+    async fn write_response<T>(
+        &mut self,
+        protocol: &StreamProtocol,
+        io: &mut T,
+        response: PeerRpcResponse,
+    ) -> io::Result<()>
+    where
+        T: AsyncWrite + Unpin + Send,
+    {
+        let ssz = match (response, metadata_version(protocol)?) {
+            (PeerRpcResponse::MetaData(metadata), Some(version)) => metadata.to_ssz(version),
+            (PeerRpcResponse::Pong(seq_number), None) => seq_number.to_le_bytes().to_vec(),
+            _ => return Err(invalid_data(format!("Response does not match protocol {}", protocol))),
+        };
+        io.write_all(&encode_plain_response(&ssz)).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use libp2p::request_response::Codec;
+
+    #[tokio::test]
+    async fn test_ping() {
+        let protocol = StreamProtocol::new(PING_PROTOCOL);
+        let mut wire = Vec::new();
+        PeerRpcCodec.write_request(&protocol, &mut wire, PeerRpcRequest::Ping(7)).await.unwrap();
+        let request = PeerRpcCodec.read_request(&protocol, &mut wire.as_slice()).await.unwrap();
+        assert_eq!(request, PeerRpcRequest::Ping(7));
+
+        let mut wire = Vec::new();
+        PeerRpcCodec.write_response(&protocol, &mut wire, request.response()).await.unwrap();
+        let response = PeerRpcCodec.read_response(&protocol, &mut wire.as_slice()).await.unwrap();
+        assert_eq!(response, PeerRpcResponse::Pong(0));
+    }
+
+    #[tokio::test]
+    async fn test_metadata_versions() {
+        let metadata = MetaData { seq_number: 3, attnets: [0x81; 8], syncnets: 0b0101 };
+        assert_eq!(MetaData::from_ssz(2, &metadata.to_ssz(2)), Ok(metadata));
+        let v1 = MetaData::from_ssz(1, &metadata.to_ssz(1)).unwrap();
+        assert_eq!(v1, MetaData { syncnets: 0, ..metadata });
+        assert!(MetaData::from_ssz(2, &metadata.to_ssz(1)).is_err());
+
+        for (id, version, len) in [(METADATA_V1_PROTOCOL, 1, 16), (METADATA_V2_PROTOCOL, 2, 17)] {
+            let protocol = StreamProtocol::new(id);
+            // Requests are empty.
+            let request = PeerRpcCodec.read_request(&protocol, &mut &[][..]).await.unwrap();
+            assert_eq!(request, PeerRpcRequest::MetaData(version));
+
+            let mut wire = Vec::new();
+            PeerRpcCodec.write_response(&protocol, &mut wire, request.response()).await.unwrap();
+            assert_eq!(read_plain_response(&mut wire.as_slice()).await.unwrap().len(), len);
+            let response = PeerRpcCodec.read_response(&protocol, &mut wire.as_slice()).await.unwrap();
+            assert_eq!(response, PeerRpcResponse::MetaData(LOCAL_METADATA));
+        }
+
+        let ping = StreamProtocol::new(PING_PROTOCOL);
+        let mismatched = PeerRpcResponse::MetaData(LOCAL_METADATA);
+        assert!(PeerRpcCodec.write_response(&ping, &mut Vec::new(), mismatched).await.is_err());
+    }
+}
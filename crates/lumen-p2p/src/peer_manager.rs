@@ -0,0 +1,464 @@
+//! Which peers to keep, drop, dial and redial.
+//!
+//! [`PeerManager`] holds the service's peer policy, from
+//! `BootstrapConfig`'s `min_peers` and `max_peers`:
+//!
+//! - Over `max_peers` connections, the worst-scored peers are pruned, and
+//!   peers whose [`PeerScore`] says to disconnect are dropped and banned
+//!   whatever the count.
+//! - An address that fails to dial is not dialed again for
+//!   [`INITIAL_DIAL_BACKOFF`], doubling with each further failure up to
+//!   [`MAX_DIAL_BACKOFF`]. A connection resets it.
+//! - Banned peers, such as those whose Status shows another chain, are
+//!   neither dialed nor kept for [`BAN_DURATION`].
+//! - Below `min_peers`, the peers we dialed before, bootnodes among them,
+//!   are redialed, checked every [`HEARTBEAT_INTERVAL`].
+//! - At most `max_concurrent_dials` outbound dials are in progress at
+//!   once, [`MAX_CONCURRENT_DIALS`] by default, and at most
+//!   `max_dials_per_round` start in each bootnode round, or between
+//!   heartbeats once the node has `min_peers`. A browser tab that opened
+//!   every handshake at once would hit the browser's own connection
+//!   limits; dials over these wait for the next chance instead.
+//! - Requests go to the peers with the lowest smoothed ping first.
+//! - Peers whose Identify shows they cannot serve light client data, not
+//!   answering all of its req/resp protocols, are pruned before any other
+//!   and asked last; those that also joined the light client gossip topics
+//!   are kept over those that only answer requests. Peers not identified
+//!   yet sit in between.
+//!
+//! The manager never touches the Swarm: the event loop tells it what
+//! happened and asks it what to do. Times are Unix milliseconds.
+
+use crate::behaviour::PeerScore;
+use crate::portal::{PortalNetwork, LUMEN_HISTORY_OVERLAY_PROTOCOL, LUMEN_STATE_OVERLAY_PROTOCOL};
+use crate::req_resp::LightClientProtocol;
+use libp2p::multiaddr::Protocol;
+use libp2p::{Multiaddr, PeerId, StreamProtocol};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::time::Duration;
+
+/// How long an address that failed once waits before it is dialed again.
+pub const INITIAL_DIAL_BACKOFF: Duration = Duration::from_secs(5);
+
+/// The longest an address waits, however often it failed.
+pub const MAX_DIAL_BACKOFF: Duration = Duration::from_secs(10 * 60);
+
+pub const BAN_DURATION: Duration = Duration::from_secs(60 * 60);
+
+/// How often the event loop prunes and redials.
+pub const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Most addresses kept for redialing; the oldest go first.
+pub const MAX_REDIAL_ADDRESSES: usize = 32;
+
+/// Most outbound dials in progress at once, by default.
+pub const MAX_CONCURRENT_DIALS: usize = 4;
+
+/// Most dials started in one round, by default.
+pub const MAX_DIALS_PER_ROUND: usize = 16;
+
+/// What a peer can do for a light client, as far as it has said.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Capabilities {
+    /// Its Identify lists every [`LightClientProtocol`].
+    pub light_client_protocols: bool,
+    /// It subscribed to a light client gossip topic.
+    pub light_client_topics: bool,
+    /// Its Identify lists [`LUMEN_HISTORY_OVERLAY_PROTOCOL`].
+    pub portal_history: bool,
+    /// Its Identify lists [`LUMEN_STATE_OVERLAY_PROTOCOL`].
+    pub portal_state: bool,
+}
+
+impl Capabilities {
+    /// The capabilities of a peer whose Identify lists `protocols`.
+    pub fn from_protocols(protocols: &[StreamProtocol]) -> Self {
+        let lists = |id: &str| protocols.iter().any(|p| p.as_ref() == id);
+        Self {
+            light_client_protocols: LightClientProtocol::ALL.into_iter().all(|protocol| lists(protocol.id())),
+            light_client_topics: false,
+            portal_history: lists(LUMEN_HISTORY_OVERLAY_PROTOCOL),
+            portal_state: lists(LUMEN_STATE_OVERLAY_PROTOCOL),
+        }
+    }
+
+    /// Whether the peer answers Portal queries on `network`.
+    pub fn portal(&self, network: PortalNetwork) -> bool {
+        match network {
+            PortalNetwork::History => self.portal_history,
+            PortalNetwork::State => self.portal_state,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Backoff {
+    failures: u32,
+    retry_at_ms: u64,
+}
+
+/// See the module docs.
+#[derive(Debug)]
+pub struct PeerManager {
+    min_peers: usize,
+    max_peers: usize,
+    scores: HashMap<PeerId, PeerScore>,
+    /// Connected peers that have been identified, or joined a topic.
+    capabilities: HashMap<PeerId, Capabilities>,
+    backoff: HashMap<Multiaddr, Backoff>,
+    /// Banned peers, and when their bans end.
+    banned: HashMap<PeerId, u64>,
+    /// Addresses to redial below `min_peers`.
+    redial: VecDeque<Multiaddr>,
+    max_concurrent_dials: usize,
+    max_dials_per_round: usize,
+    /// Dials started this round.
+    round_dials: usize,
+}
+
+impl PeerManager {
+    pub fn new(min_peers: usize, max_peers: usize) -> Self {
+        Self {
+            min_peers,
+            max_peers,
+            scores: HashMap::new(),
+            capabilities: HashMap::new(),
+            backoff: HashMap::new(),
+            banned: HashMap::new(),
+            redial: VecDeque::new(),
+            max_concurrent_dials: MAX_CONCURRENT_DIALS,
+            max_dials_per_round: MAX_DIALS_PER_ROUND,
+            round_dials: 0,
+        }
+    }
+
+    /// Limit dials to `concurrent` in progress at once and `per_round`
+    /// started each round.
+    pub fn with_dial_limits(mut self, concurrent: usize, per_round: usize) -> Self {
+        self.max_concurrent_dials = concurrent;
+        self.max_dials_per_round = per_round;
+        self
+    }
+
+    /// How many dials may start now with `pending` in progress: what the
+    /// concurrency limit leaves, within what is left of the round's budget.
+    pub fn dial_slots(&self, pending: usize) -> usize {
+        let concurrent = self.max_concurrent_dials.saturating_sub(pending);
+        concurrent.min(self.max_dials_per_round.saturating_sub(self.round_dials))
+    }
+
+    /// Count a dial against the round's budget.
+    pub fn dial_started(&mut self) {
+        self.round_dials += 1;
+    }
+
+    /// Start the next round with its whole budget.
+    pub fn start_dial_round(&mut self) {
+        self.round_dials = 0;
+    }
+
+    pub fn min_peers(&self) -> usize {
+        self.min_peers
+    }
+
+    pub fn max_peers(&self) -> usize {
+        self.max_peers
+    }
+
+    /// Keep `address` to redial when short of peers.
+    pub fn keep(&mut self, address: Multiaddr) {
+        if self.redial.contains(&address) {
+            return;
+        }
+        if self.redial.len() >= MAX_REDIAL_ADDRESSES {
+            self.redial.pop_front();
+        }
+        self.redial.push_back(address);
+    }
+
+    pub fn score(&self, peer: &PeerId) -> Option<&PeerScore> {
+        self.scores.get(peer)
+    }
+
+    pub fn score_mut(&mut self, peer: PeerId) -> &mut PeerScore {
+        self.scores.entry(peer).or_default()
+    }
+
+    pub fn capabilities(&self, peer: &PeerId) -> Option<Capabilities> {
+        self.capabilities.get(peer).copied()
+    }
+
+    /// Record the `protocols` `peer`'s Identify lists.
+    pub fn identified(&mut self, peer: PeerId, protocols: &[StreamProtocol]) {
+        let topics = self.capabilities.get(&peer).is_some_and(|known| known.light_client_topics);
+        let capabilities = Capabilities { light_client_topics: topics, ..Capabilities::from_protocols(protocols) };
+        self.capabilities.insert(peer, capabilities);
+    }
+
+    /// Record that `peer` subscribed to a light client gossip topic.
+    pub fn joined_light_client_topic(&mut self, peer: PeerId) {
+        self.capabilities.entry(peer).or_default().light_client_topics = true;
+    }
+
+    /// Forget what `peer` said it can do, once its last connection closed.
+    pub fn disconnected(&mut self, peer: &PeerId) {
+        self.capabilities.remove(peer);
+    }
+
+    /// How much `peer` is worth keeping for what it can serve: 0 if it
+    /// cannot serve light client data, 1 if it has not said or only
+    /// answers Portal queries, 2 if it answers the requests and 3 if it
+    /// gossips the updates too.
+    fn usefulness(&self, peer: &PeerId) -> u8 {
+        match self.capabilities.get(peer) {
+            None => 1,
+            Some(capabilities) if !capabilities.light_client_protocols => {
+                u8::from(capabilities.portal_history || capabilities.portal_state)
+            }
+            Some(capabilities) => 2 + u8::from(capabilities.light_client_topics),
+        }
+    }
+
+    pub fn ban(&mut self, peer: PeerId, now_ms: u64) {
+        self.banned.insert(peer, now_ms.saturating_add(BAN_DURATION.as_millis() as u64));
+    }
+
+    pub fn is_banned(&self, peer: &PeerId, now_ms: u64) -> bool {
+        self.banned.get(peer).is_some_and(|until| now_ms < *until)
+    }
+
+    /// Back `address` off after a failed dial.
+    pub fn dial_failed(&mut self, address: &Multiaddr, now_ms: u64) {
+        let failures = self.backoff.get(address).map_or(0, |backoff| backoff.failures) + 1;
+        let delay = INITIAL_DIAL_BACKOFF.saturating_mul(1 << (failures - 1).min(16)).min(MAX_DIAL_BACKOFF);
+        let retry_at_ms = now_ms.saturating_add(delay.as_millis() as u64);
+        self.backoff.insert(address.clone(), Backoff { failures, retry_at_ms });
+    }
+
+    /// Forget `address`'s failures once a connection to it is open.
+    pub fn connected(&mut self, address: &Multiaddr) {
+        self.backoff.remove(address);
+    }
+
+    /// Whether `address` may be dialed: it is not backing off, and not the
+    /// address of a banned peer.
+    pub fn can_dial(&self, address: &Multiaddr, now_ms: u64) -> bool {
+        let backing_off = self.backoff.get(address).is_some_and(|backoff| now_ms < backoff.retry_at_ms);
+        !backing_off && !peer_of(address).is_some_and(|peer| self.is_banned(&peer, now_ms))
+    }
+
+    /// Which of the `connected` peers to disconnect: those whose score says
+    /// so, banned as well, then while over `max_peers` the least useful,
+    /// the worst-scored first among equally useful ones. Peers never scored
+    /// count as neutral.
+    pub fn prune(&mut self, connected: &[PeerId], now_ms: u64) -> Vec<PeerId> {
+        let (mut pruned, mut kept): (Vec<PeerId>, Vec<PeerId>) = connected
+            .iter()
+            .partition(|peer| self.scores.get(peer).is_some_and(PeerScore::should_disconnect));
+        for peer in &pruned {
+            self.ban(*peer, now_ms);
+        }
+        if kept.len() > self.max_peers {
+            let neutral = PeerScore::new().reputation();
+            let reputation = |peer: &PeerId| self.scores.get(peer).map_or(neutral, PeerScore::reputation);
+            kept.sort_by(|a, b| {
+                let usefulness = self.usefulness(a).cmp(&self.usefulness(b));
+                usefulness.then_with(|| reputation(a).total_cmp(&reputation(b)))
+            });
+            pruned.extend(kept.drain(..kept.len() - self.max_peers));
+        }
+        pruned
+    }
+
+    /// `peers` in the order to send requests to: lowest ping first, then
+    /// those not pinged yet, leaving out any whose score says to drop them.
+    /// Peers that cannot serve light client data go last.
+    pub fn by_latency(&self, peers: impl IntoIterator<Item = PeerId>) -> Vec<PeerId> {
+        let latency = |peer: &PeerId| {
+            self.scores.get(peer).and_then(PeerScore::latency_ms).unwrap_or(f64::INFINITY)
+        };
+        let mut peers: Vec<PeerId> = peers
+            .into_iter()
+            .filter(|peer| !self.scores.get(peer).is_some_and(PeerScore::should_disconnect))
+            .collect();
+        let incapable = |peer: &PeerId| self.usefulness(peer) == 0;
+        peers.sort_by(|a, b| incapable(a).cmp(&incapable(b)).then_with(|| latency(a).total_cmp(&latency(b))));
+        peers
+    }
+
+    /// Addresses to redial while `busy` connections, open or being dialed,
+    /// are fewer than `min_peers`. Skips the peers `connected` already.
+    pub fn to_redial(&self, connected: &HashSet<PeerId>, busy: usize, now_ms: u64) -> Vec<Multiaddr> {
+        self.redial
+            .iter()
+            .filter(|address| !peer_of(address).is_some_and(|peer| connected.contains(&peer)))
+            .filter(|address| self.can_dial(address, now_ms))
+            .take(self.min_peers.saturating_sub(busy))
+            .cloned()
+            .collect()
+    }
+
+    /// Drop bans that have run out, and the backoffs of addresses that have
+    /// not failed for [`MAX_DIAL_BACKOFF`] since they could be dialed again.
+    pub fn expire(&mut self, now_ms: u64) {
+        let grace = MAX_DIAL_BACKOFF.as_millis() as u64;
+        self.banned.retain(|_, until| now_ms < *until);
+        self.backoff.retain(|_, backoff| now_ms < backoff.retry_at_ms.saturating_add(grace));
+    }
+}
+
+/// The peer `address` ends in, if it names one.
+pub(crate) fn peer_of(address: &Multiaddr) -> Option<PeerId> {
+    address.iter().last().and_then(|protocol| match protocol {
+        Protocol::P2p(peer) => Some(peer),
+        _ => None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn address(peer: &PeerId) -> Multiaddr {
+        format!("/dns4/peer.example/tcp/443/wss/p2p/{}", peer).parse().unwrap()
+    }
+
+    #[test]
+    fn test_dial_backoff_doubles_and_resets() {
+        let mut peers = PeerManager::new(3, 10);
+        let address = address(&PeerId::random());
+        assert!(peers.can_dial(&address, 0));
+
+        peers.dial_failed(&address, 0);
+        assert!(!peers.can_dial(&address, 4_999));
+        assert!(peers.can_dial(&address, 5_000));
+        peers.dial_failed(&address, 5_000);
+        assert!(!peers.can_dial(&address, 14_999));
+        assert!(peers.can_dial(&address, 15_000));
+        for _ in 0..20 {
+            peers.dial_failed(&address, 0);
+        }
+        assert!(peers.can_dial(&address, MAX_DIAL_BACKOFF.as_millis() as u64));
+
+        peers.connected(&address);
+        assert!(peers.can_dial(&address, 0));
+    }
+
+    #[test]
+    fn test_bans_block_dials_until_they_end() {
+        let mut peers = PeerManager::new(3, 10);
+        let peer = PeerId::random();
+        peers.ban(peer, 0);
+        assert!(!peers.can_dial(&address(&peer), 1_000));
+        assert!(peers.can_dial(&"/dns4/relay.lumen.dev/tcp/443/wss".parse().unwrap(), 1_000));
+
+        let end = BAN_DURATION.as_millis() as u64;
+        assert!(peers.is_banned(&peer, end - 1));
+        peers.expire(end);
+        assert!(!peers.is_banned(&peer, 0));
+    }
+
+    #[test]
+    fn test_prune_drops_the_worst_over_the_limit() {
+        let mut peers = PeerManager::new(1, 2);
+        let (good, neutral, poor, bad) = (PeerId::random(), PeerId::random(), PeerId::random(), PeerId::random());
+        *peers.score_mut(good) = PeerScore { updates_received: 10, updates_valid: 10, ..PeerScore::new() };
+        *peers.score_mut(poor) = PeerScore { updates_received: 4, updates_valid: 1, ..PeerScore::new() };
+        *peers.score_mut(bad) = PeerScore { updates_received: 10, updates_valid: 2, ..PeerScore::new() };
+
+        assert_eq!(peers.prune(&[good, neutral, poor, bad], 0), [bad, poor]);
+        assert!(peers.is_banned(&bad, 0));
+        assert!(!peers.is_banned(&poor, 0));
+        assert!(peers.prune(&[good, neutral], 0).is_empty());
+    }
+
+    #[test]
+    fn test_requests_prefer_low_latency() {
+        let mut peers = PeerManager::new(2, 4);
+        let (slow, fast, unpinged, bad) = (PeerId::random(), PeerId::random(), PeerId::random(), PeerId::random());
+        peers.score_mut(slow).record_latency(Duration::from_millis(300));
+        peers.score_mut(fast).record_latency(Duration::from_millis(40));
+        *peers.score_mut(bad) = PeerScore { updates_received: 10, updates_valid: 2, ..PeerScore::new() };
+        peers.score_mut(bad).record_latency(Duration::from_millis(10));
+        assert_eq!(peers.by_latency([unpinged, slow, bad, fast]), [fast, slow, unpinged]);
+    }
+
+    fn light_client_protocols() -> Vec<StreamProtocol> {
+        let mut protocols: Vec<StreamProtocol> =
+            LightClientProtocol::ALL.iter().map(LightClientProtocol::stream_protocol).collect();
+        protocols.push(StreamProtocol::new("/meshsub/1.1.0"));
+        protocols
+    }
+
+    #[test]
+    fn test_capabilities_from_identify() {
+        let protocols = light_client_protocols();
+        assert!(Capabilities::from_protocols(&protocols).light_client_protocols);
+        assert!(!Capabilities::from_protocols(&protocols[1..]).light_client_protocols);
+        assert!(!Capabilities::from_protocols(&[]).light_client_protocols);
+        let portal = Capabilities::from_protocols(&[PortalNetwork::State.stream_protocol()]);
+        assert!(portal.portal(PortalNetwork::State) && !portal.portal(PortalNetwork::History));
+
+        // Joining a topic before Identify arrives is kept.
+        let mut peers = PeerManager::new(1, 2);
+        let peer = PeerId::random();
+        peers.joined_light_client_topic(peer);
+        peers.identified(peer, &protocols);
+        let expected =
+            Capabilities { light_client_protocols: true, light_client_topics: true, ..Capabilities::default() };
+        assert_eq!(peers.capabilities(&peer), Some(expected));
+        peers.disconnected(&peer);
+        assert_eq!(peers.capabilities(&peer), None);
+    }
+
+    #[test]
+    fn test_prune_and_requests_prefer_light_client_peers() {
+        let mut peers = PeerManager::new(1, 2);
+        let (gossiping, serving, unknown, incapable) =
+            (PeerId::random(), PeerId::random(), PeerId::random(), PeerId::random());
+        peers.identified(gossiping, &light_client_protocols());
+        peers.joined_light_client_topic(gossiping);
+        peers.identified(serving, &light_client_protocols());
+        peers.identified(incapable, &[StreamProtocol::new("/meshsub/1.1.0")]);
+        // A good score does not save a peer that cannot serve.
+        *peers.score_mut(incapable) = PeerScore { updates_received: 10, updates_valid: 10, ..PeerScore::new() };
+
+        assert_eq!(peers.prune(&[incapable, unknown, serving, gossiping], 0), [incapable, unknown]);
+
+        peers.score_mut(incapable).record_latency(Duration::from_millis(10));
+        peers.score_mut(serving).record_latency(Duration::from_millis(50));
+        assert_eq!(peers.by_latency([incapable, unknown, serving]), [serving, unknown, incapable]);
+    }
+
+    #[test]
+    fn test_dial_limits() {
+        let mut peers = PeerManager::new(2, 10).with_dial_limits(2, 3);
+        assert_eq!(peers.dial_slots(0), 2);
+        assert_eq!(peers.dial_slots(1), 1);
+        assert_eq!(peers.dial_slots(5), 0);
+        for _ in 0..2 {
+            peers.dial_started();
+        }
+        // One left in the round, however few are in progress.
+        assert_eq!(peers.dial_slots(0), 1);
+        peers.dial_started();
+        assert_eq!(peers.dial_slots(0), 0);
+        peers.start_dial_round();
+        assert_eq!(peers.dial_slots(0), 2);
+    }
+
+    #[test]
+    fn test_redial_fills_up_to_min_peers() {
+        let mut peers = PeerManager::new(2, 10);
+        let (a, b, c) = (PeerId::random(), PeerId::random(), PeerId::random());
+        for peer in [a, b, c] {
+            peers.keep(address(&peer));
+        }
+        peers.keep(address(&a));
+        peers.dial_failed(&address(&b), 0);
+
+        assert_eq!(peers.to_redial(&HashSet::from([a]), 0, 0), [address(&c)]);
+        assert_eq!(peers.to_redial(&HashSet::new(), 1, 0), [address(&a)]);
+        assert!(peers.to_redial(&HashSet::new(), 2, 0).is_empty());
+    }
+}
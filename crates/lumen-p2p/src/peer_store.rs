@@ -0,0 +1,196 @@
+//! Good peers, remembered across sessions.
+//!
+//! A [`PeerStore`] holds the peers that passed their Status check and
+//! kept a good score: where we dialed them, over which transport, their
+//! [`PeerScore`] and when we last saw them. The host keeps it through a
+//! [`PeerStorage`] of its choosing, such as `localStorage` or a file, and
+//! the next session dials these peers first, best first, instead of
+//! starting from the bootnodes every time.
+//!
+//! At most [`MAX_STORED_PEERS`] are kept, the worst scored and least
+//! recently seen going first, and a peer not seen for [`PEER_STORE_TTL`]
+//! is forgotten. Like peer directories, the store is trusted for
+//! introductions only: a peer from it still has to pass its Status check.
+
+use crate::behaviour::PeerScore;
+use crate::transport::TransportType;
+use libp2p::{Multiaddr, PeerId};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+pub const MAX_STORED_PEERS: usize = 64;
+
+/// Most addresses kept per peer; the newest come first.
+pub const MAX_STORED_ADDRESSES: usize = 4;
+
+/// How long a peer is remembered after it was last seen.
+pub const PEER_STORE_TTL: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// The format of [`PeerStore::to_bytes`]. Stores in another are dropped.
+pub const PEER_STORE_VERSION: u32 = 1;
+
+/// Where the host keeps the [`PeerStore`] between sessions. The service
+/// calls it from its event loop, so implementations should be quick, and
+/// look the backing store up on each call rather than hold on to a
+/// browser object.
+pub trait PeerStorage: Send + Sync {
+    /// The bytes last saved, if any.
+    fn load(&self) -> Option<Vec<u8>>;
+    fn save(&self, bytes: &[u8]);
+}
+
+/// A remembered peer.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct StoredPeer {
+    pub peer_id: String,
+    /// Addresses we dialed it at, newest first.
+    pub addrs: Vec<String>,
+    pub transport: TransportType,
+    pub score: PeerScore,
+    /// Unix milliseconds.
+    pub last_seen_ms: u64,
+}
+
+/// See the module docs.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PeerStore {
+    version: u32,
+    peers: Vec<StoredPeer>,
+}
+
+impl Default for PeerStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PeerStore {
+    pub fn new() -> Self {
+        Self { version: PEER_STORE_VERSION, peers: Vec::new() }
+    }
+
+    /// Read a store saved by [`Self::to_bytes`].
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        let store: Self = serde_json::from_slice(bytes).map_err(|e| format!("Invalid peer store: {}", e))?;
+        if store.version != PEER_STORE_VERSION {
+            return Err(format!("Peer store version {} is not {}", store.version, PEER_STORE_VERSION));
+        }
+        Ok(store)
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        serde_json::to_vec(self).expect("peer store serializes")
+    }
+
+    pub fn peers(&self) -> &[StoredPeer] {
+        &self.peers
+    }
+
+    pub fn len(&self) -> usize {
+        self.peers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.peers.is_empty()
+    }
+
+    /// Remember `peer`, merging it with what is known of it already.
+    pub fn record(&mut self, mut peer: StoredPeer) {
+        if let Some(known) = self.peers.iter().position(|stored| stored.peer_id == peer.peer_id) {
+            for address in self.peers.swap_remove(known).addrs {
+                if !peer.addrs.contains(&address) {
+                    peer.addrs.push(address);
+                }
+            }
+        }
+        peer.addrs.truncate(MAX_STORED_ADDRESSES);
+        self.peers.push(peer);
+        self.sort();
+        self.peers.truncate(MAX_STORED_PEERS);
+    }
+
+    /// Forget `peer`, e.g. once it is banned.
+    pub fn remove(&mut self, peer: &PeerId) {
+        let peer = peer.to_string();
+        self.peers.retain(|stored| stored.peer_id != peer);
+    }
+
+    /// Forget peers not seen for [`PEER_STORE_TTL`].
+    pub fn expire(&mut self, now_ms: u64) {
+        let ttl = PEER_STORE_TTL.as_millis() as u64;
+        self.peers.retain(|peer| now_ms < peer.last_seen_ms.saturating_add(ttl));
+    }
+
+    /// The peers to dial, best first, with their addresses. Peers whose id
+    /// or every address does not parse are left out.
+    pub fn dial_order(&self) -> Vec<(PeerId, Vec<Multiaddr>, &PeerScore)> {
+        self.peers
+            .iter()
+            .filter_map(|peer| {
+                let id = peer.peer_id.parse().ok()?;
+                let addrs: Vec<Multiaddr> = peer.addrs.iter().filter_map(|address| address.parse().ok()).collect();
+                (!addrs.is_empty()).then_some((id, addrs, &peer.score))
+            })
+            .collect()
+    }
+
+    /// Best scored first, then most recently seen.
+    fn sort(&mut self) {
+        self.peers.sort_by(|a, b| {
+            b.score.reputation().total_cmp(&a.score.reputation()).then(b.last_seen_ms.cmp(&a.last_seen_ms))
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stored(peer: &PeerId, host: &str, valid: u64, last_seen_ms: u64) -> StoredPeer {
+        StoredPeer {
+            peer_id: peer.to_string(),
+            addrs: vec![format!("/dns4/{}.example/tcp/443/wss", host)],
+            transport: TransportType::WebSocket,
+            score: PeerScore { updates_received: 10, updates_valid: valid, ..PeerScore::new() },
+            last_seen_ms,
+        }
+    }
+
+    #[test]
+    fn test_best_peers_first_and_merged() {
+        let (good, fair, recent) = (PeerId::random(), PeerId::random(), PeerId::random());
+        let mut store = PeerStore::new();
+        store.record(stored(&fair, "fair", 7, 100));
+        store.record(stored(&good, "good", 10, 0));
+        store.record(stored(&recent, "recent", 7, 200));
+        let order: Vec<PeerId> = store.dial_order().into_iter().map(|(peer, _, _)| peer).collect();
+        assert_eq!(order, [good, recent, fair]);
+
+        // Seen again at another address: both are kept, the new one first.
+        store.record(stored(&fair, "fair2", 7, 300));
+        assert_eq!(store.len(), 3);
+        let fair_peer = store.peers().iter().find(|peer| peer.peer_id == fair.to_string()).unwrap();
+        assert_eq!(fair_peer.addrs, ["/dns4/fair2.example/tcp/443/wss", "/dns4/fair.example/tcp/443/wss"]);
+
+        store.remove(&good);
+        assert_eq!(store.len(), 2);
+        store.expire(PEER_STORE_TTL.as_millis() as u64 + 250);
+        assert_eq!(store.len(), 1);
+    }
+
+    #[test]
+    fn test_bytes_round_trip_and_cap() {
+        let mut store = PeerStore::new();
+        for i in 0..MAX_STORED_PEERS as u64 + 5 {
+            store.record(stored(&PeerId::random(), "peer", 10, i));
+        }
+        assert_eq!(store.len(), MAX_STORED_PEERS);
+        // The least recently seen went first.
+        assert!(store.peers().iter().all(|peer| peer.last_seen_ms >= 5));
+
+        let read = PeerStore::from_bytes(&store.to_bytes()).unwrap();
+        assert_eq!(read.dial_order().len(), MAX_STORED_PEERS);
+        assert!(PeerStore::from_bytes(b"{\"version\":0,\"peers\":[]}").unwrap_err().contains("version 0"));
+        assert!(PeerStore::from_bytes(b"not json").is_err());
+    }
+}
@@ -0,0 +1,721 @@
+//! A client for Lumen's content overlay, which carries Portal Network
+//! content over libp2p, for execution layer data over P2P.
+//!
+//! Account proofs, headers and receipts otherwise come from JSON-RPC
+//! endpoints, which see every query. The Portal Network keeps the same data
+//! in a DHT: its history network holds headers, bodies and receipts by
+//! block hash, its state network the state trie's nodes by path and hash.
+//! Each piece of content lives at its [`content_id`], the SHA-256 of its
+//! content key, on the nodes closest to that id by XOR distance.
+//!
+//! This is not Portal's wire protocol, and no Portal node serves it. Portal
+//! runs over discv5's TALKREQ, and large content over uTP, both on UDP and
+//! out of a browser's reach. Lumen's overlay keeps Portal's content keys,
+//! content ids and `FIND_CONTENT` / `CONTENT` messages, but sends them over
+//! a libp2p request-response protocol of its own per subnetwork
+//! ([`PortalNetwork::id`]), answered only by nodes that speak it. A stream
+//! carries content of any size whole, so uTP's connection ids are never
+//! sent; node records are multiaddrs ending in `/p2p/<peer id>` rather
+//! than ENRs, and a node's id is the SHA-256 of its peer id.
+//!
+//! Reaching the Portal DHT itself needs a bridge: a node answering the
+//! overlay from Portal, over discv5 and uTP. No such bridge exists yet, in
+//! Lumen or elsewhere; until one is running, a lookup only finds content
+//! that overlay nodes hold themselves.
+//!
+//! Lumen only asks, with `FIND_CONTENT`: it stores nothing, so answers no
+//! queries. A [`ContentLookup`] asks the closest nodes known first, moving
+//! on to the closer ones each points to. Nothing found is used before it is
+//! checked against what the light client already trusts: a header against
+//! its block hash ([`decode_block_header`]), receipts against the header's
+//! receipts root ([`decode_receipts`]), and trie nodes against their hashes
+//! and, as an account proof ([`AccountProofWalk`]), the state root.
+
+use crate::req_resp::{invalid_data, MAX_PAYLOAD_SIZE, REQUEST_TIMEOUT};
+use async_trait::async_trait;
+use futures::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use libp2p::multiaddr::Protocol;
+use libp2p::request_response::{self, ProtocolSupport};
+use libp2p::{Multiaddr, PeerId, StreamProtocol};
+use lumen_core::execution::header::{verify_block_header, ExecutionBlockHeader};
+use lumen_core::execution::proof::{
+    bytes_to_nibbles, decode_compact_path, decode_rlp_list, keccak256, verify_account_proof, ProofError,
+};
+use lumen_core::execution::receipt::verify_receipt_proof;
+use lumen_core::execution::rlp;
+use lumen_core::execution::trie_builder::TrieBuilder;
+use lumen_core::types::execution::{AccountProof, ReceiptProof, TransactionReceipt};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::io;
+
+/// Lumen's overlay protocol for Portal's history network content.
+pub const LUMEN_HISTORY_OVERLAY_PROTOCOL: &str = "/lumen/overlay/history/1";
+
+/// Lumen's overlay protocol for Portal's state network content.
+pub const LUMEN_STATE_OVERLAY_PROTOCOL: &str = "/lumen/overlay/state/1";
+
+/// Largest Portal message read, content included.
+pub const MAX_PORTAL_MESSAGE_SIZE: usize = MAX_PAYLOAD_SIZE;
+
+/// Most node records taken from one answer (`List[ByteList, 32]`).
+pub const MAX_NODE_RECORDS: usize = 32;
+
+/// Most nodes asked in one content lookup.
+pub const MAX_LOOKUP_QUERIES: usize = 16;
+
+/// Nodes asked at once in a content lookup.
+pub const LOOKUP_PARALLELISM: usize = 3;
+
+/// Most nodes on an account's path through the state trie: one per nibble
+/// of its 32-byte key, and the leaf.
+const MAX_ACCOUNT_PROOF_NODES: usize = 65;
+
+/// `FIND_CONTENT`'s message id.
+const FIND_CONTENT: u8 = 0x04;
+
+/// `CONTENT`'s message id.
+const CONTENT: u8 = 0x05;
+
+/// A Portal subnetwork.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PortalNetwork {
+    History,
+    State,
+}
+
+impl PortalNetwork {
+    pub const ALL: [PortalNetwork; 2] = [PortalNetwork::History, PortalNetwork::State];
+
+    pub fn id(&self) -> &'static str {
+        match self {
+            Self::History => LUMEN_HISTORY_OVERLAY_PROTOCOL,
+            Self::State => LUMEN_STATE_OVERLAY_PROTOCOL,
+        }
+    }
+
+    pub fn stream_protocol(&self) -> StreamProtocol {
+        StreamProtocol::new(self.id())
+    }
+
+    /// A request-response behaviour asking on this network.
+    pub fn behaviour(&self) -> request_response::Behaviour<PortalCodec> {
+        request_response::Behaviour::new(
+            [(self.stream_protocol(), ProtocolSupport::Outbound)],
+            request_response::Config::default().with_request_timeout(REQUEST_TIMEOUT),
+        )
+    }
+}
+
+/// A history network content key.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HistoryContentKey {
+    /// The RLP header of the block with this hash, with a proof.
+    BlockHeaderByHash([u8; 32]),
+    /// The block's transactions, uncles and withdrawals.
+    BlockBody([u8; 32]),
+    /// The block's receipts, in order.
+    Receipts([u8; 32]),
+    BlockHeaderByNumber(u64),
+}
+
+impl HistoryContentKey {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let (selector, key) = match self {
+            Self::BlockHeaderByHash(hash) => (0x00, hash.to_vec()),
+            Self::BlockBody(hash) => (0x01, hash.to_vec()),
+            Self::Receipts(hash) => (0x02, hash.to_vec()),
+            Self::BlockHeaderByNumber(number) => (0x03, number.to_le_bytes().to_vec()),
+        };
+        [vec![selector], key].concat()
+    }
+}
+
+/// A state network content key. Paths are the nibbles from the trie's
+/// root to the node.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum StateContentKey {
+    AccountTrieNode { path: Vec<u8>, node_hash: [u8; 32] },
+    ContractStorageTrieNode { address: [u8; 20], path: Vec<u8>, node_hash: [u8; 32] },
+    ContractBytecode { address: [u8; 20], code_hash: [u8; 32] },
+}
+
+impl StateContentKey {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        match self {
+            Self::AccountTrieNode { path, node_hash } => {
+                out.push(0x20);
+                // The path's offset, past the fixed part.
+                out.extend_from_slice(&(4u32 + 32).to_le_bytes());
+                out.extend_from_slice(node_hash);
+                out.extend_from_slice(&encode_nibbles(path));
+            }
+            Self::ContractStorageTrieNode { address, path, node_hash } => {
+                out.push(0x21);
+                out.extend_from_slice(address);
+                out.extend_from_slice(&(20u32 + 4 + 32).to_le_bytes());
+                out.extend_from_slice(node_hash);
+                out.extend_from_slice(&encode_nibbles(path));
+            }
+            Self::ContractBytecode { address, code_hash } => {
+                out.push(0x22);
+                out.extend_from_slice(address);
+                out.extend_from_slice(code_hash);
+            }
+        }
+        out
+    }
+}
+
+/// Where the content with `content_key` lives in the DHT.
+pub fn content_id(content_key: &[u8]) -> [u8; 32] {
+    Sha256::digest(content_key).into()
+}
+
+/// A node's id in Lumen's Portal overlay.
+pub fn node_id(peer: &PeerId) -> [u8; 32] {
+    Sha256::digest(peer.to_bytes()).into()
+}
+
+/// The XOR distance between two ids, as a big-endian number.
+pub fn distance(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
+    std::array::from_fn(|i| a[i] ^ b[i])
+}
+
+/// Portal's `Nibbles`: a flag byte, `0x1n` with the first nibble `n` of an
+/// odd-length path or `0x00`, then the rest packed two to a byte.
+fn encode_nibbles(nibbles: &[u8]) -> Vec<u8> {
+    let (flag, rest) = match nibbles.len() % 2 {
+        1 => (0x10 | nibbles[0], &nibbles[1..]),
+        _ => (0x00, nibbles),
+    };
+    let mut out = vec![flag];
+    out.extend(rest.chunks_exact(2).map(|pair| (pair[0] << 4) | pair[1]));
+    out
+}
+
+/// Items of an SSZ list of variable-size items, or fields of a container
+/// of nothing else: offsets, then the data they point into.
+fn decode_variable_items(bytes: &[u8]) -> Result<Vec<&[u8]>, String> {
+    if bytes.is_empty() {
+        return Ok(Vec::new());
+    }
+    let offset_at = |i: usize| {
+        bytes
+            .get(i * 4..i * 4 + 4)
+            .map(|offset| u32::from_le_bytes(offset.try_into().expect("4 bytes")) as usize)
+            .ok_or_else(|| "SSZ offsets run past the end".to_string())
+    };
+    let first = offset_at(0)?;
+    if first == 0 || first % 4 != 0 || first > bytes.len() {
+        return Err(format!("Invalid first SSZ offset {}", first));
+    }
+    let mut offsets = (0..first / 4).map(offset_at).collect::<Result<Vec<_>, _>>()?;
+    offsets.push(bytes.len());
+    offsets
+        .windows(2)
+        .map(|pair| match bytes.get(pair[0]..pair[1]) {
+            Some(item) => Ok(item),
+            None => Err(format!("Invalid SSZ offsets {} to {}", pair[0], pair[1])),
+        })
+        .collect()
+}
+
+fn encode_variable_items<T: AsRef<[u8]>>(items: &[T]) -> Vec<u8> {
+    let mut offset = 4 * items.len();
+    let mut out = Vec::new();
+    for item in items {
+        out.extend_from_slice(&(offset as u32).to_le_bytes());
+        offset += item.as_ref().len();
+    }
+    items.iter().for_each(|item| out.extend_from_slice(item.as_ref()));
+    out
+}
+
+/// `FIND_CONTENT`: ask a node for the content with `content_key`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FindContent {
+    pub content_key: Vec<u8>,
+}
+
+impl FindContent {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        [vec![FIND_CONTENT], encode_variable_items(&[&self.content_key])].concat()
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        match bytes.split_first() {
+            Some((&FIND_CONTENT, container)) => match decode_variable_items(container)?[..] {
+                [content_key] => Ok(Self { content_key: content_key.to_vec() }),
+                _ => Err("FIND_CONTENT has one field".to_string()),
+            },
+            _ => Err("Not a FIND_CONTENT message".to_string()),
+        }
+    }
+}
+
+/// `CONTENT`: the content asked for, or nodes closer to it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ContentResponse {
+    Content(Vec<u8>),
+    /// Records of nodes closer to the content, as multiaddrs that end in
+    /// the node's peer id.
+    Nodes(Vec<Multiaddr>),
+}
+
+impl ContentResponse {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = vec![CONTENT];
+        match self {
+            Self::Content(content) => {
+                out.push(1);
+                out.extend_from_slice(content);
+            }
+            Self::Nodes(nodes) => {
+                out.push(2);
+                out.extend(encode_variable_items(&nodes.iter().map(Multiaddr::to_vec).collect::<Vec<_>>()));
+            }
+        }
+        out
+    }
+
+    /// Records that do not parse are left out.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        match bytes {
+            [CONTENT, 0, ..] => Err("Content sent over uTP, which streams do not use".to_string()),
+            [CONTENT, 1, content @ ..] => Ok(Self::Content(content.to_vec())),
+            [CONTENT, 2, records @ ..] => {
+                let records = decode_variable_items(records)?;
+                if records.len() > MAX_NODE_RECORDS {
+                    return Err(format!("{} node records, more than {}", records.len(), MAX_NODE_RECORDS));
+                }
+                let nodes = records.into_iter().filter_map(|record| Multiaddr::try_from(record.to_vec()).ok());
+                Ok(Self::Nodes(nodes.collect()))
+            }
+            _ => Err("Not a CONTENT message".to_string()),
+        }
+    }
+}
+
+/// Portal messages over a stream: one SSZ message each way, each side
+/// closing its end after writing.
+#[derive(Clone, Debug, Default)]
+pub struct PortalCodec;
+
+async fn read_message<T>(io: &mut T) -> io::Result<Vec<u8>>
+where
+    T: AsyncRead + Unpin + Send,
+{
+    let mut message = Vec::new();
+    io.take(MAX_PORTAL_MESSAGE_SIZE as u64 + 1).read_to_end(&mut message).await?;
+    if message.len() > MAX_PORTAL_MESSAGE_SIZE {
+        return Err(invalid_data(format!("Portal message over {} bytes", MAX_PORTAL_MESSAGE_SIZE)));
+    }
+    Ok(message)
+}
+
+async fn write_message<T>(io: &mut T, message: &[u8]) -> io::Result<()>
+where
+    T: AsyncWrite + Unpin + Send,
+{
+    io.write_all(message).await?;
+    io.close().await
+}
+
+#[async_trait]
+impl request_response::Codec for PortalCodec {
+    type Protocol = StreamProtocol;
+    type Request = FindContent;
+    type Response = ContentResponse;
+
+    async fn read_request<T>(&mut self, _: &StreamProtocol, io: &mut T) -> io::Result<FindContent>
+    where
+        T: AsyncRead + Unpin + Send,
+    {
+        FindContent::from_bytes(&read_message(io).await?).map_err(invalid_data)
+    }
+
+    async fn read_response<T>(&mut self, _: &StreamProtocol, io: &mut T) -> io::Result<ContentResponse>
+    where
+        T: AsyncRead + Unpin + Send,
+    {
+        ContentResponse::from_bytes(&read_message(io).await?).map_err(invalid_data)
+    }
+
+    async fn write_request<T>(&mut self, _: &StreamProtocol, io: &mut T, request: FindContent) -> io::Result<()>
+    where
+        T: AsyncWrite + Unpin + Send,
+    {
+        write_message(io, &request.to_bytes()).await
+    }
+
+    async fn write_response<T>(&mut self, _: &StreamProtocol, io: &mut T, response: ContentResponse) -> io::Result<()>
+    where
+        T: AsyncWrite + Unpin + Send,
+    {
+        write_message(io, &response.to_bytes()).await
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Candidate {
+    peer: PeerId,
+    addresses: Vec<Multiaddr>,
+    asked: bool,
+}
+
+/// A lookup of one content id: the nodes known, closest first, and how
+/// many have been asked.
+#[derive(Clone, Debug)]
+pub struct ContentLookup {
+    content_id: [u8; 32],
+    local: PeerId,
+    /// By distance to the content.
+    candidates: BTreeMap<[u8; 32], Candidate>,
+    queried: usize,
+}
+
+impl ContentLookup {
+    /// A lookup of `content_key` by `local`, starting from `peers`.
+    pub fn new(content_key: &[u8], local: PeerId, peers: impl IntoIterator<Item = PeerId>) -> Self {
+        let mut lookup = Self { content_id: content_id(content_key), local, candidates: BTreeMap::new(), queried: 0 };
+        for peer in peers {
+            lookup.add(peer, Vec::new());
+        }
+        lookup
+    }
+
+    pub fn content_id(&self) -> [u8; 32] {
+        self.content_id
+    }
+
+    pub fn queried(&self) -> usize {
+        self.queried
+    }
+
+    /// The closest node not asked yet, and the addresses to reach it at,
+    /// unless [`MAX_LOOKUP_QUERIES`] have been asked already.
+    pub fn next_query(&mut self) -> Option<(PeerId, Vec<Multiaddr>)> {
+        if self.queried >= MAX_LOOKUP_QUERIES {
+            return None;
+        }
+        let candidate = self.candidates.values_mut().find(|candidate| !candidate.asked)?;
+        candidate.asked = true;
+        self.queried += 1;
+        Some((candidate.peer, candidate.addresses.clone()))
+    }
+
+    /// Add the nodes an answer pointed to. Returns how many were new.
+    pub fn add_nodes(&mut self, records: Vec<Multiaddr>) -> usize {
+        records
+            .into_iter()
+            .filter_map(|record| match record.iter().last() {
+                Some(Protocol::P2p(peer)) => Some((peer, record)),
+                _ => None,
+            })
+            .filter(|(peer, record)| self.add(*peer, vec![record.clone()]))
+            .count()
+    }
+
+    fn add(&mut self, peer: PeerId, addresses: Vec<Multiaddr>) -> bool {
+        if peer == self.local {
+            return false;
+        }
+        let key = distance(&node_id(&peer), &self.content_id);
+        match self.candidates.get_mut(&key) {
+            Some(known) => {
+                known.addresses.extend(addresses);
+                false
+            }
+            None => {
+                self.candidates.insert(key, Candidate { peer, addresses, asked: false });
+                true
+            }
+        }
+    }
+}
+
+/// Check history content for [`HistoryContentKey::BlockHeaderByHash`]: a
+/// `BlockHeaderWithProof` whose header hashes to `block_hash`. The proof
+/// of the header's place in history is not needed once the hash is
+/// trusted, and is not checked.
+pub fn decode_block_header(content: &[u8], block_hash: [u8; 32]) -> Result<ExecutionBlockHeader, String> {
+    match decode_variable_items(content)?[..] {
+        [header, _proof] => verify_block_header(header, block_hash).map_err(|e| e.to_string()),
+        _ => Err("BlockHeaderWithProof has two fields".to_string()),
+    }
+}
+
+/// Check history content for [`HistoryContentKey::Receipts`]: each of a
+/// block's receipts as in its receipts trie, which rebuilt must have
+/// `receipts_root`.
+pub fn decode_receipts(content: &[u8], receipts_root: [u8; 32]) -> Result<Vec<TransactionReceipt>, String> {
+    let encoded = decode_variable_items(content)?;
+    let mut trie = TrieBuilder::new();
+    for (index, receipt) in encoded.iter().enumerate() {
+        trie.insert(&rlp::encode_u64(index as u64), receipt);
+    }
+    if trie.root() != receipts_root {
+        return Err(format!("Receipts have root {}, not {}", hex::encode(trie.root()), hex::encode(receipts_root)));
+    }
+    (0..encoded.len() as u64)
+        .map(|tx_index| {
+            let proof = ReceiptProof { tx_index, proof: trie.proof(&rlp::encode_u64(tx_index)) };
+            verify_receipt_proof(receipts_root, tx_index, &proof).map_err(|e| e.to_string())
+        })
+        .collect()
+}
+
+/// Check state content for a trie node key: an `AccountTrieNodeRetrieval`
+/// or `ContractStorageTrieNodeRetrieval` whose node hashes to `node_hash`.
+pub fn decode_trie_node(content: &[u8], node_hash: [u8; 32]) -> Result<Vec<u8>, String> {
+    match decode_variable_items(content)?[..] {
+        [node] if keccak256(node) == node_hash => Ok(node.to_vec()),
+        [_] => Err(format!("Trie node does not hash to {}", hex::encode(node_hash))),
+        _ => Err("Trie node retrieval has one field".to_string()),
+    }
+}
+
+/// An account proof collected node by node from the state network: from
+/// the state root down the path of the account's key, until its leaf or
+/// where the path shows it absent.
+#[derive(Clone, Debug)]
+pub struct AccountProofWalk {
+    state_root: [u8; 32],
+    address: [u8; 20],
+    key: Vec<u8>,
+    /// Nibbles of the key the nodes so far cover.
+    depth: usize,
+    /// The hash of the node to fetch next.
+    next: Option<[u8; 32]>,
+    nodes: Vec<Vec<u8>>,
+}
+
+impl AccountProofWalk {
+    pub fn new(state_root: [u8; 32], address: [u8; 20]) -> Self {
+        let key = bytes_to_nibbles(&keccak256(&address));
+        Self { state_root, address, key, depth: 0, next: Some(state_root), nodes: Vec::new() }
+    }
+
+    /// The key of the node to fetch next; none once the path is complete.
+    pub fn next_key(&self) -> Option<StateContentKey> {
+        let node_hash = self.next?;
+        Some(StateContentKey::AccountTrieNode { path: self.key[..self.depth].to_vec(), node_hash })
+    }
+
+    /// Take the content found for [`next_key`](Self::next_key), checked
+    /// with [`decode_trie_node`], and move down the path.
+    pub fn push(&mut self, content: &[u8]) -> Result<(), String> {
+        let node_hash = self.next.ok_or("The account proof is complete")?;
+        if self.nodes.len() >= MAX_ACCOUNT_PROOF_NODES {
+            return Err(format!("Account proof is over {} nodes", MAX_ACCOUNT_PROOF_NODES));
+        }
+        let node = decode_trie_node(content, node_hash)?;
+        let items = decode_rlp_list(&node)?;
+        let child = match items.len() {
+            17 if self.depth < self.key.len() => {
+                self.depth += 1;
+                Some(items[self.key[self.depth - 1] as usize].clone())
+            }
+            2 => {
+                let (path, leaf) = decode_compact_path(&items[0])?;
+                if !leaf && path.is_empty() {
+                    return Err("Extension node with an empty path".to_string());
+                }
+                let on_path = self.key[self.depth..].starts_with(&path);
+                self.depth += path.len();
+                (!leaf && on_path).then(|| items[1].clone())
+            }
+            _ => None,
+        };
+        self.nodes.push(node);
+        // Children under 32 bytes are embedded in their parent, which is
+        // then the last node to fetch.
+        self.next = child.and_then(|child| child.try_into().ok());
+        Ok(())
+    }
+
+    /// The proof, checked against the state root. Its `account` is none
+    /// if the proof shows the account absent.
+    pub fn finish(self) -> Result<AccountProof, String> {
+        if self.next.is_some() {
+            return Err("The account proof is incomplete".to_string());
+        }
+        let mut proof = AccountProof { address: self.address, proof: self.nodes, account: None };
+        match verify_account_proof(self.state_root, self.address, &proof) {
+            Ok(account) => proof.account = Some(account),
+            Err(ProofError::AccountNotFound { .. }) => {}
+            Err(e) => return Err(e.to_string()),
+        }
+        Ok(proof)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lumen_core::execution::receipt::{build_receipt_proof, encode_receipt};
+    use lumen_core::types::execution::{AccountState, TransactionType};
+    use lumen_core::U256;
+
+    #[test]
+    fn test_content_keys() {
+        let hash = [0xab; 32];
+        assert_eq!(HistoryContentKey::Receipts(hash).to_bytes(), [vec![0x02], hash.to_vec()].concat());
+        assert_eq!(HistoryContentKey::BlockHeaderByNumber(1).to_bytes(), [3, 1, 0, 0, 0, 0, 0, 0, 0]);
+
+        // The root's key, and an odd path's.
+        let root = StateContentKey::AccountTrieNode { path: Vec::new(), node_hash: hash };
+        assert_eq!(root.to_bytes(), [vec![0x20, 36, 0, 0, 0], hash.to_vec(), vec![0x00]].concat());
+        let odd = StateContentKey::AccountTrieNode { path: vec![1, 2, 3], node_hash: hash };
+        assert_eq!(odd.to_bytes()[37..], [0x11, 0x23]);
+        assert_eq!(content_id(&root.to_bytes()), <[u8; 32]>::from(Sha256::digest(root.to_bytes())));
+    }
+
+    #[test]
+    fn test_messages_round_trip() {
+        let request = FindContent { content_key: HistoryContentKey::Receipts([1; 32]).to_bytes() };
+        assert_eq!(request.to_bytes()[..5], [FIND_CONTENT, 4, 0, 0, 0]);
+        assert_eq!(FindContent::from_bytes(&request.to_bytes()).unwrap(), request);
+
+        let content = ContentResponse::Content(vec![1, 2, 3]);
+        assert_eq!(content.to_bytes(), [CONTENT, 1, 1, 2, 3]);
+        assert_eq!(ContentResponse::from_bytes(&content.to_bytes()).unwrap(), content);
+        let peer = PeerId::random();
+        let nodes = ContentResponse::Nodes(vec![format!("/ip4/1.2.3.4/tcp/443/wss/p2p/{}", peer).parse().unwrap()]);
+        assert_eq!(ContentResponse::from_bytes(&nodes.to_bytes()).unwrap(), nodes);
+        assert_eq!(ContentResponse::from_bytes(&[CONTENT, 2]).unwrap(), ContentResponse::Nodes(Vec::new()));
+
+        assert!(ContentResponse::from_bytes(&[CONTENT, 0, 0x12, 0x34]).unwrap_err().contains("uTP"));
+        assert!(FindContent::from_bytes(&content.to_bytes()).is_err());
+        assert!(ContentResponse::from_bytes(&[CONTENT, 2, 9, 0, 0, 0]).is_err());
+    }
+
+    #[test]
+    fn test_lookup_asks_the_closest_first() {
+        let key = HistoryContentKey::BlockHeaderByHash([7; 32]).to_bytes();
+        let local = PeerId::random();
+        let peers: Vec<PeerId> = (0..4).map(|_| PeerId::random()).collect();
+        let mut lookup = ContentLookup::new(&key, local, peers.clone());
+        let by_distance = |peer: &PeerId| distance(&node_id(peer), &content_id(&key));
+
+        let (first, _) = lookup.next_query().unwrap();
+        assert_eq!(first, *peers.iter().min_by_key(|peer| by_distance(peer)).unwrap());
+
+        // Nodes pointed to join, with the address to reach them at; ones
+        // known, ourselves and records without a peer id do not.
+        let closer: Vec<Multiaddr> = (0..3)
+            .map(|i| format!("/ip4/10.0.0.{}/tcp/443/wss/p2p/{}", i, PeerId::random()).parse().unwrap())
+            .chain([format!("/p2p/{}", local).parse().unwrap(), format!("/p2p/{}", first).parse().unwrap()])
+            .chain(["/ip4/10.0.0.9/tcp/443/wss".parse().unwrap()])
+            .collect();
+        assert_eq!(lookup.add_nodes(closer), 3);
+        let mut asked = vec![first];
+        while let Some((peer, addresses)) = lookup.next_query() {
+            assert_eq!(addresses.is_empty(), peers.contains(&peer));
+            assert!(!asked.contains(&peer));
+            asked.push(peer);
+        }
+        assert_eq!(lookup.queried(), 7);
+        assert!(asked[1..].windows(2).all(|pair| by_distance(&pair[0]) < by_distance(&pair[1])));
+    }
+
+    #[test]
+    fn test_lookup_stops_after_max_queries() {
+        let peers = (0..MAX_LOOKUP_QUERIES + 4).map(|_| PeerId::random());
+        let mut lookup = ContentLookup::new(b"key", PeerId::random(), peers);
+        assert_eq!(std::iter::from_fn(|| lookup.next_query()).count(), MAX_LOOKUP_QUERIES);
+    }
+
+    #[test]
+    fn test_block_header_is_checked_against_its_hash() {
+        let header = ExecutionBlockHeader {
+            parent_hash: [1; 32],
+            ommers_hash: [2; 32],
+            beneficiary: [3; 20],
+            state_root: [4; 32],
+            transactions_root: [5; 32],
+            receipts_root: [6; 32],
+            logs_bloom: [0; 256],
+            difficulty: U256::ZERO,
+            number: 20_000_000,
+            gas_limit: 30_000_000,
+            gas_used: 12_000_000,
+            timestamp: 1_717_000_000,
+            extra_data: b"lumen".to_vec(),
+            mix_hash: [7; 32],
+            nonce: [0; 8],
+            base_fee_per_gas: Some(U256::from_u64(7)),
+            withdrawals_root: Some([8; 32]),
+            blob_gas_used: Some(0),
+            excess_blob_gas: Some(0),
+            parent_beacon_block_root: Some([9; 32]),
+            requests_hash: None,
+        };
+        let content = encode_variable_items(&[header.encode(), vec![0; 40]]);
+        assert_eq!(decode_block_header(&content, header.hash()).unwrap(), header);
+        assert!(decode_block_header(&content, [0; 32]).is_err());
+        assert!(decode_block_header(&header.encode(), header.hash()).is_err());
+    }
+
+    #[test]
+    fn test_receipts_are_checked_against_the_root() {
+        let receipt = |cumulative_gas_used| TransactionReceipt {
+            tx_type: TransactionType::DynamicFee,
+            status: 1,
+            cumulative_gas_used,
+            logs_bloom: [0; 256],
+            logs: Vec::new(),
+        };
+        let receipts: Vec<TransactionReceipt> = (1..=3).map(|i| receipt(21_000 * i)).collect();
+        let (root, _) = build_receipt_proof(&receipts, 0).unwrap();
+        let content = encode_variable_items(&receipts.iter().map(encode_receipt).collect::<Vec<_>>());
+
+        let decoded = decode_receipts(&content, root).unwrap();
+        assert_eq!(decoded.iter().map(|r| r.cumulative_gas_used).collect::<Vec<_>>(), [21_000, 42_000, 63_000]);
+        assert!(decode_receipts(&content, [0; 32]).unwrap_err().contains("root"));
+        // A block without transactions has the empty trie's root.
+        assert!(decode_receipts(&[], AccountState::EMPTY_STORAGE_ROOT).unwrap().is_empty());
+    }
+
+    /// Fetch every node of `walk` from `trie`, as the state network would
+    /// serve them.
+    fn walk_trie(mut walk: AccountProofWalk, trie: &TrieBuilder, address: &[u8; 20]) -> AccountProof {
+        let nodes = trie.account_proof(address).proof;
+        let by_hash: BTreeMap<[u8; 32], Vec<u8>> = nodes.into_iter().map(|node| (keccak256(&node), node)).collect();
+        while let Some(StateContentKey::AccountTrieNode { node_hash, .. }) = walk.next_key() {
+            walk.push(&encode_variable_items(&[&by_hash[&node_hash]])).unwrap();
+        }
+        walk.finish().unwrap()
+    }
+
+    #[test]
+    fn test_account_proof_walk() {
+        let mut trie = TrieBuilder::new();
+        let account = |nonce| AccountState {
+            nonce,
+            balance: U256::from_u64(nonce * 1_000),
+            storage_root: AccountState::EMPTY_STORAGE_ROOT,
+            code_hash: AccountState::EMPTY_CODE_HASH,
+        };
+        for i in 0..64u8 {
+            trie.insert_account(&[i; 20], &account(i as u64));
+        }
+        let root = trie.root();
+
+        let proof = walk_trie(AccountProofWalk::new(root, [42; 20]), &trie, &[42; 20]);
+        assert_eq!(proof.account.unwrap().nonce, 42);
+        assert_eq!(proof.proof, trie.account_proof(&[42; 20]).proof);
+
+        // An absent account's path ends where it leaves the trie.
+        let absent = walk_trie(AccountProofWalk::new(root, [0xee; 20]), &trie, &[0xee; 20]);
+        assert!(absent.account.is_none());
+
+        // The first key asked for is the root's, at the empty path.
+        let mut walk = AccountProofWalk::new(root, [42; 20]);
+        assert_eq!(walk.next_key(), Some(StateContentKey::AccountTrieNode { path: Vec::new(), node_hash: root }));
+        assert!(walk.push(&encode_variable_items(&[vec![0xc0]])).unwrap_err().contains("hash"));
+        assert!(walk.finish().unwrap_err().contains("incomplete"));
+    }
+}
@@ -0,0 +1,200 @@
+//! Whether other nodes can dial us, AutoNAT-style.
+//!
+//! AutoNAT has peers dial us back to find out. A browser gives them nothing
+//! to dial: it cannot listen. So [`ReachabilityProbe`] works from what the
+//! node sees anyway, with AutoNAT's notion of confidence:
+//!
+//! - Without a direct listen address the node is [`Reachability::Private`]
+//!   outright, as every browser is. A relay reservation does not count:
+//!   through it peers reach the relay, not us.
+//! - A direct inbound connection shows that peers can dial us, making it
+//!   `Public`, and each further one adds confidence.
+//! - Each [`PROBE_WINDOW`] with no direct inbound connection, while peers
+//!   report observing us at some address over Identify, counts against.
+//!   It takes confidence from `Public` before making it `Private`.
+//!
+//! The service reports the result in `TransportStats` and dials by it.
+//! Times are Unix milliseconds.
+
+use libp2p::Multiaddr;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::time::Duration;
+
+/// How long to wait for a direct inbound connection before counting its
+/// absence against being reachable.
+pub const PROBE_WINDOW: Duration = Duration::from_secs(60);
+
+/// Most confidence in a reachability, as AutoNAT's `confidence_max`.
+pub const MAX_REACHABILITY_CONFIDENCE: u32 = 3;
+
+/// Most addresses kept of those peers observed us at; the oldest go first.
+pub const MAX_OBSERVED_ADDRESSES: usize = 8;
+
+/// Whether peers can dial us.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Reachability {
+    /// Listening, but nothing shows either way yet.
+    #[default]
+    Unknown,
+    /// Peers have dialed us directly.
+    Public,
+    /// Peers cannot dial us: there is nothing to dial, or they have not
+    /// for a while.
+    Private,
+}
+
+impl Reachability {
+    /// Whether peers can be expected to dial us.
+    pub fn accepts_inbound(&self) -> bool {
+        *self == Reachability::Public
+    }
+}
+
+/// See the module docs.
+#[derive(Debug, Default)]
+pub struct ReachabilityProbe {
+    reachability: Reachability,
+    confidence: u32,
+    /// Direct (not relayed) listen addresses.
+    listen_addresses: usize,
+    observed: VecDeque<Multiaddr>,
+    /// When the current probe window started, while listening.
+    window_start_ms: Option<u64>,
+    inbound_in_window: bool,
+}
+
+impl ReachabilityProbe {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn reachability(&self) -> Reachability {
+        self.reachability
+    }
+
+    /// How sure of the reachability we are, up to
+    /// [`MAX_REACHABILITY_CONFIDENCE`].
+    pub fn confidence(&self) -> u32 {
+        self.confidence
+    }
+
+    /// The addresses peers have reported observing us at, oldest first.
+    pub fn observed(&self) -> impl Iterator<Item = &Multiaddr> {
+        self.observed.iter()
+    }
+
+    /// We now have `count` direct listen addresses.
+    pub fn listening(&mut self, count: usize, now_ms: u64) {
+        let was_listening = self.listen_addresses > 0;
+        self.listen_addresses = count;
+        if count == 0 {
+            self.reachability = Reachability::Private;
+            self.confidence = MAX_REACHABILITY_CONFIDENCE;
+            self.window_start_ms = None;
+        } else if !was_listening {
+            self.reachability = Reachability::Unknown;
+            self.confidence = 0;
+            self.window_start_ms = Some(now_ms);
+            self.inbound_in_window = false;
+        }
+    }
+
+    /// A peer reported observing us at `address`.
+    pub fn observed_at(&mut self, address: Multiaddr) {
+        if self.observed.contains(&address) {
+            return;
+        }
+        if self.observed.len() >= MAX_OBSERVED_ADDRESSES {
+            self.observed.pop_front();
+        }
+        self.observed.push_back(address);
+    }
+
+    /// A peer dialed us directly.
+    pub fn inbound(&mut self) {
+        if self.reachability == Reachability::Public {
+            self.confidence = (self.confidence + 1).min(MAX_REACHABILITY_CONFIDENCE);
+        } else {
+            self.reachability = Reachability::Public;
+            self.confidence = 0;
+        }
+        self.inbound_in_window = true;
+    }
+
+    /// Close the probe window if it has run its course.
+    pub fn tick(&mut self, now_ms: u64) {
+        let Some(start) = self.window_start_ms else {
+            return;
+        };
+        if now_ms < start.saturating_add(PROBE_WINDOW.as_millis() as u64) {
+            return;
+        }
+        // A window nobody could have dialed us in says nothing.
+        if !self.inbound_in_window && !self.observed.is_empty() {
+            match self.reachability {
+                Reachability::Public if self.confidence > 0 => self.confidence -= 1,
+                Reachability::Private => {
+                    self.confidence = (self.confidence + 1).min(MAX_REACHABILITY_CONFIDENCE);
+                }
+                Reachability::Public | Reachability::Unknown => {
+                    self.reachability = Reachability::Private;
+                    self.confidence = 0;
+                }
+            }
+        }
+        self.window_start_ms = Some(now_ms);
+        self.inbound_in_window = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WINDOW: u64 = PROBE_WINDOW.as_millis() as u64;
+
+    #[test]
+    fn test_not_listening_is_private() {
+        let mut probe = ReachabilityProbe::new();
+        assert_eq!(probe.reachability(), Reachability::Unknown);
+        probe.listening(0, 0);
+        assert_eq!(probe.reachability(), Reachability::Private);
+        assert_eq!(probe.confidence(), MAX_REACHABILITY_CONFIDENCE);
+        assert!(!probe.reachability().accepts_inbound());
+
+        for i in 0..10 {
+            probe.observed_at(format!("/ip4/203.0.113.{}/tcp/9000", i % 9).parse().unwrap());
+        }
+        assert_eq!(probe.observed().count(), MAX_OBSERVED_ADDRESSES);
+    }
+
+    #[test]
+    fn test_inbound_and_silence_move_confidence() {
+        let mut probe = ReachabilityProbe::new();
+        probe.listening(1, 0);
+        assert_eq!(probe.reachability(), Reachability::Unknown);
+        // Nobody knows where to dial us yet, so silence says nothing.
+        probe.tick(WINDOW);
+        assert_eq!(probe.reachability(), Reachability::Unknown);
+
+        probe.observed_at("/ip4/203.0.113.7/tcp/9000".parse().unwrap());
+        probe.inbound();
+        probe.inbound();
+        assert_eq!((probe.reachability(), probe.confidence()), (Reachability::Public, 1));
+        probe.tick(2 * WINDOW);
+        assert_eq!((probe.reachability(), probe.confidence()), (Reachability::Public, 1));
+
+        probe.tick(3 * WINDOW - 1);
+        assert_eq!(probe.confidence(), 1);
+        probe.tick(3 * WINDOW);
+        assert_eq!((probe.reachability(), probe.confidence()), (Reachability::Public, 0));
+        probe.tick(4 * WINDOW);
+        assert_eq!((probe.reachability(), probe.confidence()), (Reachability::Private, 0));
+        probe.tick(5 * WINDOW);
+        assert_eq!(probe.confidence(), 1);
+
+        probe.inbound();
+        assert_eq!((probe.reachability(), probe.confidence()), (Reachability::Public, 0));
+    }
+}
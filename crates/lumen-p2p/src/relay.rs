@@ -7,6 +7,19 @@
 //! 4. Upgrade to direct connections, drop relay dependency
 //! 5. Log clearly to console which mode we're in
 //!
+//! [`RelayFallback`] is that flow as a state machine. The service ticks it
+//! with the direct peers it has and carries out the [`RelayEvent`]s it
+//! returns: reserving a slot on a relay (listening on its `/p2p-circuit`
+//! address), looking for direct peers, releasing the slot, and recording
+//! each change of [`ConnectionMode`] and [`BootstrapPhase`].
+//!
+//! No one relay is relied on. Each keeps a [`RelayHealth`]: a relay whose
+//! reservation fails or drops, or that stops answering pings while we hold
+//! one, backs off as a failed dial does, and the flow fails over to the
+//! healthiest of the others at once. Nodes rank healthy relays from a
+//! different starting point each, derived from their peer id, so their
+//! reservations spread across the relays instead of piling onto the first.
+//!
 //! ## Trust Model
 //!
 //! Circuit relays can see:
@@ -18,10 +31,18 @@
 //! - Modify the data (integrity checked by Noise)
 //! - Forge light client updates (requires BLS signatures from sync committee)
 
+use crate::bootstrap::{BootstrapConfig, BootstrapPhase};
+use crate::peer_manager::{INITIAL_DIAL_BACKOFF, MAX_DIAL_BACKOFF};
+use libp2p::multiaddr::Protocol;
+use libp2p::{Multiaddr, PeerId};
 use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// How often the service ticks the [`RelayFallback`].
+pub const RELAY_TICK_INTERVAL: Duration = Duration::from_secs(1);
 
 /// The current connection mode — indicates trust level clearly.
-#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ConnectionMode {
     /// Connected directly via WebTransport — fully P2P, no intermediary.
     /// This is the best possible trust state.
@@ -36,6 +57,19 @@ pub enum ConnectionMode {
         peer_count: usize,
     },
 
+    /// Connected directly via WebSocket — fully P2P, no intermediary.
+    DirectWebSocket {
+        /// Number of direct WebSocket peers.
+        peer_count: usize,
+    },
+
+    /// Connected directly over TCP or QUIC, in a native build — fully P2P,
+    /// no intermediary.
+    DirectNative {
+        /// Number of direct TCP and QUIC peers.
+        peer_count: usize,
+    },
+
     /// Connected via circuit relay — relay sees metadata, not data.
     /// This is acceptable for bootstrapping but should be upgraded ASAP.
     ViaRelay {
@@ -46,6 +80,7 @@ pub enum ConnectionMode {
     },
 
     /// Bootstrapping — not yet connected to any peers.
+    #[default]
     Bootstrapping,
 
     /// Disconnected — no peers available.
@@ -67,6 +102,14 @@ impl ConnectionMode {
                 "Direct WebRTC: {} peers | Fully P2P, no intermediary | Trust: MAXIMUM",
                 peer_count
             ),
+            ConnectionMode::DirectWebSocket { peer_count } => format!(
+                "Direct WebSocket: {} peers | Fully P2P, no intermediary | Trust: MAXIMUM",
+                peer_count
+            ),
+            ConnectionMode::DirectNative { peer_count } => format!(
+                "Direct TCP/QUIC: {} peers | Fully P2P, no intermediary | Trust: MAXIMUM",
+                peer_count
+            ),
             ConnectionMode::ViaRelay {
                 relay_peer,
                 direct_peers,
@@ -84,14 +127,25 @@ impl ConnectionMode {
         }
     }
 
+    /// The mode for `peers`, named after the preferred transport among
+    /// them. None without any.
+    pub fn direct(peers: DirectPeers) -> Option<Self> {
+        if peers.webtransport > 0 {
+            Some(ConnectionMode::DirectWebTransport { peer_count: peers.webtransport })
+        } else if peers.webrtc > 0 {
+            Some(ConnectionMode::DirectWebRTC { peer_count: peers.webrtc })
+        } else if peers.websocket > 0 {
+            Some(ConnectionMode::DirectWebSocket { peer_count: peers.websocket })
+        } else if peers.native > 0 {
+            Some(ConnectionMode::DirectNative { peer_count: peers.native })
+        } else {
+            None
+        }
+    }
+
     /// Whether we have any active connections.
     pub fn is_connected(&self) -> bool {
-        matches!(
-            self,
-            ConnectionMode::DirectWebTransport { .. }
-                | ConnectionMode::DirectWebRTC { .. }
-                | ConnectionMode::ViaRelay { .. }
-        )
+        self.is_direct() || self.is_relayed()
     }
 
     /// Whether we're using a relay (less ideal trust state).
@@ -103,11 +157,31 @@ impl ConnectionMode {
     pub fn is_direct(&self) -> bool {
         matches!(
             self,
-            ConnectionMode::DirectWebTransport { .. } | ConnectionMode::DirectWebRTC { .. }
+            ConnectionMode::DirectWebTransport { .. }
+                | ConnectionMode::DirectWebRTC { .. }
+                | ConnectionMode::DirectWebSocket { .. }
+                | ConnectionMode::DirectNative { .. }
         )
     }
 }
 
+/// Peers connected directly, without a relay, by transport. Relays
+/// themselves are not counted.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DirectPeers {
+    pub webtransport: usize,
+    pub webrtc: usize,
+    pub websocket: usize,
+    /// Over TCP or QUIC, in native builds.
+    pub native: usize,
+}
+
+impl DirectPeers {
+    pub fn total(&self) -> usize {
+        self.webtransport + self.webrtc + self.websocket + self.native
+    }
+}
+
 /// Strategy for upgrading from relay to direct connections.
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct RelayUpgradeStrategy {
@@ -129,6 +203,340 @@ impl Default for RelayUpgradeStrategy {
     }
 }
 
+/// What the [`RelayFallback`] asks of the service, or tells it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RelayEvent {
+    /// Reserve a slot on the relay at this address, by listening on its
+    /// `/p2p-circuit` address.
+    Reserve(Multiaddr),
+    /// Give the reservation on `relay` up, and its connection: there are
+    /// enough direct peers, or the relay stopped answering.
+    Release { relay: PeerId },
+    /// Look for direct peers: redial known ones and ask the relay for more.
+    Discover,
+    ModeChanged(ConnectionMode),
+    PhaseChanged(BootstrapPhase),
+    /// Still relayed after `max_relay_duration_ms`, this long.
+    RelayedTooLong { duration_ms: u64 },
+}
+
+/// What is known of one relay's health, from its reservations and pings.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RelayHealth {
+    pub address: String,
+    /// Reservations it has accepted.
+    pub reservations: u32,
+    /// Reservations that failed or dropped, and failed pings, since it last
+    /// accepted one.
+    pub failures: u32,
+    /// When it may be tried again after failing, in Unix milliseconds.
+    pub retry_at_ms: u64,
+    /// The round trip of its last ping.
+    pub rtt_ms: Option<u64>,
+}
+
+#[derive(Debug)]
+struct Relay {
+    address: Multiaddr,
+    peer: Option<PeerId>,
+    health: RelayHealth,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Reservation {
+    None,
+    /// Asked of the relay at this index of `relays`.
+    Pending { index: usize },
+    Accepted { index: usize, relay: PeerId, since_ms: u64 },
+}
+
+/// The relay fallback and upgrade flow of the module docs, for the relays
+/// of a [`BootstrapConfig`]:
+///
+/// - Direct dials get `direct_timeout_ms`. Reaching `min_peers` direct
+///   peers completes the bootstrap.
+/// - With no direct peer by then, a slot is reserved on the healthiest
+///   relay. One that fails or drops fails over to the next at once; with
+///   every relay backing off, the first to come back is waited for.
+/// - While relayed, direct peers are looked for every
+///   `discovery_interval_ms` of the [`RelayUpgradeStrategy`], or less and
+///   less often unless `aggressive_discovery`; at `min_peers` the slot is
+///   released.
+/// - Losing every peer starts over.
+///
+/// Times are Unix milliseconds.
+#[derive(Debug)]
+pub struct RelayFallback {
+    strategy: RelayUpgradeStrategy,
+    relays: Vec<Relay>,
+    /// Where this node starts ranking relays of equal health.
+    rotation: usize,
+    direct_timeout_ms: u64,
+    min_peers: usize,
+    phase: BootstrapPhase,
+    mode: ConnectionMode,
+    direct: DirectPeers,
+    /// When to fall back to a relay if there is still no direct peer.
+    deadline_ms: u64,
+    reservation: Reservation,
+    next_discovery_ms: u64,
+    discovery_interval_ms: u64,
+    warned: bool,
+}
+
+impl RelayFallback {
+    /// A fallback on `bootstrap`'s relays, or on none if `enabled` is false,
+    /// for the node `local_peer_id`. Relays that do not parse are left out.
+    pub fn new(
+        bootstrap: &BootstrapConfig,
+        strategy: RelayUpgradeStrategy,
+        enabled: bool,
+        local_peer_id: &PeerId,
+    ) -> Self {
+        let addresses = if enabled { bootstrap.relays.as_slice() } else { &[] };
+        let relays = addresses
+            .iter()
+            .filter_map(|relay| relay.parse::<Multiaddr>().ok())
+            .map(|address| Relay {
+                peer: address.iter().find_map(|protocol| match protocol {
+                    Protocol::P2p(peer) => Some(peer),
+                    _ => None,
+                }),
+                health: RelayHealth {
+                    address: address.to_string(),
+                    reservations: 0,
+                    failures: 0,
+                    retry_at_ms: 0,
+                    rtt_ms: None,
+                },
+                address,
+            })
+            .collect();
+        let rotation = local_peer_id.to_bytes().iter().rev().take(8).fold(0usize, |rotation, byte| {
+            rotation.wrapping_mul(256).wrapping_add(*byte as usize)
+        });
+        Self {
+            discovery_interval_ms: strategy.discovery_interval_ms,
+            strategy,
+            relays,
+            rotation,
+            direct_timeout_ms: bootstrap.direct_timeout_ms,
+            min_peers: bootstrap.min_peers.max(1),
+            phase: BootstrapPhase::NotStarted,
+            mode: ConnectionMode::Bootstrapping,
+            direct: DirectPeers::default(),
+            deadline_ms: 0,
+            reservation: Reservation::None,
+            next_discovery_ms: 0,
+            warned: false,
+        }
+    }
+
+    pub fn phase(&self) -> &BootstrapPhase {
+        &self.phase
+    }
+
+    pub fn mode(&self) -> &ConnectionMode {
+        &self.mode
+    }
+
+    /// Whether `peer` is one of the relays.
+    pub fn is_relay(&self, peer: &PeerId) -> bool {
+        self.index_of(peer).is_some()
+    }
+
+    /// The health of each relay, in configured order.
+    pub fn health(&self) -> Vec<RelayHealth> {
+        self.relays.iter().map(|relay| relay.health.clone()).collect()
+    }
+
+    /// The relay we hold a reservation on.
+    pub fn relay_peer(&self) -> Option<PeerId> {
+        match self.reservation {
+            Reservation::Accepted { relay, .. } => Some(relay),
+            _ => None,
+        }
+    }
+
+    /// Start dialing directly, as the bootnodes are dialed.
+    pub fn start(&mut self, now_ms: u64) -> Vec<RelayEvent> {
+        let mut events = Vec::new();
+        self.deadline_ms = now_ms.saturating_add(self.direct_timeout_ms);
+        self.set_phase(BootstrapPhase::ConnectingDirect, &mut events);
+        events
+    }
+
+    /// Move the flow on, now that there are `direct` peers.
+    pub fn tick(&mut self, now_ms: u64, direct: DirectPeers) -> Vec<RelayEvent> {
+        let mut events = Vec::new();
+        self.direct = direct;
+        if self.phase == BootstrapPhase::NotStarted {
+            return events;
+        }
+        let total = direct.total();
+        match self.reservation {
+            Reservation::Accepted { relay, since_ms, .. } => {
+                if total >= self.min_peers {
+                    events.push(RelayEvent::Release { relay });
+                    self.reservation = Reservation::None;
+                    self.set_phase(BootstrapPhase::Complete, &mut events);
+                } else {
+                    if now_ms >= self.next_discovery_ms {
+                        events.push(RelayEvent::Discover);
+                        self.next_discovery_ms = now_ms.saturating_add(self.discovery_interval_ms);
+                        if !self.strategy.aggressive_discovery {
+                            let longest = self.strategy.max_relay_duration_ms.max(self.strategy.discovery_interval_ms);
+                            self.discovery_interval_ms = self.discovery_interval_ms.saturating_mul(2).min(longest);
+                        }
+                    }
+                    let duration_ms = now_ms.saturating_sub(since_ms);
+                    if !self.warned && duration_ms >= self.strategy.max_relay_duration_ms {
+                        self.warned = true;
+                        events.push(RelayEvent::RelayedTooLong { duration_ms });
+                    }
+                }
+            }
+            Reservation::Pending { .. } => {}
+            Reservation::None if total >= self.min_peers => self.set_phase(BootstrapPhase::Complete, &mut events),
+            Reservation::None if total > 0 => {
+                if matches!(self.phase, BootstrapPhase::Failed { .. }) {
+                    self.restart(now_ms, &mut events);
+                }
+            }
+            Reservation::None => {
+                if self.phase == BootstrapPhase::Complete {
+                    self.restart(now_ms, &mut events);
+                } else if now_ms >= self.deadline_ms {
+                    if self.relays.is_empty() {
+                        let reason = format!("No direct peer within {} ms, and no relay", self.direct_timeout_ms);
+                        self.set_phase(BootstrapPhase::Failed { reason }, &mut events);
+                    } else if let Some(index) = self.next_relay(now_ms) {
+                        self.reservation = Reservation::Pending { index };
+                        self.set_phase(BootstrapPhase::ConnectingRelay, &mut events);
+                        events.push(RelayEvent::Reserve(self.relays[index].address.clone()));
+                    } else {
+                        let retry_at_ms = self.relays.iter().map(|relay| relay.health.retry_at_ms).min();
+                        self.deadline_ms = retry_at_ms.unwrap_or(now_ms);
+                    }
+                }
+            }
+        }
+        self.update_mode(&mut events);
+        events
+    }
+
+    /// The relay `relay` accepted our reservation.
+    pub fn reservation_accepted(&mut self, relay: PeerId, now_ms: u64) -> Vec<RelayEvent> {
+        let mut events = Vec::new();
+        let index = match self.reservation {
+            // A renewal.
+            Reservation::Accepted { relay: held, .. } if held == relay => return events,
+            Reservation::Pending { index } => self.index_of(&relay).unwrap_or(index),
+            _ => match self.index_of(&relay) {
+                Some(index) => index,
+                None => return events,
+            },
+        };
+        let health = &mut self.relays[index].health;
+        health.reservations += 1;
+        health.failures = 0;
+        health.retry_at_ms = 0;
+        self.reservation = Reservation::Accepted { index, relay, since_ms: now_ms };
+        self.next_discovery_ms = now_ms;
+        self.discovery_interval_ms = self.strategy.discovery_interval_ms;
+        self.warned = false;
+        self.update_mode(&mut events);
+        events
+    }
+
+    /// The reservation failed, or the relay dropped it. The relay backs off,
+    /// and the next is tried at the next tick if there is still no direct
+    /// peer.
+    pub fn reservation_ended(&mut self, now_ms: u64) -> Vec<RelayEvent> {
+        let mut events = Vec::new();
+        if let Reservation::Pending { index } | Reservation::Accepted { index, .. } = self.reservation {
+            self.failed(index, now_ms);
+        }
+        self.reservation = Reservation::None;
+        self.deadline_ms = now_ms;
+        self.update_mode(&mut events);
+        events
+    }
+
+    /// A ping to `relay` failed. It backs off, and if we hold its
+    /// reservation, it is released to fail over to another.
+    pub fn relay_failed(&mut self, relay: &PeerId, now_ms: u64) -> Vec<RelayEvent> {
+        let mut events = Vec::new();
+        let Some(index) = self.index_of(relay) else {
+            return events;
+        };
+        self.failed(index, now_ms);
+        if matches!(self.reservation, Reservation::Accepted { index: held, .. } if held == index) {
+            events.push(RelayEvent::Release { relay: *relay });
+            self.reservation = Reservation::None;
+            self.deadline_ms = now_ms;
+            self.update_mode(&mut events);
+        }
+        events
+    }
+
+    /// A ping to `relay` came back after `rtt`.
+    pub fn relay_rtt(&mut self, relay: &PeerId, rtt: Duration) {
+        if let Some(index) = self.index_of(relay) {
+            self.relays[index].health.rtt_ms = Some(rtt.as_millis() as u64);
+        }
+    }
+
+    fn index_of(&self, peer: &PeerId) -> Option<usize> {
+        self.relays.iter().position(|relay| relay.peer.as_ref() == Some(peer))
+    }
+
+    /// The relay to reserve on next: of those not backing off, the one
+    /// that failed least lately, ties going round from `rotation`.
+    fn next_relay(&self, now_ms: u64) -> Option<usize> {
+        let len = self.relays.len();
+        (0..len)
+            .filter(|index| now_ms >= self.relays[*index].health.retry_at_ms)
+            .min_by_key(|index| (self.relays[*index].health.failures, (index + len - self.rotation % len) % len))
+    }
+
+    /// Back the relay at `index` off, doubling as failed dials do.
+    fn failed(&mut self, index: usize, now_ms: u64) {
+        let health = &mut self.relays[index].health;
+        health.failures += 1;
+        let delay = INITIAL_DIAL_BACKOFF.saturating_mul(1 << (health.failures - 1).min(16)).min(MAX_DIAL_BACKOFF);
+        health.retry_at_ms = now_ms.saturating_add(delay.as_millis() as u64);
+    }
+
+    fn restart(&mut self, now_ms: u64, events: &mut Vec<RelayEvent>) {
+        self.deadline_ms = now_ms.saturating_add(self.direct_timeout_ms);
+        self.set_phase(BootstrapPhase::ConnectingDirect, events);
+    }
+
+    fn set_phase(&mut self, phase: BootstrapPhase, events: &mut Vec<RelayEvent>) {
+        if self.phase != phase {
+            self.phase = phase.clone();
+            events.push(RelayEvent::PhaseChanged(phase));
+        }
+    }
+
+    fn update_mode(&mut self, events: &mut Vec<RelayEvent>) {
+        let mode = match (&self.reservation, &self.phase) {
+            (Reservation::Accepted { relay, .. }, _) => {
+                ConnectionMode::ViaRelay { relay_peer: relay.to_string(), direct_peers: self.direct.total() }
+            }
+            (_, BootstrapPhase::Failed { reason }) if self.direct.total() == 0 => {
+                ConnectionMode::Disconnected { reason: reason.clone() }
+            }
+            _ => ConnectionMode::direct(self.direct).unwrap_or(ConnectionMode::Bootstrapping),
+        };
+        if mode != self.mode {
+            self.mode = mode.clone();
+            events.push(RelayEvent::ModeChanged(mode));
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -150,5 +558,127 @@ mod tests {
 
         let bootstrap = ConnectionMode::Bootstrapping;
         assert!(!bootstrap.is_connected());
+
+        let websocket = ConnectionMode::direct(DirectPeers { websocket: 2, ..DirectPeers::default() }).unwrap();
+        assert!(websocket.is_direct());
+        let mixed = DirectPeers { webtransport: 1, websocket: 2, ..DirectPeers::default() };
+        assert_eq!(ConnectionMode::direct(mixed), Some(ConnectionMode::DirectWebTransport { peer_count: 1 }));
+        let native = DirectPeers { native: 3, ..DirectPeers::default() };
+        assert_eq!(ConnectionMode::direct(native), Some(ConnectionMode::DirectNative { peer_count: 3 }));
+        assert_eq!(ConnectionMode::direct(DirectPeers::default()), None);
+    }
+
+    const RELAY: &str = "12D3KooWReaFkMnb7YJZK9fqDFskLJiVcZpjxdKcNih3vRCCFGPr";
+
+    fn fallback(aggressive_discovery: bool) -> RelayFallback {
+        let bootstrap = BootstrapConfig { min_peers: 2, direct_timeout_ms: 3_000, ..BootstrapConfig::default() };
+        let strategy = RelayUpgradeStrategy { aggressive_discovery, ..RelayUpgradeStrategy::default() };
+        let mut fallback = RelayFallback::new(&bootstrap, strategy, true, &PeerId::random());
+        fallback.rotation = 0;
+        fallback
+    }
+
+    fn reserved(events: &[RelayEvent]) -> Option<String> {
+        events.iter().find_map(|event| match event {
+            RelayEvent::Reserve(address) => Some(address.to_string()),
+            _ => None,
+        })
+    }
+
+    fn websocket(peers: usize) -> DirectPeers {
+        DirectPeers { websocket: peers, ..DirectPeers::default() }
+    }
+
+    #[test]
+    fn test_falls_back_to_a_relay_and_upgrades() {
+        let mut relay = fallback(true);
+        let relay_peer: PeerId = RELAY.parse().unwrap();
+        assert!(relay.is_relay(&relay_peer));
+        assert_eq!(relay.start(0), [RelayEvent::PhaseChanged(BootstrapPhase::ConnectingDirect)]);
+        assert!(relay.tick(2_999, websocket(0)).is_empty());
+
+        let events = relay.tick(3_000, websocket(0));
+        assert_eq!(events[0], RelayEvent::PhaseChanged(BootstrapPhase::ConnectingRelay));
+        assert!(matches!(&events[1], RelayEvent::Reserve(address) if address.to_string().contains(RELAY)));
+        assert!(relay.tick(4_000, websocket(0)).is_empty());
+
+        let via_relay = ConnectionMode::ViaRelay { relay_peer: RELAY.to_string(), direct_peers: 0 };
+        assert_eq!(relay.reservation_accepted(relay_peer, 5_000), [RelayEvent::ModeChanged(via_relay)]);
+        assert!(relay.reservation_accepted(relay_peer, 6_000).is_empty());
+        assert_eq!(relay.tick(5_000, websocket(0)), [RelayEvent::Discover]);
+        assert!(relay.tick(9_999, websocket(0)).is_empty());
+        assert_eq!(relay.tick(10_000, websocket(0)), [RelayEvent::Discover]);
+        let events = relay.tick(65_000, websocket(1));
+        assert!(events.contains(&RelayEvent::RelayedTooLong { duration_ms: 60_000 }));
+
+        let events = relay.tick(66_000, websocket(2));
+        let release = RelayEvent::Release { relay: relay_peer };
+        assert_eq!(events[..2], [release, RelayEvent::PhaseChanged(BootstrapPhase::Complete)]);
+        assert_eq!(relay.mode(), &ConnectionMode::DirectWebSocket { peer_count: 2 });
+        assert_eq!(relay.relay_peer(), None);
+
+        // Losing every peer starts over.
+        let events = relay.tick(70_000, websocket(0));
+        assert!(events.contains(&RelayEvent::PhaseChanged(BootstrapPhase::ConnectingDirect)));
+        assert_eq!(relay.mode(), &ConnectionMode::Bootstrapping);
+    }
+
+    #[test]
+    fn test_failed_relays_fail_over_and_back_off() {
+        let mut relay = fallback(false);
+        let [first, second] = [0, 1].map(|index| relay.relays[index].health.address.clone());
+        relay.start(0);
+        assert_eq!(reserved(&relay.tick(3_000, websocket(0))), Some(first.clone()));
+        assert!(relay.reservation_ended(3_500).is_empty());
+        assert_eq!(reserved(&relay.tick(3_500, websocket(0))), Some(second.clone()));
+        relay.reservation_ended(4_000);
+
+        // Both back off; the first to come back is tried first.
+        assert!(relay.tick(4_000, websocket(0)).is_empty());
+        assert!(relay.tick(8_499, websocket(0)).is_empty());
+        assert_eq!(reserved(&relay.tick(8_500, websocket(0))), Some(first.clone()));
+        relay.reservation_ended(9_000);
+        assert_eq!(relay.health()[0].failures, 2);
+        assert_eq!(relay.health()[0].retry_at_ms, 19_000);
+        assert_eq!(reserved(&relay.tick(9_000, websocket(0))), Some(second.clone()));
+
+        // An accepted reservation clears the relay's failures. One that
+        // stops answering pings is released for the other.
+        let relay_peer = relay.relays[1].peer.unwrap();
+        relay.reservation_accepted(relay_peer, 9_500);
+        relay.relay_rtt(&relay_peer, Duration::from_millis(80));
+        assert_eq!((relay.health()[1].failures, relay.health()[1].reservations), (0, 1));
+        assert_eq!(relay.health()[1].rtt_ms, Some(80));
+        let events = relay.relay_failed(&relay_peer, 10_000);
+        assert_eq!(events[0], RelayEvent::Release { relay: relay_peer });
+        assert_eq!(relay.relay_peer(), None);
+        assert!(relay.tick(14_999, websocket(0)).is_empty());
+        // Failed once to the first's twice, so it is still preferred.
+        assert_eq!(reserved(&relay.tick(15_000, websocket(0))), Some(second.clone()));
+
+        // Nodes of equal standing start from different relays.
+        let mut other = fallback(false);
+        other.rotation = 1;
+        other.start(0);
+        assert_eq!(reserved(&other.tick(3_000, websocket(0))), Some(second));
+    }
+
+    #[test]
+    fn test_discovery_backs_off_unless_aggressive() {
+        let mut relay = fallback(false);
+        relay.start(0);
+        relay.tick(3_000, websocket(0));
+        relay.reservation_accepted(RELAY.parse().unwrap(), 7_000);
+        assert_eq!(relay.tick(7_000, websocket(0)), [RelayEvent::Discover]);
+        assert_eq!(relay.tick(12_000, websocket(0)), [RelayEvent::Discover]);
+        assert!(relay.tick(21_999, websocket(0)).is_empty());
+        assert_eq!(relay.tick(22_000, websocket(0)), [RelayEvent::Discover]);
+
+        let bootstrap = BootstrapConfig { direct_timeout_ms: 3_000, ..BootstrapConfig::default() };
+        let mut disabled = RelayFallback::new(&bootstrap, RelayUpgradeStrategy::default(), false, &PeerId::random());
+        disabled.start(0);
+        let events = disabled.tick(3_000, websocket(0));
+        assert!(matches!(disabled.phase(), BootstrapPhase::Failed { .. }));
+        assert!(matches!(events.last(), Some(RelayEvent::ModeChanged(ConnectionMode::Disconnected { .. }))));
     }
 }
@@ -0,0 +1,558 @@
+//! The consensus layer's light client request/response protocols.
+//!
+//! Each protocol is one libp2p stream per request, encoded `ssz_snappy`:
+//! the request is an SSZ object behind its uncompressed length (an unsigned
+//! varint) and compressed with snappy's framed format, or nothing for the
+//! requests for the latest finality and optimistic updates. The response is a
+//! series of chunks, each a result byte, then for a success the 4-byte
+//! fork digest of the object's fork and the object encoded as a request
+//! is. Any other result byte carries an error message instead and ends the
+//! response.
+//!
+//! Responses are read from the stream a chunk at a time rather than
+//! whole: each chunk's length prefix is checked against the limit before
+//! its frames are read, and no more frames are read than hold it, so a
+//! peer can neither make Lumen buffer more than one object's worth nor
+//! send more chunks than the protocol allows.
+//!
+//! Only the client side is implemented: Lumen requests, it does not serve.
+//! Responses are decoded no further than SSZ bytes and their fork digest;
+//! what is in them is checked by lumen-core like anything else a peer sends.
+
+use async_trait::async_trait;
+use futures::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use libp2p::request_response::{self, ProtocolSupport};
+use libp2p::StreamProtocol;
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::time::Duration;
+
+/// Largest uncompressed object accepted in a request or response chunk
+/// (`MAX_PAYLOAD_SIZE` in the consensus p2p spec).
+pub const MAX_PAYLOAD_SIZE: usize = 10 * 1024 * 1024;
+
+/// Longest error message read from an error chunk (`ErrorMessage` is a
+/// `List[byte, 256]`).
+pub const MAX_ERROR_MESSAGE_LEN: usize = 256;
+
+/// How long a peer has to answer a request (`RESP_TIMEOUT`).
+pub const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Most updates one `light_client_updates_by_range` request may ask for
+/// (`MAX_REQUEST_LIGHT_CLIENT_UPDATES`).
+pub const MAX_REQUEST_LIGHT_CLIENT_UPDATES: u64 = 128;
+
+/// The longest an unsigned varint for a `u64` can be.
+const MAX_VARINT_LEN: usize = 10;
+
+/// A light client request protocol.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum LightClientProtocol {
+    Bootstrap,
+    UpdatesByRange,
+    FinalityUpdate,
+    OptimisticUpdate,
+}
+
+impl LightClientProtocol {
+    pub const ALL: [Self; 4] = [Self::Bootstrap, Self::UpdatesByRange, Self::FinalityUpdate, Self::OptimisticUpdate];
+
+    pub fn id(&self) -> &'static str {
+        match self {
+            Self::Bootstrap => "/eth2/beacon_chain/req/light_client_bootstrap/1/ssz_snappy",
+            Self::UpdatesByRange => "/eth2/beacon_chain/req/light_client_updates_by_range/1/ssz_snappy",
+            Self::FinalityUpdate => "/eth2/beacon_chain/req/light_client_finality_update/1/ssz_snappy",
+            Self::OptimisticUpdate => "/eth2/beacon_chain/req/light_client_optimistic_update/1/ssz_snappy",
+        }
+    }
+
+    pub fn from_id(id: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|protocol| protocol.id() == id)
+    }
+
+    /// Whether requests carry content. Those that do not send nothing.
+    pub fn has_request_body(&self) -> bool {
+        matches!(self, Self::Bootstrap | Self::UpdatesByRange)
+    }
+
+    /// Most successful chunks a response may have: one object, or one
+    /// update per period asked for.
+    pub fn max_response_chunks(&self) -> usize {
+        match self {
+            Self::UpdatesByRange => MAX_REQUEST_LIGHT_CLIENT_UPDATES as usize,
+            Self::Bootstrap | Self::FinalityUpdate | Self::OptimisticUpdate => 1,
+        }
+    }
+
+    pub fn stream_protocol(&self) -> StreamProtocol {
+        StreamProtocol::new(self.id())
+    }
+
+    /// A request-response behaviour speaking only this protocol, as a
+    /// client. One per protocol, since a behaviour offers every protocol
+    /// it has for each request and lets the peer choose.
+    pub fn behaviour(&self) -> request_response::Behaviour<LightClientCodec> {
+        request_response::Behaviour::new(
+            [(self.stream_protocol(), ProtocolSupport::Outbound)],
+            request_response::Config::default().with_request_timeout(REQUEST_TIMEOUT),
+        )
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LightClientRequest {
+    /// The `LightClientBootstrap` for the block with this root, which
+    /// should be a finalized epoch boundary block.
+    Bootstrap { block_root: [u8; 32] },
+    /// The best `LightClientUpdate` of each of `count` sync committee
+    /// periods from `start_period`, one chunk each, oldest first. Peers may
+    /// send fewer, down to none, when they do not have them.
+    UpdatesByRange { start_period: u64, count: u64 },
+    /// The peer's latest `LightClientFinalityUpdate`.
+    FinalityUpdate,
+    /// The peer's latest `LightClientOptimisticUpdate`.
+    OptimisticUpdate,
+}
+
+impl LightClientRequest {
+    pub fn protocol(&self) -> LightClientProtocol {
+        match self {
+            Self::Bootstrap { .. } => LightClientProtocol::Bootstrap,
+            Self::UpdatesByRange { .. } => LightClientProtocol::UpdatesByRange,
+            Self::FinalityUpdate => LightClientProtocol::FinalityUpdate,
+            Self::OptimisticUpdate => LightClientProtocol::OptimisticUpdate,
+        }
+    }
+
+    /// The request's content; empty for those without.
+    pub fn to_ssz(&self) -> Vec<u8> {
+        match self {
+            Self::Bootstrap { block_root } => block_root.to_vec(),
+            Self::UpdatesByRange { start_period, count } => [start_period.to_le_bytes(), count.to_le_bytes()].concat(),
+            Self::FinalityUpdate | Self::OptimisticUpdate => Vec::new(),
+        }
+    }
+
+    /// Decode a request received on `protocol`.
+    pub fn from_ssz(protocol: LightClientProtocol, ssz: &[u8]) -> Result<Self, String> {
+        match protocol {
+            LightClientProtocol::Bootstrap => {
+                let block_root =
+                    ssz.try_into().map_err(|_| format!("Bootstrap request is {} bytes, not 32", ssz.len()))?;
+                Ok(Self::Bootstrap { block_root })
+            }
+            LightClientProtocol::UpdatesByRange => {
+                if ssz.len() != 16 {
+                    return Err(format!("Updates by range request is {} bytes, not 16", ssz.len()));
+                }
+                let start_period = u64::from_le_bytes(ssz[..8].try_into().expect("8 bytes"));
+                let count = u64::from_le_bytes(ssz[8..].try_into().expect("8 bytes"));
+                Ok(Self::UpdatesByRange { start_period, count })
+            }
+            LightClientProtocol::FinalityUpdate => Ok(Self::FinalityUpdate),
+            LightClientProtocol::OptimisticUpdate => Ok(Self::OptimisticUpdate),
+        }
+    }
+}
+
+/// One successful response chunk.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ResponseChunk {
+    /// Fork digest of the fork the object is encoded for: the chunk's
+    /// context bytes.
+    pub fork_digest: [u8; 4],
+    pub ssz: Vec<u8>,
+}
+
+impl ResponseChunk {
+    /// The object, if it is encoded for the fork with `fork_digest`. One
+    /// for another fork has another layout, so it is refused rather than
+    /// decoded as this one's.
+    pub fn ssz_for(&self, fork_digest: [u8; 4]) -> Result<&[u8], String> {
+        if self.fork_digest != fork_digest {
+            return Err(format!(
+                "Response chunk is for fork digest {}, not {}",
+                hex::encode(self.fork_digest),
+                hex::encode(fork_digest)
+            ));
+        }
+        Ok(&self.ssz)
+    }
+}
+
+/// The result byte that starts each response chunk.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ResponseCode {
+    Success,
+    InvalidRequest,
+    ServerError,
+    ResourceUnavailable,
+    /// Reserved, or for a client's own use.
+    Other(u8),
+}
+
+impl ResponseCode {
+    pub fn to_byte(self) -> u8 {
+        match self {
+            Self::Success => 0,
+            Self::InvalidRequest => 1,
+            Self::ServerError => 2,
+            Self::ResourceUnavailable => 3,
+            Self::Other(byte) => byte,
+        }
+    }
+}
+
+impl From<u8> for ResponseCode {
+    fn from(byte: u8) -> Self {
+        match byte {
+            0 => Self::Success,
+            1 => Self::InvalidRequest,
+            2 => Self::ServerError,
+            3 => Self::ResourceUnavailable,
+            other => Self::Other(other),
+        }
+    }
+}
+
+/// A chunk with a result other than success.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ErrorResponse {
+    pub code: ResponseCode,
+    pub message: String,
+}
+
+impl fmt::Display for ErrorResponse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Peer answered with error {}: {}", self.code.to_byte(), self.message)
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LightClientResponse {
+    pub chunks: Vec<ResponseChunk>,
+    /// Set if the response ended with an error, possibly after some chunks.
+    pub error: Option<ErrorResponse>,
+}
+
+impl LightClientResponse {
+    /// The only chunk of a response to a request for one object.
+    pub fn into_single(self) -> Result<ResponseChunk, String> {
+        if let Some(error) = self.error {
+            return Err(error.to_string());
+        }
+        if self.chunks.len() != 1 {
+            return Err(format!("Expected one response chunk, got {}", self.chunks.len()));
+        }
+        Ok(self.chunks.into_iter().next().expect("one chunk"))
+    }
+}
+
+fn write_varint(mut value: usize, out: &mut Vec<u8>) {
+    while value >= 0x80 {
+        out.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+/// Read an unsigned varint from the front of `io`, a byte at a time so
+/// nothing after it is read.
+async fn read_varint<T: AsyncRead + Unpin + Send>(io: &mut T) -> io::Result<usize> {
+    let mut value = 0u64;
+    for i in 0..MAX_VARINT_LEN {
+        let mut byte = [0u8];
+        io.read_exact(&mut byte).await?;
+        let byte = byte[0];
+        if i == MAX_VARINT_LEN - 1 && byte > 1 {
+            return Err(invalid_data("Length prefix overflows 64 bits".to_string()));
+        }
+        value |= u64::from(byte & 0x7F) << (7 * i);
+        if byte & 0x80 == 0 {
+            return usize::try_from(value).map_err(|_| invalid_data("Length prefix overflows".to_string()));
+        }
+    }
+    Err(invalid_data("Length prefix is too long".to_string()))
+}
+
+/// An SSZ object as it goes on the wire: length prefix, then framed snappy.
+pub fn encode_payload(ssz: &[u8]) -> Vec<u8> {
+    let mut out = Vec::new();
+    write_varint(ssz.len(), &mut out);
+    let mut encoder = snap::write::FrameEncoder::new(out);
+    encoder.write_all(ssz).expect("writing to a Vec");
+    encoder.into_inner().expect("flushing to a Vec")
+}
+
+/// Most framed snappy bytes an object of `len` bytes may take: the
+/// stream identifier, then at worst each 64 KiB block compressed as badly
+/// as snappy can, behind its frame header and checksum.
+fn max_framed_len(len: usize) -> usize {
+    const BLOCK: usize = 1 << 16;
+    let blocks = len.div_ceil(BLOCK).max(1);
+    10 + snap::raw::max_compress_len(len) + blocks * (32 + 8)
+}
+
+/// Read the payload at the front of `io`: its length prefix, then snappy
+/// frames until they hold that many bytes, and nothing after them, so the
+/// next chunk is left unread. Objects over `max_len` bytes are refused
+/// before any of them is read.
+pub async fn read_payload<T: AsyncRead + Unpin + Send>(io: &mut T, max_len: usize) -> io::Result<Vec<u8>> {
+    let len = read_varint(io).await?;
+    if len > max_len {
+        return Err(invalid_data(format!("Payload of {} bytes is over the {} limit", len, max_len)));
+    }
+    let limit = max_framed_len(len);
+    let mut framed = Vec::new();
+    let mut held = 0;
+    // An empty object has no frames, not even the stream identifier.
+    while held < len {
+        let mut header = [0u8; 4];
+        io.read_exact(&mut header).await?;
+        let frame_len = u32::from_le_bytes([header[1], header[2], header[3], 0]) as usize;
+        let start = framed.len() + header.len();
+        if start + frame_len > limit {
+            return Err(invalid_data(format!("Snappy frames of a {} byte payload run past {} bytes", len, limit)));
+        }
+        framed.extend_from_slice(&header);
+        framed.resize(start + frame_len, 0);
+        io.read_exact(&mut framed[start..]).await?;
+        // Compressed and uncompressed data frames start with a checksum.
+        let data = framed[start..].get(4..).unwrap_or_default();
+        held += match header[0] {
+            0x00 => snap::raw::decompress_len(data).map_err(|e| invalid_data(format!("Snappy frame: {}", e)))?,
+            0x01 => data.len(),
+            _ => 0,
+        };
+    }
+    if held != len {
+        return Err(invalid_data(format!("Snappy frames hold {} bytes, not the {} declared", held, len)));
+    }
+    let mut ssz = Vec::with_capacity(len);
+    snap::read::FrameDecoder::new(framed.as_slice())
+        .read_to_end(&mut ssz)
+        .map_err(|e| invalid_data(format!("Snappy decompression failed: {}", e)))?;
+    Ok(ssz)
+}
+
+pub fn encode_response(response: &LightClientResponse) -> Vec<u8> {
+    let mut out = Vec::new();
+    for chunk in &response.chunks {
+        out.push(ResponseCode::Success.to_byte());
+        out.extend_from_slice(&chunk.fork_digest);
+        out.extend_from_slice(&encode_payload(&chunk.ssz));
+    }
+    if let Some(error) = &response.error {
+        out.push(error.code.to_byte());
+        out.extend_from_slice(&encode_payload(error.message.as_bytes()));
+    }
+    out
+}
+
+/// The result byte of the next chunk, or `None` at the end of the stream.
+async fn read_response_code<T: AsyncRead + Unpin + Send>(io: &mut T) -> io::Result<Option<ResponseCode>> {
+    let mut byte = [0u8];
+    Ok(match io.read(&mut byte).await? {
+        0 => None,
+        _ => Some(byte[0].into()),
+    })
+}
+
+/// The message of an error chunk with `code`, read as far as it is
+/// UTF-8.
+async fn read_error<T: AsyncRead + Unpin + Send>(io: &mut T, code: ResponseCode) -> io::Result<ErrorResponse> {
+    let message = read_payload(io, MAX_ERROR_MESSAGE_LEN).await?;
+    Ok(ErrorResponse { code, message: String::from_utf8_lossy(&message).into_owned() })
+}
+
+/// Read a response a chunk at a time until the stream ends or a chunk
+/// carries an error. More than `max_chunks` successful chunks fail the
+/// response, each chunk is held to [`MAX_PAYLOAD_SIZE`] on its own, and
+/// each keeps its context bytes.
+pub async fn read_response<T: AsyncRead + Unpin + Send>(
+    io: &mut T,
+    max_chunks: usize,
+) -> io::Result<LightClientResponse> {
+    let mut response = LightClientResponse::default();
+    while let Some(code) = read_response_code(io).await? {
+        if code != ResponseCode::Success {
+            response.error = Some(read_error(io, code).await?);
+            break;
+        }
+        if response.chunks.len() == max_chunks {
+            return Err(invalid_data(format!("Response has more than {} chunks", max_chunks)));
+        }
+        let mut fork_digest = [0u8; 4];
+        io.read_exact(&mut fork_digest).await?;
+        let ssz = read_payload(io, MAX_PAYLOAD_SIZE).await?;
+        response.chunks.push(ResponseChunk { fork_digest, ssz });
+    }
+    Ok(response)
+}
+
+/// A single successful response chunk with no fork digest, as Status, Ping
+/// and MetaData send, whose layouts are the same in every fork.
+pub(crate) fn encode_plain_response(ssz: &[u8]) -> Vec<u8> {
+    let mut out = vec![ResponseCode::Success.to_byte()];
+    out.extend_from_slice(&encode_payload(ssz));
+    out
+}
+
+/// The SSZ object in a response written by [`encode_plain_response`]. An
+/// error the peer answered with fails the read.
+pub(crate) async fn read_plain_response<T: AsyncRead + Unpin + Send>(io: &mut T) -> io::Result<Vec<u8>> {
+    match read_response_code(io).await? {
+        None => Err(invalid_data("Empty response".to_string())),
+        Some(ResponseCode::Success) => read_payload(io, MAX_PAYLOAD_SIZE).await,
+        Some(code) => Err(invalid_data(read_error(io, code).await?.to_string())),
+    }
+}
+
+pub(crate) fn invalid_data(e: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, e)
+}
+
+/// The `ssz_snappy` codec for [`LightClientProtocol`]s.
+#[derive(Clone, Debug, Default)]
+pub struct LightClientCodec;
+
+#[async_trait]
+impl request_response::Codec for LightClientCodec {
+    type Protocol = StreamProtocol;
+    type Request = LightClientRequest;
+    type Response = LightClientResponse;
+
+    async fn read_request<T>(&mut self, protocol: &StreamProtocol, io: &mut T) -> io::Result<LightClientRequest>
+    where
+        T: AsyncRead + Unpin + Send,
+    {
+        let protocol = LightClientProtocol::from_id(protocol.as_ref())
+            .ok_or_else(|| invalid_data(format!("Unknown protocol {}", protocol)))?;
+        let ssz = match protocol.has_request_body() {
+            true => read_payload(io, MAX_PAYLOAD_SIZE).await?,
+            false => Vec::new(),
+        };
+        LightClientRequest::from_ssz(protocol, &ssz).map_err(invalid_data)
+    }
+
+    async fn read_response<T>(&mut self, protocol: &StreamProtocol, io: &mut T) -> io::Result<LightClientResponse>
+    where
+        T: AsyncRead + Unpin + Send,
+    {
+        let max_chunks = LightClientProtocol::from_id(protocol.as_ref()).map_or(1, |p| p.max_response_chunks());
+        read_response(io, max_chunks).await
+    }
+
+    async fn write_request<T>(&mut self, _: &StreamProtocol, io: &mut T, request: LightClientRequest) -> io::Result<()>
+    where
+        T: AsyncWrite + Unpin + Send,
+    {
+        if !request.protocol().has_request_body() {
+            return Ok(());
+        }
+        io.write_all(&encode_payload(&request.to_ssz())).await
+    }
+
+    async fn write_response<T>(
+        &mut self,
+        _: &StreamProtocol,
+        io: &mut T,
+        response: LightClientResponse,
+    ) -> io::Result<()>
+    where
+        T: AsyncWrite + Unpin + Send,
+    {
+        io.write_all(&encode_response(&response)).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use libp2p::request_response::Codec;
+
+    #[tokio::test]
+    async fn test_payload_round_trip() {
+        for len in [0, 1000, 200_000] {
+            let ssz: Vec<u8> = (0..len as u32).map(|i| (i % 7) as u8).collect();
+            let mut wire = encode_payload(&ssz);
+            wire.extend_from_slice(b"next");
+            let mut io = wire.as_slice();
+            assert_eq!(read_payload(&mut io, MAX_PAYLOAD_SIZE).await.unwrap(), ssz);
+            // The frames are read and nothing after them.
+            assert_eq!(io, b"next");
+        }
+        assert_eq!(&encode_payload(&[0; 1000])[..2], &[0xE8, 0x07]); // varint 1000
+
+        assert!(read_payload(&mut &[0xFF; 11][..], MAX_PAYLOAD_SIZE).await.is_err());
+        let mut too_long = Vec::new();
+        write_varint(MAX_PAYLOAD_SIZE + 1, &mut too_long);
+        let error = read_payload(&mut too_long.as_slice(), MAX_PAYLOAD_SIZE).await.unwrap_err();
+        assert!(error.to_string().contains("limit"));
+
+        // A prefix that understates what the frames hold.
+        let mut wire = encode_payload(&[0xAB; 100]);
+        wire[0] = 99;
+        assert!(read_payload(&mut wire.as_slice(), MAX_PAYLOAD_SIZE).await.is_err());
+        let wire = encode_payload(&[0xAB; 100]);
+        assert!(read_payload(&mut &wire[..wire.len() - 1], MAX_PAYLOAD_SIZE).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_response_chunks_and_errors() {
+        let response = LightClientResponse {
+            chunks: vec![
+                ResponseChunk { fork_digest: [1, 2, 3, 4], ssz: vec![0xAB; 40] },
+                ResponseChunk { fork_digest: [5, 6, 7, 8], ssz: vec![0xCD; 3] },
+            ],
+            error: Some(ErrorResponse { code: ResponseCode::ResourceUnavailable, message: "no more".to_string() }),
+        };
+        let wire = encode_response(&response);
+        assert_eq!(read_response(&mut wire.as_slice(), 2).await.unwrap(), response);
+        assert!(read_response(&mut wire.as_slice(), 1).await.unwrap_err().to_string().contains("more than 1"));
+        assert!(response.clone().into_single().unwrap_err().contains("error 3"));
+
+        let single = LightClientResponse { chunks: response.chunks[..1].to_vec(), error: None };
+        let chunk = single.into_single().unwrap();
+        assert_eq!(chunk.ssz_for([1, 2, 3, 4]).unwrap(), &[0xAB; 40][..]);
+        assert!(chunk.ssz_for([5, 6, 7, 8]).unwrap_err().contains("01020304"));
+        assert!(LightClientResponse::default().into_single().is_err());
+        assert_eq!(read_response(&mut &[][..], 1).await.unwrap(), LightClientResponse::default());
+        assert!(read_response(&mut &[0, 1, 2][..], 1).await.is_err());
+
+        // Error messages are held to their own limit.
+        let mut wire = vec![ResponseCode::ServerError.to_byte()];
+        wire.extend_from_slice(&encode_payload(&[b'x'; MAX_ERROR_MESSAGE_LEN + 1]));
+        assert!(read_response(&mut wire.as_slice(), 1).await.is_err());
+        assert_eq!(ResponseCode::from(7), ResponseCode::Other(7));
+        assert_eq!(ResponseCode::from(2).to_byte(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_codec_requests() {
+        let protocol = LightClientProtocol::Bootstrap.stream_protocol();
+        let request = LightClientRequest::Bootstrap { block_root: [0x42; 32] };
+        let mut wire = Vec::new();
+        LightClientCodec.write_request(&protocol, &mut wire, request.clone()).await.unwrap();
+        let read = LightClientCodec.read_request(&protocol, &mut wire.as_slice()).await.unwrap();
+        assert_eq!(read, request);
+
+        let protocol = LightClientProtocol::UpdatesByRange.stream_protocol();
+        let request = LightClientRequest::UpdatesByRange { start_period: 1300, count: 4 };
+        assert_eq!(request.to_ssz()[..2], [0x14, 0x05]);
+        let mut wire = Vec::new();
+        LightClientCodec.write_request(&protocol, &mut wire, request.clone()).await.unwrap();
+        assert_eq!(LightClientCodec.read_request(&protocol, &mut wire.as_slice()).await.unwrap(), request);
+
+        // Nothing is sent for the latest finality update.
+        let protocol = LightClientProtocol::FinalityUpdate.stream_protocol();
+        let mut wire = Vec::new();
+        LightClientCodec.write_request(&protocol, &mut wire, LightClientRequest::FinalityUpdate).await.unwrap();
+        assert!(wire.is_empty());
+        let read = LightClientCodec.read_request(&protocol, &mut wire.as_slice()).await.unwrap();
+        assert_eq!(read, LightClientRequest::FinalityUpdate);
+
+        let other = StreamProtocol::new("/eth2/beacon_chain/req/other/1/ssz_snappy");
+        assert!(LightClientCodec.read_request(&other, &mut wire.as_slice()).await.is_err());
+    }
+}
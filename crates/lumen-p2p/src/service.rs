@@ -0,0 +1,2267 @@
+//! The running P2P node: a libp2p Swarm and the task that drives it.
+//!
+//! [`LumenP2pService::start`] builds the transport stack from the
+//! [`TransportConfig`], wraps it in a Swarm with [`LumenBehaviour`],
+//! subscribes to the light client topics of the fork in effect, dials the
+//! bootnodes, fetches the peer directories, and spawns
+//! the event loop: on the browser's microtask queue under `wasm32`, on the
+//! ambient Tokio runtime natively. The Swarm is owned by that task alone;
+//! `dial`, `subscribe` and `stop` reach it as commands over a channel, so
+//! the service handle never blocks on the network.
+//!
+//! Outcomes are asynchronous. A dial that is accepted here can still fail
+//! once the transport tries it, and that shows up in [`ServiceStatus`], not
+//! as an error from `dial`.
+//!
+//! Gossip goes the other way through a bounded channel, the receiver
+//! `start` returns. When it is full the event loop waits for the consumer
+//! before reading the network again, still taking commands meanwhile, so a
+//! slow verifier slows intake instead of growing a queue. Messages are
+//! delivered unverified: checking them is the consumer's job, and so is
+//! reporting the outcome with `report_validation`. Gossipsub forwards a
+//! message only once it is accepted, and scores the peer it came from by
+//! it, as does the [`PeerManager`]. Outcomes are remembered for a while
+//! ([`VerifiedMessages`]): a message that arrives again is answered from
+//! them, counted as a duplicate and not delivered.
+//!
+//! Light client requests are commands too, each carrying a oneshot channel
+//! the loop answers on once the peer responds or the request fails. Every
+//! ping's round trip goes into the peer's [`PeerScore`], and
+//! `request_peers` offers the checked peers fastest first to send them to.
+//! Portal content queries, over Lumen's overlay, go the same way:
+//! `find_content` runs a [`ContentLookup`] from the connected peers that
+//! announce the network, dialing the closer nodes they point to, until some
+//! content passes the caller's check.
+//!
+//! Every new peer is sent our [`StatusMessage`], and gossipsub ignores its
+//! messages until its own Status passes [`check_status`]; one that fails is
+//! disconnected. Lumen has no Status until the consumer sets one with
+//! `set_status`, so until then peers are left unchecked and no gossip is
+//! delivered. The Status's fork digest is the service's own.
+//!
+//! The loop also keeps time for the [`ForkSchedule`]: around each fork it
+//! subscribes to the new fork's topics, drops the old fork's, and moves
+//! the Status over, so gossip keeps flowing across a fork. Which topics
+//! those are is up to the consumer: [`TOPIC_NAMES`] to begin with, and
+//! whatever `subscribe_topic` adds, such as `beacon_block`, each with its
+//! own gossip channel. Full nodes' MetaData and Ping requests are answered as they
+//! come, from any peer.
+//!
+//! The [`PeerManager`] decides which peers to dial and keep. Peers found
+//! by [`crate::discovery`] are queued and dialed while there are fewer
+//! than `max_peers` connections, counting dials in progress, or a quarter
+//! fewer once the [`ReachabilityProbe`] finds peers can dial us; more are
+//! pruned, and a heartbeat redials known peers below `min_peers`. Peers
+//! whose Status fails are banned as well as disconnected. With a
+//! [`PeerStorage`], the peers that pass and keep a good score are
+//! remembered in a [`PeerStore`], saved on each heartbeat that changes it
+//! and when the loop stops, and dialed first by the next `start`.
+//! With Kademlia enabled, peers that announce it join the routing table,
+//! and the light client data providers it finds are dialed the same way.
+//!
+//! Every [`RELAY_TICK_INTERVAL`] the loop hands its direct peers to the
+//! [`RelayFallback`] and does what it says: reserve a slot on a relay when
+//! the bootnodes cannot be reached in time, look for direct peers while
+//! relayed, and release the relay once there are enough. Relays that fail
+//! or stop answering pings are failed over from. The resulting
+//! [`ConnectionMode`] and [`BootstrapPhase`], and each relay's health, are
+//! in [`ServiceStatus`], with the bytes each peer and transport moved.
+//!
+//! What changes is also told as it happens, as [`P2pEvent`]s on the
+//! streams `events` hands out: peers connecting and leaving, gossip
+//! delivered, the mode and phase moving, and each error as it is
+//! recorded. Subscribers that fall behind lose events rather than hold
+//! up the loop.
+
+use crate::bandwidth::BandwidthMeter;
+use crate::beacon_gossip::{
+    blob_sidecar_topic_name, gossip_topic, topic_name, ForkSchedule, GossipMessage, GossipMessageType, GossipStats,
+    GossipValidation, TopicMeshStats, VerifiedMessages, BLOB_SIDECAR_SUBNET_COUNT, TOPIC_NAMES,
+};
+use crate::behaviour::{light_client_topic_score_params, GossipsubMetrics, LumenBehaviour, LumenBehaviourEvent};
+use crate::bootstrap::{BootnodeDialer, BootstrapConfig, BootstrapState, DialFailure};
+use crate::dht::{light_client_providers_key, LUMEN_KAD_PROTOCOL, PROVIDER_LOOKUP_INTERVAL};
+use crate::events::{P2pEvent, EVENT_CHANNEL_CAPACITY};
+use crate::discovery::{
+    fetch_directory, Discovery, PeerExchangeRequest, PeerRecord, MAX_PEER_EXCHANGE_RECORDS, PEER_EXCHANGE_PROTOCOL,
+};
+use crate::peer_manager::{peer_of, PeerManager, HEARTBEAT_INTERVAL};
+use crate::peer_store::{PeerStorage, PeerStore, StoredPeer};
+use crate::portal::{
+    decode_block_header, decode_receipts, AccountProofWalk, ContentLookup, ContentResponse, FindContent,
+    HistoryContentKey, PortalNetwork, LOOKUP_PARALLELISM,
+};
+use crate::reachability::ReachabilityProbe;
+use crate::relay::{
+    ConnectionMode, DirectPeers, RelayEvent, RelayFallback, RelayHealth, RelayUpgradeStrategy, RELAY_TICK_INTERVAL,
+};
+use crate::req_resp::{
+    LightClientProtocol, LightClientRequest, LightClientResponse, ResponseChunk, MAX_REQUEST_LIGHT_CLIENT_UPDATES,
+};
+use crate::status::{check_status, StatusMessage};
+use crate::transport::{build_transport, TransportConfig, TransportStats, TransportType};
+use futures::channel::{mpsc, oneshot};
+use futures::stream::FuturesUnordered;
+use futures::{future, FutureExt, StreamExt};
+use libp2p::core::transport::ListenerId;
+use libp2p::identity::Keypair;
+use libp2p::multiaddr::Protocol;
+use libp2p::request_response::{self, OutboundRequestId};
+use libp2p::swarm::{dial_opts::DialOpts, ConnectionId, DialError, Swarm, SwarmEvent};
+use libp2p::gossipsub::TopicHash;
+use libp2p::{gossipsub, identify, kad, noise, ping, relay, yamux, Multiaddr, PeerId, StreamProtocol, SwarmBuilder};
+use lumen_core::consensus::ssz::{
+    decode_bootstrap, decode_finality_update, decode_optimistic_update, decode_update, DecodedBootstrap,
+    DecodedOptimisticUpdate, DecodedUpdate,
+};
+use lumen_core::consensus::sync_committee::hash_beacon_block_header;
+use lumen_core::execution::header::ExecutionBlockHeader;
+use lumen_core::types::execution::{AccountProof, TransactionReceipt};
+use lumen_core::types::beacon::SLOTS_PER_SYNC_COMMITTEE_PERIOD;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// How long a connection with no open streams is kept. Gossipsub keeps
+/// streams open to mesh peers, so this only closes connections nobody uses.
+pub const IDLE_CONNECTION_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Gossip messages buffered for the consumer before the event loop waits.
+/// Light client updates arrive a few per slot, so this is minutes of them.
+pub const MESSAGE_CHANNEL_CAPACITY: usize = 64;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum P2pError {
+    /// The service is not running: `start` was never called, or `stop` was.
+    NotRunning,
+    /// `start` was called on a service that is already running.
+    AlreadyRunning,
+    InvalidMultiaddr(String),
+    /// The Swarm could not be built.
+    Setup(String),
+    /// A request failed, or its response was not what was asked for.
+    Request(String),
+}
+
+impl fmt::Display for P2pError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            P2pError::NotRunning => write!(f, "P2P service is not running"),
+            P2pError::AlreadyRunning => write!(f, "P2P service is already running"),
+            P2pError::InvalidMultiaddr(reason) => write!(f, "Invalid multiaddr: {}", reason),
+            P2pError::Setup(reason) => write!(f, "P2P setup failed: {}", reason),
+            P2pError::Request(reason) => write!(f, "P2P request failed: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for P2pError {}
+
+/// What the event loop has seen, shared with the service handle.
+#[derive(Clone, Debug, Default)]
+pub struct ServiceStatus {
+    /// Whether the event loop is running.
+    pub running: bool,
+    /// Peers with at least one open connection.
+    pub connected_peers: usize,
+    /// Connected peers whose Status matched ours: the ones gossip is taken
+    /// from.
+    pub accepted_peers: usize,
+    /// Peers found through peer directories, peer exchange and the DHT,
+    /// and queued or dialed.
+    pub discovered_peers: u64,
+    /// Gossip topics subscribed to.
+    pub topics: Vec<String>,
+    /// The fork digest in effect, to decode gossip with.
+    pub fork_digest: [u8; 4],
+    /// Gossip received so far. Messages are counted, not verified.
+    pub gossip: GossipStats,
+    /// Whether we are connected directly, through a relay, or not yet.
+    pub connection_mode: ConnectionMode,
+    /// How bootstrapping is going. Its connected peers are the direct ones,
+    /// relays aside.
+    pub bootstrap: BootstrapState,
+    /// The health of each configured relay.
+    pub relays: Vec<RelayHealth>,
+    /// Connections by transport, and whether peers can dial us.
+    pub transport: TransportStats,
+    /// Peers in the [`PeerStore`].
+    pub stored_peers: usize,
+    /// The most recent dial or listener failure.
+    pub last_error: Option<String>,
+}
+
+/// Requests from the service handle to the event loop.
+#[derive(Debug)]
+enum Command {
+    Dial(Multiaddr),
+    Subscribe(String),
+    SubscribeTopic { name: String, handler: mpsc::Sender<GossipMessage> },
+    UnsubscribeTopic(String),
+    Request { peer: PeerId, request: LightClientRequest, reply: Reply },
+    RequestPeers(oneshot::Sender<Vec<PeerId>>),
+    /// Ask `peer` on `network`, at `addresses` if not connected.
+    PortalRequest {
+        network: PortalNetwork,
+        peer: PeerId,
+        addresses: Vec<Multiaddr>,
+        request: FindContent,
+        reply: PortalReply,
+    },
+    PortalPeers { network: PortalNetwork, reply: oneshot::Sender<Vec<PeerId>> },
+    SetStatus(StatusMessage),
+    ReportValidation { message_id: Vec<u8>, source: PeerId, validation: GossipValidation },
+    /// What a peer directory at `source` answered.
+    Discovered { source: String, records: Result<Vec<PeerRecord>, String> },
+    Stop,
+}
+
+/// Where the event loop sends the outcome of a request.
+type Reply = oneshot::Sender<Result<LightClientResponse, P2pError>>;
+
+/// Where the event loop sends the outcome of a Portal query.
+type PortalReply = oneshot::Sender<Result<ContentResponse, P2pError>>;
+
+/// A libp2p node for the light client. See the module docs.
+pub struct LumenP2pService {
+    keypair: Keypair,
+    transport: TransportConfig,
+    bootstrap: BootstrapConfig,
+    forks: ForkSchedule,
+    status: Arc<Mutex<ServiceStatus>>,
+    storage: Option<Arc<dyn PeerStorage>>,
+    events: EventSink,
+    /// Present while the event loop runs.
+    commands: Option<mpsc::UnboundedSender<Command>>,
+}
+
+impl LumenP2pService {
+    pub fn new(keypair: Keypair, transport: TransportConfig, bootstrap: BootstrapConfig, forks: ForkSchedule) -> Self {
+        Self {
+            keypair,
+            transport,
+            bootstrap,
+            forks,
+            status: Arc::default(),
+            storage: None,
+            events: EventSink::default(),
+            commands: None,
+        }
+    }
+
+    /// Remember good peers in `storage` across sessions, and dial the ones
+    /// it has first.
+    pub fn with_peer_storage(mut self, storage: impl PeerStorage + 'static) -> Self {
+        self.storage = Some(Arc::new(storage));
+        self
+    }
+
+    pub fn local_peer_id(&self) -> PeerId {
+        self.keypair.public().to_peer_id()
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.commands.as_ref().is_some_and(|commands| !commands.is_closed())
+    }
+
+    pub fn status(&self) -> ServiceStatus {
+        self.status.lock().expect("status lock").clone()
+    }
+
+    /// A stream of what happens on the network from now on. It can be
+    /// taken before `start` and lasts across restarts; dropping it
+    /// unsubscribes.
+    pub fn events(&self) -> mpsc::Receiver<P2pEvent> {
+        self.events.subscribe()
+    }
+
+    /// Build the Swarm, subscribe to the light client topics of the forks
+    /// in effect, dial the stored peers and then the first bootnodes, and spawn
+    /// the event loop. Natively,
+    /// must be called within a Tokio runtime.
+    ///
+    /// Returns the receiving end of the gossip channel. Dropping it discards
+    /// gossip from then on; it does not stop the service.
+    pub fn start(&mut self) -> Result<mpsc::Receiver<GossipMessage>, P2pError> {
+        if self.is_running() {
+            return Err(P2pError::AlreadyRunning);
+        }
+        let bandwidth = BandwidthMeter::new();
+        let mut metrics = GossipsubMetrics::new();
+        let kademlia = self.bootstrap.enable_kademlia;
+        let swarm = build_swarm(&self.keypair, &self.transport, kademlia, &bandwidth, &mut metrics)?;
+        *self.status.lock().expect("status lock") = ServiceStatus { running: true, ..ServiceStatus::default() };
+        let discovery = Discovery::new(self.transport.clone());
+        let mut event_loop = EventLoop::new(swarm, self.status.clone(), self.forks.clone(), discovery);
+        event_loop.events = self.events.clone();
+        event_loop.bandwidth = bandwidth;
+        event_loop.gossipsub_metrics = metrics;
+        event_loop.peers = PeerManager::new(self.bootstrap.min_peers, self.bootstrap.max_peers)
+            .with_dial_limits(self.bootstrap.max_concurrent_dials, self.bootstrap.max_dials_per_round);
+        let strategy = RelayUpgradeStrategy::default();
+        let local_peer_id = self.local_peer_id();
+        event_loop.relay = RelayFallback::new(&self.bootstrap, strategy, self.transport.enable_relay, &local_peer_id);
+        event_loop.update_fork_topics(self.forks.epoch_at(now_ms()).unwrap_or(0));
+        if let Some(storage) = &self.storage {
+            event_loop.load_peers(storage.clone());
+        }
+        let mut bootnodes = Vec::new();
+        for bootnode in &self.bootstrap.bootnodes {
+            match bootnode.parse::<Multiaddr>() {
+                Ok(address) => bootnodes.push(address),
+                Err(e) => {
+                    record_error(&self.status, &self.events, format!("Bootnode {}: {}", bootnode, e));
+                    let reason = DialFailure::InvalidAddress { error: e.to_string() };
+                    let mut status = self.status.lock().expect("status lock");
+                    status.bootstrap.record_failure(bootnode.clone(), reason, now_ms());
+                }
+            }
+        }
+        event_loop.bootnodes = BootnodeDialer::new(bootnodes, random_seed());
+        event_loop.update_listening();
+        let events = event_loop.relay.start(now_ms());
+        let mut status = self.status.lock().expect("status lock");
+        event_loop.dial_bootnodes(&mut status);
+        event_loop.apply_relay(events, &mut status);
+        event_loop.tick(&mut status);
+        drop(status);
+
+        let (commands, command_receiver) = mpsc::unbounded();
+        let (messages, message_receiver) = mpsc::channel(MESSAGE_CHANNEL_CAPACITY);
+        spawn(run(event_loop, command_receiver, messages));
+        for source in self.bootstrap.peer_directories.clone() {
+            let commands = commands.clone();
+            spawn(async move {
+                let records = fetch_directory(&source).await;
+                let _ = commands.unbounded_send(Command::Discovered { source, records });
+            });
+        }
+        self.commands = Some(commands);
+        Ok(message_receiver)
+    }
+
+    /// Stop the event loop, closing every connection. Stopping a service
+    /// that is not running does nothing.
+    pub fn stop(&mut self) {
+        if let Some(commands) = self.commands.take() {
+            let _ = commands.unbounded_send(Command::Stop);
+        }
+    }
+
+    /// Dial `address`, e.g. `/dns4/host/tcp/443/wss/p2p/<peer id>`. Dials
+    /// asked for like this are made whatever the dial limits.
+    pub fn dial(&self, address: &str) -> Result<(), P2pError> {
+        let address = address
+            .parse::<Multiaddr>()
+            .map_err(|e| P2pError::InvalidMultiaddr(format!("{}: {}", address, e)))?;
+        self.send(Command::Dial(address))
+    }
+
+    /// Subscribe to the gossip topic `topic`, in addition to the light
+    /// client topics. It is kept across forks as it is.
+    pub fn subscribe(&self, topic: &str) -> Result<(), P2pError> {
+        self.send(Command::Subscribe(topic.to_string()))
+    }
+
+    /// Subscribe to the gossip topic `name`, e.g. `beacon_block` or
+    /// `blob_sidecar_0`, on every fork in effect, following forks as the
+    /// light client topics do. Its messages go to the receiver returned,
+    /// not to the one `start` returned, and are held back the same way
+    /// while it is full. Subscribing to `name` again replaces its
+    /// receiver; a light client topic can be taken over like this too.
+    pub fn subscribe_topic(&self, name: &str) -> Result<mpsc::Receiver<GossipMessage>, P2pError> {
+        let (handler, messages) = mpsc::channel(MESSAGE_CHANNEL_CAPACITY);
+        self.send(Command::SubscribeTopic { name: name.to_string(), handler })?;
+        Ok(messages)
+    }
+
+    /// Subscribe to the blob sidecar topics of every subnet, as
+    /// `subscribe_topic` would, with one receiver for all of them.
+    pub fn subscribe_blob_sidecars(&self) -> Result<mpsc::Receiver<GossipMessage>, P2pError> {
+        let (handler, messages) = mpsc::channel(MESSAGE_CHANNEL_CAPACITY);
+        for subnet in 0..BLOB_SIDECAR_SUBNET_COUNT {
+            self.send(Command::SubscribeTopic { name: blob_sidecar_topic_name(subnet), handler: handler.clone() })?;
+        }
+        Ok(messages)
+    }
+
+    pub fn unsubscribe_blob_sidecars(&self) -> Result<(), P2pError> {
+        (0..BLOB_SIDECAR_SUBNET_COUNT).try_for_each(|subnet| self.unsubscribe_topic(&blob_sidecar_topic_name(subnet)))
+    }
+
+    /// Stop following the gossip topic `name`, one of [`TOPIC_NAMES`] or
+    /// added by `subscribe_topic`, on every fork.
+    pub fn unsubscribe_topic(&self, name: &str) -> Result<(), P2pError> {
+        self.send(Command::UnsubscribeTopic(name.to_string()))
+    }
+
+    /// Send `request` to `peer` and wait for its response. The peer must be
+    /// connected, or at an address the Swarm knows.
+    pub async fn request(&self, peer: PeerId, request: LightClientRequest) -> Result<LightClientResponse, P2pError> {
+        let (reply, response) = oneshot::channel();
+        self.send(Command::Request { peer, request, reply })?;
+        response.await.map_err(|_| P2pError::NotRunning)?
+    }
+
+    /// The peers to send requests to, best first: connected ones whose
+    /// Status passed, the lowest smoothed ping first and those not pinged
+    /// yet last. Empty until `set_status`, as no peer is checked before.
+    pub async fn request_peers(&self) -> Result<Vec<PeerId>, P2pError> {
+        let (reply, peers) = oneshot::channel();
+        self.send(Command::RequestPeers(reply))?;
+        peers.await.map_err(|_| P2pError::NotRunning)
+    }
+
+    /// Connected peers that answer Portal queries on `network`.
+    pub async fn portal_peers(&self, network: PortalNetwork) -> Result<Vec<PeerId>, P2pError> {
+        let (reply, peers) = oneshot::channel();
+        self.send(Command::PortalPeers { network, reply })?;
+        peers.await.map_err(|_| P2pError::NotRunning)
+    }
+
+    /// Ask `peer` for the content with `content_key` on `network`, dialing
+    /// it at `addresses` if it is not connected.
+    async fn portal_request(
+        &self,
+        network: PortalNetwork,
+        peer: PeerId,
+        addresses: Vec<Multiaddr>,
+        content_key: Vec<u8>,
+    ) -> Result<ContentResponse, P2pError> {
+        let (reply, response) = oneshot::channel();
+        let request = FindContent { content_key };
+        self.send(Command::PortalRequest { network, peer, addresses, request, reply })?;
+        response.await.map_err(|_| P2pError::NotRunning)?
+    }
+
+    /// Look the content with `content_key` up on `network`, asking
+    /// [`LOOKUP_PARALLELISM`] nodes at a time, until one sends content that
+    /// `verify` accepts. Content it rejects is passed over like a node
+    /// that does not have it.
+    pub async fn find_content<T>(
+        &self,
+        network: PortalNetwork,
+        content_key: Vec<u8>,
+        verify: impl Fn(&[u8]) -> Result<T, String>,
+    ) -> Result<T, P2pError> {
+        let peers = self.portal_peers(network).await?;
+        let mut lookup = ContentLookup::new(&content_key, self.keypair.public().to_peer_id(), peers);
+        let mut queries = FuturesUnordered::new();
+        let mut last_error = None;
+        loop {
+            while queries.len() < LOOKUP_PARALLELISM {
+                let Some((peer, addresses)) = lookup.next_query() else { break };
+                let query = self.portal_request(network, peer, addresses, content_key.clone());
+                queries.push(query.map(move |response| (peer, response)));
+            }
+            let Some((peer, response)) = queries.next().await else { break };
+            match response {
+                Ok(ContentResponse::Content(content)) => match verify(&content) {
+                    Ok(found) => return Ok(found),
+                    Err(e) => last_error = Some(format!("Content from {}: {}", peer, e)),
+                },
+                Ok(ContentResponse::Nodes(nodes)) => {
+                    lookup.add_nodes(nodes);
+                }
+                Err(e) => last_error = Some(e.to_string()),
+            }
+        }
+        let key = hex::encode(&content_key);
+        let mut error = format!("{} content {} not found from {} nodes", network.id(), key, lookup.queried());
+        if let Some(last_error) = last_error {
+            error = format!("{} ({})", error, last_error);
+        }
+        Err(P2pError::Request(error))
+    }
+
+    /// Fetch the execution header of the block with hash `block_hash`
+    /// from the Portal history network, checked against the hash.
+    pub async fn portal_block_header(&self, block_hash: [u8; 32]) -> Result<ExecutionBlockHeader, P2pError> {
+        let content_key = HistoryContentKey::BlockHeaderByHash(block_hash).to_bytes();
+        self.find_content(PortalNetwork::History, content_key, |content| decode_block_header(content, block_hash))
+            .await
+    }
+
+    /// Fetch the receipts of the block with `header`, which must already
+    /// be verified, from the Portal history network, checked against its
+    /// receipts root.
+    pub async fn portal_receipts(&self, header: &ExecutionBlockHeader) -> Result<Vec<TransactionReceipt>, P2pError> {
+        let content_key = HistoryContentKey::Receipts(header.hash()).to_bytes();
+        let receipts_root = header.receipts_root;
+        self.find_content(PortalNetwork::History, content_key, |content| decode_receipts(content, receipts_root))
+            .await
+    }
+
+    /// Collect the proof of `address`'s account under the verified
+    /// `state_root` from the Portal state network, node by node. The
+    /// proof's `account` is none if it shows the account absent.
+    pub async fn portal_account_proof(
+        &self,
+        state_root: [u8; 32],
+        address: [u8; 20],
+    ) -> Result<AccountProof, P2pError> {
+        let mut walk = AccountProofWalk::new(state_root, address);
+        while let Some(content_key) = walk.next_key() {
+            let verify = |content: &[u8]| {
+                let mut next = walk.clone();
+                next.push(content).map(|_| next)
+            };
+            walk = self.find_content(PortalNetwork::State, content_key.to_bytes(), verify).await?;
+        }
+        walk.finish().map_err(|e| P2pError::Request(format!("Account proof for 0x{}: {}", hex::encode(address), e)))
+    }
+
+    /// Fetch the bootstrap for the block with root `block_root` from `peer`.
+    /// Its header is checked against the root; the rest still has to pass
+    /// `initialize_from_bootstrap`.
+    pub async fn request_bootstrap(&self, peer: PeerId, block_root: [u8; 32]) -> Result<DecodedBootstrap, P2pError> {
+        let ssz = self.request_single(peer, LightClientRequest::Bootstrap { block_root }).await?;
+        let decoded =
+            decode_bootstrap(&ssz).map_err(|e| P2pError::Request(format!("Bootstrap from {}: {}", peer, e)))?;
+        if hash_beacon_block_header(&decoded.bootstrap.header) != block_root {
+            return Err(P2pError::Request(format!("Bootstrap from {} is for another block", peer)));
+        }
+        Ok(decoded)
+    }
+
+    /// Fetch the updates of `count` sync committee periods from
+    /// `start_period` from `peer`, for `process_committee_update`. `count`
+    /// is capped at [`MAX_REQUEST_LIGHT_CLIENT_UPDATES`]. Peers may send
+    /// fewer; those sent before an error are kept.
+    pub async fn request_updates_by_range(
+        &self,
+        peer: PeerId,
+        start_period: u64,
+        count: u64,
+    ) -> Result<Vec<DecodedUpdate>, P2pError> {
+        let count = count.min(MAX_REQUEST_LIGHT_CLIENT_UPDATES);
+        let response = self.request(peer, LightClientRequest::UpdatesByRange { start_period, count }).await?;
+        match response.error {
+            Some(error) if response.chunks.is_empty() => Err(P2pError::Request(error.to_string())),
+            _ => decode_updates_by_range(&response.chunks, self.status().fork_digest, start_period, count)
+                .map_err(|e| P2pError::Request(format!("Updates from {}: {}", peer, e))),
+        }
+    }
+
+    /// Fetch `peer`'s latest finality update, for a freshly connected client
+    /// that should not wait for the next one to be gossiped.
+    pub async fn request_finality_update(&self, peer: PeerId) -> Result<DecodedUpdate, P2pError> {
+        let ssz = self.request_single(peer, LightClientRequest::FinalityUpdate).await?;
+        decode_finality_update(&ssz)
+            .map_err(|e| P2pError::Request(format!("Finality update from {}: {}", peer, e)))
+    }
+
+    /// Fetch `peer`'s latest optimistic update.
+    pub async fn request_optimistic_update(&self, peer: PeerId) -> Result<DecodedOptimisticUpdate, P2pError> {
+        let ssz = self.request_single(peer, LightClientRequest::OptimisticUpdate).await?;
+        decode_optimistic_update(&ssz)
+            .map_err(|e| P2pError::Request(format!("Optimistic update from {}: {}", peer, e)))
+    }
+
+    /// The one object `peer` answers `request` with, if it is encoded for
+    /// the fork in effect, the only one whose layouts lumen-core decodes.
+    async fn request_single(&self, peer: PeerId, request: LightClientRequest) -> Result<Vec<u8>, P2pError> {
+        let chunk = self.request(peer, request).await?.into_single().map_err(P2pError::Request)?;
+        let fork_digest = self.status().fork_digest;
+        chunk.ssz_for(fork_digest).map(<[u8]>::to_vec).map_err(|e| P2pError::Request(format!("{}: {}", peer, e)))
+    }
+
+    /// Set the Status sent to peers, e.g. [`StatusMessage::light_client`]
+    /// for the client's finalized checkpoint. Its fork digest is replaced
+    /// by the one in effect. Needed after each `start`
+    /// for any gossip to be delivered: peers that connected before it are
+    /// checked once it is set. Update it as the client finalizes; peers
+    /// already accepted are not checked again.
+    pub fn set_status(&self, status: StatusMessage) -> Result<(), P2pError> {
+        self.send(Command::SetStatus(status))
+    }
+
+    /// Report what verifying `message` found. Every message delivered
+    /// should be reported once; one never reported is not forwarded.
+    /// Messages with no source peer are not from the network, and are
+    /// left alone.
+    pub fn report_validation(&self, message: &GossipMessage, validation: GossipValidation) -> Result<(), P2pError> {
+        let Some(source) = message.source_peer.as_ref().and_then(|peer| peer.parse().ok()) else {
+            return Ok(());
+        };
+        self.send(Command::ReportValidation { message_id: message.message_id.clone(), source, validation })
+    }
+
+    fn send(&self, command: Command) -> Result<(), P2pError> {
+        self.commands
+            .as_ref()
+            .ok_or(P2pError::NotRunning)?
+            .unbounded_send(command)
+            .map_err(|_| P2pError::NotRunning)
+    }
+}
+
+impl Drop for LumenP2pService {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+fn build_swarm(
+    keypair: &Keypair,
+    config: &TransportConfig,
+    kademlia: bool,
+    bandwidth: &BandwidthMeter,
+    metrics: &mut GossipsubMetrics,
+) -> Result<Swarm<LumenBehaviour>, P2pError> {
+    let builder = SwarmBuilder::with_existing_identity(keypair.clone());
+    #[cfg(target_arch = "wasm32")]
+    let builder = builder.with_wasm_bindgen();
+    #[cfg(not(target_arch = "wasm32"))]
+    let builder = builder.with_tokio();
+
+    Ok(builder
+        .with_other_transport(|keypair| build_transport(keypair, config, bandwidth))
+        .map_err(|e| P2pError::Setup(e.to_string()))?
+        .with_relay_client(noise::Config::new, yamux::Config::default)
+        .map_err(|e| P2pError::Setup(e.to_string()))?
+        .with_behaviour(|keypair, relay_client| {
+            LumenBehaviour::new(keypair, relay_client, kademlia, metrics).map_err(Into::into)
+        })
+        .map_err(|e| P2pError::Setup(e.to_string()))?
+        .with_swarm_config(|swarm| swarm.with_idle_connection_timeout(IDLE_CONNECTION_TIMEOUT))
+        .build())
+}
+
+#[cfg(target_arch = "wasm32")]
+fn spawn(task: impl std::future::Future<Output = ()> + 'static) {
+    wasm_bindgen_futures::spawn_local(task);
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn spawn(task: impl std::future::Future<Output = ()> + Send + 'static) {
+    tokio::spawn(task);
+}
+
+/// Unix time in milliseconds.
+#[cfg(target_arch = "wasm32")]
+fn now_ms() -> u64 {
+    js_sys::Date::now() as u64
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn now_ms() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_millis() as u64)
+}
+
+/// A seed for jitter, different in each client that starts at once.
+fn random_seed() -> u64 {
+    let mut seed = [0u8; 8];
+    // Without randomness, the clock still tells clients apart.
+    match getrandom::getrandom(&mut seed) {
+        Ok(()) => u64::from_le_bytes(seed),
+        Err(_) => now_ms(),
+    }
+}
+
+/// Resolve after `duration`, with `setTimeout` in any global scope. Its
+/// delay is a signed 32-bit count of milliseconds, so longer waits end
+/// early, after about 24 days.
+#[cfg(target_arch = "wasm32")]
+async fn sleep(duration: Duration) {
+    use wasm_bindgen::{JsCast, JsValue};
+    let ms = duration.as_millis().min(i32::MAX as u128) as i32;
+    let promise = js_sys::Promise::new(&mut |resolve, _reject| {
+        let set_timeout = js_sys::Reflect::get(&js_sys::global(), &JsValue::from_str("setTimeout"))
+            .ok()
+            .and_then(|f| f.dyn_into::<js_sys::Function>().ok());
+        if let Some(set_timeout) = set_timeout {
+            let _ = set_timeout.call2(&JsValue::NULL, &resolve, &JsValue::from(ms));
+        }
+    });
+    let _ = wasm_bindgen_futures::JsFuture::from(promise).await;
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+async fn sleep(duration: Duration) {
+    tokio::time::sleep(duration).await;
+}
+
+/// Resolve after `delay`, or never without one.
+async fn wake_after(delay: Option<Duration>) {
+    match delay {
+        Some(delay) => sleep(delay).await,
+        None => future::pending().await,
+    }
+}
+
+/// Decode an updates-by-range response: one update per period, in order,
+/// within the `count` periods from `start_period`, each encoded for the
+/// fork with `fork_digest`.
+fn decode_updates_by_range(
+    chunks: &[ResponseChunk],
+    fork_digest: [u8; 4],
+    start_period: u64,
+    count: u64,
+) -> Result<Vec<DecodedUpdate>, String> {
+    let periods = start_period..start_period.saturating_add(count);
+    let mut updates: Vec<DecodedUpdate> = Vec::with_capacity(chunks.len());
+    for chunk in chunks {
+        let decoded = decode_update(chunk.ssz_for(fork_digest)?).map_err(|e| e.to_string())?;
+        let period = decoded.update.attested_header.slot / SLOTS_PER_SYNC_COMMITTEE_PERIOD;
+        let previous = updates
+            .last()
+            .map(|last| last.update.attested_header.slot / SLOTS_PER_SYNC_COMMITTEE_PERIOD);
+        if !periods.contains(&period) || previous.is_some_and(|previous| period <= previous) {
+            return Err(format!("Update for period {} is out of order or outside {:?}", period, periods));
+        }
+        updates.push(decoded);
+    }
+    Ok(updates)
+}
+
+/// The event loop's state, owned by its task.
+struct EventLoop {
+    swarm: Swarm<LumenBehaviour>,
+    status: Arc<Mutex<ServiceStatus>>,
+    /// Requests awaiting a response. Ids are only unique per protocol.
+    requests: HashMap<(LightClientProtocol, OutboundRequestId), Reply>,
+    /// Portal queries awaiting an answer. Ids are only unique per network.
+    portal_requests: HashMap<(PortalNetwork, OutboundRequestId), PortalReply>,
+    /// The Status we send, once the consumer has set one.
+    local_status: Option<StatusMessage>,
+    /// Connected peers whose Status has not passed yet. Gossipsub has them
+    /// blacklisted, so ignores their messages.
+    unchecked: HashSet<PeerId>,
+    forks: ForkSchedule,
+    /// The epoch the fork topics were last updated for.
+    topics_epoch: u64,
+    /// The names of the topics to follow across forks.
+    topic_names: Vec<String>,
+    /// Where messages on the topics with these names go, instead of the
+    /// gossip channel.
+    topic_handlers: HashMap<String, mpsc::Sender<GossipMessage>>,
+    /// The topics subscribed to for the forks in effect.
+    fork_topics: Vec<String>,
+    discovery: Discovery,
+    peers: PeerManager,
+    verified: VerifiedMessages,
+    /// The DHT key of light client data providers on our network.
+    providers_key: kad::RecordKey,
+    /// The provider lookup in progress.
+    provider_lookup: Option<kad::QueryId>,
+    /// When the last provider lookup started, in Unix milliseconds.
+    last_provider_lookup_ms: Option<u64>,
+    bootnodes: BootnodeDialer,
+    relay: RelayFallback,
+    /// The `/p2p-circuit` listener holding our relay reservation.
+    relay_listener: Option<ListenerId>,
+    connections: HashMap<ConnectionId, Connection>,
+    reachability: ReachabilityProbe,
+    store: PeerStore,
+    storage: Option<Arc<dyn PeerStorage>>,
+    /// What was last saved to `storage`.
+    saved: Vec<u8>,
+    events: EventSink,
+    bandwidth: BandwidthMeter,
+    gossipsub_metrics: GossipsubMetrics,
+}
+
+/// An open connection.
+struct Connection {
+    peer: PeerId,
+    transport: TransportType,
+    /// The address we dialed, or None if the peer dialed us.
+    dialed: Option<Multiaddr>,
+}
+
+/// The event loop: runs until told to stop or the service handle is dropped.
+async fn run(
+    mut event_loop: EventLoop,
+    mut commands: mpsc::UnboundedReceiver<Command>,
+    mut messages: mpsc::Sender<GossipMessage>,
+) {
+    let mut fork_change = Box::pin(wake_after(event_loop.until_fork_change()).fuse());
+    let mut heartbeat = Box::pin(sleep(HEARTBEAT_INTERVAL).fuse());
+    let mut relay_tick = Box::pin(sleep(RELAY_TICK_INTERVAL).fuse());
+    let mut bootnode_dial = Box::pin(sleep(event_loop.until_bootnode_dial()).fuse());
+    loop {
+        let message = futures::select! {
+            command = commands.next() => {
+                if !event_loop.apply(command) {
+                    break;
+                }
+                None
+            }
+            event = event_loop.swarm.select_next_some() => event_loop.handle_event(event),
+            () = fork_change => {
+                if let Some(epoch) = event_loop.forks.epoch_at(now_ms()) {
+                    event_loop.update_fork_topics(epoch);
+                }
+                fork_change = Box::pin(wake_after(event_loop.until_fork_change()).fuse());
+                None
+            }
+            () = heartbeat => {
+                event_loop.heartbeat();
+                heartbeat = Box::pin(sleep(HEARTBEAT_INTERVAL).fuse());
+                None
+            }
+            () = relay_tick => {
+                let status = event_loop.status.clone();
+                event_loop.tick(&mut status.lock().expect("status lock"));
+                relay_tick = Box::pin(sleep(RELAY_TICK_INTERVAL).fuse());
+                None
+            }
+            () = bootnode_dial => {
+                let status = event_loop.status.clone();
+                event_loop.dial_bootnodes(&mut status.lock().expect("status lock"));
+                bootnode_dial = Box::pin(sleep(event_loop.until_bootnode_dial()).fuse());
+                None
+            }
+        };
+        if let Some(message) = message {
+            let mut handler = event_loop.topic_handler(&message.topic);
+            let messages = handler.as_mut().unwrap_or(&mut messages);
+            if !deliver(message, messages, &mut commands, &mut event_loop).await {
+                break;
+            }
+        }
+    }
+    event_loop.save_peers();
+    event_loop.status.lock().expect("status lock").running = false;
+}
+
+/// Hand `message` to the consumer, waiting while the channel is full and
+/// applying commands in the meantime. Returns false if told to stop first.
+/// A consumer that dropped its receiver loses the message.
+async fn deliver(
+    message: GossipMessage,
+    messages: &mut mpsc::Sender<GossipMessage>,
+    commands: &mut mpsc::UnboundedReceiver<Command>,
+    event_loop: &mut EventLoop,
+) -> bool {
+    // Waits for room, not for the consumer to take the message, as
+    // `SinkExt::send` would: its flush only finishes once the buffer drains.
+    let mut ready = future::poll_fn(|cx| messages.poll_ready(cx)).fuse();
+    let ready = loop {
+        futures::select! {
+            ready = ready => break ready,
+            command = commands.next() => {
+                if !event_loop.apply(command) {
+                    return false;
+                }
+            }
+        }
+    };
+    if ready.is_ok() {
+        let _ = messages.start_send(message);
+    }
+    true
+}
+
+impl EventLoop {
+    fn new(
+        swarm: Swarm<LumenBehaviour>,
+        status: Arc<Mutex<ServiceStatus>>,
+        forks: ForkSchedule,
+        discovery: Discovery,
+    ) -> Self {
+        let local = *swarm.local_peer_id();
+        Self {
+            swarm,
+            status,
+            requests: HashMap::new(),
+            portal_requests: HashMap::new(),
+            local_status: None,
+            unchecked: HashSet::new(),
+            providers_key: light_client_providers_key(&forks.genesis_validators_root),
+            forks,
+            topics_epoch: 0,
+            topic_names: TOPIC_NAMES.iter().map(ToString::to_string).collect(),
+            topic_handlers: HashMap::new(),
+            fork_topics: Vec::new(),
+            discovery,
+            peers: PeerManager::new(BootstrapConfig::default().min_peers, BootstrapConfig::default().max_peers),
+            provider_lookup: None,
+            last_provider_lookup_ms: None,
+            verified: VerifiedMessages::new(),
+            bootnodes: BootnodeDialer::new(Vec::new(), 0),
+            relay: RelayFallback::new(&BootstrapConfig::default(), RelayUpgradeStrategy::default(), false, &local),
+            relay_listener: None,
+            connections: HashMap::new(),
+            reachability: ReachabilityProbe::new(),
+            store: PeerStore::new(),
+            storage: None,
+            saved: Vec::new(),
+            events: EventSink::default(),
+            bandwidth: BandwidthMeter::new(),
+            gossipsub_metrics: GossipsubMetrics::new(),
+        }
+    }
+
+    /// Subscribe to the named topics of the forks in effect at `epoch`,
+    /// drop those of forks no longer in effect or names no longer followed,
+    /// and take the fork digest into our Status.
+    fn update_fork_topics(&mut self, epoch: u64) {
+        self.topics_epoch = epoch;
+        let digests = self.forks.topic_digests_at(epoch);
+        let topics: Vec<String> = digests
+            .into_iter()
+            .flat_map(|digest| self.topic_names.iter().map(move |name| gossip_topic(digest, name)))
+            .collect();
+        let status = self.status.clone();
+        let mut status = status.lock().expect("status lock");
+        for topic in self.fork_topics.iter().filter(|topic| !topics.contains(topic)) {
+            unsubscribe(&mut self.swarm, &self.events, &mut status, topic);
+        }
+        for topic in &topics {
+            subscribe(&mut self.swarm, &self.events, &mut status, topic);
+            if topic_name(topic).is_some_and(|name| TOPIC_NAMES.contains(&name)) {
+                let params = light_client_topic_score_params();
+                let topic = gossipsub::IdentTopic::new(topic);
+                let _ = self.swarm.behaviour_mut().gossipsub.set_topic_params(topic, params);
+            }
+        }
+        self.fork_topics = topics;
+
+        status.fork_digest = self.forks.fork_digest_at(epoch);
+        if let Some(ours) = &mut self.local_status {
+            ours.fork_digest = status.fork_digest;
+        }
+    }
+
+    /// Where to deliver a message on `topic`, if not to the gossip channel.
+    fn topic_handler(&self, topic: &str) -> Option<mpsc::Sender<GossipMessage>> {
+        topic_name(topic).and_then(|name| self.topic_handlers.get(name)).cloned()
+    }
+
+    /// How long until the fork topics next change, if the schedule has a
+    /// change ahead and knows the time.
+    fn until_fork_change(&self) -> Option<Duration> {
+        let now = now_ms();
+        let epoch = self.forks.next_topic_change(self.forks.epoch_at(now)?)?;
+        Some(Duration::from_millis(self.forks.epoch_start_ms(epoch).saturating_sub(now)))
+    }
+
+    /// Carry out `command`. Returns false if the loop should stop.
+    fn apply(&mut self, command: Option<Command>) -> bool {
+        match command {
+            Some(Command::Dial(address)) => dial(&mut self.swarm, &self.status, &self.events, address),
+            Some(Command::Subscribe(topic)) => {
+                subscribe(&mut self.swarm, &self.events, &mut self.status.lock().expect("status lock"), &topic)
+            }
+            Some(Command::SubscribeTopic { name, handler }) => {
+                if !self.topic_names.contains(&name) {
+                    self.topic_names.push(name.clone());
+                }
+                self.topic_handlers.insert(name, handler);
+                self.update_fork_topics(self.topics_epoch);
+            }
+            Some(Command::UnsubscribeTopic(name)) => {
+                self.topic_names.retain(|followed| *followed != name);
+                self.topic_handlers.remove(&name);
+                self.update_fork_topics(self.topics_epoch);
+            }
+            Some(Command::Request { peer, request, reply }) => {
+                let protocol = request.protocol();
+                let id = self.swarm.behaviour_mut().send_light_client_request(&peer, request);
+                self.requests.insert((protocol, id), reply);
+            }
+            Some(Command::RequestPeers(reply)) => {
+                let _ = reply.send(self.request_peers());
+            }
+            Some(Command::PortalRequest { network, peer, addresses, request, reply }) => {
+                for address in addresses {
+                    self.swarm.add_peer_address(peer, address);
+                }
+                let id = self.swarm.behaviour_mut().send_portal_request(network, &peer, request);
+                self.portal_requests.insert((network, id), reply);
+            }
+            Some(Command::PortalPeers { network, reply }) => {
+                let connected = self.swarm.connected_peers();
+                let serving = connected.filter(|peer| self.peers.capabilities(peer).is_some_and(|c| c.portal(network)));
+                let _ = reply.send(serving.copied().collect());
+            }
+            Some(Command::SetStatus(mut status)) => {
+                status.fork_digest = self.status.lock().expect("status lock").fork_digest;
+                if self.local_status.replace(status).is_none() {
+                    for peer in &self.unchecked {
+                        self.swarm.behaviour_mut().status.send_request(peer, status);
+                    }
+                }
+            }
+            Some(Command::ReportValidation { message_id, source, validation }) => {
+                self.report_validation(message_id, source, validation);
+            }
+            Some(Command::Discovered { source, records }) => match records {
+                Ok(records) => {
+                    let status = self.status.clone();
+                    self.discovered(records, &mut status.lock().expect("status lock"));
+                }
+                Err(e) => record_error(&self.status, &self.events, format!("Peer directory {}: {}", source, e)),
+            },
+            Some(Command::Stop) | None => return false,
+        }
+        true
+    }
+
+    /// Record what `event` changes, and return it as a message if it is gossip.
+    fn handle_event(&mut self, event: SwarmEvent<LumenBehaviourEvent>) -> Option<GossipMessage> {
+        let status = self.status.clone();
+        let mut status = status.lock().expect("status lock");
+        match &event {
+            SwarmEvent::ConnectionEstablished { peer_id, connection_id, endpoint, .. } => {
+                let transport = if endpoint.is_relayed() {
+                    TransportType::CircuitRelay
+                } else {
+                    TransportType::of_address(endpoint.get_remote_address())
+                };
+                let dialed = endpoint.is_dialer().then(|| endpoint.get_remote_address().clone());
+                if dialed.is_none() && transport.is_direct() {
+                    self.reachability.inbound();
+                }
+                self.connections.insert(*connection_id, Connection { peer: *peer_id, transport, dialed });
+            }
+            SwarmEvent::NewListenAddr { .. } | SwarmEvent::ExpiredListenAddr { .. } => self.update_listening(),
+            SwarmEvent::ConnectionClosed { connection_id, .. } => {
+                self.connections.remove(connection_id);
+            }
+            _ => {}
+        }
+        match event {
+            SwarmEvent::ConnectionEstablished { peer_id, connection_id, endpoint, num_established, .. } => {
+                if self.peers.is_banned(&peer_id, now_ms()) {
+                    let _ = self.swarm.disconnect_peer_id(peer_id);
+                    return None;
+                }
+                if endpoint.is_dialer() {
+                    self.bootnodes.connected(endpoint.get_remote_address(), &mut status.bootstrap);
+                    self.peers.connected(endpoint.get_remote_address());
+                    self.peers.keep(endpoint.get_remote_address().clone());
+                }
+                if num_established.get() == 1 {
+                    self.events.emit(P2pEvent::PeerConnected {
+                        peer_id: peer_id.to_string(),
+                        transport: self.connections[&connection_id].transport,
+                        inbound: !endpoint.is_dialer(),
+                    });
+                    self.swarm.behaviour_mut().gossipsub.blacklist_peer(&peer_id);
+                    self.unchecked.insert(peer_id);
+                    if let Some(ours) = self.local_status {
+                        self.swarm.behaviour_mut().status.send_request(&peer_id, ours);
+                    }
+                }
+                self.count_peers(&mut status);
+                self.prune();
+                self.tick(&mut status);
+            }
+            SwarmEvent::ConnectionClosed { peer_id, num_established: 0, .. } => {
+                self.events.emit(P2pEvent::PeerDisconnected { peer_id: peer_id.to_string() });
+                self.unchecked.remove(&peer_id);
+                self.swarm.behaviour_mut().gossipsub.remove_blacklisted_peer(&peer_id);
+                self.discovery.forget(&peer_id);
+                self.peers.disconnected(&peer_id);
+                self.count_peers(&mut status);
+                self.dial_candidates(&mut status);
+                self.find_providers();
+                self.tick(&mut status);
+            }
+            SwarmEvent::OutgoingConnectionError { peer_id, error, .. } => {
+                status.bootstrap.connection_failures += 1;
+                let now = now_ms();
+                match &error {
+                    DialError::Transport(errors) => {
+                        for (address, error) in errors {
+                            self.peers.dial_failed(address, now);
+                            let reason = DialFailure::Unreachable { error: error.to_string() };
+                            self.bootnodes.failed(address, reason, now, &mut status.bootstrap);
+                        }
+                    }
+                    DialError::WrongPeerId { obtained, endpoint } => {
+                        self.peers.dial_failed(endpoint.get_remote_address(), now);
+                        let reason = DialFailure::WrongPeerId { obtained: obtained.to_string() };
+                        self.bootnodes.failed(endpoint.get_remote_address(), reason, now, &mut status.bootstrap);
+                    }
+                    _ => {}
+                }
+                let peer = peer_id.map_or_else(|| "unknown peer".to_string(), |peer| peer.to_string());
+                self.events.error(&mut status, format!("Dial to {} failed: {}", peer, error));
+                self.dial_candidates(&mut status);
+            }
+            SwarmEvent::Behaviour(LumenBehaviourEvent::Identify(identify::Event::Received { peer_id, info, .. })) => {
+                let exchanges = info.protocols.contains(&StreamProtocol::new(PEER_EXCHANGE_PROTOCOL));
+                let routes = info.protocols.contains(&StreamProtocol::new(LUMEN_KAD_PROTOCOL));
+                self.reachability.observed_at(info.observed_addr);
+                self.peers.identified(peer_id, &info.protocols);
+                // What the peer serves may make it the one to drop.
+                self.prune();
+                self.discovery.identified(peer_id, info.listen_addrs);
+                if exchanges {
+                    let request = PeerExchangeRequest { max_records: MAX_PEER_EXCHANGE_RECORDS };
+                    self.swarm.behaviour_mut().peer_exchange.send_request(&peer_id, request);
+                }
+                if let Some(kademlia) = self.swarm.behaviour_mut().kademlia.as_mut().filter(|_| routes) {
+                    for address in self.discovery.addresses(&peer_id) {
+                        kademlia.add_address(&peer_id, address.clone());
+                    }
+                }
+            }
+            SwarmEvent::Behaviour(LumenBehaviourEvent::Kademlia(kad::Event::RoutingUpdated { .. })) => {
+                self.find_providers();
+            }
+            SwarmEvent::Behaviour(LumenBehaviourEvent::Kademlia(kad::Event::OutboundQueryProgressed {
+                id,
+                result: kad::QueryResult::GetProviders(result),
+                step,
+                ..
+            })) if self.provider_lookup == Some(id) => {
+                if step.last {
+                    self.provider_lookup = None;
+                }
+                // Failures are timeouts, with whatever was found already
+                // reported.
+                if let Ok(kad::GetProvidersOk::FoundProviders { providers, .. }) = result {
+                    self.dial_providers(providers, &mut status);
+                }
+            }
+            SwarmEvent::Behaviour(LumenBehaviourEvent::PeerExchange(request_response::Event::Message {
+                peer,
+                message,
+            })) => match message {
+                request_response::Message::Request { request, channel, .. } => {
+                    let records = self.discovery.records_for(&peer, request.max_records);
+                    let _ = self.swarm.behaviour_mut().peer_exchange.send_response(channel, records);
+                }
+                request_response::Message::Response { response, .. } => self.discovered(response, &mut status),
+            },
+            SwarmEvent::ListenerError { error, .. } => {
+                self.events.error(&mut status, format!("Listener failed: {}", error));
+            }
+            SwarmEvent::Behaviour(LumenBehaviourEvent::RelayClient(relay::client::Event::ReservationReqAccepted {
+                relay_peer_id,
+                ..
+            })) => {
+                let events = self.relay.reservation_accepted(relay_peer_id, now_ms());
+                self.apply_relay(events, &mut status);
+            }
+            SwarmEvent::Behaviour(LumenBehaviourEvent::Ping(ping::Event { peer, result, .. })) => match result {
+                Ok(rtt) => {
+                    self.peers.score_mut(peer).record_latency(rtt);
+                    self.relay.relay_rtt(&peer, rtt);
+                }
+                Err(e) if self.relay.is_relay(&peer) => {
+                    self.events.error(&mut status, format!("Relay {} stopped answering: {}", peer, e));
+                    let events = self.relay.relay_failed(&peer, now_ms());
+                    self.apply_relay(events, &mut status);
+                }
+                Err(_) => {}
+            }
+            SwarmEvent::ListenerClosed { listener_id, reason, .. } if self.relay_listener == Some(listener_id) => {
+                self.relay_listener = None;
+                if let Err(e) = reason {
+                    self.events.error(&mut status, format!("Relay reservation failed: {}", e));
+                }
+                let events = self.relay.reservation_ended(now_ms());
+                self.apply_relay(events, &mut status);
+            }
+            SwarmEvent::Behaviour(LumenBehaviourEvent::Gossipsub(gossipsub::Event::Subscribed { peer_id, topic }))
+                if topic_name(topic.as_str()).is_some_and(|name| TOPIC_NAMES.contains(&name)) =>
+            {
+                self.peers.joined_light_client_topic(peer_id);
+            }
+            SwarmEvent::Behaviour(LumenBehaviourEvent::Gossipsub(gossipsub::Event::Message {
+                propagation_source,
+                message_id,
+                message,
+            })) => {
+                status.gossip.messages_received += 1;
+                match GossipMessageType::from_topic(message.topic.as_str()) {
+                    GossipMessageType::FinalityUpdate => status.gossip.finality_updates += 1,
+                    GossipMessageType::OptimisticUpdate => status.gossip.optimistic_updates += 1,
+                    GossipMessageType::BeaconBlock => status.gossip.beacon_blocks += 1,
+                    GossipMessageType::BlobSidecar => status.gossip.blob_sidecars += 1,
+                    GossipMessageType::Unknown(_) => {}
+                }
+                if let Some(validation) = self.verified.get(&message_id.0, now_ms()) {
+                    status.gossip.messages_duplicate += 1;
+                    let acceptance = acceptance(validation);
+                    let gossipsub = &mut self.swarm.behaviour_mut().gossipsub;
+                    let _ = gossipsub.report_message_validation_result(&message_id, &propagation_source, acceptance);
+                    return None;
+                }
+                let message = GossipMessage {
+                    topic: message.topic.into_string(),
+                    data: message.data,
+                    source_peer: Some(propagation_source.to_string()),
+                    message_id: message_id.0,
+                };
+                self.events.emit(P2pEvent::GossipReceived {
+                    topic: message.topic.clone(),
+                    message_id: hex::encode(&message.message_id),
+                    source_peer: message.source_peer.clone(),
+                });
+                return Some(message);
+            }
+            SwarmEvent::Behaviour(LumenBehaviourEvent::Status(request_response::Event::Message { peer, message })) => {
+                let theirs = match message {
+                    request_response::Message::Request { request, channel, .. } => {
+                        // Without a Status of our own the channel is dropped,
+                        // which the peer sees as a failed request.
+                        if let Some(ours) = self.local_status {
+                            let _ = self.swarm.behaviour_mut().status.send_response(channel, ours);
+                        }
+                        request
+                    }
+                    request_response::Message::Response { response, .. } => response,
+                };
+                self.check_peer(peer, &theirs, &mut status);
+            }
+            // A peer that does not answer, like a relay, stays connected but
+            // unchecked, so its gossip is still ignored.
+            SwarmEvent::Behaviour(LumenBehaviourEvent::Status(_)) => {}
+            SwarmEvent::Behaviour(LumenBehaviourEvent::PeerRpc(request_response::Event::Message {
+                message: request_response::Message::Request { request, channel, .. },
+                ..
+            })) => {
+                let _ = self.swarm.behaviour_mut().peer_rpc.send_response(channel, request.response());
+            }
+            SwarmEvent::Behaviour(LumenBehaviourEvent::LightClientBootstrap(event)) => {
+                answer(&mut self.requests, LightClientProtocol::Bootstrap, event);
+            }
+            SwarmEvent::Behaviour(LumenBehaviourEvent::LightClientUpdatesByRange(event)) => {
+                answer(&mut self.requests, LightClientProtocol::UpdatesByRange, event);
+            }
+            SwarmEvent::Behaviour(LumenBehaviourEvent::LightClientFinalityUpdate(event)) => {
+                answer(&mut self.requests, LightClientProtocol::FinalityUpdate, event);
+            }
+            SwarmEvent::Behaviour(LumenBehaviourEvent::LightClientOptimisticUpdate(event)) => {
+                answer(&mut self.requests, LightClientProtocol::OptimisticUpdate, event);
+            }
+            SwarmEvent::Behaviour(LumenBehaviourEvent::PortalHistory(event)) => {
+                answer_portal(&mut self.portal_requests, PortalNetwork::History, event);
+            }
+            SwarmEvent::Behaviour(LumenBehaviourEvent::PortalState(event)) => {
+                answer_portal(&mut self.portal_requests, PortalNetwork::State, event);
+            }
+            _ => {}
+        }
+        None
+    }
+
+    /// Accept `peer`'s gossip if its Status passes, else disconnect it.
+    /// Peers already accepted or gone are left as they are.
+    fn check_peer(&mut self, peer: PeerId, theirs: &StatusMessage, status: &mut ServiceStatus) {
+        let Some(ours) = &self.local_status else {
+            return;
+        };
+        if !self.unchecked.contains(&peer) {
+            return;
+        }
+        match check_status(ours, theirs) {
+            Ok(()) => {
+                self.unchecked.remove(&peer);
+                self.swarm.behaviour_mut().gossipsub.remove_blacklisted_peer(&peer);
+            }
+            Err(reason) => {
+                self.peers.ban(peer, now_ms());
+                let _ = self.swarm.disconnect_peer_id(peer);
+                self.events.error(status, format!("Disconnected {}: {}", peer, reason));
+            }
+        }
+        self.count_peers(status);
+    }
+
+    /// Queue the dialable peers of `records` and dial what the peer limit
+    /// allows.
+    fn discovered(&mut self, records: Vec<PeerRecord>, status: &mut ServiceStatus) {
+        let local = *self.swarm.local_peer_id();
+        status.discovered_peers += self.discovery.add(records, &local) as u64;
+        self.dial_candidates(status);
+    }
+
+    /// Pass a message's validation on to gossipsub, and score its `source`
+    /// by it. Ignored messages count for nothing.
+    fn report_validation(&mut self, message_id: Vec<u8>, source: PeerId, validation: GossipValidation) {
+        let id = gossipsub::MessageId(message_id);
+        let gossipsub = &mut self.swarm.behaviour_mut().gossipsub;
+        let _ = gossipsub.report_message_validation_result(&id, &source, acceptance(validation));
+        self.verified.insert(id.0, validation, now_ms());
+        if validation == GossipValidation::Ignore {
+            return;
+        }
+
+        let score = self.peers.score_mut(source);
+        score.updates_received += 1;
+        let valid = validation == GossipValidation::Accept;
+        if valid {
+            score.updates_valid += 1;
+        } else {
+            score.updates_invalid += 1;
+        }
+        let (application_score, disconnect) = (score.application_score(), score.should_disconnect());
+        self.swarm.behaviour_mut().gossipsub.set_application_score(&source, application_score);
+        let status = self.status.clone();
+        let mut status = status.lock().expect("status lock");
+        if valid {
+            status.gossip.messages_valid += 1;
+        } else {
+            status.gossip.messages_invalid += 1;
+        }
+        if disconnect {
+            self.peers.ban(source, now_ms());
+            let _ = self.swarm.disconnect_peer_id(source);
+        }
+    }
+
+    /// Prune and redial, as the peer manager says, and dial what the peer
+    /// limit allows.
+    fn heartbeat(&mut self) {
+        let status = self.status.clone();
+        let mut status = status.lock().expect("status lock");
+        let now = now_ms();
+        self.peers.expire(now);
+        self.prune();
+        self.redial(&mut status);
+        self.dial_candidates(&mut status);
+        self.find_providers();
+        self.remember_peers(&mut status);
+        self.save_peers();
+    }
+
+    /// Take the peers `storage` has, seed their scores, and dial the best
+    /// of them while there is room.
+    fn load_peers(&mut self, storage: Arc<dyn PeerStorage>) {
+        let status = self.status.clone();
+        let mut status = status.lock().expect("status lock");
+        if let Some(bytes) = storage.load() {
+            match PeerStore::from_bytes(&bytes) {
+                Ok(store) => {
+                    self.store = store;
+                    self.saved = bytes;
+                }
+                Err(e) => self.events.error(&mut status, e),
+            }
+        }
+        self.storage = Some(storage);
+        self.store.expire(now_ms());
+        status.stored_peers = self.store.len();
+
+        let mut busy = self.busy();
+        for (peer, addrs, score) in self.store.dial_order() {
+            *self.peers.score_mut(peer) = score.clone();
+            if busy >= self.dial_target() || self.dial_slots() == 0 {
+                continue;
+            }
+            for address in &addrs {
+                self.peers.keep(address.clone());
+            }
+            match self.swarm.dial(DialOpts::peer_id(peer).addresses(addrs).build()) {
+                Ok(()) => {
+                    busy += 1;
+                    self.peers.dial_started();
+                }
+                Err(e) => self.events.error(&mut status, format!("Dial to {} failed: {}", peer, e)),
+            }
+        }
+    }
+
+    /// Remember the peers we dialed directly whose Status passed, unless
+    /// their score says to drop them, and forget banned ones.
+    fn remember_peers(&mut self, status: &mut ServiceStatus) {
+        let now = now_ms();
+        for connection in self.connections.values() {
+            let Some(address) = &connection.dialed else {
+                continue;
+            };
+            let score = self.peers.score(&connection.peer).cloned().unwrap_or_default();
+            let relay = self.relay.is_relay(&connection.peer);
+            if !connection.transport.is_direct() || relay || self.unchecked.contains(&connection.peer) {
+                continue;
+            }
+            if score.should_disconnect() || self.peers.is_banned(&connection.peer, now) {
+                continue;
+            }
+            self.store.record(StoredPeer {
+                peer_id: connection.peer.to_string(),
+                addrs: vec![address.to_string()],
+                transport: connection.transport,
+                score,
+                last_seen_ms: now,
+            });
+        }
+        let banned: Vec<PeerId> = self
+            .store
+            .dial_order()
+            .into_iter()
+            .map(|(peer, _, _)| peer)
+            .filter(|peer| self.peers.is_banned(peer, now))
+            .collect();
+        for peer in banned {
+            self.store.remove(&peer);
+        }
+        self.store.expire(now);
+        status.stored_peers = self.store.len();
+    }
+
+    /// Save the peer store, if there is storage and it changed.
+    fn save_peers(&mut self) {
+        let Some(storage) = &self.storage else {
+            return;
+        };
+        let bytes = self.store.to_bytes();
+        if bytes != self.saved {
+            storage.save(&bytes);
+            self.saved = bytes;
+        }
+    }
+
+    /// Redial known peers below `min_peers`, as the peer manager says.
+    fn redial(&mut self, status: &mut ServiceStatus) {
+        let connected: HashSet<PeerId> = self.swarm.connected_peers().copied().collect();
+        for address in self.peers.to_redial(&connected, self.busy(), now_ms()) {
+            if self.dial_slots() == 0 {
+                break;
+            }
+            match self.swarm.dial(address.clone()) {
+                Ok(()) => self.peers.dial_started(),
+                Err(e) => self.events.error(status, format!("Dial to {} failed: {}", address, e)),
+            }
+        }
+    }
+
+    /// Peers connected without a relay, relays aside, by the transport of
+    /// their first such connection.
+    fn direct_peers(&self) -> DirectPeers {
+        let mut transports: HashMap<PeerId, TransportType> = HashMap::new();
+        for connection in self.connections.values() {
+            if connection.transport.is_direct() && !self.relay.is_relay(&connection.peer) {
+                transports.entry(connection.peer).or_insert(connection.transport);
+            }
+        }
+        let mut direct = DirectPeers::default();
+        for transport in transports.values() {
+            match transport {
+                TransportType::WebTransport => direct.webtransport += 1,
+                TransportType::WebRTC => direct.webrtc += 1,
+                TransportType::Tcp | TransportType::Quic => direct.native += 1,
+                _ => direct.websocket += 1,
+            }
+        }
+        direct
+    }
+
+    /// Move the relay fallback on with the direct peers we have, and the
+    /// reachability probe, and refresh the transport stats.
+    fn tick(&mut self, status: &mut ServiceStatus) {
+        let now = now_ms();
+        self.reachability.tick(now);
+        status.transport = self.transport_stats();
+        status.gossip.mesh = self.mesh_stats();
+        let direct = self.direct_peers();
+        status.bootstrap.peers_connected = direct.total();
+        status.bootstrap.peers_discovered = status.discovered_peers as usize;
+        status.relays = self.relay.health();
+        let events = self.relay.tick(now, direct);
+        self.apply_relay(events, status);
+    }
+
+    /// The mesh of each topic subscribed to, with gossipsub's counts.
+    fn mesh_stats(&self) -> BTreeMap<String, TopicMeshStats> {
+        let gossipsub = &self.swarm.behaviour().gossipsub;
+        let mut counters = self.gossipsub_metrics.topic_counters();
+        let mut topic_peers: HashMap<&TopicHash, usize> = HashMap::new();
+        for topic in gossipsub.all_peers().flat_map(|(_, topics)| topics) {
+            *topic_peers.entry(topic).or_default() += 1;
+        }
+        gossipsub
+            .topics()
+            .map(|topic| {
+                let stats = TopicMeshStats {
+                    mesh_peers: gossipsub.mesh_peers(topic).count(),
+                    topic_peers: topic_peers.get(topic).copied().unwrap_or(0),
+                    ..counters.remove(topic.as_str()).unwrap_or_default()
+                };
+                (topic.to_string(), stats)
+            })
+            .collect()
+    }
+
+    /// Tell the reachability probe how many direct listen addresses we have.
+    fn update_listening(&mut self) {
+        let direct = self.swarm.listeners().filter(|address| !address.iter().any(|p| p == Protocol::P2pCircuit));
+        self.reachability.listening(direct.count(), now_ms());
+    }
+
+    fn transport_stats(&self) -> TransportStats {
+        let mut connections: Vec<(TransportType, usize)> = Vec::new();
+        for Connection { transport, .. } in self.connections.values() {
+            match connections.iter_mut().find(|(counted, _)| counted == transport) {
+                Some((_, count)) => *count += 1,
+                None => connections.push((*transport, 1)),
+            }
+        }
+        let direct = |transport: &TransportType| transport.is_direct();
+        let bandwidth = self.bandwidth.sample();
+        TransportStats {
+            has_direct_connection: connections.iter().any(|(transport, _)| direct(transport)),
+            using_relay: connections.iter().any(|(transport, _)| !direct(transport)) || self.relay_listener.is_some(),
+            connections,
+            total_peers: self.swarm.connected_peers().count(),
+            inbound_connections: self
+                .connections
+                .values()
+                .filter(|connection| connection.dialed.is_none() && connection.transport.is_direct())
+                .count(),
+            reachability: self.reachability.reachability(),
+            reachability_confidence: self.reachability.confidence(),
+            observed_addresses: self.reachability.observed().map(ToString::to_string).collect(),
+            bandwidth: bandwidth.total,
+            bandwidth_by_transport: bandwidth.by_transport,
+            bandwidth_by_peer: bandwidth.by_peer,
+        }
+    }
+
+    /// How many connections to dial up to: all of `max_peers` while peers
+    /// cannot dial us, and a quarter fewer once they can, to leave them room.
+    fn dial_target(&self) -> usize {
+        let max_peers = self.peers.max_peers();
+        if self.reachability.reachability().accepts_inbound() {
+            max_peers - max_peers / 4
+        } else {
+            max_peers
+        }
+    }
+
+    /// Carry out what the relay fallback asks, and record what it reports.
+    fn apply_relay(&mut self, events: Vec<RelayEvent>, status: &mut ServiceStatus) {
+        for event in events {
+            match event {
+                RelayEvent::Reserve(relay) => match self.swarm.listen_on(relay.clone().with(Protocol::P2pCircuit)) {
+                    Ok(listener) => self.relay_listener = Some(listener),
+                    Err(e) => {
+                        self.events.error(status, format!("Relay reservation on {} failed: {}", relay, e));
+                        let events = self.relay.reservation_ended(now_ms());
+                        self.apply_relay(events, status);
+                    }
+                },
+                RelayEvent::Release { relay } => {
+                    if let Some(listener) = self.relay_listener.take() {
+                        self.swarm.remove_listener(listener);
+                    }
+                    let _ = self.swarm.disconnect_peer_id(relay);
+                }
+                RelayEvent::Discover => {
+                    self.redial(status);
+                    self.dial_candidates(status);
+                    self.find_providers();
+                    if let Some(relay) = self.relay.relay_peer() {
+                        let request = PeerExchangeRequest { max_records: MAX_PEER_EXCHANGE_RECORDS };
+                        self.swarm.behaviour_mut().peer_exchange.send_request(&relay, request);
+                    }
+                }
+                RelayEvent::ModeChanged(mode) => {
+                    status.bootstrap.using_relay = mode.is_relayed();
+                    status.connection_mode = mode.clone();
+                    self.events.emit(P2pEvent::ModeChanged { mode });
+                }
+                RelayEvent::PhaseChanged(phase) => {
+                    status.bootstrap.phase = phase.clone();
+                    self.events.emit(P2pEvent::BootstrapPhaseChanged { phase });
+                }
+                RelayEvent::RelayedTooLong { duration_ms } => {
+                    let seconds = duration_ms / 1000;
+                    self.events.error(status, format!("Still relayed after {} s, with too few direct peers", seconds));
+                }
+            }
+        }
+    }
+
+    /// Disconnect the peers the peer manager prunes.
+    fn prune(&mut self) {
+        let connected: Vec<PeerId> = self.swarm.connected_peers().copied().collect();
+        for peer in self.peers.prune(&connected, now_ms()) {
+            let _ = self.swarm.disconnect_peer_id(peer);
+        }
+    }
+
+    /// Dial the bootnodes whose turn has come while there are fewer than
+    /// `min_peers` connections, skipping those connected or banned. A new
+    /// bootnode round, or a check that finds enough peers, starts a new
+    /// dial round.
+    fn dial_bootnodes(&mut self, status: &mut ServiceStatus) {
+        let now = now_ms();
+        let short = self.swarm.connected_peers().count() < self.peers.min_peers();
+        if !short || self.bootnodes.round_due(now, short) {
+            self.peers.start_dial_round();
+        }
+        for address in self.bootnodes.due(now, short, self.dial_slots(), &mut status.bootstrap) {
+            let skip = peer_of(&address).is_some_and(|peer| {
+                self.swarm.is_connected(&peer) || self.peers.is_banned(&peer, now)
+            });
+            if skip {
+                continue;
+            }
+            match self.swarm.dial(address.clone()) {
+                Ok(()) => self.peers.dial_started(),
+                Err(e) => self.events.error(status, format!("Dial to {} failed: {}", address, e)),
+            }
+        }
+    }
+
+    /// How long until the bootnode dialer next has something to do, or
+    /// until it is worth checking whether peers ran short again.
+    fn until_bootnode_dial(&self) -> Duration {
+        let at = self.bootnodes.next_wake_ms();
+        at.map_or(HEARTBEAT_INTERVAL, |at| Duration::from_millis(at.saturating_sub(now_ms()))).min(HEARTBEAT_INTERVAL)
+    }
+
+    /// Connections open or being dialed.
+    fn busy(&self) -> usize {
+        self.swarm.connected_peers().count() + self.pending_dials()
+    }
+
+    fn pending_dials(&self) -> usize {
+        self.swarm.network_info().connection_counters().num_pending_outgoing() as usize
+    }
+
+    /// How many dials the peer manager's limits let start now.
+    fn dial_slots(&self) -> usize {
+        self.peers.dial_slots(self.pending_dials())
+    }
+
+    /// Dial queued candidates until [`Self::dial_target`] connections are
+    /// open or being dialed, or the dial limits are reached. Banned
+    /// candidates, and addresses backing off, are skipped.
+    fn dial_candidates(&mut self, status: &mut ServiceStatus) {
+        let now = now_ms();
+        let mut busy = self.busy();
+        while busy < self.dial_target() && self.dial_slots() > 0 {
+            let Some((peer, mut addrs)) = self.discovery.next_candidate() else {
+                break;
+            };
+            addrs.retain(|address| self.peers.can_dial(address, now));
+            if self.swarm.is_connected(&peer) || self.peers.is_banned(&peer, now) || addrs.is_empty() {
+                continue;
+            }
+            match self.swarm.dial(DialOpts::peer_id(peer).addresses(addrs).build()) {
+                Ok(()) => {
+                    busy += 1;
+                    self.peers.dial_started();
+                }
+                Err(e) => self.events.error(status, format!("Dial to {} failed: {}", peer, e)),
+            }
+        }
+    }
+
+    /// Start looking up light client data providers, unless Kademlia is
+    /// off, a lookup is in progress, or the last one was too recent.
+    fn find_providers(&mut self) {
+        let now = now_ms();
+        let interval = PROVIDER_LOOKUP_INTERVAL.as_millis() as u64;
+        if self.provider_lookup.is_some() || self.last_provider_lookup_ms.is_some_and(|last| now < last + interval) {
+            return;
+        }
+        if let Some(kademlia) = self.swarm.behaviour_mut().kademlia.as_mut() {
+            self.provider_lookup = Some(kademlia.get_providers(self.providers_key.clone()));
+            self.last_provider_lookup_ms = Some(now);
+        }
+    }
+
+    /// Dial the `providers` a lookup found while there is room and the dial
+    /// limits allow. They are dialed at once, by peer id alone: Kademlia
+    /// knows their addresses only while the lookup runs.
+    fn dial_providers(&mut self, providers: HashSet<PeerId>, status: &mut ServiceStatus) {
+        let local = *self.swarm.local_peer_id();
+        let now = now_ms();
+        let mut busy = self.busy();
+        for peer in providers {
+            if busy >= self.dial_target() || self.dial_slots() == 0 {
+                break;
+            }
+            if peer == local || self.swarm.is_connected(&peer) || self.peers.is_banned(&peer, now) {
+                continue;
+            }
+            status.discovered_peers += 1;
+            match self.swarm.dial(DialOpts::peer_id(peer).build()) {
+                Ok(()) => {
+                    busy += 1;
+                    self.peers.dial_started();
+                }
+                Err(e) => self.events.error(status, format!("Dial to {} failed: {}", peer, e)),
+            }
+        }
+    }
+
+    /// The connected peers whose Status passed, fastest first.
+    fn request_peers(&self) -> Vec<PeerId> {
+        let checked = self.swarm.connected_peers().filter(|peer| !self.unchecked.contains(peer));
+        self.peers.by_latency(checked.copied())
+    }
+
+    fn count_peers(&self, status: &mut ServiceStatus) {
+        status.connected_peers = self.swarm.connected_peers().count();
+        status.accepted_peers = status.connected_peers.saturating_sub(self.unchecked.len());
+    }
+}
+
+fn acceptance(validation: GossipValidation) -> gossipsub::MessageAcceptance {
+    match validation {
+        GossipValidation::Accept => gossipsub::MessageAcceptance::Accept,
+        GossipValidation::Reject => gossipsub::MessageAcceptance::Reject,
+        GossipValidation::Ignore => gossipsub::MessageAcceptance::Ignore,
+    }
+}
+
+/// Pass the outcome of a request on `protocol` to whoever made it.
+fn answer(
+    requests: &mut HashMap<(LightClientProtocol, OutboundRequestId), Reply>,
+    protocol: LightClientProtocol,
+    event: request_response::Event<LightClientRequest, LightClientResponse>,
+) {
+    if let Some((request_id, result)) = outcome(protocol.id(), event) {
+        if let Some(reply) = requests.remove(&(protocol, request_id)) {
+            let _ = reply.send(result);
+        }
+    }
+}
+
+/// Pass the outcome of a Portal query on `network` to whoever made it.
+fn answer_portal(
+    requests: &mut HashMap<(PortalNetwork, OutboundRequestId), PortalReply>,
+    network: PortalNetwork,
+    event: request_response::Event<FindContent, ContentResponse>,
+) {
+    if let Some((request_id, result)) = outcome(network.id(), event) {
+        if let Some(reply) = requests.remove(&(network, request_id)) {
+            let _ = reply.send(result);
+        }
+    }
+}
+
+/// The request a response or failure on `protocol` answers, and what it
+/// came to.
+fn outcome<Request, Response>(
+    protocol: &str,
+    event: request_response::Event<Request, Response>,
+) -> Option<(OutboundRequestId, Result<Response, P2pError>)> {
+    match event {
+        request_response::Event::Message {
+            message: request_response::Message::Response { request_id, response },
+            ..
+        } => Some((request_id, Ok(response))),
+        request_response::Event::OutboundFailure { peer, request_id, error } => {
+            let error = format!("{} to {}: {}", protocol, peer, error);
+            Some((request_id, Err(P2pError::Request(error))))
+        }
+        // The protocols are outbound only, so nothing else happens.
+        _ => None,
+    }
+}
+
+fn dial(swarm: &mut Swarm<LumenBehaviour>, status: &Mutex<ServiceStatus>, events: &EventSink, address: Multiaddr) {
+    if let Err(e) = swarm.dial(address.clone()) {
+        record_error(status, events, format!("Dial to {} failed: {}", address, e));
+    }
+}
+
+fn subscribe(swarm: &mut Swarm<LumenBehaviour>, events: &EventSink, status: &mut ServiceStatus, topic: &str) {
+    match swarm.behaviour_mut().gossipsub.subscribe(&gossipsub::IdentTopic::new(topic)) {
+        Ok(_) if !status.topics.iter().any(|t| t == topic) => status.topics.push(topic.to_string()),
+        Ok(_) => {}
+        Err(e) => events.error(status, format!("Subscribe to {} failed: {}", topic, e)),
+    }
+}
+
+fn unsubscribe(swarm: &mut Swarm<LumenBehaviour>, events: &EventSink, status: &mut ServiceStatus, topic: &str) {
+    match swarm.behaviour_mut().gossipsub.unsubscribe(&gossipsub::IdentTopic::new(topic)) {
+        Ok(_) => status.topics.retain(|t| t != topic),
+        Err(e) => events.error(status, format!("Unsubscribe from {} failed: {}", topic, e)),
+    }
+}
+
+fn record_error(status: &Mutex<ServiceStatus>, events: &EventSink, error: String) {
+    events.error(&mut status.lock().expect("status lock"), error);
+}
+
+/// The subscribers to `LumenP2pService::events`, shared by the service
+/// handle and its event loop.
+#[derive(Clone, Default)]
+struct EventSink(Arc<Mutex<Vec<mpsc::Sender<P2pEvent>>>>);
+
+impl EventSink {
+    fn subscribe(&self) -> mpsc::Receiver<P2pEvent> {
+        let (sender, receiver) = mpsc::channel(EVENT_CHANNEL_CAPACITY);
+        self.0.lock().expect("events lock").push(sender);
+        receiver
+    }
+
+    /// Send `event` to every subscriber with room for it, dropping those
+    /// that are gone.
+    fn emit(&self, event: P2pEvent) {
+        self.0.lock().expect("events lock").retain_mut(|sender| match sender.try_send(event.clone()) {
+            Ok(()) => true,
+            Err(e) => e.is_full(),
+        });
+    }
+
+    /// Record `message` as the last error and report it.
+    fn error(&self, status: &mut ServiceStatus, message: String) {
+        self.emit(P2pEvent::Error { message: message.clone() });
+        status.last_error = Some(message);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::beacon_gossip::{light_client_topics, LIGHT_CLIENT_FINALITY_UPDATE, LIGHT_CLIENT_OPTIMISTIC_UPDATE};
+    use crate::behaviour::PeerScore;
+    use crate::bootstrap::BootstrapPhase;
+    use crate::reachability::Reachability;
+    use lumen_core::consensus::block::{execution_body_root, execution_header_root};
+    use lumen_core::types::network::NetworkConfig;
+
+    /// Mainnet Electra.
+    const ELECTRA: [u8; 4] = [0xad, 0x53, 0x2c, 0xeb];
+
+    fn service() -> LumenP2pService {
+        let bootstrap = BootstrapConfig { bootnodes: Vec::new(), ..BootstrapConfig::default() };
+        let forks = ForkSchedule::new(&NetworkConfig::mainnet());
+        LumenP2pService::new(Keypair::generate_ed25519(), TransportConfig::browser(), bootstrap, forks)
+    }
+
+    fn event_loop(forks: ForkSchedule) -> (EventLoop, Arc<Mutex<ServiceStatus>>) {
+        let (bandwidth, mut metrics) = (BandwidthMeter::new(), GossipsubMetrics::new());
+        let keypair = Keypair::generate_ed25519();
+        let swarm = build_swarm(&keypair, &TransportConfig::browser(), false, &bandwidth, &mut metrics).unwrap();
+        let status = Arc::new(Mutex::new(ServiceStatus::default()));
+        let discovery = Discovery::new(TransportConfig::browser());
+        (EventLoop::new(swarm, status.clone(), forks, discovery), status)
+    }
+
+    /// Let the event loop handle what has been sent to it.
+    async fn settle() {
+        for _ in 0..10 {
+            tokio::task::yield_now().await;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_start_subscribe_stop() {
+        let mut service = service();
+        assert_eq!(service.dial("/memory/1"), Err(P2pError::NotRunning));
+
+        let _messages = service.start().unwrap();
+        assert!(service.is_running());
+        assert_eq!(service.start().err(), Some(P2pError::AlreadyRunning));
+        assert_eq!(service.status().topics, light_client_topics(ELECTRA));
+        assert_eq!(service.status().fork_digest, ELECTRA);
+        assert_eq!(service.status().bootstrap.phase, BootstrapPhase::ConnectingDirect);
+        assert_eq!(service.status().connection_mode, ConnectionMode::Bootstrapping);
+        assert_eq!(service.status().relays.len(), BootstrapConfig::default().relays.len());
+        // Nothing to listen on, so nothing for peers to dial.
+        assert_eq!(service.status().transport.reachability, Reachability::Private);
+
+        service.subscribe("/eth2/b5303f2a/beacon_block/ssz_snappy").unwrap();
+        settle().await;
+        assert_eq!(service.status().topics.len(), 3);
+        let _blob_sidecars = service.subscribe_blob_sidecars().unwrap();
+        settle().await;
+        assert_eq!(service.status().topics.len(), 3 + BLOB_SIDECAR_SUBNET_COUNT as usize);
+        assert!(service.status().topics.contains(&gossip_topic(ELECTRA, "blob_sidecar_8")));
+        service.unsubscribe_blob_sidecars().unwrap();
+        settle().await;
+        assert_eq!(service.status().topics.len(), 3);
+
+        service.stop();
+        settle().await;
+        assert!(!service.is_running());
+        assert!(!service.status().running);
+        assert_eq!(service.subscribe("/eth2/b5303f2a/x"), Err(P2pError::NotRunning));
+        // A stopped service can be started again.
+        service.start().unwrap();
+        assert!(service.is_running());
+    }
+
+    #[tokio::test]
+    async fn test_dial_failures() {
+        let mut service = service();
+        let _messages = service.start().unwrap();
+        assert!(matches!(service.dial("not a multiaddr"), Err(P2pError::InvalidMultiaddr(_))));
+
+        // Natively there is no transport that takes this address: the dial is
+        // accepted, and its failure reported through the status.
+        service.dial("/dns4/relay.lumen.dev/tcp/443/wss").unwrap();
+        settle().await;
+        assert!(service.status().last_error.unwrap().contains("relay.lumen.dev"));
+    }
+
+    #[tokio::test]
+    async fn test_request_failures() {
+        let mut service = service();
+        let peer = PeerId::random();
+        assert_eq!(service.request_bootstrap(peer, [0; 32]).await.err(), Some(P2pError::NotRunning));
+        assert_eq!(service.request_peers().await, Err(P2pError::NotRunning));
+
+        // The peer is unknown and unreachable, so the request fails, and
+        // says which protocol and peer it was for.
+        let _messages = service.start().unwrap();
+        let Err(P2pError::Request(error)) = service.request_bootstrap(peer, [0; 32]).await else {
+            panic!("expected a request failure");
+        };
+        assert!(error.contains("light_client_bootstrap"));
+        assert!(error.contains(&peer.to_string()));
+        let Err(P2pError::Request(error)) = service.request_optimistic_update(peer).await else {
+            panic!("expected a request failure");
+        };
+        assert!(error.contains("light_client_optimistic_update"));
+        assert_eq!(service.request_peers().await, Ok(Vec::new()));
+    }
+
+    #[tokio::test]
+    async fn test_pings_feed_peer_latency() {
+        let (mut event_loop, _status) = event_loop(ForkSchedule::new(&NetworkConfig::mainnet()));
+        let peer = PeerId::random();
+        for rtt in [100, 200] {
+            let result = Ok(Duration::from_millis(rtt));
+            let ping = ping::Event { peer, connection: ConnectionId::new_unchecked(0), result };
+            event_loop.handle_event(SwarmEvent::Behaviour(LumenBehaviourEvent::Ping(ping)));
+        }
+        assert_eq!(event_loop.peers.score(&peer).unwrap().latency_ms(), Some(120.0));
+    }
+
+    #[tokio::test]
+    async fn test_status_check_gates_peers() {
+        let ours = StatusMessage::light_client(ELECTRA, [1; 32], 100);
+        assert_eq!(service().set_status(ours), Err(P2pError::NotRunning));
+
+        let (mut event_loop, status) = event_loop(ForkSchedule::new(&NetworkConfig::mainnet()));
+        event_loop.update_fork_topics(100);
+        let (good, bad) = (PeerId::random(), PeerId::random());
+        event_loop.unchecked.extend([good, bad]);
+
+        // With no Status of our own, nobody can be checked.
+        event_loop.check_peer(good, &ours, &mut status.lock().unwrap());
+        assert_eq!(event_loop.unchecked.len(), 2);
+
+        assert!(event_loop.apply(Some(Command::SetStatus(ours))));
+        event_loop.check_peer(good, &ours, &mut status.lock().unwrap());
+        assert_eq!(event_loop.unchecked, HashSet::from([bad]));
+
+        // A peer on another chain stays unchecked until its connection
+        // closes, and is banned.
+        let other_chain = StatusMessage { finalized_root: [2; 32], ..ours };
+        event_loop.check_peer(bad, &other_chain, &mut status.lock().unwrap());
+        assert!(event_loop.unchecked.contains(&bad));
+        assert!(event_loop.peers.is_banned(&bad, now_ms()));
+        let error = status.lock().unwrap().last_error.clone().unwrap();
+        assert!(error.starts_with(&format!("Disconnected {}", bad)));
+    }
+
+    #[test]
+    fn test_fork_topics_follow_the_schedule() {
+        let forks = ForkSchedule::new(&NetworkConfig::mainnet()).with_fork(1000, [0x06, 0, 0, 0]);
+        let next = forks.fork_digest_at(1000);
+        let (mut event_loop, status) = event_loop(forks);
+        event_loop.update_fork_topics(990);
+        assert!(event_loop.apply(Some(Command::SetStatus(StatusMessage::light_client([0; 4], [1; 32], 980)))));
+        assert_eq!(event_loop.local_status.unwrap().fork_digest, ELECTRA);
+
+        // Both forks' topics around the fork, then only the new one's.
+        event_loop.update_fork_topics(999);
+        assert_eq!(status.lock().unwrap().topics, [light_client_topics(ELECTRA), light_client_topics(next)].concat());
+        assert_eq!(status.lock().unwrap().fork_digest, ELECTRA);
+        event_loop.update_fork_topics(1002);
+        assert_eq!(status.lock().unwrap().topics, light_client_topics(next));
+        assert_eq!(status.lock().unwrap().fork_digest, next);
+        assert_eq!(event_loop.local_status.unwrap().fork_digest, next);
+    }
+
+    #[test]
+    fn test_named_topics_follow_forks_to_their_handler() {
+        let forks = ForkSchedule::new(&NetworkConfig::mainnet()).with_fork(1000, [0x06, 0, 0, 0]);
+        let next = forks.fork_digest_at(1000);
+        let (mut event_loop, status) = event_loop(forks);
+        event_loop.update_fork_topics(990);
+        let (handler, _blocks) = mpsc::channel(1);
+        event_loop.apply(Some(Command::SubscribeTopic { name: "beacon_block".to_string(), handler }));
+        event_loop.apply(Some(Command::UnsubscribeTopic(LIGHT_CLIENT_OPTIMISTIC_UPDATE.to_string())));
+        let finality = gossip_topic(ELECTRA, LIGHT_CLIENT_FINALITY_UPDATE);
+        let block = gossip_topic(ELECTRA, "beacon_block");
+        assert_eq!(status.lock().unwrap().topics, [finality.clone(), block.clone()]);
+        assert!(event_loop.topic_handler(&block).is_some());
+        assert!(event_loop.topic_handler(&finality).is_none());
+
+        event_loop.update_fork_topics(1002);
+        let topics = [gossip_topic(next, LIGHT_CLIENT_FINALITY_UPDATE), gossip_topic(next, "beacon_block")];
+        assert_eq!(status.lock().unwrap().topics, topics);
+        assert!(event_loop.topic_handler(&topics[1]).is_some());
+    }
+
+    #[tokio::test]
+    async fn test_discovered_peers_wait_for_room() {
+        let (mut event_loop, status) = event_loop(ForkSchedule::new(&NetworkConfig::mainnet()));
+        event_loop.peers = PeerManager::new(1, 1);
+        let records: Vec<PeerRecord> = ["a", "b"]
+            .iter()
+            .map(|host| PeerRecord {
+                peer_id: PeerId::random().to_string(),
+                addrs: vec![format!("/dns4/{}.example/tcp/443/wss", host)],
+            })
+            .collect();
+        let source = "https://peers.example".to_string();
+        assert!(event_loop.apply(Some(Command::Discovered { source: source.clone(), records: Ok(records) })));
+
+        // One is being dialed, the other waits for it to finish.
+        assert_eq!(status.lock().unwrap().discovered_peers, 2);
+        assert_eq!(event_loop.discovery.candidates(), 1);
+
+        assert!(event_loop.apply(Some(Command::Discovered { source, records: Err("HTTP 503".to_string()) })));
+        let error = status.lock().unwrap().last_error.clone();
+        assert_eq!(error.as_deref(), Some("Peer directory https://peers.example: HTTP 503"));
+    }
+
+    #[tokio::test]
+    async fn test_reachable_nodes_leave_room_to_be_dialed() {
+        let (mut event_loop, _) = event_loop(ForkSchedule::new(&NetworkConfig::mainnet()));
+        event_loop.peers = PeerManager::new(3, 8);
+        event_loop.update_listening();
+        assert_eq!(event_loop.dial_target(), 8);
+
+        event_loop.reachability.listening(1, now_ms());
+        event_loop.reachability.inbound();
+        assert_eq!(event_loop.dial_target(), 6);
+        let stats = event_loop.transport_stats();
+        assert_eq!((stats.reachability, stats.total_peers), (Reachability::Public, 0));
+    }
+
+    #[derive(Clone, Default)]
+    struct MemoryStorage(Arc<Mutex<Option<Vec<u8>>>>);
+
+    impl PeerStorage for MemoryStorage {
+        fn load(&self) -> Option<Vec<u8>> {
+            self.0.lock().unwrap().clone()
+        }
+
+        fn save(&self, bytes: &[u8]) {
+            *self.0.lock().unwrap() = Some(bytes.to_vec());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_good_peers_are_stored_and_dialed_first() {
+        let (mut event_loop, status) = event_loop(ForkSchedule::new(&NetworkConfig::mainnet()));
+        let address = |peer: &PeerId| format!("/dns4/peer.example/tcp/443/wss/p2p/{}", peer);
+        let known = PeerId::random();
+        let mut store = PeerStore::new();
+        store.record(StoredPeer {
+            peer_id: known.to_string(),
+            addrs: vec![address(&known)],
+            transport: TransportType::WebSocket,
+            score: PeerScore { updates_received: 10, updates_valid: 9, ..PeerScore::new() },
+            last_seen_ms: now_ms(),
+        });
+        let storage = MemoryStorage::default();
+        storage.save(&store.to_bytes());
+
+        event_loop.load_peers(Arc::new(storage.clone()));
+        assert_eq!(status.lock().unwrap().stored_peers, 1);
+        assert_eq!(event_loop.peers.score(&known).unwrap().updates_valid, 9);
+        assert_eq!(event_loop.busy(), 1);
+
+        // Peers we dialed are remembered once their Status passes.
+        let (good, unchecked) = (PeerId::random(), PeerId::random());
+        for (id, peer) in [good, unchecked].into_iter().enumerate() {
+            let dialed = Some(address(&peer).parse().unwrap());
+            let connection = Connection { peer, transport: TransportType::WebSocket, dialed };
+            event_loop.connections.insert(ConnectionId::new_unchecked(id), connection);
+        }
+        event_loop.unchecked.insert(unchecked);
+        event_loop.heartbeat();
+        let saved = PeerStore::from_bytes(&storage.load().unwrap()).unwrap();
+        let mut stored: Vec<String> = saved.peers().iter().map(|peer| peer.peer_id.clone()).collect();
+        stored.sort();
+        let mut expected = vec![known.to_string(), good.to_string()];
+        expected.sort();
+        assert_eq!(stored, expected);
+        assert_eq!(status.lock().unwrap().stored_peers, 2);
+
+        // Banned peers are forgotten.
+        event_loop.peers.ban(known, now_ms());
+        event_loop.heartbeat();
+        assert_eq!(PeerStore::from_bytes(&storage.load().unwrap()).unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_provider_lookups_are_throttled() {
+        let (mut event_loop, _) = event_loop(ForkSchedule::new(&NetworkConfig::mainnet()));
+        event_loop.find_providers();
+        assert_eq!(event_loop.provider_lookup, None);
+
+        let (bandwidth, mut metrics) = (BandwidthMeter::new(), GossipsubMetrics::new());
+        let keypair = Keypair::generate_ed25519();
+        let swarm = build_swarm(&keypair, &TransportConfig::browser(), true, &bandwidth, &mut metrics).unwrap();
+        event_loop.swarm = swarm;
+        event_loop.find_providers();
+        let first = event_loop.provider_lookup.unwrap();
+        event_loop.find_providers();
+        assert_eq!(event_loop.provider_lookup, Some(first));
+
+        // Finished, but too recently to look again.
+        event_loop.provider_lookup = None;
+        event_loop.find_providers();
+        assert_eq!(event_loop.provider_lookup, None);
+        event_loop.last_provider_lookup_ms = Some(0);
+        event_loop.find_providers();
+        assert!(event_loop.provider_lookup.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_validation_scores_the_source() {
+        let (mut event_loop, status) = event_loop(ForkSchedule::new(&NetworkConfig::mainnet()));
+        let (good, bad) = (PeerId::random(), PeerId::random());
+        for i in 0..10u8 {
+            event_loop.report_validation(vec![i], good, GossipValidation::Accept);
+            event_loop.report_validation(vec![i], bad, GossipValidation::Reject);
+            event_loop.report_validation(vec![i], bad, GossipValidation::Ignore);
+        }
+        assert_eq!(event_loop.peers.score(&good).unwrap().updates_valid, 10);
+        assert_eq!(event_loop.peers.score(&bad).unwrap().updates_received, 10);
+        // The last outcome reported for each message is remembered.
+        assert_eq!(event_loop.verified.get(&[3], now_ms()), Some(GossipValidation::Reject));
+
+        // The bad peer was not connected, but is banned all the same.
+        assert!(event_loop.peers.is_banned(&bad, now_ms()));
+        assert!(!event_loop.peers.is_banned(&good, now_ms()));
+        let gossip = status.lock().unwrap().gossip.clone();
+        assert_eq!((gossip.messages_valid, gossip.messages_invalid), (10, 10));
+
+        let message = GossipMessage { source_peer: None, ..message(1) };
+        assert_eq!(service().report_validation(&message, GossipValidation::Accept), Ok(()));
+    }
+
+    #[tokio::test]
+    async fn test_relay_events_drive_the_swarm() {
+        let (mut event_loop, status) = event_loop(ForkSchedule::new(&NetworkConfig::mainnet()));
+        let relay: Multiaddr = BootstrapConfig::default().relays[0].parse().unwrap();
+        let Some(Protocol::P2p(relay_peer)) = relay.iter().last() else {
+            panic!("relays name their peer");
+        };
+        event_loop.apply_relay(vec![RelayEvent::Reserve(relay)], &mut status.lock().unwrap());
+        assert!(event_loop.relay_listener.is_some());
+
+        let mode = ConnectionMode::ViaRelay { relay_peer: relay_peer.to_string(), direct_peers: 0 };
+        let events = vec![RelayEvent::ModeChanged(mode.clone()), RelayEvent::Release { relay: relay_peer }];
+        event_loop.apply_relay(events, &mut status.lock().unwrap());
+        assert_eq!(event_loop.relay_listener, None);
+        let status = status.lock().unwrap();
+        assert_eq!(status.connection_mode, mode);
+        assert!(status.bootstrap.using_relay);
+    }
+
+    #[tokio::test]
+    async fn test_events_reach_every_subscriber() {
+        let service = service();
+        let (mut first, mut second) = (service.events(), service.events());
+        drop(service.events());
+        let (mut event_loop, status) = event_loop(ForkSchedule::new(&NetworkConfig::mainnet()));
+        event_loop.events = service.events.clone();
+
+        let mode = ConnectionMode::DirectWebSocket { peer_count: 1 };
+        let events = vec![RelayEvent::PhaseChanged(BootstrapPhase::Complete), RelayEvent::ModeChanged(mode.clone())];
+        event_loop.apply_relay(events, &mut status.lock().unwrap());
+        let records = Err("unreachable".to_string());
+        event_loop.apply(Some(Command::Discovered { source: "https://peers.example".to_string(), records }));
+        let error = status.lock().unwrap().last_error.clone().unwrap();
+
+        let expected = vec![
+            P2pEvent::BootstrapPhaseChanged { phase: BootstrapPhase::Complete },
+            P2pEvent::ModeChanged { mode },
+            P2pEvent::Error { message: error },
+        ];
+        for events in [&mut first, &mut second] {
+            let received: Vec<P2pEvent> = std::iter::from_fn(|| events.try_recv().ok()).collect();
+            assert_eq!(received, expected);
+        }
+        // The dropped subscriber is gone.
+        assert_eq!(service.events.0.lock().unwrap().len(), 2);
+    }
+
+    /// An Electra `LightClientUpdate` attesting to `slot`, as a response chunk.
+    fn update_chunk(slot: u64) -> ResponseChunk {
+        let mut execution = vec![0u8; 584];
+        execution[436..440].copy_from_slice(&584u32.to_le_bytes()); // extra_data offset
+        let body_root = execution_body_root(&execution_header_root(&execution).unwrap(), &[[0u8; 32]; 4]);
+        let mut header = slot.to_le_bytes().to_vec();
+        header.extend_from_slice(&[0u8; 8 + 32 * 2]);
+        header.extend_from_slice(&body_root);
+        header.extend_from_slice(&244u32.to_le_bytes()); // execution offset
+        header.extend_from_slice(&[0u8; 32 * 4]);
+        header.extend_from_slice(&execution);
+
+        let fixed_len = 4 + 513 * 48 + 32 * 6 + 4 + 32 * 7 + 160 + 8;
+        let mut ssz = (fixed_len as u32).to_le_bytes().to_vec();
+        ssz.extend_from_slice(&[0x40; 513 * 48 + 32 * 6]); // next sync committee and branch
+        ssz.extend_from_slice(&((fixed_len + header.len()) as u32).to_le_bytes());
+        ssz.extend_from_slice(&[0u8; 32 * 7 + 160]);
+        ssz.extend_from_slice(&(slot + 1).to_le_bytes());
+        ssz.extend_from_slice(&header);
+        ssz.extend_from_slice(&header);
+        ResponseChunk { fork_digest: [0; 4], ssz }
+    }
+
+    #[test]
+    fn test_updates_by_range_checks_periods() {
+        let period = |p: u64| update_chunk(p * SLOTS_PER_SYNC_COMMITTEE_PERIOD + 100);
+        let decode = |chunks: &[ResponseChunk]| decode_updates_by_range(chunks, [0; 4], 10, 3);
+        let updates = decode(&[period(10), period(11)]).unwrap();
+        assert_eq!(updates.len(), 2);
+        assert!(updates[0].update.next_sync_committee.is_some());
+        assert!(decode(&[]).unwrap().is_empty());
+
+        // Out of order, repeated, or outside the requested range.
+        assert!(decode(&[period(11), period(10)]).is_err());
+        assert!(decode(&[period(10), period(10)]).is_err());
+        assert!(decode(&[period(13)]).is_err());
+        assert!(decode(&[period(9)]).is_err());
+
+        // Encoded for another fork.
+        let other_fork = ResponseChunk { fork_digest: ELECTRA, ..period(11) };
+        assert!(decode(&[period(10), other_fork]).unwrap_err().contains("fork digest"));
+    }
+
+    fn message(data: u8) -> GossipMessage {
+        GossipMessage {
+            topic: light_client_topics(ELECTRA)[0].clone(),
+            data: vec![data],
+            source_peer: None,
+            message_id: vec![data],
+        }
+    }
+
+    #[tokio::test]
+    async fn test_full_channel_holds_the_loop_but_not_commands() {
+        let (mut event_loop, status) = event_loop(ForkSchedule::new(&NetworkConfig::mainnet()));
+        let (mut messages, mut receiver) = mpsc::channel(0);
+        let (commands, mut command_receiver) = mpsc::unbounded();
+        messages.try_send(message(1)).unwrap();
+
+        // The consumer has not taken message 1, so message 2 waits, while a
+        // subscription sent meanwhile still goes through.
+        commands.unbounded_send(Command::Subscribe("/eth2/b5303f2a/x".to_string())).unwrap();
+        let delivered = async {
+            settle().await;
+            assert_eq!(status.lock().unwrap().topics, vec!["/eth2/b5303f2a/x".to_string()]);
+            assert_eq!(receiver.next().await.unwrap().data, vec![1]);
+        };
+        let (kept_running, ()) = futures::join!(
+            deliver(message(2), &mut messages, &mut command_receiver, &mut event_loop),
+            delivered
+        );
+        assert!(kept_running);
+        assert_eq!(receiver.next().await.unwrap().data, vec![2]);
+
+        // A stop while blocked ends the loop.
+        messages.try_send(message(3)).unwrap();
+        commands.unbounded_send(Command::Stop).unwrap();
+        assert!(!deliver(message(4), &mut messages, &mut command_receiver, &mut event_loop).await);
+    }
+}
@@ -0,0 +1,327 @@
+//! Decoding of `ssz_snappy` gossip payloads into lumen-core types.
+//!
+//! A gossip message is an SSZ object compressed with snappy's block format
+//! (not the framed format req/resp uses). Its layout depends on the fork,
+//! which the topic names by fork digest. lumen-core decodes the Electra
+//! layouts only, so messages are decoded against one expected digest: the
+//! configured network's, from `compute_fork_digest`. Anything else is
+//! refused rather than read with the wrong layout.
+//!
+//! Decoding is structural. The results still have to pass the same
+//! sync committee signature checks as updates fetched over HTTP, and a
+//! beacon block or blob sidecar is only used once a verified header names
+//! its block.
+
+use crate::beacon_gossip::{GossipMessage, GossipMessageType, GossipValidation};
+use lumen_core::blob::sidecar::{decode_blob_sidecar, DecodedBlobSidecar};
+use lumen_core::consensus::block::{decode_signed_beacon_block, DecodedBeaconBlock};
+use lumen_core::consensus::ssz::{
+    decode_finality_update, decode_optimistic_update, DecodedOptimisticUpdate, DecodedUpdate, SszError,
+};
+use sha2::{Digest, Sha256};
+use std::fmt;
+
+/// Largest uncompressed gossip payload accepted (`GOSSIP_MAX_SIZE` in the
+/// consensus p2p spec). Checked against the length snappy declares, before
+/// decompressing.
+pub const GOSSIP_MAX_SIZE: usize = 10 * 1024 * 1024;
+
+/// Prefixes of a message id's preimage: whether the payload decompressed.
+const MESSAGE_DOMAIN_INVALID_SNAPPY: [u8; 4] = [0, 0, 0, 0];
+const MESSAGE_DOMAIN_VALID_SNAPPY: [u8; 4] = [1, 0, 0, 0];
+
+/// A decoded gossip message.
+#[derive(Clone, Debug)]
+pub enum GossipPayload {
+    FinalityUpdate(Box<DecodedUpdate>),
+    OptimisticUpdate(Box<DecodedOptimisticUpdate>),
+    /// Unverified: keep it in a `RecentBlocks` until a verified header names it.
+    BeaconBlock(Box<DecodedBeaconBlock>),
+    /// Unverified: keep it in a `PendingBlobSidecars` until a verified header names its block.
+    BlobSidecar(Box<DecodedBlobSidecar>),
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum GossipDecodeError {
+    /// Not a topic Lumen decodes, or not `ssz_snappy` encoded.
+    UnknownTopic(String),
+    /// The topic is for another fork or network.
+    ForkDigest { expected: [u8; 4], topic: String },
+    /// The declared uncompressed length exceeds [`GOSSIP_MAX_SIZE`].
+    TooLarge(usize),
+    Snappy(String),
+    Ssz(SszError),
+}
+
+impl fmt::Display for GossipDecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownTopic(topic) => write!(f, "Not a decoded gossip topic: {}", topic),
+            Self::ForkDigest { expected, topic } => {
+                write!(f, "Topic {} is not for fork digest {}", topic, hex::encode(expected))
+            }
+            Self::TooLarge(len) => {
+                write!(f, "Payload decompresses to {} bytes, over the {} limit", len, GOSSIP_MAX_SIZE)
+            }
+            Self::Snappy(e) => write!(f, "Snappy decompression failed: {}", e),
+            Self::Ssz(e) => write!(f, "SSZ decoding failed: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for GossipDecodeError {}
+
+impl GossipDecodeError {
+    /// The validation to report for a message that failed to decode. A
+    /// topic Lumen does not decode is no fault of the sender's, but a
+    /// payload that does not decode is.
+    pub fn validation(&self) -> GossipValidation {
+        match self {
+            Self::UnknownTopic(_) | Self::ForkDigest { .. } => GossipValidation::Ignore,
+            Self::TooLarge(_) | Self::Snappy(_) | Self::Ssz(_) => GossipValidation::Reject,
+        }
+    }
+}
+
+impl From<SszError> for GossipDecodeError {
+    fn from(e: SszError) -> Self {
+        Self::Ssz(e)
+    }
+}
+
+/// The fork digest of a topic `/eth2/{fork_digest}/{name}/{encoding}`.
+pub fn topic_fork_digest(topic: &str) -> Option<[u8; 4]> {
+    let digest = topic.strip_prefix("/eth2/")?.split('/').next()?;
+    hex::decode(digest).ok()?.try_into().ok()
+}
+
+/// Decompress a snappy block-format payload, refusing oversized ones.
+pub fn decompress(data: &[u8]) -> Result<Vec<u8>, GossipDecodeError> {
+    let len = snap::raw::decompress_len(data).map_err(|e| GossipDecodeError::Snappy(e.to_string()))?;
+    if len > GOSSIP_MAX_SIZE {
+        return Err(GossipDecodeError::TooLarge(len));
+    }
+    snap::raw::Decoder::new()
+        .decompress_vec(data)
+        .map_err(|e| GossipDecodeError::Snappy(e.to_string()))
+}
+
+/// The id of a gossip message on `topic` with payload `data`, as the
+/// consensus p2p spec defines it: the first 20 bytes of a SHA-256 of the
+/// topic and the decompressed payload, or of the payload as it is if it
+/// does not decompress. Gossipsub's default, the source and sequence
+/// number, would give every anonymous message the same id.
+pub fn gossip_message_id(topic: &str, data: &[u8]) -> Vec<u8> {
+    let (domain, payload) = match decompress(data) {
+        Ok(ssz) => (MESSAGE_DOMAIN_VALID_SNAPPY, ssz),
+        Err(_) => (MESSAGE_DOMAIN_INVALID_SNAPPY, data.to_vec()),
+    };
+    let mut hasher = Sha256::new();
+    hasher.update(domain);
+    hasher.update((topic.len() as u64).to_le_bytes());
+    hasher.update(topic.as_bytes());
+    hasher.update(&payload);
+    hasher.finalize()[..20].to_vec()
+}
+
+impl GossipMessage {
+    /// Decode this message, expecting topics for `fork_digest`.
+    pub fn decode(&self, fork_digest: [u8; 4]) -> Result<GossipPayload, GossipDecodeError> {
+        let message_type = GossipMessageType::from_topic(&self.topic);
+        if matches!(message_type, GossipMessageType::Unknown(_)) || !self.topic.ends_with("/ssz_snappy") {
+            return Err(GossipDecodeError::UnknownTopic(self.topic.clone()));
+        }
+        if topic_fork_digest(&self.topic) != Some(fork_digest) {
+            return Err(GossipDecodeError::ForkDigest { expected: fork_digest, topic: self.topic.clone() });
+        }
+
+        let ssz = decompress(&self.data)?;
+        Ok(match message_type {
+            GossipMessageType::FinalityUpdate => {
+                GossipPayload::FinalityUpdate(Box::new(decode_finality_update(&ssz)?))
+            }
+            GossipMessageType::OptimisticUpdate => {
+                GossipPayload::OptimisticUpdate(Box::new(decode_optimistic_update(&ssz)?))
+            }
+            GossipMessageType::BeaconBlock => GossipPayload::BeaconBlock(Box::new(decode_signed_beacon_block(&ssz)?)),
+            GossipMessageType::BlobSidecar => GossipPayload::BlobSidecar(Box::new(decode_blob_sidecar(&ssz)?)),
+            GossipMessageType::Unknown(_) => unreachable!("checked above"),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::beacon_gossip::{RecentBlocks, RECENT_BLOCKS_CAPACITY};
+    use lumen_core::consensus::block::{execution_body_root, execution_header_root};
+    use lumen_core::types::beacon::BeaconBlockHeader;
+
+    /// Mainnet Electra.
+    const DIGEST: [u8; 4] = [0xad, 0x53, 0x2c, 0xeb];
+
+    fn message(topic: &str, ssz: &[u8]) -> GossipMessage {
+        GossipMessage {
+            topic: topic.to_string(),
+            data: snap::raw::Encoder::new().compress_vec(ssz).unwrap(),
+            source_peer: None,
+            message_id: Vec::new(),
+        }
+    }
+
+    /// An Electra `LightClientOptimisticUpdate` attesting to `slot`.
+    fn optimistic_update(slot: u64) -> Vec<u8> {
+        let mut execution = vec![0u8; 584];
+        execution[436..440].copy_from_slice(&584u32.to_le_bytes()); // extra_data offset
+        let body_root = execution_body_root(&execution_header_root(&execution).unwrap(), &[[0u8; 32]; 4]);
+        let mut header = slot.to_le_bytes().to_vec();
+        header.extend_from_slice(&[0u8; 8 + 32 * 2]);
+        header.extend_from_slice(&body_root);
+        header.extend_from_slice(&244u32.to_le_bytes()); // execution offset
+        header.extend_from_slice(&[0u8; 32 * 4]);
+        header.extend_from_slice(&execution);
+
+        let mut out = 172u32.to_le_bytes().to_vec(); // attested_header offset
+        out.extend_from_slice(&[0xFF; 64]);
+        out.extend_from_slice(&[0xAA; 96]);
+        out.extend_from_slice(&(slot + 1).to_le_bytes());
+        out.extend_from_slice(&header);
+        out
+    }
+
+    #[test]
+    fn test_decode_optimistic_update() {
+        let topic = "/eth2/ad532ceb/light_client_optimistic_update/ssz_snappy";
+        let Ok(GossipPayload::OptimisticUpdate(update)) = message(topic, &optimistic_update(9000)).decode(DIGEST)
+        else {
+            panic!("expected an optimistic update");
+        };
+        assert_eq!(update.attested_header.slot, 9000);
+        assert_eq!(update.signature_slot, 9001);
+
+        // The same bytes on the finality topic are the wrong container.
+        let topic = "/eth2/ad532ceb/light_client_finality_update/ssz_snappy";
+        assert!(matches!(
+            message(topic, &optimistic_update(9000)).decode(DIGEST),
+            Err(GossipDecodeError::Ssz(_))
+        ));
+    }
+
+    #[test]
+    fn test_topics_checked_before_payload() {
+        let ssz = optimistic_update(1);
+        let other_fork = message("/eth2/b5303f2a/light_client_optimistic_update/ssz_snappy", &ssz);
+        let error = other_fork.decode(DIGEST).unwrap_err();
+        assert!(matches!(error, GossipDecodeError::ForkDigest { .. }));
+        assert_eq!(error.validation(), GossipValidation::Ignore);
+        let unknown = message("/eth2/ad532ceb/beacon_aggregate_and_proof/ssz_snappy", &ssz);
+        assert!(matches!(unknown.decode(DIGEST), Err(GossipDecodeError::UnknownTopic(_))));
+        let blob_sidecar = message("/eth2/ad532ceb/blob_sidecar_0/ssz_snappy", &ssz).decode(DIGEST);
+        assert!(matches!(blob_sidecar, Err(GossipDecodeError::Ssz(SszError::TooShort { .. }))));
+        let uncompressed = message("/eth2/ad532ceb/light_client_optimistic_update/ssz", &ssz);
+        assert!(matches!(uncompressed.decode(DIGEST), Err(GossipDecodeError::UnknownTopic(_))));
+
+        assert_eq!(topic_fork_digest("/eth2/ad532ceb/x/ssz_snappy"), Some(DIGEST));
+        assert_eq!(topic_fork_digest("/eth2/ad532c/x/ssz_snappy"), None);
+    }
+
+    /// An Electra `SignedBeaconBlock` with an empty body, but for the
+    /// execution payload's block number.
+    fn beacon_block(slot: u64, block_number: u64) -> Vec<u8> {
+        const BODY_FIXED_LEN: u32 = 396;
+        const PAYLOAD_FIXED_LEN: u32 = 528;
+        let mut payload = vec![0u8; PAYLOAD_FIXED_LEN as usize];
+        payload[404..412].copy_from_slice(&block_number.to_le_bytes());
+        for offset in [436, 504, 508] {
+            // extra_data, transactions, withdrawals: all empty
+            payload[offset..offset + 4].copy_from_slice(&PAYLOAD_FIXED_LEN.to_le_bytes());
+        }
+
+        let mut body = vec![0u8; 96 + 72 + 32];
+        for _ in 0..5 {
+            body.extend_from_slice(&BODY_FIXED_LEN.to_le_bytes()); // empty operation lists
+        }
+        body.extend_from_slice(&[0u8; 64 + 96]); // sync_aggregate
+        body.extend_from_slice(&BODY_FIXED_LEN.to_le_bytes()); // execution_payload
+        for _ in 0..3 {
+            // bls_to_execution_changes, blob_kzg_commitments, execution_requests
+            body.extend_from_slice(&(BODY_FIXED_LEN + PAYLOAD_FIXED_LEN).to_le_bytes());
+        }
+        body.extend_from_slice(&payload);
+        for _ in 0..3 {
+            body.extend_from_slice(&12u32.to_le_bytes()); // empty execution requests
+        }
+
+        let mut out = 100u32.to_le_bytes().to_vec(); // message offset
+        out.extend_from_slice(&[0xAA; 96]);
+        out.extend_from_slice(&slot.to_le_bytes());
+        out.extend_from_slice(&[0u8; 8 + 32 * 2]);
+        out.extend_from_slice(&84u32.to_le_bytes()); // body offset
+        out.extend_from_slice(&body);
+        out
+    }
+
+    #[test]
+    fn test_beacon_block_execution_for_verified_header() {
+        let topic = "/eth2/ad532ceb/beacon_block/ssz_snappy";
+        let Ok(GossipPayload::BeaconBlock(block)) = message(topic, &beacon_block(9000, 21_000_000)).decode(DIGEST)
+        else {
+            panic!("expected a beacon block");
+        };
+        assert_eq!(GossipMessageType::from_topic(topic), GossipMessageType::BeaconBlock);
+        let verified = block.header.clone();
+
+        let mut blocks = RecentBlocks::new();
+        blocks.insert(*block);
+        let execution = blocks.execution_for(&verified).unwrap();
+        assert_eq!(execution.block_number, 21_000_000);
+        assert!(blocks.execution_for(&BeaconBlockHeader { slot: 9001, ..verified.clone() }).is_none());
+
+        for slot in 0..RECENT_BLOCKS_CAPACITY as u64 {
+            let ssz = beacon_block(slot, 0);
+            let Ok(GossipPayload::BeaconBlock(block)) = message(topic, &ssz).decode(DIGEST) else {
+                panic!("expected a beacon block");
+            };
+            blocks.insert(*block);
+        }
+        assert_eq!(blocks.len(), RECENT_BLOCKS_CAPACITY);
+        // The first block went to make room.
+        assert!(blocks.execution_for(&verified).is_none());
+
+        let truncated = message(topic, &beacon_block(1, 1)[..200]).decode(DIGEST).unwrap_err();
+        assert_eq!(truncated.validation(), GossipValidation::Reject);
+    }
+
+    #[test]
+    fn test_message_ids() {
+        let topic = "/eth2/ad532ceb/light_client_finality_update/ssz_snappy";
+        let id = gossip_message_id(topic, &[0xFF, 0xFF]);
+        assert_eq!(hex::encode(id), "ad021f775c00bc504360e8010abffe849a4f0e55");
+
+        // Decompressed payloads are hashed under the other domain, so the
+        // same bytes give different ids as they do or do not decompress.
+        let compressed = snap::raw::Encoder::new().compress_vec(b"update").unwrap();
+        let mut preimage = MESSAGE_DOMAIN_VALID_SNAPPY.to_vec();
+        preimage.extend_from_slice(&(topic.len() as u64).to_le_bytes());
+        preimage.extend_from_slice(topic.as_bytes());
+        preimage.extend_from_slice(b"update");
+        assert_eq!(gossip_message_id(topic, &compressed), Sha256::digest(&preimage)[..20].to_vec());
+        assert_ne!(gossip_message_id(topic, &compressed), gossip_message_id("/eth2/other", &compressed));
+    }
+
+    #[test]
+    fn test_decompress_limits() {
+        let error = decompress(&[0xFF, 0xFF]).unwrap_err();
+        assert!(matches!(error, GossipDecodeError::Snappy(_)));
+        assert_eq!(error.validation(), GossipValidation::Reject);
+        // A varint length just over the limit, with no data after it.
+        let mut oversized = Vec::new();
+        let mut len = GOSSIP_MAX_SIZE + 1;
+        while len >= 0x80 {
+            oversized.push((len as u8) | 0x80);
+            len >>= 7;
+        }
+        oversized.push(len as u8);
+        assert_eq!(decompress(&oversized), Err(GossipDecodeError::TooLarge(GOSSIP_MAX_SIZE + 1)));
+    }
+}
@@ -0,0 +1,203 @@
+//! The consensus layer's Status handshake.
+//!
+//! Beacon nodes exchange a `Status` on every new connection: the fork
+//! digest they run, their finalized checkpoint and their head. A peer on
+//! another fork or network, or one that finalized a different block at an
+//! epoch we finalized too, is on a chain Lumen cannot follow, and its gossip
+//! would only fail verification. The service holds a peer's gossip until
+//! its Status has been checked with [`check_status`], and disconnects it if
+//! the check fails.
+//!
+//! The exchange is one request and one response chunk, `ssz_snappy` as for
+//! the light client protocols, but with no fork digest in the response:
+//! Status has the same layout in every fork. Unlike those, both sides are
+//! implemented, since whichever side opened the connection sends first.
+
+use crate::req_resp::{
+    encode_payload, encode_plain_response, invalid_data, read_payload, read_plain_response, MAX_PAYLOAD_SIZE,
+};
+use async_trait::async_trait;
+use futures::{AsyncRead, AsyncWrite, AsyncWriteExt};
+use libp2p::request_response::{self, ProtocolSupport};
+use libp2p::StreamProtocol;
+use lumen_core::types::beacon::SLOTS_PER_EPOCH;
+use std::io;
+
+pub const STATUS_PROTOCOL: &str = "/eth2/beacon_chain/req/status/1/ssz_snappy";
+
+/// Length of an SSZ-encoded [`StatusMessage`].
+pub const STATUS_LEN: usize = 4 + 32 + 8 + 32 + 8;
+
+/// A `Status`, ours or a peer's.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StatusMessage {
+    pub fork_digest: [u8; 4],
+    pub finalized_root: [u8; 32],
+    pub finalized_epoch: u64,
+    pub head_root: [u8; 32],
+    pub head_slot: u64,
+}
+
+impl StatusMessage {
+    /// A light client's Status. It follows no head, so it reports its
+    /// finalized checkpoint as its head, at the checkpoint's first slot.
+    pub fn light_client(fork_digest: [u8; 4], finalized_root: [u8; 32], finalized_epoch: u64) -> Self {
+        Self {
+            fork_digest,
+            finalized_root,
+            finalized_epoch,
+            head_root: finalized_root,
+            head_slot: finalized_epoch.saturating_mul(SLOTS_PER_EPOCH),
+        }
+    }
+
+    pub fn to_ssz(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(STATUS_LEN);
+        out.extend_from_slice(&self.fork_digest);
+        out.extend_from_slice(&self.finalized_root);
+        out.extend_from_slice(&self.finalized_epoch.to_le_bytes());
+        out.extend_from_slice(&self.head_root);
+        out.extend_from_slice(&self.head_slot.to_le_bytes());
+        out
+    }
+
+    pub fn from_ssz(ssz: &[u8]) -> Result<Self, String> {
+        if ssz.len() != STATUS_LEN {
+            return Err(format!("Status is {} bytes, not {}", ssz.len(), STATUS_LEN));
+        }
+        Ok(Self {
+            fork_digest: ssz[..4].try_into().expect("4 bytes"),
+            finalized_root: ssz[4..36].try_into().expect("32 bytes"),
+            finalized_epoch: u64::from_le_bytes(ssz[36..44].try_into().expect("8 bytes")),
+            head_root: ssz[44..76].try_into().expect("32 bytes"),
+            head_slot: u64::from_le_bytes(ssz[76..].try_into().expect("8 bytes")),
+        })
+    }
+}
+
+/// Whether a peer that sent `theirs` is on our chain, judged by what a
+/// light client knows of it: the fork digest, and the block finalized at
+/// our finalized epoch. A peer that finalized further is given the benefit
+/// of the doubt, since the block it finalized at our epoch is unknown to
+/// us; one behind us is kept too, as it may still catch up.
+pub fn check_status(ours: &StatusMessage, theirs: &StatusMessage) -> Result<(), String> {
+    if theirs.fork_digest != ours.fork_digest {
+        return Err(format!(
+            "Peer is on fork digest {}, not {}",
+            hex::encode(theirs.fork_digest),
+            hex::encode(ours.fork_digest)
+        ));
+    }
+    if theirs.finalized_epoch == ours.finalized_epoch && theirs.finalized_root != ours.finalized_root {
+        return Err(format!(
+            "Peer finalized block 0x{} at epoch {}, not 0x{}",
+            hex::encode(theirs.finalized_root),
+            theirs.finalized_epoch,
+            hex::encode(ours.finalized_root)
+        ));
+    }
+    Ok(())
+}
+
+/// A request-response behaviour for the Status protocol, both asking and
+/// answering.
+pub fn status_behaviour() -> request_response::Behaviour<StatusCodec> {
+    request_response::Behaviour::new(
+        [(StreamProtocol::new(STATUS_PROTOCOL), ProtocolSupport::Full)],
+        request_response::Config::default().with_request_timeout(crate::req_resp::REQUEST_TIMEOUT),
+    )
+}
+
+/// The `ssz_snappy` codec for [`STATUS_PROTOCOL`]: a Status each way.
+#[derive(Clone, Debug, Default)]
+pub struct StatusCodec;
+
+#[async_trait]
+impl request_response::Codec for StatusCodec {
+    type Protocol = StreamProtocol;
+    type Request = StatusMessage;
+    type Response = StatusMessage;
+
+    async fn read_request<T>(&mut self, _: &StreamProtocol, io: &mut T) -> io::Result<StatusMessage>
+    where
+        T: AsyncRead + Unpin + Send,
+    {
+        let ssz = read_payload(io, MAX_PAYLOAD_SIZE).await?;
+        StatusMessage::from_ssz(&ssz).map_err(invalid_data)
+    }
+
+    async fn read_response<T>(&mut self, _: &StreamProtocol, io: &mut T) -> io::Result<StatusMessage>
+    where
+        T: AsyncRead + Unpin + Send,
+    {
+        let ssz = read_plain_response(io).await?;
+        StatusMessage::from_ssz(&ssz).map_err(invalid_data)
+    }
+
+    async fn write_request<T>(&mut self, _: &StreamProtocol, io: &mut T, request: StatusMessage) -> io::Result<()>
+    where
+        T: AsyncWrite + Unpin + Send,
+    {
+        io.write_all(&encode_payload(&request.to_ssz())).await
+    }
+
+    async fn write_response<T>(&mut self, _: &StreamProtocol, io: &mut T, response: StatusMessage) -> io::Result<()>
+    where
+        T: AsyncWrite + Unpin + Send,
+    {
+        io.write_all(&encode_plain_response(&response.to_ssz())).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use libp2p::request_response::Codec;
+
+    /// Mainnet Electra.
+    const DIGEST: [u8; 4] = [0xad, 0x53, 0x2c, 0xeb];
+
+    #[test]
+    fn test_check_status() {
+        let ours = StatusMessage::light_client(DIGEST, [1; 32], 100);
+        assert_eq!(ours.head_slot, 3200);
+        assert_eq!(check_status(&ours, &ours), Ok(()));
+
+        // Ahead of us or behind us on what may be the same chain.
+        let ahead = StatusMessage { finalized_root: [2; 32], finalized_epoch: 101, ..ours };
+        assert_eq!(check_status(&ours, &ahead), Ok(()));
+        let behind = StatusMessage { finalized_root: [3; 32], finalized_epoch: 90, ..ours };
+        assert_eq!(check_status(&ours, &behind), Ok(()));
+
+        let other_fork = StatusMessage { fork_digest: [0x6a, 0x95, 0xa1, 0xa9], ..ours };
+        assert!(check_status(&ours, &other_fork).unwrap_err().contains("6a95a1a9"));
+        let other_chain = StatusMessage { finalized_root: [2; 32], ..ours };
+        assert!(check_status(&ours, &other_chain).unwrap_err().contains("epoch 100"));
+    }
+
+    #[tokio::test]
+    async fn test_codec_round_trip() {
+        let protocol = StreamProtocol::new(STATUS_PROTOCOL);
+        let status = StatusMessage {
+            head_root: [7; 32],
+            head_slot: 3210,
+            ..StatusMessage::light_client(DIGEST, [1; 32], 100)
+        };
+        assert_eq!(StatusMessage::from_ssz(&status.to_ssz()), Ok(status));
+        assert!(StatusMessage::from_ssz(&[0; STATUS_LEN - 1]).is_err());
+
+        let mut wire = Vec::new();
+        StatusCodec.write_request(&protocol, &mut wire, status).await.unwrap();
+        assert_eq!(StatusCodec.read_request(&protocol, &mut wire.as_slice()).await.unwrap(), status);
+
+        let mut wire = Vec::new();
+        StatusCodec.write_response(&protocol, &mut wire, status).await.unwrap();
+        assert_eq!(wire[0], 0);
+        assert_eq!(StatusCodec.read_response(&protocol, &mut wire.as_slice()).await.unwrap(), status);
+
+        let mut refused = vec![2];
+        refused.extend_from_slice(&encode_payload(b"busy"));
+        let error = StatusCodec.read_response(&protocol, &mut refused.as_slice()).await.unwrap_err();
+        assert!(error.to_string().contains("error 2: busy"));
+    }
+}
@@ -2,18 +2,42 @@
 //!
 //! In the browser, we cannot use TCP or QUIC directly.
 //! We use WebRTC and WebTransport, which are browser-native.
+//! Native builds with the `native` feature dial TCP and QUIC instead, as
+//! beacon nodes listen on, so the same behaviour can be run against
+//! mainnet from a CLI or CI.
 //!
 //! ## Transport Priority
 //!
 //! 1. **WebTransport** (preferred) — lower latency, better performance
 //! 2. **WebRTC** (fallback) — wider peer support, works behind more NATs
 //! 3. **Circuit relay** (bootstrap only) — for initial peer discovery
+//!
+//! [`build_transport`] assembles the enabled transports into the one stack
+//! the Swarm dials through. Only transports compiled for the target count:
+//! the browser transports exist on `wasm32` alone, and TCP and QUIC (with
+//! DNS resolution) on native targets with the `native` feature alone; a
+//! native build without it has no transport and every dial fails with an
+//! unsupported address. The in-process `/memory` transport can be enabled on any target, for tests
+//! that run several nodes in one process. Every connection it makes is
+//! counted by a [`BandwidthMeter`].
 
+use crate::bandwidth::{Bandwidth, BandwidthMeter, PeerBandwidth};
+use crate::reachability::Reachability;
+use libp2p::core::muxing::StreamMuxerBox;
+use libp2p::core::transport::{dummy::DummyTransport, timeout::TransportTimeout, Boxed};
+use libp2p::core::Transport;
+use libp2p::multiaddr::Protocol;
+use libp2p::{identity::Keypair, noise, Multiaddr, PeerId};
 use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Whether TCP and QUIC are built: native targets with the `native`
+/// feature.
+const NATIVE_TRANSPORTS: bool = cfg!(all(feature = "native", not(target_arch = "wasm32")));
 
 /// Transport type used for a connection.
 /// Logged clearly so developers can audit their trust state.
-#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum TransportType {
     /// WebTransport — direct, encrypted, fully P2P.
     /// No intermediary. Best performance and trust model.
@@ -27,6 +51,12 @@ pub enum TransportType {
     /// Used as a fallback when WebRTC/WebTransport are not available.
     WebSocket,
 
+    /// TCP — direct, encrypted with Noise. Native builds only.
+    Tcp,
+
+    /// QUIC — direct, encrypted with its own TLS. Native builds only.
+    Quic,
+
     /// Circuit relay — indirect, via relay node.
     /// The relay can see metadata (who's talking to whom) but NOT the data
     /// (which is encrypted with Noise). Used only for bootstrapping.
@@ -34,11 +64,34 @@ pub enum TransportType {
 }
 
 impl TransportType {
+    /// The transport a connection to `address` goes over. Addresses that
+    /// are none of the others are taken to be WebSocket.
+    pub fn of_address(address: &Multiaddr) -> Self {
+        let mut transport = TransportType::WebSocket;
+        for protocol in address.iter() {
+            match protocol {
+                Protocol::P2pCircuit => return TransportType::CircuitRelay,
+                Protocol::WebTransport => transport = TransportType::WebTransport,
+                Protocol::WebRTCDirect => transport = TransportType::WebRTC,
+                // WebSocket and WebTransport run over these, and come after.
+                Protocol::Tcp(_) => transport = TransportType::Tcp,
+                Protocol::QuicV1 => transport = TransportType::Quic,
+                Protocol::Ws(_) | Protocol::Wss(_) => transport = TransportType::WebSocket,
+                _ => {}
+            }
+        }
+        transport
+    }
+
     /// Whether this transport is direct (no intermediary).
     pub fn is_direct(&self) -> bool {
         matches!(
             self,
-            TransportType::WebTransport | TransportType::WebRTC | TransportType::WebSocket
+            TransportType::WebTransport
+                | TransportType::WebRTC
+                | TransportType::WebSocket
+                | TransportType::Tcp
+                | TransportType::Quic
         )
     }
 
@@ -48,6 +101,8 @@ impl TransportType {
             TransportType::WebTransport => "WebTransport (direct, encrypted, fully P2P)",
             TransportType::WebRTC => "WebRTC (direct, encrypted, fully P2P)",
             TransportType::WebSocket => "WebSocket (direct, encrypted)",
+            TransportType::Tcp => "TCP (direct, encrypted)",
+            TransportType::Quic => "QUIC (direct, encrypted)",
             TransportType::CircuitRelay => {
                 "Circuit Relay (indirect — relay sees metadata, not data)"
             }
@@ -80,6 +135,26 @@ pub struct TransportConfig {
     /// If no direct connection is established within this time,
     /// fall back to circuit relay.
     pub bootstrap_timeout_ms: u64,
+
+    /// Enable the in-process `/memory` transport, upgraded with Noise and
+    /// Yamux like WebSocket. For tests only: it reaches nothing outside
+    /// the process.
+    #[serde(default)]
+    pub enable_memory: bool,
+
+    /// Enable TCP, upgraded with Noise and Yamux. Only with the `native`
+    /// feature, off `wasm32`.
+    #[serde(default = "enabled")]
+    pub enable_tcp: bool,
+
+    /// Enable QUIC (`/quic-v1`). Only with the `native` feature, off
+    /// `wasm32`.
+    #[serde(default = "enabled")]
+    pub enable_quic: bool,
+}
+
+fn enabled() -> bool {
+    true
 }
 
 impl Default for TransportConfig {
@@ -92,7 +167,38 @@ impl Default for TransportConfig {
             max_peers: 10,
             connection_timeout_ms: 10_000,
             bootstrap_timeout_ms: 3_000,
+            enable_memory: false,
+            enable_tcp: true,
+            enable_quic: true,
+        }
+    }
+}
+
+impl TransportConfig {
+    /// Whether `address` is one an enabled transport dials: WebTransport,
+    /// WebRTC (`/webrtc-direct`), WebSocket, `/memory`, or TCP and QUIC
+    /// where they are built. Relayed
+    /// (`/p2p-circuit`) addresses are not: the relay is for bootstrapping,
+    /// reserved by `RelayFallback`, and discovered peers are dialed directly.
+    pub fn can_dial(&self, address: &Multiaddr) -> bool {
+        let mut dialable = false;
+        for protocol in address.iter() {
+            dialable |= match protocol {
+                Protocol::P2pCircuit => return false,
+                Protocol::WebTransport => self.enable_webtransport,
+                Protocol::WebRTCDirect => self.enable_webrtc,
+                Protocol::Ws(_) | Protocol::Wss(_) => self.enable_websocket,
+                Protocol::Memory(_) => self.enable_memory,
+                _ => false,
+            };
         }
+        dialable
+            || NATIVE_TRANSPORTS
+                && match TransportType::of_address(address) {
+                    TransportType::Tcp => self.enable_tcp,
+                    TransportType::Quic => self.enable_quic,
+                    _ => false,
+                }
     }
 }
 
@@ -107,6 +213,26 @@ pub struct TransportStats {
     pub using_relay: bool,
     /// Whether we have at least one direct connection.
     pub has_direct_connection: bool,
+    /// Open direct connections that peers dialed.
+    pub inbound_connections: usize,
+    /// Whether peers can dial us, as far as we can tell. Browsers cannot
+    /// listen, so are always `Private`.
+    pub reachability: Reachability,
+    /// How sure of `reachability` we are.
+    pub reachability_confidence: u32,
+    /// Addresses peers report observing us at, over Identify.
+    pub observed_addresses: Vec<String>,
+    /// Bytes moved this session, over all connections.
+    pub bandwidth: Bandwidth,
+    pub bandwidth_by_transport: Vec<(TransportType, Bandwidth)>,
+    /// Bytes moved this session per peer, the busiest first.
+    pub bandwidth_by_peer: Vec<PeerBandwidth>,
+}
+
+impl Default for TransportStats {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl TransportStats {
@@ -116,6 +242,163 @@ impl TransportStats {
             total_peers: 0,
             using_relay: false,
             has_direct_connection: false,
+            inbound_connections: 0,
+            reachability: Reachability::Unknown,
+            reachability_confidence: 0,
+            observed_addresses: Vec::new(),
+            bandwidth: Bandwidth::default(),
+            bandwidth_by_transport: Vec::new(),
+            bandwidth_by_peer: Vec::new(),
         }
     }
 }
+
+/// An authenticated, multiplexed transport: what the Swarm dials through.
+pub type LumenTransport = Boxed<(PeerId, StreamMuxerBox)>;
+
+/// Build the transport stack `config` enables, with connection attempts cut
+/// off after `connection_timeout_ms` and what connections move counted by
+/// `bandwidth`.
+///
+/// WebTransport authenticates with its own Noise handshake and multiplexes
+/// natively, so it needs no upgrade. WebSocket (`/ws` and `/wss`
+/// addresses) is a plain byte stream, upgraded with Noise and Yamux as
+/// Ethereum nodes expect. WebRTC is not built yet: no libp2p release pairs
+/// a browser WebRTC transport with this libp2p version.
+///
+/// Natively, TCP is upgraded the same way and QUIC secures and multiplexes
+/// itself; with either enabled, `/dns` addresses are resolved with the
+/// system's resolver, which fails the build if its configuration cannot be
+/// read.
+pub fn build_transport(
+    keypair: &Keypair,
+    config: &TransportConfig,
+    bandwidth: &BandwidthMeter,
+) -> Result<LumenTransport, Box<dyn std::error::Error + Send + Sync>> {
+    let transport: LumenTransport = DummyTransport::<(PeerId, StreamMuxerBox)>::new().boxed();
+
+    #[cfg(target_arch = "wasm32")]
+    let transport = {
+        use libp2p::core::transport::OptionalTransport;
+        use libp2p::core::upgrade::Version;
+        use libp2p::{websocket_websys, webtransport_websys, yamux};
+
+        let webtransport = if config.enable_webtransport {
+            OptionalTransport::some(webtransport_websys::Transport::new(webtransport_websys::Config::new(keypair)))
+        } else {
+            OptionalTransport::none()
+        };
+        let websocket = if config.enable_websocket {
+            OptionalTransport::some(
+                websocket_websys::Transport::default()
+                    .upgrade(Version::V1Lazy)
+                    .authenticate(noise::Config::new(keypair)?)
+                    .multiplex(yamux::Config::default()),
+            )
+        } else {
+            OptionalTransport::none()
+        };
+        webtransport
+            .map(|(peer, connection), _| (peer, StreamMuxerBox::new(connection)))
+            .or_transport(websocket.map(|(peer, muxer), _| (peer, StreamMuxerBox::new(muxer))))
+            .map(|either, _| either.into_inner())
+            .or_transport(transport)
+            .map(|either, _| either.into_inner())
+            .boxed()
+    };
+
+    #[cfg(all(feature = "native", not(target_arch = "wasm32")))]
+    let transport = if config.enable_tcp || config.enable_quic {
+        use libp2p::core::transport::OptionalTransport;
+        use libp2p::core::upgrade::Version;
+        use libp2p::{dns, quic, tcp, yamux};
+
+        let tcp = if config.enable_tcp {
+            OptionalTransport::some(
+                tcp::tokio::Transport::new(tcp::Config::default().nodelay(true))
+                    .upgrade(Version::V1Lazy)
+                    .authenticate(noise::Config::new(keypair)?)
+                    .multiplex(yamux::Config::default()),
+            )
+        } else {
+            OptionalTransport::none()
+        };
+        let quic = if config.enable_quic {
+            OptionalTransport::some(quic::tokio::Transport::new(quic::Config::new(keypair)))
+        } else {
+            OptionalTransport::none()
+        };
+        let native = quic
+            .map(|(peer, connection), _| (peer, StreamMuxerBox::new(connection)))
+            .or_transport(tcp.map(|(peer, muxer), _| (peer, StreamMuxerBox::new(muxer))))
+            .map(|either, _| either.into_inner());
+        dns::tokio::Transport::system(native)?
+            .or_transport(transport)
+            .map(|either, _| either.into_inner())
+            .boxed()
+    } else {
+        transport
+    };
+
+    let transport = if config.enable_memory {
+        use libp2p::core::transport::MemoryTransport;
+        use libp2p::core::upgrade::Version;
+
+        MemoryTransport::default()
+            .upgrade(Version::V1)
+            .authenticate(noise::Config::new(keypair)?)
+            .multiplex(libp2p::yamux::Config::default())
+            .map(|(peer, muxer), _| (peer, StreamMuxerBox::new(muxer)))
+            .or_transport(transport)
+            .map(|either, _| either.into_inner())
+            .boxed()
+    } else {
+        transport
+    };
+
+    let bandwidth = bandwidth.clone();
+    let transport = transport.map(move |(peer, muxer), endpoint| {
+        let transport = TransportType::of_address(endpoint.get_remote_address());
+        (peer, bandwidth.meter(peer, transport, muxer))
+    });
+    Ok(TransportTimeout::new(transport, Duration::from_millis(config.connection_timeout_ms)).boxed())
+}
+
+#[cfg(test)]
+impl TransportConfig {
+    /// The browser transports alone, whether or not this build has TCP
+    /// and QUIC.
+    pub(crate) fn browser() -> Self {
+        Self { enable_tcp: false, enable_quic: false, ..Self::default() }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transport_of_address() {
+        let of = |address: &str| TransportType::of_address(&address.parse().unwrap());
+        assert_eq!(of("/ip4/1.2.3.4/tcp/9000"), TransportType::Tcp);
+        assert_eq!(of("/dns4/a.example/tcp/443/wss"), TransportType::WebSocket);
+        assert_eq!(of("/ip4/1.2.3.4/udp/9001/quic-v1"), TransportType::Quic);
+        assert_eq!(of("/ip4/1.2.3.4/udp/443/quic-v1/webtransport"), TransportType::WebTransport);
+        assert_eq!(of("/dns4/relay.lumen.dev/tcp/443/wss/p2p-circuit"), TransportType::CircuitRelay);
+        assert!(TransportType::Quic.is_direct());
+    }
+
+    #[test]
+    fn test_native_addresses_need_the_native_transports() {
+        let (tcp, quic) = ("/ip4/1.2.3.4/tcp/9000".parse().unwrap(), "/ip4/1.2.3.4/udp/9001/quic-v1".parse().unwrap());
+        let config = TransportConfig::default();
+        assert_eq!(config.can_dial(&tcp), NATIVE_TRANSPORTS);
+        assert_eq!(config.can_dial(&quic), NATIVE_TRANSPORTS);
+        let quic_only = TransportConfig { enable_tcp: false, ..TransportConfig::default() };
+        assert!(!quic_only.can_dial(&tcp));
+        assert!(!TransportConfig::browser().can_dial(&quic));
+        // TCP does not take WebSocket addresses, which run over it.
+        let no_websocket = TransportConfig { enable_websocket: false, ..TransportConfig::default() };
+        assert!(!no_websocket.can_dial(&"/dns4/a.example/tcp/443/wss".parse().unwrap()));
+    }
+}
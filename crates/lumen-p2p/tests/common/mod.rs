@@ -0,0 +1,229 @@
+//! An in-process network for integration tests: full nodes on the
+//! `/memory` transport, each driven by its own task, and a Lumen client
+//! configured to dial them.
+//!
+//! A [`FullNode`] speaks what a beacon node would to a light client:
+//! gossipsub, Identify, Ping, Status (echoing the client's back, so every
+//! check passes) and, if asked to, the light client req/resp protocols,
+//! answered from a fixed [`LightClientResponse`] per protocol, or Portal
+//! history queries, answered from a fixed [`ContentResponse`] per key.
+
+use futures::channel::{mpsc, oneshot};
+use futures::{FutureExt, StreamExt};
+use libp2p::request_response::{self, ProtocolSupport};
+use libp2p::swarm::{NetworkBehaviour, SwarmEvent};
+use libp2p::{gossipsub, identify, identity::Keypair, ping, Multiaddr, PeerId, Swarm, SwarmBuilder};
+use lumen_core::types::network::NetworkConfig;
+use lumen_p2p::transport::{build_transport, TransportConfig};
+use lumen_p2p::{
+    create_gossipsub_config, status_behaviour, BandwidthMeter, BootstrapConfig, ContentResponse, ForkSchedule,
+    LightClientCodec, LightClientProtocol, LightClientResponse, LumenP2pService, PortalCodec, PortalNetwork,
+    ServiceStatus, StatusCodec,
+};
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// How long a test waits for the network to get somewhere.
+pub const TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(NetworkBehaviour)]
+pub struct FullNodeBehaviour {
+    gossipsub: gossipsub::Behaviour,
+    identify: identify::Behaviour,
+    ping: ping::Behaviour,
+    status: request_response::Behaviour<StatusCodec>,
+    light_client: request_response::Behaviour<LightClientCodec>,
+    portal_history: request_response::Behaviour<PortalCodec>,
+}
+
+enum NodeCommand {
+    Publish { topic: String, data: Vec<u8>, reply: oneshot::Sender<Result<(), String>> },
+}
+
+/// A full node running on its own task until dropped.
+pub struct FullNode {
+    pub peer_id: PeerId,
+    /// Where to dial it, ending in its peer id.
+    pub address: Multiaddr,
+    commands: mpsc::UnboundedSender<NodeCommand>,
+}
+
+impl FullNode {
+    /// A node that serves the light client protocols with `responses`.
+    pub async fn serving(responses: HashMap<LightClientProtocol, LightClientResponse>) -> Self {
+        Self::spawn(Some(responses), None).await
+    }
+
+    /// A node without the light client protocols, as Identify shows.
+    pub async fn not_serving() -> Self {
+        Self::spawn(None, None).await
+    }
+
+    /// A node on Lumen's history overlay, answering queries for the
+    /// content keys in `content` and no nodes for the rest.
+    pub async fn portal(content: HashMap<Vec<u8>, ContentResponse>) -> Self {
+        Self::spawn(None, Some(content)).await
+    }
+
+    async fn spawn(
+        responses: Option<HashMap<LightClientProtocol, LightClientResponse>>,
+        content: Option<HashMap<Vec<u8>, ContentResponse>>,
+    ) -> Self {
+        let mut swarm = full_node_swarm(responses.is_some(), content.is_some());
+        swarm.listen_on("/memory/0".parse().unwrap()).unwrap();
+        let listening = loop {
+            if let SwarmEvent::NewListenAddr { address, .. } = swarm.select_next_some().await {
+                break address;
+            }
+        };
+        let peer_id = *swarm.local_peer_id();
+        let (commands, receiver) = mpsc::unbounded();
+        tokio::spawn(run(swarm, receiver, responses.unwrap_or_default(), content.unwrap_or_default()));
+        Self { peer_id, address: listening.with_p2p(peer_id).unwrap(), commands }
+    }
+
+    /// Publish `data` on `topic` once some peer has joined it.
+    pub async fn publish(&self, topic: &str, data: Vec<u8>) {
+        wait_until("a peer to join the topic", || async {
+            let (reply, result) = oneshot::channel();
+            let command = NodeCommand::Publish { topic: topic.to_string(), data: data.clone(), reply };
+            self.commands.unbounded_send(command).unwrap();
+            result.await.unwrap().is_ok()
+        })
+        .await;
+    }
+}
+
+fn full_node_swarm(serves_light_clients: bool, serves_portal: bool) -> Swarm<FullNodeBehaviour> {
+    let transport = memory_transport();
+    SwarmBuilder::with_existing_identity(Keypair::generate_ed25519())
+        .with_tokio()
+        .with_other_transport(|keypair| build_transport(keypair, &transport, &BandwidthMeter::new()))
+        .unwrap()
+        .with_behaviour(|keypair| {
+            let gossipsub =
+                gossipsub::Behaviour::new(gossipsub::MessageAuthenticity::Anonymous, create_gossipsub_config())?;
+            let protocols = LightClientProtocol::ALL
+                .iter()
+                .filter(|_| serves_light_clients)
+                .map(|protocol| (protocol.stream_protocol(), ProtocolSupport::Inbound));
+            let portal = [PortalNetwork::History.stream_protocol()]
+                .into_iter()
+                .filter(|_| serves_portal)
+                .map(|protocol| (protocol, ProtocolSupport::Inbound));
+            Ok(FullNodeBehaviour {
+                gossipsub,
+                identify: identify::Behaviour::new(identify::Config::new("/eth2/1.0.0".into(), keypair.public())),
+                ping: ping::Behaviour::default(),
+                status: status_behaviour(),
+                light_client: request_response::Behaviour::new(protocols, request_response::Config::default()),
+                portal_history: request_response::Behaviour::new(portal, request_response::Config::default()),
+            })
+        })
+        .unwrap()
+        .with_swarm_config(|config| config.with_idle_connection_timeout(Duration::from_secs(60)))
+        .build()
+}
+
+async fn run(
+    mut swarm: Swarm<FullNodeBehaviour>,
+    mut commands: mpsc::UnboundedReceiver<NodeCommand>,
+    responses: HashMap<LightClientProtocol, LightClientResponse>,
+    content: HashMap<Vec<u8>, ContentResponse>,
+) {
+    loop {
+        futures::select! {
+            command = commands.next() => match command {
+                Some(NodeCommand::Publish { topic, data, reply }) => {
+                    let result = swarm.behaviour_mut().gossipsub.publish(gossipsub::IdentTopic::new(topic), data);
+                    let _ = reply.send(result.map(|_| ()).map_err(|e| e.to_string()));
+                }
+                None => break,
+            },
+            event = swarm.select_next_some() => match event {
+                SwarmEvent::Behaviour(FullNodeBehaviourEvent::Status(request_response::Event::Message {
+                    message: request_response::Message::Request { request, channel, .. },
+                    ..
+                })) => {
+                    let _ = swarm.behaviour_mut().status.send_response(channel, request);
+                }
+                SwarmEvent::Behaviour(FullNodeBehaviourEvent::LightClient(request_response::Event::Message {
+                    message: request_response::Message::Request { request, channel, .. },
+                    ..
+                })) => {
+                    let response = responses.get(&request.protocol()).cloned().unwrap_or_default();
+                    let _ = swarm.behaviour_mut().light_client.send_response(channel, response);
+                }
+                SwarmEvent::Behaviour(FullNodeBehaviourEvent::PortalHistory(request_response::Event::Message {
+                    message: request_response::Message::Request { request, channel, .. },
+                    ..
+                })) => {
+                    let response = content.get(&request.content_key).cloned();
+                    let response = response.unwrap_or(ContentResponse::Nodes(Vec::new()));
+                    let _ = swarm.behaviour_mut().portal_history.send_response(channel, response);
+                }
+                // Join what the client joins, so publishing reaches it.
+                SwarmEvent::Behaviour(FullNodeBehaviourEvent::Gossipsub(gossipsub::Event::Subscribed {
+                    topic, ..
+                })) => {
+                    let _ = swarm.behaviour_mut().gossipsub.subscribe(&gossipsub::IdentTopic::new(topic.as_str()));
+                }
+                _ => {}
+            },
+        }
+    }
+}
+
+/// A transport config with only `/memory` enabled.
+pub fn memory_transport() -> TransportConfig {
+    TransportConfig {
+        enable_webtransport: false,
+        enable_webrtc: false,
+        enable_websocket: false,
+        enable_relay: false,
+        enable_memory: true,
+        enable_tcp: false,
+        enable_quic: false,
+        ..TransportConfig::default()
+    }
+}
+
+/// A mainnet client with `nodes` as its bootnodes, wanting `min_peers` to
+/// `max_peers` of them.
+pub fn client(nodes: &[&FullNode], min_peers: usize, max_peers: usize) -> LumenP2pService {
+    let bootstrap = BootstrapConfig {
+        bootnodes: nodes.iter().map(|node| node.address.to_string()).collect(),
+        relays: Vec::new(),
+        min_peers,
+        max_peers,
+        ..BootstrapConfig::default()
+    };
+    let forks = ForkSchedule::new(&NetworkConfig::mainnet());
+    LumenP2pService::new(Keypair::generate_ed25519(), memory_transport(), bootstrap, forks)
+}
+
+/// Wait until `service`'s status passes `condition`, or fail the test.
+pub async fn wait_for_status(service: &LumenP2pService, what: &str, condition: impl Fn(&ServiceStatus) -> bool) {
+    let condition = &condition;
+    wait_until(what, || {
+        let status = service.status();
+        async move { condition(&status) }
+    })
+    .await;
+}
+
+/// Poll `condition` until it holds, or fail the test after [`TIMEOUT`].
+pub async fn wait_until<F, Fut>(what: &str, mut condition: F)
+where
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = bool>,
+{
+    let poll = async {
+        while !condition().await {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    };
+    if tokio::time::timeout(TIMEOUT, poll.fuse()).await.is_err() {
+        panic!("Timed out waiting for {}", what);
+    }
+}
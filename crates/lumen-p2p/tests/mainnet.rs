@@ -0,0 +1,40 @@
+//! A smoke test against Ethereum mainnet over the native transports: dial
+//! the bootnodes, pass Status with one and fetch its latest finality
+//! update. It needs the `native` feature and the internet, so it is
+//! ignored unless asked for:
+//!
+//! `cargo test -p lumen-p2p --features native --test mainnet -- --ignored`
+
+#![cfg(all(feature = "native", not(target_arch = "wasm32")))]
+
+use libp2p::identity::Keypair;
+use lumen_core::types::network::NetworkConfig;
+use lumen_p2p::transport::TransportConfig;
+use lumen_p2p::{BootstrapConfig, ForkSchedule, LumenP2pService, StatusMessage};
+use std::time::Duration;
+
+#[tokio::test]
+#[ignore = "dials mainnet beacon nodes"]
+async fn test_mainnet_finality_update_over_tcp_and_quic() {
+    let bootstrap = BootstrapConfig { min_peers: 1, ..BootstrapConfig::default() };
+    let forks = ForkSchedule::new(&NetworkConfig::mainnet());
+    let mut service = LumenP2pService::new(Keypair::generate_ed25519(), TransportConfig::default(), bootstrap, forks);
+    let _gossip = service.start().unwrap();
+    // Nothing finalized yet, which any node's Status check lets through.
+    service.set_status(StatusMessage::light_client(service.status().fork_digest, [0; 32], 0)).unwrap();
+
+    let fetched = async {
+        loop {
+            for peer in service.request_peers().await.unwrap() {
+                if let Ok(update) = service.request_finality_update(peer).await {
+                    return update;
+                }
+            }
+            tokio::time::sleep(Duration::from_secs(1)).await;
+        }
+    };
+    let update = tokio::time::timeout(Duration::from_secs(120), fetched).await.expect("no peer served an update");
+    let update = update.update;
+    assert!(update.finalized_header.slot > 0);
+    assert!(update.attested_header.slot >= update.finalized_header.slot);
+}
@@ -0,0 +1,181 @@
+//! Multi-peer tests of the running service, over the in-process network
+//! in `common`.
+
+#![cfg(not(target_arch = "wasm32"))]
+
+mod common;
+
+use common::{client, wait_for_status, wait_until, FullNode};
+use futures::StreamExt;
+use lumen_core::execution::header::ExecutionBlockHeader;
+use lumen_core::U256;
+use lumen_p2p::{
+    gossip_topic, ContentResponse, ErrorResponse, GossipValidation, HistoryContentKey, LightClientProtocol,
+    LightClientResponse, PortalNetwork, ResponseChunk, ResponseCode, ServiceStatus, StatusMessage,
+    LIGHT_CLIENT_FINALITY_UPDATE,
+};
+use std::collections::HashMap;
+
+/// Our Status, on the fork the client is on.
+fn status(fork_digest: [u8; 4]) -> StatusMessage {
+    StatusMessage::light_client(fork_digest, [0x11; 32], 300_000)
+}
+
+/// A gossip payload: snappy-compressed bytes.
+fn payload(seed: u8) -> Vec<u8> {
+    snap::raw::Encoder::new().compress_vec(&[seed; 64]).unwrap()
+}
+
+#[tokio::test]
+async fn test_client_checks_peers_and_receives_their_gossip() {
+    let nodes = [FullNode::serving(HashMap::new()).await, FullNode::serving(HashMap::new()).await];
+    let mut service = client(&[&nodes[0], &nodes[1]], 2, 4);
+    let mut gossip = service.start().unwrap();
+    let fork_digest = service.status().fork_digest;
+    service.set_status(status(fork_digest)).unwrap();
+    wait_for_status(&service, "both peers to pass Status", |status| status.accepted_peers == 2).await;
+
+    let topic = gossip_topic(fork_digest, LIGHT_CLIENT_FINALITY_UPDATE);
+    nodes[1].publish(&topic, payload(1)).await;
+    let message = tokio::time::timeout(common::TIMEOUT, gossip.next()).await.unwrap().unwrap();
+    assert_eq!(message.topic, topic);
+    assert_eq!(message.data, payload(1));
+    assert_eq!(message.source_peer, Some(nodes[1].peer_id.to_string()));
+    service.report_validation(&message, GossipValidation::Accept).unwrap();
+    wait_for_status(&service, "the message to count", |status| status.gossip.messages_valid == 1).await;
+}
+
+#[tokio::test]
+async fn test_mesh_stats_follow_peers_joining_and_leaving() {
+    let mut nodes = vec![FullNode::serving(HashMap::new()).await, FullNode::serving(HashMap::new()).await];
+    let mut service = client(&[&nodes[0], &nodes[1]], 2, 4);
+    service.start().unwrap();
+    let fork_digest = service.status().fork_digest;
+    service.set_status(status(fork_digest)).unwrap();
+    let topic = gossip_topic(fork_digest, LIGHT_CLIENT_FINALITY_UPDATE);
+    let mesh = |status: &ServiceStatus| status.gossip.mesh.get(&topic).cloned().unwrap_or_default();
+
+    wait_for_status(&service, "both peers to join the mesh", |status| mesh(status).mesh_peers == 2).await;
+    let joined = mesh(&service.status());
+    assert_eq!(joined.topic_peers, 2);
+    assert!(joined.grafts >= 2);
+
+    nodes.pop();
+    wait_for_status(&service, "the mesh to lose the peer", |status| {
+        let mesh = mesh(status);
+        mesh.mesh_peers == 1 && mesh.topic_peers == 1 && mesh.prunes >= 1
+    })
+    .await;
+    assert!(service.status().gossip.summary().contains("mesh peers on"));
+}
+
+#[tokio::test]
+async fn test_light_client_requests_through_the_stream_codec() {
+    let chunk = |fork_digest| ResponseChunk { fork_digest, ssz: vec![0; 8] };
+    let unavailable = ErrorResponse { code: ResponseCode::ResourceUnavailable, message: "not yet".to_string() };
+    let responses = HashMap::from([
+        (LightClientProtocol::UpdatesByRange, LightClientResponse::default()),
+        (LightClientProtocol::FinalityUpdate, LightClientResponse { chunks: Vec::new(), error: Some(unavailable) }),
+        (LightClientProtocol::OptimisticUpdate, LightClientResponse { chunks: vec![chunk([9; 4])], error: None }),
+    ]);
+    let node = FullNode::serving(responses).await;
+    let mut service = client(&[&node], 1, 4);
+    service.start().unwrap();
+    service.set_status(status(service.status().fork_digest)).unwrap();
+    wait_for_status(&service, "the peer to pass Status", |status| status.accepted_peers == 1).await;
+
+    assert_eq!(service.request_peers().await.unwrap(), [node.peer_id]);
+    // A peer with none of the periods sends no chunks.
+    assert!(service.request_updates_by_range(node.peer_id, 1000, 4).await.unwrap().is_empty());
+    let error = service.request_finality_update(node.peer_id).await.unwrap_err();
+    assert!(error.to_string().contains("error 3: not yet"), "{}", error);
+    // A chunk for another fork is refused before it is decoded.
+    let error = service.request_optimistic_update(node.peer_id).await.unwrap_err();
+    assert!(error.to_string().contains("fork digest 09090909"), "{}", error);
+}
+
+#[tokio::test]
+async fn test_peer_sending_invalid_gossip_is_ignored() {
+    let node = FullNode::serving(HashMap::new()).await;
+    let mut service = client(&[&node], 1, 4);
+    let mut gossip = service.start().unwrap();
+    let fork_digest = service.status().fork_digest;
+    service.set_status(status(fork_digest)).unwrap();
+    wait_for_status(&service, "the peer to pass Status", |status| status.accepted_peers == 1).await;
+
+    let topic = gossip_topic(fork_digest, LIGHT_CLIENT_FINALITY_UPDATE);
+    for seed in 0..6 {
+        node.publish(&topic, payload(seed)).await;
+        let message = tokio::time::timeout(common::TIMEOUT, gossip.next()).await.unwrap().unwrap();
+        service.report_validation(&message, GossipValidation::Reject).unwrap();
+    }
+    wait_for_status(&service, "the rejections to count", |status| status.gossip.messages_invalid == 6).await;
+    // Six invalid messages put the peer under the graylist threshold.
+    node.publish(&topic, payload(6)).await;
+    let next = tokio::time::timeout(std::time::Duration::from_secs(1), gossip.next()).await;
+    assert!(next.is_err(), "gossip from a graylisted peer was delivered");
+}
+
+#[tokio::test]
+async fn test_pruning_keeps_peers_that_serve_light_clients() {
+    let incapable = FullNode::not_serving().await;
+    let serving = FullNode::serving(HashMap::new()).await;
+    // Two bootnodes wanted, room for one: the one that cannot serve goes.
+    let mut service = client(&[&incapable, &serving], 2, 1);
+    service.start().unwrap();
+    service.set_status(status(service.status().fork_digest)).unwrap();
+    wait_until("only the serving peer to be left", || async {
+        service.request_peers().await.unwrap() == [serving.peer_id] && service.status().connected_peers == 1
+    })
+    .await;
+}
+
+/// The SSZ `BlockHeaderWithProof` the history network stores `header` as.
+fn header_with_proof(header: &ExecutionBlockHeader) -> Vec<u8> {
+    let (encoded, proof) = (header.encode(), vec![0; 16]);
+    let offsets = [8u32, 8 + encoded.len() as u32].map(u32::to_le_bytes).concat();
+    [offsets, encoded, proof].concat()
+}
+
+#[tokio::test]
+async fn test_portal_lookup_follows_nodes_to_the_content() {
+    let header = ExecutionBlockHeader {
+        parent_hash: [1; 32],
+        ommers_hash: [2; 32],
+        beneficiary: [3; 20],
+        state_root: [4; 32],
+        transactions_root: [5; 32],
+        receipts_root: [6; 32],
+        logs_bloom: [0; 256],
+        difficulty: U256::ZERO,
+        number: 21_000_000,
+        gas_limit: 30_000_000,
+        gas_used: 15_000_000,
+        timestamp: 1_730_000_000,
+        extra_data: Vec::new(),
+        mix_hash: [7; 32],
+        nonce: [0; 8],
+        base_fee_per_gas: Some(U256::from_u64(9)),
+        withdrawals_root: Some([8; 32]),
+        blob_gas_used: Some(0),
+        excess_blob_gas: Some(0),
+        parent_beacon_block_root: Some([9; 32]),
+        requests_hash: None,
+    };
+    let key = HistoryContentKey::BlockHeaderByHash(header.hash()).to_bytes();
+    let content = ContentResponse::Content(header_with_proof(&header));
+    let holder = FullNode::portal(HashMap::from([(key.clone(), content)])).await;
+    // The only node the client knows points it to the one that has it.
+    let pointer = FullNode::portal(HashMap::from([(key, ContentResponse::Nodes(vec![holder.address.clone()]))])).await;
+    let mut service = client(&[&pointer], 1, 4);
+    service.start().unwrap();
+    wait_until("the node to announce the history network", || async {
+        service.portal_peers(PortalNetwork::History).await.unwrap() == [pointer.peer_id]
+    })
+    .await;
+
+    assert_eq!(service.portal_block_header(header.hash()).await.unwrap(), header);
+    // Both are connected now, and neither has this.
+    let missing = service.portal_block_header([0xee; 32]).await.unwrap_err();
+    assert!(missing.to_string().contains("not found from 2 nodes"), "{}", missing);
+}
@@ -10,9 +10,11 @@
 //! - API nests headers as { beacon: {...}, execution: {...} }
 //! - API wraps everything in { data: {...} }
 
+use lumen_core::consensus::block::{execution_header_root, verify_execution_branch};
 use lumen_core::types::beacon::*;
 use lumen_core::types::execution::*;
-use serde::Deserialize;
+use lumen_core::types::uint::U256;
+use serde::{Deserialize, Serialize};
 
 // ---------------------------------------------------------------------------
 // Hex conversion helpers
@@ -118,6 +120,45 @@ impl ApiFinalityUpdateData {
     }
 }
 
+// ---------------------------------------------------------------------------
+// Beacon API: Period updates (GET /eth/v1/beacon/light_client/updates)
+// ---------------------------------------------------------------------------
+
+/// One element of the `light_client/updates` response array.
+#[derive(Deserialize)]
+pub struct ApiVersionedUpdate {
+    pub data: ApiLightClientUpdateData,
+}
+
+#[derive(Deserialize)]
+pub struct ApiLightClientUpdateData {
+    pub attested_header: ApiLightClientHeader,
+    pub next_sync_committee: ApiSyncCommittee,
+    pub next_sync_committee_branch: Vec<String>,
+    pub finalized_header: ApiLightClientHeader,
+    pub finality_branch: Vec<String>,
+    pub sync_aggregate: ApiSyncAggregate,
+    pub signature_slot: String,
+}
+
+impl ApiLightClientUpdateData {
+    pub fn to_core_update(&self) -> Result<LightClientUpdate, String> {
+        let parse_branch = |branch: &[String]| -> Result<Vec<[u8; 32]>, String> {
+            branch.iter().map(|s| hex_to_bytes32(s)).collect()
+        };
+
+        Ok(LightClientUpdate {
+            attested_header: self.attested_header.beacon.to_core()?,
+            finalized_header: self.finalized_header.beacon.to_core()?,
+            finality_branch: parse_branch(&self.finality_branch)?,
+            sync_aggregate: self.sync_aggregate.to_core()?,
+            signature_slot: parse_u64_string(&self.signature_slot)?,
+            next_sync_committee: Some(self.next_sync_committee.to_core()?),
+            next_sync_committee_branch: parse_branch(&self.next_sync_committee_branch)?,
+        })
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Beacon API: Shared sub-structures
 // ---------------------------------------------------------------------------
@@ -129,6 +170,29 @@ pub struct ApiLightClientHeader {
     pub execution_branch: Option<Vec<String>>,
 }
 
+impl ApiLightClientHeader {
+    /// The execution payload header, once `execution_branch` proves it in
+    /// the beacon header's `body_root`. A header without a payload has none;
+    /// one with a payload and a missing or wrong branch is rejected.
+    pub fn verified_execution(&self) -> Result<Option<ExecutionPayloadHeader>, String> {
+        let Some(execution) = &self.execution else {
+            return Ok(None);
+        };
+        let branch = self
+            .execution_branch
+            .as_ref()
+            .ok_or("missing execution_branch")?
+            .iter()
+            .map(|s| hex_to_bytes32(s))
+            .collect::<Result<Vec<_>, _>>()?;
+        let root = execution_header_root(&execution.to_ssz()?).map_err(|e| e.to_string())?;
+        if !verify_execution_branch(&root, &branch, &hex_to_bytes32(&self.beacon.body_root)?) {
+            return Err("execution_branch does not prove the execution header in body_root".to_string());
+        }
+        execution.to_core().map(Some)
+    }
+}
+
 #[derive(Deserialize)]
 pub struct ApiBeaconBlockHeader {
     pub slot: String,
@@ -156,14 +220,19 @@ pub struct ApiExecutionPayloadHeader {
     pub fee_recipient: String,
     pub state_root: String,
     pub receipts_root: String,
+    pub logs_bloom: String,
+    pub prev_randao: String,
     pub block_number: String,
     pub gas_limit: String,
     pub gas_used: String,
     pub timestamp: String,
+    pub extra_data: String,
     pub base_fee_per_gas: String,
     pub block_hash: String,
     pub transactions_root: String,
     pub withdrawals_root: String,
+    pub blob_gas_used: String,
+    pub excess_blob_gas: String,
 }
 
 impl ApiExecutionPayloadHeader {
@@ -177,14 +246,50 @@ impl ApiExecutionPayloadHeader {
             gas_limit: parse_u64_string(&self.gas_limit)?,
             gas_used: parse_u64_string(&self.gas_used)?,
             timestamp: parse_u64_string(&self.timestamp)?,
-            base_fee_per_gas: parse_u64_string(&self.base_fee_per_gas)?,
+            base_fee_per_gas: U256::from_dec_str(&self.base_fee_per_gas)?,
             block_hash: hex_to_bytes32(&self.block_hash)?,
             transactions_root: hex_to_bytes32(&self.transactions_root)?,
             withdrawals_root: hex_to_bytes32(&self.withdrawals_root)?,
+            blob_gas_used: parse_u64_string(&self.blob_gas_used)?,
+            excess_blob_gas: parse_u64_string(&self.excess_blob_gas)?,
         })
     }
+
+    /// The header's SSZ encoding (Deneb and later), which its root is taken over.
+    fn to_ssz(&self) -> Result<Vec<u8>, String> {
+        let logs_bloom = hex_to_bytes(&self.logs_bloom)?;
+        if logs_bloom.len() != 256 {
+            return Err(format!("logs_bloom: expected 256 bytes, got {}", logs_bloom.len()));
+        }
+        let extra_data = hex_to_bytes(&self.extra_data)?;
+        let mut base_fee_per_gas = U256::from_dec_str(&self.base_fee_per_gas)?.to_be_bytes();
+        base_fee_per_gas.reverse(); // SSZ uint256 is little-endian
+
+        let mut out = Vec::with_capacity(EXECUTION_HEADER_FIXED_LEN + extra_data.len());
+        out.extend_from_slice(&hex_to_bytes32(&self.parent_hash)?);
+        out.extend_from_slice(&hex_to_bytes20(&self.fee_recipient)?);
+        out.extend_from_slice(&hex_to_bytes32(&self.state_root)?);
+        out.extend_from_slice(&hex_to_bytes32(&self.receipts_root)?);
+        out.extend_from_slice(&logs_bloom);
+        out.extend_from_slice(&hex_to_bytes32(&self.prev_randao)?);
+        for number in [&self.block_number, &self.gas_limit, &self.gas_used, &self.timestamp] {
+            out.extend_from_slice(&parse_u64_string(number)?.to_le_bytes());
+        }
+        out.extend_from_slice(&(EXECUTION_HEADER_FIXED_LEN as u32).to_le_bytes()); // extra_data offset
+        out.extend_from_slice(&base_fee_per_gas);
+        out.extend_from_slice(&hex_to_bytes32(&self.block_hash)?);
+        out.extend_from_slice(&hex_to_bytes32(&self.transactions_root)?);
+        out.extend_from_slice(&hex_to_bytes32(&self.withdrawals_root)?);
+        out.extend_from_slice(&parse_u64_string(&self.blob_gas_used)?.to_le_bytes());
+        out.extend_from_slice(&parse_u64_string(&self.excess_blob_gas)?.to_le_bytes());
+        out.extend_from_slice(&extra_data);
+        Ok(out)
+    }
 }
 
+/// Fixed part of an SSZ `ExecutionPayloadHeader`; `extra_data` follows it.
+const EXECUTION_HEADER_FIXED_LEN: usize = 584;
+
 #[derive(Deserialize)]
 pub struct ApiSyncAggregate {
     pub sync_committee_bits: String,
@@ -262,7 +367,6 @@ pub struct ApiHeaderMessage {
 
 #[derive(Deserialize)]
 pub struct RpcGetProofResponse {
-    pub address: String,
     #[serde(rename = "accountProof")]
     pub account_proof: Vec<String>,
     pub balance: String,
@@ -271,6 +375,10 @@ pub struct RpcGetProofResponse {
     pub nonce: String,
     #[serde(rename = "storageHash")]
     pub storage_hash: String,
+    /// One entry per requested storage key, in request order. Absent when
+    /// no keys were asked for.
+    #[serde(rename = "storageProof", default)]
+    pub storage_proof: Vec<RpcStorageProof>,
 }
 
 impl RpcGetProofResponse {
@@ -291,6 +399,195 @@ impl RpcGetProofResponse {
             account: None, // decoded from the proof itself
         })
     }
+
+    /// Convert every entry of `storageProof`, in the order the RPC sent them.
+    /// They verify against the storage root of the account proven by
+    /// [`Self::to_core_account_proof`], not against the state root.
+    pub fn to_core_storage_proofs(&self) -> Result<Vec<StorageProof>, String> {
+        self.storage_proof
+            .iter()
+            .map(RpcStorageProof::to_core_storage_proof)
+            .collect()
+    }
+
+    /// Fields where the RPC's claimed values disagree with `verified`, the
+    /// account decoded from the proof. A claim that does not parse counts
+    /// as a disagreement.
+    ///
+    /// The claims are never used for anything, so a mismatch cannot cause a
+    /// wrong answer — but a proof that verifies alongside a false claim means
+    /// the RPC is buggy or lying, which is worth knowing.
+    pub fn discrepancies(&self, verified: &AccountState) -> Vec<Discrepancy> {
+        let mut found = Vec::new();
+        let mut check = |field: &str, claimed: &str, matches: bool, verified: String| {
+            if !matches {
+                found.push(Discrepancy {
+                    field: field.to_string(),
+                    claimed: claimed.to_string(),
+                    verified,
+                });
+            }
+        };
+
+        check(
+            "balance",
+            &self.balance,
+            U256::from_str_auto(&self.balance) == Ok(verified.balance),
+            verified.balance.to_hex(),
+        );
+        let claimed_nonce = self.nonce.strip_prefix("0x").map(|d| u64::from_str_radix(d, 16));
+        check(
+            "nonce",
+            &self.nonce,
+            claimed_nonce == Some(Ok(verified.nonce)),
+            format!("0x{:x}", verified.nonce),
+        );
+        check(
+            "codeHash",
+            &self.code_hash,
+            hash_claim_matches(&self.code_hash, &verified.code_hash, &AccountState::EMPTY_CODE_HASH),
+            format!("0x{}", hex::encode(verified.code_hash)),
+        );
+        check(
+            "storageHash",
+            &self.storage_hash,
+            hash_claim_matches(&self.storage_hash, &verified.storage_root, &AccountState::EMPTY_STORAGE_ROOT),
+            format!("0x{}", hex::encode(verified.storage_root)),
+        );
+        found
+    }
+}
+
+/// Whether a claimed 32-byte hash equals `verified`. Some clients report a
+/// zero hash instead of `empty` for accounts that do not exist, so that is
+/// accepted too.
+fn hash_claim_matches(claimed: &str, verified: &[u8; 32], empty: &[u8; 32]) -> bool {
+    match hex_to_bytes32(claimed) {
+        Ok(claimed) => claimed == *verified || (claimed == [0; 32] && verified == empty),
+        Err(_) => false,
+    }
+}
+
+/// An account field whose value claimed by the RPC differs from the
+/// verified one. Field names are as in eth_getProof.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Discrepancy {
+    pub field: String,
+    pub claimed: String,
+    pub verified: String,
+}
+
+/// One entry of the eth_getProof `storageProof` array.
+#[derive(Deserialize)]
+pub struct RpcStorageProof {
+    pub key: String,
+    pub value: String,
+    pub proof: Vec<String>,
+}
+
+impl RpcStorageProof {
+    /// Convert to lumen-core's storage proof. The claimed `value` is carried
+    /// along for display only — the verified value comes from the proof.
+    pub fn to_core_storage_proof(&self) -> Result<StorageProof, String> {
+        // Keys come back as quantities ("0x0") or full words; both are uint256 slots.
+        let key = U256::from_str_auto(&self.key)?.to_be_bytes();
+
+        Ok(StorageProof {
+            key,
+            value: U256::from_str_auto(&self.value)?,
+            proof: self
+                .proof
+                .iter()
+                .map(|hex_str| hex_to_bytes(hex_str))
+                .collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+/// A transaction receipt as returned by eth_getTransactionReceipt and
+/// eth_getBlockReceipts. Only the consensus fields are converted; the rest
+/// (gasUsed, effectiveGasPrice, ...) are not committed to by the receipts trie.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcReceipt {
+    pub transaction_index: String,
+    pub block_hash: String,
+    pub block_number: String,
+    /// Absent on some clients for legacy transactions.
+    #[serde(rename = "type", default)]
+    pub tx_type: Option<String>,
+    /// Absent on pre-Byzantium receipts, which carry a state root instead.
+    #[serde(default)]
+    pub status: Option<String>,
+    pub cumulative_gas_used: String,
+    pub logs_bloom: String,
+    pub logs: Vec<RpcLog>,
+}
+
+#[derive(Deserialize)]
+pub struct RpcLog {
+    pub address: String,
+    pub topics: Vec<String>,
+    pub data: String,
+}
+
+impl RpcReceipt {
+    pub fn tx_index(&self) -> Result<u64, String> {
+        crate::provider::parse_quantity(&self.transaction_index)
+    }
+
+    pub fn block_number(&self) -> Result<u64, String> {
+        crate::provider::parse_quantity(&self.block_number)
+    }
+
+    /// Convert to lumen-core's receipt, ready to be re-encoded into the receipts trie.
+    pub fn to_core_receipt(&self) -> Result<TransactionReceipt, String> {
+        let type_byte = match &self.tx_type {
+            Some(t) => crate::provider::parse_quantity(t)?,
+            None => 0,
+        };
+        let tx_type = u8::try_from(type_byte)
+            .ok()
+            .and_then(TransactionType::from_type_byte)
+            .ok_or_else(|| format!("unsupported receipt type {}", type_byte))?;
+
+        let status = match self.status.as_deref() {
+            Some(s) => match crate::provider::parse_quantity(s)? {
+                0 => 0,
+                1 => 1,
+                other => return Err(format!("invalid receipt status {}", other)),
+            },
+            None => return Err("pre-Byzantium receipts (no status field) are not supported".to_string()),
+        };
+
+        let logs_bloom: [u8; 256] = hex_to_bytes(&self.logs_bloom)?
+            .try_into()
+            .map_err(|b: Vec<u8>| format!("logsBloom must be 256 bytes, got {}", b.len()))?;
+
+        let logs = self
+            .logs
+            .iter()
+            .map(|log| {
+                Ok(Log {
+                    address: hex_to_bytes20(&log.address)?,
+                    topics: log
+                        .topics
+                        .iter()
+                        .map(|t| hex_to_bytes32(t))
+                        .collect::<Result<_, String>>()?,
+                    data: hex_to_bytes(&log.data)?,
+                })
+            })
+            .collect::<Result<_, String>>()?;
+
+        Ok(TransactionReceipt {
+            tx_type,
+            status,
+            cumulative_gas_used: crate::provider::parse_quantity(&self.cumulative_gas_used)?,
+            logs_bloom,
+            logs,
+        })
+    }
 }
 
 #[cfg(test)]
@@ -332,4 +629,185 @@ mod tests {
         assert_eq!(core.proposer_index, 42);
         assert_eq!(core.parent_root[31], 1);
     }
+
+    /// A JSON light client header whose `[0x20; 32]` execution branch
+    /// proves its execution header in its body root.
+    fn light_client_header() -> serde_json::Value {
+        let mut header = serde_json::json!({
+            "beacon": {
+                "slot": "100",
+                "proposer_index": "7",
+                "parent_root": format!("0x{}", "01".repeat(32)),
+                "state_root": format!("0x{}", "02".repeat(32)),
+                "body_root": "",
+            },
+            "execution": {
+                "parent_hash": format!("0x{}", "10".repeat(32)),
+                "fee_recipient": format!("0x{}", "11".repeat(20)),
+                "state_root": format!("0x{}", "12".repeat(32)),
+                "receipts_root": format!("0x{}", "13".repeat(32)),
+                "logs_bloom": format!("0x{}", "00".repeat(256)),
+                "prev_randao": format!("0x{}", "17".repeat(32)),
+                "block_number": "21000000",
+                "gas_limit": "30000000",
+                "gas_used": "12000000",
+                "timestamp": "1700000000",
+                "extra_data": "0x6c756d656e",
+                "base_fee_per_gas": "100",
+                "block_hash": format!("0x{}", "14".repeat(32)),
+                "transactions_root": format!("0x{}", "15".repeat(32)),
+                "withdrawals_root": format!("0x{}", "16".repeat(32)),
+                "blob_gas_used": "131072",
+                "excess_blob_gas": "0",
+            },
+            "execution_branch": vec![format!("0x{}", "20".repeat(32)); 4],
+        });
+        let execution: ApiExecutionPayloadHeader = serde_json::from_value(header["execution"].clone()).unwrap();
+        let root = execution_header_root(&execution.to_ssz().unwrap()).unwrap();
+        let body_root = lumen_core::consensus::block::execution_body_root(&root, &[[0x20; 32]; 4]);
+        header["beacon"]["body_root"] = format!("0x{}", hex::encode(body_root)).into();
+        header
+    }
+
+    #[test]
+    fn test_execution_header_verified_against_body_root() {
+        let verified = |header: &serde_json::Value| {
+            serde_json::from_value::<ApiLightClientHeader>(header.clone()).unwrap().verified_execution()
+        };
+        let header = light_client_header();
+        let execution = verified(&header).unwrap().unwrap();
+        assert_eq!(execution.state_root, [0x12; 32]);
+        assert_eq!(execution.base_fee_per_gas, U256::from_u64(100));
+
+        // A substituted state root no longer has the root the body commits to.
+        let mut tampered = header.clone();
+        tampered["execution"]["state_root"] = format!("0x{}", "66".repeat(32)).into();
+        assert!(verified(&tampered).unwrap_err().contains("execution_branch"));
+
+        // Nor does a payload with no branch, or a branch for another body.
+        let mut missing = header.clone();
+        missing.as_object_mut().unwrap().remove("execution_branch");
+        assert_eq!(verified(&missing).unwrap_err(), "missing execution_branch");
+        let mut wrong = header.clone();
+        wrong["execution_branch"][3] = format!("0x{}", "21".repeat(32)).into();
+        assert!(verified(&wrong).is_err());
+
+        // A header without an execution payload hands none over.
+        let mut bare = header;
+        bare.as_object_mut().unwrap().remove("execution");
+        assert!(verified(&bare).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_rpc_storage_proof_conversion() {
+        let json = r#"{"key":"0x1","value":"0x2a","proof":["0xc180","0x80"]}"#;
+        let rpc: RpcStorageProof = serde_json::from_str(json).unwrap();
+        let proof = rpc.to_core_storage_proof().unwrap();
+        assert_eq!(proof.key[31], 1);
+        assert_eq!(proof.value, U256::from_u64(42));
+        assert_eq!(proof.proof, vec![vec![0xC1, 0x80], vec![0x80]]);
+    }
+
+    #[test]
+    fn test_get_proof_response_with_storage_proofs() {
+        let json = r#"{
+            "address": "0x1111111111111111111111111111111111111111",
+            "accountProof": ["0xc180"],
+            "balance": "0x0",
+            "codeHash": "0x00",
+            "nonce": "0x0",
+            "storageHash": "0x00",
+            "storageProof": [
+                {"key": "0x0", "value": "0x0", "proof": []},
+                {"key": "0x0000000000000000000000000000000000000000000000000000000000000005", "value": "0x2a", "proof": ["0x80"]}
+            ]
+        }"#;
+        let rpc: RpcGetProofResponse = serde_json::from_str(json).unwrap();
+        let proofs = rpc.to_core_storage_proofs().unwrap();
+        assert_eq!(proofs.len(), 2);
+        assert_eq!(proofs[1].key[31], 5);
+        assert_eq!(proofs[1].value, U256::from_u64(42));
+        assert_eq!(proofs[1].proof, vec![vec![0x80]]);
+
+        // Without storage keys some clients leave the field out entirely.
+        let mut bare: serde_json::Value = serde_json::from_str(json).unwrap();
+        bare.as_object_mut().unwrap().remove("storageProof");
+        let rpc: RpcGetProofResponse = serde_json::from_value(bare).unwrap();
+        assert!(rpc.to_core_storage_proofs().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_get_proof_discrepancies() {
+        let verified = AccountState {
+            nonce: 5,
+            balance: U256::from_u64(1000),
+            storage_root: AccountState::EMPTY_STORAGE_ROOT,
+            code_hash: AccountState::EMPTY_CODE_HASH,
+        };
+        let mut rpc = RpcGetProofResponse {
+            account_proof: Vec::new(),
+            balance: "0x3e8".into(),
+            code_hash: format!("0x{}", hex::encode(AccountState::EMPTY_CODE_HASH)),
+            nonce: "0x5".into(),
+            storage_hash: format!("0x{}", "00".repeat(32)),
+            storage_proof: Vec::new(),
+        };
+        assert!(rpc.discrepancies(&verified).is_empty());
+
+        rpc.balance = "0x3e9".into();
+        rpc.nonce = "5".into();
+        rpc.code_hash = "0xzz".into();
+        let found = rpc.discrepancies(&verified);
+        let fields: Vec<&str> = found.iter().map(|d| d.field.as_str()).collect();
+        assert_eq!(fields, ["balance", "nonce", "codeHash"]);
+        assert_eq!(
+            found[0],
+            Discrepancy {
+                field: "balance".into(),
+                claimed: "0x3e9".into(),
+                verified: "0x3e8".into(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_rpc_receipt_to_core() {
+        let bloom = format!("0x{}", "00".repeat(256));
+        let json = format!(
+            r#"{{
+                "transactionHash": "0x{hash}",
+                "transactionIndex": "0x2",
+                "blockHash": "0x{hash}",
+                "blockNumber": "0x10",
+                "type": "0x2",
+                "status": "0x1",
+                "cumulativeGasUsed": "0x5208",
+                "gasUsed": "0x5208",
+                "logsBloom": "{bloom}",
+                "logs": [{{
+                    "address": "0x{addr}",
+                    "topics": ["0x{hash}"],
+                    "data": "0xdead",
+                    "logIndex": "0x0"
+                }}]
+            }}"#,
+            hash = "11".repeat(32),
+            addr = "22".repeat(20),
+            bloom = bloom,
+        );
+        let rpc: RpcReceipt = serde_json::from_str(&json).unwrap();
+        assert_eq!(rpc.tx_index().unwrap(), 2);
+        assert_eq!(rpc.block_number().unwrap(), 16);
+
+        let receipt = rpc.to_core_receipt().unwrap();
+        assert_eq!(receipt.tx_type, TransactionType::DynamicFee);
+        assert_eq!(receipt.status, 1);
+        assert_eq!(receipt.cumulative_gas_used, 21000);
+        assert_eq!(receipt.logs[0].address, [0x22; 20]);
+        assert_eq!(receipt.logs[0].data, vec![0xDE, 0xAD]);
+
+        let pre_byzantium = json.replace(r#""status": "0x1","#, r#""root": "0x00","#);
+        let rpc: RpcReceipt = serde_json::from_str(&pre_byzantium).unwrap();
+        assert!(rpc.to_core_receipt().is_err());
+    }
 }
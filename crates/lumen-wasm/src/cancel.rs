@@ -0,0 +1,115 @@
+//! Cancellation of long-running operations.
+//!
+//! `sync_to_head` and `start_finality_polling` can run for minutes, most of
+//! it waiting on fetches and timers. When the host no longer wants the
+//! result (the tab was hidden, the user navigated away) it aborts them with
+//! an `AbortSignal`, the worker with a `cancel` command. Either way the
+//! operation's future is dropped at its next await, together with whatever
+//! fetch or sleep it was waiting on, instead of running on to completion
+//! with nobody listening.
+
+use std::cell::{Cell, RefCell};
+use std::future::Future;
+use std::pin::{pin, Pin};
+use std::rc::Rc;
+use std::task::{Context, Poll, Waker};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+#[derive(Default)]
+struct Inner {
+    cancelled: Cell<bool>,
+    /// Tasks waiting in [`Cancellation::run`].
+    wakers: RefCell<Vec<Waker>>,
+}
+
+/// A shared flag that, once set, stops every operation run under it.
+#[derive(Clone, Default)]
+pub struct Cancellation {
+    inner: Rc<Inner>,
+}
+
+impl Cancellation {
+    /// Cancel when `signal` aborts (or now, if it already has).
+    pub fn follow(&self, signal: &web_sys::AbortSignal) {
+        if signal.aborted() {
+            self.cancel();
+            return;
+        }
+        let cancellation = self.clone();
+        let on_abort = Closure::once_into_js(move || cancellation.cancel());
+        let _ = signal.add_event_listener_with_callback("abort", on_abort.unchecked_ref());
+    }
+
+    pub fn cancel(&self) {
+        self.inner.cancelled.set(true);
+        for waker in self.inner.wakers.take() {
+            waker.wake();
+        }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.inner.cancelled.get()
+    }
+
+    /// Drive `operation` until it finishes or this is cancelled. Returns
+    /// None, having dropped `operation`, if cancelled first.
+    pub async fn run<F: Future>(&self, operation: F) -> Option<F::Output> {
+        let mut operation = pin!(operation);
+        std::future::poll_fn(|cx| self.poll_run(operation.as_mut(), cx)).await
+    }
+
+    fn poll_run<F: Future>(&self, operation: Pin<&mut F>, cx: &mut Context<'_>) -> Poll<Option<F::Output>> {
+        if self.is_cancelled() {
+            return Poll::Ready(None);
+        }
+        if let Poll::Ready(output) = operation.poll(cx) {
+            return Poll::Ready(Some(output));
+        }
+        let mut wakers = self.inner.wakers.borrow_mut();
+        if !wakers.iter().any(|waker| waker.will_wake(cx.waker())) {
+            wakers.push(cx.waker().clone());
+        }
+        Poll::Pending
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::executor::block_on;
+
+    /// Sets its flag when dropped.
+    struct DropFlag(Rc<Cell<bool>>);
+
+    impl Drop for DropFlag {
+        fn drop(&mut self) {
+            self.0.set(true);
+        }
+    }
+
+    #[test]
+    fn test_cancel_drops_the_waiting_operation() {
+        let cancellation = Cancellation::default();
+        let dropped = Rc::new(Cell::new(false));
+        let flag = DropFlag(dropped.clone());
+        let operation = async move {
+            let _flag = flag;
+            std::future::pending::<()>().await;
+        };
+        let (outcome, ()) = block_on(futures::future::join(cancellation.run(operation), async {
+            cancellation.cancel();
+        }));
+        assert_eq!(outcome, None);
+        assert!(dropped.get());
+        // Cancelled for good: later operations do not start.
+        assert_eq!(block_on(cancellation.run(async { 1 })), None);
+    }
+
+    #[test]
+    fn test_finished_operation_returns_its_output() {
+        let cancellation = Cancellation::default();
+        assert_eq!(block_on(cancellation.run(async { 7 })), Some(7));
+        assert!(!cancellation.is_cancelled());
+    }
+}
@@ -0,0 +1,365 @@
+//! Multi-source checkpoint selection.
+//!
+//! The checkpoint is the one input Lumen cannot verify cryptographically,
+//! so it is taken from several independent beacon API providers and only
+//! used if enough of them report the same finalized block root. The
+//! bootstrap is then fetched from a provider that agreed, and its header
+//! must hash to that root before the sync committee in it is accepted.
+//!
+//! A small built-in registry of public providers per network gives
+//! integrators working defaults; any list passed in explicitly replaces it.
+
+use crate::beacon_api::{hex_to_bytes32, ApiBootstrapResponse, ApiHeaderResponse};
+use crate::network::BeaconPayload;
+use lumen_core::consensus::checkpoint::{verify_checkpoint_consensus, VerifiedCheckpoint};
+use lumen_core::consensus::ssz::decode_bootstrap;
+use lumen_core::consensus::sync_committee::hash_beacon_block_header;
+use serde::Serialize;
+
+/// Beacon API path for the latest finalized block header.
+pub const FINALIZED_HEADER_PATH: &str = "/eth/v1/beacon/headers/finalized";
+
+/// Public beacon API providers, run by independent operators, that serve
+/// both the finalized header and light client bootstraps.
+const MAINNET_PROVIDERS: &[&str] = &[
+    "https://lodestar-mainnet.chainsafe.io",
+    "https://ethereum-beacon-api.publicnode.com",
+];
+const SEPOLIA_PROVIDERS: &[&str] = &[
+    "https://lodestar-sepolia.chainsafe.io",
+    "https://ethereum-sepolia-beacon-api.publicnode.com",
+];
+const HOLESKY_PROVIDERS: &[&str] = &[
+    "https://lodestar-holesky.chainsafe.io",
+    "https://ethereum-holesky-beacon-api.publicnode.com",
+];
+
+/// The built-in providers for a preset network, or `None` for networks
+/// without a registry entry (custom devnets must bring their own list).
+pub fn default_providers(network: &str) -> Option<&'static [&'static str]> {
+    match network.to_ascii_lowercase().as_str() {
+        "mainnet" => Some(MAINNET_PROVIDERS),
+        "sepolia" => Some(SEPOLIA_PROVIDERS),
+        "holesky" => Some(HOLESKY_PROVIDERS),
+        _ => None,
+    }
+}
+
+/// The providers to ask: `overrides` if any were given, else the registry's.
+pub fn resolve_providers(overrides: Vec<String>, network: &str) -> Result<Vec<String>, String> {
+    if !overrides.is_empty() {
+        return Ok(overrides);
+    }
+    default_providers(network)
+        .map(|urls| urls.iter().map(|url| url.to_string()).collect())
+        .ok_or_else(|| format!("no built-in checkpoint providers for {}; pass provider URLs", network))
+}
+
+/// What one provider reported for the finalized checkpoint.
+pub struct ProviderReport {
+    pub url: String,
+    /// `(block_root, slot)`, or why the provider could not be used.
+    pub checkpoint: Result<([u8; 32], u64), String>,
+    /// Round-trip time of the finalized header request.
+    pub latency_ms: f64,
+}
+
+/// One provider's entry in a [`HealthReport`].
+#[derive(Debug, Serialize)]
+pub struct ProviderHealth {
+    pub url: String,
+    pub healthy: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub slot: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub block_root: Option<String>,
+    /// Whether this provider reported the most widely agreed root.
+    pub agrees: bool,
+    pub latency_ms: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Health and agreement across a set of checkpoint providers.
+#[derive(Debug, Serialize)]
+pub struct HealthReport {
+    pub providers: Vec<ProviderHealth>,
+    /// The root the most providers agree on, if any provider answered.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub agreed_root: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub agreed_slot: Option<u64>,
+    /// Providers reporting `agreed_root`.
+    pub agreeing: usize,
+    /// Providers that answered with a self-consistent checkpoint.
+    pub healthy: usize,
+    pub total: usize,
+}
+
+/// Summarize provider reports for display or monitoring.
+pub fn health_report(reports: &[ProviderReport]) -> HealthReport {
+    let agreed = select_checkpoint(reports, 1).ok();
+    let agreed_root = agreed.as_ref().map(|c| c.block_root);
+    let providers: Vec<ProviderHealth> = reports
+        .iter()
+        .map(|report| match &report.checkpoint {
+            Ok((root, slot)) => ProviderHealth {
+                url: report.url.clone(),
+                healthy: true,
+                slot: Some(*slot),
+                block_root: Some(format!("0x{}", hex::encode(root))),
+                agrees: Some(*root) == agreed_root,
+                latency_ms: report.latency_ms,
+                error: None,
+            },
+            Err(e) => ProviderHealth {
+                url: report.url.clone(),
+                healthy: false,
+                slot: None,
+                block_root: None,
+                agrees: false,
+                latency_ms: report.latency_ms,
+                error: Some(e.clone()),
+            },
+        })
+        .collect();
+
+    HealthReport {
+        agreed_root: agreed_root.map(|root| format!("0x{}", hex::encode(root))),
+        agreed_slot: agreed.as_ref().map(|c| c.slot),
+        agreeing: agreed.map_or(0, |c| c.source_agreement),
+        healthy: providers.iter().filter(|p| p.healthy).count(),
+        total: providers.len(),
+        providers,
+    }
+}
+
+/// Parse a `/eth/v1/beacon/headers/finalized` response into `(block_root, slot)`.
+///
+/// The reported root must be the hash of the reported header; a provider
+/// that contradicts itself is not counted as a vote for either.
+pub fn parse_finalized_checkpoint(json: &str) -> Result<([u8; 32], u64), String> {
+    let response: ApiHeaderResponse =
+        serde_json::from_str(json).map_err(|e| format!("invalid header response: {}", e))?;
+    let root = hex_to_bytes32(&response.data.root)?;
+    let header = response.data.header.message.to_core()?;
+    if hash_beacon_block_header(&header) != root {
+        return Err(format!(
+            "reported root 0x{} is not the hash of the reported header",
+            hex::encode(root)
+        ));
+    }
+    Ok((root, header.slot))
+}
+
+/// Run the consensus check over the providers that answered.
+pub fn select_checkpoint(
+    reports: &[ProviderReport],
+    required_agreement: usize,
+) -> Result<VerifiedCheckpoint, String> {
+    let votes: Vec<([u8; 32], u64)> = reports
+        .iter()
+        .filter_map(|report| report.checkpoint.as_ref().ok().copied())
+        .collect();
+    let mut checkpoint =
+        verify_checkpoint_consensus(&votes, required_agreement).map_err(|e| e.to_string())?;
+    // Agreement is judged against every provider asked, not just those that answered.
+    checkpoint.total_sources = reports.len();
+    Ok(checkpoint)
+}
+
+/// Check that a bootstrap is for `block_root` and carries the branch that
+/// commits its sync committee to that block's state.
+pub fn check_bootstrap(bootstrap: &BeaconPayload, block_root: [u8; 32]) -> Result<(), String> {
+    let (header, branch_len) = match bootstrap {
+        BeaconPayload::Ssz(bytes) => {
+            let decoded = decode_bootstrap(bytes).map_err(|e| format!("invalid bootstrap: {}", e))?;
+            (decoded.bootstrap.header, decoded.bootstrap.current_sync_committee_branch.len())
+        }
+        BeaconPayload::Json(json) => {
+            let response: ApiBootstrapResponse =
+                serde_json::from_str(json).map_err(|e| format!("invalid bootstrap: {}", e))?;
+            (response.data.header.beacon.to_core()?, response.data.current_sync_committee_branch.len())
+        }
+    };
+    if hash_beacon_block_header(&header) != block_root {
+        return Err(format!(
+            "bootstrap header (slot {}) is not the agreed checkpoint 0x{}",
+            header.slot,
+            hex::encode(block_root)
+        ));
+    }
+    if branch_len == 0 {
+        return Err("bootstrap has no sync committee branch".to_string());
+    }
+    Ok(())
+}
+
+fn base_url(url: &str) -> &str {
+    url.trim_end_matches('/')
+}
+
+/// Ask every provider for its finalized checkpoint, concurrently.
+pub(crate) async fn fetch_reports(urls: &[String]) -> Vec<ProviderReport> {
+    let fetches = urls.iter().map(|url| async move {
+        let endpoint = format!("{}{}", base_url(url), FINALIZED_HEADER_PATH);
+        let started = js_sys::Date::now();
+        let checkpoint = match crate::network::fetch_text(&endpoint).await {
+            Ok(body) => parse_finalized_checkpoint(&body),
+            Err(e) => Err(e.to_string()),
+        };
+        ProviderReport {
+            url: url.clone(),
+            checkpoint,
+            latency_ms: js_sys::Date::now() - started,
+        }
+    });
+    futures::future::join_all(fetches).await
+}
+
+/// Fetch the bootstrap for `block_root` from the providers that voted for
+/// it, returning the first response that passes [`check_bootstrap`]. SSZ is
+/// requested first: the JSON bootstrap is several times the size.
+pub(crate) async fn fetch_bootstrap(
+    reports: &[ProviderReport],
+    block_root: [u8; 32],
+) -> Result<BeaconPayload, String> {
+    let mut errors = Vec::new();
+    let agreeing = reports
+        .iter()
+        .filter(|report| matches!(report.checkpoint, Ok((root, _)) if root == block_root));
+    for report in agreeing {
+        let url = format!(
+            "{}/eth/v1/beacon/light_client/bootstrap/0x{}",
+            base_url(&report.url),
+            hex::encode(block_root)
+        );
+        let result = match crate::network::fetch_beacon(&url).await {
+            Ok(body) => check_bootstrap(&body, block_root).map(|()| body),
+            Err(e) => Err(e.to_string()),
+        };
+        match result {
+            Ok(body) => return Ok(body),
+            Err(e) => errors.push(format!("{}: {}", report.url, e)),
+        }
+    }
+    Err(format!("no agreeing provider served a valid bootstrap ({})", errors.join("; ")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lumen_core::types::beacon::BeaconBlockHeader;
+
+    fn header_json(slot: u64, root: Option<[u8; 32]>) -> String {
+        let header = BeaconBlockHeader {
+            slot,
+            proposer_index: 42,
+            parent_root: [0x01; 32],
+            state_root: [0x02; 32],
+            body_root: [0x03; 32],
+        };
+        let root = root.unwrap_or_else(|| hash_beacon_block_header(&header));
+        serde_json::json!({
+            "data": {
+                "root": format!("0x{}", hex::encode(root)),
+                "header": { "message": {
+                    "slot": slot.to_string(),
+                    "proposer_index": "42",
+                    "parent_root": format!("0x{}", hex::encode([0x01; 32])),
+                    "state_root": format!("0x{}", hex::encode([0x02; 32])),
+                    "body_root": format!("0x{}", hex::encode([0x03; 32])),
+                }},
+            }
+        })
+        .to_string()
+    }
+
+    fn report(url: &str, checkpoint: Result<([u8; 32], u64), String>) -> ProviderReport {
+        ProviderReport {
+            url: url.to_string(),
+            checkpoint,
+            latency_ms: 50.0,
+        }
+    }
+
+    #[test]
+    fn test_parse_finalized_checkpoint() {
+        let (root, slot) = parse_finalized_checkpoint(&header_json(9_000_000, None)).unwrap();
+        assert_eq!(slot, 9_000_000);
+        assert_ne!(root, [0; 32]);
+
+        let err = parse_finalized_checkpoint(&header_json(9_000_000, Some([0xEE; 32]))).unwrap_err();
+        assert!(err.contains("not the hash"));
+    }
+
+    #[test]
+    fn test_select_checkpoint_counts_all_providers() {
+        let reports = vec![
+            report("a", Ok(([0xAA; 32], 100))),
+            report("b", Ok(([0xAA; 32], 100))),
+            report("c", Err("timeout".to_string())),
+        ];
+        let checkpoint = select_checkpoint(&reports, 2).unwrap();
+        assert_eq!(checkpoint.block_root, [0xAA; 32]);
+        assert_eq!(checkpoint.source_agreement, 2);
+        assert_eq!(checkpoint.total_sources, 3);
+
+        assert!(select_checkpoint(&reports, 3).is_err());
+    }
+
+    #[test]
+    fn test_provider_registry() {
+        assert_eq!(default_providers("Sepolia").unwrap().len(), 2);
+        assert!(default_providers("devnet").is_none());
+
+        let defaults = resolve_providers(vec![], "mainnet").unwrap();
+        assert_eq!(defaults[0], MAINNET_PROVIDERS[0]);
+        let custom = resolve_providers(vec!["https://mine.example".to_string()], "devnet").unwrap();
+        assert_eq!(custom, vec!["https://mine.example".to_string()]);
+        assert!(resolve_providers(vec![], "devnet").is_err());
+    }
+
+    #[test]
+    fn test_health_report() {
+        let reports = vec![
+            report("a", Ok(([0xAA; 32], 100))),
+            report("b", Ok(([0xBB; 32], 99))),
+            report("c", Ok(([0xAA; 32], 100))),
+            report("d", Err("HTTP error 503".to_string())),
+        ];
+        let health = health_report(&reports);
+        assert_eq!(health.agreed_root, Some(format!("0x{}", "aa".repeat(32))));
+        assert_eq!(health.agreed_slot, Some(100));
+        assert_eq!((health.agreeing, health.healthy, health.total), (2, 3, 4));
+        let agrees: Vec<bool> = health.providers.iter().map(|p| p.agrees).collect();
+        assert_eq!(agrees, vec![true, false, true, false]);
+        assert_eq!(health.providers[3].error.as_deref(), Some("HTTP error 503"));
+
+        let empty = health_report(&[]);
+        assert!(empty.agreed_root.is_none());
+        assert_eq!(empty.agreeing, 0);
+    }
+
+    #[test]
+    fn test_check_bootstrap_rejects_other_block() {
+        let bootstrap = serde_json::json!({
+            "data": {
+                "header": { "beacon": {
+                    "slot": "100", "proposer_index": "1",
+                    "parent_root": format!("0x{}", hex::encode([0; 32])),
+                    "state_root": format!("0x{}", hex::encode([0; 32])),
+                    "body_root": format!("0x{}", hex::encode([0; 32])),
+                }},
+                "current_sync_committee": { "pubkeys": [], "aggregate_pubkey": "0x" },
+                "current_sync_committee_branch": [],
+            }
+        })
+        .to_string();
+        let err = check_bootstrap(&BeaconPayload::Json(bootstrap), [0xAA; 32]).unwrap_err();
+        assert!(err.contains("not the agreed checkpoint"));
+
+        let err = check_bootstrap(&BeaconPayload::Ssz(vec![0; 16]), [0xAA; 32]).unwrap_err();
+        assert!(err.starts_with("invalid bootstrap"));
+    }
+}
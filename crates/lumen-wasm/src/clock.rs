@@ -0,0 +1,90 @@
+//! Where the client gets the time.
+//!
+//! Everything time-dependent in `LumenClient` — endpoint quarantines, the
+//! polling interval, future-slot rejection and staleness warnings — reads
+//! the clock through [`TimeSource`]. The default is the wall clock; tests
+//! inject a [`TestClock`] and move time by hand, so those paths run
+//! deterministically and without waiting.
+
+use futures::future::LocalBoxFuture;
+use std::cell::Cell;
+use std::rc::Rc;
+
+/// A clock and timer for one client.
+pub trait TimeSource {
+    /// Unix time in milliseconds.
+    fn now_ms(&self) -> f64;
+    /// Resolve after `ms` milliseconds of this clock's time.
+    fn sleep_ms<'a>(&'a self, ms: u32) -> LocalBoxFuture<'a, ()>;
+}
+
+/// [`TimeSource`] over `Date.now()` and `setTimeout`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+impl TimeSource for SystemClock {
+    fn now_ms(&self) -> f64 {
+        #[cfg(target_arch = "wasm32")]
+        return js_sys::Date::now();
+        // Native builds (tests) have no JS clock.
+        #[cfg(not(target_arch = "wasm32"))]
+        return std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0.0, |elapsed| elapsed.as_secs_f64() * 1000.0);
+    }
+
+    fn sleep_ms<'a>(&'a self, ms: u32) -> LocalBoxFuture<'a, ()> {
+        Box::pin(crate::network::sleep_ms(ms))
+    }
+}
+
+/// A [`TimeSource`] that only moves when told to. Sleeping advances it by
+/// the requested time and returns at once.
+#[derive(Debug, Default)]
+pub struct TestClock {
+    now_ms: Cell<f64>,
+}
+
+impl TestClock {
+    pub fn new(now_ms: f64) -> Rc<Self> {
+        Rc::new(Self { now_ms: Cell::new(now_ms) })
+    }
+
+    pub fn set(&self, now_ms: f64) {
+        self.now_ms.set(now_ms);
+    }
+
+    pub fn advance(&self, ms: f64) {
+        self.now_ms.set(self.now_ms.get() + ms);
+    }
+}
+
+/// Shared, so a test keeps a handle to the clock it gave the client.
+impl TimeSource for Rc<TestClock> {
+    fn now_ms(&self) -> f64 {
+        self.now_ms.get()
+    }
+
+    fn sleep_ms<'a>(&'a self, ms: u32) -> LocalBoxFuture<'a, ()> {
+        self.advance(f64::from(ms));
+        Box::pin(std::future::ready(()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_test_clock_moves_only_when_told() {
+        let clock = TestClock::new(1_000.0);
+        let source: Box<dyn TimeSource> = Box::new(clock.clone());
+        assert_eq!(source.now_ms(), 1_000.0);
+        clock.advance(500.0);
+        assert_eq!(source.now_ms(), 1_500.0);
+        futures::executor::block_on(source.sleep_ms(12_000));
+        assert_eq!(clock.now_ms.get(), 13_500.0);
+        clock.set(0.0);
+        assert_eq!(source.now_ms(), 0.0);
+    }
+}
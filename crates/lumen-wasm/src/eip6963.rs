@@ -0,0 +1,147 @@
+//! EIP-6963 provider discovery.
+//!
+//! Pages find injected providers by dispatching `eip6963:requestProvider`
+//! and collecting the `eip6963:announceProvider` events that answer it, each
+//! carrying `{ info, provider }`. This module builds that detail for a
+//! `LumenClient`, so Lumen can be listed next to wallet providers; the page
+//! dispatches the events itself.
+
+use crate::error::{ErrorCode, LumenError};
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+pub const DEFAULT_NAME: &str = "Lumen";
+
+/// Reverse-DNS identifier of the provider's publisher.
+pub const DEFAULT_RDNS: &str = "io.github.vanshsahay.lumen";
+
+/// A 96×96 SVG mark, as the data URI the spec requires.
+pub const DEFAULT_ICON: &str = "data:image/svg+xml,%3Csvg xmlns='http://www.w3.org/2000/svg' \
+     width='96' height='96' viewBox='0 0 96 96'%3E%3Crect width='96' height='96' rx='20' \
+     fill='%23111827'/%3E%3Cpath d='M48 14 26 50l22 13 22-13z' fill='%23facc15'/%3E%3Cpath \
+     d='m26 55 22 27 22-27-22 13z' fill='%23fde68a'/%3E%3C/svg%3E";
+
+/// `EIP6963ProviderInfo`: how a wallet picker shows the provider.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProviderInfo {
+    /// A UUIDv4, unique to this announcement session.
+    pub uuid: String,
+    pub name: String,
+    /// A data URI of a square image of at least 96×96 pixels.
+    pub icon: String,
+    pub rdns: String,
+}
+
+impl ProviderInfo {
+    /// Info with a fresh UUID; `None` fields take the Lumen defaults.
+    pub fn new(
+        name: Option<String>,
+        icon: Option<String>,
+        rdns: Option<String>,
+        uuid_bytes: [u8; 16],
+    ) -> Result<Self, String> {
+        let info = Self {
+            uuid: uuid_v4(uuid_bytes),
+            name: name.unwrap_or_else(|| DEFAULT_NAME.to_string()),
+            icon: icon.unwrap_or_else(|| DEFAULT_ICON.to_string()),
+            rdns: rdns.unwrap_or_else(|| DEFAULT_RDNS.to_string()),
+        };
+        if info.name.trim().is_empty() {
+            return Err("Provider name must not be empty".to_string());
+        }
+        if !info.icon.starts_with("data:image/") {
+            return Err("Provider icon must be a data:image/ URI".to_string());
+        }
+        if !is_reverse_dns(&info.rdns) {
+            return Err(format!("Provider rdns {:?} is not a reverse-DNS name like com.example.app", info.rdns));
+        }
+        Ok(info)
+    }
+}
+
+/// Format 16 random bytes as a version 4, variant 1 UUID.
+pub fn uuid_v4(mut bytes: [u8; 16]) -> String {
+    bytes[6] = bytes[6] & 0x0F | 0x40;
+    bytes[8] = bytes[8] & 0x3F | 0x80;
+    let hex = hex::encode(bytes);
+    format!("{}-{}-{}-{}-{}", &hex[..8], &hex[8..12], &hex[12..16], &hex[16..20], &hex[20..])
+}
+
+/// At least two dot-separated labels of letters, digits and hyphens.
+fn is_reverse_dns(name: &str) -> bool {
+    let labels: Vec<&str> = name.split('.').collect();
+    labels.len() >= 2
+        && labels.iter().all(|label| {
+            !label.is_empty()
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        })
+}
+
+/// The `{ info, provider }` detail of an `eip6963:announceProvider` event.
+///
+/// `provider.request` is `client.request` bound to `client`, so requests
+/// from the page go through the same verifying dispatch. Both objects are
+/// frozen, as the spec asks. `name`, `icon` and `rdns` default to Lumen's;
+/// embedders that ship their own build should pass their own. Build the
+/// detail once per page and announce the same one on every request, so the
+/// UUID stays stable.
+#[wasm_bindgen(unchecked_return_type = "EIP6963ProviderDetail")]
+pub fn eip6963_provider_detail(
+    #[wasm_bindgen(unchecked_param_type = "LumenClient")] client: JsValue,
+    name: Option<String>,
+    icon: Option<String>,
+    rdns: Option<String>,
+) -> Result<JsValue, JsValue> {
+    let request = js_sys::Reflect::get(&client, &"request".into())
+        .ok()
+        .and_then(|request| request.dyn_into::<js_sys::Function>().ok())
+        .ok_or_else(|| LumenError::new(ErrorCode::InvalidInput, "Expected a LumenClient"))?;
+
+    let mut uuid_bytes = [0u8; 16];
+    getrandom::getrandom(&mut uuid_bytes)
+        .map_err(|e| LumenError::new(ErrorCode::InvalidConfig, format!("No randomness for the UUID: {}", e)))?;
+    let info = ProviderInfo::new(name, icon, rdns, uuid_bytes)
+        .map_err(|e| LumenError::new(ErrorCode::InvalidConfig, e))?;
+    let info = info
+        .serialize(&serde_wasm_bindgen::Serializer::json_compatible())
+        .map_err(LumenError::serialization)?;
+
+    let provider = js_sys::Object::new();
+    js_sys::Reflect::set(&provider, &"request".into(), &request.bind0(&client))?;
+    let detail = js_sys::Object::new();
+    js_sys::Reflect::set(&detail, &"info".into(), &js_sys::Object::freeze(info.unchecked_ref::<js_sys::Object>()))?;
+    js_sys::Reflect::set(&detail, &"provider".into(), &js_sys::Object::freeze(&provider))?;
+    Ok(js_sys::Object::freeze(&detail).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_uuid_v4() {
+        let uuid = uuid_v4([0xFF; 16]);
+        assert_eq!(uuid, "ffffffff-ffff-4fff-bfff-ffffffffffff");
+        assert_eq!(uuid_v4([0; 16]), "00000000-0000-4000-8000-000000000000");
+    }
+
+    #[test]
+    fn test_provider_info_defaults_and_validation() {
+        let info = ProviderInfo::new(None, None, None, [0; 16]).unwrap();
+        assert_eq!(info.name, DEFAULT_NAME);
+        assert_eq!(info.rdns, DEFAULT_RDNS);
+        assert!(info.icon.starts_with("data:image/svg+xml,"));
+
+        let own = ProviderInfo::new(Some("Wallet".into()), None, Some("com.example.wallet".into()), [0; 16]);
+        assert_eq!(own.unwrap().rdns, "com.example.wallet");
+
+        assert!(ProviderInfo::new(Some(" ".into()), None, None, [0; 16]).is_err());
+        assert!(ProviderInfo::new(None, Some("https://example.com/icon.png".into()), None, [0; 16]).is_err());
+        for rdns in ["lumen", "com..example", "com.-example", "com.exa mple"] {
+            assert!(ProviderInfo::new(None, None, Some(rdns.into()), [0; 16]).is_err(), "{}", rdns);
+        }
+    }
+}
@@ -0,0 +1,282 @@
+//! ENS name resolution from verified storage.
+//!
+//! Resolving a name normally takes two eth_calls — the registry's
+//! `resolver(node)`, then the resolver's `addr(node)` — whose results would
+//! have to be trusted. Instead, the storage those calls read is fetched
+//! with eth_getProof and verified against the finalized state root:
+//!
+//! 1. the registry's `records[node]`, holding the owner and resolver;
+//! 2. the resolver's `recordVersions[node]`, then
+//!    `versionable_addresses[version][node][60]`, the ETH address.
+//!
+//! Step 2 depends on the resolver's storage layout, so only the ENS
+//! PublicResolver deployments in [`public_resolvers`] are read. Names set to
+//! any other resolver, including wildcard and offchain (CCIP-read)
+//! resolvers, are refused rather than answered from a guessed layout.
+//!
+//! Names must already be normalized (ENSIP-15). ASCII names are lowercased;
+//! others are refused, since normalizing them needs the Unicode tables.
+
+use crate::logging::lumen_log;
+use crate::provider::DispatchError;
+use crate::{FetchedAccount, LumenClient};
+use lumen_core::execution::proof::keccak256;
+use lumen_core::types::uint::U256;
+use serde::Serialize;
+
+/// The ENS registry (`ENSRegistryWithFallback`), at the same address on
+/// mainnet, Sepolia and Holesky.
+pub const REGISTRY: &str = "0x00000000000c2e074ec69a0dfb2997ba6c7d2e1e";
+
+/// SLIP-44 coin type of ETH, the key `addr(node)` reads.
+const ETH_COIN_TYPE: u64 = 60;
+
+/// Registry slot of `mapping(bytes32 => Record) records`. A `Record` takes
+/// two slots: the owner, then the resolver with the TTL packed above it.
+const RECORDS_SLOT: u64 = 0;
+
+/// PublicResolver slots of `ResolverBase.recordVersions` and
+/// `AddrResolver.versionable_addresses`.
+const RECORD_VERSIONS_SLOT: u64 = 0;
+const ADDRESSES_SLOT: u64 = 2;
+
+/// ENS PublicResolver deployments on `chain_id`, lowercase.
+pub fn public_resolvers(chain_id: u64) -> &'static [&'static str] {
+    match chain_id {
+        1 => &["0x231b0ee14048e9dccd1d247744d114a4eb5e8e63"],
+        11_155_111 => &["0x8fade66b79cc9f707ab26799354482eb93a5b7dd"],
+        _ => &[],
+    }
+}
+
+/// What `resolve_ens` returns.
+#[derive(Debug, Serialize)]
+pub struct EnsResolution {
+    pub name: String,
+    /// The name's namehash.
+    pub node: String,
+    /// None if the name has no record in the registry.
+    pub resolver: Option<String>,
+    /// None if the name is unregistered or has no ETH address set.
+    pub address: Option<String>,
+    /// The finalized block every proof was taken at.
+    pub block_number: u64,
+    pub proof_nodes: usize,
+}
+
+/// EIP-137 namehash of `name`.
+pub fn namehash(name: &str) -> Result<[u8; 32], String> {
+    if !name.is_ascii() {
+        return Err(format!("{} is not ASCII; pass its ENSIP-15 normalized form", name));
+    }
+    let name = name.to_ascii_lowercase();
+    let mut node = [0u8; 32];
+    if name.is_empty() {
+        return Ok(node);
+    }
+    for label in name.rsplit('.') {
+        if label.is_empty() {
+            return Err(format!("{} has an empty label", name));
+        }
+        node = keccak256(&[node, keccak256(label.as_bytes())].concat());
+    }
+    Ok(node)
+}
+
+/// `value` as a 32-byte big-endian word.
+fn word(value: u64) -> [u8; 32] {
+    U256::from_u64(value).to_be_bytes()
+}
+
+/// Storage slot of `mapping[key]` for a mapping declared at `slot`.
+pub fn mapping_slot(key: &[u8; 32], slot: &[u8; 32]) -> [u8; 32] {
+    keccak256(&[*key, *slot].concat())
+}
+
+/// Registry slots of `records[node].owner` and `records[node].resolver`.
+pub fn record_slots(node: &[u8; 32]) -> [[u8; 32]; 2] {
+    let owner = mapping_slot(node, &word(RECORDS_SLOT));
+    let mut resolver = owner;
+    // owner + 1, wrapping as the EVM does.
+    for byte in resolver.iter_mut().rev() {
+        let (sum, carry) = byte.overflowing_add(1);
+        *byte = sum;
+        if !carry {
+            break;
+        }
+    }
+    [owner, resolver]
+}
+
+/// PublicResolver slot of `recordVersions[node]`.
+pub fn version_slot(node: &[u8; 32]) -> [u8; 32] {
+    mapping_slot(node, &word(RECORD_VERSIONS_SLOT))
+}
+
+/// PublicResolver slot of `versionable_addresses[version][node][60]`.
+pub fn addr_slot(node: &[u8; 32], version: u64) -> [u8; 32] {
+    let by_version = mapping_slot(&word(version), &word(ADDRESSES_SLOT));
+    let by_node = mapping_slot(node, &by_version);
+    mapping_slot(&word(ETH_COIN_TYPE), &by_node)
+}
+
+/// The address in the low 20 bytes of a storage word, or None if zero.
+pub fn word_address(value: &U256) -> Option<[u8; 20]> {
+    let bytes = value.to_be_bytes();
+    let address: [u8; 20] = bytes[12..].try_into().expect("20 bytes");
+    (address != [0; 20]).then_some(address)
+}
+
+/// An address stored as Solidity `bytes`, or None if unset. Values under
+/// 32 bytes sit in the slot itself, left-aligned, with twice their length
+/// in the lowest byte.
+pub fn stored_address(value: &U256) -> Result<Option<[u8; 20]>, String> {
+    let bytes = value.to_be_bytes();
+    let tag = bytes[31];
+    if tag & 1 == 1 {
+        return Err("address record is longer than 31 bytes".to_string());
+    }
+    match tag / 2 {
+        0 => Ok(None),
+        20 => Ok(Some(bytes[..20].try_into().expect("20 bytes"))),
+        len => Err(format!("address record is {} bytes, not 20", len)),
+    }
+}
+
+/// Verified values of `fetched`'s storage slots, in request order.
+fn slot_values(fetched: &FetchedAccount) -> Result<Vec<U256>, String> {
+    fetched
+        .storage
+        .iter()
+        .map(|slot| slot.result.clone().map_err(|e| format!("Storage proof verification: {}", e)))
+        .collect()
+}
+
+fn hex_address(address: &[u8; 20]) -> String {
+    format!("0x{}", hex::encode(address))
+}
+
+impl LumenClient {
+    /// Resolve `name` to its ETH address from storage proofs, all at the
+    /// finalized block.
+    pub(crate) async fn resolve_ens_name(&self, name: &str) -> Result<EnsResolution, DispatchError> {
+        let node = namehash(name).map_err(DispatchError::InvalidParams)?;
+        let header = self.verified_execution_header()?;
+        let (state_root, block_number) = (header.state_root, header.block_number);
+        let mut resolution = EnsResolution {
+            name: name.to_string(),
+            node: format!("0x{}", hex::encode(node)),
+            resolver: None,
+            address: None,
+            block_number,
+            proof_nodes: 0,
+        };
+
+        let record_slots = record_slots(&node);
+        let (record, proof_nodes) = self
+            .try_endpoints(|endpoint| async move {
+                let registry = self.fetch_proof_at(endpoint, REGISTRY, &record_slots, state_root, block_number).await?;
+                Ok((slot_values(&registry)?, registry.proof_nodes + registry.storage_proof_nodes))
+            })
+            .await?;
+        resolution.proof_nodes += proof_nodes;
+        // The registry falls back to its pre-2020 predecessor for names with
+        // no owner here; that fallback is not followed, so they count as unregistered.
+        let (Some(_), Some(resolver)) = (word_address(&record[0]), word_address(&record[1])) else {
+            return Ok(resolution);
+        };
+        let resolver = hex_address(&resolver);
+        if !public_resolvers(self.network.chain_id).contains(&resolver.as_str()) {
+            return Err(DispatchError::Verification(format!(
+                "{} uses resolver {}, which is not a known ENS PublicResolver; its storage layout is unknown",
+                name, resolver
+            )));
+        }
+
+        let (address, proof_nodes) = self
+            .try_endpoints(|endpoint| {
+                let resolver = &resolver;
+                async move {
+                    let versions = self
+                        .fetch_proof_at(endpoint, resolver, &[version_slot(&node)], state_root, block_number)
+                        .await?;
+                    let version = slot_values(&versions)?[0]
+                        .to_u64()
+                        .ok_or("record version does not fit in 64 bits")?;
+                    let addresses = self
+                        .fetch_proof_at(endpoint, resolver, &[addr_slot(&node, version)], state_root, block_number)
+                        .await?;
+                    let address = stored_address(&slot_values(&addresses)?[0])?;
+                    let proof_nodes = versions.proof_nodes
+                        + versions.storage_proof_nodes
+                        + addresses.proof_nodes
+                        + addresses.storage_proof_nodes;
+                    Ok((address, proof_nodes))
+                }
+            })
+            .await?;
+        resolution.proof_nodes += proof_nodes;
+        resolution.address = address.map(|a| hex_address(&a));
+        lumen_log!(Debug, "rpc", "Resolved {} via {} at block {}", name, resolver, block_number);
+        resolution.resolver = Some(resolver);
+        Ok(resolution)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex32(s: &str) -> [u8; 32] {
+        hex::decode(s).unwrap().try_into().unwrap()
+    }
+
+    #[test]
+    fn test_namehash_matches_eip137() {
+        assert_eq!(namehash("").unwrap(), [0; 32]);
+        assert_eq!(
+            namehash("eth").unwrap(),
+            hex32("93cdeb708b7545dc668eb9280176169d1c33cfd8ed6f04690a0bcc88a93fc4ae")
+        );
+        assert_eq!(
+            namehash("foo.eth").unwrap(),
+            hex32("de9b09fd7c5f901e23a3f19fecc54828e9c848539801e86591bd9801b019f84f")
+        );
+        assert_eq!(namehash("FOO.eth").unwrap(), namehash("foo.eth").unwrap());
+        assert!(namehash("foo..eth").is_err());
+        assert!(namehash("ünicode.eth").is_err());
+    }
+
+    #[test]
+    fn test_storage_slots() {
+        let node = namehash("foo.eth").unwrap();
+        let [owner, resolver] = record_slots(&node);
+        assert_eq!(owner, keccak256(&[node, [0; 32]].concat()));
+        assert_eq!(U256::from_be_bytes(resolver), U256::from_be_bytes(owner).checked_add(&U256::from_u64(1)).unwrap());
+        assert_eq!(version_slot(&node), owner);
+
+        let inner = keccak256(&[word(3), word(2)].concat());
+        let middle = keccak256(&[node, inner].concat());
+        assert_eq!(addr_slot(&node, 3), keccak256(&[word(60), middle].concat()));
+    }
+
+    #[test]
+    fn test_decode_stored_values() {
+        // Resolver with a TTL packed above it.
+        let mut record = [0u8; 32];
+        record[4..12].copy_from_slice(&300u64.to_be_bytes());
+        record[12..].copy_from_slice(&[0x23; 20]);
+        assert_eq!(word_address(&U256::from_be_bytes(record)), Some([0x23; 20]));
+        assert_eq!(word_address(&U256::ZERO), None);
+
+        let mut short = [0u8; 32];
+        short[..20].copy_from_slice(&[0xd8; 20]);
+        short[31] = 40;
+        assert_eq!(stored_address(&U256::from_be_bytes(short)), Ok(Some([0xd8; 20])));
+        assert_eq!(stored_address(&U256::ZERO), Ok(None));
+        short[31] = 41;
+        assert!(stored_address(&U256::from_be_bytes(short)).is_err());
+        short[31] = 8;
+        assert!(stored_address(&U256::from_be_bytes(short)).is_err());
+    }
+}
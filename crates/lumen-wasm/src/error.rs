@@ -0,0 +1,176 @@
+//! Structured errors thrown to JavaScript.
+//!
+//! Every error a `LumenClient` method returns is a JS `Error` whose message
+//! is the human-readable description, with three extra properties callers
+//! can branch on instead of matching message text:
+//!
+//! - `code`: a stable number, grouped by category in blocks of 1000
+//! - `kind`: the same code as a snake_case name
+//! - `category`: `parse`, `consensus`, `proof`, `network` or `internal`
+//!
+//! Codes are never renumbered or reused; new ones are appended to their block.
+
+use serde::Serialize;
+use std::fmt;
+use wasm_bindgen::prelude::*;
+
+/// What part of the pipeline an error came from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCategory {
+    /// The caller's input could not be decoded.
+    Parse,
+    /// Beacon chain data failed verification, or the client is not far enough along.
+    Consensus,
+    /// An execution-layer proof failed verification.
+    Proof,
+    /// Data could not be fetched.
+    Network,
+    /// A bug: something that should not fail did.
+    Internal,
+}
+
+/// Stable error codes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+#[repr(u16)]
+pub enum ErrorCode {
+    /// A malformed argument: address, hash, slot, hex string.
+    InvalidInput = 1000,
+    /// JSON that does not parse or does not have the expected shape.
+    InvalidJson = 1001,
+    /// SSZ bytes that do not decode.
+    InvalidSsz = 1002,
+    /// An unknown network or malformed network / provider configuration.
+    InvalidConfig = 1003,
+    /// A persisted state snapshot that cannot be restored.
+    InvalidSnapshot = 1004,
+    /// A worker message for a protocol version this build does not speak.
+    UnsupportedProtocol = 1005,
+
+    /// An update or bootstrap failed BLS or Merkle branch verification.
+    VerificationFailed = 2000,
+    /// No BLS-verified execution state root yet.
+    NotSynced = 2001,
+    /// Checkpoint providers did not agree on a finalized root.
+    NoCheckpointConsensus = 2002,
+    /// Data belongs to a different network than the client.
+    NetworkMismatch = 2003,
+
+    /// A Merkle-Patricia or receipts proof failed verification.
+    ProofInvalid = 3000,
+
+    /// A request to a beacon node or RPC failed.
+    RequestFailed = 4000,
+    /// No endpoints were given to fetch from.
+    NoEndpoints = 4001,
+    /// Too many RPC requests are already queued behind the rate limit.
+    RateLimited = 4002,
+    /// The caller aborted the operation before it finished.
+    Cancelled = 4003,
+
+    /// A result could not be converted to a JS value.
+    Serialization = 9000,
+}
+
+impl ErrorCode {
+    pub fn category(self) -> ErrorCategory {
+        match self as u16 {
+            1000..=1999 => ErrorCategory::Parse,
+            2000..=2999 => ErrorCategory::Consensus,
+            3000..=3999 => ErrorCategory::Proof,
+            4000..=4999 => ErrorCategory::Network,
+            _ => ErrorCategory::Internal,
+        }
+    }
+}
+
+/// An error with a stable code, converted to a JS `Error` at the boundary.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LumenError {
+    pub code: ErrorCode,
+    pub message: String,
+}
+
+impl LumenError {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+
+    pub fn serialization(error: impl fmt::Display) -> Self {
+        Self::new(ErrorCode::Serialization, format!("Serialization error: {}", error))
+    }
+
+    /// The properties set on the JS error object, besides `message`.
+    pub fn info(&self) -> ErrorInfo {
+        ErrorInfo {
+            code: self.code as u16,
+            kind: self.code,
+            category: self.code.category(),
+            message: self.message.clone(),
+        }
+    }
+}
+
+impl fmt::Display for LumenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.message, self.code as u16)
+    }
+}
+
+/// Serialized form of a [`LumenError`].
+#[derive(Debug, Serialize)]
+pub struct ErrorInfo {
+    pub code: u16,
+    pub kind: ErrorCode,
+    pub category: ErrorCategory,
+    pub message: String,
+}
+
+impl From<LumenError> for JsValue {
+    fn from(error: LumenError) -> Self {
+        let js_error = js_sys::Error::new(&error.message);
+        js_error.set_name("LumenError");
+        // `info` is plain data; serializing it cannot fail.
+        if let Ok(props) = error
+            .info()
+            .serialize(&serde_wasm_bindgen::Serializer::json_compatible())
+        {
+            js_sys::Object::assign(&js_error, &props.unchecked_into());
+        }
+        js_error.into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_categories_follow_code_blocks() {
+        assert_eq!(ErrorCode::InvalidJson.category(), ErrorCategory::Parse);
+        assert_eq!(ErrorCode::NotSynced.category(), ErrorCategory::Consensus);
+        assert_eq!(ErrorCode::ProofInvalid.category(), ErrorCategory::Proof);
+        assert_eq!(ErrorCode::NoEndpoints.category(), ErrorCategory::Network);
+        assert_eq!(ErrorCode::Serialization.category(), ErrorCategory::Internal);
+    }
+
+    #[test]
+    fn test_error_info_shape() {
+        let error = LumenError::new(ErrorCode::VerificationFailed, "bad signature");
+        let json = serde_json::to_value(error.info()).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "code": 2000,
+                "kind": "verification_failed",
+                "category": "consensus",
+                "message": "bad signature",
+            })
+        );
+        assert_eq!(error.to_string(), "bad signature (2000)");
+    }
+}
@@ -0,0 +1,69 @@
+//! Structured client events for `set_event_handler`.
+//!
+//! Every verification outcome that used to reach only the console is also
+//! described here, so a UI can react to it. Events are delivered after the
+//! state change they describe, and only ever describe verified state — a
+//! rejected update changes nothing and says why.
+
+use serde::Serialize;
+
+/// An event delivered to the handler as `{ type, ...fields }`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ClientEvent {
+    /// The BLS-verified finalized header advanced.
+    HeadAdvanced { finalized_slot: u64, period: u64 },
+    /// The verified execution state root changed.
+    ExecutionRootChanged { state_root: String, block_number: u64 },
+    /// An update parsed but failed verification; the state is unchanged.
+    UpdateRejected { update_slot: u64, reason: String },
+    /// An RPC served a valid proof but claimed account fields that disagree
+    /// with it. `endpoint` is unset for proofs passed in by the caller.
+    RpcDiscrepancy {
+        address: String,
+        endpoint: Option<String>,
+        discrepancies: Vec<crate::beacon_api::Discrepancy>,
+    },
+    /// Input could not be processed at all (malformed JSON, bad encoding),
+    /// or the finality poller could not fetch an update.
+    /// `code` is the `LumenError` code the call also throws.
+    Error { source: &'static str, code: u16, message: String },
+}
+
+impl ClientEvent {
+    pub fn execution_root_changed(state_root: [u8; 32], block_number: u64) -> Self {
+        ClientEvent::ExecutionRootChanged {
+            state_root: format!("0x{}", hex::encode(state_root)),
+            block_number,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_event_shape() {
+        let json = serde_json::to_value(ClientEvent::HeadAdvanced {
+            finalized_slot: 100,
+            period: 0,
+        })
+        .unwrap();
+        assert_eq!(json, serde_json::json!({"type": "head_advanced", "finalized_slot": 100, "period": 0}));
+
+        let json = serde_json::to_value(ClientEvent::execution_root_changed([0xAB; 32], 7)).unwrap();
+        assert_eq!(json["type"], "execution_root_changed");
+        assert_eq!(json["state_root"], format!("0x{}", "ab".repeat(32)));
+
+        let json = serde_json::to_value(ClientEvent::Error {
+            source: "process_update",
+            code: 1001,
+            message: "bad json".to_string(),
+        })
+        .unwrap();
+        assert_eq!(json["type"], "error");
+        assert_eq!(json["source"], "process_update");
+        assert_eq!(json["code"], 1001);
+    }
+}
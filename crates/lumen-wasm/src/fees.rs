@@ -0,0 +1,202 @@
+//! Fee estimates from verified headers, for `eth_gasPrice` and
+//! `eth_feeHistory`.
+//!
+//! Base fees and gas used are header fields, so they come from BLS-verified
+//! payload headers (see [`crate::history`]) or headers hashed back along
+//! parent hashes from one, and the next block's base fee follows from the
+//! newest of them by the EIP-1559 formula. Priority fees are another
+//! matter: what a block's transactions tipped is only committed to through
+//! its transactions and receipts, so the tip here is a fixed heuristic,
+//! [`SUGGESTED_PRIORITY_FEE_WEI`], and responses that include it are
+//! marked `heuristic` in their metadata.
+//!
+//! "latest" means the finalized block, as for every other method, so the
+//! base fees reported trail the chain head by a few minutes.
+
+use crate::provider::{to_quantity, DispatchError};
+use crate::LumenClient;
+use lumen_core::execution::header::{next_base_fee, ExecutionBlockHeader};
+use lumen_core::types::beacon::ExecutionPayloadHeader;
+use lumen_core::types::uint::U256;
+
+/// The priority fee suggested by `eth_gasPrice` and reported for every
+/// percentile of `eth_feeHistory`'s rewards: 1 gwei. A heuristic, not
+/// derived from any block.
+pub const SUGGESTED_PRIORITY_FEE_WEI: u64 = 1_000_000_000;
+
+/// Most blocks one `eth_feeHistory` call reports; larger counts are cut to
+/// the newest this many. Each block missing from the history is one header
+/// fetch.
+pub const MAX_FEE_HISTORY_BLOCKS: u64 = 64;
+
+/// The fee-relevant fields of a verified header.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FeeBlock {
+    pub number: u64,
+    pub base_fee_per_gas: U256,
+    pub gas_used: u64,
+    pub gas_limit: u64,
+}
+
+impl From<&ExecutionPayloadHeader> for FeeBlock {
+    fn from(header: &ExecutionPayloadHeader) -> Self {
+        Self {
+            number: header.block_number,
+            base_fee_per_gas: header.base_fee_per_gas,
+            gas_used: header.gas_used,
+            gas_limit: header.gas_limit,
+        }
+    }
+}
+
+impl From<&ExecutionBlockHeader> for FeeBlock {
+    fn from(header: &ExecutionBlockHeader) -> Self {
+        Self {
+            number: header.number,
+            // Every block since the merge is post-London.
+            base_fee_per_gas: header.base_fee_per_gas.unwrap_or(U256::ZERO),
+            gas_used: header.gas_used,
+            gas_limit: header.gas_limit,
+        }
+    }
+}
+
+/// `eth_gasPrice`: the base fee of the block after `header`, plus the
+/// suggested priority fee.
+pub fn gas_price(header: &ExecutionPayloadHeader) -> U256 {
+    next_base_fee(header.base_fee_per_gas, header.gas_used, header.gas_limit)
+        .checked_add(&U256::from_u64(SUGGESTED_PRIORITY_FEE_WEI))
+        .unwrap_or(U256::MAX)
+}
+
+/// Parse `eth_feeHistory`'s block count: a hex quantity or a plain number.
+pub fn parse_block_count(value: Option<&serde_json::Value>) -> Result<u64, String> {
+    let count = match value {
+        Some(serde_json::Value::String(s)) => crate::provider::parse_quantity(s)?,
+        Some(serde_json::Value::Number(n)) => n.as_u64().ok_or("block count must be a non-negative integer")?,
+        _ => return Err("missing block count".to_string()),
+    };
+    if count == 0 {
+        return Err("block count must be at least 1".to_string());
+    }
+    Ok(count)
+}
+
+/// Parse `eth_feeHistory`'s reward percentiles: numbers from 0 to 100, in
+/// ascending order. Absent means none.
+pub fn parse_percentiles(value: Option<&serde_json::Value>) -> Result<Vec<f64>, String> {
+    let Some(value) = value.filter(|v| !v.is_null()) else {
+        return Ok(Vec::new());
+    };
+    let percentiles = value
+        .as_array()
+        .ok_or("reward percentiles must be an array")?
+        .iter()
+        .map(|p| p.as_f64().filter(|p| (0.0..=100.0).contains(p)))
+        .collect::<Option<Vec<f64>>>()
+        .ok_or("reward percentiles must be numbers from 0 to 100")?;
+    if percentiles.windows(2).any(|pair| pair[0] > pair[1]) {
+        return Err("reward percentiles must be in ascending order".to_string());
+    }
+    Ok(percentiles)
+}
+
+/// The `eth_feeHistory` result for `blocks`, oldest first. `baseFeePerGas`
+/// has one more entry than there are blocks: the next block's base fee.
+/// Blob fees are left out, since their parameters change with every fork.
+pub fn fee_history(blocks: &[FeeBlock], percentiles: &[f64]) -> serde_json::Value {
+    let Some(newest) = blocks.last() else {
+        return serde_json::json!({ "oldestBlock": to_quantity(0), "baseFeePerGas": [], "gasUsedRatio": [] });
+    };
+    let mut base_fees: Vec<String> = blocks.iter().map(|block| block.base_fee_per_gas.to_hex()).collect();
+    base_fees.push(next_base_fee(newest.base_fee_per_gas, newest.gas_used, newest.gas_limit).to_hex());
+    let gas_used_ratio: Vec<f64> = blocks
+        .iter()
+        .map(|block| match block.gas_limit {
+            0 => 0.0,
+            limit => block.gas_used as f64 / limit as f64,
+        })
+        .collect();
+
+    let mut result = serde_json::json!({
+        "oldestBlock": to_quantity(blocks[0].number),
+        "baseFeePerGas": base_fees,
+        "gasUsedRatio": gas_used_ratio,
+    });
+    if !percentiles.is_empty() {
+        let tip = U256::from_u64(SUGGESTED_PRIORITY_FEE_WEI).to_hex();
+        result["reward"] = serde_json::json!(vec![vec![tip; percentiles.len()]; blocks.len()]);
+    }
+    result
+}
+
+impl LumenClient {
+    /// The verified headers of the `count` blocks up to `newest`, oldest
+    /// first: from the history if it holds all of them, otherwise walked
+    /// back from the nearest verified block.
+    pub(crate) async fn fee_history_blocks(&self, newest: u64, count: u64) -> Result<Vec<FeeBlock>, DispatchError> {
+        let oldest = newest.saturating_sub(count.min(MAX_FEE_HISTORY_BLOCKS) - 1);
+        let recorded: Option<Vec<FeeBlock>> = {
+            let history = self.execution_history.borrow();
+            (oldest..=newest).map(|n| history.get(n).map(FeeBlock::from)).collect()
+        };
+        if let Some(blocks) = recorded {
+            return Ok(blocks);
+        }
+
+        let chain = self
+            .try_endpoints(|endpoint| self.fetch_header_chain(endpoint, oldest))
+            .await?;
+        Ok(chain
+            .iter()
+            .rev()
+            .map(|(header, _)| FeeBlock::from(header))
+            .filter(|block| block.number <= newest)
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn block(number: u64, base_fee: u64, gas_used: u64) -> FeeBlock {
+        FeeBlock { number, base_fee_per_gas: U256::from_u64(base_fee), gas_used, gas_limit: 30_000_000 }
+    }
+
+    #[test]
+    fn test_fee_history() {
+        let blocks = [block(100, 1_000_000_000, 30_000_000), block(101, 1_125_000_000, 0)];
+        let history = fee_history(&blocks, &[]);
+        assert_eq!(history["oldestBlock"], "0x64");
+        // The newest block was empty, so the next base fee falls by 1/8.
+        assert_eq!(history["baseFeePerGas"], json!(["0x3b9aca00", "0x430e2340", "0x3aac5ed8"]));
+        assert_eq!(history["gasUsedRatio"], json!([1.0, 0.0]));
+        assert!(history.get("reward").is_none());
+
+        let history = fee_history(&blocks, &[25.0, 75.0]);
+        assert_eq!(history["reward"], json!([["0x3b9aca00", "0x3b9aca00"], ["0x3b9aca00", "0x3b9aca00"]]));
+    }
+
+    #[test]
+    fn test_gas_price_adds_the_suggested_tip() {
+        let mut header = crate::tests::state([0; 32]).latest_execution_payload_header.unwrap();
+        header.base_fee_per_gas = U256::from_u64(1_000_000_000);
+        header.gas_used = header.gas_limit / 2;
+        assert_eq!(gas_price(&header), U256::from_u64(2_000_000_000));
+    }
+
+    #[test]
+    fn test_parse_params() {
+        assert_eq!(parse_block_count(Some(&json!("0x5"))), Ok(5));
+        assert_eq!(parse_block_count(Some(&json!(5))), Ok(5));
+        assert!(parse_block_count(Some(&json!("0x0"))).is_err());
+        assert!(parse_block_count(None).is_err());
+
+        assert_eq!(parse_percentiles(None), Ok(vec![]));
+        assert_eq!(parse_percentiles(Some(&json!([10, 50.5]))), Ok(vec![10.0, 50.5]));
+        assert!(parse_percentiles(Some(&json!([50, 10]))).is_err());
+        assert!(parse_percentiles(Some(&json!([101]))).is_err());
+    }
+}
@@ -0,0 +1,112 @@
+//! One report on how far the client's answers can be relied on right now.
+//!
+//! Verification never becomes less sound with time, but it does become
+//! less current: a client whose finality updates stopped arriving still
+//! proves balances correctly, at a block that may be hours old. The report
+//! collects what a host app needs to decide whether to show a "data may be
+//! stale" banner — how old the trust anchor and the finalized head are,
+//! when the head last advanced, and whether the client can reach an RPC
+//! endpoint and the P2P network at all.
+
+use crate::rpc_health::EndpointHealth;
+use serde::{Deserialize, Serialize};
+
+/// Overall verdict, worst first.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HealthStatus {
+    /// No finality update has been applied yet; nothing can be answered.
+    Syncing,
+    /// The finalized head trails the wall clock by more than
+    /// `STALE_HEAD_SLOTS`: answers are verified but old.
+    Stale,
+    /// Every configured RPC endpoint is quarantined, so proof-backed
+    /// requests will fail until one recovers.
+    Degraded,
+    Healthy,
+}
+
+/// The P2P layer's connection state. The P2P bridge runs on the JS side,
+/// so this is whatever it last reported through `set_p2p_status`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct P2pStatus {
+    /// `"direct-webtransport"`, `"direct-webrtc"`, `"relay"` or `"disconnected"`.
+    pub connection_mode: String,
+    pub peer_count: u32,
+}
+
+impl Default for P2pStatus {
+    fn default() -> Self {
+        Self { connection_mode: "disconnected".to_string(), peer_count: 0 }
+    }
+}
+
+/// What `health` returns. Ages and lags are None on networks without a
+/// known genesis time, where slots cannot be placed on the wall clock.
+#[derive(Debug, Serialize)]
+pub struct ClientHealth {
+    pub status: HealthStatus,
+    pub head_slot: u64,
+    /// Slot of the state the client started from: the checkpoint, or the
+    /// snapshot it was resumed from.
+    pub checkpoint_slot: u64,
+    pub checkpoint_age_ms: Option<f64>,
+    /// Slots the finalized head trails the wall clock by.
+    pub finalized_lag_slots: Option<u64>,
+    /// Unix time in ms the finalized head last advanced, if it has since
+    /// the client was created.
+    pub last_update_at_ms: Option<f64>,
+    /// As `get_rpc_health`.
+    pub rpc_endpoints: Vec<EndpointHealth>,
+    pub p2p: P2pStatus,
+}
+
+/// The verdict for a client with `head_slot`, `lag_slots` behind the wall
+/// clock, whose `configured` RPC endpoints have the `tracked` health.
+/// Endpoints never tried count as usable.
+pub fn status(
+    head_slot: u64,
+    lag_slots: Option<u64>,
+    configured: &[String],
+    tracked: &[EndpointHealth],
+) -> HealthStatus {
+    if head_slot == 0 {
+        return HealthStatus::Syncing;
+    }
+    if lag_slots.is_some_and(|lag| lag > lumen_core::consensus::clock::STALE_HEAD_SLOTS) {
+        return HealthStatus::Stale;
+    }
+    let usable = |url: &String| tracked.iter().find(|e| &e.url == url).is_none_or(|e| e.healthy);
+    if !configured.is_empty() && !configured.iter().any(usable) {
+        return HealthStatus::Degraded;
+    }
+    HealthStatus::Healthy
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rpc_health::{RpcHealth, QUARANTINE_AFTER};
+    use lumen_core::consensus::clock::STALE_HEAD_SLOTS;
+
+    #[test]
+    fn test_status_reports_the_worst_problem() {
+        let configured = vec!["a".to_string(), "b".to_string()];
+        let mut rpc = RpcHealth::default();
+        for _ in 0..QUARANTINE_AFTER {
+            rpc.record_failure("a", "down", 0.0);
+        }
+        // b is untried, so still usable.
+        assert_eq!(status(100, Some(2), &configured, &rpc.report(0.0)), HealthStatus::Healthy);
+
+        for _ in 0..QUARANTINE_AFTER {
+            rpc.record_failure("b", "down", 0.0);
+        }
+        let tracked = rpc.report(0.0);
+        assert_eq!(status(100, Some(2), &configured, &tracked), HealthStatus::Degraded);
+        assert_eq!(status(100, Some(STALE_HEAD_SLOTS + 1), &configured, &tracked), HealthStatus::Stale);
+        assert_eq!(status(0, None, &configured, &tracked), HealthStatus::Syncing);
+        // No endpoints configured is a choice, not a fault.
+        assert_eq!(status(100, None, &[], &[]), HealthStatus::Healthy);
+    }
+}
@@ -0,0 +1,118 @@
+//! Recently finalized execution payload headers, for historical state queries.
+//!
+//! Every execution payload header the client BLS-verifies is kept, oldest
+//! first, up to [`HISTORY_LEN`]. A query at one of those blocks uses its state
+//! root directly. A query at a block in between walks parent hashes back from
+//! the next recorded block after it, which is at most an epoch or so away,
+//! instead of from the head.
+
+use lumen_core::types::beacon::ExecutionPayloadHeader;
+use std::collections::VecDeque;
+
+/// Headers kept. Finality advances about once per epoch, so 64 headers
+/// reach back roughly seven hours on mainnet.
+pub const HISTORY_LEN: usize = 64;
+
+#[derive(Debug, Default)]
+pub struct ExecutionHistory {
+    /// Ascending by block number.
+    headers: VecDeque<ExecutionPayloadHeader>,
+}
+
+impl ExecutionHistory {
+    /// Record a verified header. Headers at or before the newest one are
+    /// ignored: finality only moves forward.
+    pub fn record(&mut self, header: &ExecutionPayloadHeader) {
+        if self.headers.back().is_some_and(|newest| newest.block_number >= header.block_number) {
+            return;
+        }
+        if self.headers.len() == HISTORY_LEN {
+            self.headers.pop_front();
+        }
+        self.headers.push_back(header.clone());
+    }
+
+    /// Every recorded header, oldest first.
+    pub fn headers(&self) -> impl Iterator<Item = &ExecutionPayloadHeader> {
+        self.headers.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.headers.len()
+    }
+
+    /// The recorded header for block `number`, if any.
+    pub fn get(&self, number: u64) -> Option<&ExecutionPayloadHeader> {
+        self.anchor_for(number).filter(|header| header.block_number == number)
+    }
+
+    /// The recorded header of the block with hash `block_hash`.
+    pub fn with_block_hash(&self, block_hash: &[u8; 32]) -> Option<&ExecutionPayloadHeader> {
+        self.headers.iter().rev().find(|header| &header.block_hash == block_hash)
+    }
+
+    /// The recorded header whose receipts trie has root `receipts_root`.
+    pub fn with_receipts_root(&self, receipts_root: &[u8; 32]) -> Option<&ExecutionPayloadHeader> {
+        self.headers.iter().rev().find(|header| &header.receipts_root == receipts_root)
+    }
+
+    /// The oldest recorded header at or after block `number`: the nearest
+    /// trusted hash to walk back from.
+    pub fn anchor_for(&self, number: u64) -> Option<&ExecutionPayloadHeader> {
+        let index = self.headers.partition_point(|header| header.block_number < number);
+        self.headers.get(index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lumen_core::types::uint::U256;
+
+    fn header(block_number: u64) -> ExecutionPayloadHeader {
+        ExecutionPayloadHeader {
+            parent_hash: [0; 32],
+            fee_recipient: [0; 20],
+            state_root: [block_number as u8; 32],
+            receipts_root: [0; 32],
+            block_number,
+            gas_limit: 30_000_000,
+            gas_used: 0,
+            timestamp: 0,
+            base_fee_per_gas: U256::from_u64(7),
+            block_hash: [0; 32],
+            transactions_root: [0; 32],
+            withdrawals_root: [0; 32],
+            blob_gas_used: 0,
+            excess_blob_gas: 0,
+        }
+    }
+
+    #[test]
+    fn test_lookup_and_anchor() {
+        let mut history = ExecutionHistory::default();
+        assert!(history.anchor_for(100).is_none());
+        for number in [100, 132, 164] {
+            history.record(&header(number));
+        }
+        // Stale or repeated headers are ignored.
+        history.record(&header(120));
+        history.record(&header(164));
+
+        assert_eq!(history.get(132).unwrap().state_root, [132; 32]);
+        assert!(history.get(140).is_none());
+        assert_eq!(history.anchor_for(140).unwrap().block_number, 164);
+        assert_eq!(history.anchor_for(50).unwrap().block_number, 100);
+        assert!(history.anchor_for(165).is_none());
+    }
+
+    #[test]
+    fn test_oldest_headers_are_dropped() {
+        let mut history = ExecutionHistory::default();
+        for number in 0..HISTORY_LEN as u64 + 10 {
+            history.record(&header(number * 32));
+        }
+        assert!(history.get(9 * 32).is_none());
+        assert_eq!(history.anchor_for(0).unwrap().block_number, 10 * 32);
+    }
+}
@@ -13,17 +13,59 @@
 //! - Accepts raw beacon API / RPC JSON — format conversion handled internally
 
 mod beacon_api;
+mod cancel;
+mod checkpoint;
+mod clock;
+mod eip6963;
+mod ens;
+mod error;
+mod events;
+mod fees;
+mod health;
+mod history;
+mod logging;
+mod metrics;
 mod network;
+mod preflight;
 mod provider;
+mod rate_limit;
+mod registry;
+mod resources;
+mod response_cache;
+mod rpc_health;
 mod state;
+mod subscriptions;
+mod sync;
+mod typescript;
+mod watcher;
+mod worker;
 
 use lumen_core::types::beacon::*;
 use lumen_core::types::execution::*;
+use lumen_core::types::network::NetworkConfig;
+use lumen_core::types::uint::U256;
+use lumen_core::execution::account::StorageSlotOutcome;
+use lumen_core::execution::header::ExecutionBlockHeader;
+use lumen_core::execution::proof::ProofError;
 use lumen_core::consensus::checkpoint::parse_checkpoint_hash;
 use lumen_core::consensus::light_client::initialize_from_bootstrap;
+use lumen_core::consensus::sync_committee::VerificationError;
 use serde::{Deserialize, Serialize};
+use error::{ErrorCode, LumenError};
+use logging::lumen_log;
+use network::BeaconPayload;
+use std::cell::{Cell, RefCell};
 use wasm_bindgen::prelude::*;
 
+pub use clock::{SystemClock, TestClock, TimeSource};
+pub use network::{FetchProvider, NetworkError, NetworkProvider};
+pub use registry::LumenRegistry;
+
+/// `initThreadPool(n)` in JS: starts the Web Workers that parallel sync
+/// committee verification runs on. Await it once before constructing a client.
+#[cfg(all(feature = "threads", target_arch = "wasm32"))]
+pub use wasm_bindgen_rayon::init_thread_pool;
+
 /// Set up panic hook on WASM initialization.
 /// This ensures Rust panics are logged to the browser console with full stack traces.
 #[wasm_bindgen(start)]
@@ -31,6 +73,29 @@ pub fn init() {
     console_error_panic_hook::set_once();
 }
 
+/// Choose which log messages are emitted.
+///
+/// filter: a default level optionally followed by per-target overrides,
+/// e.g. `"warn"`, `"info,proof=debug"` or `"off,consensus=warn"`. Levels are
+/// error, warn, info, debug, trace and off; targets are client, checkpoint,
+/// consensus, sync, proof, rpc and events. The default is `"info"`.
+#[wasm_bindgen]
+pub fn set_log_level(filter: &str) -> Result<(), JsValue> {
+    let filter = logging::LogFilter::parse(filter).map_err(|e| LumenError::new(ErrorCode::InvalidConfig, e))?;
+    logging::set_filter(filter);
+    Ok(())
+}
+
+/// Send log records to `callback` as `{ level, target, message }` instead
+/// of the console; pass `undefined` to log to the console again.
+#[wasm_bindgen]
+pub fn set_log_sink(
+    #[wasm_bindgen(unchecked_param_type = "((record: LogRecord) => void) | undefined")]
+    callback: Option<js_sys::Function>,
+) {
+    logging::set_sink(callback);
+}
+
 /// The main Lumen client — holds verified chain state and exposes verification methods.
 ///
 /// This struct is the WASM-side counterpart of the TypeScript `LumenProvider`.
@@ -38,7 +103,45 @@ pub fn init() {
 /// and provides methods to verify proofs against that view.
 #[wasm_bindgen]
 pub struct LumenClient {
-    state: LightClientState,
+    /// Verified chain state. Behind a RefCell so the `&self` updates of a
+    /// running `start_finality_polling` and calls from JS can share it.
+    state: RefCell<LightClientState>,
+    /// The network the client was configured for; answers `eth_chainId`.
+    network: NetworkConfig,
+    /// Execution RPC endpoints used by `handle_request`. Untrusted: every
+    /// answer they give is verified (or flagged as unverified) before use.
+    rpc_endpoints: Vec<String>,
+    /// Success and latency per RPC endpoint, used to order and quarantine them.
+    rpc_health: RefCell<rpc_health::RpcHealth>,
+    /// Token buckets every execution RPC request is paced through.
+    rate_limiter: RefCell<rate_limit::RateLimiter>,
+    /// Recent block and proof results, reused within a block.
+    response_cache: RefCell<response_cache::ResponseCache>,
+    /// Submitted transactions awaiting a verified receipt. Behind a RefCell
+    /// so `&self` request handlers can register what they broadcast.
+    watcher: RefCell<watcher::TransactionWatcher>,
+    /// `subscribe` callbacks, invoked after verified state advances.
+    subscriptions: RefCell<subscriptions::SubscriptionRegistry<js_sys::Function>>,
+    /// Receives every `events::ClientEvent`, if set.
+    event_handler: RefCell<Option<js_sys::Function>>,
+    /// Stops the running `start_finality_polling`, if any.
+    polling: RefCell<Option<cancel::Cancellation>>,
+    /// Which block `fetch_and_verify_*` and proof-backed requests ask for.
+    proof_mode: Cell<provider::ProofMode>,
+    /// Execution payload headers verified so far, for historical queries.
+    execution_history: RefCell<history::ExecutionHistory>,
+    /// Carries execution RPC requests; the Fetch API unless replaced.
+    transport: Box<dyn network::NetworkProvider>,
+    /// Wall clock for quarantines, polling and slot checks; replaceable in tests.
+    clock: Box<dyn clock::TimeSource>,
+    /// Aggregate committee keys of recent participation bitfields.
+    aggregate_cache: RefCell<lumen_core::consensus::aggregate_cache::AggregatePubkeyCache>,
+    /// Finalized slot of the state the client was created from.
+    checkpoint_slot: u64,
+    /// Wall-clock time the finalized head last advanced.
+    last_update_at_ms: Cell<Option<f64>>,
+    /// Last reported by the JS P2P bridge.
+    p2p_status: RefCell<health::P2pStatus>,
 }
 
 #[wasm_bindgen]
@@ -52,13 +155,27 @@ impl LumenClient {
     /// After initialization, all verification is purely cryptographic.
     #[wasm_bindgen(constructor)]
     pub fn new(checkpoint_hash: &str) -> Result<LumenClient, JsValue> {
+        Self::new_with_config(checkpoint_hash, "mainnet")
+    }
+
+    /// Initialize a client from a checkpoint on a network other than mainnet.
+    ///
+    /// `network_config` is either a preset name (`"mainnet"`, `"sepolia"`,
+    /// `"holesky"`) or a JSON `NetworkConfig` with `name`, `chain_id`,
+    /// `genesis_validators_root` and `fork_version`. The genesis root and
+    /// fork version go into the signing domain, so a client configured for
+    /// one network cannot be fed another network's updates.
+    pub fn new_with_config(checkpoint_hash: &str, network_config: &str) -> Result<LumenClient, JsValue> {
+        let network = parse_network_config(network_config).map_err(|e| LumenError::new(ErrorCode::InvalidConfig, e))?;
         let block_root = parse_checkpoint_hash(checkpoint_hash)
-            .map_err(|e| JsValue::from_str(&format!("Invalid checkpoint hash: {}", e)))?;
+            .map_err(|e| LumenError::new(ErrorCode::InvalidInput, format!("Invalid checkpoint hash: {}", e)))?;
 
-        log_to_console(&format!(
-            "[Lumen] Initializing with checkpoint: 0x{}",
+        lumen_log!(
+            Info,
+            "client",
+            "Initializing with checkpoint: 0x{}",
             hex::encode(block_root)
-        ));
+        );
 
         // Create a bootstrap state. In production, this would be fetched from
         // multiple beacon API endpoints and verified for consensus.
@@ -78,25 +195,17 @@ impl LumenClient {
             current_sync_committee_branch: vec![], // Skip verification for bootstrap
         };
 
-        // Ethereum mainnet genesis validators root
-        let genesis_validators_root = [
-            0x4b, 0x36, 0x3d, 0xb9, 0x4e, 0x28, 0x61, 0x20, 0xd7, 0x6e, 0xb9, 0x05, 0x34,
-            0x0f, 0xdd, 0x4e, 0x54, 0xbf, 0xe9, 0xf0, 0x6b, 0xf3, 0x3f, 0xf6, 0xcf, 0x5a,
-            0xd2, 0x7f, 0x51, 0x1b, 0xfe, 0x95,
-        ];
-
-        // Deneb fork version (current as of 2024)
-        let fork_version = [0x04, 0x00, 0x00, 0x00];
-
-        let state = initialize_from_bootstrap(&bootstrap, genesis_validators_root, fork_version)
-            .map_err(|e| JsValue::from_str(&format!("Failed to initialize: {}", e)))?;
+        let state = initialize_from_bootstrap(
+            &bootstrap,
+            network.genesis_validators_root,
+            network.fork_version,
+        )
+        .map_err(|e| LumenError::new(ErrorCode::VerificationFailed, format!("Failed to initialize: {}", e)))?;
 
-        log_to_console("[Lumen] Client initialized successfully");
-        log_to_console(&format!(
-            "[Lumen] Trust state: checkpoint-based initialization, awaiting P2P sync"
-        ));
+        lumen_log!(Info, "client", "Client initialized successfully on {}", network.name);
+        lumen_log!(Debug, "client", "Trust state: checkpoint-based initialization, awaiting P2P sync");
 
-        Ok(LumenClient { state })
+        Ok(LumenClient::from_state(state, network))
     }
 
     /// Process a light client update received from a peer.
@@ -107,43 +216,41 @@ impl LumenClient {
     ///
     /// IMPORTANT: Every field in the update is cryptographically verified.
     /// The update source is untrusted — we verify everything regardless.
-    pub fn process_update(&mut self, update_json: &str) -> Result<bool, JsValue> {
+    pub fn process_update(&self, update_json: &str) -> Result<bool, JsValue> {
         let update: LightClientUpdate = serde_json::from_str(update_json)
-            .map_err(|e| JsValue::from_str(&format!("Invalid update JSON: {}", e)))?;
+            .map_err(|e| {
+                self.input_error("process_update", ErrorCode::InvalidJson, format!("Invalid update JSON: {}", e))
+            })?;
 
-        let current_slot = self.state.finalized_header.slot;
-        let genesis_validators_root = self.state.genesis_validators_root;
+        Ok(self.apply_update(&update))
+    }
 
-        match lumen_core::consensus::light_client::process_light_client_update(
-            &mut self.state,
-            &update,
-            current_slot,
-            genesis_validators_root,
-        ) {
-            Ok(()) => {
-                log_to_console(&format!(
-                    "[Lumen] State advanced to slot {}",
-                    self.state.finalized_header.slot
-                ));
-                Ok(true)
-            }
-            Err(e) => {
-                log_to_console(&format!("[Lumen] Update rejected: {}", e));
-                Ok(false)
-            }
-        }
+    /// Process a light client update given as SSZ bytes.
+    ///
+    /// Same verification as `process_update`, without the hex and JSON
+    /// decoding: `update_ssz` is the body of
+    /// one update from the octet-stream response of
+    ///   GET /eth/v1/beacon/light_client/updates
+    /// (each is preceded by its length and fork digest, which the caller
+    /// strips), as a `Uint8Array`.
+    pub fn process_update_ssz(&self, update_ssz: &[u8]) -> Result<bool, JsValue> {
+        let decoded = lumen_core::consensus::ssz::decode_update(update_ssz)
+            .map_err(|e| {
+                self.input_error("process_update_ssz", ErrorCode::InvalidSsz, format!("Invalid update SSZ: {}", e))
+            })?;
+        Ok(self.apply_update(&decoded.update))
     }
 
     /// Get the current verified head slot number.
     /// This is the latest finalized slot we have cryptographic proof for.
     pub fn head_slot(&self) -> u64 {
-        self.state.finalized_header.slot
+        self.state.borrow().finalized_header.slot
     }
 
     /// Get the current verified state root (hex encoded).
     /// This root is used to verify all Merkle-Patricia trie proofs.
     pub fn state_root(&self) -> String {
-        format!("0x{}", hex::encode(self.state.finalized_header.state_root))
+        format!("0x{}", hex::encode(self.state.borrow().finalized_header.state_root))
     }
 
     /// Get the current verified execution state root, if available.
@@ -151,6 +258,7 @@ impl LumenClient {
     /// eth_getProof verifies against.
     pub fn execution_state_root(&self) -> Option<String> {
         self.state
+            .borrow()
             .verified_state_root()
             .map(|r| format!("0x{}", hex::encode(r)))
     }
@@ -163,113 +271,478 @@ impl LumenClient {
     /// IMPORTANT: the proof is verified against our internally held state root.
     /// The caller cannot pass in a fake state root — we use our verified one.
     /// The proof data can come from any source (including untrusted RPCs).
+    #[wasm_bindgen(unchecked_return_type = "AccountStateResponse")]
     pub fn verify_account(&self, address: &str, proof_json: &str) -> Result<JsValue, JsValue> {
         let state_root = self
             .state
+            .borrow()
             .verified_state_root()
-            .unwrap_or(self.state.finalized_header.state_root);
+            .unwrap_or(self.state.borrow().finalized_header.state_root);
 
         // Parse the address
         let addr_hex = address.strip_prefix("0x").unwrap_or(address);
         let addr_bytes = hex::decode(addr_hex)
-            .map_err(|e| JsValue::from_str(&format!("Invalid address: {}", e)))?;
+            .map_err(|e| LumenError::new(ErrorCode::InvalidInput, format!("Invalid address: {}", e)))?;
         if addr_bytes.len() != 20 {
-            return Err(JsValue::from_str("Address must be 20 bytes"));
+            return Err(LumenError::new(ErrorCode::InvalidInput, "Address must be 20 bytes").into());
         }
         let mut addr = [0u8; 20];
         addr.copy_from_slice(&addr_bytes);
 
         // Parse the proof
         let proof: AccountProof = serde_json::from_str(proof_json)
-            .map_err(|e| JsValue::from_str(&format!("Invalid proof JSON: {}", e)))?;
+            .map_err(|e| LumenError::new(ErrorCode::InvalidJson, format!("Invalid proof JSON: {}", e)))?;
 
         // Verify the proof against our verified state root
-        let account = lumen_core::execution::proof::verify_account_proof(state_root, addr, &proof)
-            .map_err(|e| JsValue::from_str(&format!("Proof verification failed: {}", e)))?;
+        let account = metrics::proof(|| {
+            lumen_core::execution::proof::verify_account_proof(state_root, addr, &proof)
+        })
+        .map_err(|e| LumenError::new(ErrorCode::ProofInvalid, format!("Proof verification failed: {}", e)))?;
 
         // Return as JSON
         let result = AccountStateResponse {
             nonce: account.nonce,
-            balance: format!("0x{}", hex::encode(account.balance)),
+            balance: account.balance.to_hex(),
             storage_root: format!("0x{}", hex::encode(account.storage_root)),
             code_hash: format!("0x{}", hex::encode(account.code_hash)),
             is_contract: account.is_contract(),
             verified: true,
-            verified_against_slot: self.state.finalized_header.slot,
+            verified_against_slot: self.head_slot(),
         };
 
         serde_wasm_bindgen::to_value(&result)
-            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+            .map_err(|e| LumenError::serialization(e).into())
     }
 
     /// Verify a storage proof for a contract slot.
     ///
     /// address: hex-encoded Ethereum address (0x...)
     /// slot: hex-encoded storage slot (0x...)
-    /// proof_json: JSON-encoded storage proof
+    /// account_proof_json: JSON-encoded account proof for `address`
+    /// storage_proof_json: JSON-encoded storage proof for `slot`
     ///
-    /// The proof is verified against our internally held verified state root.
+    /// The account proof is verified against our internally held verified
+    /// state root, and the storage proof against the storage root it proves.
+    #[wasm_bindgen(unchecked_return_type = "StorageValueResponse")]
     pub fn verify_storage(
         &self,
         address: &str,
         slot: &str,
-        proof_json: &str,
+        account_proof_json: &str,
+        storage_proof_json: &str,
     ) -> Result<JsValue, JsValue> {
-        let _state_root = self
+        let state_root = self
             .state
+            .borrow()
             .verified_state_root()
-            .unwrap_or(self.state.finalized_header.state_root);
+            .unwrap_or(self.state.borrow().finalized_header.state_root);
+
+        let addr_hex = address.strip_prefix("0x").unwrap_or(address);
+        let addr_bytes = hex::decode(addr_hex)
+            .map_err(|e| LumenError::new(ErrorCode::InvalidInput, format!("Invalid address: {}", e)))?;
+        if addr_bytes.len() != 20 {
+            return Err(LumenError::new(ErrorCode::InvalidInput, "Address must be 20 bytes").into());
+        }
+        let mut addr = [0u8; 20];
+        addr.copy_from_slice(&addr_bytes);
 
         // Parse the storage slot
         let slot_hex = slot.strip_prefix("0x").unwrap_or(slot);
         let slot_bytes = hex::decode(slot_hex)
-            .map_err(|e| JsValue::from_str(&format!("Invalid slot: {}", e)))?;
-        let mut slot_arr = [0u8; 32];
-        if slot_bytes.len() <= 32 {
-            slot_arr[32 - slot_bytes.len()..].copy_from_slice(&slot_bytes);
+            .map_err(|e| LumenError::new(ErrorCode::InvalidInput, format!("Invalid slot: {}", e)))?;
+        if slot_bytes.len() > 32 {
+            return Err(LumenError::new(ErrorCode::InvalidInput, "Slot must be at most 32 bytes").into());
         }
+        let mut slot_arr = [0u8; 32];
+        slot_arr[32 - slot_bytes.len()..].copy_from_slice(&slot_bytes);
 
-        // Parse the storage proof
-        let proof: StorageProof = serde_json::from_str(proof_json)
-            .map_err(|e| JsValue::from_str(&format!("Invalid proof JSON: {}", e)))?;
+        let account_proof: AccountProof = serde_json::from_str(account_proof_json)
+            .map_err(|e| LumenError::new(ErrorCode::InvalidJson, format!("Invalid account proof JSON: {}", e)))?;
+        let proof: StorageProof = serde_json::from_str(storage_proof_json)
+            .map_err(|e| LumenError::new(ErrorCode::InvalidJson, format!("Invalid storage proof JSON: {}", e)))?;
 
-        // For storage proofs, we need the account's storage root first
-        // This requires the account proof to have been verified already
-        // For now, we'll use the proof's claimed storage root and verify it
-        let value = lumen_core::execution::proof::verify_storage_proof(
-            [0u8; 32], // Would come from verified account state
+        // The storage root comes from the account, proven under our root.
+        let account = metrics::proof(|| {
+            lumen_core::execution::proof::verify_account_proof(state_root, addr, &account_proof)
+        })
+        .map_err(|e| {
+            LumenError::new(ErrorCode::ProofInvalid, format!("Account proof verification failed: {}", e))
+        })?;
+        let value = metrics::proof(|| lumen_core::execution::proof::verify_storage_proof(
+            account.storage_root,
             slot_arr,
             &proof,
-        )
-        .map_err(|e| JsValue::from_str(&format!("Storage proof verification failed: {}", e)))?;
+        ))
+        .map_err(|e| LumenError::new(ErrorCode::ProofInvalid, format!("Storage proof verification failed: {}", e)))?;
+
+        let result = StorageValueResponse {
+            value: format!("0x{}", hex::encode(value.to_be_bytes())),
+            verified: true,
+            verified_against_slot: self.head_slot(),
+        };
+
+        serde_wasm_bindgen::to_value(&result)
+            .map_err(|e| LumenError::serialization(e).into())
+    }
+
+    /// Verify many storage slots of one contract in a single call.
+    ///
+    /// address: hex-encoded Ethereum address (0x...)
+    /// account_proof_json: JSON-encoded account proof for `address`
+    /// storage_proofs_json: JSON array of storage proofs for that account
+    ///
+    /// The account proof is verified against our verified state root first;
+    /// every storage proof is then checked against the account's verified
+    /// storage root. Slots are reported individually — a bad proof for one
+    /// slot yields an error entry for that slot only.
+    ///
+    /// `on_progress`, if given, receives `proofs` progress events with `done`
+    /// and `total` slots, about once per percent of the batch.
+    #[wasm_bindgen(unchecked_return_type = "StorageBatchResponse")]
+    pub fn verify_storage_batch(
+        &self,
+        address: &str,
+        account_proof_json: &str,
+        storage_proofs_json: &str,
+        #[wasm_bindgen(unchecked_param_type = "((progress: SyncProgress) => void) | undefined")]
+        on_progress: Option<js_sys::Function>,
+    ) -> Result<JsValue, JsValue> {
+        let state_root = self
+            .state
+            .borrow()
+            .verified_state_root()
+            .unwrap_or(self.state.borrow().finalized_header.state_root);
+
+        let addr_hex = address.strip_prefix("0x").unwrap_or(address);
+        let addr_bytes = hex::decode(addr_hex)
+            .map_err(|e| LumenError::new(ErrorCode::InvalidInput, format!("Invalid address: {}", e)))?;
+        if addr_bytes.len() != 20 {
+            return Err(LumenError::new(ErrorCode::InvalidInput, "Address must be 20 bytes").into());
+        }
+        let mut addr = [0u8; 20];
+        addr.copy_from_slice(&addr_bytes);
+
+        let account_proof: AccountProof = serde_json::from_str(account_proof_json)
+            .map_err(|e| LumenError::new(ErrorCode::InvalidJson, format!("Invalid account proof JSON: {}", e)))?;
+        let storage_proofs: Vec<StorageProof> = serde_json::from_str(storage_proofs_json)
+            .map_err(|e| LumenError::new(ErrorCode::InvalidJson, format!("Invalid storage proofs JSON: {}", e)))?;
+
+        let account = metrics::proof(|| {
+            lumen_core::execution::proof::verify_account_proof(state_root, addr, &account_proof)
+        })
+        .map_err(|e| {
+            LumenError::new(ErrorCode::ProofInvalid, format!("Account proof verification failed: {}", e))
+        })?;
+
+        // verify_storage_proofs, one slot at a time so progress can be reported.
+        let total = storage_proofs.len() as u64;
+        let results = metrics::proof_batch(|| {
+            let mut cache = lumen_core::execution::node_cache::TrieNodeCache::default();
+            storage_proofs
+                .iter()
+                .enumerate()
+                .map(|(index, proof)| {
+                    let result = lumen_core::execution::proof::verify_storage_proof_cached(
+                        account.storage_root,
+                        proof.key,
+                        proof,
+                        &mut cache,
+                    );
+                    let done = index as u64 + 1;
+                    if on_progress.is_some() && sync::should_report(done, total) {
+                        report_progress(
+                            on_progress.as_ref(),
+                            &sync::SyncProgress {
+                                stage: sync::SyncStage::Proofs,
+                                message: format!("Verified storage proof {} of {}", done, total),
+                                finalized_slot: None,
+                                done: Some(done),
+                                total: Some(total),
+                            },
+                        );
+                    }
+                    result
+                })
+                .collect()
+        });
+        let slots: Vec<StorageSlotResult> = storage_proofs
+            .iter()
+            .zip(results)
+            .map(|(proof, result)| StorageSlotResult::new(&proof.key, result))
+            .collect();
+
+        let verified_count = slots.iter().filter(|s| s.verified).count();
+        lumen_log!(
+            Debug,
+            "proof",
+            "Storage batch for {}: {}/{} slots verified",
+            address,
+            verified_count,
+            slots.len()
+        );
+
+        let result = StorageBatchResponse {
+            storage_root: format!("0x{}", hex::encode(account.storage_root)),
+            slots,
+            verified_count,
+            verified_against_slot: self.head_slot(),
+        };
+
+        serde_wasm_bindgen::to_value(&result)
+            .map_err(|e| LumenError::serialization(e).into())
+    }
+
+    /// Verify an account proof given as raw trie nodes.
+    ///
+    /// address: the 20 address bytes
+    /// proof_nodes: the RLP-encoded trie nodes of the proof, one `Uint8Array`
+    /// each (eth_getProof's `accountProof`, already decoded from hex)
+    ///
+    /// Same verification as `verify_account_rpc_proof`, against the
+    /// BLS-verified execution state root, without parsing a JSON proof.
+    #[wasm_bindgen(unchecked_return_type = "AccountStateResponse")]
+    pub fn verify_account_proof_nodes(
+        &self,
+        address: &[u8],
+        #[wasm_bindgen(unchecked_param_type = "Uint8Array[]")] proof_nodes: js_sys::Array,
+    ) -> Result<JsValue, JsValue> {
+        let account = self.verify_account_nodes(address, &proof_nodes)?;
+
+        let result = AccountStateResponse {
+            nonce: account.nonce,
+            balance: account.balance.to_hex(),
+            storage_root: format!("0x{}", hex::encode(account.storage_root)),
+            code_hash: format!("0x{}", hex::encode(account.code_hash)),
+            is_contract: account.is_contract(),
+            verified: true,
+            verified_against_slot: self.head_slot(),
+        };
+
+        serde_wasm_bindgen::to_value(&result)
+            .map_err(|e| LumenError::serialization(e).into())
+    }
+
+    /// Verify a storage slot given as raw trie nodes.
+    ///
+    /// address: the 20 address bytes
+    /// account_proof_nodes: the account's proof, as for `verify_account_proof_nodes`
+    /// slot: the storage key, up to 32 bytes (left-padded)
+    /// storage_proof_nodes: the slot's proof (eth_getProof's `storageProof[i].proof`)
+    ///
+    /// The account proof is verified first, so the storage root the slot is
+    /// checked against is itself proven — the caller never supplies one.
+    #[wasm_bindgen(unchecked_return_type = "StorageValueResponse")]
+    pub fn verify_storage_proof_nodes(
+        &self,
+        address: &[u8],
+        #[wasm_bindgen(unchecked_param_type = "Uint8Array[]")] account_proof_nodes: js_sys::Array,
+        slot: &[u8],
+        #[wasm_bindgen(unchecked_param_type = "Uint8Array[]")] storage_proof_nodes: js_sys::Array,
+    ) -> Result<JsValue, JsValue> {
+        if slot.len() > 32 {
+            return Err(LumenError::new(ErrorCode::InvalidInput, "Storage slot must be at most 32 bytes").into());
+        }
+        let mut key = [0u8; 32];
+        key[32 - slot.len()..].copy_from_slice(slot);
+
+        let account = self.verify_account_nodes(address, &account_proof_nodes)?;
+        let proof = StorageProof {
+            key,
+            value: U256::ZERO,
+            proof: proof_nodes_from_array(&storage_proof_nodes)
+                .map_err(|e| LumenError::new(ErrorCode::InvalidInput, e))?,
+        };
+        let value = metrics::proof(|| {
+            lumen_core::execution::proof::verify_storage_proof(account.storage_root, key, &proof)
+        })
+        .map_err(|e| {
+            LumenError::new(ErrorCode::ProofInvalid, format!("Storage proof verification failed: {}", e))
+        })?;
 
         let result = StorageValueResponse {
-            value: format!("0x{}", hex::encode(value)),
+            value: format!("0x{}", hex::encode(value.to_be_bytes())),
+            verified: true,
+            verified_against_slot: self.head_slot(),
+        };
+
+        serde_wasm_bindgen::to_value(&result)
+            .map_err(|e| LumenError::serialization(e).into())
+    }
+
+    /// Verify a receipt proof the caller fetched itself, against the
+    /// receipts root of a BLS-verified block.
+    ///
+    /// receipts_root: `0x`-hex receipts root of one of the last 64 finalized
+    /// blocks this client verified; omit for the latest finalized block's
+    /// tx_index: the transaction's index in that block
+    /// proof_json: JSON array of the `0x`-hex RLP trie nodes from the
+    /// receipts root down to the receipt, as `build_receipt_proof` produces
+    ///
+    /// A root this client has not verified is refused rather than trusted.
+    #[wasm_bindgen(unchecked_return_type = "ReceiptProofResponse")]
+    pub fn verify_receipt(
+        &self,
+        receipts_root: Option<String>,
+        tx_index: u64,
+        proof_json: &str,
+    ) -> Result<JsValue, JsValue> {
+        let (receipt, header) = self.verify_receipt_json(receipts_root.as_deref(), tx_index, proof_json)?;
+        let result = ReceiptProofResponse {
+            tx_type: receipt.tx_type.type_byte(),
+            status: receipt.status,
+            cumulative_gas_used: receipt.cumulative_gas_used,
+            logs_bloom: format!("0x{}", hex::encode(receipt.logs_bloom)),
+            logs: receipt.logs.iter().map(LogResponse::from).collect(),
+            transaction_index: tx_index,
+            block_number: header.block_number,
+            receipts_root: format!("0x{}", hex::encode(header.receipts_root)),
+            verified: true,
+        };
+        serde_wasm_bindgen::to_value(&result)
+            .map_err(|e| LumenError::serialization(e).into())
+    }
+
+    /// Verify a receipt proof as `verify_receipt` does and return only its
+    /// log at `log_index` (counted within the receipt, from 0).
+    #[wasm_bindgen(unchecked_return_type = "VerifiedLogResponse")]
+    pub fn verify_log(
+        &self,
+        receipts_root: Option<String>,
+        tx_index: u64,
+        log_index: u32,
+        proof_json: &str,
+    ) -> Result<JsValue, JsValue> {
+        let (receipt, header) = self.verify_receipt_json(receipts_root.as_deref(), tx_index, proof_json)?;
+        let log = receipt.logs.get(log_index as usize).ok_or_else(|| {
+            LumenError::new(
+                ErrorCode::InvalidInput,
+                format!("Receipt {} has {} logs, no log {}", tx_index, receipt.logs.len(), log_index),
+            )
+        })?;
+        let result = VerifiedLogResponse {
+            log: LogResponse::from(log),
+            log_index,
+            transaction_index: tx_index,
+            block_number: header.block_number,
             verified: true,
-            verified_against_slot: self.state.finalized_header.slot,
         };
+        serde_wasm_bindgen::to_value(&result)
+            .map_err(|e| LumenError::serialization(e).into())
+    }
+
+    /// Verify a receipt proof as `verify_receipt` does and find the ERC-4337
+    /// `UserOperationEvent` for `user_op_hash` in it: whether a smart
+    /// account's user operation landed, and whether its call succeeded.
+    ///
+    /// user_op_hash: `0x`-hex hash of the user operation
+    /// entry_point: `0x` address of the EntryPoint expected to emit the
+    /// event; omit to accept the v0.6, v0.7 and v0.8 deployments
+    ///
+    /// Only events emitted by an EntryPoint count, since any contract can
+    /// emit a log with the same signature. `success: false` means the
+    /// operation was included and paid for, but its call reverted. A receipt
+    /// without the event fails with `INVALID_INPUT`.
+    #[wasm_bindgen(unchecked_return_type = "VerifiedUserOperation")]
+    pub fn verify_user_operation(
+        &self,
+        receipts_root: Option<String>,
+        tx_index: u64,
+        proof_json: &str,
+        user_op_hash: &str,
+        entry_point: Option<String>,
+    ) -> Result<JsValue, JsValue> {
+        let (hash, entry_points) = parse_user_operation_query(user_op_hash, entry_point.as_deref())?;
+        let (receipt, header) = self.verify_receipt_json(receipts_root.as_deref(), tx_index, proof_json)?;
+        let result = find_user_operation(&receipt.logs, &hash, &entry_points, tx_index, header.block_number)?;
+        serde_wasm_bindgen::to_value(&result)
+            .map_err(|e| LumenError::serialization(e).into())
+    }
+
+    /// Verify that a transaction is in a BLS-verified block and decode it.
+    ///
+    /// block_header_rlp: `0x`-hex RLP of the block's execution header, as
+    /// `debug_getRawHeader` returns it; it must hash to the block hash of
+    /// one of the last 64 finalized blocks this client verified, which
+    /// authenticates its `transactionsRoot`
+    /// tx_index: the transaction's index in that block
+    /// proof_json: JSON array of the `0x`-hex RLP trie nodes from the
+    /// transactions root down to the transaction
+    ///
+    /// The transaction hash is computed from the proven envelope, so it can
+    /// be compared with the hash the caller looked up.
+    #[wasm_bindgen(unchecked_return_type = "VerifiedTransactionResponse")]
+    pub fn verify_transaction_inclusion(
+        &self,
+        block_header_rlp: &str,
+        tx_index: u64,
+        proof_json: &str,
+    ) -> Result<JsValue, JsValue> {
+        let (verified, header) = self.verify_transaction_json(block_header_rlp, tx_index, proof_json)?;
+        let result = VerifiedTransactionResponse::new(&verified, &header);
+        serde_wasm_bindgen::to_value(&result)
+            .map_err(|e| LumenError::serialization(e).into())
+    }
 
+    /// Verify an execution block header against the verified chain.
+    ///
+    /// header: either the `0x`-hex RLP of the header, as `debug_getRawHeader`
+    /// returns it, or the JSON block object from `eth_getBlockByHash` /
+    /// `eth_getBlockByNumber` (transactions are ignored). It must hash to the
+    /// block hash of one of the last 64 finalized blocks this client verified.
+    ///
+    /// For a JSON block the hash is recomputed from its header fields, and a
+    /// `hash` field that disagrees is rejected, so the result can answer
+    /// `eth_getBlockByHash` without trusting the RPC.
+    #[wasm_bindgen(unchecked_return_type = "VerifiedBlockHeaderResponse")]
+    pub fn verify_block_header(&self, header: &str) -> Result<JsValue, JsValue> {
+        let header = self.verify_header_input(header)?;
+        let result = VerifiedBlockHeaderResponse::from(&header);
         serde_wasm_bindgen::to_value(&result)
-            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+            .map_err(|e| LumenError::serialization(e).into())
     }
 
     /// Returns true if the client has synced past slot 0 and is ready to serve queries.
     pub fn is_synced(&self) -> bool {
-        self.state.finalized_header.slot > 0
+        self.head_slot() > 0
     }
 
     /// Get the full sync state as JSON for the TypeScript layer.
+    #[wasm_bindgen(unchecked_return_type = "SyncStateResponse")]
     pub fn get_sync_state(&self) -> Result<JsValue, JsValue> {
-        let sync_state = SyncStateResponse {
-            head_slot: self.state.finalized_header.slot,
-            current_period: self.state.current_period(),
-            has_next_committee: self.state.next_sync_committee.is_some(),
-            has_execution_root: self.state.latest_execution_payload_header.is_some(),
-            is_synced: self.is_synced(),
-        };
+        serde_wasm_bindgen::to_value(&self.sync_state())
+            .map_err(|e| LumenError::serialization(e).into())
+    }
+
+    /// Counters and timing histograms for the verification work done so far.
+    ///
+    /// Counts updates applied, rejected and duplicate, proofs verified and
+    /// rejected, and times BLS update verification, MPT proof verification
+    /// and RPC round trips in milliseconds. Metrics are shared by every
+    /// client in the same worker.
+    #[wasm_bindgen(unchecked_return_type = "LumenMetrics")]
+    pub fn get_metrics(&self) -> Result<JsValue, JsValue> {
+        let mut metrics = metrics::snapshot();
+        metrics.progress.head_slot = self.head_slot();
+        metrics.progress.is_initial_sync_complete = self.is_synced();
 
-        serde_wasm_bindgen::to_value(&sync_state)
-            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+        metrics
+            .serialize(&serde_wasm_bindgen::Serializer::json_compatible())
+            .map_err(|e| LumenError::serialization(e).into())
+    }
+
+    /// Zero every counter and histogram.
+    pub fn reset_metrics(&self) {
+        metrics::reset();
+    }
+
+    /// Memory held by this client: the size of the WebAssembly heap, and
+    /// entries, capacity, approximate bytes and hit counts of each cache
+    /// and ring buffer, for tuning limits on memory-constrained devices.
+    #[wasm_bindgen(unchecked_return_type = "ResourceStats")]
+    pub fn resource_stats(&self) -> Result<JsValue, JsValue> {
+        self.resource_report()
+            .serialize(&serde_wasm_bindgen::Serializer::json_compatible())
+            .map_err(|e| LumenError::serialization(e).into())
     }
 
     // =======================================================================
@@ -291,51 +764,250 @@ impl LumenClient {
     /// The bootstrap is the ONE moment of trust — the block root must be
     /// obtained from multiple independent sources.
     pub fn from_beacon_bootstrap(bootstrap_json: &str) -> Result<LumenClient, JsValue> {
+        Self::from_beacon_bootstrap_with_config(bootstrap_json, "mainnet")
+    }
+
+    /// `from_beacon_bootstrap` for a network other than mainnet.
+    /// `network_config` takes the same forms as in `new_with_config`.
+    pub fn from_beacon_bootstrap_with_config(
+        bootstrap_json: &str,
+        network_config: &str,
+    ) -> Result<LumenClient, JsValue> {
+        let network = parse_network_config(network_config).map_err(|e| LumenError::new(ErrorCode::InvalidConfig, e))?;
         let api_resp: beacon_api::ApiBootstrapResponse =
             serde_json::from_str(bootstrap_json)
-                .map_err(|e| JsValue::from_str(&format!("Invalid bootstrap JSON: {}", e)))?;
+                .map_err(|e| LumenError::new(ErrorCode::InvalidJson, format!("Invalid bootstrap JSON: {}", e)))?;
 
         let bootstrap = api_resp.data.to_core_bootstrap()
-            .map_err(|e| JsValue::from_str(&format!("Bootstrap conversion: {}", e)))?;
+            .map_err(|e| LumenError::new(ErrorCode::InvalidJson, format!("Bootstrap conversion: {}", e)))?;
 
         let exec_header = api_resp
             .data
             .header
-            .execution
-            .as_ref()
-            .map(|exec| exec.to_core())
-            .transpose()
-            .map_err(|e| JsValue::from_str(&format!("Execution header: {}", e)))?;
+            .verified_execution()
+            .map_err(|e| LumenError::new(ErrorCode::InvalidJson, format!("Execution header: {}", e)))?;
 
-        // Ethereum mainnet genesis validators root
-        let genesis_validators_root = [
-            0x4b, 0x36, 0x3d, 0xb9, 0x4e, 0x28, 0x61, 0x20, 0xd7, 0x6e, 0xb9, 0x05, 0x34,
-            0x0f, 0xdd, 0x4e, 0x54, 0xbf, 0xe9, 0xf0, 0x6b, 0xf3, 0x3f, 0xf6, 0xcf, 0x5a,
-            0xd2, 0x7f, 0x51, 0x1b, 0xfe, 0x95,
-        ];
+        Self::from_bootstrap(&bootstrap, exec_header, network)
+    }
 
-        // Deneb fork version
-        let fork_version = [0x04, 0x00, 0x00, 0x00];
+    /// `from_beacon_bootstrap_with_config` for a bootstrap given as SSZ bytes.
+    ///
+    /// `bootstrap_ssz` is the body of
+    ///   GET /eth/v1/beacon/light_client/bootstrap/{block_root}
+    /// requested with `Accept: application/octet-stream`, as a `Uint8Array`:
+    /// several times smaller than the JSON, whose 512 public keys are hex.
+    /// `network_config` defaults to mainnet.
+    pub fn from_beacon_bootstrap_ssz(
+        bootstrap_ssz: &[u8],
+        network_config: Option<String>,
+    ) -> Result<LumenClient, JsValue> {
+        let network = parse_network_config(network_config.as_deref().unwrap_or("mainnet"))
+            .map_err(|e| LumenError::new(ErrorCode::InvalidConfig, e))?;
+        let decoded = lumen_core::consensus::ssz::decode_bootstrap(bootstrap_ssz)
+            .map_err(|e| LumenError::new(ErrorCode::InvalidSsz, format!("Invalid bootstrap SSZ: {}", e)))?;
+        Self::from_bootstrap(&decoded.bootstrap, Some(decoded.execution), network)
+    }
 
-        let committee_size = bootstrap.current_sync_committee.pubkeys.len();
 
-        let mut state = initialize_from_bootstrap(&bootstrap, genesis_validators_root, fork_version)
-            .map_err(|e| JsValue::from_str(&format!("Bootstrap init: {}", e)))?;
+    /// Pick a checkpoint by agreement between independent beacon API
+    /// providers, then bootstrap from it.
+    ///
+    /// `urls_json` is a JSON array of beacon API base URLs; an empty array
+    /// uses the built-in providers for the network. Each is asked for its
+    /// finalized header; at least `required_agreement` of them must report
+    /// the same block root. The bootstrap is fetched from an agreeing provider
+    /// and must be for exactly that block, with its sync committee branch.
+    /// `network_config` is as in `new_with_config` and defaults to mainnet.
+    pub async fn from_checkpoint_providers(
+        urls_json: &str,
+        required_agreement: usize,
+        network_config: Option<String>,
+    ) -> Result<LumenClient, JsValue> {
+        let network_config = network_config.unwrap_or_else(|| "mainnet".to_string());
+        let urls = checkpoint_provider_urls(urls_json, &network_config)
+            .map_err(|e| LumenError::new(ErrorCode::InvalidConfig, e))?;
 
-        if let Some(exec) = exec_header {
-            log_to_console(&format!(
-                "[Lumen] Bootstrap execution state root: 0x{}",
-                hex::encode(exec.state_root)
-            ));
-            state.latest_execution_payload_header = Some(exec);
+        let reports = checkpoint::fetch_reports(&urls).await;
+        for report in &reports {
+            match &report.checkpoint {
+                Ok((root, slot)) => lumen_log!(
+                    Debug,
+                    "checkpoint",
+                    "Checkpoint provider {}: slot {}, root 0x{}",
+                    report.url,
+                    slot,
+                    hex::encode(root)
+                ),
+                Err(e) => lumen_log!(Warn, "checkpoint", "Checkpoint provider {} failed: {}", report.url, e),
+            }
         }
 
-        log_to_console(&format!(
-            "[Lumen] Initialized from beacon bootstrap — slot {}, {} sync committee members",
-            state.finalized_header.slot, committee_size
-        ));
+        let agreed = checkpoint::select_checkpoint(&reports, required_agreement)
+            .map_err(|e| {
+                LumenError::new(ErrorCode::NoCheckpointConsensus, format!("Checkpoint consensus failed: {}", e))
+            })?;
+        lumen_log!(
+            Info,
+            "checkpoint",
+            "Checkpoint consensus: {}/{} providers agree on slot {}",
+            agreed.source_agreement, agreed.total_sources, agreed.slot
+        );
+
+        let bootstrap = checkpoint::fetch_bootstrap(&reports, agreed.block_root)
+            .await
+            .map_err(|e| LumenError::new(ErrorCode::RequestFailed, format!("Bootstrap failed: {}", e)))?;
+        match bootstrap {
+            BeaconPayload::Ssz(bytes) => Self::from_beacon_bootstrap_ssz(&bytes, Some(network_config)),
+            BeaconPayload::Json(json) => Self::from_beacon_bootstrap_with_config(&json, &network_config),
+        }
+    }
+
+    /// Bootstrap and sync to the latest finalized head in one call.
+    ///
+    /// Runs the whole pipeline against `beacon_endpoints_json` (a JSON array
+    /// of beacon API base URLs; empty for the network's built-in providers):
+    /// 1. checkpoint consensus — a majority of the endpoints must agree
+    /// 2. bootstrap from the agreed checkpoint
+    /// 3. backfill committee periods up to the latest finality update
+    /// 4. BLS-verify that finality update
+    ///
+    /// `on_progress`, if given, is called with `{ stage, message,
+    /// finalized_slot? }` as each stage starts or finishes; `stage` is one of
+    /// `checkpoint`, `bootstrap`, `backfill`, `finality`, `synced`.
+    ///
+    /// Aborting `signal` stops the sync at its next fetch and rejects with a
+    /// `cancelled` error; nothing of the partial sync is kept.
+    pub async fn sync_to_head(
+        beacon_endpoints_json: &str,
+        #[wasm_bindgen(unchecked_param_type = "((progress: SyncProgress) => void) | undefined")]
+        on_progress: Option<js_sys::Function>,
+        network_config: Option<String>,
+        signal: Option<web_sys::AbortSignal>,
+    ) -> Result<LumenClient, JsValue> {
+        let cancellation = cancel::Cancellation::default();
+        if let Some(signal) = &signal {
+            cancellation.follow(signal);
+        }
+        Self::sync_to_head_until(beacon_endpoints_json, on_progress, network_config, &cancellation).await
+    }
+    /// The built-in checkpoint providers for a preset network, as an array
+    /// of URLs (empty for networks without defaults).
+    pub fn default_checkpoint_providers(network: &str) -> Vec<String> {
+        checkpoint::default_providers(network)
+            .unwrap_or_default()
+            .iter()
+            .map(|url| url.to_string())
+            .collect()
+    }
+
+    /// Ask checkpoint providers for their finalized checkpoint and report
+    /// which answered, how fast, and whether they agree, without
+    /// bootstrapping. Takes the same arguments as `from_checkpoint_providers`.
+    ///
+    /// Returns `{ providers: [{ url, healthy, slot?, block_root?, agrees,
+    /// latency_ms, error? }], agreed_root?, agreed_slot?, agreeing, healthy, total }`.
+    #[wasm_bindgen(unchecked_return_type = "CheckpointHealthReport")]
+    pub async fn probe_checkpoint_providers(
+        urls_json: &str,
+        network_config: Option<String>,
+    ) -> Result<JsValue, JsValue> {
+        let network_config = network_config.unwrap_or_else(|| "mainnet".to_string());
+        let urls = checkpoint_provider_urls(urls_json, &network_config)
+            .map_err(|e| LumenError::new(ErrorCode::InvalidConfig, e))?;
+        let report = checkpoint::health_report(&checkpoint::fetch_reports(&urls).await);
+        report
+            .serialize(&serde_wasm_bindgen::Serializer::json_compatible())
+            .map_err(|e| LumenError::serialization(e).into())
+    }
+
+    /// Restore a client from bytes written by `export_state`.
+    ///
+    /// Skips the checkpoint bootstrap entirely: the sync committee and
+    /// finalized header were verified before they were exported, so the
+    /// client continues from there with the next finality update.
+    ///
+    /// The network is recognised from the saved genesis validators root.
+    /// For a custom network, pass the same `network_config` the state was
+    /// created with; it must match the saved root.
+    pub fn resume(snapshot: &[u8], network_config: Option<String>) -> Result<LumenClient, JsValue> {
+        let state = lumen_core::consensus::snapshot::decode_state(snapshot)
+            .map_err(|e| LumenError::new(ErrorCode::InvalidSnapshot, format!("Cannot resume: {}", e)))?;
+        let network = snapshot_network(&state, network_config)?;
+
+        lumen_log!(
+            Info,
+            "client",
+            "Resumed from saved state — slot {}, period {}",
+            state.finalized_header.slot,
+            state.current_period()
+        );
+
+        Ok(LumenClient::from_state(state, network))
+    }
+
+    /// Serialize the verified state (sync committees, finalized header,
+    /// execution header) for storage, e.g. in IndexedDB.
+    /// Returned to JS as a `Uint8Array`; pass it to `LumenClient.resume`.
+    pub fn export_state(&self) -> Vec<u8> {
+        lumen_core::consensus::snapshot::encode_state(&self.state.borrow())
+    }
+
+    /// Serialize the verified state and recent execution headers for
+    /// another tab, tagged with `key`. Pass the bytes to
+    /// `LumenClient.import_shared_state` there, e.g. over a BroadcastChannel
+    /// or through a SharedWorker, so each tab does not bootstrap and verify
+    /// the same updates again.
+    ///
+    /// key: at least 16 bytes shared by the tabs, e.g. generated once and
+    /// kept in IndexedDB. A snapshot only imports under the key it was
+    /// exported with.
+    pub fn export_shared_state(&self, key: &[u8]) -> Result<Vec<u8>, JsValue> {
+        let snapshot = lumen_core::consensus::snapshot::SharedSnapshot {
+            state: self.state.borrow().clone(),
+            execution_headers: self.execution_history.borrow().headers().cloned().collect(),
+        };
+        lumen_core::consensus::snapshot::encode_shared(&snapshot, key)
+            .map_err(|e| LumenError::new(ErrorCode::InvalidInput, e.to_string()).into())
+    }
+
+    /// Create a client from bytes written by `export_shared_state` in
+    /// another tab, under the same `key`.
+    ///
+    /// The snapshot's tag and structure are checked — full-size sync
+    /// committees, execution headers in order and ending with the verified
+    /// one — but its signatures are not re-verified: it is trusted as much
+    /// as the tab that exported it. `network_config` is as for `resume`.
+    pub fn import_shared_state(
+        snapshot: &[u8],
+        key: &[u8],
+        network_config: Option<String>,
+    ) -> Result<LumenClient, JsValue> {
+        use lumen_core::consensus::snapshot::{decode_shared, SnapshotError};
+
+        let shared = decode_shared(snapshot, key).map_err(|e| {
+            let code = match e {
+                SnapshotError::WeakKey => ErrorCode::InvalidInput,
+                _ => ErrorCode::InvalidSnapshot,
+            };
+            LumenError::new(code, format!("Cannot import shared state: {}", e))
+        })?;
+        let network = snapshot_network(&shared.state, network_config)?;
+
+        lumen_log!(
+            Info,
+            "client",
+            "Imported shared state — slot {}, {} execution headers",
+            shared.state.finalized_header.slot,
+            shared.execution_headers.len()
+        );
 
-        Ok(LumenClient { state })
+        let client = LumenClient::from_state(shared.state, network);
+        let mut history = history::ExecutionHistory::default();
+        for header in &shared.execution_headers {
+            history.record(header);
+        }
+        *client.execution_history.borrow_mut() = history;
+        Ok(client)
     }
 
     /// Process a beacon API finality update with full BLS verification.
@@ -348,105 +1020,113 @@ impl LumenClient {
     /// 2. Verify sync committee BLS aggregate signature (THE trust anchor)
     /// 3. Verify finality Merkle branch
     /// 4. Advance the verified head
-    /// 5. Store the execution state root for proof verification
+    /// 5. Store the execution state root for proof verification, once the
+    ///    execution branch proves its header in the finalized `body_root`
     ///
     /// Returns a FinalityUpdateResult on success with verified state info.
-    pub fn process_finality_update(&mut self, update_json: &str) -> Result<JsValue, JsValue> {
+    #[wasm_bindgen(unchecked_return_type = "FinalityUpdateResult")]
+    pub fn process_finality_update(&self, update_json: &str) -> Result<JsValue, JsValue> {
         let api_resp: beacon_api::ApiFinalityUpdateResponse =
             serde_json::from_str(update_json)
-                .map_err(|e| JsValue::from_str(&format!("Invalid finality update JSON: {}", e)))?;
+                .map_err(|e| {
+                    self.input_error(
+                        "process_finality_update",
+                        ErrorCode::InvalidJson,
+                        format!("Invalid finality update JSON: {}", e),
+                    )
+                })?;
 
         let update = api_resp.data.to_core_update()
-            .map_err(|e| JsValue::from_str(&format!("Update conversion: {}", e)))?;
+            .map_err(|e| {
+                self.input_error("process_finality_update", ErrorCode::InvalidJson, format!("Update conversion: {}", e))
+            })?;
 
         let exec_header = api_resp
             .data
             .finalized_header
-            .execution
-            .as_ref()
-            .map(|exec| exec.to_core())
-            .transpose()
-            .map_err(|e| JsValue::from_str(&format!("Execution header: {}", e)))?;
-
-        let participation = update.sync_aggregate.num_participants();
-
-        // If the update doesn't advance us, skip silently
-        if update.finalized_header.slot <= self.state.finalized_header.slot {
-            let result = FinalityUpdateResult {
-                verified: true,
-                advanced: false,
-                finalized_slot: self.state.finalized_header.slot,
-                execution_state_root: self.execution_state_root().unwrap_or_default(),
-                execution_block_number: self
-                    .state
-                    .latest_execution_payload_header
-                    .as_ref()
-                    .map(|h| h.block_number)
-                    .unwrap_or(0),
-                sync_participation: participation,
-                message: "Already at this slot or newer".into(),
-            };
-            return serde_wasm_bindgen::to_value(&result)
-                .map_err(|e| JsValue::from_str(&e.to_string()));
-        }
-
-        let genesis_validators_root = self.state.genesis_validators_root;
-        let current_slot = self.state.finalized_header.slot;
-
-        // This is where BLS verification happens — the core trust operation
-        lumen_core::consensus::light_client::process_light_client_update(
-            &mut self.state,
-            &update,
-            current_slot,
-            genesis_validators_root,
-        )
-        .map_err(|e| JsValue::from_str(&format!("BLS verification failed: {}", e)))?;
-
-        // BLS passed — store the execution state root
-        if let Some(exec) = exec_header {
-            log_to_console(&format!(
-                "[Lumen] BLS-verified execution state root: 0x{} (block #{})",
-                hex::encode(exec.state_root),
-                exec.block_number
-            ));
-            self.state.latest_execution_payload_header = Some(exec);
-        }
-
-        log_to_console(&format!(
-            "[Lumen] BLS verification passed — {}/512 validators signed, slot {}",
-            participation, self.state.finalized_header.slot
-        ));
+            .verified_execution()
+            .map_err(|e| {
+                self.input_error("process_finality_update", ErrorCode::InvalidJson, format!("Execution header: {}", e))
+            })?;
 
-        let result = FinalityUpdateResult {
-            verified: true,
-            advanced: true,
-            finalized_slot: self.state.finalized_header.slot,
-            execution_state_root: self.execution_state_root().unwrap_or_default(),
-            execution_block_number: self
-                .state
-                .latest_execution_payload_header
-                .as_ref()
-                .map(|h| h.block_number)
-                .unwrap_or(0),
-            sync_participation: participation,
-            message: format!(
-                "BLS-verified finality at slot {} ({}/512 signers)",
-                self.state.finalized_header.slot, participation
-            ),
-        };
+        self.apply_finality_update(&update, exec_header)
+    }
 
-        serde_wasm_bindgen::to_value(&result)
-            .map_err(|e| JsValue::from_str(&e.to_string()))
+    /// Process a finality update given as SSZ bytes, with the same
+    /// verification as `process_finality_update`.
+    ///
+    /// `update_ssz` is the body of
+    ///   GET /eth/v1/beacon/light_client/finality_update
+    /// requested with `Accept: application/octet-stream`, as a `Uint8Array`.
+    /// Skips the hex and JSON decoding of the JSON path, which dominates the
+    /// cost of frequent updates.
+    #[wasm_bindgen(unchecked_return_type = "FinalityUpdateResult")]
+    pub fn process_finality_update_ssz(&self, update_ssz: &[u8]) -> Result<JsValue, JsValue> {
+        let decoded = lumen_core::consensus::ssz::decode_finality_update(update_ssz).map_err(|e| {
+            self.input_error(
+                "process_finality_update_ssz",
+                ErrorCode::InvalidSsz,
+                format!("Invalid finality update SSZ: {}", e),
+            )
+        })?;
+        self.apply_finality_update(&decoded.update, Some(decoded.finalized_execution))
     }
 
-    /// Verify an account proof from a raw eth_getProof RPC response.
+    /// Backfill sync committee periods from the beacon API.
     ///
-    /// The proof data is UNTRUSTED — it could come from any RPC, P2P peer,
-    /// or even a malicious actor. The verification uses:
-    /// - Our BLS-verified execution state root (from process_finality_update)
-    /// - keccak256 Merkle-Patricia trie traversal
+    /// Accepts the raw JSON array from:
+    ///   GET /eth/v1/beacon/light_client/updates?start_period={p}&count={n}
+    ///
+    /// Each update is BLS-verified against the committee of its period, in
+    /// order, so a client initialized from an old checkpoint (or resumed from
+    /// an old snapshot) can walk forward to the current period. Stops at the
+    /// first update that fails verification; the ones before it stay applied.
+    ///
+    /// `on_progress`, if given, receives a `backfill` progress event with
+    /// `done` and `total` after each update.
+    #[wasm_bindgen(unchecked_return_type = "CommitteeBackfillResult")]
+    pub fn process_committee_updates(
+        &self,
+        updates_json: &str,
+        #[wasm_bindgen(unchecked_param_type = "((progress: SyncProgress) => void) | undefined")]
+        on_progress: Option<js_sys::Function>,
+    ) -> Result<JsValue, JsValue> {
+        self.committee_updates_json(updates_json, |done, total| {
+            if on_progress.is_some() {
+                report_progress(on_progress.as_ref(), &self.backfill_progress(done, total));
+            }
+        })
+    }
+
+    /// Backfill sync committee periods from updates given as SSZ bytes, with
+    /// the same verification and progress events as `process_committee_updates`.
+    ///
+    /// `updates_ssz` is the body of
+    ///   GET /eth/v1/beacon/light_client/updates?start_period={p}&count={n}
+    /// requested with `Accept: application/octet-stream`, as a `Uint8Array`.
+    #[wasm_bindgen(unchecked_return_type = "CommitteeBackfillResult")]
+    pub fn process_committee_updates_ssz(
+        &self,
+        updates_ssz: &[u8],
+        #[wasm_bindgen(unchecked_param_type = "((progress: SyncProgress) => void) | undefined")]
+        on_progress: Option<js_sys::Function>,
+    ) -> Result<JsValue, JsValue> {
+        self.committee_updates_ssz(updates_ssz, |done, total| {
+            if on_progress.is_some() {
+                report_progress(on_progress.as_ref(), &self.backfill_progress(done, total));
+            }
+        })
+    }
+
+    /// Verify an account proof from a raw eth_getProof RPC response.
+    ///
+    /// The proof data is UNTRUSTED — it could come from any RPC, P2P peer,
+    /// or even a malicious actor. The verification uses:
+    /// - Our BLS-verified execution state root (from process_finality_update)
+    /// - keccak256 Merkle-Patricia trie traversal
     ///
     /// No keccak256 collision = no way to forge a valid proof.
+    #[wasm_bindgen(unchecked_return_type = "VerifiedAccountResponse")]
     pub fn verify_account_rpc_proof(
         &self,
         address: &str,
@@ -454,52 +1134,63 @@ impl LumenClient {
     ) -> Result<JsValue, JsValue> {
         let state_root = self
             .state
+            .borrow()
             .verified_state_root()
-            .ok_or_else(|| JsValue::from_str("No verified execution state root yet — process a finality update first"))?;
+            .ok_or_else(|| {
+                LumenError::new(
+                    ErrorCode::NotSynced,
+                    "No verified execution state root yet — process a finality update first",
+                )
+            })?;
 
         let rpc_proof: beacon_api::RpcGetProofResponse =
             serde_json::from_str(rpc_proof_json)
-                .map_err(|e| JsValue::from_str(&format!("Invalid proof JSON: {}", e)))?;
+                .map_err(|e| LumenError::new(ErrorCode::InvalidJson, format!("Invalid proof JSON: {}", e)))?;
 
         let addr_hex = address.strip_prefix("0x").unwrap_or(address);
         let addr_bytes = hex::decode(addr_hex)
-            .map_err(|e| JsValue::from_str(&format!("Invalid address: {}", e)))?;
+            .map_err(|e| LumenError::new(ErrorCode::InvalidInput, format!("Invalid address: {}", e)))?;
         if addr_bytes.len() != 20 {
-            return Err(JsValue::from_str("Address must be 20 bytes"));
+            return Err(LumenError::new(ErrorCode::InvalidInput, "Address must be 20 bytes").into());
         }
         let mut addr = [0u8; 20];
         addr.copy_from_slice(&addr_bytes);
 
         let account_proof = rpc_proof
             .to_core_account_proof(&addr)
-            .map_err(|e| JsValue::from_str(&format!("Proof conversion: {}", e)))?;
+            .map_err(|e| LumenError::new(ErrorCode::InvalidJson, format!("Proof conversion: {}", e)))?;
 
         let proof_node_count = account_proof.proof.len();
 
-        let account = lumen_core::execution::proof::verify_account_proof(state_root, addr, &account_proof)
-            .map_err(|e| JsValue::from_str(&format!("Proof verification failed: {}", e)))?;
+        let account = metrics::proof(|| {
+            lumen_core::execution::proof::verify_account_proof(state_root, addr, &account_proof)
+        })
+        .map_err(|e| LumenError::new(ErrorCode::ProofInvalid, format!("Proof verification failed: {}", e)))?;
 
-        log_to_console(&format!(
-            "[Lumen] Account {} verified: {} nodes, balance=0x{}",
+        lumen_log!(
+            Debug,
+            "proof",
+            "Account {} verified: {} nodes, balance={}",
             address,
             proof_node_count,
-            hex::encode(account.balance)
-        ));
+            account.balance.to_hex()
+        );
 
         let result = VerifiedAccountResponse {
             nonce: account.nonce,
-            balance_hex: format!("0x{}", account.balance_hex()),
+            balance_hex: account.balance.to_hex(),
             storage_root: format!("0x{}", hex::encode(account.storage_root)),
             code_hash: format!("0x{}", hex::encode(account.code_hash)),
             is_contract: account.is_contract(),
             verified: true,
-            verified_against_slot: self.state.finalized_header.slot,
+            verified_against_slot: self.head_slot(),
             proof_nodes_verified: proof_node_count,
             rpc_claimed_balance: rpc_proof.balance.clone(),
+            discrepancies: self.check_rpc_claims(address, None, &rpc_proof, &account),
         };
 
         serde_wasm_bindgen::to_value(&result)
-            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+            .map_err(|e| LumenError::serialization(e).into())
     }
 
     /// Verify an account proof against an EXPLICIT state root (not the internal one).
@@ -508,6 +1199,7 @@ impl LumenClient {
     /// and block number at the same instant, fetches the proof (async), then passes
     /// the originally-captured state root here. Even if the internal state advanced
     /// during the network round-trip, verification uses the correct root.
+    #[wasm_bindgen(unchecked_return_type = "VerifiedAccountResponse")]
     pub fn verify_account_rpc_proof_with_root(
         &self,
         state_root_hex: &str,
@@ -516,60 +1208,65 @@ impl LumenClient {
     ) -> Result<JsValue, JsValue> {
         let root_hex = state_root_hex.strip_prefix("0x").unwrap_or(state_root_hex);
         let root_bytes = hex::decode(root_hex)
-            .map_err(|e| JsValue::from_str(&format!("Invalid state root hex: {}", e)))?;
+            .map_err(|e| LumenError::new(ErrorCode::InvalidInput, format!("Invalid state root hex: {}", e)))?;
         if root_bytes.len() != 32 {
-            return Err(JsValue::from_str(&format!(
+            return Err(LumenError::new(ErrorCode::InvalidInput, format!(
                 "State root must be 32 bytes, got {}",
                 root_bytes.len()
-            )));
+            )).into());
         }
         let mut state_root = [0u8; 32];
         state_root.copy_from_slice(&root_bytes);
 
         let rpc_proof: beacon_api::RpcGetProofResponse =
             serde_json::from_str(rpc_proof_json)
-                .map_err(|e| JsValue::from_str(&format!("Invalid proof JSON: {}", e)))?;
+                .map_err(|e| LumenError::new(ErrorCode::InvalidJson, format!("Invalid proof JSON: {}", e)))?;
 
         let addr_hex = address.strip_prefix("0x").unwrap_or(address);
         let addr_bytes = hex::decode(addr_hex)
-            .map_err(|e| JsValue::from_str(&format!("Invalid address: {}", e)))?;
+            .map_err(|e| LumenError::new(ErrorCode::InvalidInput, format!("Invalid address: {}", e)))?;
         if addr_bytes.len() != 20 {
-            return Err(JsValue::from_str("Address must be 20 bytes"));
+            return Err(LumenError::new(ErrorCode::InvalidInput, "Address must be 20 bytes").into());
         }
         let mut addr = [0u8; 20];
         addr.copy_from_slice(&addr_bytes);
 
         let account_proof = rpc_proof
             .to_core_account_proof(&addr)
-            .map_err(|e| JsValue::from_str(&format!("Proof conversion: {}", e)))?;
+            .map_err(|e| LumenError::new(ErrorCode::InvalidJson, format!("Proof conversion: {}", e)))?;
 
         let proof_node_count = account_proof.proof.len();
 
-        let account = lumen_core::execution::proof::verify_account_proof(state_root, addr, &account_proof)
-            .map_err(|e| JsValue::from_str(&format!("Proof verification failed: {}", e)))?;
+        let account = metrics::proof(|| {
+            lumen_core::execution::proof::verify_account_proof(state_root, addr, &account_proof)
+        })
+        .map_err(|e| LumenError::new(ErrorCode::ProofInvalid, format!("Proof verification failed: {}", e)))?;
 
-        log_to_console(&format!(
-            "[Lumen] Account {} verified against explicit root 0x{}: {} nodes, balance=0x{}",
+        lumen_log!(
+            Debug,
+            "proof",
+            "Account {} verified against explicit root 0x{}: {} nodes, balance={}",
             address,
             &root_hex[..8],
             proof_node_count,
-            hex::encode(account.balance)
-        ));
+            account.balance.to_hex()
+        );
 
         let result = VerifiedAccountResponse {
             nonce: account.nonce,
-            balance_hex: format!("0x{}", account.balance_hex()),
+            balance_hex: account.balance.to_hex(),
             storage_root: format!("0x{}", hex::encode(account.storage_root)),
             code_hash: format!("0x{}", hex::encode(account.code_hash)),
             is_contract: account.is_contract(),
             verified: true,
-            verified_against_slot: self.state.finalized_header.slot,
+            verified_against_slot: self.head_slot(),
             proof_nodes_verified: proof_node_count,
             rpc_claimed_balance: rpc_proof.balance.clone(),
+            discrepancies: self.check_rpc_claims(address, None, &rpc_proof, &account),
         };
 
         serde_wasm_bindgen::to_value(&result)
-            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+            .map_err(|e| LumenError::serialization(e).into())
     }
 
     /// Fetch an account's Merkle proof from an execution RPC and verify it.
@@ -581,22 +1278,29 @@ impl LumenClient {
     /// 4. Cross-checks: latest block ≥ BLS-verified finalized block
     /// 5. Returns the verified account state
     ///
-    /// The RPC endpoints are tried in order. All data from RPCs is untrusted
+    /// With `set_proof_mode("finalized")`, steps 1 and 4 are skipped: the
+    /// proof is requested at the finalized block and verified against its
+    /// BLS-verified state root.
+    ///
+    /// The RPC endpoints are tried healthiest first (see `get_rpc_health`).
+    /// All data from RPCs is untrusted
     /// and verified locally.
+    #[wasm_bindgen(unchecked_return_type = "FetchVerifyAccountResult")]
     pub async fn fetch_and_verify_account(
         &self,
         address: &str,
         rpc_endpoints_json: &str,
     ) -> Result<JsValue, JsValue> {
         let endpoints: Vec<String> = serde_json::from_str(rpc_endpoints_json)
-            .map_err(|e| JsValue::from_str(&format!("Invalid endpoints JSON: {}", e)))?;
+            .map_err(|e| LumenError::new(ErrorCode::InvalidJson, format!("Invalid endpoints JSON: {}", e)))?;
 
         if endpoints.is_empty() {
-            return Err(JsValue::from_str("No RPC endpoints provided"));
+            return Err(LumenError::new(ErrorCode::NoEndpoints, "No RPC endpoints provided").into());
         }
 
         let finalized_block_num = self
             .state
+            .borrow()
             .latest_execution_payload_header
             .as_ref()
             .map(|h| h.block_number)
@@ -604,45 +1308,581 @@ impl LumenClient {
 
         let mut last_error = String::from("No endpoints tried");
 
-        for endpoint in &endpoints {
+        for endpoint in self.ranked_endpoints(&endpoints) {
             match self
-                .try_fetch_and_verify(endpoint, address, finalized_block_num)
+                .tracked(endpoint, self.try_fetch_and_verify(endpoint, address, finalized_block_num))
                 .await
             {
                 Ok(result) => return Ok(result),
                 Err(e) => {
-                    let msg = e.as_string().unwrap_or_default();
-                    log_to_console(&format!(
-                        "[Lumen] RPC {} failed: {}",
-                        endpoint, msg
-                    ));
-                    last_error = msg;
+                    lumen_log!(
+                        Warn,
+                        "rpc",
+                        "RPC {} failed: {}",
+                        endpoint, e.message
+                    );
+                    last_error = e.message;
                 }
             }
         }
 
-        Err(JsValue::from_str(&format!(
+        Err(LumenError::new(ErrorCode::RequestFailed, format!(
             "All RPC endpoints failed. Last error: {}",
             last_error
-        )))
+        )).into())
+    }
+
+    /// Fetch storage slots via eth_getProof and verify them end to end.
+    ///
+    /// address: hex-encoded contract address (0x...)
+    /// slots_json: JSON array of hex-encoded storage slots (0x...), each left-padded to 32 bytes
+    /// rpc_endpoints_json: JSON array of RPC URLs, tried healthiest first
+    ///
+    /// The storage counterpart of `fetch_and_verify_account`: the latest block
+    /// (or the finalized one, per `set_proof_mode`) and an eth_getProof for all
+    /// slots are fetched in one round, the account
+    /// proof is verified against the block's state root, and every slot is
+    /// verified against the account's verified storage root. An endpoint whose
+    /// proof fails for any slot is skipped for the next one. Values are 32-byte
+    /// hex, as eth_getStorageAt returns them, in the order the slots were given.
+    #[wasm_bindgen(unchecked_return_type = "StorageFetchResult")]
+    pub async fn fetch_and_verify_storage(
+        &self,
+        address: &str,
+        slots_json: &str,
+        rpc_endpoints_json: &str,
+    ) -> Result<JsValue, JsValue> {
+        provider::parse_address(address).map_err(|e| LumenError::new(ErrorCode::InvalidInput, e))?;
+        let slots: Vec<String> = serde_json::from_str(slots_json)
+            .map_err(|e| LumenError::new(ErrorCode::InvalidJson, format!("Invalid slots JSON: {}", e)))?;
+        if slots.is_empty() {
+            return Err(LumenError::new(ErrorCode::InvalidInput, "No storage slots provided").into());
+        }
+        let slots = slots
+            .iter()
+            .map(|slot| provider::parse_slot(slot))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| LumenError::new(ErrorCode::InvalidInput, e))?;
+
+        let endpoints: Vec<String> = serde_json::from_str(rpc_endpoints_json)
+            .map_err(|e| LumenError::new(ErrorCode::InvalidJson, format!("Invalid endpoints JSON: {}", e)))?;
+        if endpoints.is_empty() {
+            return Err(LumenError::new(ErrorCode::NoEndpoints, "No RPC endpoints provided").into());
+        }
+
+        let finalized_block_num = self.finalized_block_number();
+        let mut last_error = String::from("No endpoints tried");
+
+        for endpoint in self.ranked_endpoints(&endpoints) {
+            let attempt = self.try_fetch_and_verify_storage(endpoint, address, &slots, finalized_block_num);
+            match self.tracked(endpoint, attempt).await {
+                Ok(result) => return Ok(result),
+                Err(e) => {
+                    lumen_log!(Warn, "rpc", "RPC {} failed: {}", endpoint, e.message);
+                    last_error = e.message;
+                }
+            }
+        }
+
+        Err(LumenError::new(ErrorCode::RequestFailed, format!(
+            "All RPC endpoints failed. Last error: {}",
+            last_error
+        )).into())
+    }
+
+    /// Fetch a transaction receipt and verify it against the receipts root
+    /// of the BLS-verified execution payload.
+    ///
+    /// tx_hash: hex-encoded transaction hash (0x...)
+    /// rpc_endpoints_json: JSON array of RPC URLs, tried healthiest first
+    ///
+    /// The receipt's location comes from eth_getTransactionReceipt. The whole
+    /// block's receipts are then fetched with eth_getBlockReceipts, the
+    /// receipts trie is rebuilt locally, and its root must equal the verified
+    /// `receipts_root` of that block. The block must be the BLS-verified block
+    /// or one of its last 256 ancestors, whose header is checked by parent hash.
+    ///
+    /// The block's transactions are fetched with debug_getRawBlock and its
+    /// transactions trie rebuilt the same way, proving which transaction sits
+    /// at the receipt's position; it must hash to `tx_hash`.
+    ///
+    /// Returns the standard receipt JSON with `verified: true`, every field
+    /// derived from the proven transaction, receipts and header.
+    #[wasm_bindgen(unchecked_return_type = "VerifiedReceipt")]
+    pub async fn fetch_and_verify_receipt(
+        &self,
+        tx_hash: &str,
+        rpc_endpoints_json: &str,
+    ) -> Result<JsValue, JsValue> {
+        let receipt = self.fetch_receipt_from_any(tx_hash, rpc_endpoints_json).await?;
+        receipt
+            .serialize(&serde_wasm_bindgen::Serializer::json_compatible())
+            .map_err(|e| LumenError::serialization(e).into())
+    }
+
+    /// Fetch and verify the receipt of the bundle transaction `tx_hash` as
+    /// `fetch_and_verify_receipt` does, then find the `UserOperationEvent`
+    /// for `user_op_hash` in it as `verify_user_operation` does.
+    ///
+    /// tx_hash: the bundle's transaction hash, e.g. `receipt.transactionHash`
+    /// from a bundler's eth_getUserOperationReceipt — only a hint of where
+    /// to look, since the event itself is checked against the verified receipt
+    #[wasm_bindgen(unchecked_return_type = "VerifiedUserOperation")]
+    pub async fn fetch_and_verify_user_operation(
+        &self,
+        user_op_hash: &str,
+        tx_hash: &str,
+        rpc_endpoints_json: &str,
+        entry_point: Option<String>,
+    ) -> Result<JsValue, JsValue> {
+        let (hash, entry_points) = parse_user_operation_query(user_op_hash, entry_point.as_deref())?;
+        let receipt = self.fetch_receipt_from_any(tx_hash, rpc_endpoints_json).await?;
+        let receipt: beacon_api::RpcReceipt = serde_json::from_value(receipt)
+            .map_err(|e| LumenError::new(ErrorCode::RequestFailed, format!("Receipt parse: {}", e)))?;
+        let failed = |e: String| LumenError::new(ErrorCode::RequestFailed, e);
+        let logs = receipt.to_core_receipt().map_err(failed)?.logs;
+        let tx_index = receipt.tx_index().map_err(failed)?;
+        let block_number = receipt.block_number().map_err(failed)?;
+        let result = find_user_operation(&logs, &hash, &entry_points, tx_index, block_number)?;
+        result
+            .serialize(&serde_wasm_bindgen::Serializer::json_compatible())
+            .map_err(|e| LumenError::serialization(e).into())
+    }
+
+    /// Check a signed transaction against verified state before sending it.
+    ///
+    /// raw_tx_hex: the signed EIP-2718 envelope (0x...)
+    ///
+    /// Recovers the sender, proves its nonce and balance at the finalized
+    /// block, and checks the chain id, nonce, gas limit and fees against
+    /// that block's verified header. `ok` is false if any issue has
+    /// severity "error": no node would accept the transaction. Warnings
+    /// (a nonce gap, a fee cap below the base fee) may clear as the chain
+    /// moves on. Nothing is sent.
+    #[wasm_bindgen(unchecked_return_type = "PreflightReport")]
+    pub async fn preflight(&self, raw_tx_hex: &str) -> Result<JsValue, JsValue> {
+        let report = self.preflight_report(raw_tx_hex).await.map_err(LumenError::from)?;
+        report
+            .serialize(&serde_wasm_bindgen::Serializer::json_compatible())
+            .map_err(|e| LumenError::serialization(e).into())
+    }
+
+    /// Broadcast a signed raw transaction to every configured endpoint.
+    ///
+    /// raw_tx_hex: the signed EIP-2718 envelope (0x...)
+    ///
+    /// All endpoints are sent the transaction concurrently; each answer is
+    /// checked against the locally computed hash. Succeeds if any endpoint
+    /// accepted it, and starts watching the transaction — call
+    /// `poll_transactions` after each finality update to collect its
+    /// verified receipt.
+    #[wasm_bindgen(unchecked_return_type = "BroadcastOutcome")]
+    pub async fn broadcast_transaction(&self, raw_tx_hex: &str) -> Result<JsValue, JsValue> {
+        let outcome = self
+            .broadcast_raw_transaction(raw_tx_hex)
+            .await
+            .map_err(LumenError::from)?;
+        serde_wasm_bindgen::to_value(&outcome)
+            .map_err(|e| LumenError::serialization(e).into())
+    }
+
+    /// Watch a transaction until its receipt verifies against finalized state.
+    ///
+    /// tx_hash: hex-encoded transaction hash (0x...)
+    ///
+    /// Returns false if the hash was already being watched. Transactions sent
+    /// with `broadcast_transaction` are watched automatically.
+    pub fn watch_transaction(&self, tx_hash: &str) -> Result<bool, JsValue> {
+        beacon_api::hex_to_bytes32(tx_hash)
+            .map_err(|e| LumenError::new(ErrorCode::InvalidInput, format!("Invalid transaction hash: {}", e)))?;
+        Ok(self
+            .watcher
+            .borrow_mut()
+            .watch(tx_hash, self.finalized_block_number())
+            .is_some())
+    }
+
+    /// Stop watching a transaction. Returns false if it was not being watched.
+    pub fn unwatch_transaction(&self, tx_hash: &str) -> bool {
+        self.watcher.borrow_mut().unwatch(tx_hash).is_some()
+    }
+
+    /// Number of transactions still being watched.
+    pub fn watched_transaction_count(&self) -> usize {
+        self.watcher.borrow().len()
+    }
+
+    /// Advance every watched transaction and return the status changes.
+    ///
+    /// Call after each finality update. Each event is
+    /// `{tx_hash, status, block_number?, receipt?, error?}` with status one of
+    /// `included` (in a block the RPC names, not verified yet),
+    /// `finalized-verified` (receipt verified, transaction succeeded) or
+    /// `failed` (verified revert, or dropped). Transactions reaching
+    /// `finalized-verified` or `failed` stop being watched. One an RPC places
+    /// too far back to verify stays `included` until unwatched.
+    #[wasm_bindgen(unchecked_return_type = "TransactionEvent[]")]
+    pub async fn poll_transactions(&self) -> Result<JsValue, JsValue> {
+        self.poll_transaction_events()
+            .await
+            .serialize(&serde_wasm_bindgen::Serializer::json_compatible())
+            .map_err(|e| LumenError::serialization(e).into())
+    }
+
+    /// `poll_transactions` without the conversion to JS.
+    pub(crate) async fn poll_transaction_events(&self) -> Vec<watcher::TransactionEvent> {
+        let finalized = self.finalized_block_number();
+        let watched = self.watcher.borrow().watched_hashes();
+        let mut events = Vec::new();
+
+        for tx_hash in watched {
+            let located = match self
+                .try_endpoints(|endpoint| self.locate_transaction(endpoint, &tx_hash))
+                .await
+            {
+                Ok(Some(located)) => located,
+                Ok(None) => {
+                    events.extend(self.watcher.borrow_mut().record_unseen(&tx_hash));
+                    continue;
+                }
+                // RPC trouble says nothing about the transaction; try again next poll
+                Err(_) => continue,
+            };
+            let Ok(block_number) = located.block_number() else {
+                continue;
+            };
+
+            // `block_number` is the RPC's claim, so it settles nothing until
+            // the receipt verifies: that reaches the block's header from the
+            // finalized block, verifying the number too.
+            let status = if block_number > finalized {
+                watcher::TxStatus::Included { block_number }
+            } else {
+                match self
+                    .try_endpoints(|endpoint| self.verify_located_receipt(endpoint, &tx_hash, &located))
+                    .await
+                {
+                    Ok(receipt) if receipt.get("status").and_then(|s| s.as_str()) == Some("0x0") => {
+                        watcher::TxStatus::Failed {
+                            reason: "transaction reverted".to_string(),
+                            receipt: Some(receipt),
+                        }
+                    }
+                    Ok(receipt) => watcher::TxStatus::FinalizedVerified {
+                        block_number,
+                        receipt,
+                    },
+                    // Not verifiable (yet, or at all if the RPC placed it in a
+                    // block that was reorganized away or is too far back);
+                    // keep waiting.
+                    Err(_) => watcher::TxStatus::Included { block_number },
+                }
+            };
+            events.extend(self.watcher.borrow_mut().transition(&tx_hash, status));
+        }
+        events
+    }
+
+    /// Subscribe to verified chain events, emulating EIP-1193 `eth_subscribe`.
+    ///
+    /// kind: `"newHeads"` — the only stream Lumen can serve from verified data
+    /// callback: invoked with `{type: "eth_subscription", data: {subscription, result}}`
+    /// each time a BLS-verified update advances the execution header
+    ///
+    /// Returns the subscription id.
+    pub fn subscribe(
+        &self,
+        kind: &str,
+        #[wasm_bindgen(unchecked_param_type = "(message: NewHeadsMessage) => void")] callback: js_sys::Function,
+    ) -> Result<String, JsValue> {
+        let kind = subscriptions::SubscriptionKind::parse(kind)
+            .map_err(|e| LumenError::new(ErrorCode::InvalidInput, e))?;
+        Ok(self.subscriptions.borrow_mut().subscribe(kind, callback))
+    }
+
+    /// Cancel a subscription. Returns false if the id is unknown.
+    pub fn unsubscribe(&self, subscription_id: &str) -> bool {
+        self.subscriptions.borrow_mut().unsubscribe(subscription_id)
+    }
+
+    /// Register `callback` to receive structured client events, replacing
+    /// any previous handler; pass `undefined` to remove it.
+    ///
+    /// Each event is an object with a `type` field:
+    /// - `head_advanced` — `{ finalized_slot, period }`
+    /// - `execution_root_changed` — `{ state_root, block_number }`
+    /// - `update_rejected` — `{ update_slot, reason }`: failed verification, state unchanged
+    /// - `error` — `{ source, code, message }`: input that could not be
+    ///   processed, or a finality update the poller could not fetch
+    pub fn set_event_handler(
+        &self,
+        #[wasm_bindgen(unchecked_param_type = "((event: ClientEvent) => void) | undefined")]
+        callback: Option<js_sys::Function>,
+    ) {
+        *self.event_handler.borrow_mut() = callback;
+    }
+
+    /// Keep the client at the head: fetch the latest finality update from
+    /// `beacon_endpoints_json` every `interval_ms` milliseconds, BLS-verify
+    /// it and advance, backfilling committee periods first when the update
+    /// is from a later period than the client.
+    ///
+    /// `beacon_endpoints_json` is a JSON array of beacon API base URLs, tried
+    /// in order; empty for the network's built-in providers. `interval_ms`
+    /// must be at least 1000; one finality update per epoch (384 s) is the
+    /// most there is to find.
+    ///
+    /// Progress is reported through the usual channels: `head_advanced` and
+    /// `execution_root_changed` events, `newHeads` subscriptions, and an
+    /// `error` event with source `finality_polling` when no endpoint serves
+    /// a finality update.
+    /// A failed round is retried at the next interval.
+    ///
+    /// The first round starts immediately. The returned promise resolves
+    /// when polling stops — do not await it unless that is what you want.
+    /// Calling this again replaces the running schedule.
+    ///
+    /// Within four epochs of a period's end, a round that leaves the next
+    /// sync committee unknown also fetches the period's update, so the
+    /// committee is in hand before the first update it signs.
+    ///
+    /// Polling stops on `stop_finality_polling` or when `signal` aborts,
+    /// abandoning a round or wait in progress.
+    pub async fn start_finality_polling(
+        &self,
+        beacon_endpoints_json: &str,
+        interval_ms: u32,
+        signal: Option<web_sys::AbortSignal>,
+    ) -> Result<(), JsValue> {
+        if interval_ms < sync::MIN_POLL_INTERVAL_MS {
+            return Err(LumenError::new(
+                ErrorCode::InvalidInput,
+                format!("interval_ms must be at least {}", sync::MIN_POLL_INTERVAL_MS),
+            )
+            .into());
+        }
+        let endpoints = checkpoint_provider_urls(beacon_endpoints_json, &self.network.name)
+            .map_err(|e| LumenError::new(ErrorCode::InvalidConfig, e))?;
+
+        let cancellation = cancel::Cancellation::default();
+        if let Some(signal) = &signal {
+            cancellation.follow(signal);
+        }
+        if let Some(previous) = self.polling.replace(Some(cancellation.clone())) {
+            previous.cancel();
+        }
+        lumen_log!(
+            Info,
+            "sync",
+            "Polling {} beacon endpoint(s) for finality updates every {} ms",
+            endpoints.len(),
+            interval_ms
+        );
+
+        cancellation
+            .run(async {
+                loop {
+                    if let Err(e) = self.poll_finality(&endpoints).await {
+                        // Already reported as an event; the next round tries again.
+                        lumen_log!(Debug, "sync", "Finality poll round failed: {:?}", e);
+                    }
+                    if let Err(e) = self.prefetch_next_committee(&endpoints).await {
+                        lumen_log!(Debug, "sync", "Next committee prefetch failed: {:?}", e);
+                    }
+                    self.warn_if_stale();
+                    self.clock.sleep_ms(interval_ms).await;
+                }
+            })
+            .await;
+        lumen_log!(Info, "sync", "Finality polling stopped");
+        Ok(())
+    }
+
+    /// Stop `start_finality_polling`, abandoning a round in flight.
+    pub fn stop_finality_polling(&self) {
+        if let Some(polling) = self.polling.take() {
+            polling.cancel();
+        }
+    }
+
+    /// Choose the block account and storage proofs are requested at.
+    ///
+    /// mode: `"latest"` (default) asks for the RPC's latest block and checks
+    /// it is not behind the finalized block. `"finalized"` asks for the
+    /// BLS-verified finalized block and verifies the proof against the state
+    /// root from its verified header, so nothing about the block comes from
+    /// the RPC and "latest" cannot move between the two requests. The RPC
+    /// must still serve state for that block, which every full node does for
+    /// recent blocks.
+    ///
+    /// Applies to `fetch_and_verify_account` and `fetch_and_verify_storage`.
+    /// `handle_request`'s account methods (eth_getBalance,
+    /// eth_getTransactionCount, eth_getCode, eth_getStorageAt) always use the
+    /// finalized block, or an older one verified back from it.
+    pub fn set_proof_mode(&self, mode: &str) -> Result<(), JsValue> {
+        let mode = mode.parse().map_err(|e| LumenError::new(ErrorCode::InvalidConfig, e))?;
+        self.proof_mode.set(mode);
+        Ok(())
+    }
+
+    /// Configure the execution RPC endpoints used by `handle_request`.
+    ///
+    /// endpoints_json: JSON array of RPC URLs. They are tried healthiest
+    /// first; the order given breaks ties.
+    pub fn set_rpc_endpoints(&mut self, endpoints_json: &str) -> Result<(), JsValue> {
+        let endpoints: Vec<String> = serde_json::from_str(endpoints_json)
+            .map_err(|e| LumenError::new(ErrorCode::InvalidJson, format!("Invalid endpoints JSON: {}", e)))?;
+        self.rpc_endpoints = endpoints;
+        Ok(())
+    }
+
+    /// Limit how fast execution RPC requests go out.
+    ///
+    /// limits_json: `{ per_endpoint, global, max_queue_ms }`, each limit
+    /// `{ requests_per_second, burst }` or `null` for none. Omitted fields
+    /// keep their defaults: 10/s (burst 20) per endpoint, 25/s (burst 50)
+    /// overall, and requests queued for at most 10 s. A request that would
+    /// wait longer is refused with a `rate_limited` error — JSON-RPC code
+    /// -32005 from `handle_request` — and does not count against the
+    /// endpoint's health.
+    pub fn set_rate_limits(&self, limits_json: &str) -> Result<(), JsValue> {
+        let config: rate_limit::RateLimitConfig = serde_json::from_str(limits_json)
+            .map_err(|e| LumenError::new(ErrorCode::InvalidJson, format!("Invalid rate limits JSON: {}", e)))?;
+        config.validate().map_err(|e| LumenError::new(ErrorCode::InvalidConfig, e))?;
+        *self.rate_limiter.borrow_mut() = rate_limit::RateLimiter::new(config);
+        Ok(())
+    }
+
+    /// Configure the cache of raw `eth_getBlockByNumber` / `eth_getProof`
+    /// results. Cached results are still verified on every use.
+    ///
+    /// ttl_ms: how long a result is reused (default 12000, one slot); 0
+    /// turns the cache off. Results for "latest" and other block tags are
+    /// also dropped whenever the verified head advances.
+    /// max_entries: oldest results are evicted beyond this (default 256)
+    pub fn set_response_cache(&self, ttl_ms: f64, max_entries: Option<usize>) {
+        *self.response_cache.borrow_mut() = response_cache::ResponseCache::new(
+            ttl_ms,
+            max_entries.unwrap_or(response_cache::DEFAULT_MAX_ENTRIES),
+        );
+    }
+
+    /// Success counts, average latency and quarantine state of every RPC
+    /// endpoint this client has used, in the order they would be tried.
+    ///
+    /// Endpoints are quarantined after 3 consecutive failures (including
+    /// proofs that failed verification) for 30 seconds, doubling per further
+    /// failure up to 10 minutes, and skipped while another endpoint is usable.
+    #[wasm_bindgen(unchecked_return_type = "EndpointHealth[]")]
+    pub fn get_rpc_health(&self) -> Result<JsValue, JsValue> {
+        self.rpc_health
+            .borrow()
+            .report(self.clock.now_ms())
+            .serialize(&serde_wasm_bindgen::Serializer::json_compatible())
+            .map_err(|e| LumenError::serialization(e).into())
+    }
+
+    /// Whether the client's answers are current, for "data may be stale"
+    /// banners and the like.
+    ///
+    /// `status` is the worst of: `"syncing"` (no update applied yet),
+    /// `"stale"` (the finalized head is more than 4 epochs behind the wall
+    /// clock), `"degraded"` (every configured RPC endpoint is quarantined)
+    /// and otherwise `"healthy"`. Alongside it are the checkpoint's age,
+    /// the finalized head's lag in slots, when the head last advanced, the
+    /// `get_rpc_health` report and the P2P state from `set_p2p_status`.
+    #[wasm_bindgen(unchecked_return_type = "ClientHealth")]
+    pub fn health(&self) -> Result<JsValue, JsValue> {
+        self.health_report()
+            .serialize(&serde_wasm_bindgen::Serializer::json_compatible())
+            .map_err(|e| LumenError::serialization(e).into())
+    }
+
+    /// Record the P2P bridge's connection state for `health`.
+    ///
+    /// connection_mode: `"direct-webtransport"`, `"direct-webrtc"`,
+    /// `"relay"` or `"disconnected"`
+    pub fn set_p2p_status(&self, connection_mode: &str, peer_count: u32) -> Result<(), JsValue> {
+        if !["direct-webtransport", "direct-webrtc", "relay", "disconnected"].contains(&connection_mode) {
+            return Err(LumenError::new(
+                ErrorCode::InvalidInput,
+                format!("Unknown connection mode: {}", connection_mode),
+            )
+            .into());
+        }
+        *self.p2p_status.borrow_mut() = health::P2pStatus {
+            connection_mode: connection_mode.to_string(),
+            peer_count,
+        };
+        Ok(())
+    }
+
+    /// Resolve an ENS name to its ETH address without eth_call.
+    ///
+    /// The registry's record for the name and the resolver's address record
+    /// are read with storage proofs at the finalized block, from the
+    /// endpoints set with `set_rpc_endpoints`. `address` is null if the
+    /// name is unregistered or has no address. Only names using the ENS
+    /// PublicResolver can be resolved this way; others fail with
+    /// `REQUEST_FAILED`. The name must be ENSIP-15 normalized; ASCII names
+    /// are lowercased. Also available as the `lumen_resolveName` method.
+    #[wasm_bindgen(unchecked_return_type = "EnsResolution")]
+    pub async fn resolve_ens(&self, name: &str) -> Result<JsValue, JsValue> {
+        let resolution = self.resolve_ens_name(name).await.map_err(LumenError::from)?;
+        resolution
+            .serialize(&serde_wasm_bindgen::Serializer::json_compatible())
+            .map_err(|e| LumenError::serialization(e).into())
+    }
+
+    /// Handle an EIP-1193 JSON-RPC request end to end.
+    ///
+    /// request_json: `{ "method": ..., "params": [...], "id": ... }`
+    ///
+    /// Always resolves to a JSON-RPC response string: failures (including
+    /// verification failures) are reported in its `error` field, never as
+    /// unverified data.
+    pub async fn handle_request(&self, request_json: &str) -> Result<String, JsValue> {
+        let response = match provider::parse_request_json(request_json) {
+            Ok(request) => self.dispatch(&request).await,
+            Err(message) => provider::error_response(serde_json::Value::Null, provider::PARSE_ERROR, message),
+        };
+        serde_json::to_string(&response)
+            .map_err(|e| LumenError::serialization(e).into())
+    }
+
+    /// EIP-1193 `request`: resolve to the verified result, or reject with a
+    /// `ProviderRpcError` carrying the standard `code` (4200 unsupported
+    /// method, 4900 no RPC configured, 4901 not synced, ...).
+    ///
+    /// The same dispatch as `handle_request`, without the JSON envelope or
+    /// the `lumen` verification metadata.
+    #[wasm_bindgen(unchecked_return_type = "unknown")]
+    pub async fn request(
+        &self,
+        #[wasm_bindgen(unchecked_param_type = "RequestArguments")] args: JsValue,
+    ) -> Result<JsValue, JsValue> {
+        let request = provider::parse_request_args(args)?;
+        provider::settle(self.dispatch(&request).await)
     }
 
     /// Get the execution state info for the TypeScript layer.
+    #[wasm_bindgen(unchecked_return_type = "ExecutionStateResponse")]
     pub fn get_execution_state(&self) -> Result<JsValue, JsValue> {
         let exec_state = ExecutionStateResponse {
-            has_state_root: self.state.latest_execution_payload_header.is_some(),
+            has_state_root: self.state.borrow().latest_execution_payload_header.is_some(),
             state_root: self.execution_state_root().unwrap_or_default(),
             block_number: self
                 .state
+                .borrow()
                 .latest_execution_payload_header
                 .as_ref()
                 .map(|h| h.block_number)
                 .unwrap_or(0),
-            finalized_slot: self.state.finalized_header.slot,
+            finalized_slot: self.head_slot(),
         };
 
         serde_wasm_bindgen::to_value(&exec_state)
-            .map_err(|e| JsValue::from_str(&e.to_string()))
+            .map_err(|e| LumenError::serialization(e).into())
     }
 }
 
@@ -666,6 +1906,44 @@ struct StorageValueResponse {
     verified_against_slot: u64,
 }
 
+#[derive(Serialize, Deserialize)]
+struct StorageBatchResponse {
+    storage_root: String,
+    slots: Vec<StorageSlotResult>,
+    verified_count: usize,
+    verified_against_slot: u64,
+}
+
+#[derive(Serialize, Deserialize)]
+struct StorageSlotResult {
+    key: String,
+    /// Verified value, present only when `verified` is true.
+    value: Option<String>,
+    verified: bool,
+    /// Why this slot failed verification, if it did.
+    error: Option<String>,
+}
+
+impl StorageSlotResult {
+    fn new(key: &[u8; 32], result: Result<U256, ProofError>) -> Self {
+        let key = format!("0x{}", hex::encode(key));
+        match result {
+            Ok(value) => Self {
+                key,
+                value: Some(format!("0x{}", hex::encode(value.to_be_bytes()))),
+                verified: true,
+                error: None,
+            },
+            Err(e) => Self {
+                key,
+                value: None,
+                verified: false,
+                error: Some(e.to_string()),
+            },
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize)]
 struct SyncStateResponse {
     head_slot: u64,
@@ -686,6 +1964,16 @@ struct FinalityUpdateResult {
     message: String,
 }
 
+#[derive(Serialize, Default)]
+struct CommitteeBackfillResult {
+    advanced: usize,
+    committees_stored: usize,
+    skipped: usize,
+    finalized_slot: u64,
+    period: u64,
+    has_next_committee: bool,
+}
+
 #[derive(Serialize, Deserialize)]
 struct VerifiedAccountResponse {
     nonce: u64,
@@ -697,6 +1985,169 @@ struct VerifiedAccountResponse {
     verified_against_slot: u64,
     proof_nodes_verified: usize,
     rpc_claimed_balance: String,
+    discrepancies: Vec<beacon_api::Discrepancy>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct LogResponse {
+    address: String,
+    topics: Vec<String>,
+    data: String,
+}
+
+impl From<&Log> for LogResponse {
+    fn from(log: &Log) -> Self {
+        LogResponse {
+            address: format!("0x{}", hex::encode(log.address)),
+            topics: log.topics.iter().map(|topic| format!("0x{}", hex::encode(topic))).collect(),
+            data: format!("0x{}", hex::encode(&log.data)),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct ReceiptProofResponse {
+    tx_type: u8,
+    status: u8,
+    cumulative_gas_used: u64,
+    logs_bloom: String,
+    logs: Vec<LogResponse>,
+    transaction_index: u64,
+    block_number: u64,
+    receipts_root: String,
+    verified: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+struct VerifiedLogResponse {
+    #[serde(flatten)]
+    log: LogResponse,
+    /// Position among the receipt's logs.
+    log_index: u32,
+    transaction_index: u64,
+    block_number: u64,
+    verified: bool,
+}
+
+/// A `UserOperationEvent` found in a verified receipt; `log_index` counts
+/// within the receipt.
+#[derive(Serialize, Deserialize)]
+struct VerifiedUserOperation {
+    user_op_hash: String,
+    entry_point: String,
+    sender: String,
+    /// None when the operation paid for itself.
+    paymaster: Option<String>,
+    nonce: String,
+    success: bool,
+    actual_gas_cost: String,
+    actual_gas_used: String,
+    log_index: u32,
+    transaction_index: u64,
+    block_number: u64,
+    verified: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+struct VerifiedTransactionResponse {
+    transaction_hash: String,
+    transaction_index: u64,
+    block_hash: String,
+    block_number: u64,
+    tx_type: u8,
+    chain_id: Option<u64>,
+    nonce: u64,
+    gas_price: Option<String>,
+    max_fee_per_gas: Option<String>,
+    max_priority_fee_per_gas: Option<String>,
+    gas_limit: u64,
+    /// Absent for contract creation.
+    to: Option<String>,
+    value: String,
+    input: String,
+    verified: bool,
+}
+
+impl VerifiedTransactionResponse {
+    fn new(verified: &VerifiedTransaction, header: &ExecutionBlockHeader) -> Self {
+        let tx = &verified.transaction;
+        VerifiedTransactionResponse {
+            transaction_hash: format!("0x{}", hex::encode(verified.transaction_hash)),
+            transaction_index: verified.transaction_index,
+            block_hash: format!("0x{}", hex::encode(header.hash())),
+            block_number: header.number,
+            tx_type: tx.tx_type.type_byte(),
+            chain_id: tx.chain_id,
+            nonce: tx.nonce,
+            gas_price: tx.gas_price.map(|price| price.to_hex()),
+            max_fee_per_gas: tx.max_fee_per_gas.map(|fee| fee.to_hex()),
+            max_priority_fee_per_gas: tx.max_priority_fee_per_gas.map(|fee| fee.to_hex()),
+            gas_limit: tx.gas_limit,
+            to: tx.to.map(|to| format!("0x{}", hex::encode(to))),
+            value: tx.value.to_hex(),
+            input: format!("0x{}", hex::encode(&tx.input)),
+            verified: true,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct VerifiedBlockHeaderResponse {
+    block_hash: String,
+    block_number: u64,
+    parent_hash: String,
+    ommers_hash: String,
+    miner: String,
+    state_root: String,
+    transactions_root: String,
+    receipts_root: String,
+    logs_bloom: String,
+    difficulty: String,
+    gas_limit: u64,
+    gas_used: u64,
+    timestamp: u64,
+    extra_data: String,
+    mix_hash: String,
+    nonce: String,
+    /// Fields from later forks are absent for headers from before the fork.
+    base_fee_per_gas: Option<String>,
+    withdrawals_root: Option<String>,
+    blob_gas_used: Option<u64>,
+    excess_blob_gas: Option<u64>,
+    parent_beacon_block_root: Option<String>,
+    requests_hash: Option<String>,
+    verified: bool,
+}
+
+impl From<&ExecutionBlockHeader> for VerifiedBlockHeaderResponse {
+    fn from(header: &ExecutionBlockHeader) -> Self {
+        let hex = |bytes: &[u8]| format!("0x{}", hex::encode(bytes));
+        VerifiedBlockHeaderResponse {
+            block_hash: hex(&header.hash()),
+            block_number: header.number,
+            parent_hash: hex(&header.parent_hash),
+            ommers_hash: hex(&header.ommers_hash),
+            miner: hex(&header.beneficiary),
+            state_root: hex(&header.state_root),
+            transactions_root: hex(&header.transactions_root),
+            receipts_root: hex(&header.receipts_root),
+            logs_bloom: hex(&header.logs_bloom),
+            difficulty: header.difficulty.to_hex(),
+            gas_limit: header.gas_limit,
+            gas_used: header.gas_used,
+            timestamp: header.timestamp,
+            extra_data: hex(&header.extra_data),
+            mix_hash: hex(&header.mix_hash),
+            nonce: hex(&header.nonce),
+            base_fee_per_gas: header.base_fee_per_gas.map(|fee| fee.to_hex()),
+            withdrawals_root: header.withdrawals_root.map(|root| hex(&root)),
+            blob_gas_used: header.blob_gas_used,
+            excess_blob_gas: header.excess_blob_gas,
+            parent_beacon_block_root: header.parent_beacon_block_root.map(|root| hex(&root)),
+            requests_hash: header.requests_hash.map(|hash| hex(&hash)),
+            verified: true,
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize)]
@@ -720,152 +2171,2413 @@ struct FetchVerifyAccountResult {
     proof_nodes_verified: usize,
     rpc_endpoint: String,
     rpc_claimed_balance: String,
+    discrepancies: Vec<beacon_api::Discrepancy>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct StorageFetchResult {
+    address: String,
+    storage_root: String,
+    slots: Vec<VerifiedSlotValue>,
+    verified: bool,
+    finalized_block: u64,
+    proof_block: u64,
+    proof_nodes_verified: usize,
+    rpc_endpoint: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct VerifiedSlotValue {
+    slot: String,
+    value: String,
 }
 
 // --- Private helpers ---
 
+/// An account proof fetched from an RPC and verified locally.
+pub(crate) struct FetchedAccount {
+    pub account: AccountState,
+    /// Outcomes for the requested storage slots, in request order.
+    pub storage: Vec<StorageSlotOutcome>,
+    /// The block the proof was taken at.
+    pub block_number: u64,
+    pub proof_nodes: usize,
+    /// Nodes across the proofs of the requested storage slots.
+    pub storage_proof_nodes: usize,
+    /// The balance the RPC claimed, kept only for display / cross-checking.
+    pub rpc_claimed_balance: String,
+    /// Claimed account fields that disagree with the proof.
+    pub discrepancies: Vec<beacon_api::Discrepancy>,
+}
+
 impl LumenClient {
-    async fn try_fetch_and_verify(
+    /// Send this client's execution RPC requests through `provider` instead
+    /// of the Fetch API.
+    pub fn set_network_provider(&mut self, provider: Box<dyn NetworkProvider>) {
+        self.transport = provider;
+    }
+
+    /// Read the time from `clock` instead of the system clock.
+    pub fn set_time_source(&mut self, clock: Box<dyn TimeSource>) {
+        self.clock = clock;
+    }
+
+    pub(crate) async fn rpc_call(
         &self,
         endpoint: &str,
-        address: &str,
-        finalized_block_num: u64,
-    ) -> Result<JsValue, JsValue> {
-        // 1. Fetch latest block header (state root)
-        let block_req = serde_json::json!({
-            "jsonrpc": "2.0",
-            "id": 1,
-            "method": "eth_getBlockByNumber",
-            "params": ["latest", false]
-        });
-        let block_resp_text = network::post_json(endpoint, &block_req.to_string())
-            .await
-            .map_err(|e| JsValue::from_str(&format!("Block fetch: {}", e)))?;
+        method: &str,
+        params: serde_json::Value,
+    ) -> Result<serde_json::Value, String> {
+        self.rpc_call_optional(endpoint, method, params)
+            .await?
+            .ok_or_else(|| format!("{} result is null", method))
+    }
 
-        let block_resp: serde_json::Value = serde_json::from_str(&block_resp_text)
-            .map_err(|e| JsValue::from_str(&format!("Block JSON parse: {}", e)))?;
+    pub(crate) async fn rpc_call_optional(
+        &self,
+        endpoint: &str,
+        method: &str,
+        params: serde_json::Value,
+    ) -> Result<Option<serde_json::Value>, String> {
+        let key = response_cache::ResponseCache::key(endpoint, method, &params);
+        if let Some(key) = &key {
+            if let Some(result) = self.response_cache.borrow_mut().get(key, self.clock.now_ms()) {
+                return Ok(Some(result));
+            }
+        }
+        self.throttle(endpoint).await?;
+        let result = network::rpc_call_optional(self.transport.as_ref(), endpoint, method, params.clone()).await?;
+        if let (Some(key), Some(result)) = (key, &result) {
+            self.response_cache.borrow_mut().insert(key, &params, result.clone(), self.clock.now_ms());
+        }
+        Ok(result)
+    }
+
+    /// Wait for a rate-limit token for `endpoint`, or fail if the queue is full.
+    async fn throttle(&self, endpoint: &str) -> Result<(), String> {
+        let wait_ms = self.rate_limiter.borrow_mut().reserve(endpoint, self.clock.now_ms())?;
+        if wait_ms > 0.0 {
+            lumen_log!(Debug, "rpc", "Request to {} queued {} ms by the rate limit", endpoint, wait_ms.ceil());
+            self.clock.sleep_ms(wait_ms.ceil() as u32).await;
+        }
+        Ok(())
+    }
+
+    /// Initialize the state from a parsed bootstrap.
+    fn from_bootstrap(
+        bootstrap: &LightClientBootstrap,
+        exec_header: Option<ExecutionPayloadHeader>,
+        network: NetworkConfig,
+    ) -> Result<LumenClient, JsValue> {
+        let committee_size = bootstrap.current_sync_committee.pubkeys.len();
+
+        let mut state = initialize_from_bootstrap(
+            bootstrap,
+            network.genesis_validators_root,
+            network.fork_version,
+        )
+        .map_err(|e| LumenError::new(ErrorCode::VerificationFailed, format!("Bootstrap init: {}", e)))?;
+
+        if let Some(exec) = exec_header {
+            lumen_log!(
+                Debug,
+                "client",
+                "Bootstrap execution state root: 0x{}",
+                hex::encode(exec.state_root)
+            );
+            state.latest_execution_payload_header = Some(exec);
+        }
+
+        lumen_log!(
+            Info,
+            "client",
+            "Initialized from {} beacon bootstrap — slot {}, {} sync committee members",
+            network.name, state.finalized_header.slot, committee_size
+        );
+
+        Ok(LumenClient::from_state(state, network))
+    }
+
+    /// `sync_to_head`, given up as soon as `cancellation` is cancelled.
+    pub(crate) async fn sync_to_head_until(
+        beacon_endpoints_json: &str,
+        on_progress: Option<js_sys::Function>,
+        network_config: Option<String>,
+        cancellation: &cancel::Cancellation,
+    ) -> Result<LumenClient, JsValue> {
+        cancellation
+            .run(Self::sync_to_head_inner(beacon_endpoints_json, on_progress, network_config))
+            .await
+            .unwrap_or_else(|| Err(LumenError::new(ErrorCode::Cancelled, "sync_to_head was cancelled").into()))
+    }
+
+    async fn sync_to_head_inner(
+        beacon_endpoints_json: &str,
+        on_progress: Option<js_sys::Function>,
+        network_config: Option<String>,
+    ) -> Result<LumenClient, JsValue> {
+        use sync::{SyncProgress, SyncStage};
+
+        let network_config = network_config.unwrap_or_else(|| "mainnet".to_string());
+        let endpoints =
+            checkpoint_provider_urls(beacon_endpoints_json, &network_config)
+                .map_err(|e| LumenError::new(ErrorCode::InvalidConfig, e))?;
+        let progress = |stage: SyncStage, message: String, finalized_slot: Option<u64>| {
+            report_progress(
+                on_progress.as_ref(),
+                &SyncProgress {
+                    stage,
+                    message,
+                    finalized_slot,
+                    done: None,
+                    total: None,
+                },
+            )
+        };
+
+        let required = sync::majority(endpoints.len());
+        progress(
+            SyncStage::Checkpoint,
+            format!("Asking {} providers, {} must agree", endpoints.len(), required),
+            None,
+        );
+        let urls_json = serde_json::to_string(&endpoints).map_err(LumenError::serialization)?;
+        let client =
+            Self::from_checkpoint_providers(&urls_json, required, Some(network_config)).await?;
+        progress(
+            SyncStage::Bootstrap,
+            format!("Bootstrapped in period {}", client.committee_position().0),
+            Some(client.head_slot()),
+        );
+
+        let finality = sync::fetch_from_any(&endpoints, sync::FINALITY_UPDATE_PATH)
+            .await
+            .map_err(|e| LumenError::new(ErrorCode::RequestFailed, e))?;
+        let head_period = finality_attested_period(&finality)?;
+
+        client
+            .backfill_committees(
+                &endpoints,
+                head_period,
+                |start_period, count| {
+                    progress(
+                        SyncStage::Backfill,
+                        format!("Fetching {} period update(s) from period {}", count, start_period),
+                        Some(client.head_slot()),
+                    )
+                },
+                |done, total| report_progress(on_progress.as_ref(), &client.backfill_progress(done, total)),
+            )
+            .await?;
+
+        progress(
+            SyncStage::Finality,
+            format!("Verifying finality update for period {}", head_period),
+            Some(client.head_slot()),
+        );
+        client.process_finality_payload(&finality)?;
+        progress(
+            SyncStage::Synced,
+            format!("Synced to finalized slot {}", client.head_slot()),
+            Some(client.head_slot()),
+        );
+        Ok(client)
+    }
+
+    fn from_state(state: LightClientState, network: NetworkConfig) -> Self {
+        let mut execution_history = history::ExecutionHistory::default();
+        if let Some(exec) = &state.latest_execution_payload_header {
+            execution_history.record(exec);
+        }
+        LumenClient {
+            checkpoint_slot: state.finalized_header.slot,
+            state: RefCell::new(state),
+            network,
+            rpc_endpoints: Vec::new(),
+            rpc_health: RefCell::default(),
+            rate_limiter: RefCell::default(),
+            response_cache: RefCell::default(),
+            watcher: RefCell::default(),
+            subscriptions: RefCell::default(),
+            event_handler: RefCell::default(),
+            polling: RefCell::default(),
+            proof_mode: Cell::default(),
+            execution_history: RefCell::new(execution_history),
+            transport: Box::new(network::FetchProvider),
+            clock: Box::new(clock::SystemClock),
+            aggregate_cache: RefCell::default(),
+            last_update_at_ms: Cell::new(None),
+            p2p_status: RefCell::default(),
+        }
+    }
+
+    /// BLS-verify a parsed finality update, advance the head and store the
+    /// execution payload header it carries.
+    fn apply_finality_update(
+        &self,
+        update: &LightClientUpdate,
+        exec_header: Option<ExecutionPayloadHeader>,
+    ) -> Result<JsValue, JsValue> {
+        let participation = update.sync_aggregate.num_participants();
+
+        // If the update doesn't advance us, skip silently
+        if update.finalized_header.slot <= self.head_slot() {
+            let result = FinalityUpdateResult {
+                verified: true,
+                advanced: false,
+                finalized_slot: self.head_slot(),
+                execution_state_root: self.execution_state_root().unwrap_or_default(),
+                execution_block_number: self
+                    .state
+                    .borrow()
+                    .latest_execution_payload_header
+                    .as_ref()
+                    .map(|h| h.block_number)
+                    .unwrap_or(0),
+                sync_participation: participation,
+                message: "Already at this slot or newer".into(),
+            };
+            metrics::with(|m| m.record_update(metrics::UpdateOutcome::Duplicate, None));
+            return serde_wasm_bindgen::to_value(&result)
+                .map_err(|e| LumenError::serialization(e).into());
+        }
+
+        let genesis_validators_root = self.state.borrow().genesis_validators_root;
+        let current_slot = self.head_slot();
+        let previous_root = self.state.borrow().verified_state_root();
+
+        // This is where BLS verification happens — the core trust operation
+        let started = metrics::now_ms();
+        let verified = self.check_not_future(update).and_then(|()| {
+            lumen_core::consensus::light_client::process_light_client_update_cached(
+                &mut self.state.borrow_mut(),
+                update,
+                current_slot,
+                genesis_validators_root,
+                &mut self.aggregate_cache.borrow_mut(),
+            )
+        });
+        let outcome = if verified.is_ok() {
+            metrics::UpdateOutcome::Applied
+        } else {
+            metrics::UpdateOutcome::Rejected
+        };
+        metrics::with(|m| m.record_update(outcome, Some(metrics::now_ms() - started)));
+        verified.map_err(|e| {
+            self.emit(events::ClientEvent::UpdateRejected {
+                update_slot: update.finalized_header.slot,
+                reason: e.to_string(),
+            });
+            LumenError::new(ErrorCode::VerificationFailed, format!("BLS verification failed: {}", e))
+        })?;
+
+        // BLS passed — store the execution state root
+        if let Some(exec) = exec_header {
+            lumen_log!(
+                Debug,
+                "consensus",
+                "BLS-verified execution state root: 0x{} (block #{})",
+                hex::encode(exec.state_root),
+                exec.block_number
+            );
+            self.store_execution_header(exec);
+        }
+        self.emit_head_advanced(previous_root);
+        if self.state.borrow().latest_execution_payload_header.is_some() {
+            self.notify_new_heads();
+        }
+
+        lumen_log!(
+            Info,
+            "consensus",
+            "BLS verification passed — {}/512 validators signed, slot {}",
+            participation, self.head_slot()
+        );
+
+        let result = FinalityUpdateResult {
+            verified: true,
+            advanced: true,
+            finalized_slot: self.head_slot(),
+            execution_state_root: self.execution_state_root().unwrap_or_default(),
+            execution_block_number: self
+                .state
+                .borrow()
+                .latest_execution_payload_header
+                .as_ref()
+                .map(|h| h.block_number)
+                .unwrap_or(0),
+            sync_participation: participation,
+            message: format!(
+                "BLS-verified finality at slot {} ({}/512 signers)",
+                self.head_slot(), participation
+            ),
+        };
+
+        serde_wasm_bindgen::to_value(&result)
+            .map_err(|e| LumenError::serialization(e).into())
+    }
+
+    /// Verify an account proof of raw trie nodes against the BLS-verified
+    /// execution state root.
+    fn verify_account_nodes(&self, address: &[u8], proof_nodes: &js_sys::Array) -> Result<AccountState, LumenError> {
+        let state_root = self
+            .state
+            .borrow()
+            .verified_state_root()
+            .ok_or_else(|| {
+                LumenError::new(
+                    ErrorCode::NotSynced,
+                    "No verified execution state root yet — process a finality update first",
+                )
+            })?;
+        let address: [u8; 20] = address
+            .try_into()
+            .map_err(|_| LumenError::new(ErrorCode::InvalidInput, "Address must be 20 bytes"))?;
+        let proof = AccountProof {
+            address,
+            proof: proof_nodes_from_array(proof_nodes).map_err(|e| LumenError::new(ErrorCode::InvalidInput, e))?,
+            account: None,
+        };
+        metrics::proof(|| {
+            lumen_core::execution::proof::verify_account_proof(state_root, address, &proof)
+        })
+        .map_err(|e| LumenError::new(ErrorCode::ProofInvalid, format!("Proof verification failed: {}", e)))
+    }
+
+    /// Verify a JSON receipt proof against `receipts_root`, or the finalized
+    /// block's if None, returning the receipt and the header it is under.
+    fn verify_receipt_json(
+        &self,
+        receipts_root: Option<&str>,
+        tx_index: u64,
+        proof_json: &str,
+    ) -> Result<(TransactionReceipt, ExecutionPayloadHeader), LumenError> {
+        let header = match receipts_root {
+            None => self.state.borrow().latest_execution_payload_header.clone().ok_or_else(|| {
+                LumenError::new(
+                    ErrorCode::NotSynced,
+                    "No verified execution payload yet — process a finality update first",
+                )
+            })?,
+            Some(root) => {
+                let root = beacon_api::hex_to_bytes32(root).map_err(|e| {
+                    LumenError::new(ErrorCode::InvalidInput, format!("Invalid receipts root: {}", e))
+                })?;
+                self.execution_history.borrow().with_receipts_root(&root).cloned().ok_or_else(|| {
+                    LumenError::new(
+                        ErrorCode::InvalidInput,
+                        format!("0x{} is not the receipts root of a recently verified block", hex::encode(root)),
+                    )
+                })?
+            }
+        };
+        let nodes: Vec<String> = serde_json::from_str(proof_json)
+            .map_err(|e| LumenError::new(ErrorCode::InvalidJson, format!("Invalid proof JSON: {}", e)))?;
+        let proof = ReceiptProof {
+            tx_index,
+            proof: nodes
+                .iter()
+                .map(|node| beacon_api::hex_to_bytes(node))
+                .collect::<Result<_, _>>()
+                .map_err(|e| LumenError::new(ErrorCode::InvalidJson, format!("Invalid proof node: {}", e)))?,
+        };
+        let receipt = metrics::proof(|| {
+            lumen_core::execution::receipt::verify_receipt_proof(header.receipts_root, tx_index, &proof)
+        })
+        .map_err(|e| LumenError::new(ErrorCode::ProofInvalid, format!("Receipt proof verification failed: {}", e)))?;
+        lumen_log!(
+            Debug,
+            "proof",
+            "Receipt {} verified at block #{}: {} nodes, {} logs",
+            tx_index,
+            header.block_number,
+            proof.proof.len(),
+            receipt.logs.len()
+        );
+        Ok((receipt, header))
+    }
+
+    /// Decode a raw header that must hash to the block hash of a recorded,
+    /// BLS-verified block.
+    fn verify_recorded_header(&self, raw_header: &[u8]) -> Result<ExecutionBlockHeader, LumenError> {
+        let block_hash = lumen_core::execution::proof::keccak256(raw_header);
+        if self.execution_history.borrow().with_block_hash(&block_hash).is_none() {
+            return Err(LumenError::new(
+                ErrorCode::InvalidInput,
+                format!("0x{} is not the hash of a recently verified block", hex::encode(block_hash)),
+            ));
+        }
+        lumen_core::execution::header::verify_block_header(raw_header, block_hash)
+            .map_err(|e| LumenError::new(ErrorCode::InvalidInput, format!("Invalid block header: {}", e)))
+    }
+
+    /// `verify_block_header`'s input: raw header hex, or a JSON block whose
+    /// header is re-encoded and whose claimed `hash`, if any, must match.
+    fn verify_header_input(&self, header: &str) -> Result<ExecutionBlockHeader, LumenError> {
+        let header = header.trim();
+        if !header.starts_with('{') {
+            let raw_header = beacon_api::hex_to_bytes(header)
+                .map_err(|e| LumenError::new(ErrorCode::InvalidInput, format!("Invalid block header hex: {}", e)))?;
+            return self.verify_recorded_header(&raw_header);
+        }
+
+        let block: serde_json::Value = serde_json::from_str(header)
+            .map_err(|e| LumenError::new(ErrorCode::InvalidJson, format!("Invalid block JSON: {}", e)))?;
+        let decoded = provider::header_from_json(&block)
+            .map_err(|e| LumenError::new(ErrorCode::InvalidInput, format!("Invalid block: {}", e)))?;
+        let verified = self.verify_recorded_header(&decoded.encode())?;
+        if let Some(claimed) = block.get("hash").and_then(|v| v.as_str()) {
+            let computed = verified.hash();
+            if beacon_api::hex_to_bytes32(claimed).ok() != Some(computed) {
+                return Err(LumenError::new(
+                    ErrorCode::ProofInvalid,
+                    format!("Block claims hash {} but its header hashes to 0x{}", claimed, hex::encode(computed)),
+                ));
+            }
+        }
+        Ok(verified)
+    }
+
+    /// Verify a raw header against a recorded block hash, then a JSON
+    /// transaction proof against the header's transactions root.
+    fn verify_transaction_json(
+        &self,
+        block_header_rlp: &str,
+        tx_index: u64,
+        proof_json: &str,
+    ) -> Result<(VerifiedTransaction, ExecutionBlockHeader), LumenError> {
+        let raw_header = beacon_api::hex_to_bytes(block_header_rlp)
+            .map_err(|e| LumenError::new(ErrorCode::InvalidInput, format!("Invalid block header hex: {}", e)))?;
+        let header = self.verify_recorded_header(&raw_header)?;
+
+        let nodes: Vec<String> = serde_json::from_str(proof_json)
+            .map_err(|e| LumenError::new(ErrorCode::InvalidJson, format!("Invalid proof JSON: {}", e)))?;
+        let proof = TransactionProof {
+            tx_index,
+            proof: nodes
+                .iter()
+                .map(|node| beacon_api::hex_to_bytes(node))
+                .collect::<Result<_, _>>()
+                .map_err(|e| LumenError::new(ErrorCode::InvalidJson, format!("Invalid proof node: {}", e)))?,
+        };
+        let verified = metrics::proof(|| {
+            lumen_core::execution::transaction::verify_transaction_proof(header.transactions_root, tx_index, &proof)
+        })
+        .map_err(|e| {
+            LumenError::new(ErrorCode::ProofInvalid, format!("Transaction proof verification failed: {}", e))
+        })?;
+        lumen_log!(
+            Debug,
+            "proof",
+            "Transaction 0x{} verified at block #{} index {}",
+            hex::encode(verified.transaction_hash),
+            header.number,
+            tx_index
+        );
+        Ok((verified, header))
+    }
+
+    /// `process_committee_updates`, with `on_verified(done, total)` called
+    /// after each update.
+    fn committee_updates_json(&self, updates_json: &str, on_verified: impl Fn(u64, u64)) -> Result<JsValue, JsValue> {
+        let updates: Vec<beacon_api::ApiVersionedUpdate> = serde_json::from_str(updates_json)
+            .map_err(|e| {
+                self.input_error(
+                    "process_committee_updates",
+                    ErrorCode::InvalidJson,
+                    format!("Invalid updates JSON: {}", e),
+                )
+            })?;
+
+        let converted = updates.iter().enumerate().map(|(index, api_update)| {
+            api_update
+                .data
+                .to_core_update()
+                .and_then(|update| {
+                    let exec_header = api_update.data.finalized_header.verified_execution()?;
+                    Ok((update, exec_header))
+                })
+                .map_err(|e| {
+                    self.input_error(
+                        "process_committee_updates",
+                        ErrorCode::InvalidJson,
+                        format!("Update {} conversion: {}", index, e),
+                    )
+                })
+        });
+        self.apply_committee_updates(converted, on_verified)
+    }
+
+    fn committee_updates_ssz(&self, updates_ssz: &[u8], on_verified: impl Fn(u64, u64)) -> Result<JsValue, JsValue> {
+        let updates = lumen_core::consensus::ssz::decode_updates(updates_ssz).map_err(|e| {
+            self.input_error(
+                "process_committee_updates_ssz",
+                ErrorCode::InvalidSsz,
+                format!("Invalid updates SSZ: {}", e),
+            )
+        })?;
+        self.apply_committee_updates(
+            updates
+                .into_iter()
+                .map(|decoded| Ok((decoded.update, Some(decoded.finalized_execution)))),
+            on_verified,
+        )
+    }
+
+    /// A `backfill` progress event after `done` of `total` period updates.
+    fn backfill_progress(&self, done: u64, total: u64) -> sync::SyncProgress {
+        sync::SyncProgress {
+            stage: sync::SyncStage::Backfill,
+            message: format!("Processed period update {} of {}", done, total),
+            finalized_slot: Some(self.head_slot()),
+            done: Some(done),
+            total: Some(total),
+        }
+    }
+
+    /// Verify and apply period updates in order, stopping at the first that
+    /// fails to convert or verify. `on_verified(done, total)` follows every
+    /// update that was processed.
+    fn apply_committee_updates(
+        &self,
+        updates: impl ExactSizeIterator<Item = Result<(LightClientUpdate, Option<ExecutionPayloadHeader>), LumenError>>,
+        on_verified: impl Fn(u64, u64),
+    ) -> Result<JsValue, JsValue> {
+        use lumen_core::consensus::light_client::{process_committee_update, CommitteeUpdateOutcome};
+
+        let start_slot = self.head_slot();
+        let previous_root = self.state.borrow().verified_state_root();
+        let mut result = CommitteeBackfillResult::default();
+        let mut failure = None;
+        let total = updates.len() as u64;
+        for (index, converted) in updates.enumerate() {
+            let (update, exec_header) = match converted {
+                Ok(converted) => converted,
+                Err(e) => {
+                    failure = Some(e);
+                    break;
+                }
+            };
+
+            let genesis_validators_root = self.state.borrow().genesis_validators_root;
+            let started = metrics::now_ms();
+            let processed = process_committee_update(&mut self.state.borrow_mut(), &update, genesis_validators_root);
+            let outcome = match processed {
+                Ok(CommitteeUpdateOutcome::AlreadyKnown) => metrics::UpdateOutcome::Duplicate,
+                Ok(_) => metrics::UpdateOutcome::Applied,
+                Err(_) => metrics::UpdateOutcome::Rejected,
+            };
+            metrics::with(|m| m.record_update(outcome, Some(metrics::now_ms() - started)));
+            match processed {
+                Ok(CommitteeUpdateOutcome::Advanced) => {
+                    result.advanced += 1;
+                    if let Some(exec) = exec_header {
+                        self.store_execution_header(exec);
+                    }
+                }
+                Ok(CommitteeUpdateOutcome::NextCommitteeStored) => result.committees_stored += 1,
+                Ok(CommitteeUpdateOutcome::AlreadyKnown) => result.skipped += 1,
+                Err(e) => {
+                    self.emit(events::ClientEvent::UpdateRejected {
+                        update_slot: update.finalized_header.slot,
+                        reason: e.to_string(),
+                    });
+                    failure = Some(LumenError::new(
+                        ErrorCode::VerificationFailed,
+                        format!(
+                            "Update {} (slot {}) failed verification: {}",
+                            index, update.attested_header.slot, e
+                        ),
+                    ));
+                    break;
+                }
+            }
+            on_verified(index as u64 + 1, total);
+        }
+
+        // Updates before a failure stay applied, so report them either way.
+        if self.head_slot() > start_slot {
+            self.emit_head_advanced(previous_root);
+            self.notify_new_heads();
+        }
+        if let Some(error) = failure {
+            return Err(error.into());
+        }
+
+        (result.period, result.has_next_committee) = self.committee_position();
+        result.finalized_slot = self.head_slot();
+        lumen_log!(
+            Info,
+            "consensus",
+            "Committee backfill: {} advanced, {} committees learned, {} skipped — now at period {}",
+            result.advanced, result.committees_stored, result.skipped, result.period
+        );
+
+        serde_wasm_bindgen::to_value(&result).map_err(|e| LumenError::serialization(e).into())
+    }
+
+    /// Verify `update` and advance the state, reporting the outcome.
+    /// Returns false if the update was rejected.
+    fn apply_update(&self, update: &LightClientUpdate) -> bool {
+        let current_slot = self.head_slot();
+        let genesis_validators_root = self.state.borrow().genesis_validators_root;
+        let previous_root = self.state.borrow().verified_state_root();
+
+        let started = metrics::now_ms();
+        let verified = self.check_not_future(update).and_then(|()| {
+            lumen_core::consensus::light_client::process_light_client_update_cached(
+                &mut self.state.borrow_mut(),
+                update,
+                current_slot,
+                genesis_validators_root,
+                &mut self.aggregate_cache.borrow_mut(),
+            )
+        });
+        let elapsed = metrics::now_ms() - started;
+        let outcome = match &verified {
+            Ok(()) => metrics::UpdateOutcome::Applied,
+            Err(VerificationError::UpdateNotNewer { .. }) => metrics::UpdateOutcome::Duplicate,
+            Err(_) => metrics::UpdateOutcome::Rejected,
+        };
+        metrics::with(|m| m.record_update(outcome, Some(elapsed)));
+
+        match verified {
+            Ok(()) => {
+                lumen_log!(
+                    Info,
+                    "consensus",
+                    "State advanced to slot {}",
+                    self.head_slot()
+                );
+                self.emit_head_advanced(previous_root);
+                self.notify_new_heads();
+                true
+            }
+            Err(e) => {
+                lumen_log!(Warn, "consensus", "Update rejected: {}", e);
+                self.emit(events::ClientEvent::UpdateRejected {
+                    update_slot: update.finalized_header.slot,
+                    reason: e.to_string(),
+                });
+                false
+            }
+        }
+    }
+
+    /// Reject `update` if it is signed for a slot the wall clock has not
+    /// reached. Skipped for networks without a known genesis time.
+    fn check_not_future(&self, update: &LightClientUpdate) -> Result<(), VerificationError> {
+        match self.slot_clock() {
+            Some(clock) => clock.check_not_future(update, self.clock.now_ms() as u64),
+            None => Ok(()),
+        }
+    }
+
+    fn slot_clock(&self) -> Option<lumen_core::SlotClock> {
+        (self.network.genesis_time != 0).then(|| lumen_core::SlotClock::new(self.network.genesis_time))
+    }
+
+    /// Warn if the finalized head trails the wall clock by more than
+    /// `STALE_HEAD_SLOTS`. Returns whether it does.
+    fn warn_if_stale(&self) -> bool {
+        let Some(clock) = self.slot_clock() else {
+            return false;
+        };
+        let now = self.clock.now_ms() as u64;
+        let head_slot = self.head_slot();
+        if !clock.is_stale(head_slot, now) {
+            return false;
+        }
+        lumen_log!(
+            Warn,
+            "sync",
+            "Finalized head (slot {}) is {} slots behind the wall clock; updates may have stalled",
+            head_slot,
+            clock.head_lag(head_slot, now)
+        );
+        true
+    }
+
+    /// Deliver `event` to the registered event handler, if any.
+    fn emit(&self, event: events::ClientEvent) {
+        // Copy the handler out so it may replace itself.
+        let Some(handler) = self.event_handler.borrow().clone() else {
+            return;
+        };
+        let delivered = event
+            .serialize(&serde_wasm_bindgen::Serializer::json_compatible())
+            .map_err(|e| JsValue::from_str(&e.to_string()))
+            .and_then(|payload| handler.call1(&JsValue::NULL, &payload));
+        if let Err(e) = delivered {
+            lumen_log!(Error, "events", "Event handler threw: {:?}", e);
+        }
+    }
+
+    /// Compare what the RPC claimed about `address` with the verified
+    /// `account`, warning and emitting `rpc_discrepancy` on any mismatch.
+    fn check_rpc_claims(
+        &self,
+        address: &str,
+        endpoint: Option<&str>,
+        rpc_proof: &beacon_api::RpcGetProofResponse,
+        account: &AccountState,
+    ) -> Vec<beacon_api::Discrepancy> {
+        let discrepancies = rpc_proof.discrepancies(account);
+        if !discrepancies.is_empty() {
+            let fields: Vec<&str> = discrepancies.iter().map(|d| d.field.as_str()).collect();
+            lumen_log!(
+                Warn,
+                "proof",
+                "RPC {} misreported {} for {}: the proof verified, the claims did not",
+                endpoint.unwrap_or("(caller-supplied)"),
+                fields.join(", "),
+                address
+            );
+            self.emit(events::ClientEvent::RpcDiscrepancy {
+                address: address.to_string(),
+                endpoint: endpoint.map(str::to_string),
+                discrepancies: discrepancies.clone(),
+            });
+        }
+        discrepancies
+    }
+
+    /// Emit `error` for input `source` could not process, returning it for `?`.
+    fn input_error(&self, source: &'static str, code: ErrorCode, message: String) -> LumenError {
+        self.emit(events::ClientEvent::Error {
+            source,
+            code: code as u16,
+            message: message.clone(),
+        });
+        LumenError::new(code, message)
+    }
+
+    /// Note the time for `health`, then emit `head_advanced`, plus
+    /// `execution_root_changed` if the verified execution state root is no
+    /// longer `previous_root`.
+    fn emit_head_advanced(&self, previous_root: Option<[u8; 32]>) {
+        self.last_update_at_ms.set(Some(self.clock.now_ms()));
+        self.emit(events::ClientEvent::HeadAdvanced {
+            finalized_slot: self.head_slot(),
+            period: self.state.borrow().current_period(),
+        });
+        // Clone the header so the state is not borrowed while the handler runs.
+        let header = self.state.borrow().latest_execution_payload_header.clone();
+        if let Some(header) = header {
+            if Some(header.state_root) != previous_root {
+                self.emit(events::ClientEvent::execution_root_changed(
+                    header.state_root,
+                    header.block_number,
+                ));
+            }
+        }
+    }
+
+    /// Deliver the current verified execution header to newHeads subscribers.
+    fn notify_new_heads(&self) {
+        let Some(header) = self.state.borrow().latest_execution_payload_header.clone() else {
+            return;
+        };
+        let result = provider::payload_header_to_json(&header);
+        let serializer = serde_wasm_bindgen::Serializer::json_compatible();
+        // Copy the subscribers out so a callback may itself (un)subscribe.
+        let subscribers: Vec<(String, js_sys::Function)> = self
+            .subscriptions
+            .borrow()
+            .subscribers(subscriptions::SubscriptionKind::NewHeads)
+            .map(|(id, callback)| (id.to_string(), callback.clone()))
+            .collect();
+
+        for (id, callback) in subscribers {
+            let message = subscriptions::subscription_message(&id, result.clone());
+            let delivered = message
+                .serialize(&serializer)
+                .map_err(|e| JsValue::from_str(&e.to_string()))
+                .and_then(|payload| callback.call1(&JsValue::NULL, &payload));
+            if let Err(e) = delivered {
+                lumen_log!(Error, "events", "newHeads subscriber {} threw: {:?}", id, e);
+            }
+        }
+    }
+
+    /// One `start_finality_polling` round: fetch the latest finality update,
+    /// backfill committees up to its period if needed, then verify it.
+    async fn poll_finality(&self, endpoints: &[String]) -> Result<(), JsValue> {
+        let finality = match sync::fetch_from_any(endpoints, sync::FINALITY_UPDATE_PATH).await {
+            Ok(finality) => finality,
+            Err(e) => {
+                lumen_log!(Warn, "sync", "Finality poll: {}", e);
+                self.emit(events::ClientEvent::Error {
+                    source: "finality_polling",
+                    code: ErrorCode::RequestFailed as u16,
+                    message: e.clone(),
+                });
+                return Err(LumenError::new(ErrorCode::RequestFailed, e).into());
+            }
+        };
+        let head_period = finality_attested_period(&finality)
+            .map_err(|e| self.input_error("finality_polling", e.code, e.message))?;
+        self.backfill_committees(
+            endpoints,
+            head_period,
+            |start_period, count| {
+                lumen_log!(
+                    Info,
+                    "sync",
+                    "Finality poll: fetching {} period update(s) from period {}",
+                    count,
+                    start_period
+                )
+            },
+            |_, _| {},
+        )
+        .await?;
+        self.process_finality_payload(&finality).map(|_| ())
+    }
+
+    /// Fetch the update carrying the next sync committee once the finalized
+    /// head nears the end of its period without it, so the first update
+    /// after the rotation does not wait on a backfill — or fail, if the
+    /// round that brings it cannot reach an endpoint serving period updates.
+    async fn prefetch_next_committee(&self, endpoints: &[String]) -> Result<(), JsValue> {
+        let Some(period) = sync::prefetch_period(self.head_slot(), self.committee_position().1) else {
+            return Ok(());
+        };
+        lumen_log!(Info, "sync", "Period {} is ending; fetching the next sync committee", period);
+        self.backfill_committees(endpoints, period, |_, _| {}, |_, _| {}).await
+    }
+
+    /// Verify a finality update in whichever encoding the endpoint returned.
+    fn process_finality_payload(&self, finality: &BeaconPayload) -> Result<JsValue, JsValue> {
+        match finality {
+            BeaconPayload::Ssz(bytes) => self.process_finality_update_ssz(bytes),
+            BeaconPayload::Json(json) => self.process_finality_update(json),
+        }
+    }
+
+    fn health_report(&self) -> health::ClientHealth {
+        let now = self.clock.now_ms();
+        let head_slot = self.head_slot();
+        let slot_clock = self.slot_clock();
+        let finalized_lag_slots = slot_clock.as_ref().map(|clock| clock.head_lag(head_slot, now as u64));
+        let rpc_endpoints = self.rpc_health.borrow().report(now);
+        health::ClientHealth {
+            status: health::status(head_slot, finalized_lag_slots, &self.rpc_endpoints, &rpc_endpoints),
+            head_slot,
+            checkpoint_slot: self.checkpoint_slot,
+            checkpoint_age_ms: slot_clock
+                .map(|clock| (now - clock.slot_start_ms(self.checkpoint_slot) as f64).max(0.0)),
+            finalized_lag_slots,
+            last_update_at_ms: self.last_update_at_ms.get(),
+            rpc_endpoints,
+            p2p: self.p2p_status.borrow().clone(),
+        }
+    }
+
+    fn resource_report(&self) -> resources::ResourceStats {
+        use resources::CacheStats;
+
+        let state = self.state.borrow();
+        let committee_keys = state.current_sync_committee.pubkeys.len()
+            + state.next_sync_committee.as_ref().map_or(0, |c| c.pubkeys.len());
+        let response_cache = self.response_cache.borrow();
+        let aggregate_cache = self.aggregate_cache.borrow();
+        let history = self.execution_history.borrow();
+        resources::ResourceStats {
+            wasm_memory_bytes: resources::wasm_memory_bytes(),
+            state_bytes: std::mem::size_of::<LightClientState>()
+                + committee_keys * std::mem::size_of::<BlsPublicKey>(),
+            response_cache: CacheStats {
+                entries: response_cache.len(),
+                capacity: response_cache.capacity(),
+                approx_bytes: response_cache.approx_bytes(),
+                hits: Some(response_cache.hits()),
+                misses: Some(response_cache.misses()),
+            },
+            aggregate_cache: CacheStats {
+                entries: aggregate_cache.len(),
+                capacity: aggregate_cache.capacity(),
+                approx_bytes: aggregate_cache.approx_bytes(),
+                hits: Some(aggregate_cache.hits() + aggregate_cache.extensions()),
+                misses: Some(aggregate_cache.misses()),
+            },
+            execution_history: CacheStats {
+                entries: history.len(),
+                capacity: history::HISTORY_LEN,
+                approx_bytes: history.len() * std::mem::size_of::<ExecutionPayloadHeader>(),
+                hits: None,
+                misses: None,
+            },
+            watched_transactions: self.watcher.borrow().len(),
+            subscriptions: self.subscriptions.borrow().len(),
+            tracked_endpoints: self.rpc_health.borrow().report(self.clock.now_ms()).len(),
+        }
+    }
+
+    fn sync_state(&self) -> SyncStateResponse {
+        SyncStateResponse {
+            head_slot: self.head_slot(),
+            current_period: self.state.borrow().current_period(),
+            has_next_committee: self.state.borrow().next_sync_committee.is_some(),
+            has_execution_root: self.state.borrow().latest_execution_payload_header.is_some(),
+            is_synced: self.is_synced(),
+        }
+    }
+
+    /// Make `exec` the verified execution payload header and keep it in the history.
+    fn store_execution_header(&self, exec: ExecutionPayloadHeader) {
+        let previous = self.finalized_block_number();
+        if exec.block_number > previous {
+            self.response_cache.borrow_mut().head_advanced();
+        }
+        self.execution_history.borrow_mut().record(&exec);
+        self.state.borrow_mut().latest_execution_payload_header = Some(exec);
+    }
+
+    /// `(period, has_next_committee)`: how far committee tracking has got.
+    fn committee_position(&self) -> (u64, bool) {
+        let state = self.state.borrow();
+        (state.current_period(), state.next_sync_committee.is_some())
+    }
+
+    /// Fetch and apply period updates from beacon `endpoints` until the
+    /// client reaches `head_period` or the endpoints have nothing newer.
+    /// `on_fetch(start_period, count)` is called before each request, and
+    /// `on_verified(done, total)` after each update, counted over the whole
+    /// backfill.
+    async fn backfill_committees(
+        &self,
+        endpoints: &[String],
+        head_period: u64,
+        on_fetch: impl Fn(u64, u64),
+        on_verified: impl Fn(u64, u64),
+    ) -> Result<(), JsValue> {
+        let first_period = self.committee_position().0;
+        let total = head_period.saturating_sub(first_period) + 1;
+        let mut done = 0;
+        loop {
+            let before = self.committee_position();
+            let Some((start_period, count)) = sync::backfill_range(before.0, head_period, before.1) else {
+                return Ok(());
+            };
+            on_fetch(start_period, count);
+            let updates = sync::fetch_from_any(endpoints, &sync::updates_path(start_period, count))
+                .await
+                .map_err(|e| LumenError::new(ErrorCode::RequestFailed, e))?;
+            let batch_done = std::cell::Cell::new(0);
+            let on_update = |batch: u64, _| {
+                batch_done.set(batch);
+                on_verified((done + batch).min(total), total);
+            };
+            match updates {
+                BeaconPayload::Ssz(bytes) => self.committee_updates_ssz(&bytes, on_update)?,
+                BeaconPayload::Json(json) => self.committee_updates_json(&json, on_update)?,
+            };
+            done += batch_done.get();
+            if self.committee_position() == before {
+                // The providers have nothing newer for us; let the finality update decide.
+                return Ok(());
+            }
+        }
+    }
+
+    fn finalized_block_number(&self) -> u64 {
+        self.state
+            .borrow()
+            .latest_execution_payload_header
+            .as_ref()
+            .map(|h| h.block_number)
+            .unwrap_or(0)
+    }
+
+    async fn try_fetch_and_verify(
+        &self,
+        endpoint: &str,
+        address: &str,
+        finalized_block_num: u64,
+    ) -> Result<JsValue, LumenError> {
+        let fetched = self
+            .fetch_account(endpoint, address, &[])
+            .await
+            .map_err(|e| LumenError::new(ErrorCode::RequestFailed, e))?;
+        let account = &fetched.account;
+
+        let result = FetchVerifyAccountResult {
+            nonce: account.nonce,
+            balance_hex: account.balance.to_hex(),
+            storage_root: format!("0x{}", hex::encode(account.storage_root)),
+            code_hash: format!("0x{}", hex::encode(account.code_hash)),
+            is_contract: account.is_contract(),
+            verified: true,
+            finalized_block: finalized_block_num,
+            proof_block: fetched.block_number,
+            proof_nodes_verified: fetched.proof_nodes,
+            rpc_endpoint: endpoint.to_string(),
+            rpc_claimed_balance: fetched.rpc_claimed_balance,
+            discrepancies: fetched.discrepancies,
+        };
+
+        serde_wasm_bindgen::to_value(&result).map_err(LumenError::serialization)
+    }
+
+    async fn try_fetch_and_verify_storage(
+        &self,
+        endpoint: &str,
+        address: &str,
+        slots: &[[u8; 32]],
+        finalized_block_num: u64,
+    ) -> Result<JsValue, LumenError> {
+        let fetched = self
+            .fetch_account(endpoint, address, slots)
+            .await
+            .map_err(|e| LumenError::new(ErrorCode::RequestFailed, e))?;
+
+        let values = fetched
+            .storage
+            .iter()
+            .map(|outcome| match &outcome.result {
+                Ok(value) => Ok(VerifiedSlotValue {
+                    slot: format!("0x{}", hex::encode(outcome.key)),
+                    value: format!("0x{}", hex::encode(value.to_be_bytes())),
+                }),
+                Err(e) => Err(LumenError::new(ErrorCode::ProofInvalid, format!(
+                    "Storage proof verification for slot 0x{}: {}",
+                    hex::encode(outcome.key),
+                    e
+                ))),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        lumen_log!(
+            Debug,
+            "proof",
+            "Storage {}: {} slots verified at block #{}",
+            address,
+            values.len(),
+            fetched.block_number
+        );
+
+        let result = StorageFetchResult {
+            address: address.to_string(),
+            storage_root: format!("0x{}", hex::encode(fetched.account.storage_root)),
+            slots: values,
+            verified: true,
+            finalized_block: finalized_block_num,
+            proof_block: fetched.block_number,
+            proof_nodes_verified: fetched.proof_nodes,
+            rpc_endpoint: endpoint.to_string(),
+        };
+
+        serde_wasm_bindgen::to_value(&result).map_err(LumenError::serialization)
+    }
 
-        if let Some(err) = block_resp.get("error") {
-            return Err(JsValue::from_str(&format!("Block RPC error: {}", err)));
+    /// Fetch and verify an account proof at the block the proof mode selects.
+    pub(crate) async fn fetch_account(
+        &self,
+        endpoint: &str,
+        address: &str,
+        storage_keys: &[[u8; 32]],
+    ) -> Result<FetchedAccount, String> {
+        match self.proof_mode.get() {
+            provider::ProofMode::Latest => {
+                self.fetch_verified_account(endpoint, address, storage_keys, self.finalized_block_number())
+                    .await
+            }
+            provider::ProofMode::Finalized => self.fetch_finalized_account(endpoint, address, storage_keys).await,
         }
+    }
 
-        let block_result = block_resp
-            .get("result")
-            .and_then(|r| if r.is_null() { None } else { Some(r) })
-            .ok_or_else(|| JsValue::from_str("Block result is null"))?;
+    /// Fetch the latest block and an account proof at it, then verify the proof.
+    ///
+    /// Any `storage_keys` are requested in the same eth_getProof call and
+    /// verified against the account's verified storage root.
+    async fn fetch_verified_account(
+        &self,
+        endpoint: &str,
+        address: &str,
+        storage_keys: &[[u8; 32]],
+        finalized_block_num: u64,
+    ) -> Result<FetchedAccount, String> {
+        // 1. Fetch latest block header (state root)
+        let block_result =
+            self.rpc_call(endpoint, "eth_getBlockByNumber", serde_json::json!(["latest", false]))
+                .await
+                .map_err(|e| format!("Block fetch: {}", e))?;
 
         let state_root_hex = block_result
             .get("stateRoot")
             .and_then(|v| v.as_str())
-            .ok_or_else(|| JsValue::from_str("No stateRoot in block"))?;
+            .ok_or("No stateRoot in block")?;
 
         let block_num_hex = block_result
             .get("number")
             .and_then(|v| v.as_str())
-            .ok_or_else(|| JsValue::from_str("No number in block"))?;
+            .ok_or("No number in block")?;
 
-        let block_num = u64::from_str_radix(
-            block_num_hex.strip_prefix("0x").unwrap_or(block_num_hex),
-            16,
-        )
-        .map_err(|e| JsValue::from_str(&format!("Block number parse: {}", e)))?;
+        let block_num = provider::parse_quantity(block_num_hex)
+            .map_err(|e| format!("Block number parse: {}", e))?;
 
         // 2. Cross-check: latest block must extend finalized chain
         if block_num < finalized_block_num {
-            return Err(JsValue::from_str(&format!(
+            return Err(format!(
                 "RPC latest block {} < finalized block {}",
                 block_num, finalized_block_num
-            )));
+            ));
         }
 
-        // 3. Fetch proof at latest
-        let proof_req = serde_json::json!({
-            "jsonrpc": "2.0",
-            "id": 2,
-            "method": "eth_getProof",
-            "params": [address, [], "latest"]
-        });
-        let proof_resp_text = network::post_json(endpoint, &proof_req.to_string())
+        let state_root = beacon_api::hex_to_bytes32(state_root_hex)
+            .map_err(|e| format!("State root hex: {}", e))?;
+        self.fetch_proof_at(endpoint, address, storage_keys, state_root, block_num)
             .await
-            .map_err(|e| JsValue::from_str(&format!("Proof fetch: {}", e)))?;
+    }
+
+    /// Fetch an account proof at the BLS-finalized execution block and verify
+    /// it against the state root from our own verified header. Unlike
+    /// [`Self::fetch_verified_account`], nothing about the target block comes
+    /// from the RPC.
+    pub(crate) async fn fetch_finalized_account(
+        &self,
+        endpoint: &str,
+        address: &str,
+        storage_keys: &[[u8; 32]],
+    ) -> Result<FetchedAccount, String> {
+        let header = self
+            .state
+            .borrow()
+            .latest_execution_payload_header
+            .clone()
+            .ok_or("No BLS-verified execution payload yet")?;
+        self.fetch_proof_at(endpoint, address, storage_keys, header.state_root, header.block_number)
+            .await
+    }
+
+    /// Ask an RPC where a transaction landed. Unverified: this only says
+    /// which block's receipts to check. `Ok(None)` if the RPC has no receipt yet.
+    pub(crate) async fn locate_transaction(
+        &self,
+        endpoint: &str,
+        tx_hash: &str,
+    ) -> Result<Option<beacon_api::RpcReceipt>, String> {
+        let located =
+            self.rpc_call_optional(endpoint, "eth_getTransactionReceipt", serde_json::json!([tx_hash]))
+                .await
+                .map_err(|e| format!("Receipt fetch: {}", e))?;
+        located
+            .map(|value| serde_json::from_value(value).map_err(|e| format!("Receipt parse: {}", e)))
+            .transpose()
+    }
+
+    /// Locate a transaction and verify its receipt. See [`Self::verify_located_receipt`].
+    pub(crate) async fn fetch_verified_receipt(
+        &self,
+        endpoint: &str,
+        tx_hash: &str,
+    ) -> Result<serde_json::Value, String> {
+        let located = self
+            .locate_transaction(endpoint, tx_hash)
+            .await?
+            .ok_or_else(|| format!("No receipt for {} yet", tx_hash))?;
+        self.verify_located_receipt(endpoint, tx_hash, &located).await
+    }
 
-        let proof_resp: serde_json::Value = serde_json::from_str(&proof_resp_text)
-            .map_err(|e| JsValue::from_str(&format!("Proof JSON parse: {}", e)))?;
+    /// Fetch every transaction and receipt in `located`'s block, and check
+    /// the rebuilt transactions and receipts tries against the roots of that
+    /// block's verified header.
+    ///
+    /// The block must be finalized: either the BLS-verified block itself, or
+    /// an ancestor within `MAX_HEADER_ANCESTRY` whose header is reached by
+    /// parent hashes. `located` only says where to look: the transaction at
+    /// its index must hash to `tx_hash`. Returns the receipt JSON, built from
+    /// the proven transaction, receipts and header alone, with `verified: true`.
+    pub(crate) async fn verify_located_receipt(
+        &self,
+        endpoint: &str,
+        tx_hash: &str,
+        located: &beacon_api::RpcReceipt,
+    ) -> Result<serde_json::Value, String> {
+        let finalized = self
+            .state
+            .borrow()
+            .latest_execution_payload_header
+            .clone()
+            .ok_or("No BLS-verified execution payload yet")?;
+        let expected_hash = beacon_api::hex_to_bytes32(tx_hash).map_err(|e| format!("Transaction hash hex: {}", e))?;
 
-        if let Some(err) = proof_resp.get("error") {
-            return Err(JsValue::from_str(&format!("Proof RPC error: {}", err)));
+        // 1. Verify the header of the block the RPC placed the transaction in
+        let block_hash = beacon_api::hex_to_bytes32(&located.block_hash)
+            .map_err(|e| format!("Block hash hex: {}", e))?;
+        let block_number = located.block_number()?;
+        if block_number > finalized.block_number {
+            return Err(format!(
+                "Block #{} is not finalized yet (finalized: #{})",
+                block_number, finalized.block_number
+            ));
+        }
+        if finalized.block_number - block_number > provider::MAX_HEADER_ANCESTRY {
+            return Err(format!(
+                "Block #{} is more than {} blocks behind the finalized block",
+                block_number,
+                provider::MAX_HEADER_ANCESTRY
+            ));
+        }
+        let (header, hash) = self.fetch_verified_header(endpoint, block_number).await?;
+        if hash != block_hash {
+            return Err(format!(
+                "Block 0x{} is not the finalized block #{} (0x{})",
+                hex::encode(block_hash),
+                block_number,
+                hex::encode(hash)
+            ));
         }
+        let tx_index = located.tx_index()?;
 
-        let proof_result = proof_resp
-            .get("result")
-            .and_then(|r| if r.is_null() { None } else { Some(r) })
-            .ok_or_else(|| JsValue::from_str("Proof result is null"))?;
+        // 2. Prove the transaction at that index: rebuild the transactions trie
+        let raw_block = self
+            .rpc_call(endpoint, "debug_getRawBlock", serde_json::json!([provider::to_quantity(block_number)]))
+            .await
+            .map_err(|e| format!("Raw block fetch: {}", e))?;
+        let raw_block = raw_block.as_str().ok_or("debug_getRawBlock result is not a string")?;
+        let raw_block = beacon_api::hex_to_bytes(raw_block).map_err(|e| format!("Raw block hex: {}", e))?;
+        let envelopes = block_transactions(&raw_block)?;
+        let (root, tx_proof) = lumen_core::execution::transaction::build_transaction_proof(&envelopes, tx_index)
+            .ok_or_else(|| {
+                format!("Transaction index {} outside block's {} transactions", tx_index, envelopes.len())
+            })?;
+        if root != header.transactions_root {
+            return Err(format!(
+                "Block transactions hash to 0x{}, verified transactions root is 0x{}",
+                hex::encode(root),
+                hex::encode(header.transactions_root)
+            ));
+        }
+        let transaction = metrics::proof(|| {
+            let transactions_root = header.transactions_root;
+            lumen_core::execution::transaction::verify_transaction_proof(transactions_root, tx_index, &tx_proof)
+        })
+        .map_err(|e| format!("Transaction proof verification: {}", e))?;
+        if transaction.transaction_hash != expected_hash {
+            return Err(format!(
+                "Block transaction at index {} is 0x{}, not {}",
+                tx_index,
+                hex::encode(transaction.transaction_hash),
+                tx_hash
+            ));
+        }
+        let sender = lumen_core::execution::transaction::recover_sender(&envelopes[tx_index as usize])
+            .map_err(|e| format!("Sender recovery: {}", e))?;
 
-        let proof_json = proof_result.to_string();
+        // 3. Fetch every receipt in the block
+        let block_receipts = self.rpc_call(
+            endpoint,
+            "eth_getBlockReceipts",
+            serde_json::json!([located.block_hash]),
+        )
+        .await
+        .map_err(|e| format!("Block receipts fetch: {}", e))?;
+        let raw_receipts: Vec<beacon_api::RpcReceipt> = serde_json::from_value(block_receipts)
+            .map_err(|e| format!("Block receipts parse: {}", e))?;
+        let receipts: Vec<TransactionReceipt> = raw_receipts
+            .iter()
+            .map(|rpc| rpc.to_core_receipt())
+            .collect::<Result<_, _>>()
+            .map_err(|e| format!("Block receipt conversion: {}", e))?;
 
-        // 4. Parse state root
-        let root_hex = state_root_hex
-            .strip_prefix("0x")
-            .unwrap_or(state_root_hex);
-        let root_bytes = hex::decode(root_hex)
-            .map_err(|e| JsValue::from_str(&format!("State root hex: {}", e)))?;
-        if root_bytes.len() != 32 {
-            return Err(JsValue::from_str("State root must be 32 bytes"));
+        // 4. Rebuild the receipts trie; its root authenticates every receipt in the block
+        let (root, proof) = lumen_core::execution::receipt::build_receipt_proof(&receipts, tx_index)
+            .ok_or_else(|| {
+                format!(
+                    "Transaction index {} outside block's {} receipts",
+                    tx_index,
+                    receipts.len()
+                )
+            })?;
+        if root != header.receipts_root {
+            return Err(format!(
+                "Block receipts hash to 0x{}, verified receipts root is 0x{}",
+                hex::encode(root),
+                hex::encode(header.receipts_root)
+            ));
+        }
+        let receipt = metrics::proof(|| {
+            lumen_core::execution::receipt::verify_receipt_proof(header.receipts_root, tx_index, &proof)
+        })
+        .map_err(|e| format!("Receipt proof verification: {}", e))?;
+        if receipt.tx_type != transaction.transaction.tx_type {
+            return Err(format!(
+                "Receipt type 0x{:02x} does not match transaction type 0x{:02x}",
+                receipt.tx_type.type_byte(),
+                transaction.transaction.tx_type.type_byte()
+            ));
         }
-        let mut state_root = [0u8; 32];
-        state_root.copy_from_slice(&root_bytes);
 
-        // 5. Parse address
-        let addr_hex = address.strip_prefix("0x").unwrap_or(address);
-        let addr_bytes = hex::decode(addr_hex)
-            .map_err(|e| JsValue::from_str(&format!("Address hex: {}", e)))?;
-        if addr_bytes.len() != 20 {
-            return Err(JsValue::from_str("Address must be 20 bytes"));
+        lumen_log!(
+            Debug,
+            "proof",
+            "Receipt {} verified at block #{} index {} ({} receipts)",
+            tx_hash,
+            block_number,
+            tx_index,
+            receipts.len()
+        );
+
+        Ok(receipt_json(&header, &transaction, &sender, &receipts[..tx_index as usize], &receipt))
+    }
+
+    /// `fetch_and_verify_receipt` without the conversion to JS: the first
+    /// endpoint, healthiest first, whose receipt for `tx_hash` verifies.
+    async fn fetch_receipt_from_any(
+        &self,
+        tx_hash: &str,
+        rpc_endpoints_json: &str,
+    ) -> Result<serde_json::Value, LumenError> {
+        beacon_api::hex_to_bytes32(tx_hash)
+            .map_err(|e| LumenError::new(ErrorCode::InvalidInput, format!("Invalid transaction hash: {}", e)))?;
+
+        let endpoints: Vec<String> = serde_json::from_str(rpc_endpoints_json)
+            .map_err(|e| LumenError::new(ErrorCode::InvalidJson, format!("Invalid endpoints JSON: {}", e)))?;
+
+        if endpoints.is_empty() {
+            return Err(LumenError::new(ErrorCode::NoEndpoints, "No RPC endpoints provided"));
         }
-        let mut addr = [0u8; 20];
-        addr.copy_from_slice(&addr_bytes);
 
-        // 6. Parse proof and verify via keccak256 MPT
-        let rpc_proof: beacon_api::RpcGetProofResponse =
-            serde_json::from_str(&proof_json)
-                .map_err(|e| JsValue::from_str(&format!("Proof parse: {}", e)))?;
+        let mut last_error = String::from("No endpoints tried");
+
+        for endpoint in self.ranked_endpoints(&endpoints) {
+            match self.tracked(endpoint, self.fetch_verified_receipt(endpoint, tx_hash)).await {
+                Ok(receipt) => return Ok(receipt),
+                Err(e) => {
+                    lumen_log!(Warn, "rpc", "RPC {} failed: {}", endpoint, e);
+                    last_error = e;
+                }
+            }
+        }
+
+        Err(LumenError::new(ErrorCode::RequestFailed, format!(
+            "All RPC endpoints failed. Last error: {}",
+            last_error
+        )))
+    }
+
+    /// Fetch eth_getProof at `block_num` and verify it against `state_root`.
+    pub(crate) async fn fetch_proof_at(
+        &self,
+        endpoint: &str,
+        address: &str,
+        storage_keys: &[[u8; 32]],
+        state_root: [u8; 32],
+        block_num: u64,
+    ) -> Result<FetchedAccount, String> {
+        // 3. Fetch proof at the target block
+        let keys_hex: Vec<String> = storage_keys
+            .iter()
+            .map(|k| format!("0x{}", hex::encode(k)))
+            .collect();
+        let proof_result = self.rpc_call(
+            endpoint,
+            "eth_getProof",
+            serde_json::json!([address, keys_hex, provider::to_quantity(block_num)]),
+        )
+        .await
+        .map_err(|e| format!("Proof fetch: {}", e))?;
+
+        // 4. Parse address
+        let addr = provider::parse_address(address)?;
+
+        // 5. Parse proof and verify via keccak256 MPT
+        let rpc_proof: beacon_api::RpcGetProofResponse = serde_json::from_value(proof_result)
+            .map_err(|e| format!("Proof parse: {}", e))?;
 
         let account_proof = rpc_proof
             .to_core_account_proof(&addr)
-            .map_err(|e| JsValue::from_str(&format!("Proof conversion: {}", e)))?;
+            .map_err(|e| format!("Proof conversion: {}", e))?;
 
         let proof_node_count = account_proof.proof.len();
 
-        let account =
+        let account = metrics::proof(|| {
             lumen_core::execution::proof::verify_account_proof(state_root, addr, &account_proof)
-                .map_err(|e| JsValue::from_str(&format!("Proof verification: {}", e)))?;
+        })
+        .map_err(|e| format!("Proof verification: {}", e))?;
 
-        log_to_console(&format!(
-            "[Lumen] Account {} verified at block #{}: {} nodes, balance=0x{}",
-            address, block_num, proof_node_count, hex::encode(account.balance)
-        ));
+        lumen_log!(
+            Debug,
+            "proof",
+            "Account {} verified at block #{}: {} nodes, balance={}",
+            address, block_num, proof_node_count, account.balance.to_hex()
+        );
 
-        let result = FetchVerifyAccountResult {
-            nonce: account.nonce,
-            balance_hex: format!("0x{}", account.balance_hex()),
-            storage_root: format!("0x{}", hex::encode(account.storage_root)),
-            code_hash: format!("0x{}", hex::encode(account.code_hash)),
-            is_contract: account.is_contract(),
-            verified: true,
-            finalized_block: finalized_block_num,
-            proof_block: block_num,
-            proof_nodes_verified: proof_node_count,
-            rpc_endpoint: endpoint.to_string(),
-            rpc_claimed_balance: rpc_proof.balance.clone(),
-        };
+        // 6. Verify requested storage slots against the verified storage root.
+        // Proofs are matched to our keys by position and checked under our key,
+        // so the RPC cannot answer a different slot than the one asked for.
+        let mut storage_proofs = rpc_proof
+            .to_core_storage_proofs()
+            .map_err(|e| format!("Storage proof conversion: {}", e))?;
+        if storage_proofs.len() != storage_keys.len() {
+            return Err(format!(
+                "RPC returned {} storage proofs for {} keys",
+                storage_proofs.len(),
+                storage_keys.len()
+            ));
+        }
+        for (proof, key) in storage_proofs.iter_mut().zip(storage_keys) {
+            proof.key = *key;
+        }
+        let storage = storage_proofs
+            .iter()
+            .zip(metrics::proof_batch(|| lumen_core::execution::proof::verify_storage_proofs(
+                account.storage_root,
+                &storage_proofs,
+            )))
+            .map(|(proof, result)| StorageSlotOutcome {
+                key: proof.key,
+                result,
+            })
+            .collect();
 
-        serde_wasm_bindgen::to_value(&result)
-            .map_err(|e| JsValue::from_str(&format!("Serialization: {}", e)))
+        let discrepancies = self.check_rpc_claims(address, Some(endpoint), &rpc_proof, &account);
+
+        Ok(FetchedAccount {
+            account,
+            storage,
+            block_number: block_num,
+            proof_nodes: proof_node_count,
+            storage_proof_nodes: storage_proofs.iter().map(|proof| proof.proof.len()).sum(),
+            rpc_claimed_balance: rpc_proof.balance,
+            discrepancies,
+        })
+    }
+}
+
+/// Copy an array of `Uint8Array` trie nodes out of JS memory.
+/// The user operation hash and the EntryPoints whose events count, from
+/// `verify_user_operation`'s arguments.
+fn parse_user_operation_query(
+    user_op_hash: &str,
+    entry_point: Option<&str>,
+) -> Result<([u8; 32], Vec<[u8; 20]>), LumenError> {
+    let hash = beacon_api::hex_to_bytes32(user_op_hash)
+        .map_err(|e| LumenError::new(ErrorCode::InvalidInput, format!("Invalid user operation hash: {}", e)))?;
+    let entry_points = match entry_point {
+        Some(address) => {
+            vec![provider::parse_address(address).map_err(|e| LumenError::new(ErrorCode::InvalidInput, e))?]
+        }
+        None => lumen_core::execution::user_operation::ENTRY_POINTS.to_vec(),
+    };
+    Ok((hash, entry_points))
+}
+
+/// The `UserOperationEvent` for `user_op_hash` among a verified receipt's `logs`.
+fn find_user_operation(
+    logs: &[Log],
+    user_op_hash: &[u8; 32],
+    entry_points: &[[u8; 20]],
+    tx_index: u64,
+    block_number: u64,
+) -> Result<VerifiedUserOperation, LumenError> {
+    let event = lumen_core::execution::user_operation::find_user_operation(logs, user_op_hash, entry_points)
+        .map_err(|e| LumenError::new(ErrorCode::ProofInvalid, e))?
+        .ok_or_else(|| {
+            LumenError::new(
+                ErrorCode::InvalidInput,
+                format!(
+                    "Receipt {} in block #{} has no UserOperationEvent for 0x{} from an EntryPoint",
+                    tx_index,
+                    block_number,
+                    hex::encode(user_op_hash)
+                ),
+            )
+        })?;
+    let address = |a: &[u8; 20]| format!("0x{}", hex::encode(a));
+    Ok(VerifiedUserOperation {
+        user_op_hash: format!("0x{}", hex::encode(event.user_op_hash)),
+        entry_point: address(&event.entry_point),
+        sender: address(&event.sender),
+        paymaster: (event.paymaster != [0; 20]).then(|| address(&event.paymaster)),
+        nonce: event.nonce.to_hex(),
+        success: event.success,
+        actual_gas_cost: event.actual_gas_cost.to_hex(),
+        actual_gas_used: event.actual_gas_used.to_hex(),
+        log_index: event.log_index as u32,
+        transaction_index: tx_index,
+        block_number,
+        verified: true,
+    })
+}
+
+/// The EIP-2718 envelopes of a block, from its RLP as `debug_getRawBlock`
+/// returns it: `[header, transactions, ommers, ...]`, with legacy
+/// transactions as lists and typed ones as byte strings.
+fn block_transactions(raw_block: &[u8]) -> Result<Vec<Vec<u8>>, String> {
+    let decode = lumen_core::execution::proof::decode_rlp_list;
+    let fields = decode(raw_block).map_err(|e| format!("Raw block RLP: {}", e))?;
+    let transactions = fields.get(1).ok_or("Raw block has no transaction list")?;
+    decode(transactions).map_err(|e| format!("Raw block transactions RLP: {}", e))
+}
+
+/// A receipt in `eth_getTransactionReceipt`'s shape, from proven data only:
+/// the transaction, its sender, the receipts before it and its own receipt,
+/// all of the block whose verified header is `header`.
+fn receipt_json(
+    header: &ExecutionBlockHeader,
+    verified: &VerifiedTransaction,
+    sender: &[u8; 20],
+    earlier: &[TransactionReceipt],
+    receipt: &TransactionReceipt,
+) -> serde_json::Value {
+    use provider::to_quantity;
+    let hex = |bytes: &[u8]| format!("0x{}", hex::encode(bytes));
+    let tx = &verified.transaction;
+    let block_hash = hex(&header.hash());
+    let transaction_hash = hex(&verified.transaction_hash);
+    let previous_gas = earlier.last().map_or(0, |r| r.cumulative_gas_used);
+    let first_log_index: usize = earlier.iter().map(|r| r.logs.len()).sum();
+
+    // Creations deploy to keccak256(rlp([sender, nonce]))[12..].
+    let contract_address = tx.to.is_none().then(|| {
+        use lumen_core::execution::rlp;
+        let encoded = rlp::encode_list(&[rlp::encode_bytes(sender), rlp::encode_u64(tx.nonce)]);
+        hex(&lumen_core::keccak256(&encoded)[12..])
+    });
+    // Legacy and EIP-2930 transactions pay their gas price; later types the
+    // base fee plus as much of the tip as their fee cap leaves room for.
+    let effective_gas_price = match (tx.gas_price, tx.max_fee_per_gas, header.base_fee_per_gas) {
+        (Some(price), _, _) => Some(price),
+        (None, Some(max_fee), Some(base_fee)) => {
+            let tip = tx.max_priority_fee_per_gas.unwrap_or(U256::ZERO);
+            Some(base_fee.checked_add(&tip).map_or(max_fee, |fee| fee.min(max_fee)))
+        }
+        _ => None,
+    };
+
+    let logs: Vec<serde_json::Value> = receipt
+        .logs
+        .iter()
+        .enumerate()
+        .map(|(i, log)| {
+            serde_json::json!({
+                "address": hex(&log.address),
+                "topics": log.topics.iter().map(|topic| hex(topic)).collect::<Vec<_>>(),
+                "data": hex(&log.data),
+                "blockNumber": to_quantity(header.number),
+                "blockHash": block_hash,
+                "transactionHash": transaction_hash,
+                "transactionIndex": to_quantity(verified.transaction_index),
+                "logIndex": to_quantity((first_log_index + i) as u64),
+                "removed": false,
+            })
+        })
+        .collect();
+
+    serde_json::json!({
+        "transactionHash": transaction_hash,
+        "transactionIndex": to_quantity(verified.transaction_index),
+        "blockHash": block_hash,
+        "blockNumber": to_quantity(header.number),
+        "from": hex(sender),
+        "to": tx.to.map(|to| hex(&to)),
+        "contractAddress": contract_address,
+        "cumulativeGasUsed": to_quantity(receipt.cumulative_gas_used),
+        "gasUsed": to_quantity(receipt.cumulative_gas_used.saturating_sub(previous_gas)),
+        "effectiveGasPrice": effective_gas_price.map(|price| price.to_hex()),
+        "logsBloom": hex(&receipt.logs_bloom),
+        "logs": logs,
+        "status": to_quantity(receipt.status as u64),
+        "type": to_quantity(tx.tx_type.type_byte() as u64),
+        "verified": true,
+    })
+}
+
+fn proof_nodes_from_array(nodes: &js_sys::Array) -> Result<Vec<Vec<u8>>, String> {
+    nodes
+        .iter()
+        .enumerate()
+        .map(|(i, node)| {
+            node.dyn_into::<js_sys::Uint8Array>()
+                .map(|bytes| bytes.to_vec())
+                .map_err(|_| format!("Proof node {} is not a Uint8Array", i))
+        })
+        .collect()
+}
+
+/// The network a saved or shared `state` belongs to: `network_config` if
+/// given, else the known network with its genesis validators root.
+fn snapshot_network(state: &LightClientState, network_config: Option<String>) -> Result<NetworkConfig, LumenError> {
+    let network = match network_config {
+        Some(config) => parse_network_config(&config).map_err(|e| LumenError::new(ErrorCode::InvalidConfig, e))?,
+        None => NetworkConfig::for_genesis_validators_root(&state.genesis_validators_root).ok_or_else(|| {
+            LumenError::new(ErrorCode::InvalidConfig, format!(
+                "Cannot resume: genesis validators root 0x{} is not a known network; pass its network config",
+                hex::encode(state.genesis_validators_root)
+            ))
+        })?,
+    };
+    if network.genesis_validators_root != state.genesis_validators_root {
+        return Err(LumenError::new(ErrorCode::NetworkMismatch, format!(
+            "Cannot resume: saved state is not from {} (genesis validators root mismatch)",
+            network.name
+        )));
+    }
+    Ok(network)
+}
+
+/// Accept either a preset name or a JSON `NetworkConfig`.
+fn parse_network_config(config: &str) -> Result<NetworkConfig, String> {
+    let config = config.trim();
+    if config.starts_with('{') {
+        return serde_json::from_str(config).map_err(|e| format!("Invalid network config: {}", e));
+    }
+    NetworkConfig::preset(config).ok_or_else(|| {
+        let known: Vec<String> = NetworkConfig::presets().into_iter().map(|n| n.name).collect();
+        format!("Unknown network {:?} (known: {})", config, known.join(", "))
+    })
+}
+
+/// Provider URLs from a JSON array, falling back to the network's built-in
+/// registry when the array is empty.
+fn checkpoint_provider_urls(urls_json: &str, network_config: &str) -> Result<Vec<String>, String> {
+    let overrides: Vec<String> =
+        serde_json::from_str(urls_json).map_err(|e| format!("Invalid provider list: {}", e))?;
+    let network = parse_network_config(network_config)?;
+    checkpoint::resolve_providers(overrides, &network.name)
+}
+
+/// The sync committee period of a finality update's attested header.
+fn finality_attested_period(finality: &BeaconPayload) -> Result<u64, LumenError> {
+    let attested_slot = match finality {
+        BeaconPayload::Ssz(bytes) => {
+            lumen_core::consensus::ssz::decode_finality_update(bytes)
+                .map_err(|e| LumenError::new(ErrorCode::InvalidSsz, format!("Invalid finality update SSZ: {}", e)))?
+                .update
+                .attested_header
+                .slot
+        }
+        BeaconPayload::Json(json) => {
+            let response: beacon_api::ApiFinalityUpdateResponse = serde_json::from_str(json).map_err(|e| {
+                LumenError::new(ErrorCode::InvalidJson, format!("Invalid finality update JSON: {}", e))
+            })?;
+            response.data.attested_header.beacon.to_core().map_err(|e| LumenError::new(ErrorCode::InvalidJson, e))?.slot
+        }
+    };
+    Ok(attested_slot / SLOTS_PER_SYNC_COMMITTEE_PERIOD)
+}
+
+/// Log a sync progress event and hand it to the caller's callback, if any.
+fn report_progress(callback: Option<&js_sys::Function>, progress: &sync::SyncProgress) {
+    // Per-item events would flood the log at info.
+    if progress.done.is_some() {
+        lumen_log!(Debug, "sync", "{}", progress.message);
+    } else {
+        lumen_log!(Info, "sync", "{}", progress.message);
+    }
+    let Some(callback) = callback else {
+        return;
+    };
+    let delivered = progress
+        .serialize(&serde_wasm_bindgen::Serializer::json_compatible())
+        .map_err(|e| JsValue::from_str(&e.to_string()))
+        .and_then(|payload| callback.call1(&JsValue::NULL, &payload));
+    if let Err(e) = delivered {
+        lumen_log!(Error, "events", "Sync progress callback threw: {:?}", e);
     }
 }
 
-// --- Console logging ---
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lumen_core::execution::trie_builder::TrieBuilder;
+    use network::MockNetwork;
+    use std::rc::Rc;
+
+    const ADDRESS: &str = "0x00000000219ab540356cbb839cbe05303d7705fa";
+
+    fn account() -> AccountState {
+        AccountState {
+            nonce: 3,
+            balance: U256::from_u64(1_000_000_007),
+            storage_root: AccountState::EMPTY_STORAGE_ROOT,
+            code_hash: AccountState::EMPTY_CODE_HASH,
+        }
+    }
+
+    /// A synced state whose verified execution block #100 has `state_root`.
+    pub(crate) fn state(state_root: [u8; 32]) -> LightClientState {
+        LightClientState {
+            finalized_header: BeaconBlockHeader {
+                slot: 9_000_000,
+                proposer_index: 7,
+                parent_root: [0x01; 32],
+                state_root: [0x02; 32],
+                body_root: [0x03; 32],
+            },
+            current_sync_committee: SyncCommittee {
+                pubkeys: Vec::new(),
+                aggregate_pubkey: BlsPublicKey([0x22; 48]),
+            },
+            next_sync_committee: None,
+            latest_execution_payload_header: Some(ExecutionPayloadHeader {
+                parent_hash: [0; 32],
+                fee_recipient: [0; 20],
+                state_root,
+                receipts_root: [0; 32],
+                block_number: 100,
+                gas_limit: 30_000_000,
+                gas_used: 0,
+                timestamp: 0,
+                base_fee_per_gas: U256::from_u64(7),
+                block_hash: [0; 32],
+                transactions_root: [0; 32],
+                withdrawals_root: [0; 32],
+                blob_gas_used: 0,
+                excess_blob_gas: 0,
+            }),
+            genesis_validators_root: [0xAA; 32],
+            fork_version: [0x05, 0x00, 0x00, 0x00],
+            last_updated_slot: 9_000_000,
+        }
+    }
+
+    /// A client with `state(state_root)`, fetching through `network`.
+    fn client(state_root: [u8; 32], network: Rc<MockNetwork>) -> LumenClient {
+        let mut client = LumenClient::from_state(state(state_root), NetworkConfig::mainnet());
+        client.set_network_provider(Box::new(network));
+        client
+    }
+
+    /// An RPC serving `trie`'s proof for ADDRESS, with latest block #101 at `latest_root`.
+    fn rpc(trie: &TrieBuilder, latest_root: [u8; 32]) -> Rc<MockNetwork> {
+        rpc_claiming(trie, latest_root, "0x3b9aca07")
+    }
+
+    /// As `rpc`, but claiming `balance` alongside the proof.
+    fn rpc_claiming(trie: &TrieBuilder, latest_root: [u8; 32], balance: &str) -> Rc<MockNetwork> {
+        let address = provider::parse_address(ADDRESS).unwrap();
+        let nodes: Vec<String> = trie
+            .account_proof(&address)
+            .proof
+            .iter()
+            .map(|node| format!("0x{}", hex::encode(node)))
+            .collect();
+        Rc::new(
+            MockNetwork::default()
+                .with_result(
+                    "eth_getBlockByNumber",
+                    serde_json::json!({"number": "0x65", "stateRoot": format!("0x{}", hex::encode(latest_root))}),
+                )
+                .with_result(
+                    "eth_getProof",
+                    serde_json::json!({
+                        "address": ADDRESS,
+                        "accountProof": nodes,
+                        "balance": balance,
+                        "codeHash": format!("0x{}", hex::encode(AccountState::EMPTY_CODE_HASH)),
+                        "nonce": "0x3",
+                        "storageHash": format!("0x{}", hex::encode(AccountState::EMPTY_STORAGE_ROOT)),
+                        "storageProof": [],
+                    }),
+                ),
+        )
+    }
+
+    fn trie() -> TrieBuilder {
+        let mut trie = TrieBuilder::new();
+        trie.insert_account(&provider::parse_address(ADDRESS).unwrap(), &account());
+        trie.insert_account(&[0x42; 20], &account());
+        trie
+    }
+
+    #[test]
+    fn test_fetch_account_through_injected_transport() {
+        let trie = trie();
+        let network = rpc(&trie, trie.root());
+        let client = client(trie.root(), network.clone());
+
+        let fetched = futures::executor::block_on(client.fetch_account("mock://rpc", ADDRESS, &[])).unwrap();
+        assert_eq!(fetched.account, account());
+        assert_eq!(fetched.block_number, 101);
+        assert!(fetched.discrepancies.is_empty());
+        let calls = network.calls.borrow();
+        assert_eq!(calls[0].0, "eth_getBlockByNumber");
+        assert_eq!(calls[1], ("eth_getProof".to_string(), serde_json::json!([ADDRESS, [], "0x65"])));
+    }
+
+    #[test]
+    fn test_finalized_mode_ignores_the_rpc_block() {
+        let trie = trie();
+        // The RPC lies about its latest state root; finalized mode never asks.
+        let network = rpc(&trie, [0xEE; 32]);
+        let client = client(trie.root(), network.clone());
+        client.set_proof_mode("finalized").unwrap();
+
+        let fetched = futures::executor::block_on(client.fetch_account("mock://rpc", ADDRESS, &[])).unwrap();
+        assert_eq!(fetched.account.balance, U256::from_u64(1_000_000_007));
+        assert_eq!(fetched.block_number, 100);
+        let calls = network.calls.borrow();
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].1[2], "0x64");
+    }
+
+    #[test]
+    fn test_dispatch_serves_code_and_storage_at_the_finalized_block() {
+        let trie = trie();
+        // The default proof mode; the RPC lies about its latest state root.
+        let network = rpc(&trie, [0xEE; 32]);
+        let mut client = client(trie.root(), network.clone());
+        client.set_rpc_endpoints(r#"["mock://rpc"]"#).unwrap();
+        // The storage proof is missing; its warning would go to the browser console.
+        logging::set_filter(logging::LogFilter::parse("off").unwrap());
+        let dispatch = |method: &str, params: serde_json::Value| {
+            let request: provider::JsonRpcRequest =
+                serde_json::from_value(serde_json::json!({"method": method, "params": params, "id": 1})).unwrap();
+            futures::executor::block_on(client.dispatch(&request))
+        };
+
+        let code = dispatch("eth_getCode", serde_json::json!([ADDRESS, "latest"]));
+        assert_eq!(code.result, Some(serde_json::json!("0x")));
+        assert_eq!(code.lumen.unwrap().block_number, Some(100));
+        dispatch("eth_getStorageAt", serde_json::json!([ADDRESS, "0x0"]));
+
+        let calls = network.calls.borrow();
+        assert!(calls.iter().all(|(method, _)| method == "eth_getProof"));
+        assert!(calls.iter().all(|(_, params)| params[2] == "0x64"));
+        assert_eq!(calls.len(), 2);
+    }
+
+    #[test]
+    fn test_fetch_account_flags_false_claims() {
+        let trie = trie();
+        let client = client(trie.root(), rpc_claiming(&trie, trie.root(), "0xffffffff"));
+        // The warning would go to the browser console, which native tests lack.
+        logging::set_filter(logging::LogFilter::parse("off").unwrap());
+
+        let fetched = futures::executor::block_on(client.fetch_account("mock://rpc", ADDRESS, &[])).unwrap();
+        // The proof still decides the answer; the false claim is only reported.
+        assert_eq!(fetched.account, account());
+        assert_eq!(fetched.discrepancies.len(), 1);
+        assert_eq!(fetched.discrepancies[0].field, "balance");
+        assert_eq!(fetched.discrepancies[0].verified, "0x3b9aca07");
+    }
+
+    #[test]
+    fn test_dispatch_uses_eip1193_error_codes() {
+        let trie = trie();
+        let client = client(trie.root(), Rc::new(MockNetwork::default()));
+        let code = |method: &str| {
+            let request: provider::JsonRpcRequest =
+                serde_json::from_value(serde_json::json!({"method": method, "params": [ADDRESS], "id": 1})).unwrap();
+            let response = futures::executor::block_on(client.dispatch(&request));
+            response.error.map(|e| e.code)
+        };
+
+        assert_eq!(code("eth_requestAccounts"), Some(provider::USER_REJECTED));
+        assert_eq!(code("eth_signTypedData_v4"), Some(provider::UNSUPPORTED_METHOD));
+        assert_eq!(code("eth_getBalance"), Some(provider::DISCONNECTED));
+        assert_eq!(code("eth_blockNumber"), None);
+        assert_eq!(code("eth_gasPrice"), None);
+        assert_eq!(code("eth_chainId"), None);
+
+        client.state.borrow_mut().latest_execution_payload_header = None;
+        assert_eq!(code("eth_blockNumber"), Some(provider::CHAIN_DISCONNECTED));
+    }
+
+    #[test]
+    fn test_dispatch_attaches_verification_metadata() {
+        let trie = trie();
+        let mut client = client(trie.root(), rpc(&trie, trie.root()));
+        client.set_rpc_endpoints(r#"["mock://rpc"]"#).unwrap();
+        client.set_proof_mode("finalized").unwrap();
+        let meta = |method: &str, params: serde_json::Value| {
+            let request: provider::JsonRpcRequest =
+                serde_json::from_value(serde_json::json!({"method": method, "params": params, "id": 1})).unwrap();
+            futures::executor::block_on(client.dispatch(&request)).lumen.unwrap()
+        };
+
+        let balance = meta("eth_getBalance", serde_json::json!([ADDRESS, "latest"]));
+        assert!(balance.verified);
+        assert_eq!(balance.path, provider::VerificationPath::AccountProof);
+        assert_eq!(balance.finalized_slot, Some(client.head_slot()));
+        assert_eq!(balance.block_number, Some(client.finalized_block_number()));
+        assert_eq!(balance.proof_nodes, Some(trie.account_proof(&provider::parse_address(ADDRESS).unwrap()).proof.len()));
+
+        let chain_id = meta("eth_chainId", serde_json::json!([]));
+        assert_eq!(chain_id.path, provider::VerificationPath::Local);
+        assert_eq!(chain_id.finalized_slot, Some(client.head_slot()));
+        assert_eq!(meta("eth_blockNumber", serde_json::json!([])).path, provider::VerificationPath::FinalizedHeader);
+
+        // Fee estimates that include a priority fee say it is a guess.
+        assert!(!balance.heuristic);
+        assert!(meta("eth_gasPrice", serde_json::json!([])).heuristic);
+        let fees = meta("eth_feeHistory", serde_json::json!(["0x1", "latest"]));
+        assert_eq!((fees.path, fees.heuristic), (provider::VerificationPath::HeaderChain, false));
+        assert!(meta("eth_feeHistory", serde_json::json!(["0x1", "latest", [50]])).heuristic);
+    }
+
+    #[test]
+    fn test_repeated_fetches_reuse_cached_results_until_the_head_advances() {
+        let trie = trie();
+        let network = rpc(&trie, trie.root());
+        let client = client(trie.root(), network.clone());
+        let fetch = || futures::executor::block_on(client.fetch_account("mock://rpc", ADDRESS, &[])).unwrap();
+
+        fetch();
+        fetch();
+        assert_eq!(network.calls.borrow().len(), 2);
+
+        // "latest" now means another block; the proof at block #101 is still good.
+        let mut next = client.state.borrow().latest_execution_payload_header.clone().unwrap();
+        next.block_number += 1;
+        client.store_execution_header(next);
+        fetch();
+        let methods: Vec<String> = network.calls.borrow().iter().map(|(method, _)| method.clone()).collect();
+        assert_eq!(methods, vec!["eth_getBlockByNumber", "eth_getProof", "eth_getBlockByNumber"]);
+
+        client.set_response_cache(0.0, None);
+        fetch();
+        assert_eq!(network.calls.borrow().len(), 5);
+    }
+
+    #[test]
+    fn test_rate_limit_queues_then_refuses() {
+        let trie = trie();
+        let mut client = client(trie.root(), rpc(&trie, trie.root()));
+        let time = TestClock::new(1_000_000.0);
+        client.set_time_source(Box::new(time.clone()));
+        client.set_rpc_endpoints(r#"["mock://rpc"]"#).unwrap();
+        client.set_proof_mode("finalized").unwrap();
+        client.set_response_cache(0.0, None);
+        logging::set_filter(logging::LogFilter::parse("off").unwrap());
+        let balance = || {
+            let request: provider::JsonRpcRequest = serde_json::from_value(
+                serde_json::json!({"method": "eth_getBalance", "params": [ADDRESS, "latest"], "id": 1}),
+            )
+            .unwrap();
+            futures::executor::block_on(client.dispatch(&request))
+        };
+
+        client
+            .set_rate_limits(r#"{"per_endpoint": {"requests_per_second": 1, "burst": 1}, "global": null}"#)
+            .unwrap();
+        assert!(balance().error.is_none());
+        assert!(balance().error.is_none());
+        // The second request waited a second for its token.
+        assert_eq!(time.now_ms(), 1_001_000.0);
 
-fn log_to_console(msg: &str) {
-    web_sys::console::log_1(&JsValue::from_str(msg));
+        client
+            .set_rate_limits(r#"{"per_endpoint": {"requests_per_second": 1, "burst": 1}, "max_queue_ms": 0}"#)
+            .unwrap();
+        assert!(balance().error.is_none());
+        assert_eq!(balance().error.map(|e| e.code), Some(provider::LIMIT_EXCEEDED));
+        // Refusals are not held against the endpoint.
+        assert_eq!(client.rpc_health.borrow().report(time.now_ms())[0].failures, 0);
+    }
+
+    #[test]
+    fn test_wall_clock_checks_follow_the_time_source() {
+        let mut client = LumenClient::from_state(state([0; 32]), NetworkConfig::mainnet());
+        let slots = lumen_core::SlotClock::new(client.network.genesis_time);
+        let time = TestClock::new(slots.slot_start_ms(9_000_010) as f64);
+        client.set_time_source(Box::new(time.clone()));
+        logging::set_filter(logging::LogFilter::parse("off").unwrap());
+
+        let header = BeaconBlockHeader {
+            slot: 9_000_032,
+            proposer_index: 0,
+            parent_root: [0; 32],
+            state_root: [0; 32],
+            body_root: [0; 32],
+        };
+        let update = LightClientUpdate {
+            attested_header: header.clone(),
+            next_sync_committee: None,
+            next_sync_committee_branch: Vec::new(),
+            finalized_header: header,
+            finality_branch: Vec::new(),
+            sync_aggregate: SyncAggregate {
+                sync_committee_bits: vec![0xFF; 64],
+                sync_committee_signature: BlsSignature([0; 96]),
+            },
+            signature_slot: 9_000_033,
+        };
+        assert!(matches!(client.check_not_future(&update), Err(VerificationError::FutureSlot { .. })));
+        assert!(!client.apply_update(&update));
+        assert!(!client.warn_if_stale());
+
+        time.set(slots.slot_start_ms(9_000_033) as f64);
+        assert!(client.check_not_future(&update).is_ok());
+        time.advance(((lumen_core::consensus::clock::STALE_HEAD_SLOTS + 1) * 12_000) as f64);
+        assert!(client.warn_if_stale());
+    }
+
+    #[test]
+    fn test_shared_state_carries_execution_history_to_another_client() {
+        logging::set_filter(logging::LogFilter::parse("off").unwrap());
+        let mut verified = state([0x77; 32]);
+        verified.genesis_validators_root = NetworkConfig::mainnet().genesis_validators_root;
+        verified.current_sync_committee.pubkeys = vec![BlsPublicKey([0x11; 48]); 512];
+        let older = ExecutionPayloadHeader {
+            block_number: 68,
+            ..verified.latest_execution_payload_header.clone().unwrap()
+        };
+        let client = LumenClient::from_state(verified, NetworkConfig::mainnet());
+        {
+            let mut history = history::ExecutionHistory::default();
+            history.record(&older);
+            history.record(client.state.borrow().latest_execution_payload_header.as_ref().unwrap());
+            *client.execution_history.borrow_mut() = history;
+        }
+
+        let key = [0x5A; 32];
+        let shared = client.export_shared_state(&key).unwrap();
+        let imported = LumenClient::import_shared_state(&shared, &key, None).unwrap();
+        assert_eq!(imported.head_slot(), client.head_slot());
+        assert_eq!(imported.network.chain_id, 1);
+        assert_eq!(imported.finalized_block_number(), 100);
+        assert!(imported.execution_history.borrow().get(68).is_some());
+    }
+
+    #[test]
+    fn test_resource_stats_count_cache_entries() {
+        let trie = trie();
+        let client = client(trie.root(), rpc(&trie, trie.root()));
+        let fetch = || futures::executor::block_on(client.fetch_account("mock://rpc", ADDRESS, &[])).unwrap();
+        fetch();
+        fetch();
+
+        let stats = client.resource_report();
+        assert_eq!(stats.wasm_memory_bytes, None);
+        assert_eq!(stats.execution_history.entries, 1);
+        assert_eq!(stats.execution_history.capacity, history::HISTORY_LEN);
+        assert!(stats.response_cache.entries > 0);
+        assert!(stats.response_cache.approx_bytes > 0);
+        assert!(stats.response_cache.hits.unwrap() > 0);
+        assert_eq!(stats.aggregate_cache.entries, 0);
+    }
+
+    #[test]
+    fn test_health_tracks_lag_endpoints_and_updates() {
+        let mut client = LumenClient::from_state(state([0; 32]), NetworkConfig::mainnet());
+        let slots = lumen_core::SlotClock::new(client.network.genesis_time);
+        let time = TestClock::new(slots.slot_start_ms(9_000_010) as f64);
+        client.set_time_source(Box::new(time.clone()));
+        client.rpc_endpoints = vec!["https://rpc.example".to_string()];
+
+        let report = client.health_report();
+        assert_eq!(report.status, health::HealthStatus::Healthy);
+        assert_eq!(report.checkpoint_slot, 9_000_000);
+        assert_eq!(report.checkpoint_age_ms, Some(120_000.0));
+        assert_eq!(report.finalized_lag_slots, Some(10));
+        assert_eq!(report.last_update_at_ms, None);
+        assert_eq!(report.p2p, health::P2pStatus::default());
+
+        client.set_p2p_status("relay", 3).unwrap();
+        for _ in 0..rpc_health::QUARANTINE_AFTER {
+            client.rpc_health.borrow_mut().record_failure("https://rpc.example", "down", time.now_ms());
+        }
+        let report = client.health_report();
+        assert_eq!(report.status, health::HealthStatus::Degraded);
+        assert_eq!(report.p2p.peer_count, 3);
+        assert!(!report.rpc_endpoints[0].healthy);
+
+        time.advance(((lumen_core::consensus::clock::STALE_HEAD_SLOTS + 1) * 12_000) as f64);
+        assert_eq!(client.health_report().status, health::HealthStatus::Stale);
+        client.emit_head_advanced(None);
+        assert_eq!(client.health_report().last_update_at_ms, Some(time.now_ms()));
+    }
+
+    #[test]
+    fn test_verify_receipt_against_verified_roots() {
+        let receipts: Vec<TransactionReceipt> = (0..3)
+            .map(|i| TransactionReceipt {
+                tx_type: TransactionType::DynamicFee,
+                status: 1,
+                cumulative_gas_used: 21_000 * (i + 1),
+                logs_bloom: [0; 256],
+                logs: vec![Log { address: [i as u8; 20], topics: vec![[0xDD; 32]], data: vec![i as u8] }],
+            })
+            .collect();
+        let (root, proof) = lumen_core::execution::receipt::build_receipt_proof(&receipts, 1).unwrap();
+        let proof_json = serde_json::to_string(
+            &proof.proof.iter().map(|node| format!("0x{}", hex::encode(node))).collect::<Vec<_>>(),
+        )
+        .unwrap();
+
+        let mut verified = state([0; 32]);
+        verified.latest_execution_payload_header.as_mut().unwrap().receipts_root = root;
+        let client = LumenClient::from_state(verified, NetworkConfig::mainnet());
+        let root_hex = format!("0x{}", hex::encode(root));
+
+        let (receipt, header) = client.verify_receipt_json(None, 1, &proof_json).unwrap();
+        assert_eq!(receipt, receipts[1]);
+        assert_eq!(header.block_number, 100);
+        assert!(client.verify_receipt_json(Some(&root_hex), 1, &proof_json).is_ok());
+
+        let wrong_index = client.verify_receipt_json(None, 2, &proof_json);
+        assert_eq!(wrong_index.err().map(|e| e.code), Some(ErrorCode::ProofInvalid));
+        let unknown_root = client.verify_receipt_json(Some(&format!("0x{}", "ab".repeat(32))), 1, &proof_json);
+        assert_eq!(unknown_root.err().map(|e| e.code), Some(ErrorCode::InvalidInput));
+    }
+
+    #[test]
+    fn test_user_operation_found_in_verified_receipt() {
+        use lumen_core::execution::user_operation::{ENTRY_POINTS, USER_OPERATION_EVENT};
+
+        let user_op_hash = [0xAB; 32];
+        let mut sender = [0u8; 32];
+        sender[12..].copy_from_slice(&[0x5E; 20]);
+        let data: Vec<u8> = [1u64, 1, 21_000, 70_000]
+            .iter()
+            .flat_map(|v| U256::from_u64(*v).to_be_bytes())
+            .collect();
+        let event = Log {
+            address: ENTRY_POINTS[1],
+            topics: vec![lumen_core::execution::proof::keccak256(USER_OPERATION_EVENT.as_bytes()), user_op_hash, sender, [0; 32]],
+            data,
+        };
+        let receipt = TransactionReceipt {
+            tx_type: TransactionType::DynamicFee,
+            status: 1,
+            cumulative_gas_used: 90_000,
+            logs_bloom: [0; 256],
+            logs: vec![Log { address: [0x11; 20], topics: vec![], data: vec![] }, event],
+        };
+        let (root, proof) = lumen_core::execution::receipt::build_receipt_proof(&[receipt], 0).unwrap();
+        let proof_json = serde_json::to_string(
+            &proof.proof.iter().map(|node| format!("0x{}", hex::encode(node))).collect::<Vec<_>>(),
+        )
+        .unwrap();
+        let mut verified = state([0; 32]);
+        verified.latest_execution_payload_header.as_mut().unwrap().receipts_root = root;
+        let client = LumenClient::from_state(verified, NetworkConfig::mainnet());
+
+        let hash_hex = format!("0x{}", hex::encode(user_op_hash));
+        let (hash, entry_points) = parse_user_operation_query(&hash_hex, None).unwrap();
+        let (receipt, header) = client.verify_receipt_json(None, 0, &proof_json).unwrap();
+        let found = find_user_operation(&receipt.logs, &hash, &entry_points, 0, header.block_number).unwrap();
+        assert!(found.success);
+        assert_eq!(found.log_index, 1);
+        assert_eq!(found.sender, format!("0x{}", "5e".repeat(20)));
+        assert_eq!(found.paymaster, None);
+        assert_eq!(found.actual_gas_used, "0x11170");
+        assert_eq!(found.block_number, 100);
+
+        // Pinned to another EntryPoint, the same event does not count.
+        let v06 = format!("0x{}", hex::encode(ENTRY_POINTS[0]));
+        let (_, v06) = parse_user_operation_query(&hash_hex, Some(&v06)).unwrap();
+        let missing = find_user_operation(&receipt.logs, &hash, &v06, 0, 100);
+        assert_eq!(missing.err().map(|e| e.code), Some(ErrorCode::InvalidInput));
+    }
+
+    fn block_header() -> ExecutionBlockHeader {
+        ExecutionBlockHeader {
+            parent_hash: [0x01; 32],
+            ommers_hash: [0x02; 32],
+            beneficiary: [0x03; 20],
+            state_root: [0; 32],
+            transactions_root: [0x05; 32],
+            receipts_root: [0x06; 32],
+            logs_bloom: [0; 256],
+            difficulty: U256::ZERO,
+            number: 100,
+            gas_limit: 30_000_000,
+            gas_used: 84_000,
+            timestamp: 1_730_000_000,
+            extra_data: vec![],
+            mix_hash: [0x07; 32],
+            nonce: [0; 8],
+            base_fee_per_gas: Some(U256::from_u64(1_000_000_000)),
+            withdrawals_root: Some([0x08; 32]),
+            blob_gas_used: None,
+            excess_blob_gas: None,
+            parent_beacon_block_root: None,
+            requests_hash: None,
+        }
+    }
+
+    #[test]
+    fn test_verify_block_header_from_rlp_or_json() {
+        let header = block_header();
+        let mut verified_state = state([0; 32]);
+        verified_state.latest_execution_payload_header.as_mut().unwrap().block_hash = header.hash();
+        let client = LumenClient::from_state(verified_state, NetworkConfig::mainnet());
+
+        let raw_header = format!("0x{}", hex::encode(header.encode()));
+        assert_eq!(client.verify_header_input(&raw_header).unwrap(), header);
+        let block = provider::header_to_json(&header, header.hash());
+        assert_eq!(client.verify_header_input(&block.to_string()).unwrap(), header);
+        let response = VerifiedBlockHeaderResponse::from(&header);
+        assert_eq!(response.block_hash, format!("0x{}", hex::encode(header.hash())));
+        assert_eq!(response.base_fee_per_gas.as_deref(), Some("0x3b9aca00"));
+        assert!(response.blob_gas_used.is_none());
+
+        let mut wrong_hash = block.clone();
+        wrong_hash["hash"] = format!("0x{}", "ab".repeat(32)).into();
+        let rejected = client.verify_header_input(&wrong_hash.to_string());
+        assert_eq!(rejected.err().map(|e| e.code), Some(ErrorCode::ProofInvalid));
+        let mut tampered = block;
+        tampered["gasUsed"] = "0x1".into();
+        let unknown = client.verify_header_input(&tampered.to_string());
+        assert_eq!(unknown.err().map(|e| e.code), Some(ErrorCode::InvalidInput));
+    }
+
+    #[test]
+    fn test_verify_transaction_inclusion_in_verified_block() {
+        use lumen_core::execution::rlp;
+        let envelopes: Vec<Vec<u8>> = (0..4u64)
+            .map(|nonce| {
+                rlp::encode_list(&[
+                    rlp::encode_u64(nonce),
+                    rlp::encode_u64(1_000_000_000),
+                    rlp::encode_u64(21_000),
+                    rlp::encode_bytes(&[0x22; 20]),
+                    rlp::encode_u64(nonce * 100),
+                    rlp::encode_bytes(&[]),
+                    rlp::encode_u64(37),
+                    rlp::encode_bytes(&[0xAA; 32]),
+                    rlp::encode_bytes(&[0xBB; 32]),
+                ])
+            })
+            .collect();
+        let (transactions_root, proof) =
+            lumen_core::execution::transaction::build_transaction_proof(&envelopes, 2).unwrap();
+        let proof_json = serde_json::to_string(
+            &proof.proof.iter().map(|node| format!("0x{}", hex::encode(node))).collect::<Vec<_>>(),
+        )
+        .unwrap();
+        let header = ExecutionBlockHeader { transactions_root, ..block_header() };
+        let raw_header = format!("0x{}", hex::encode(header.encode()));
+
+        let mut verified_state = state([0; 32]);
+        verified_state.latest_execution_payload_header.as_mut().unwrap().block_hash = header.hash();
+        let client = LumenClient::from_state(verified_state, NetworkConfig::mainnet());
+
+        let (verified, block) = client.verify_transaction_json(&raw_header, 2, &proof_json).unwrap();
+        assert_eq!(block.number, 100);
+        let response = VerifiedTransactionResponse::new(&verified, &block);
+        assert_eq!(response.transaction_hash, format!("0x{}", hex::encode(lumen_core::keccak256(&envelopes[2]))));
+        assert_eq!(response.nonce, 2);
+        assert_eq!(response.chain_id, Some(1));
+        assert_eq!(response.value, "0xc8");
+        assert_eq!(response.to.as_deref(), Some(format!("0x{}", "22".repeat(20)).as_str()));
+
+        let wrong_index = client.verify_transaction_json(&raw_header, 3, &proof_json);
+        assert_eq!(wrong_index.err().map(|e| e.code), Some(ErrorCode::ProofInvalid));
+        let unverified = LumenClient::from_state(state([0; 32]), NetworkConfig::mainnet());
+        let unknown_block = unverified.verify_transaction_json(&raw_header, 2, &proof_json);
+        assert_eq!(unknown_block.err().map(|e| e.code), Some(ErrorCode::InvalidInput));
+    }
+
+    #[test]
+    fn test_receipt_bound_to_proven_transaction() {
+        use lumen_core::execution::rlp;
+        // The EIP-155 example transaction, sent by 0x9d8a...4a4f.
+        let signed = hex::decode(concat!(
+            "f86c098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a7640000",
+            "8025a028ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276",
+            "a067cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83"
+        ))
+        .unwrap();
+        let receipt = TransactionReceipt {
+            tx_type: TransactionType::Legacy,
+            status: 1,
+            cumulative_gas_used: 21_000,
+            logs_bloom: [0; 256],
+            logs: vec![Log { address: [0x44; 20], topics: vec![[0x55; 32]], data: vec![0x66] }],
+        };
+        let envelopes = vec![signed.clone()];
+        let transactions_root = lumen_core::execution::transaction::build_transaction_proof(&envelopes, 0).unwrap().0;
+        let receipts_root = lumen_core::execution::receipt::build_receipt_proof(&[receipt], 0).unwrap().0;
+        let header = ExecutionBlockHeader { transactions_root, receipts_root, ..block_header() };
+        let block_hash = format!("0x{}", hex::encode(header.hash()));
+        let raw_block = rlp::encode_list(&[header.encode(), rlp::encode_list(&envelopes), rlp::encode_list(&[])]);
+
+        let tx_hash = format!("0x{}", hex::encode(lumen_core::keccak256(&signed)));
+        let rpc_receipt = |tx_hash: &str| {
+            serde_json::json!({
+                "transactionHash": tx_hash,
+                "transactionIndex": "0x0",
+                "blockHash": block_hash,
+                "blockNumber": "0x64",
+                "type": "0x0",
+                "status": "0x1",
+                "cumulativeGasUsed": "0x5208",
+                "gasUsed": "0x1",
+                "logsBloom": format!("0x{}", "00".repeat(256)),
+                "logs": [{
+                    "address": format!("0x{}", "44".repeat(20)),
+                    "topics": [format!("0x{}", "55".repeat(32))],
+                    "data": "0x66",
+                }],
+            })
+        };
+        let network = Rc::new(
+            MockNetwork::default()
+                .with_result("debug_getRawHeader", format!("0x{}", hex::encode(header.encode())).into())
+                .with_result("debug_getRawBlock", format!("0x{}", hex::encode(&raw_block)).into())
+                .with_result("eth_getBlockReceipts", serde_json::json!([rpc_receipt(&tx_hash)])),
+        );
+        let mut verified_state = state([0; 32]);
+        verified_state.latest_execution_payload_header.as_mut().unwrap().block_hash = header.hash();
+        let mut client = LumenClient::from_state(verified_state, NetworkConfig::mainnet());
+        client.set_network_provider(Box::new(network));
+        let verify = |tx_hash: &str| {
+            let located = serde_json::from_value(rpc_receipt(tx_hash)).unwrap();
+            futures::executor::block_on(client.verify_located_receipt("mock://rpc", tx_hash, &located))
+        };
+
+        let verified = verify(&tx_hash).unwrap();
+        assert_eq!(verified["transactionHash"], tx_hash.as_str());
+        assert_eq!(verified["from"], "0x9d8a62f656a8d1615c1294fd71e9cfb3e4855a4f");
+        assert_eq!(verified["to"], format!("0x{}", "35".repeat(20)).as_str());
+        assert_eq!(verified["gasUsed"], "0x5208");
+        assert_eq!(verified["effectiveGasPrice"], "0x4a817c800");
+        assert_eq!(verified["logs"][0]["logIndex"], "0x0");
+        assert_eq!(verified["logs"][0]["blockHash"], block_hash.as_str());
+        assert_eq!(verified["verified"], true);
+
+        // An RPC naming another transaction at this position is caught by
+        // the transactions trie, not trusted.
+        let other = format!("0x{}", "ab".repeat(32));
+        let err = verify(&other).unwrap_err();
+        assert!(err.starts_with("Block transaction at index 0"), "{}", err);
+    }
+
+    #[test]
+    fn test_watched_transaction_not_failed_on_an_rpc_claim() {
+        let tx_hash = format!("0x{}", "ab".repeat(32));
+        // The RPC places the transaction far behind the finalized block #1000.
+        let network = Rc::new(MockNetwork::default().with_result(
+            "eth_getTransactionReceipt",
+            serde_json::json!({
+                "transactionHash": tx_hash,
+                "transactionIndex": "0x0",
+                "blockHash": format!("0x{}", "cd".repeat(32)),
+                "blockNumber": "0x1",
+                "status": "0x1",
+                "cumulativeGasUsed": "0x5208",
+                "logsBloom": format!("0x{}", "00".repeat(256)),
+                "logs": [],
+            }),
+        ));
+        let mut client = client([0; 32], network);
+        client.set_rpc_endpoints(r#"["mock://rpc"]"#).unwrap();
+        client.state.borrow_mut().latest_execution_payload_header.as_mut().unwrap().block_number = 1000;
+        assert!(client.watch_transaction(&tx_hash).unwrap());
+        // Failed verifications would log to the browser console.
+        logging::set_filter(logging::LogFilter::parse("off").unwrap());
+
+        let events = futures::executor::block_on(client.poll_transaction_events());
+        assert_eq!(events.len(), 1);
+        assert_eq!((events[0].status, events[0].block_number), ("included", Some(1)));
+        assert!(futures::executor::block_on(client.poll_transaction_events()).is_empty());
+        assert_eq!(client.watched_transaction_count(), 1);
+    }
+
+    #[test]
+    fn test_fetch_account_rejects_proof_for_other_root() {
+        let trie = trie();
+        let network = rpc(&trie, [0xEE; 32]);
+        let client = client(trie.root(), network);
+
+        let result = futures::executor::block_on(client.fetch_account("mock://rpc", ADDRESS, &[]));
+        let err = result.err().expect("proof against the wrong root was accepted");
+        assert!(err.starts_with("Proof verification"), "{}", err);
+    }
 }
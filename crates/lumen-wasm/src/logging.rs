@@ -0,0 +1,228 @@
+//! Leveled logging with per-target filters and a pluggable sink.
+//!
+//! Every message has a level and a target naming the part of the client it
+//! comes from. A filter such as `"warn,sync=info,proof=off"` decides what is
+//! emitted: the first entry without `=` is the default level, the others
+//! override it per target. By default everything at `info` and above goes to
+//! the browser console; `set_log_sink` hands records to a JS callback instead.
+//!
+//! Targets: `client` (construction, resume), `checkpoint`, `consensus`
+//! (updates, BLS verification, backfill), `sync`, `proof`, `rpc`, `events`
+//! (callbacks that threw).
+//!
+//! Messages are only formatted when their level and target pass the filter.
+
+use serde::Serialize;
+use std::cell::RefCell;
+use std::str::FromStr;
+use wasm_bindgen::prelude::*;
+
+/// Log `format!(...)` at `level` for `target`: `lumen_log!(Info, "sync", "at {}", slot)`.
+macro_rules! lumen_log {
+    ($level:ident, $target:expr, $($arg:tt)+) => {
+        $crate::logging::log($crate::logging::Level::$level, $target, || format!($($arg)+))
+    };
+}
+pub(crate) use lumen_log;
+
+/// Severity, most severe first.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Level {
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl FromStr for Level {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "error" => Ok(Level::Error),
+            "warn" | "warning" => Ok(Level::Warn),
+            "info" => Ok(Level::Info),
+            "debug" => Ok(Level::Debug),
+            "trace" => Ok(Level::Trace),
+            other => Err(format!(
+                "Unknown log level {:?} (expected error, warn, info, debug, trace or off)",
+                other
+            )),
+        }
+    }
+}
+
+/// The most verbose level allowed, or `None` for "off".
+fn parse_threshold(s: &str) -> Result<Option<Level>, String> {
+    if s.trim().eq_ignore_ascii_case("off") {
+        return Ok(None);
+    }
+    s.parse().map(Some)
+}
+
+/// Which records are emitted: a default threshold plus per-target overrides.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LogFilter {
+    default: Option<Level>,
+    targets: Vec<(String, Option<Level>)>,
+}
+
+impl Default for LogFilter {
+    fn default() -> Self {
+        Self {
+            default: Some(Level::Info),
+            targets: Vec::new(),
+        }
+    }
+}
+
+impl LogFilter {
+    /// Parse `"level,target=level,..."`; any part may be omitted.
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let mut filter = Self::default();
+        for part in spec.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            match part.split_once('=') {
+                Some((target, level)) => {
+                    let level = parse_threshold(level)?;
+                    let target = target.trim().to_string();
+                    filter.targets.retain(|(t, _)| *t != target);
+                    filter.targets.push((target, level));
+                }
+                None => filter.default = parse_threshold(part)?,
+            }
+        }
+        Ok(filter)
+    }
+
+    pub fn enabled(&self, level: Level, target: &str) -> bool {
+        let threshold = self
+            .targets
+            .iter()
+            .find(|(t, _)| t == target)
+            .map(|(_, level)| *level)
+            .unwrap_or(self.default);
+        threshold.is_some_and(|max| level <= max)
+    }
+}
+
+/// A record as delivered to the sink callback.
+#[derive(Debug, Serialize)]
+pub struct LogRecord<'a> {
+    pub level: Level,
+    pub target: &'a str,
+    pub message: &'a str,
+}
+
+#[derive(Default)]
+struct Logger {
+    filter: LogFilter,
+    sink: Option<js_sys::Function>,
+}
+
+thread_local! {
+    static LOGGER: RefCell<Logger> = RefCell::new(Logger::default());
+}
+
+pub fn set_filter(filter: LogFilter) {
+    LOGGER.with(|logger| logger.borrow_mut().filter = filter);
+}
+
+pub fn set_sink(sink: Option<js_sys::Function>) {
+    LOGGER.with(|logger| logger.borrow_mut().sink = sink);
+}
+
+/// Emit a record if the filter allows it. `message` is only called then.
+pub fn log(level: Level, target: &str, message: impl FnOnce() -> String) {
+    // Copy the sink out so a callback that reconfigures logging does not
+    // find the logger borrowed.
+    let Some(sink) = LOGGER.with(|logger| {
+        let logger = logger.borrow();
+        logger.filter.enabled(level, target).then(|| logger.sink.clone())
+    }) else {
+        return;
+    };
+    let message = message();
+
+    if let Some(sink) = sink {
+        let record = LogRecord {
+            level,
+            target,
+            message: &message,
+        };
+        let delivered = record
+            .serialize(&serde_wasm_bindgen::Serializer::json_compatible())
+            .map_err(|e| JsValue::from_str(&e.to_string()))
+            .and_then(|payload| sink.call1(&JsValue::NULL, &payload));
+        if delivered.is_ok() {
+            return;
+        }
+    }
+    write_console(level, target, &message);
+}
+
+fn write_console(level: Level, target: &str, message: &str) {
+    let line = JsValue::from_str(&format!("[Lumen:{}] {}", target, message));
+    match level {
+        Level::Error => web_sys::console::error_1(&line),
+        Level::Warn => web_sys::console::warn_1(&line),
+        Level::Info => web_sys::console::log_1(&line),
+        Level::Debug | Level::Trace => web_sys::console::debug_1(&line),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_filter() {
+        let filter = LogFilter::default();
+        assert!(filter.enabled(Level::Warn, "rpc"));
+        assert!(filter.enabled(Level::Info, "sync"));
+        assert!(!filter.enabled(Level::Debug, "sync"));
+    }
+
+    #[test]
+    fn test_parse_filter() {
+        let filter = LogFilter::parse("warn, sync=debug, proof=off").unwrap();
+        assert!(!filter.enabled(Level::Info, "consensus"));
+        assert!(filter.enabled(Level::Error, "consensus"));
+        assert!(filter.enabled(Level::Debug, "sync"));
+        assert!(!filter.enabled(Level::Trace, "sync"));
+        assert!(!filter.enabled(Level::Error, "proof"));
+
+        let filter = LogFilter::parse("off,rpc=WARN").unwrap();
+        assert!(!filter.enabled(Level::Error, "sync"));
+        assert!(filter.enabled(Level::Warn, "rpc"));
+
+        // A later override for the same target wins.
+        let filter = LogFilter::parse("rpc=error,rpc=trace").unwrap();
+        assert!(filter.enabled(Level::Trace, "rpc"));
+
+        assert_eq!(LogFilter::parse("").unwrap(), LogFilter::default());
+        assert!(LogFilter::parse("loud").is_err());
+        assert!(LogFilter::parse("sync=verbose").is_err());
+    }
+
+    #[test]
+    fn test_filtered_messages_are_not_formatted() {
+        set_filter(LogFilter::parse("off").unwrap());
+        log(Level::Error, "sync", || panic!("formatted a filtered message"));
+        set_filter(LogFilter::default());
+    }
+
+    #[test]
+    fn test_record_shape() {
+        let record = LogRecord {
+            level: Level::Warn,
+            target: "rpc",
+            message: "timed out",
+        };
+        assert_eq!(
+            serde_json::to_value(&record).unwrap(),
+            serde_json::json!({"level": "warn", "target": "rpc", "message": "timed out"})
+        );
+    }
+}
@@ -0,0 +1,210 @@
+//! Counters and timings for verification work, read with `get_metrics`.
+//!
+//! Metrics are kept per module instance (one per worker), not per client:
+//! RPC requests are timed in the network layer, which has no client to
+//! report to. Counting costs a few additions; nothing is sampled.
+//!
+//! An update counts as a duplicate when it does not advance anything the
+//! client already knows — the usual fate of the same gossip message from a
+//! second peer — and as rejected only when it fails verification.
+
+use crate::state::SyncProgress;
+use serde::Serialize;
+use std::cell::RefCell;
+
+/// Upper bounds of the histogram buckets, in milliseconds. A final
+/// unbounded bucket catches everything slower.
+pub const BUCKET_BOUNDS_MS: [f64; 12] = [1.0, 2.0, 5.0, 10.0, 25.0, 50.0, 100.0, 250.0, 500.0, 1000.0, 2500.0, 5000.0];
+
+/// Count of observations at or below `le_ms` (and above the previous bound).
+/// `le_ms` is null for the unbounded bucket.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Bucket {
+    pub le_ms: Option<f64>,
+    pub count: u64,
+}
+
+/// A latency distribution.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Histogram {
+    pub count: u64,
+    pub sum_ms: f64,
+    pub max_ms: f64,
+    pub buckets: Vec<Bucket>,
+}
+
+impl Default for Histogram {
+    fn default() -> Self {
+        let buckets = BUCKET_BOUNDS_MS
+            .iter()
+            .map(|bound| Some(*bound))
+            .chain(std::iter::once(None))
+            .map(|le_ms| Bucket { le_ms, count: 0 })
+            .collect();
+        Self {
+            count: 0,
+            sum_ms: 0.0,
+            max_ms: 0.0,
+            buckets,
+        }
+    }
+}
+
+impl Histogram {
+    pub fn record(&mut self, ms: f64) {
+        let ms = ms.max(0.0);
+        self.count += 1;
+        self.sum_ms += ms;
+        self.max_ms = self.max_ms.max(ms);
+        let bucket = self
+            .buckets
+            .iter_mut()
+            .find(|b| b.le_ms.is_none_or(|bound| ms <= bound))
+            .expect("the last bucket is unbounded");
+        bucket.count += 1;
+    }
+}
+
+/// How a light client update was handled.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UpdateOutcome {
+    Applied,
+    Duplicate,
+    Rejected,
+}
+
+/// Everything `get_metrics` returns.
+#[derive(Clone, Debug, Serialize)]
+pub struct Metrics {
+    #[serde(flatten)]
+    pub progress: SyncProgress,
+    /// Updates that carried nothing new.
+    pub updates_duplicate: u64,
+    /// Sync committee signature and branch verification, per update.
+    pub bls_verify_ms: Histogram,
+    /// Merkle-Patricia proof verification, per proof or batch.
+    pub mpt_verify_ms: Histogram,
+    /// JSON-RPC round trips, successful or not.
+    pub rpc_latency_ms: Histogram,
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self {
+            progress: SyncProgress::new(),
+            updates_duplicate: 0,
+            bls_verify_ms: Histogram::default(),
+            mpt_verify_ms: Histogram::default(),
+            rpc_latency_ms: Histogram::default(),
+        }
+    }
+}
+
+impl Metrics {
+    pub fn record_update(&mut self, outcome: UpdateOutcome, verify_ms: Option<f64>) {
+        match outcome {
+            UpdateOutcome::Applied => self.progress.updates_processed += 1,
+            UpdateOutcome::Duplicate => self.updates_duplicate += 1,
+            UpdateOutcome::Rejected => self.progress.updates_rejected += 1,
+        }
+        if let Some(ms) = verify_ms {
+            self.bls_verify_ms.record(ms);
+        }
+    }
+
+    pub fn record_proofs(&mut self, verified: u64, rejected: u64, verify_ms: f64) {
+        self.progress.proofs_verified += verified;
+        self.progress.proofs_rejected += rejected;
+        self.mpt_verify_ms.record(verify_ms);
+    }
+}
+
+thread_local! {
+    static METRICS: RefCell<Metrics> = RefCell::new(Metrics::default());
+}
+
+pub fn with<T>(f: impl FnOnce(&mut Metrics) -> T) -> T {
+    METRICS.with(|metrics| f(&mut metrics.borrow_mut()))
+}
+
+pub fn snapshot() -> Metrics {
+    METRICS.with(|metrics| metrics.borrow().clone())
+}
+
+pub fn reset() {
+    with(|metrics| *metrics = Metrics::default());
+}
+
+pub fn now_ms() -> f64 {
+    // Durations are measured on the wall clock, whatever the client's time source.
+    crate::clock::TimeSource::now_ms(&crate::clock::SystemClock)
+}
+
+/// Run one proof verification, counting its outcome and timing it.
+pub fn proof<T, E>(verify: impl FnOnce() -> Result<T, E>) -> Result<T, E> {
+    let started = now_ms();
+    let result = verify();
+    let elapsed = now_ms() - started;
+    let verified = result.is_ok() as u64;
+    with(|metrics| metrics.record_proofs(verified, 1 - verified, elapsed));
+    result
+}
+
+/// Run a batch of independent proof verifications, counting each outcome
+/// and timing the batch as a whole.
+pub fn proof_batch<T, E>(verify: impl FnOnce() -> Vec<Result<T, E>>) -> Vec<Result<T, E>> {
+    let started = now_ms();
+    let results = verify();
+    let elapsed = now_ms() - started;
+    let verified = results.iter().filter(|r| r.is_ok()).count() as u64;
+    with(|metrics| metrics.record_proofs(verified, results.len() as u64 - verified, elapsed));
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_histogram_buckets() {
+        let mut histogram = Histogram::default();
+        assert_eq!(histogram.buckets.len(), BUCKET_BOUNDS_MS.len() + 1);
+        histogram.record(0.4);
+        histogram.record(1.0);
+        histogram.record(7.5);
+        histogram.record(60_000.0);
+
+        assert_eq!(histogram.count, 4);
+        assert_eq!(histogram.max_ms, 60_000.0);
+        assert_eq!(histogram.buckets[0].count, 2); // <= 1ms, bound inclusive
+        assert_eq!(histogram.buckets[3].count, 1); // <= 10ms
+        assert_eq!(histogram.buckets.last().unwrap().count, 1);
+        assert_eq!(histogram.buckets.iter().map(|b| b.count).sum::<u64>(), 4);
+    }
+
+    #[test]
+    fn test_record_update_and_proofs() {
+        let mut metrics = Metrics::default();
+        metrics.record_update(UpdateOutcome::Applied, Some(12.0));
+        metrics.record_update(UpdateOutcome::Duplicate, None);
+        metrics.record_update(UpdateOutcome::Rejected, Some(15.0));
+        metrics.record_proofs(3, 1, 2.0);
+
+        assert_eq!(metrics.progress.updates_processed, 1);
+        assert_eq!(metrics.progress.updates_rejected, 1);
+        assert_eq!(metrics.updates_duplicate, 1);
+        assert_eq!(metrics.bls_verify_ms.count, 2);
+        assert_eq!(metrics.progress.proofs_verified, 3);
+        assert_eq!(metrics.progress.proofs_rejected, 1);
+        assert_eq!(metrics.mpt_verify_ms.count, 1);
+    }
+
+    #[test]
+    fn test_metrics_shape() {
+        let json = serde_json::to_value(Metrics::default()).unwrap();
+        assert_eq!(json["updates_processed"], 0);
+        assert_eq!(json["updates_duplicate"], 0);
+        assert_eq!(json["bls_verify_ms"]["buckets"][0], serde_json::json!({"le_ms": 1.0, "count": 0}));
+        assert_eq!(json["rpc_latency_ms"]["buckets"][12]["le_ms"], serde_json::Value::Null);
+    }
+}
@@ -11,6 +11,7 @@
 //! All data received over any transport is cryptographically verified
 //! by lumen-core before being trusted. The network layer is untrusted.
 
+use futures::future::LocalBoxFuture;
 use wasm_bindgen::prelude::*;
 use wasm_bindgen::JsCast;
 use wasm_bindgen_futures::JsFuture;
@@ -27,6 +28,8 @@ pub enum NetworkError {
     BodyReadFailed(String),
     /// WebSocket connection failed.
     WebSocketFailed(String),
+    /// The server answered in another format than the one requested.
+    UnexpectedContentType(String),
 }
 
 impl std::fmt::Display for NetworkError {
@@ -38,10 +41,57 @@ impl std::fmt::Display for NetworkError {
             }
             NetworkError::BodyReadFailed(e) => write!(f, "Body read failed: {}", e),
             NetworkError::WebSocketFailed(e) => write!(f, "WebSocket failed: {}", e),
+            NetworkError::UnexpectedContentType(t) => write!(f, "Unexpected content type: {}", t),
         }
     }
 }
 
+/// Media type of SSZ-encoded beacon API responses.
+const SSZ_CONTENT_TYPE: &str = "application/octet-stream";
+
+/// Start a fetch from whichever global scope we run in.
+///
+/// A window, or any worker: dedicated, shared and service worker scopes all
+/// extend `WorkerGlobalScope`. Any other global that has a `fetch` function
+/// (Node.js 18+, Deno) is called through it. Nothing here assumes a window.
+fn global_fetch(request: &Request) -> Result<js_sys::Promise, NetworkError> {
+    if let Some(window) = web_sys::window() {
+        return Ok(window.fetch_with_request(request));
+    }
+    let global = js_sys::global();
+    if let Some(scope) = global.dyn_ref::<web_sys::WorkerGlobalScope>() {
+        return Ok(scope.fetch_with_request(request));
+    }
+    let fetch = global_function("fetch")
+        .ok_or_else(|| NetworkError::RequestFailed("No fetch in this global scope".to_string()))?;
+    fetch
+        .call1(&global, request)
+        .and_then(|promise| promise.dyn_into::<js_sys::Promise>())
+        .map_err(|e| NetworkError::RequestFailed(format!("{:?}", e)))
+}
+
+/// A function on the global object, whatever kind of scope that is.
+pub(crate) fn global_function(name: &str) -> Option<js_sys::Function> {
+    js_sys::Reflect::get(&js_sys::global(), &JsValue::from_str(name))
+        .ok()
+        .and_then(|f| f.dyn_into::<js_sys::Function>().ok())
+}
+
+/// Resolve after `ms` milliseconds, in any global scope.
+pub async fn sleep_ms(ms: u32) {
+    let promise = js_sys::Promise::new(&mut |resolve, _reject| {
+        match global_function("setTimeout") {
+            Some(set_timeout) => {
+                let _ = set_timeout.call2(&JsValue::NULL, &resolve, &JsValue::from(ms));
+            }
+            None => {
+                let _ = resolve.call0(&JsValue::NULL);
+            }
+        }
+    });
+    let _ = JsFuture::from(promise).await;
+}
+
 /// Fetch a URL and return bytes using the browser Fetch API.
 ///
 /// This is used ONLY for initial checkpoint fetching from multiple sources.
@@ -49,17 +99,14 @@ impl std::fmt::Display for NetworkError {
 /// The response data is always verified cryptographically — this function
 /// does not trust the source at all.
 pub async fn fetch_bytes(url: &str) -> Result<Vec<u8>, NetworkError> {
-    let mut opts = RequestInit::new();
-    opts.method("GET");
-    opts.mode(RequestMode::Cors);
+    let opts = RequestInit::new();
+    opts.set_method("GET");
+    opts.set_mode(RequestMode::Cors);
 
     let request = Request::new_with_str_and_init(url, &opts)
         .map_err(|e| NetworkError::RequestFailed(format!("{:?}", e)))?;
 
-    let window = web_sys::window()
-        .ok_or_else(|| NetworkError::RequestFailed("No window object".to_string()))?;
-
-    let resp_value = JsFuture::from(window.fetch_with_request(&request))
+    let resp_value = JsFuture::from(global_fetch(&request)?)
         .await
         .map_err(|e| NetworkError::RequestFailed(format!("{:?}", e)))?;
 
@@ -86,21 +133,86 @@ pub async fn fetch_bytes(url: &str) -> Result<Vec<u8>, NetworkError> {
     Ok(uint8_array.to_vec())
 }
 
+/// Fetch a beacon API URL as SSZ (`Accept: application/octet-stream`).
+///
+/// Fails with [`NetworkError::UnexpectedContentType`] if the server answers
+/// with anything else, typically JSON from a node without SSZ support.
+pub async fn fetch_ssz(url: &str) -> Result<Vec<u8>, NetworkError> {
+    let opts = RequestInit::new();
+    opts.set_method("GET");
+    opts.set_mode(RequestMode::Cors);
+
+    let headers = web_sys::Headers::new()
+        .map_err(|e| NetworkError::RequestFailed(format!("{:?}", e)))?;
+    headers
+        .set("Accept", SSZ_CONTENT_TYPE)
+        .map_err(|e| NetworkError::RequestFailed(format!("{:?}", e)))?;
+    opts.set_headers(&headers);
+
+    let request = Request::new_with_str_and_init(url, &opts)
+        .map_err(|e| NetworkError::RequestFailed(format!("{:?}", e)))?;
+
+    let resp_value = JsFuture::from(global_fetch(&request)?)
+        .await
+        .map_err(|e| NetworkError::RequestFailed(format!("{:?}", e)))?;
+
+    let resp: Response = resp_value
+        .dyn_into()
+        .map_err(|_| NetworkError::RequestFailed("Response is not a Response object".to_string()))?;
+
+    let status = resp.status();
+    if status != 200 {
+        return Err(NetworkError::HttpError(
+            status,
+            resp.status_text(),
+        ));
+    }
+
+    let content_type = resp.headers().get("Content-Type").ok().flatten().unwrap_or_default();
+    if !content_type.starts_with(SSZ_CONTENT_TYPE) {
+        return Err(NetworkError::UnexpectedContentType(content_type));
+    }
+
+    let array_buffer = JsFuture::from(
+        resp.array_buffer()
+            .map_err(|e| NetworkError::BodyReadFailed(format!("{:?}", e)))?,
+    )
+    .await
+    .map_err(|e| NetworkError::BodyReadFailed(format!("{:?}", e)))?;
+
+    Ok(js_sys::Uint8Array::new(&array_buffer).to_vec())
+}
+
+/// A beacon API response body in whichever encoding the node served.
+pub enum BeaconPayload {
+    Ssz(Vec<u8>),
+    Json(String),
+}
+
+/// Fetch a beacon API URL as SSZ, falling back to JSON when the node
+/// refuses or ignores the SSZ request. SSZ bodies are several times smaller:
+/// a bootstrap's 512 public keys are sent as raw bytes instead of hex.
+pub async fn fetch_beacon(url: &str) -> Result<BeaconPayload, NetworkError> {
+    match fetch_ssz(url).await {
+        Ok(bytes) => Ok(BeaconPayload::Ssz(bytes)),
+        // The node is unreachable; asking again for JSON will not help.
+        Err(e @ NetworkError::RequestFailed(_)) => Err(e),
+        Err(_) => fetch_text(url).await.map(BeaconPayload::Json),
+    }
+}
+
 /// Fetch a URL and return the response as a string.
 ///
 /// Same trust model as fetch_bytes — the response is untrusted.
 pub async fn fetch_text(url: &str) -> Result<String, NetworkError> {
-    let mut opts = RequestInit::new();
-    opts.method("GET");
-    opts.mode(RequestMode::Cors);
+    let opts = RequestInit::new();
+    opts.set_method("GET");
+    opts.set_mode(RequestMode::Cors);
 
     let request = Request::new_with_str_and_init(url, &opts)
         .map_err(|e| NetworkError::RequestFailed(format!("{:?}", e)))?;
 
-    let window = web_sys::window()
-        .ok_or_else(|| NetworkError::RequestFailed("No window object".to_string()))?;
-
-    let resp_value = JsFuture::from(window.fetch_with_request(&request))
+    let resp_value = JsFuture::from(global_fetch(&request)?)
         .await
         .map_err(|e| NetworkError::RequestFailed(format!("{:?}", e)))?;
 
@@ -133,25 +245,22 @@ pub async fn fetch_text(url: &str) -> Result<String, NetworkError> {
 /// The response is NEVER trusted for correctness — all data is verified
 /// against our cryptographic chain state.
 pub async fn post_json(url: &str, body: &str) -> Result<String, NetworkError> {
-    let mut opts = RequestInit::new();
-    opts.method("POST");
-    opts.mode(RequestMode::Cors);
-    opts.body(Some(&JsValue::from_str(body)));
+    let opts = RequestInit::new();
+    opts.set_method("POST");
+    opts.set_mode(RequestMode::Cors);
+    opts.set_body(&JsValue::from_str(body));
 
     let headers = web_sys::Headers::new()
         .map_err(|e| NetworkError::RequestFailed(format!("{:?}", e)))?;
     headers
         .set("Content-Type", "application/json")
         .map_err(|e| NetworkError::RequestFailed(format!("{:?}", e)))?;
-    opts.headers(&headers);
+    opts.set_headers(&headers);
 
     let request = Request::new_with_str_and_init(url, &opts)
         .map_err(|e| NetworkError::RequestFailed(format!("{:?}", e)))?;
 
-    let window = web_sys::window()
-        .ok_or_else(|| NetworkError::RequestFailed("No window object".to_string()))?;
-
-    let resp_value = JsFuture::from(window.fetch_with_request(&request))
+    let resp_value = JsFuture::from(global_fetch(&request)?)
         .await
         .map_err(|e| NetworkError::RequestFailed(format!("{:?}", e)))?;
 
@@ -169,3 +278,107 @@ pub async fn post_json(url: &str, body: &str) -> Result<String, NetworkError> {
     text.as_string()
         .ok_or_else(|| NetworkError::BodyReadFailed("Response text is not a string".to_string()))
 }
+
+/// The HTTP transport a `LumenClient` sends execution RPC requests through.
+///
+/// [`FetchProvider`], the Fetch API, is the default. Another transport can
+/// be injected with `LumenClient::set_network_provider` — an in-memory one
+/// for tests, or one for a runtime without `fetch`. Implementations move
+/// bytes and nothing else: every answer is verified exactly as before.
+pub trait NetworkProvider {
+    /// GET `url`, returning the body of a 200 response.
+    fn fetch_bytes<'a>(&'a self, url: &'a str) -> LocalBoxFuture<'a, Result<Vec<u8>, NetworkError>>;
+    /// POST a JSON `body` to `url`, returning the response text.
+    fn post_json<'a>(&'a self, url: &'a str, body: &'a str) -> LocalBoxFuture<'a, Result<String, NetworkError>>;
+}
+
+/// [`NetworkProvider`] over the Fetch API of the current global scope.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FetchProvider;
+
+impl NetworkProvider for FetchProvider {
+    fn fetch_bytes<'a>(&'a self, url: &'a str) -> LocalBoxFuture<'a, Result<Vec<u8>, NetworkError>> {
+        Box::pin(fetch_bytes(url))
+    }
+
+    fn post_json<'a>(&'a self, url: &'a str, body: &'a str) -> LocalBoxFuture<'a, Result<String, NetworkError>> {
+        Box::pin(post_json(url, body))
+    }
+}
+
+/// An in-memory [`NetworkProvider`]: canned JSON-RPC results by method and
+/// GET bodies by URL, with a log of every request made.
+#[cfg(test)]
+#[derive(Default)]
+pub struct MockNetwork {
+    results: Vec<(String, serde_json::Value)>,
+    bodies: Vec<(String, Vec<u8>)>,
+    /// `(method, params)` of every JSON-RPC request, in order.
+    pub calls: std::cell::RefCell<Vec<(String, serde_json::Value)>>,
+}
+
+#[cfg(test)]
+impl MockNetwork {
+    pub fn with_result(mut self, method: &str, result: serde_json::Value) -> Self {
+        self.results.push((method.to_string(), result));
+        self
+    }
+
+    pub fn with_body(mut self, url: &str, body: Vec<u8>) -> Self {
+        self.bodies.push((url.to_string(), body));
+        self
+    }
+}
+
+#[cfg(test)]
+impl NetworkProvider for std::rc::Rc<MockNetwork> {
+    fn fetch_bytes<'a>(&'a self, url: &'a str) -> LocalBoxFuture<'a, Result<Vec<u8>, NetworkError>> {
+        let body = self.bodies.iter().find(|(u, _)| u == url).map(|(_, body)| body.clone());
+        Box::pin(async move { body.ok_or(NetworkError::HttpError(404, "Not Found".to_string())) })
+    }
+
+    fn post_json<'a>(&'a self, _url: &'a str, body: &'a str) -> LocalBoxFuture<'a, Result<String, NetworkError>> {
+        let request: serde_json::Value = serde_json::from_str(body).unwrap_or_default();
+        let method = request["method"].as_str().unwrap_or_default().to_string();
+        self.calls.borrow_mut().push((method.clone(), request["params"].clone()));
+        let response = match self.results.iter().find(|(m, _)| *m == method) {
+            Some((_, result)) => serde_json::json!({"jsonrpc": "2.0", "id": 1, "result": result}),
+            None => serde_json::json!({"jsonrpc": "2.0", "id": 1, "error": {"code": -32601, "message": "not mocked"}}),
+        };
+        Box::pin(async move { Ok(response.to_string()) })
+    }
+}
+
+/// Make a JSON-RPC call and return its `result`, `None` when it is null.
+///
+/// JSON-RPC errors are reported as errors, so callers only ever see a value
+/// that still has to be verified.
+pub async fn rpc_call_optional(
+    transport: &dyn NetworkProvider,
+    url: &str,
+    method: &str,
+    params: serde_json::Value,
+) -> Result<Option<serde_json::Value>, String> {
+    let request = serde_json::json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": method,
+        "params": params,
+    });
+    let started = crate::metrics::now_ms();
+    let text = transport.post_json(url, &request.to_string()).await;
+    crate::metrics::with(|m| m.rpc_latency_ms.record(crate::metrics::now_ms() - started));
+    let text = text.map_err(|e| e.to_string())?;
+
+    let mut response: serde_json::Value =
+        serde_json::from_str(&text).map_err(|e| format!("{} JSON parse: {}", method, e))?;
+
+    if let Some(err) = response.get("error") {
+        return Err(format!("{} RPC error: {}", method, err));
+    }
+
+    match response.get_mut("result").map(serde_json::Value::take) {
+        Some(serde_json::Value::Null) | None => Ok(None),
+        Some(result) => Ok(Some(result)),
+    }
+}
@@ -0,0 +1,286 @@
+//! Checks on a signed transaction against verified state, before it is sent.
+//!
+//! Nodes reject a transaction with a used nonce, a cost its sender cannot
+//! pay or another chain's id, and a wallet that broadcasts it anyway only
+//! learns so from an RPC error, or not at all when the node drops it from
+//! its pool. `preflight` decodes the transaction, recovers its sender, and
+//! checks it against the sender's account proven at the finalized block and
+//! that block's verified header.
+//!
+//! The finalized block trails the chain head by a few minutes, so findings
+//! that later blocks can change — a nonce gap the sender's pending
+//! transactions fill, a base fee that has since moved — are warnings.
+//! Errors are transactions no node will accept.
+
+use crate::provider::DispatchError;
+use crate::LumenClient;
+use lumen_core::execution::proof::keccak256;
+use lumen_core::execution::transaction::{decode_transaction, recover_sender};
+use lumen_core::types::beacon::ExecutionPayloadHeader;
+use lumen_core::types::execution::{AccountState, Transaction};
+use lumen_core::types::uint::U256;
+use serde::Serialize;
+
+/// Gas every transaction pays before executing anything.
+pub const TX_BASE_GAS: u64 = 21_000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    /// No node will accept the transaction.
+    Error,
+    /// It may be accepted, but likely not as the sender expects.
+    Warning,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct PreflightIssue {
+    pub severity: Severity,
+    /// Stable identifier, e.g. `"nonce_too_low"`.
+    pub code: &'static str,
+    pub message: String,
+}
+
+impl PreflightIssue {
+    fn error(code: &'static str, message: String) -> Self {
+        Self { severity: Severity::Error, code, message }
+    }
+
+    fn warning(code: &'static str, message: String) -> Self {
+        Self { severity: Severity::Warning, code, message }
+    }
+}
+
+/// What `preflight` returns. Quantities are hex.
+#[derive(Debug, Serialize)]
+pub struct PreflightReport {
+    /// keccak256 of the envelope.
+    pub tx_hash: String,
+    /// Recovered from the signature.
+    pub sender: String,
+    pub nonce: u64,
+    /// The sender's proven nonce at `block_number`.
+    pub account_nonce: u64,
+    pub balance: String,
+    /// Gas limit times the fee cap, plus the value sent.
+    pub max_cost: Option<String>,
+    pub base_fee_per_gas: String,
+    pub block_number: u64,
+    /// True if no issue is an error.
+    pub ok: bool,
+    pub issues: Vec<PreflightIssue>,
+}
+
+/// The most `tx` can cost its sender: every unit of gas at the fee cap,
+/// plus the value. None if that overflows 256 bits.
+pub fn max_cost(tx: &Transaction) -> Option<U256> {
+    let fee_cap = tx.max_fee_per_gas.or(tx.gas_price).unwrap_or(U256::ZERO);
+    fee_cap.checked_mul(&U256::from_u64(tx.gas_limit))?.checked_add(&tx.value)
+}
+
+/// Everything wrong with sending `tx` on chain `chain_id` from `account`,
+/// judged at the block `header` describes.
+pub fn check(
+    tx: &Transaction,
+    chain_id: u64,
+    account: &AccountState,
+    header: &ExecutionPayloadHeader,
+) -> Vec<PreflightIssue> {
+    let mut issues = Vec::new();
+
+    match tx.chain_id {
+        Some(id) if id != chain_id => issues.push(PreflightIssue::error(
+            "wrong_chain",
+            format!("Signed for chain {}, but this client follows chain {}", id, chain_id),
+        )),
+        Some(_) => {}
+        None => issues.push(PreflightIssue::warning(
+            "no_replay_protection",
+            "Signed without a chain id (pre-EIP-155): it can be replayed on any chain, and many nodes refuse it"
+                .to_string(),
+        )),
+    }
+
+    if tx.nonce < account.nonce {
+        issues.push(PreflightIssue::error(
+            "nonce_too_low",
+            format!("Nonce {} is already used; the sender's next nonce is {}", tx.nonce, account.nonce),
+        ));
+    } else if tx.nonce > account.nonce {
+        issues.push(PreflightIssue::warning(
+            "nonce_gap",
+            format!(
+                "Nonce {} is ahead of the sender's next nonce {} at block #{}; it stays pending until \
+                 nonces {}..{} are mined",
+                tx.nonce,
+                account.nonce,
+                header.block_number,
+                account.nonce,
+                tx.nonce - 1
+            ),
+        ));
+    }
+
+    if tx.gas_limit < TX_BASE_GAS {
+        issues.push(PreflightIssue::error(
+            "gas_limit_too_low",
+            format!("Gas limit {} is below the {} every transaction costs", tx.gas_limit, TX_BASE_GAS),
+        ));
+    }
+    if tx.gas_limit > header.gas_limit {
+        issues.push(PreflightIssue::error(
+            "gas_limit_above_block",
+            format!("Gas limit {} exceeds the block gas limit {}", tx.gas_limit, header.gas_limit),
+        ));
+    }
+
+    if let (Some(priority), Some(max_fee)) = (tx.max_priority_fee_per_gas, tx.max_fee_per_gas) {
+        if priority > max_fee {
+            issues.push(PreflightIssue::error(
+                "priority_fee_above_max_fee",
+                format!(
+                    "Max priority fee {} is above the max fee {}",
+                    priority.to_dec_string(),
+                    max_fee.to_dec_string()
+                ),
+            ));
+        }
+    }
+    let fee_cap = tx.max_fee_per_gas.or(tx.gas_price).unwrap_or(U256::ZERO);
+    if fee_cap < header.base_fee_per_gas {
+        issues.push(PreflightIssue::warning(
+            "fee_below_base_fee",
+            format!(
+                "Fee cap {} wei is below block #{}'s base fee of {} wei; it waits until the base fee falls",
+                fee_cap.to_dec_string(),
+                header.block_number,
+                header.base_fee_per_gas.to_dec_string()
+            ),
+        ));
+    }
+
+    match max_cost(tx) {
+        Some(cost) if cost > account.balance => issues.push(PreflightIssue::error(
+            "insufficient_funds",
+            format!(
+                "Costs up to {} wei, but the sender holds {} wei at block #{}",
+                cost.to_dec_string(),
+                account.balance.to_dec_string(),
+                header.block_number
+            ),
+        )),
+        Some(_) => {}
+        None => issues.push(PreflightIssue::error(
+            "insufficient_funds",
+            "Gas limit times fee cap plus value overflows 256 bits".to_string(),
+        )),
+    }
+
+    issues
+}
+
+impl LumenClient {
+    /// Decode `raw_hex`, recover its sender, and check it against the
+    /// sender's account and the header of the finalized block.
+    pub(crate) async fn preflight_report(&self, raw_hex: &str) -> Result<PreflightReport, DispatchError> {
+        let raw = hex::decode(raw_hex.strip_prefix("0x").unwrap_or(raw_hex))
+            .map_err(|e| DispatchError::InvalidParams(format!("Invalid transaction hex: {}", e)))?;
+        let tx = decode_transaction(&raw).map_err(|e| DispatchError::InvalidParams(e.to_string()))?;
+        let sender = recover_sender(&raw).map_err(|e| DispatchError::InvalidParams(e.to_string()))?;
+        let sender = format!("0x{}", hex::encode(sender));
+
+        let header = self.verified_execution_header()?;
+        let fetched = self
+            .try_endpoints(|endpoint| {
+                self.fetch_proof_at(endpoint, &sender, &[], header.state_root, header.block_number)
+            })
+            .await?;
+        let issues = check(&tx, self.network.chain_id, &fetched.account, &header);
+
+        Ok(PreflightReport {
+            tx_hash: format!("0x{}", hex::encode(keccak256(&raw))),
+            sender,
+            nonce: tx.nonce,
+            account_nonce: fetched.account.nonce,
+            balance: fetched.account.balance.to_hex(),
+            max_cost: max_cost(&tx).map(|cost| cost.to_hex()),
+            base_fee_per_gas: header.base_fee_per_gas.to_hex(),
+            block_number: header.block_number,
+            ok: issues.iter().all(|issue| issue.severity != Severity::Error),
+            issues,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lumen_core::types::execution::TransactionType;
+
+    fn transfer(nonce: u64, max_fee: u64, value: u64) -> Transaction {
+        Transaction {
+            tx_type: TransactionType::DynamicFee,
+            chain_id: Some(1),
+            nonce,
+            gas_price: None,
+            max_fee_per_gas: Some(U256::from_u64(max_fee)),
+            max_priority_fee_per_gas: Some(U256::from_u64(1)),
+            gas_limit: 21_000,
+            to: Some([0x11; 20]),
+            value: U256::from_u64(value),
+            input: Vec::new(),
+        }
+    }
+
+    fn account(nonce: u64, balance: u64) -> AccountState {
+        AccountState {
+            nonce,
+            balance: U256::from_u64(balance),
+            storage_root: AccountState::EMPTY_STORAGE_ROOT,
+            code_hash: AccountState::EMPTY_CODE_HASH,
+        }
+    }
+
+    fn header(base_fee: u64) -> ExecutionPayloadHeader {
+        let mut header = crate::tests::state([0; 32]).latest_execution_payload_header.unwrap();
+        header.base_fee_per_gas = U256::from_u64(base_fee);
+        header
+    }
+
+    fn codes(issues: &[PreflightIssue]) -> Vec<&'static str> {
+        issues.iter().map(|issue| issue.code).collect()
+    }
+
+    #[test]
+    fn test_affordable_transfer_passes() {
+        let tx = transfer(5, 10, 1_000);
+        assert_eq!(max_cost(&tx), Some(U256::from_u64(211_000)));
+        assert!(check(&tx, 1, &account(5, 211_000), &header(7)).is_empty());
+    }
+
+    #[test]
+    fn test_doomed_transactions_are_flagged() {
+        let issues = check(&transfer(4, 10, 1_000), 11_155_111, &account(5, 211_000), &header(7));
+        assert_eq!(codes(&issues), vec!["wrong_chain", "nonce_too_low"]);
+        assert!(issues.iter().all(|issue| issue.severity == Severity::Error));
+
+        let issues = check(&transfer(5, 10, 1_000), 1, &account(5, 210_999), &header(7));
+        assert_eq!(codes(&issues), vec!["insufficient_funds"]);
+
+        let mut tx = transfer(5, 10, 0);
+        tx.gas_limit = 20_000;
+        tx.max_priority_fee_per_gas = Some(U256::from_u64(11));
+        let issues = check(&tx, 1, &account(5, u64::MAX), &header(7));
+        assert_eq!(codes(&issues), vec!["gas_limit_too_low", "priority_fee_above_max_fee"]);
+    }
+
+    #[test]
+    fn test_conditions_later_blocks_can_change_are_warnings() {
+        let mut tx = transfer(8, 5, 0);
+        tx.chain_id = None;
+        let issues = check(&tx, 1, &account(5, u64::MAX), &header(7));
+        assert_eq!(codes(&issues), vec!["no_replay_protection", "nonce_gap", "fee_below_base_fee"]);
+        assert!(issues.iter().all(|issue| issue.severity == Severity::Warning));
+        assert!(issues[1].message.contains("nonces 5..7"));
+    }
+}
@@ -11,13 +11,21 @@
 //!
 //! NEVER return unverified data. If verification fails, return an error.
 
+use crate::error::{ErrorCode, LumenError};
+use crate::logging::lumen_log;
+use crate::LumenClient;
+use lumen_core::execution::header::{verify_header_chain, ExecutionBlockHeader};
+use lumen_core::execution::proof::keccak256;
+use lumen_core::types::beacon::ExecutionPayloadHeader;
+use lumen_core::types::uint::U256;
 use serde::{Deserialize, Serialize};
-use wasm_bindgen::prelude::*;
+use wasm_bindgen::{JsCast, JsValue};
 
 /// EIP-1193 JSON-RPC request.
 #[derive(Serialize, Deserialize, Debug)]
 pub struct JsonRpcRequest {
     pub method: String,
+    #[serde(default)]
     pub params: Vec<serde_json::Value>,
     #[serde(default)]
     pub id: serde_json::Value,
@@ -31,9 +39,86 @@ pub struct JsonRpcResponse {
     pub result: Option<serde_json::Value>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub error: Option<JsonRpcError>,
+    /// Lumen's verification metadata. Not part of JSON-RPC; EIP-1193
+    /// consumers ignore it, the TypeScript layer reads it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lumen: Option<ResponseMeta>,
+}
+
+/// How a dispatched result was obtained.
+///
+/// Every successful response carries one, with every key present (`null`
+/// when it does not apply), so an auditor can assert on each result that
+/// nothing unverified passed through unmarked.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ResponseMeta {
+    /// True if the result was verified locally; false for trusted methods.
+    pub verified: bool,
+    /// What the result was checked against.
+    pub path: VerificationPath,
+    /// The client's finalized beacon slot when the result was produced.
+    #[serde(default)]
+    pub finalized_slot: Option<u64>,
+    /// The execution block the result was verified at, when block-specific.
+    #[serde(default)]
+    pub block_number: Option<u64>,
+    /// Merkle-Patricia trie nodes hashed to verify the result, when proof-backed.
+    #[serde(default)]
+    pub proof_nodes: Option<usize>,
+    /// True if part of the result is a heuristic rather than verified data:
+    /// the priority fee in `eth_gasPrice` and `eth_feeHistory` rewards.
+    #[serde(default)]
+    pub heuristic: bool,
+}
+
+impl ResponseMeta {
+    pub fn new(path: VerificationPath) -> Self {
+        Self {
+            verified: path != VerificationPath::Unverified,
+            path,
+            finalized_slot: None,
+            block_number: None,
+            proof_nodes: None,
+            heuristic: false,
+        }
+    }
+
+    pub fn at_block(self, block_number: u64) -> Self {
+        Self { block_number: Some(block_number), ..self }
+    }
+
+    pub fn with_proof_nodes(self, proof_nodes: usize) -> Self {
+        Self { proof_nodes: Some(proof_nodes), ..self }
+    }
+
+    pub fn heuristic(self, heuristic: bool) -> Self {
+        Self { heuristic, ..self }
+    }
 }
 
-/// JSON-RPC error object.
+/// The check a dispatched result passed.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum VerificationPath {
+    /// Answered from the client's own configuration, without an RPC.
+    Local,
+    /// Read from the BLS-verified execution payload header.
+    FinalizedHeader,
+    /// A header hashed back along parent hashes from a BLS-verified block.
+    HeaderChain,
+    /// An account proof against a verified state root; for eth_getCode, the
+    /// code is also checked against the proven code hash.
+    AccountProof,
+    /// A storage proof under a proven account's storage root.
+    StorageProof,
+    /// A transaction hash computed locally from the signed envelope.
+    TransactionHash,
+    /// Forwarded to an RPC and returned as-is.
+    Unverified,
+}
+
+/// JSON-RPC error object. Also the EIP-1193 `ProviderRpcError` shape that
+/// `request` rejects with: `{ code, message, data? }`.
 #[derive(Serialize, Deserialize, Debug)]
 pub struct JsonRpcError {
     pub code: i64,
@@ -42,19 +127,57 @@ pub struct JsonRpcError {
     pub data: Option<serde_json::Value>,
 }
 
+impl From<JsonRpcError> for JsValue {
+    fn from(error: JsonRpcError) -> Self {
+        let js_error = js_sys::Error::new(&error.message);
+        js_error.set_name("ProviderRpcError");
+        // Plain data; serializing it cannot fail.
+        if let Ok(props) = serde_json::json!({"code": error.code, "data": error.data})
+            .serialize(&serde_wasm_bindgen::Serializer::json_compatible())
+        {
+            js_sys::Object::assign(&js_error, &props.unchecked_into());
+        }
+        js_error.into()
+    }
+}
+
+/// EIP-1193: the user rejected the request.
+pub const USER_REJECTED: i64 = 4001;
+
+/// EIP-1193: the provider does not support the requested method.
+pub const UNSUPPORTED_METHOD: i64 = 4200;
+
+/// EIP-1193: the provider is disconnected from all chains — for Lumen, no
+/// execution RPC is configured to fetch proofs from.
+pub const DISCONNECTED: i64 = 4900;
+
+/// EIP-1193: the provider is not connected to the requested chain — for
+/// Lumen, no execution payload has been BLS-verified yet.
+pub const CHAIN_DISCONNECTED: i64 = 4901;
+
 /// Methods that Lumen fully supports with cryptographic verification.
 pub const VERIFIED_METHODS: &[&str] = &[
     "eth_blockNumber",
+    "eth_getBlockByNumber",
     "eth_getBalance",
     "eth_getCode",
     "eth_getStorageAt",
     "eth_getTransactionCount",
     "eth_sendRawTransaction",
+    "eth_gasPrice",
+    "eth_feeHistory",
     "eth_chainId",
     "net_version",
+    "lumen_resolveName",
 ];
 
 /// Methods that require trusted execution (documented clearly).
+///
+/// `eth_estimateGas` stays here alongside `eth_call`: estimating gas means
+/// executing the call, and there is no local EVM in this crate yet. Both
+/// move to `VERIFIED_METHODS` once calls run against proof-backed state.
+/// The same applies to `eth_call` state overrides: they are forwarded to the
+/// fallback RPC as-is, since layering them over verified state needs that EVM.
 pub const TRUSTED_METHODS: &[&str] = &[
     "eth_call",
     "eth_estimateGas",
@@ -67,6 +190,10 @@ pub const INFO_METHODS: &[&str] = &[
     "web3_clientVersion",
 ];
 
+/// Account methods. Lumen holds no keys, so a request to connect is
+/// answered as a refusal, as a wallet would if the user declined.
+pub const ACCOUNT_METHODS: &[&str] = &["eth_requestAccounts"];
+
 /// Check if a method is supported.
 pub fn is_method_supported(method: &str) -> bool {
     VERIFIED_METHODS.contains(&method)
@@ -74,24 +201,29 @@ pub fn is_method_supported(method: &str) -> bool {
         || INFO_METHODS.contains(&method)
 }
 
-/// Check if a method returns verified data.
-pub fn is_method_verified(method: &str) -> bool {
-    VERIFIED_METHODS.contains(&method)
-}
-
 /// Create an error response for unsupported methods.
 pub fn method_not_supported(id: serde_json::Value, method: &str) -> JsonRpcResponse {
     JsonRpcResponse {
         id,
         result: None,
         error: Some(JsonRpcError {
-            code: -32601,
+            code: UNSUPPORTED_METHOD,
             message: format!("Method {} is not supported by Lumen", method),
             data: None,
         }),
+        lumen: None,
     }
 }
 
+/// Create an error response refusing an account request.
+pub fn user_rejected(id: serde_json::Value, method: &str) -> JsonRpcResponse {
+    error_response(
+        id,
+        USER_REJECTED,
+        format!("{} rejected: Lumen is a read-only light client with no accounts", method),
+    )
+}
+
 /// Create an error response for verification failures.
 pub fn verification_failed(id: serde_json::Value, reason: &str) -> JsonRpcResponse {
     JsonRpcResponse {
@@ -105,6 +237,7 @@ pub fn verification_failed(id: serde_json::Value, reason: &str) -> JsonRpcRespon
             ),
             data: None,
         }),
+        lumen: None,
     }
 }
 
@@ -114,19 +247,21 @@ pub fn success_response(id: serde_json::Value, result: serde_json::Value) -> Jso
         id,
         result: Some(result),
         error: None,
+        lumen: None,
     }
 }
 
 /// Handle informational methods that don't require network or verification.
-pub fn handle_info_method(request: &JsonRpcRequest) -> Option<JsonRpcResponse> {
+/// `chain_id` is that of the network the client was configured for.
+pub fn handle_info_method(request: &JsonRpcRequest, chain_id: u64) -> Option<JsonRpcResponse> {
     match request.method.as_str() {
         "eth_chainId" => Some(success_response(
             request.id.clone(),
-            serde_json::Value::String("0x1".to_string()), // Mainnet
+            serde_json::Value::String(to_quantity(chain_id)),
         )),
         "net_version" => Some(success_response(
             request.id.clone(),
-            serde_json::Value::String("1".to_string()),
+            serde_json::Value::String(chain_id.to_string()),
         )),
         "web3_clientVersion" => Some(success_response(
             request.id.clone(),
@@ -135,3 +270,968 @@ pub fn handle_info_method(request: &JsonRpcRequest) -> Option<JsonRpcResponse> {
         _ => None,
     }
 }
+
+/// JSON-RPC error code for an unparseable request.
+pub const PARSE_ERROR: i64 = -32700;
+
+/// JSON-RPC error code for missing or malformed parameters.
+pub const INVALID_PARAMS: i64 = -32602;
+
+/// EIP-1474 "limit exceeded": the outbound RPC queue is full.
+pub const LIMIT_EXCEEDED: i64 = -32005;
+
+/// Create an error response with an arbitrary code.
+pub fn error_response(id: serde_json::Value, code: i64, message: String) -> JsonRpcResponse {
+    JsonRpcResponse {
+        id,
+        result: None,
+        error: Some(JsonRpcError {
+            code,
+            message,
+            data: None,
+        }),
+        lumen: None,
+    }
+}
+
+/// Parse a `handle_request` argument; the error is for a [`PARSE_ERROR`] response.
+pub fn parse_request_json(request_json: &str) -> Result<JsonRpcRequest, String> {
+    serde_json::from_str(request_json).map_err(|e| format!("Invalid JSON-RPC request: {}", e))
+}
+
+/// Parse the `{ method, params }` argument of an EIP-1193 `request`.
+pub fn parse_request_args(args: JsValue) -> Result<JsonRpcRequest, JsValue> {
+    serde_wasm_bindgen::from_value(args).map_err(|e| {
+        JsonRpcError {
+            code: INVALID_PARAMS,
+            message: format!("Invalid request arguments: {}", e),
+            data: None,
+        }
+        .into()
+    })
+}
+
+/// Resolve an EIP-1193 `request` from a dispatched response: its result,
+/// or its error as a thrown `ProviderRpcError`.
+pub fn settle(response: JsonRpcResponse) -> Result<JsValue, JsValue> {
+    if let Some(error) = response.error {
+        return Err(error.into());
+    }
+    response
+        .result
+        .unwrap_or_default()
+        .serialize(&serde_wasm_bindgen::Serializer::json_compatible())
+        .map_err(|e| LumenError::serialization(e).into())
+}
+
+/// Parse a 0x-prefixed 20-byte address.
+pub fn parse_address(s: &str) -> Result<[u8; 20], String> {
+    let bytes = hex::decode(s.strip_prefix("0x").unwrap_or(s))
+        .map_err(|e| format!("Invalid address: {}", e))?;
+    bytes
+        .try_into()
+        .map_err(|b: Vec<u8>| format!("Address must be 20 bytes, got {}", b.len()))
+}
+
+/// Parse a JSON-RPC hex quantity ("0x1b4") into a u64.
+pub fn parse_quantity(s: &str) -> Result<u64, String> {
+    let digits = s
+        .strip_prefix("0x")
+        .ok_or_else(|| format!("Quantity {:?} is missing the 0x prefix", s))?;
+    u64::from_str_radix(digits, 16).map_err(|e| format!("Invalid quantity {:?}: {}", s, e))
+}
+
+/// Parse a storage slot: any hex value up to 32 bytes, left-padded.
+pub fn parse_slot(s: &str) -> Result<[u8; 32], String> {
+    U256::from_hex_str(s)
+        .map(|slot| slot.to_be_bytes())
+        .map_err(|e| format!("Invalid storage slot: {}", e))
+}
+
+/// Format a u64 as a JSON-RPC hex quantity.
+pub fn to_quantity(value: u64) -> String {
+    format!("0x{:x}", value)
+}
+
+/// How far back from a verified block parent hashes are walked to reach an
+/// older one. Each step is one header fetch; 256 matches the window the
+/// BLOCKHASH opcode exposes.
+pub const MAX_HEADER_ANCESTRY: u64 = 256;
+
+/// Which block account and storage proofs are requested at.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ProofMode {
+    /// The RPC's latest block, whose state root the RPC reports. Fresher,
+    /// but that root is only checked to be at or after the finalized block.
+    #[default]
+    Latest,
+    /// The BLS-verified finalized block, checked against our own state root.
+    /// "latest" cannot advance between fetching the root and the proof.
+    Finalized,
+}
+
+impl std::str::FromStr for ProofMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "latest" => Ok(ProofMode::Latest),
+            "finalized" => Ok(ProofMode::Finalized),
+            other => Err(format!("Unknown proof mode {:?} (expected \"latest\" or \"finalized\")", other)),
+        }
+    }
+}
+
+/// Resolve a block parameter to a block number no later than `finalized`.
+/// "latest" and "safe" mean the finalized block: nothing newer is verified.
+fn resolve_block(tag: &str, finalized: u64) -> Result<u64, DispatchError> {
+    let number = match tag {
+        "latest" | "safe" | "finalized" => finalized,
+        tag if tag.starts_with("0x") => parse_quantity(tag).map_err(DispatchError::InvalidParams)?,
+        tag => {
+            return Err(DispatchError::InvalidParams(format!(
+                "block tag {:?} cannot be verified; use a number or \"finalized\"",
+                tag
+            )))
+        }
+    };
+    if number > finalized {
+        return Err(DispatchError::Verification(format!(
+            "block {} is not finalized yet (finalized: {})",
+            number, finalized
+        )));
+    }
+    Ok(number)
+}
+
+/// Render a verified header in the standard eth_getBlockByNumber shape.
+///
+/// Transactions are omitted: the header commits to them only through
+/// `transactionsRoot`, and they are not fetched or checked here.
+pub fn header_to_json(header: &ExecutionBlockHeader, hash: [u8; 32]) -> serde_json::Value {
+    let hex = |bytes: &[u8]| format!("0x{}", hex::encode(bytes));
+    let mut block = serde_json::json!({
+        "number": to_quantity(header.number),
+        "hash": hex(&hash),
+        "parentHash": hex(&header.parent_hash),
+        "sha3Uncles": hex(&header.ommers_hash),
+        "miner": hex(&header.beneficiary),
+        "stateRoot": hex(&header.state_root),
+        "transactionsRoot": hex(&header.transactions_root),
+        "receiptsRoot": hex(&header.receipts_root),
+        "logsBloom": hex(&header.logs_bloom),
+        "difficulty": header.difficulty.to_hex(),
+        "gasLimit": to_quantity(header.gas_limit),
+        "gasUsed": to_quantity(header.gas_used),
+        "timestamp": to_quantity(header.timestamp),
+        "extraData": hex(&header.extra_data),
+        "mixHash": hex(&header.mix_hash),
+        "nonce": hex(&header.nonce),
+    });
+    let optional = [
+        ("baseFeePerGas", header.base_fee_per_gas.map(|v| v.to_hex())),
+        ("withdrawalsRoot", header.withdrawals_root.map(|r| hex(&r))),
+        ("blobGasUsed", header.blob_gas_used.map(to_quantity)),
+        ("excessBlobGas", header.excess_blob_gas.map(to_quantity)),
+        ("parentBeaconBlockRoot", header.parent_beacon_block_root.map(|r| hex(&r))),
+        ("requestsHash", header.requests_hash.map(|r| hex(&r))),
+    ];
+    for (key, value) in optional {
+        if let Some(value) = value {
+            block[key] = value.into();
+        }
+    }
+    block
+}
+
+/// Rebuild a header from an eth_getBlockByHash / eth_getBlockByNumber result,
+/// the inverse of [`header_to_json`].
+///
+/// Only the header fields are read; the returned header is unverified until
+/// its hash is checked against a trusted one.
+pub fn header_from_json(block: &serde_json::Value) -> Result<ExecutionBlockHeader, String> {
+    let field = |key: &str| block.get(key).and_then(|v| v.as_str());
+    let required = |key: &str| field(key).ok_or_else(|| format!("Block is missing {}", key));
+    let bytes = |key: &str| -> Result<Vec<u8>, String> {
+        let value = required(key)?;
+        hex::decode(value.strip_prefix("0x").unwrap_or(value)).map_err(|e| format!("Invalid {}: {}", key, e))
+    };
+    let fixed = |key: &str| -> Result<[u8; 32], String> {
+        bytes(key)?.try_into().map_err(|b: Vec<u8>| format!("{} should be 32 bytes, got {}", key, b.len()))
+    };
+    let quantity = |key: &str| required(key).and_then(parse_quantity);
+    let optional = |key: &str| field(key).is_some();
+
+    Ok(ExecutionBlockHeader {
+        parent_hash: fixed("parentHash")?,
+        ommers_hash: fixed("sha3Uncles")?,
+        beneficiary: parse_address(required("miner")?)?,
+        state_root: fixed("stateRoot")?,
+        transactions_root: fixed("transactionsRoot")?,
+        receipts_root: fixed("receiptsRoot")?,
+        logs_bloom: bytes("logsBloom")?
+            .try_into()
+            .map_err(|b: Vec<u8>| format!("logsBloom should be 256 bytes, got {}", b.len()))?,
+        difficulty: U256::from_hex_str(required("difficulty")?)?,
+        number: quantity("number")?,
+        gas_limit: quantity("gasLimit")?,
+        gas_used: quantity("gasUsed")?,
+        timestamp: quantity("timestamp")?,
+        extra_data: bytes("extraData")?,
+        mix_hash: fixed("mixHash")?,
+        nonce: bytes("nonce")?
+            .try_into()
+            .map_err(|b: Vec<u8>| format!("nonce should be 8 bytes, got {}", b.len()))?,
+        base_fee_per_gas: field("baseFeePerGas").map(U256::from_hex_str).transpose()?,
+        withdrawals_root: optional("withdrawalsRoot").then(|| fixed("withdrawalsRoot")).transpose()?,
+        blob_gas_used: optional("blobGasUsed").then(|| quantity("blobGasUsed")).transpose()?,
+        excess_blob_gas: optional("excessBlobGas").then(|| quantity("excessBlobGas")).transpose()?,
+        parent_beacon_block_root: optional("parentBeaconBlockRoot")
+            .then(|| fixed("parentBeaconBlockRoot"))
+            .transpose()?,
+        requests_hash: optional("requestsHash").then(|| fixed("requestsHash")).transpose()?,
+    })
+}
+
+/// Result of sending one raw transaction to every configured endpoint.
+#[derive(Serialize, Debug, Default, PartialEq, Eq)]
+pub struct BroadcastOutcome {
+    /// keccak256 of the signed envelope — computed locally, not taken from any RPC.
+    pub tx_hash: String,
+    /// Endpoints that accepted the transaction (or already had it).
+    pub accepted: Vec<String>,
+    /// Endpoints that rejected it or answered with the wrong hash.
+    pub rejected: Vec<BroadcastRejection>,
+}
+
+#[derive(Serialize, Debug, PartialEq, Eq)]
+pub struct BroadcastRejection {
+    pub endpoint: String,
+    pub error: String,
+}
+
+/// Node errors meaning the transaction is already in that node's pool,
+/// which for a broadcast is as good as accepting it.
+const ALREADY_KNOWN_ERRORS: &[&str] = &["already known", "known transaction"];
+
+/// Fold per-endpoint eth_sendRawTransaction results into one outcome.
+///
+/// The transaction hash is keccak256 of the signed envelope, so every
+/// endpoint's answer is checked against `expected_hash`; an endpoint that
+/// returns anything else is counted as rejecting the transaction.
+pub fn summarize_broadcast(
+    expected_hash: &str,
+    results: Vec<(String, Result<serde_json::Value, String>)>,
+) -> BroadcastOutcome {
+    let mut outcome = BroadcastOutcome {
+        tx_hash: expected_hash.to_string(),
+        ..Default::default()
+    };
+    for (endpoint, result) in results {
+        let error = match result {
+            Ok(value) => match value.as_str() {
+                Some(hash) if hash.eq_ignore_ascii_case(expected_hash) => None,
+                _ => Some(format!("returned {} but the transaction hash is {}", value, expected_hash)),
+            },
+            Err(e) => {
+                let lower = e.to_ascii_lowercase();
+                if ALREADY_KNOWN_ERRORS.iter().any(|known| lower.contains(known)) {
+                    None
+                } else {
+                    Some(e)
+                }
+            }
+        };
+        match error {
+            None => outcome.accepted.push(endpoint),
+            Some(error) => outcome.rejected.push(BroadcastRejection { endpoint, error }),
+        }
+    }
+    outcome
+}
+
+/// Render a BLS-verified execution payload header as a newHeads result.
+///
+/// The payload header's `transactions_root` and `withdrawals_root` are SSZ
+/// roots, not the MPT roots of the block header, so they are left out.
+pub fn payload_header_to_json(header: &ExecutionPayloadHeader) -> serde_json::Value {
+    let hex = |bytes: &[u8]| format!("0x{}", hex::encode(bytes));
+    serde_json::json!({
+        "number": to_quantity(header.block_number),
+        "hash": hex(&header.block_hash),
+        "parentHash": hex(&header.parent_hash),
+        "miner": hex(&header.fee_recipient),
+        "stateRoot": hex(&header.state_root),
+        "receiptsRoot": hex(&header.receipts_root),
+        "gasLimit": to_quantity(header.gas_limit),
+        "gasUsed": to_quantity(header.gas_used),
+        "timestamp": to_quantity(header.timestamp),
+        "baseFeePerGas": header.base_fee_per_gas.to_hex(),
+        "blobGasUsed": to_quantity(header.blob_gas_used),
+        "excessBlobGas": to_quantity(header.excess_blob_gas),
+    })
+}
+
+const NO_ENDPOINTS: &str = "no RPC endpoints configured; call set_rpc_endpoints first";
+
+/// Why a request could not be answered.
+pub(crate) enum DispatchError {
+    /// The request itself is malformed.
+    InvalidParams(String),
+    /// Data could not be fetched or failed verification.
+    Verification(String),
+    /// No execution RPC is configured.
+    Disconnected(String),
+    /// Nothing about the chain has been verified yet.
+    ChainDisconnected(String),
+    /// The rate limiter refused to queue another RPC request.
+    RateLimited(String),
+}
+
+impl std::fmt::Display for DispatchError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DispatchError::InvalidParams(message)
+            | DispatchError::Verification(message)
+            | DispatchError::Disconnected(message)
+            | DispatchError::ChainDisconnected(message)
+            | DispatchError::RateLimited(message) => f.write_str(message),
+        }
+    }
+}
+
+impl From<DispatchError> for LumenError {
+    fn from(error: DispatchError) -> Self {
+        match error {
+            DispatchError::InvalidParams(message) => LumenError::new(ErrorCode::InvalidInput, message),
+            DispatchError::Verification(message) => LumenError::new(ErrorCode::RequestFailed, message),
+            DispatchError::Disconnected(message) => LumenError::new(ErrorCode::NoEndpoints, message),
+            DispatchError::ChainDisconnected(message) => LumenError::new(ErrorCode::NotSynced, message),
+            DispatchError::RateLimited(message) => LumenError::new(ErrorCode::RateLimited, message),
+        }
+    }
+}
+
+impl From<String> for DispatchError {
+    fn from(reason: String) -> Self {
+        if crate::rate_limit::is_rate_limited(&reason) {
+            DispatchError::RateLimited(reason)
+        } else {
+            DispatchError::Verification(reason)
+        }
+    }
+}
+
+fn param_str(request: &JsonRpcRequest, index: usize) -> Result<&str, DispatchError> {
+    request
+        .params
+        .get(index)
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| {
+            DispatchError::InvalidParams(format!(
+                "{} expects a string at params[{}]",
+                request.method, index
+            ))
+        })
+}
+
+impl LumenClient {
+    /// Route a JSON-RPC request through the matching fetch-and-verify pipeline.
+    ///
+    /// Verified methods only return data that passed local verification;
+    /// trusted methods are forwarded to the RPC with a console warning.
+    pub(crate) async fn dispatch(&self, request: &JsonRpcRequest) -> JsonRpcResponse {
+        let id = request.id.clone();
+
+        if let Some(response) = handle_info_method(request, self.network.chain_id) {
+            return self.with_meta(response, ResponseMeta::new(VerificationPath::Local));
+        }
+        if ACCOUNT_METHODS.contains(&request.method.as_str()) {
+            return user_rejected(id, &request.method);
+        }
+        if !is_method_supported(&request.method) {
+            return method_not_supported(id, &request.method);
+        }
+        // Only the block number and gas price are answered without an RPC.
+        let local = matches!(request.method.as_str(), "eth_blockNumber" | "eth_gasPrice");
+        if self.rpc_endpoints.is_empty() && !local {
+            return error_response(id, DISCONNECTED, NO_ENDPOINTS.to_string());
+        }
+
+        match self.dispatch_method(request).await {
+            Ok((result, meta)) => self.with_meta(success_response(id, result), meta),
+            Err(DispatchError::InvalidParams(message)) => error_response(id, INVALID_PARAMS, message),
+            Err(DispatchError::Verification(reason)) => verification_failed(id, &reason),
+            Err(DispatchError::Disconnected(message)) => error_response(id, DISCONNECTED, message),
+            Err(DispatchError::ChainDisconnected(message)) => error_response(id, CHAIN_DISCONNECTED, message),
+            Err(DispatchError::RateLimited(message)) => error_response(id, LIMIT_EXCEEDED, message),
+        }
+    }
+
+    /// Attach `meta` to a successful response, stamped with the finalized slot.
+    fn with_meta(&self, response: JsonRpcResponse, meta: ResponseMeta) -> JsonRpcResponse {
+        JsonRpcResponse {
+            lumen: Some(ResponseMeta { finalized_slot: Some(self.head_slot()), ..meta }),
+            ..response
+        }
+    }
+
+    async fn dispatch_method(
+        &self,
+        request: &JsonRpcRequest,
+    ) -> Result<(serde_json::Value, ResponseMeta), DispatchError> {
+        let method = request.method.as_str();
+        match method {
+            "eth_blockNumber" => {
+                let header = self.verified_execution_header()?;
+                Ok((
+                    to_quantity(header.block_number).into(),
+                    ResponseMeta::new(VerificationPath::FinalizedHeader).at_block(header.block_number),
+                ))
+            }
+            "eth_gasPrice" => {
+                let header = self.verified_execution_header()?;
+                Ok((
+                    crate::fees::gas_price(&header).to_hex().into(),
+                    ResponseMeta::new(VerificationPath::FinalizedHeader)
+                        .at_block(header.block_number)
+                        .heuristic(true),
+                ))
+            }
+            "eth_feeHistory" => {
+                let count =
+                    crate::fees::parse_block_count(request.params.first()).map_err(DispatchError::InvalidParams)?;
+                let finalized = self.verified_execution_header()?.block_number;
+                let newest = resolve_block(param_str(request, 1)?, finalized)?;
+                let percentiles =
+                    crate::fees::parse_percentiles(request.params.get(2)).map_err(DispatchError::InvalidParams)?;
+
+                let blocks = self.fee_history_blocks(newest, count).await?;
+                Ok((
+                    crate::fees::fee_history(&blocks, &percentiles),
+                    ResponseMeta::new(VerificationPath::HeaderChain)
+                        .at_block(newest)
+                        .heuristic(!percentiles.is_empty()),
+                ))
+            }
+            "eth_getBlockByNumber" => {
+                let finalized = self.verified_execution_header()?.block_number;
+                let number = resolve_block(param_str(request, 0)?, finalized)?;
+                if request.params.get(1).and_then(|v| v.as_bool()) == Some(true) {
+                    return Err(DispatchError::InvalidParams(
+                        "full transaction objects cannot be verified; pass false".to_string(),
+                    ));
+                }
+                if finalized - number > MAX_HEADER_ANCESTRY {
+                    return Err(DispatchError::Verification(format!(
+                        "block {} is more than {} blocks behind the finalized block {}",
+                        number, MAX_HEADER_ANCESTRY, finalized
+                    )));
+                }
+
+                let (header, hash) = self
+                    .try_endpoints(|endpoint| self.fetch_verified_header(endpoint, number))
+                    .await?;
+                Ok((header_to_json(&header, hash), ResponseMeta::new(VerificationPath::HeaderChain).at_block(number)))
+            }
+            "eth_getBalance" | "eth_getTransactionCount" => {
+                // Served at the BLS-finalized block, whose state root we hold
+                // ourselves — no race against the RPC's idea of "latest" — or
+                // at an older block whose header is verified back from it.
+                let address = param_str(request, 0)?;
+                parse_address(address).map_err(DispatchError::InvalidParams)?;
+                let block = self.requested_block(request, 1)?;
+
+                let fetched = self
+                    .try_endpoints(|endpoint| self.verified_account_at(endpoint, address, &[], block))
+                    .await?;
+                let result = if method == "eth_getBalance" {
+                    fetched.account.balance.to_hex()
+                } else {
+                    to_quantity(fetched.account.nonce)
+                };
+                Ok((
+                    result.into(),
+                    ResponseMeta::new(VerificationPath::AccountProof)
+                        .at_block(block)
+                        .with_proof_nodes(fetched.proof_nodes),
+                ))
+            }
+            "eth_getCode" => {
+                let address = param_str(request, 0)?;
+                parse_address(address).map_err(DispatchError::InvalidParams)?;
+                let block = self.requested_block(request, 1)?;
+
+                let (fetched, code) = self
+                    .try_endpoints(|endpoint| self.fetch_account_and_code(endpoint, address, block, true))
+                    .await?;
+                Ok((
+                    format!("0x{}", hex::encode(code.unwrap_or_default())).into(),
+                    ResponseMeta::new(VerificationPath::AccountProof)
+                        .at_block(fetched.block_number)
+                        .with_proof_nodes(fetched.proof_nodes),
+                ))
+            }
+            "eth_sendRawTransaction" => {
+                let outcome = self.broadcast_raw_transaction(param_str(request, 0)?).await?;
+                Ok((outcome.tx_hash.into(), ResponseMeta::new(VerificationPath::TransactionHash)))
+            }
+            "eth_getStorageAt" => {
+                let address = param_str(request, 0)?;
+                parse_address(address).map_err(DispatchError::InvalidParams)?;
+                let slot = parse_slot(param_str(request, 1)?).map_err(DispatchError::InvalidParams)?;
+                let block = self.requested_block(request, 2)?;
+
+                let (fetched, value) = self.verified_storage_at(address, slot, block).await?;
+                Ok((
+                    format!("0x{}", hex::encode(value.to_be_bytes())).into(),
+                    ResponseMeta::new(VerificationPath::StorageProof)
+                        .at_block(fetched.block_number)
+                        .with_proof_nodes(fetched.proof_nodes + fetched.storage_proof_nodes),
+                ))
+            }
+            "lumen_resolveName" => {
+                let resolution = self.resolve_ens_name(param_str(request, 0)?).await?;
+                Ok((
+                    resolution.address.into(),
+                    ResponseMeta::new(VerificationPath::StorageProof)
+                        .at_block(resolution.block_number)
+                        .with_proof_nodes(resolution.proof_nodes),
+                ))
+            }
+            _ if TRUSTED_METHODS.contains(&method) => {
+                lumen_log!(
+                    Warn,
+                    "rpc",
+                    "{} is forwarded to an RPC and its result is NOT verified",
+                    method
+                );
+                let params = serde_json::Value::Array(request.params.clone());
+                let result = self
+                    .try_endpoints(|endpoint| self.rpc_call(endpoint, method, params.clone()))
+                    .await?;
+                Ok((result, ResponseMeta::new(VerificationPath::Unverified)))
+            }
+            _ => Err(DispatchError::Verification(format!("no handler for {}", method))),
+        }
+    }
+
+    pub(crate) fn verified_execution_header(&self) -> Result<ExecutionPayloadHeader, DispatchError> {
+        self.state
+            .borrow()
+            .latest_execution_payload_header
+            .clone()
+            .ok_or_else(|| DispatchError::ChainDisconnected("no BLS-verified execution payload yet".to_string()))
+    }
+
+    /// Run `attempt` against the configured endpoints, healthiest first,
+    /// until one succeeds.
+    pub(crate) async fn try_endpoints<'a, T, F, Fut>(&'a self, attempt: F) -> Result<T, String>
+    where
+        F: Fn(&'a str) -> Fut,
+        Fut: std::future::Future<Output = Result<T, String>>,
+    {
+        if self.rpc_endpoints.is_empty() {
+            return Err(NO_ENDPOINTS.to_string());
+        }
+
+        let mut last_error = String::new();
+        for endpoint in self.ranked_endpoints(&self.rpc_endpoints) {
+            match self.tracked(endpoint, attempt(endpoint)).await {
+                Ok(value) => return Ok(value),
+                Err(e) => {
+                    lumen_log!(Warn, "rpc", "RPC {} failed: {}", endpoint, e);
+                    last_error = e;
+                }
+            }
+        }
+        Err(format!("all RPC endpoints failed, last error: {}", last_error))
+    }
+
+    /// `endpoints` in the order to try them, per their recorded health.
+    pub(crate) fn ranked_endpoints<'e>(&self, endpoints: &'e [String]) -> Vec<&'e str> {
+        self.rpc_health
+            .borrow()
+            .rank(endpoints, self.clock.now_ms())
+            .into_iter()
+            .map(|index| endpoints[index].as_str())
+            .collect()
+    }
+
+    /// Await one attempt against `endpoint`, recording its outcome and latency.
+    pub(crate) async fn tracked<T, E: std::fmt::Display>(
+        &self,
+        endpoint: &str,
+        attempt: impl std::future::Future<Output = Result<T, E>>,
+    ) -> Result<T, E> {
+        let started = self.clock.now_ms();
+        let result = attempt.await;
+        let now = self.clock.now_ms();
+        let quarantined = match &result {
+            Ok(_) => {
+                self.rpc_health.borrow_mut().record_success(endpoint, now - started);
+                None
+            }
+            // Our own backpressure, not the endpoint's fault.
+            Err(e) if crate::rate_limit::is_rate_limited(&e.to_string()) => None,
+            Err(e) => self.rpc_health.borrow_mut().record_failure(endpoint, &e.to_string(), now),
+        };
+        if let Some(ms) = quarantined {
+            lumen_log!(
+                Warn,
+                "rpc",
+                "RPC {} quarantined for {}s after repeated failures",
+                endpoint,
+                ms / 1000.0
+            );
+        }
+        result
+    }
+
+    /// Send a signed transaction to all configured endpoints at once and
+    /// start watching it for a verified receipt.
+    ///
+    /// Succeeds if at least one endpoint accepted the transaction.
+    pub(crate) async fn broadcast_raw_transaction(&self, raw_hex: &str) -> Result<BroadcastOutcome, DispatchError> {
+        let raw = hex::decode(raw_hex.strip_prefix("0x").unwrap_or(raw_hex))
+            .map_err(|e| DispatchError::InvalidParams(format!("Invalid transaction hex: {}", e)))?;
+        if self.rpc_endpoints.is_empty() {
+            return Err(DispatchError::Disconnected(NO_ENDPOINTS.to_string()));
+        }
+        let expected = format!("0x{}", hex::encode(keccak256(&raw)));
+
+        let sends = self.rpc_endpoints.iter().map(|endpoint| async move {
+            let send = self.rpc_call(endpoint, "eth_sendRawTransaction", serde_json::json!([raw_hex]));
+            let result = self.tracked(endpoint, send).await;
+            (endpoint.clone(), result)
+        });
+        let outcome = summarize_broadcast(&expected, futures::future::join_all(sends).await);
+
+        for rejection in &outcome.rejected {
+            lumen_log!(
+                Warn,
+                "rpc",
+                "RPC {} rejected {}: {}",
+                rejection.endpoint, outcome.tx_hash, rejection.error
+            );
+        }
+        if outcome.accepted.is_empty() {
+            return Err(DispatchError::Verification(format!(
+                "no endpoint accepted transaction {}",
+                outcome.tx_hash
+            )));
+        }
+
+        let _ = self
+            .watcher
+            .borrow_mut()
+            .watch(&outcome.tx_hash, self.finalized_block_number());
+        lumen_log!(
+            Info,
+            "rpc",
+            "Broadcast {} accepted by {}/{} endpoints, watching for a verified receipt",
+            outcome.tx_hash,
+            outcome.accepted.len(),
+            self.rpc_endpoints.len()
+        );
+        Ok(outcome)
+    }
+
+    /// Fetch raw headers back to `number` from the nearest BLS-verified block
+    /// at or after it (see [`crate::history`]) and check the chain of parent
+    /// hashes from that block's hash.
+    pub(crate) async fn fetch_verified_header(
+        &self,
+        endpoint: &str,
+        number: u64,
+    ) -> Result<(ExecutionBlockHeader, [u8; 32]), String> {
+        let (header, hash) = self.fetch_header_chain(endpoint, number).await?.pop().ok_or("No headers fetched")?;
+        if header.number != number {
+            return Err(format!("RPC returned header #{} for block {}", header.number, number));
+        }
+        Ok((header, hash))
+    }
+
+    /// Every header from the nearest BLS-verified block at or after `number`
+    /// back to `number`, with its hash, newest first, checked along parent
+    /// hashes from the verified block's hash.
+    pub(crate) async fn fetch_header_chain(
+        &self,
+        endpoint: &str,
+        number: u64,
+    ) -> Result<Vec<(ExecutionBlockHeader, [u8; 32])>, String> {
+        let anchor = self
+            .execution_history
+            .borrow()
+            .anchor_for(number)
+            .cloned()
+            .ok_or("No BLS-verified execution payload at or after the block")?;
+        if anchor.block_number - number > MAX_HEADER_ANCESTRY {
+            return Err(format!(
+                "Block #{} is more than {} blocks behind the nearest verified block #{}",
+                number, MAX_HEADER_ANCESTRY, anchor.block_number
+            ));
+        }
+
+        let mut raw_headers = Vec::new();
+        for n in (number..=anchor.block_number).rev() {
+            let raw = self.rpc_call(endpoint, "debug_getRawHeader", serde_json::json!([to_quantity(n)]))
+                .await?;
+            let raw_hex = raw.as_str().ok_or("debug_getRawHeader result is not a string")?;
+            raw_headers.push(
+                hex::decode(raw_hex.strip_prefix("0x").unwrap_or(raw_hex))
+                    .map_err(|e| format!("Invalid header hex: {}", e))?,
+            );
+        }
+
+        let headers = verify_header_chain(anchor.block_hash, &raw_headers)
+            .map_err(|e| format!("Header verification: {}", e))?;
+        Ok(headers.into_iter().zip(raw_headers.iter().map(|raw| keccak256(raw))).collect())
+    }
+
+    /// The verified state root of block `number`: from the history if the
+    /// client verified that block's payload itself, otherwise from the block's
+    /// header, verified by parent hashes.
+    pub(crate) async fn historical_state_root(&self, endpoint: &str, number: u64) -> Result<[u8; 32], String> {
+        let recorded = self.execution_history.borrow().get(number).map(|header| header.state_root);
+        match recorded {
+            Some(state_root) => Ok(state_root),
+            None => Ok(self.fetch_verified_header(endpoint, number).await?.0.state_root),
+        }
+    }
+
+    /// The block a request's optional block parameter at `index` names,
+    /// the finalized block if absent. See [`resolve_block`].
+    fn requested_block(&self, request: &JsonRpcRequest, index: usize) -> Result<u64, DispatchError> {
+        let finalized = self.verified_execution_header()?.block_number;
+        match request.params.get(index) {
+            None => Ok(finalized),
+            Some(_) => resolve_block(param_str(request, index)?, finalized),
+        }
+    }
+
+    /// Fetch and verify an account, with any `storage_keys`, at `block`.
+    /// At the BLS-finalized block it is checked against the state root we
+    /// hold ourselves — no race against the RPC's idea of "latest" — and at
+    /// an older block against that block's header, verified back from it.
+    async fn verified_account_at(
+        &self,
+        endpoint: &str,
+        address: &str,
+        storage_keys: &[[u8; 32]],
+        block: u64,
+    ) -> Result<crate::FetchedAccount, String> {
+        if block == self.finalized_block_number() {
+            return self.fetch_finalized_account(endpoint, address, storage_keys).await;
+        }
+        let state_root = self.historical_state_root(endpoint, block).await?;
+        self.fetch_proof_at(endpoint, address, storage_keys, state_root, block).await
+    }
+
+    /// Fetch and verify one storage slot at `block`: the account proof
+    /// first, then the slot against the account's verified storage root.
+    pub(crate) async fn verified_storage_at(
+        &self,
+        address: &str,
+        slot: [u8; 32],
+        block: u64,
+    ) -> Result<(crate::FetchedAccount, U256), String> {
+        self.try_endpoints(|endpoint| async move {
+            let fetched = self.verified_account_at(endpoint, address, &[slot], block).await?;
+            let value = match fetched.storage.first().map(|s| &s.result) {
+                Some(Ok(value)) => *value,
+                Some(Err(e)) => return Err(format!("Storage proof verification: {}", e)),
+                None => return Err("RPC returned no storage proof".to_string()),
+            };
+            Ok((fetched, value))
+        })
+        .await
+    }
+
+    /// Verify an account at `block` and, if asked, fetch its code and check
+    /// it against the verified code hash.
+    async fn fetch_account_and_code(
+        &self,
+        endpoint: &str,
+        address: &str,
+        block: u64,
+        with_code: bool,
+    ) -> Result<(crate::FetchedAccount, Option<Vec<u8>>), String> {
+        let fetched = self.verified_account_at(endpoint, address, &[], block).await?;
+        if !with_code || !fetched.account.is_contract() {
+            return Ok((fetched, None));
+        }
+
+        let code = self.rpc_call(
+            endpoint,
+            "eth_getCode",
+            serde_json::json!([address, to_quantity(fetched.block_number)]),
+        )
+        .await?;
+        let code_hex = code.as_str().ok_or("eth_getCode result is not a string")?;
+        let code = hex::decode(code_hex.strip_prefix("0x").unwrap_or(code_hex))
+            .map_err(|e| format!("Invalid code hex: {}", e))?;
+
+        if keccak256(&code) != fetched.account.code_hash {
+            return Err(format!(
+                "code returned by RPC does not hash to the verified code hash 0x{}",
+                hex::encode(fetched.account.code_hash)
+            ));
+        }
+        Ok((fetched, Some(code)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_block() {
+        assert!(matches!(resolve_block("latest", 100), Ok(100)));
+        assert!(matches!(resolve_block("0x5a", 100), Ok(90)));
+        assert!(matches!(resolve_block("0x65", 100), Err(DispatchError::Verification(_))));
+        assert!(matches!(resolve_block("pending", 100), Err(DispatchError::InvalidParams(_))));
+    }
+
+    #[test]
+    fn test_parse_proof_mode() {
+        assert_eq!("finalized".parse(), Ok(ProofMode::Finalized));
+        assert_eq!("latest".parse(), Ok(ProofMode::Latest));
+        assert_eq!(ProofMode::default(), ProofMode::Latest);
+        assert!("safe".parse::<ProofMode>().is_err());
+    }
+
+    #[test]
+    fn test_parse_address() {
+        let addr = parse_address("0x00000000219ab540356cBB839Cbe05303d7705Fa").unwrap();
+        assert_eq!(addr[0], 0x00);
+        assert_eq!(addr[19], 0xFA);
+        assert!(parse_address("0x1234").is_err());
+        assert!(parse_address("0xzz").is_err());
+    }
+
+    #[test]
+    fn test_parse_slot_left_pads() {
+        let slot = parse_slot("0x5").unwrap();
+        assert_eq!(slot[31], 5);
+        assert!(slot[..31].iter().all(|&b| b == 0));
+        assert_eq!(parse_slot(&format!("0x{}", "ab".repeat(32))).unwrap(), [0xAB; 32]);
+        assert!(parse_slot(&format!("0x{}", "ab".repeat(33))).is_err());
+    }
+
+    #[test]
+    fn test_quantity_round_trip() {
+        assert_eq!(parse_quantity("0x1b4").unwrap(), 436);
+        assert_eq!(to_quantity(436), "0x1b4");
+        assert_eq!(to_quantity(0), "0x0");
+        assert!(parse_quantity("1b4").is_err());
+    }
+
+    #[test]
+    fn test_request_params_default_to_empty() {
+        let request: JsonRpcRequest =
+            serde_json::from_str(r#"{"method":"eth_blockNumber","id":7}"#).unwrap();
+        assert!(request.params.is_empty());
+        assert!(is_method_supported(&request.method));
+    }
+
+    #[test]
+    fn test_chain_id_follows_network() {
+        let request: JsonRpcRequest = serde_json::from_str(r#"{"method":"eth_chainId","id":1}"#).unwrap();
+        let response = serde_json::to_value(handle_info_method(&request, 11_155_111).unwrap()).unwrap();
+        assert_eq!(response["result"], "0xaa36a7");
+
+        let request: JsonRpcRequest = serde_json::from_str(r#"{"method":"net_version","id":2}"#).unwrap();
+        let response = serde_json::to_value(handle_info_method(&request, 17_000).unwrap()).unwrap();
+        assert_eq!(response["result"], "17000");
+    }
+
+    #[test]
+    fn test_response_meta_serialization() {
+        let response = JsonRpcResponse {
+            lumen: Some(ResponseMeta::new(VerificationPath::StorageProof).at_block(19_000_000).with_proof_nodes(14)),
+            ..success_response(serde_json::json!(1), serde_json::json!("0x0"))
+        };
+        let json = serde_json::to_value(&response).unwrap();
+        assert_eq!(json["lumen"]["verified"], true);
+        assert_eq!(json["lumen"]["path"], "storage_proof");
+        assert_eq!(json["lumen"]["block_number"], 19_000_000);
+        assert_eq!(json["lumen"]["proof_nodes"], 14);
+        assert!(json["lumen"]["finalized_slot"].is_null());
+
+        let trusted = serde_json::to_value(ResponseMeta::new(VerificationPath::Unverified)).unwrap();
+        assert_eq!(trusted["verified"], false);
+        assert!(trusted["block_number"].is_null());
+
+        let plain = serde_json::to_value(success_response(serde_json::json!(1), serde_json::json!(1))).unwrap();
+        assert!(plain.get("lumen").is_none());
+    }
+
+    #[test]
+    fn test_error_response_shape() {
+        let response = error_response(serde_json::json!(1), INVALID_PARAMS, "bad".to_string());
+        let json = serde_json::to_value(&response).unwrap();
+        assert_eq!(json["error"]["code"], INVALID_PARAMS);
+        assert!(json.get("result").is_none());
+    }
+
+    #[test]
+    fn test_header_to_json() {
+        let header = ExecutionBlockHeader {
+            parent_hash: [0x01; 32],
+            ommers_hash: [0x02; 32],
+            beneficiary: [0x03; 20],
+            state_root: [0x04; 32],
+            transactions_root: [0x05; 32],
+            receipts_root: [0x06; 32],
+            logs_bloom: [0; 256],
+            difficulty: U256::ZERO,
+            number: 21_000_000,
+            gas_limit: 30_000_000,
+            gas_used: 15_000_000,
+            timestamp: 1_730_000_000,
+            extra_data: vec![],
+            mix_hash: [0x07; 32],
+            nonce: [0; 8],
+            base_fee_per_gas: Some(U256::from_u64(1_000_000_000)),
+            withdrawals_root: Some([0x08; 32]),
+            blob_gas_used: None,
+            excess_blob_gas: None,
+            parent_beacon_block_root: None,
+            requests_hash: None,
+        };
+        let json = header_to_json(&header, [0xAB; 32]);
+        assert_eq!(json["number"], "0x1406f40");
+        assert_eq!(json["hash"], format!("0x{}", "ab".repeat(32)));
+        assert_eq!(json["baseFeePerGas"], "0x3b9aca00");
+        assert_eq!(json["difficulty"], "0x0");
+        assert_eq!(json["extraData"], "0x");
+        assert!(json.get("withdrawalsRoot").is_some());
+        assert!(json.get("blobGasUsed").is_none());
+        assert!(json.get("transactions").is_none());
+        assert_eq!(header_from_json(&json).unwrap(), header);
+
+        let mut truncated = json.clone();
+        truncated["nonce"] = "0x00".into();
+        assert!(header_from_json(&truncated).is_err());
+    }
+
+    #[test]
+    fn test_summarize_broadcast() {
+        let hash = format!("0x{}", "ab".repeat(32));
+        let outcome = summarize_broadcast(
+            &hash,
+            vec![
+                ("a".to_string(), Ok(serde_json::json!(hash.to_uppercase().replacen("0X", "0x", 1)))),
+                ("b".to_string(), Err("eth_sendRawTransaction RPC error: already known".to_string())),
+                ("c".to_string(), Ok(serde_json::json!(format!("0x{}", "cd".repeat(32))))),
+                ("d".to_string(), Err("HTTP error 503".to_string())),
+            ],
+        );
+        assert_eq!(outcome.tx_hash, hash);
+        assert_eq!(outcome.accepted, vec!["a".to_string(), "b".to_string()]);
+        let rejected: Vec<&str> = outcome.rejected.iter().map(|r| r.endpoint.as_str()).collect();
+        assert_eq!(rejected, vec!["c", "d"]);
+    }
+}
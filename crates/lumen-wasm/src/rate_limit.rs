@@ -0,0 +1,212 @@
+//! Token-bucket limits on outbound execution RPC requests.
+//!
+//! Public RPC endpoints ban clients that send too much too fast, and a dApp
+//! polling `eth_getBalance` for a long token list easily does. Every RPC
+//! request takes a token from its endpoint's bucket and from one global
+//! bucket; buckets refill continuously up to their burst size.
+//!
+//! A request that finds a bucket empty reserves the next token anyway and
+//! waits for it, so requests queue in order instead of failing. Once the
+//! wait would exceed [`RateLimitConfig::max_queue_ms`] the request is
+//! refused instead, and the refusal is reported to the caller as
+//! backpressure rather than counted against the endpoint's health.
+
+use serde::{Deserialize, Serialize};
+
+/// Marks refusals, so they can be told apart from endpoint failures.
+pub const RATE_LIMITED: &str = "local rate limit";
+
+/// True if `error` is (or wraps) a refusal from [`RateLimiter::reserve`].
+pub fn is_rate_limited(error: &str) -> bool {
+    error.contains(RATE_LIMITED)
+}
+
+/// One bucket's refill rate and capacity.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RateLimit {
+    pub requests_per_second: f64,
+    /// Requests that may go out back to back after a quiet period.
+    pub burst: f64,
+}
+
+/// Limits for one client, as passed to `set_rate_limits`. Omitted fields
+/// keep their defaults; a `null` limit turns that bucket off.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RateLimitConfig {
+    pub per_endpoint: Option<RateLimit>,
+    pub global: Option<RateLimit>,
+    /// Longest a request may wait for a token before it is refused.
+    pub max_queue_ms: f64,
+}
+
+/// Comfortably under the free tiers of the common public RPC providers.
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            per_endpoint: Some(RateLimit { requests_per_second: 10.0, burst: 20.0 }),
+            global: Some(RateLimit { requests_per_second: 25.0, burst: 50.0 }),
+            max_queue_ms: 10_000.0,
+        }
+    }
+}
+
+impl RateLimitConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        for (name, limit) in [("per_endpoint", self.per_endpoint), ("global", self.global)] {
+            if let Some(limit) = limit {
+                let valid = limit.requests_per_second > 0.0 && limit.burst >= 1.0;
+                if !valid {
+                    return Err(format!("{} needs requests_per_second > 0 and burst >= 1", name));
+                }
+            }
+        }
+        if self.max_queue_ms.is_nan() || self.max_queue_ms < 0.0 {
+            return Err("max_queue_ms must not be negative".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Tokens may go negative: each token below zero is a request waiting for it.
+#[derive(Clone, Copy, Debug)]
+struct Bucket {
+    tokens: f64,
+    updated_ms: f64,
+}
+
+impl Bucket {
+    fn full(limit: &RateLimit, now_ms: f64) -> Self {
+        Self { tokens: limit.burst, updated_ms: now_ms }
+    }
+
+    fn refill(&mut self, limit: &RateLimit, now_ms: f64) {
+        let elapsed = (now_ms - self.updated_ms).max(0.0);
+        self.tokens = (self.tokens + elapsed * limit.requests_per_second / 1000.0).min(limit.burst);
+        self.updated_ms = self.updated_ms.max(now_ms);
+    }
+
+    /// How long until the next token taken from here is available.
+    fn wait_ms(&self, limit: &RateLimit) -> f64 {
+        if self.tokens >= 1.0 {
+            0.0
+        } else {
+            (1.0 - self.tokens) * 1000.0 / limit.requests_per_second
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct RateLimiter {
+    config: RateLimitConfig,
+    global: Option<Bucket>,
+    endpoints: Vec<(String, Bucket)>,
+}
+
+impl Default for RateLimiter {
+    fn default() -> Self {
+        Self::new(RateLimitConfig::default())
+    }
+}
+
+impl RateLimiter {
+    pub fn new(config: RateLimitConfig) -> Self {
+        Self { config, global: None, endpoints: Vec::new() }
+    }
+
+    /// Take a token for a request to `url`. Returns how long to wait before
+    /// sending it, or, if that is longer than the queue allows, an error and
+    /// takes nothing.
+    pub fn reserve(&mut self, url: &str, now_ms: f64) -> Result<f64, String> {
+        let mut buckets: Vec<(RateLimit, &mut Bucket)> = Vec::with_capacity(2);
+        if let Some(limit) = self.config.global {
+            buckets.push((limit, self.global.get_or_insert_with(|| Bucket::full(&limit, now_ms))));
+        }
+        if let Some(limit) = self.config.per_endpoint {
+            let index = match self.endpoints.iter().position(|(u, _)| u == url) {
+                Some(index) => index,
+                None => {
+                    self.endpoints.push((url.to_string(), Bucket::full(&limit, now_ms)));
+                    self.endpoints.len() - 1
+                }
+            };
+            buckets.push((limit, &mut self.endpoints[index].1));
+        }
+
+        let mut wait_ms: f64 = 0.0;
+        for (limit, bucket) in buckets.iter_mut() {
+            bucket.refill(limit, now_ms);
+            wait_ms = wait_ms.max(bucket.wait_ms(limit));
+        }
+        if wait_ms > self.config.max_queue_ms {
+            return Err(format!(
+                "{}: too many queued requests to {}, retry in {} ms",
+                RATE_LIMITED,
+                url,
+                wait_ms.ceil()
+            ));
+        }
+        for (_, bucket) in buckets {
+            bucket.tokens -= 1.0;
+        }
+        Ok(wait_ms)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limiter(per_endpoint: f64, global: f64, burst: f64) -> RateLimiter {
+        RateLimiter::new(RateLimitConfig {
+            per_endpoint: Some(RateLimit { requests_per_second: per_endpoint, burst }),
+            global: Some(RateLimit { requests_per_second: global, burst: burst * 2.0 }),
+            max_queue_ms: 1_000.0,
+        })
+    }
+
+    #[test]
+    fn test_burst_then_queue_then_refuse() {
+        let mut limiter = limiter(10.0, 100.0, 2.0);
+        assert_eq!(limiter.reserve("a", 0.0), Ok(0.0));
+        assert_eq!(limiter.reserve("a", 0.0), Ok(0.0));
+        // Each further request waits one refill interval longer than the last.
+        assert_eq!(limiter.reserve("a", 0.0), Ok(100.0));
+        assert_eq!(limiter.reserve("a", 0.0), Ok(200.0));
+        for _ in 0..8 {
+            limiter.reserve("a", 0.0).unwrap();
+        }
+        let refused = limiter.reserve("a", 0.0).unwrap_err();
+        assert!(is_rate_limited(&refused));
+        // The refusal took nothing: time frees the queue again.
+        assert_eq!(limiter.reserve("a", 200.0), Ok(900.0));
+    }
+
+    #[test]
+    fn test_endpoints_are_limited_separately_and_together() {
+        let mut limiter = limiter(10.0, 10.0, 1.0);
+        assert_eq!(limiter.reserve("a", 0.0), Ok(0.0));
+        assert_eq!(limiter.reserve("b", 0.0), Ok(0.0));
+        // "c" has a token of its own, but the global bucket is empty.
+        assert_eq!(limiter.reserve("c", 0.0), Ok(100.0));
+    }
+
+    #[test]
+    fn test_config_json_and_validation() {
+        let config: RateLimitConfig =
+            serde_json::from_str(r#"{"per_endpoint": {"requests_per_second": 5, "burst": 5}, "global": null}"#)
+                .unwrap();
+        assert_eq!(config.global, None);
+        assert_eq!(config.max_queue_ms, RateLimitConfig::default().max_queue_ms);
+        assert!(config.validate().is_ok());
+
+        let mut unlimited = RateLimiter::new(RateLimitConfig { per_endpoint: None, global: None, max_queue_ms: 0.0 });
+        for _ in 0..1_000 {
+            assert_eq!(unlimited.reserve("a", 0.0), Ok(0.0));
+        }
+
+        let zero: RateLimitConfig =
+            serde_json::from_str(r#"{"global": {"requests_per_second": 0, "burst": 1}}"#).unwrap();
+        assert!(zero.validate().is_err());
+    }
+}
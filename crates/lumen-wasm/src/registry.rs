@@ -0,0 +1,240 @@
+//! Several clients, one per chain, behind one EIP-1193 entry point.
+//!
+//! A `LumenRegistry` owns clients keyed by chain id and routes every request
+//! to the active one. `wallet_switchEthereumChain` (EIP-3326) changes which
+//! client is active; `eth_chainId` and all other methods are answered by
+//! that client. One worker can so serve mainnet and a testnet side by side,
+//! each verified against its own light client state.
+
+use crate::error::{ErrorCode, LumenError};
+use crate::provider::{self, JsonRpcRequest, JsonRpcResponse};
+use crate::LumenClient;
+use std::cell::Cell;
+use std::rc::Rc;
+use wasm_bindgen::prelude::*;
+
+/// EIP-3326: the requested chain has not been added.
+pub const UNRECOGNIZED_CHAIN: i64 = 4902;
+
+/// Clients keyed by the chain id of the network each was configured for.
+#[wasm_bindgen]
+#[derive(Default)]
+pub struct LumenRegistry {
+    /// In the order they were added; at most one per chain id.
+    clients: Vec<Rc<LumenClient>>,
+    /// The chain requests are routed to. Only None while `clients` is empty.
+    active: Cell<Option<u64>>,
+}
+
+/// Where a request goes: a client to dispatch it to, or an answer already.
+pub(crate) enum Route {
+    Client(Rc<LumenClient>),
+    Answered(JsonRpcResponse),
+}
+
+#[wasm_bindgen]
+impl LumenRegistry {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add `client` under its network's chain id and return that id.
+    ///
+    /// A client already registered for the chain is replaced and its
+    /// finality polling stopped. The first client added becomes active.
+    /// `client` is moved into the registry and unusable from JS afterwards.
+    pub fn add_client(&mut self, client: LumenClient) -> u64 {
+        self.insert(Rc::new(client))
+    }
+
+    /// Remove the client for `chain_id`, stopping its polling. If it was
+    /// active, the earliest added remaining client becomes active.
+    pub fn remove_client(&mut self, chain_id: u64) -> bool {
+        let Some(index) = self.position(chain_id) else {
+            return false;
+        };
+        self.clients.remove(index).stop_finality_polling();
+        if self.active.get() == Some(chain_id) {
+            self.active.set(self.clients.first().map(|client| client.network.chain_id));
+        }
+        true
+    }
+
+    /// Registered chain ids, in the order they were added.
+    pub fn chain_ids(&self) -> Vec<u64> {
+        self.clients.iter().map(|client| client.network.chain_id).collect()
+    }
+
+    pub fn active_chain_id(&self) -> Option<u64> {
+        self.active.get()
+    }
+
+    /// Route subsequent requests to the client for `chain_id`.
+    pub fn switch_chain(&self, chain_id: u64) -> Result<(), JsValue> {
+        if !self.activate(chain_id) {
+            return Err(LumenError::new(
+                ErrorCode::InvalidInput,
+                format!("No client registered for chain {}", chain_id),
+            )
+            .into());
+        }
+        Ok(())
+    }
+
+    /// `LumenClient.handle_request`, answered by the active chain's client.
+    pub async fn handle_request(&self, request_json: &str) -> Result<String, JsValue> {
+        let response = match provider::parse_request_json(request_json) {
+            Ok(request) => self.dispatch(&request).await,
+            Err(message) => provider::error_response(serde_json::Value::Null, provider::PARSE_ERROR, message),
+        };
+        serde_json::to_string(&response).map_err(|e| LumenError::serialization(e).into())
+    }
+
+    /// `LumenClient.request`, answered by the active chain's client.
+    #[wasm_bindgen(unchecked_return_type = "unknown")]
+    pub async fn request(
+        &self,
+        #[wasm_bindgen(unchecked_param_type = "RequestArguments")] args: JsValue,
+    ) -> Result<JsValue, JsValue> {
+        let request = provider::parse_request_args(args)?;
+        provider::settle(self.dispatch(&request).await)
+    }
+}
+
+impl LumenRegistry {
+    pub(crate) fn insert(&mut self, client: Rc<LumenClient>) -> u64 {
+        let chain_id = client.network.chain_id;
+        match self.position(chain_id) {
+            Some(index) => std::mem::replace(&mut self.clients[index], client).stop_finality_polling(),
+            None => self.clients.push(client),
+        }
+        if self.active.get().is_none() {
+            self.active.set(Some(chain_id));
+        }
+        chain_id
+    }
+
+    pub(crate) fn get(&self, chain_id: u64) -> Option<Rc<LumenClient>> {
+        self.position(chain_id).map(|index| self.clients[index].clone())
+    }
+
+    pub(crate) fn active(&self) -> Option<Rc<LumenClient>> {
+        self.active.get().and_then(|chain_id| self.get(chain_id))
+    }
+
+    /// Make `chain_id` active, if a client is registered for it.
+    pub(crate) fn activate(&self, chain_id: u64) -> bool {
+        let known = self.position(chain_id).is_some();
+        if known {
+            self.active.set(Some(chain_id));
+        }
+        known
+    }
+
+    /// Answer chain switches here; send everything else to the active client.
+    pub(crate) fn route(&self, request: &JsonRpcRequest) -> Route {
+        let id = request.id.clone();
+        if request.method == "wallet_switchEthereumChain" {
+            return Route::Answered(self.switch_request(request));
+        }
+        match self.active() {
+            Some(client) => Route::Client(client),
+            None => Route::Answered(provider::error_response(
+                id,
+                provider::DISCONNECTED,
+                "no clients registered; call add_client first".to_string(),
+            )),
+        }
+    }
+
+    async fn dispatch(&self, request: &JsonRpcRequest) -> JsonRpcResponse {
+        match self.route(request) {
+            Route::Client(client) => client.dispatch(request).await,
+            Route::Answered(response) => response,
+        }
+    }
+
+    /// `wallet_switchEthereumChain` with params `[{ chainId }]`.
+    fn switch_request(&self, request: &JsonRpcRequest) -> JsonRpcResponse {
+        let id = request.id.clone();
+        let chain_id = request
+            .params
+            .first()
+            .and_then(|param| param.get("chainId"))
+            .and_then(|chain_id| chain_id.as_str())
+            .ok_or_else(|| "wallet_switchEthereumChain expects [{ chainId }]".to_string())
+            .and_then(provider::parse_quantity);
+        match chain_id {
+            Err(message) => provider::error_response(id, provider::INVALID_PARAMS, message),
+            Ok(chain_id) if self.activate(chain_id) => provider::JsonRpcResponse {
+                lumen: Some(provider::ResponseMeta::new(provider::VerificationPath::Local)),
+                ..provider::success_response(id, serde_json::Value::Null)
+            },
+            Ok(chain_id) => provider::error_response(
+                id,
+                UNRECOGNIZED_CHAIN,
+                format!("Unrecognized chain ID {}; add a client for it first", provider::to_quantity(chain_id)),
+            ),
+        }
+    }
+
+    fn position(&self, chain_id: u64) -> Option<usize> {
+        self.clients.iter().position(|client| client.network.chain_id == chain_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lumen_core::types::network::NetworkConfig;
+
+    fn client(network: NetworkConfig) -> LumenClient {
+        LumenClient::from_state(crate::tests::state([0; 32]), network)
+    }
+
+    fn call(registry: &LumenRegistry, method: &str, params: serde_json::Value) -> serde_json::Value {
+        let request = serde_json::json!({"method": method, "params": params, "id": 1}).to_string();
+        let response = futures::executor::block_on(registry.handle_request(&request)).unwrap();
+        serde_json::from_str(&response).unwrap()
+    }
+
+    #[test]
+    fn test_routes_to_the_active_chain() {
+        let mut registry = LumenRegistry::new();
+        assert_eq!(call(&registry, "eth_chainId", serde_json::json!([]))["error"]["code"], provider::DISCONNECTED);
+
+        assert_eq!(registry.add_client(client(NetworkConfig::mainnet())), 1);
+        assert_eq!(registry.add_client(client(NetworkConfig::sepolia())), 11_155_111);
+        assert_eq!(registry.chain_ids(), vec![1, 11_155_111]);
+        assert_eq!(call(&registry, "eth_chainId", serde_json::json!([]))["result"], "0x1");
+
+        let switched = call(&registry, "wallet_switchEthereumChain", serde_json::json!([{"chainId": "0xaa36a7"}]));
+        assert_eq!(switched["result"], serde_json::Value::Null);
+        assert!(switched.get("error").is_none());
+        assert_eq!(switched["lumen"]["path"], "local");
+        assert_eq!(registry.active_chain_id(), Some(11_155_111));
+        assert_eq!(call(&registry, "eth_chainId", serde_json::json!([]))["result"], "0xaa36a7");
+
+        let unknown = call(&registry, "wallet_switchEthereumChain", serde_json::json!([{"chainId": "0x4268"}]));
+        assert_eq!(unknown["error"]["code"], UNRECOGNIZED_CHAIN);
+        let malformed = call(&registry, "wallet_switchEthereumChain", serde_json::json!(["0x1"]));
+        assert_eq!(malformed["error"]["code"], provider::INVALID_PARAMS);
+        assert_eq!(registry.active_chain_id(), Some(11_155_111));
+    }
+
+    #[test]
+    fn test_replace_and_remove() {
+        let mut registry = LumenRegistry::new();
+        registry.add_client(client(NetworkConfig::mainnet()));
+        registry.add_client(client(NetworkConfig::sepolia()));
+        registry.add_client(client(NetworkConfig::mainnet()));
+        assert_eq!(registry.chain_ids(), vec![1, 11_155_111]);
+
+        assert!(registry.remove_client(1));
+        assert!(!registry.remove_client(1));
+        assert_eq!(registry.active_chain_id(), Some(11_155_111));
+        assert!(registry.remove_client(11_155_111));
+        assert_eq!(registry.active_chain_id(), None);
+    }
+}
@@ -0,0 +1,57 @@
+//! Memory held by one client, read with `resource_stats`.
+//!
+//! Every cache and ring buffer in the client is bounded, but the defaults
+//! are sized for desktops. The numbers here let an integrator targeting
+//! low-memory mobile browsers see which ones are worth shrinking, and
+//! whether they are hit often enough to be worth keeping at all. Byte
+//! counts are estimates of the payload held, not of allocator overhead.
+
+use serde::Serialize;
+
+/// One bounded cache or ring buffer.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct CacheStats {
+    pub entries: usize,
+    /// Most entries kept before the oldest is evicted; 0 if disabled.
+    pub capacity: usize,
+    pub approx_bytes: usize,
+    /// Lookups answered from the cache, for caches that are looked up.
+    pub hits: Option<u64>,
+    pub misses: Option<u64>,
+}
+
+/// What `resource_stats` returns.
+#[derive(Debug, Serialize)]
+pub struct ResourceStats {
+    /// Size of the WebAssembly linear memory, shared by every client in
+    /// the worker. It grows on demand and never shrinks, so this is the
+    /// high-water mark. None outside WebAssembly.
+    pub wasm_memory_bytes: Option<f64>,
+    /// The verified state: sync committees and headers.
+    pub state_bytes: usize,
+    /// Raw RPC results, see `set_response_cache`.
+    pub response_cache: CacheStats,
+    /// Aggregated sync committee keys.
+    pub aggregate_cache: CacheStats,
+    /// Recent finalized execution headers, for historical queries.
+    pub execution_history: CacheStats,
+    pub watched_transactions: usize,
+    pub subscriptions: usize,
+    /// Endpoints with a health record.
+    pub tracked_endpoints: usize,
+}
+
+/// Current size of the module's linear memory.
+#[cfg(target_arch = "wasm32")]
+pub fn wasm_memory_bytes() -> Option<f64> {
+    use wasm_bindgen::JsCast;
+
+    let memory: js_sys::WebAssembly::Memory = wasm_bindgen::memory().unchecked_into();
+    let buffer: js_sys::ArrayBuffer = memory.buffer().unchecked_into();
+    Some(f64::from(buffer.byte_length()))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn wasm_memory_bytes() -> Option<f64> {
+    None
+}
@@ -0,0 +1,172 @@
+//! Short-lived cache of raw execution RPC results.
+//!
+//! Several components asking about the same account in the same block
+//! would otherwise each fetch the same block and proof. Cached results are
+//! exactly what the RPC sent, so they are verified again on every use:
+//! the cache saves network round trips, never verification.
+//!
+//! Entries are keyed by endpoint, method and params, and expire after the
+//! TTL. An entry whose params name a block tag ("latest", "finalized", ...)
+//! also goes as soon as the verified head advances, since the tag now means
+//! another block. Entries at an explicit block number stay until they
+//! expire. Only [`CACHEABLE_METHODS`] are cached, and only non-null results.
+
+use std::collections::VecDeque;
+
+/// Idempotent reads whose results are worth reusing within a block.
+pub const CACHEABLE_METHODS: &[&str] = &["eth_getBlockByNumber", "eth_getProof"];
+
+/// About one slot: a "latest" answer older than that is likely superseded.
+pub const DEFAULT_TTL_MS: f64 = 12_000.0;
+
+/// Account proofs run to a few kilobytes each.
+pub const DEFAULT_MAX_ENTRIES: usize = 256;
+
+const BLOCK_TAGS: &[&str] = &["latest", "safe", "finalized", "pending", "earliest"];
+
+#[derive(Debug)]
+struct Entry {
+    key: String,
+    result: serde_json::Value,
+    expires_ms: f64,
+    /// Names a block tag, so is dropped when the head advances.
+    tagged: bool,
+}
+
+#[derive(Debug)]
+pub struct ResponseCache {
+    ttl_ms: f64,
+    max_entries: usize,
+    /// Oldest first.
+    entries: VecDeque<Entry>,
+    hits: u64,
+    misses: u64,
+}
+
+impl Default for ResponseCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_TTL_MS, DEFAULT_MAX_ENTRIES)
+    }
+}
+
+impl ResponseCache {
+    /// A TTL of 0 or `max_entries` of 0 turns caching off.
+    pub fn new(ttl_ms: f64, max_entries: usize) -> Self {
+        Self { ttl_ms, max_entries, entries: VecDeque::new(), hits: 0, misses: 0 }
+    }
+
+    /// The cache key for a request, or None if `method` is not cached.
+    pub fn key(endpoint: &str, method: &str, params: &serde_json::Value) -> Option<String> {
+        CACHEABLE_METHODS
+            .contains(&method)
+            .then(|| format!("{} {} {}", endpoint, method, params))
+    }
+
+    pub fn get(&mut self, key: &str, now_ms: f64) -> Option<serde_json::Value> {
+        self.entries.retain(|entry| entry.expires_ms > now_ms);
+        let found = self.entries.iter().find(|entry| entry.key == key).map(|entry| entry.result.clone());
+        match found {
+            Some(_) => self.hits += 1,
+            None => self.misses += 1,
+        }
+        found
+    }
+
+    pub fn insert(&mut self, key: String, params: &serde_json::Value, result: serde_json::Value, now_ms: f64) {
+        if self.ttl_ms <= 0.0 || self.max_entries == 0 {
+            return;
+        }
+        self.entries.retain(|entry| entry.key != key);
+        if self.entries.len() >= self.max_entries {
+            self.entries.pop_front();
+        }
+        let tagged = params
+            .as_array()
+            .is_some_and(|params| params.iter().any(|p| p.as_str().is_some_and(|s| BLOCK_TAGS.contains(&s))));
+        self.entries.push_back(Entry { key, result, expires_ms: now_ms + self.ttl_ms, tagged });
+    }
+
+    /// Entries held, including any expired but not yet swept.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.max_entries
+    }
+
+    /// Approximate memory held, measured as the results' JSON text.
+    pub fn approx_bytes(&self) -> usize {
+        self.entries
+            .iter()
+            .map(|entry| entry.key.len() + entry.result.to_string().len())
+            .sum()
+    }
+
+    /// Lookups answered from the cache.
+    pub fn hits(&self) -> u64 {
+        self.hits
+    }
+
+    /// Lookups of cacheable requests that had to go to the network.
+    pub fn misses(&self) -> u64 {
+        self.misses
+    }
+
+    /// Drop every entry that asked about a block by tag.
+    pub fn head_advanced(&mut self) {
+        self.entries.retain(|entry| !entry.tagged);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn put(cache: &mut ResponseCache, method: &str, params: serde_json::Value, now_ms: f64) -> String {
+        let key = ResponseCache::key("rpc", method, &params).unwrap();
+        cache.insert(key.clone(), &params, json!({"method": method}), now_ms);
+        key
+    }
+
+    #[test]
+    fn test_entries_expire() {
+        let mut cache = ResponseCache::new(1_000.0, 8);
+        let key = put(&mut cache, "eth_getProof", json!(["0xabc", [], "0x10"]), 0.0);
+        assert_eq!(cache.get(&key, 999.0), Some(json!({"method": "eth_getProof"})));
+        assert_eq!(cache.get(&key, 1_000.0), None);
+        assert_eq!(cache.entries.len(), 0);
+        assert_eq!((cache.hits(), cache.misses()), (1, 1));
+        assert!(ResponseCache::key("rpc", "eth_sendRawTransaction", &json!(["0x00"])).is_none());
+        // Another endpoint is another entry.
+        assert_ne!(
+            ResponseCache::key("other", "eth_getProof", &json!([])),
+            ResponseCache::key("rpc", "eth_getProof", &json!([]))
+        );
+    }
+
+    #[test]
+    fn test_head_advance_drops_tagged_entries() {
+        let mut cache = ResponseCache::new(60_000.0, 8);
+        let latest = put(&mut cache, "eth_getBlockByNumber", json!(["latest", false]), 0.0);
+        let pinned = put(&mut cache, "eth_getProof", json!(["0xabc", [], "0x10"]), 0.0);
+        cache.head_advanced();
+        assert_eq!(cache.get(&latest, 1.0), None);
+        assert!(cache.get(&pinned, 1.0).is_some());
+    }
+
+    #[test]
+    fn test_oldest_entry_is_evicted_and_zero_ttl_disables() {
+        let mut cache = ResponseCache::new(60_000.0, 2);
+        let first = put(&mut cache, "eth_getProof", json!(["0x1", [], "0x10"]), 0.0);
+        put(&mut cache, "eth_getProof", json!(["0x2", [], "0x10"]), 0.0);
+        put(&mut cache, "eth_getProof", json!(["0x3", [], "0x10"]), 0.0);
+        assert_eq!(cache.entries.len(), 2);
+        assert_eq!(cache.get(&first, 1.0), None);
+
+        let mut off = ResponseCache::new(0.0, 8);
+        put(&mut off, "eth_getProof", json!([]), 0.0);
+        assert_eq!(off.entries.len(), 0);
+    }
+}
@@ -0,0 +1,227 @@
+//! Per-endpoint health for execution RPCs, and the order to try them in.
+//!
+//! Every attempt against an endpoint is recorded. A proof that fails
+//! verification counts against the endpoint just like a timeout does: either
+//! way it did not give us data we could use.
+//!
+//! Endpoints are tried healthiest first: not quarantined, then fewest
+//! consecutive failures, then lowest average latency. Endpoints without a
+//! latency yet rank first, so each one is probed once; ties keep the
+//! caller's order. After [`QUARANTINE_AFTER`] consecutive failures an
+//! endpoint is quarantined for [`QUARANTINE_BASE_MS`], doubling with every
+//! further failure up to [`QUARANTINE_MAX_MS`]. Quarantined endpoints are
+//! only tried when every endpoint is quarantined, soonest to recover first.
+
+use serde::Serialize;
+
+/// Consecutive failures before an endpoint is quarantined.
+pub const QUARANTINE_AFTER: u32 = 3;
+pub const QUARANTINE_BASE_MS: f64 = 30_000.0;
+pub const QUARANTINE_MAX_MS: f64 = 600_000.0;
+
+/// Weight of the newest sample in the latency moving average.
+const LATENCY_SMOOTHING: f64 = 0.3;
+
+#[derive(Clone, Debug, Default)]
+struct Record {
+    successes: u64,
+    failures: u64,
+    consecutive_failures: u32,
+    /// Moving average over successful attempts.
+    avg_latency_ms: Option<f64>,
+    last_error: Option<String>,
+    quarantined_until_ms: Option<f64>,
+}
+
+impl Record {
+    fn quarantined(&self, now_ms: f64) -> bool {
+        self.quarantined_until_ms.is_some_and(|until| until > now_ms)
+    }
+}
+
+/// Health of every endpoint attempted so far, keyed by URL.
+#[derive(Debug, Default)]
+pub struct RpcHealth {
+    records: Vec<(String, Record)>,
+}
+
+impl RpcHealth {
+    fn record(&self, url: &str) -> Option<&Record> {
+        self.records.iter().find(|(u, _)| u == url).map(|(_, r)| r)
+    }
+
+    fn record_mut(&mut self, url: &str) -> &mut Record {
+        let index = match self.records.iter().position(|(u, _)| u == url) {
+            Some(index) => index,
+            None => {
+                self.records.push((url.to_string(), Record::default()));
+                self.records.len() - 1
+            }
+        };
+        &mut self.records[index].1
+    }
+
+    /// Indices into `endpoints`, in the order they should be tried.
+    pub fn rank(&self, endpoints: &[String], now_ms: f64) -> Vec<usize> {
+        let default = Record::default();
+        let mut order: Vec<usize> = (0..endpoints.len()).collect();
+        order.sort_by(|&a, &b| {
+            let a = self.record(&endpoints[a]).unwrap_or(&default);
+            let b = self.record(&endpoints[b]).unwrap_or(&default);
+            let key = |r: &Record| {
+                let quarantined = r.quarantined(now_ms);
+                (
+                    quarantined,
+                    // Only quarantined endpoints have an expiry to compare.
+                    r.quarantined_until_ms.filter(|_| quarantined).unwrap_or(0.0),
+                    r.consecutive_failures,
+                    r.avg_latency_ms.unwrap_or(0.0),
+                )
+            };
+            key(a).partial_cmp(&key(b)).unwrap_or(std::cmp::Ordering::Equal)
+        });
+        order
+    }
+
+    pub fn record_success(&mut self, url: &str, latency_ms: f64) {
+        let record = self.record_mut(url);
+        record.successes += 1;
+        record.consecutive_failures = 0;
+        record.quarantined_until_ms = None;
+        let latency_ms = latency_ms.max(0.0);
+        record.avg_latency_ms = Some(match record.avg_latency_ms {
+            Some(avg) => avg + LATENCY_SMOOTHING * (latency_ms - avg),
+            None => latency_ms,
+        });
+    }
+
+    /// Record a failed attempt. Returns the quarantine length if the
+    /// endpoint has just been quarantined (again).
+    pub fn record_failure(&mut self, url: &str, error: &str, now_ms: f64) -> Option<f64> {
+        let record = self.record_mut(url);
+        record.failures += 1;
+        record.consecutive_failures += 1;
+        record.last_error = Some(error.to_string());
+        if record.consecutive_failures < QUARANTINE_AFTER {
+            return None;
+        }
+        let doublings = (record.consecutive_failures - QUARANTINE_AFTER).min(16);
+        let duration = (QUARANTINE_BASE_MS * f64::from(1u32 << doublings)).min(QUARANTINE_MAX_MS);
+        record.quarantined_until_ms = Some(now_ms + duration);
+        Some(duration)
+    }
+
+    /// Every tracked endpoint, in the order they would be tried.
+    pub fn report(&self, now_ms: f64) -> Vec<EndpointHealth> {
+        let urls: Vec<String> = self.records.iter().map(|(url, _)| url.clone()).collect();
+        self.rank(&urls, now_ms)
+            .into_iter()
+            .map(|index| {
+                let (url, record) = &self.records[index];
+                let attempts = record.successes + record.failures;
+                EndpointHealth {
+                    url: url.clone(),
+                    healthy: !record.quarantined(now_ms),
+                    successes: record.successes,
+                    failures: record.failures,
+                    consecutive_failures: record.consecutive_failures,
+                    success_rate: (attempts > 0).then(|| record.successes as f64 / attempts as f64),
+                    avg_latency_ms: record.avg_latency_ms,
+                    last_error: record.last_error.clone(),
+                    retry_in_ms: record
+                        .quarantined_until_ms
+                        .filter(|_| record.quarantined(now_ms))
+                        .map(|until| until - now_ms),
+                }
+            })
+            .collect()
+    }
+}
+
+/// One endpoint's entry in `get_rpc_health`.
+#[derive(Debug, Serialize)]
+pub struct EndpointHealth {
+    pub url: String,
+    /// False while the endpoint is quarantined.
+    pub healthy: bool,
+    pub successes: u64,
+    pub failures: u64,
+    pub consecutive_failures: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub success_rate: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub avg_latency_ms: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+    /// Time left in quarantine.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retry_in_ms: Option<f64>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn urls(names: &[&str]) -> Vec<String> {
+        names.iter().map(|n| n.to_string()).collect()
+    }
+
+    #[test]
+    fn test_rank_prefers_reliable_then_fast() {
+        let endpoints = urls(&["a", "b", "c", "d"]);
+        let mut health = RpcHealth::default();
+        assert_eq!(health.rank(&endpoints, 0.0), vec![0, 1, 2, 3]);
+
+        health.record_success("a", 300.0);
+        health.record_success("b", 80.0);
+        health.record_failure("c", "timeout", 0.0);
+        // d is untried and gets probed first; c's failure sends it last.
+        assert_eq!(health.rank(&endpoints, 0.0), vec![3, 1, 0, 2]);
+
+        health.record_success("c", 10.0);
+        assert_eq!(health.rank(&endpoints, 0.0), vec![3, 2, 1, 0]);
+    }
+
+    #[test]
+    fn test_quarantine_and_recovery() {
+        let endpoints = urls(&["a", "b"]);
+        let mut health = RpcHealth::default();
+        health.record_success("b", 500.0);
+        assert_eq!(health.record_failure("a", "bad proof", 0.0), None);
+        assert_eq!(health.record_failure("a", "bad proof", 0.0), None);
+        assert_eq!(health.record_failure("a", "bad proof", 0.0), Some(QUARANTINE_BASE_MS));
+        assert_eq!(health.record_failure("a", "bad proof", 0.0), Some(2.0 * QUARANTINE_BASE_MS));
+        assert_eq!(health.rank(&endpoints, 1_000.0), vec![1, 0]);
+
+        let report = health.report(1_000.0);
+        assert_eq!(report[1].url, "a");
+        assert!(!report[1].healthy);
+        assert_eq!(report[1].retry_in_ms, Some(2.0 * QUARANTINE_BASE_MS - 1_000.0));
+        assert_eq!(report[1].success_rate, Some(0.0));
+        assert_eq!(report[1].last_error.as_deref(), Some("bad proof"));
+
+        // Quarantine expires; a success clears the failure streak.
+        assert!(health.report(2.0 * QUARANTINE_BASE_MS).iter().all(|e| e.healthy));
+        health.record_success("a", 20.0);
+        assert_eq!(health.rank(&endpoints, 0.0), vec![0, 1]);
+    }
+
+    #[test]
+    fn test_quarantine_is_capped() {
+        let mut health = RpcHealth::default();
+        let longest = (0..40).filter_map(|_| health.record_failure("a", "down", 0.0)).last();
+        assert_eq!(longest, Some(QUARANTINE_MAX_MS));
+    }
+
+    #[test]
+    fn test_all_quarantined_still_ranked() {
+        let endpoints = urls(&["a", "b"]);
+        let mut health = RpcHealth::default();
+        for _ in 0..QUARANTINE_AFTER {
+            health.record_failure("a", "down", 100.0);
+            health.record_failure("b", "down", 0.0);
+        }
+        // b recovers first, so it is tried first.
+        assert_eq!(health.rank(&endpoints, 200.0), vec![1, 0]);
+    }
+}
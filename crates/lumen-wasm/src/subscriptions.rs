@@ -0,0 +1,128 @@
+//! EIP-1193 style subscriptions (`eth_subscribe`) fed by verified chain events.
+//!
+//! Subscribers are only notified from the update paths, after BLS
+//! verification has advanced the client — a subscription never sees a
+//! header the client has not verified itself.
+
+/// The event streams a subscription can follow.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SubscriptionKind {
+    /// A new BLS-verified execution header.
+    NewHeads,
+}
+
+impl SubscriptionKind {
+    pub fn parse(kind: &str) -> Result<Self, String> {
+        match kind {
+            "newHeads" => Ok(SubscriptionKind::NewHeads),
+            "logs" | "newPendingTransactions" | "syncing" => Err(format!(
+                "subscription {:?} cannot be served from verified data",
+                kind
+            )),
+            other => Err(format!("unknown subscription kind {:?}", other)),
+        }
+    }
+}
+
+struct Subscription<C> {
+    id: String,
+    kind: SubscriptionKind,
+    callback: C,
+}
+
+/// Registered subscriptions, generic over the callback so the bookkeeping
+/// is testable without a JS runtime.
+pub struct SubscriptionRegistry<C> {
+    subscriptions: Vec<Subscription<C>>,
+    next_id: u64,
+}
+
+impl<C> Default for SubscriptionRegistry<C> {
+    fn default() -> Self {
+        Self {
+            subscriptions: Vec::new(),
+            next_id: 1,
+        }
+    }
+}
+
+impl<C> SubscriptionRegistry<C> {
+    /// Register `callback` for `kind`, returning a hex subscription id as
+    /// eth_subscribe does.
+    pub fn subscribe(&mut self, kind: SubscriptionKind, callback: C) -> String {
+        let id = format!("0x{:x}", self.next_id);
+        self.next_id += 1;
+        self.subscriptions.push(Subscription {
+            id: id.clone(),
+            kind,
+            callback,
+        });
+        id
+    }
+
+    /// Remove a subscription. Returns false if `id` was not registered.
+    pub fn unsubscribe(&mut self, id: &str) -> bool {
+        let before = self.subscriptions.len();
+        self.subscriptions.retain(|s| !s.id.eq_ignore_ascii_case(id));
+        self.subscriptions.len() != before
+    }
+
+    pub fn len(&self) -> usize {
+        self.subscriptions.len()
+    }
+
+    /// Subscriptions following `kind`, as `(id, callback)` pairs.
+    pub fn subscribers(&self, kind: SubscriptionKind) -> impl Iterator<Item = (&str, &C)> {
+        self.subscriptions
+            .iter()
+            .filter(move |s| s.kind == kind)
+            .map(|s| (s.id.as_str(), &s.callback))
+    }
+}
+
+/// The payload delivered to a subscriber, shaped like an EIP-1193 `message` event.
+pub fn subscription_message(id: &str, result: serde_json::Value) -> serde_json::Value {
+    serde_json::json!({
+        "type": "eth_subscription",
+        "data": { "subscription": id, "result": result },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_kind() {
+        assert_eq!(SubscriptionKind::parse("newHeads").unwrap(), SubscriptionKind::NewHeads);
+        assert!(SubscriptionKind::parse("logs").is_err());
+        assert!(SubscriptionKind::parse("bogus").is_err());
+    }
+
+    #[test]
+    fn test_subscribe_and_unsubscribe() {
+        let mut registry = SubscriptionRegistry::default();
+        let a = registry.subscribe(SubscriptionKind::NewHeads, 1u32);
+        let b = registry.subscribe(SubscriptionKind::NewHeads, 2u32);
+        assert_eq!(a, "0x1");
+        assert_eq!(b, "0x2");
+
+        let callbacks: Vec<u32> = registry.subscribers(SubscriptionKind::NewHeads).map(|(_, c)| *c).collect();
+        assert_eq!(callbacks, vec![1, 2]);
+
+        assert!(registry.unsubscribe(&a));
+        assert!(!registry.unsubscribe(&a));
+        assert_eq!(registry.subscribers(SubscriptionKind::NewHeads).count(), 1);
+
+        // Ids are never reused.
+        assert_eq!(registry.subscribe(SubscriptionKind::NewHeads, 3u32), "0x3");
+    }
+
+    #[test]
+    fn test_subscription_message_shape() {
+        let message = subscription_message("0x1", serde_json::json!({"number": "0x10"}));
+        assert_eq!(message["type"], "eth_subscription");
+        assert_eq!(message["data"]["subscription"], "0x1");
+        assert_eq!(message["data"]["result"]["number"], "0x10");
+    }
+}
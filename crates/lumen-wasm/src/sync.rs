@@ -0,0 +1,182 @@
+//! Helpers for `sync_to_head`: the checkpoint → bootstrap → backfill →
+//! finality pipeline as a single call, and for `start_finality_polling`,
+//! which repeats the last two stages to stay at the head.
+//!
+//! Each stage reuses the verified path that exists as its own method; this
+//! module only decides what to fetch and how to report progress.
+
+use crate::network::BeaconPayload;
+use lumen_core::types::beacon::{SLOTS_PER_EPOCH, SLOTS_PER_SYNC_COMMITTEE_PERIOD};
+use serde::Serialize;
+
+/// Most updates a beacon node serves per `light_client/updates` request
+/// (`MAX_REQUEST_LIGHT_CLIENT_UPDATES`).
+pub const MAX_UPDATES_PER_REQUEST: u64 = 128;
+
+/// Beacon API path for the latest finality update.
+pub const FINALITY_UPDATE_PATH: &str = "/eth/v1/beacon/light_client/finality_update";
+
+/// Shortest interval `start_finality_polling` accepts. A new finality
+/// update appears at most once per epoch, so polling faster only adds load.
+pub const MIN_POLL_INTERVAL_MS: u32 = 1_000;
+
+/// How close the finalized head gets to the end of its period before
+/// polling fetches a still-missing next committee on its own. Finality
+/// trails the chain by about two epochs, so the first update signed by the
+/// next committee arrives while the finalized head is still this close.
+pub const PREFETCH_WINDOW_SLOTS: u64 = 4 * SLOTS_PER_EPOCH;
+
+/// Stages reported to `on_progress` callbacks. `sync_to_head` goes through
+/// the first five in order; `verify_storage_batch` reports `Proofs`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncStage {
+    Checkpoint,
+    Bootstrap,
+    Backfill,
+    Finality,
+    Synced,
+    Proofs,
+}
+
+/// A progress event, delivered to JS as `{ stage, message, finalized_slot?,
+/// done?, total? }`.
+#[derive(Debug, Serialize)]
+pub struct SyncProgress {
+    pub stage: SyncStage,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub finalized_slot: Option<u64>,
+    /// Items finished so far in a stage that works through a known number
+    /// of them: period updates while backfilling, slots in a storage batch.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub done: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total: Option<u64>,
+}
+
+/// Whether item `done` of `total` gets a progress event: about one per
+/// percent, and always the last, so large batches do not call back into JS
+/// for every item.
+pub fn should_report(done: u64, total: u64) -> bool {
+    done == total || done.is_multiple_of((total / 100).max(1))
+}
+
+/// Agreement required when the caller gives only a provider list: a strict
+/// majority, so no single provider decides the checkpoint on its own
+/// unless it is the only one.
+pub fn majority(providers: usize) -> usize {
+    providers / 2 + 1
+}
+
+/// The `(start_period, count)` of committee updates needed to carry a client
+/// at `state_period` up to `head_period`, or `None` if it is already there
+/// and holds the next committee.
+///
+/// The update for the client's own period is included when the next
+/// committee is missing: it is what carries that committee.
+pub fn backfill_range(state_period: u64, head_period: u64, has_next_committee: bool) -> Option<(u64, u64)> {
+    if head_period < state_period || (head_period == state_period && has_next_committee) {
+        return None;
+    }
+    let count = (head_period - state_period + 1).min(MAX_UPDATES_PER_REQUEST);
+    Some((state_period, count))
+}
+
+/// The period whose update to fetch now so the next committee is known
+/// before the rotation: the head's own, once a client finalized at
+/// `head_slot` without the next committee is within
+/// [`PREFETCH_WINDOW_SLOTS`] of the period's end.
+pub fn prefetch_period(head_slot: u64, has_next_committee: bool) -> Option<u64> {
+    if head_slot == 0 || has_next_committee {
+        return None;
+    }
+    let slots_left = SLOTS_PER_SYNC_COMMITTEE_PERIOD - head_slot % SLOTS_PER_SYNC_COMMITTEE_PERIOD;
+    (slots_left <= PREFETCH_WINDOW_SLOTS).then_some(head_slot / SLOTS_PER_SYNC_COMMITTEE_PERIOD)
+}
+
+/// Beacon API path for `count` period updates starting at `start_period`.
+pub fn updates_path(start_period: u64, count: u64) -> String {
+    format!(
+        "/eth/v1/beacon/light_client/updates?start_period={}&count={}",
+        start_period, count
+    )
+}
+
+/// GET `path` from each endpoint in turn, returning the first success.
+/// SSZ is requested first; see [`crate::network::fetch_beacon`].
+pub(crate) async fn fetch_from_any(endpoints: &[String], path: &str) -> Result<BeaconPayload, String> {
+    let mut errors = Vec::new();
+    for endpoint in endpoints {
+        let url = format!("{}{}", endpoint.trim_end_matches('/'), path);
+        match crate::network::fetch_beacon(&url).await {
+            Ok(body) => return Ok(body),
+            Err(e) => errors.push(format!("{}: {}", endpoint, e)),
+        }
+    }
+    Err(format!("no endpoint served {} ({})", path, errors.join("; ")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_majority() {
+        assert_eq!(majority(1), 1);
+        assert_eq!(majority(2), 2);
+        assert_eq!(majority(3), 2);
+        assert_eq!(majority(4), 3);
+    }
+
+    #[test]
+    fn test_backfill_range() {
+        // Freshly bootstrapped in the head's period: fetch its update for the next committee.
+        assert_eq!(backfill_range(1200, 1200, false), Some((1200, 1)));
+        assert_eq!(backfill_range(1200, 1200, true), None);
+        // Resumed three periods behind.
+        assert_eq!(backfill_range(1197, 1200, true), Some((1197, 4)));
+        // Capped at one request's worth.
+        assert_eq!(backfill_range(0, 1000, false), Some((0, MAX_UPDATES_PER_REQUEST)));
+        assert_eq!(backfill_range(1201, 1200, false), None);
+    }
+
+    #[test]
+    fn test_prefetch_period() {
+        let period_end = 1201 * SLOTS_PER_SYNC_COMMITTEE_PERIOD;
+        assert_eq!(prefetch_period(period_end - PREFETCH_WINDOW_SLOTS - 1, false), None);
+        assert_eq!(prefetch_period(period_end - PREFETCH_WINDOW_SLOTS, false), Some(1200));
+        assert_eq!(prefetch_period(period_end - 1, false), Some(1200));
+        // Already known, or nothing finalized yet to place in a period.
+        assert_eq!(prefetch_period(period_end - 1, true), None);
+        assert_eq!(prefetch_period(0, false), None);
+        // The first slot of the next period is far from its end.
+        assert_eq!(prefetch_period(period_end, false), None);
+    }
+
+    #[test]
+    fn test_should_report() {
+        assert!((1..=5).all(|done| should_report(done, 5)));
+        let reported: Vec<u64> = (1..=1000).filter(|&done| should_report(done, 1000)).collect();
+        assert_eq!(reported.len(), 100);
+        assert_eq!(reported[0], 10);
+        assert!(should_report(1001, 1001));
+    }
+
+    #[test]
+    fn test_progress_serialization() {
+        let progress = SyncProgress {
+            stage: SyncStage::Backfill,
+            message: "4 periods".to_string(),
+            finalized_slot: None,
+            done: Some(1),
+            total: Some(4),
+        };
+        let json = serde_json::to_value(&progress).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({"stage": "backfill", "message": "4 periods", "done": 1, "total": 4})
+        );
+        assert_eq!(updates_path(5, 2), "/eth/v1/beacon/light_client/updates?start_period=5&count=2");
+    }
+}
@@ -0,0 +1,848 @@
+//! TypeScript declarations for the objects `LumenClient` returns.
+//!
+//! wasm-bindgen types every `JsValue` as `any`. The interfaces below are
+//! emitted into the generated `.d.ts`, and methods name them through
+//! `unchecked_return_type` / `unchecked_param_type`, so TypeScript callers
+//! get the real shapes with no extra conversion at runtime. The Rust structs
+//! remain the source of truth; the tests check that every interface lists
+//! exactly the fields its struct serializes.
+
+use wasm_bindgen::prelude::*;
+
+// Only referenced from the custom section on wasm32, and from the tests.
+#[allow(dead_code)]
+const TS_INTERFACES: &str = r#"
+export interface AccountStateResponse {
+  nonce: number;
+  balance: string;
+  storage_root: string;
+  code_hash: string;
+  is_contract: boolean;
+  verified: boolean;
+  verified_against_slot: number;
+}
+
+export interface StorageValueResponse {
+  value: string;
+  verified: boolean;
+  verified_against_slot: number;
+}
+
+export interface StorageSlotResult {
+  key: string;
+  value?: string;
+  verified: boolean;
+  error?: string;
+}
+
+export interface StorageBatchResponse {
+  storage_root: string;
+  slots: StorageSlotResult[];
+  verified_count: number;
+  verified_against_slot: number;
+}
+
+export interface LogResponse {
+  address: string;
+  topics: string[];
+  data: string;
+}
+
+/** A receipt as committed to by a verified receipts root. */
+export interface ReceiptProofResponse {
+  tx_type: number;
+  status: number;
+  cumulative_gas_used: number;
+  logs_bloom: string;
+  logs: LogResponse[];
+  transaction_index: number;
+  block_number: number;
+  receipts_root: string;
+  verified: boolean;
+}
+
+/** One log of a verified receipt; `log_index` counts within the receipt. */
+export interface VerifiedLogResponse extends LogResponse {
+  log_index: number;
+  transaction_index: number;
+  block_number: number;
+  verified: boolean;
+}
+
+/**
+ * An ERC-4337 `UserOperationEvent` from a verified receipt. `success` is
+ * false if the operation's call reverted; `paymaster` is null when the
+ * account paid for itself. Amounts are hex quantities.
+ */
+export interface VerifiedUserOperation {
+  user_op_hash: string;
+  entry_point: string;
+  sender: string;
+  paymaster: string | null;
+  nonce: string;
+  success: boolean;
+  actual_gas_cost: string;
+  actual_gas_used: string;
+  log_index: number;
+  transaction_index: number;
+  block_number: number;
+  verified: boolean;
+}
+
+/** A transaction proven to be in a verified block; `to` is absent for contract creation. */
+export interface VerifiedTransactionResponse {
+  transaction_hash: string;
+  transaction_index: number;
+  block_hash: string;
+  block_number: number;
+  tx_type: number;
+  chain_id?: number;
+  nonce: number;
+  gas_price?: string;
+  max_fee_per_gas?: string;
+  max_priority_fee_per_gas?: string;
+  gas_limit: number;
+  to?: string;
+  value: string;
+  input: string;
+  verified: boolean;
+}
+
+/** A header whose hash matches a verified block; fork fields are absent before their fork. */
+export interface VerifiedBlockHeaderResponse {
+  block_hash: string;
+  block_number: number;
+  parent_hash: string;
+  ommers_hash: string;
+  miner: string;
+  state_root: string;
+  transactions_root: string;
+  receipts_root: string;
+  logs_bloom: string;
+  difficulty: string;
+  gas_limit: number;
+  gas_used: number;
+  timestamp: number;
+  extra_data: string;
+  mix_hash: string;
+  nonce: string;
+  base_fee_per_gas?: string;
+  withdrawals_root?: string;
+  blob_gas_used?: number;
+  excess_blob_gas?: number;
+  parent_beacon_block_root?: string;
+  requests_hash?: string;
+  verified: boolean;
+}
+
+export interface SyncStateResponse {
+  head_slot: number;
+  current_period: number;
+  has_next_committee: boolean;
+  has_execution_root: boolean;
+  is_synced: boolean;
+}
+
+export interface FinalityUpdateResult {
+  verified: boolean;
+  advanced: boolean;
+  finalized_slot: number;
+  execution_state_root: string;
+  execution_block_number: number;
+  sync_participation: number;
+  message: string;
+}
+
+export interface CommitteeBackfillResult {
+  advanced: number;
+  committees_stored: number;
+  skipped: number;
+  finalized_slot: number;
+  period: number;
+  has_next_committee: boolean;
+}
+
+export interface VerifiedAccountResponse {
+  nonce: number;
+  balance_hex: string;
+  storage_root: string;
+  code_hash: string;
+  is_contract: boolean;
+  verified: boolean;
+  verified_against_slot: number;
+  proof_nodes_verified: number;
+  rpc_claimed_balance: string;
+  discrepancies: RpcDiscrepancy[];
+}
+
+export interface ExecutionStateResponse {
+  has_state_root: boolean;
+  state_root: string;
+  block_number: number;
+  finalized_slot: number;
+}
+
+export interface FetchVerifyAccountResult {
+  nonce: number;
+  balance_hex: string;
+  storage_root: string;
+  code_hash: string;
+  is_contract: boolean;
+  verified: boolean;
+  finalized_block: number;
+  proof_block: number;
+  proof_nodes_verified: number;
+  rpc_endpoint: string;
+  rpc_claimed_balance: string;
+  discrepancies: RpcDiscrepancy[];
+}
+
+/** An eth_getProof field whose claimed value differs from the verified one. */
+export interface RpcDiscrepancy {
+  field: "balance" | "nonce" | "codeHash" | "storageHash";
+  claimed: string;
+  verified: string;
+}
+
+export interface VerifiedSlotValue {
+  slot: string;
+  value: string;
+}
+
+export interface StorageFetchResult {
+  address: string;
+  storage_root: string;
+  slots: VerifiedSlotValue[];
+  verified: boolean;
+  finalized_block: number;
+  proof_block: number;
+  proof_nodes_verified: number;
+  rpc_endpoint: string;
+}
+
+/** A receipt as returned by eth_getTransactionReceipt, after verification. */
+export interface VerifiedReceipt {
+  transactionHash: string;
+  transactionIndex: string;
+  blockHash: string;
+  blockNumber: string;
+  status: string;
+  verified: true;
+  [field: string]: unknown;
+}
+
+export interface BroadcastRejection {
+  endpoint: string;
+  error: string;
+}
+
+export interface BroadcastOutcome {
+  tx_hash: string;
+  accepted: string[];
+  rejected: BroadcastRejection[];
+}
+
+/**
+ * A finding of `preflight`. Errors mean no node would accept the
+ * transaction; warnings may clear as the chain moves on. Codes:
+ * "wrong_chain", "nonce_too_low", "gas_limit_too_low",
+ * "gas_limit_above_block", "priority_fee_above_max_fee" and
+ * "insufficient_funds" (errors); "no_replay_protection", "nonce_gap" and
+ * "fee_below_base_fee" (warnings).
+ */
+export interface PreflightIssue {
+  severity: "error" | "warning";
+  code: string;
+  message: string;
+}
+
+/**
+ * Returned by `preflight`, judged at the finalized block `block_number`.
+ * `max_cost` is null if gas limit times fee cap plus value overflows.
+ */
+export interface PreflightReport {
+  tx_hash: string;
+  sender: string;
+  nonce: number;
+  account_nonce: number;
+  balance: string;
+  max_cost: string | null;
+  base_fee_per_gas: string;
+  block_number: number;
+  ok: boolean;
+  issues: PreflightIssue[];
+}
+
+export interface TransactionEvent {
+  tx_hash: string;
+  status: "pending" | "included" | "finalized-verified" | "failed";
+  block_number?: number;
+  receipt?: VerifiedReceipt;
+  error?: string;
+}
+
+export interface CheckpointProviderHealth {
+  url: string;
+  healthy: boolean;
+  slot?: number;
+  block_root?: string;
+  agrees: boolean;
+  latency_ms: number;
+  error?: string;
+}
+
+export interface CheckpointHealthReport {
+  providers: CheckpointProviderHealth[];
+  agreed_root?: string;
+  agreed_slot?: number;
+  agreeing: number;
+  healthy: number;
+  total: number;
+}
+
+export interface EndpointHealth {
+  url: string;
+  healthy: boolean;
+  successes: number;
+  failures: number;
+  consecutive_failures: number;
+  success_rate?: number;
+  avg_latency_ms?: number;
+  last_error?: string;
+  retry_in_ms?: number;
+}
+
+/**
+ * Returned by `resolve_ens`. `resolver` is null for unregistered names;
+ * `address` also when the resolver holds no ETH address for the name.
+ */
+export interface EnsResolution {
+  name: string;
+  node: string;
+  resolver: string | null;
+  address: string | null;
+  block_number: number;
+  proof_nodes: number;
+}
+
+export type HealthStatus = "syncing" | "stale" | "degraded" | "healthy";
+
+/** As last reported through `set_p2p_status`. */
+export interface P2pStatus {
+  connection_mode: "direct-webtransport" | "direct-webrtc" | "relay" | "disconnected";
+  peer_count: number;
+}
+
+/**
+ * Returned by `health`. Ages and lags are `null` on networks without a
+ * known genesis time; `last_update_at_ms` is `null` until the head first
+ * advances.
+ */
+export interface ClientHealth {
+  status: HealthStatus;
+  head_slot: number;
+  checkpoint_slot: number;
+  checkpoint_age_ms: number | null;
+  finalized_lag_slots: number | null;
+  last_update_at_ms: number | null;
+  rpc_endpoints: EndpointHealth[];
+  p2p: P2pStatus;
+}
+
+export interface SyncProgress {
+  stage: "checkpoint" | "bootstrap" | "backfill" | "finality" | "synced" | "proofs";
+  message: string;
+  finalized_slot?: number;
+  done?: number;
+  total?: number;
+}
+
+export type ClientEvent =
+  | { type: "head_advanced"; finalized_slot: number; period: number }
+  | { type: "execution_root_changed"; state_root: string; block_number: number }
+  | { type: "update_rejected"; update_slot: number; reason: string }
+  | { type: "rpc_discrepancy"; address: string; endpoint: string | null; discrepancies: RpcDiscrepancy[] }
+  | { type: "error"; source: string; code: number; message: string };
+
+/** Observations at or below `le_ms`; `le_ms` is null for the last, unbounded bucket. */
+export interface HistogramBucket {
+  le_ms: number | null;
+  count: number;
+}
+
+export interface Histogram {
+  count: number;
+  sum_ms: number;
+  max_ms: number;
+  buckets: HistogramBucket[];
+}
+
+/** One bounded cache or ring buffer in `ResourceStats`. */
+export interface CacheStats {
+  entries: number;
+  capacity: number;
+  approx_bytes: number;
+  hits: number | null;
+  misses: number | null;
+}
+
+/**
+ * Returned by `resource_stats`. `wasm_memory_bytes` is the whole module's
+ * heap, shared by every client in the worker.
+ */
+export interface ResourceStats {
+  wasm_memory_bytes: number | null;
+  state_bytes: number;
+  response_cache: CacheStats;
+  aggregate_cache: CacheStats;
+  execution_history: CacheStats;
+  watched_transactions: number;
+  subscriptions: number;
+  tracked_endpoints: number;
+}
+
+export interface LumenMetrics {
+  head_slot: number;
+  target_slot: number | null;
+  is_initial_sync_complete: boolean;
+  updates_processed: number;
+  updates_rejected: number;
+  updates_duplicate: number;
+  proofs_verified: number;
+  proofs_rejected: number;
+  bls_verify_ms: Histogram;
+  mpt_verify_ms: Histogram;
+  rpc_latency_ms: Histogram;
+}
+
+export interface LogRecord {
+  level: "error" | "warn" | "info" | "debug" | "trace";
+  target: string;
+  message: string;
+}
+
+/** Thrown by every `LumenClient` method that fails. */
+export interface LumenError extends Error {
+  name: "LumenError";
+  code: number;
+  kind: string;
+  category: "parse" | "consensus" | "proof" | "network" | "internal";
+}
+
+/** The argument of `LumenClient.request`, per EIP-1193. */
+export interface RequestArguments {
+  method: string;
+  params?: unknown[];
+}
+
+/**
+ * Rejection of `LumenClient.request`, per EIP-1193. `code` is 4001, 4200,
+ * 4900 or 4901 for the standard provider errors, else a JSON-RPC code.
+ */
+export interface ProviderRpcError extends Error {
+  name: "ProviderRpcError";
+  code: number;
+  data?: unknown;
+}
+
+export interface EIP6963ProviderInfo {
+  uuid: string;
+  name: string;
+  icon: string;
+  rdns: string;
+}
+
+/** The `detail` of an `eip6963:announceProvider` event. */
+export interface EIP6963ProviderDetail {
+  info: EIP6963ProviderInfo;
+  provider: { request(args: RequestArguments): Promise<unknown> };
+}
+
+export type VerificationPath =
+  | "local"
+  | "finalized_header"
+  | "header_chain"
+  | "account_proof"
+  | "storage_proof"
+  | "transaction_hash"
+  | "unverified";
+
+/**
+ * The `lumen` key of every successful `handle_request` response: how the
+ * result was checked. Keys that do not apply are `null`, never missing.
+ * `heuristic` is true if part of the result is estimated rather than
+ * verified: the priority fee in `eth_gasPrice` and `eth_feeHistory` rewards.
+ */
+export interface ResponseMeta {
+  verified: boolean;
+  path: VerificationPath;
+  finalized_slot: number | null;
+  block_number: number | null;
+  proof_nodes: number | null;
+  heuristic: boolean;
+}
+
+/** A command for `dispatch_worker_message`, sent as UTF-8 JSON bytes. */
+export interface WorkerEnvelope {
+  v: 1;
+  id: number;
+  command: WorkerCommand;
+}
+
+export type WorkerCommand =
+  | { type: "init"; network?: string; beacon_endpoints?: string[]; rpc_endpoints?: string[] }
+  | { type: "rpc"; request: { method: string; params?: unknown[]; id?: unknown } }
+  | { type: "start_polling"; interval_ms: number; chain_id?: number }
+  | { type: "stop_polling"; chain_id?: number }
+  | { type: "get_sync_state"; chain_id?: number }
+  | { type: "cancel"; target: number };
+
+/** `code`, `kind` and `category` as on a thrown `LumenError`. */
+export interface WorkerError {
+  code: number;
+  kind: string;
+  category: string;
+  message: string;
+}
+
+export type CommandResult =
+  | { kind: "ready"; chain_id: number; head_slot: number }
+  | { kind: "rpc"; response: { id: unknown; result?: unknown; error?: { code: number; message: string }; lumen?: ResponseMeta } }
+  | { kind: "sync_state"; state: SyncStateResponse }
+  | { kind: "done" }
+  | { kind: "error"; error: WorkerError };
+
+/** Everything the worker posts back, as UTF-8 JSON bytes. */
+export type WorkerMessage = { v: 1 } & (
+  | { type: "response"; id: number | null; result: CommandResult }
+  | { type: "event"; chain_id: number; event: ClientEvent }
+  | { type: "progress"; progress: SyncProgress }
+);
+
+export interface NewHeadsMessage {
+  type: "eth_subscription";
+  data: { subscription: string; result: Record<string, unknown> };
+}
+"#;
+
+#[wasm_bindgen(typescript_custom_section)]
+const TS_SECTION: &str = TS_INTERFACES;
+
+#[cfg(test)]
+mod tests {
+    use super::TS_INTERFACES;
+    use serde::Serialize;
+    use std::collections::BTreeSet;
+
+    /// Field names declared by `export interface name { ... }`.
+    fn interface_fields(name: &str) -> BTreeSet<String> {
+        let start = TS_INTERFACES
+            .find(&format!("export interface {} ", name))
+            .unwrap_or_else(|| panic!("no interface {}", name));
+        let body = &TS_INTERFACES[start..];
+        let body = &body[body.find('{').unwrap() + 1..body.find("\n}").unwrap()];
+        body.lines()
+            .filter_map(|line| line.trim().split(':').next())
+            .map(|field| field.trim_end_matches('?').to_string())
+            .filter(|field| !field.is_empty() && !field.starts_with('['))
+            .collect()
+    }
+
+    /// Every field `value` can serialize, including optional ones it omitted.
+    fn assert_matches(name: &str, value: &impl Serialize, omitted: &[&str]) {
+        assert_matches_fields(value, omitted, interface_fields(name));
+    }
+
+    fn assert_matches_fields(value: &impl Serialize, omitted: &[&str], expected: BTreeSet<String>) {
+        let json = serde_json::to_value(value).unwrap();
+        let mut fields: BTreeSet<String> = json.as_object().unwrap().keys().cloned().collect();
+        fields.extend(omitted.iter().map(|f| f.to_string()));
+        assert_eq!(fields, expected, "interface is out of date");
+    }
+
+    #[test]
+    fn test_interfaces_match_response_structs() {
+        assert_matches(
+            "FinalityUpdateResult",
+            &crate::FinalityUpdateResult {
+                verified: true,
+                advanced: true,
+                finalized_slot: 1,
+                execution_state_root: String::new(),
+                execution_block_number: 1,
+                sync_participation: 512,
+                message: String::new(),
+            },
+            &[],
+        );
+        assert_matches(
+            "ResponseMeta",
+            &crate::provider::ResponseMeta::new(crate::provider::VerificationPath::Local),
+            &[],
+        );
+        assert_matches("CommitteeBackfillResult", &crate::CommitteeBackfillResult::default(), &[]);
+        let log = crate::LogResponse { address: String::new(), topics: Vec::new(), data: String::new() };
+        let mut log_fields = interface_fields("LogResponse");
+        log_fields.extend(interface_fields("VerifiedLogResponse"));
+        assert_matches_fields(
+            &crate::VerifiedLogResponse { log, log_index: 0, transaction_index: 0, block_number: 1, verified: true },
+            &[],
+            log_fields,
+        );
+        assert_matches(
+            "VerifiedUserOperation",
+            &crate::VerifiedUserOperation {
+                user_op_hash: String::new(),
+                entry_point: String::new(),
+                sender: String::new(),
+                paymaster: None,
+                nonce: String::new(),
+                success: true,
+                actual_gas_cost: String::new(),
+                actual_gas_used: String::new(),
+                log_index: 0,
+                transaction_index: 0,
+                block_number: 1,
+                verified: true,
+            },
+            &[],
+        );
+        assert_matches(
+            "VerifiedTransactionResponse",
+            &crate::VerifiedTransactionResponse {
+                transaction_hash: String::new(),
+                transaction_index: 0,
+                block_hash: String::new(),
+                block_number: 1,
+                tx_type: 2,
+                chain_id: Some(1),
+                nonce: 0,
+                gas_price: None,
+                max_fee_per_gas: None,
+                max_priority_fee_per_gas: None,
+                gas_limit: 21_000,
+                to: None,
+                value: String::new(),
+                input: String::new(),
+                verified: true,
+            },
+            &[],
+        );
+        assert_matches(
+            "VerifiedBlockHeaderResponse",
+            &crate::VerifiedBlockHeaderResponse {
+                block_hash: String::new(),
+                block_number: 1,
+                parent_hash: String::new(),
+                ommers_hash: String::new(),
+                miner: String::new(),
+                state_root: String::new(),
+                transactions_root: String::new(),
+                receipts_root: String::new(),
+                logs_bloom: String::new(),
+                difficulty: String::new(),
+                gas_limit: 30_000_000,
+                gas_used: 0,
+                timestamp: 0,
+                extra_data: String::new(),
+                mix_hash: String::new(),
+                nonce: String::new(),
+                base_fee_per_gas: None,
+                withdrawals_root: None,
+                blob_gas_used: None,
+                excess_blob_gas: None,
+                parent_beacon_block_root: None,
+                requests_hash: None,
+                verified: true,
+            },
+            &[],
+        );
+        assert_matches(
+            "ReceiptProofResponse",
+            &crate::ReceiptProofResponse {
+                tx_type: 2,
+                status: 1,
+                cumulative_gas_used: 21_000,
+                logs_bloom: String::new(),
+                logs: Vec::new(),
+                transaction_index: 0,
+                block_number: 1,
+                receipts_root: String::new(),
+                verified: true,
+            },
+            &[],
+        );
+        assert_matches(
+            "SyncStateResponse",
+            &crate::SyncStateResponse {
+                head_slot: 1,
+                current_period: 0,
+                has_next_committee: false,
+                has_execution_root: false,
+                is_synced: false,
+            },
+            &[],
+        );
+        assert_matches(
+            "ExecutionStateResponse",
+            &crate::ExecutionStateResponse {
+                has_state_root: false,
+                state_root: String::new(),
+                block_number: 0,
+                finalized_slot: 0,
+            },
+            &[],
+        );
+        assert_matches(
+            "StorageSlotResult",
+            &crate::StorageSlotResult::new(&[0; 32], Ok(lumen_core::types::uint::U256::ZERO)),
+            &[],
+        );
+        assert_matches(
+            "StorageFetchResult",
+            &crate::StorageFetchResult {
+                address: String::new(),
+                storage_root: String::new(),
+                slots: Vec::new(),
+                verified: true,
+                finalized_block: 0,
+                proof_block: 0,
+                proof_nodes_verified: 0,
+                rpc_endpoint: String::new(),
+            },
+            &[],
+        );
+        assert_matches(
+            "RpcDiscrepancy",
+            &crate::beacon_api::Discrepancy {
+                field: "balance".to_string(),
+                claimed: String::new(),
+                verified: String::new(),
+            },
+            &[],
+        );
+        assert_matches(
+            "VerifiedSlotValue",
+            &crate::VerifiedSlotValue {
+                slot: String::new(),
+                value: String::new(),
+            },
+            &[],
+        );
+    }
+
+    #[test]
+    fn test_interfaces_match_module_types() {
+        let error = crate::error::LumenError::new(crate::error::ErrorCode::InvalidInput, "bad");
+        let mut fields = interface_fields("LumenError");
+        fields.insert("message".to_string()); // inherited from Error
+        fields.remove("name");
+        let json = serde_json::to_value(error.info()).unwrap();
+        assert_eq!(json.as_object().unwrap().keys().cloned().collect::<BTreeSet<_>>(), fields);
+
+        let rejection = crate::provider::JsonRpcError {
+            code: crate::provider::UNSUPPORTED_METHOD,
+            message: "unsupported".to_string(),
+            data: None,
+        };
+        let mut fields = interface_fields("ProviderRpcError");
+        fields.insert("message".to_string()); // inherited from Error
+        fields.remove("name");
+        assert_matches_fields(&rejection, &["data"], fields);
+        let request = crate::provider::JsonRpcRequest {
+            method: "eth_chainId".to_string(),
+            params: Vec::new(),
+            id: serde_json::Value::Null,
+        };
+        let mut fields = interface_fields("RequestArguments");
+        fields.insert("id".to_string()); // accepted, but not part of EIP-1193
+        assert_matches_fields(&request, &[], fields);
+
+        let info = crate::eip6963::ProviderInfo::new(None, None, None, [0; 16]).unwrap();
+        assert_matches("EIP6963ProviderInfo", &info, &[]);
+
+        let event = crate::watcher::TransactionWatcher::default().watch("0x01", 0).unwrap();
+        assert_matches("TransactionEvent", &event, &["block_number", "receipt", "error"]);
+
+        let resolution = crate::ens::EnsResolution {
+            name: "vitalik.eth".to_string(),
+            node: String::new(),
+            resolver: None,
+            address: None,
+            block_number: 0,
+            proof_nodes: 0,
+        };
+        assert_matches("EnsResolution", &resolution, &[]);
+
+        let issue = crate::preflight::PreflightIssue {
+            severity: crate::preflight::Severity::Warning,
+            code: "nonce_gap",
+            message: String::new(),
+        };
+        assert_matches("PreflightIssue", &issue, &[]);
+        let report = crate::preflight::PreflightReport {
+            tx_hash: String::new(),
+            sender: String::new(),
+            nonce: 0,
+            account_nonce: 0,
+            balance: String::new(),
+            max_cost: None,
+            base_fee_per_gas: String::new(),
+            block_number: 0,
+            ok: true,
+            issues: vec![issue],
+        };
+        assert_matches("PreflightReport", &report, &[]);
+
+        let outcome = crate::provider::summarize_broadcast("0x01", vec![]);
+        assert_matches("BroadcastOutcome", &outcome, &[]);
+
+        assert_matches(
+            "CheckpointHealthReport",
+            &crate::checkpoint::health_report(&[]),
+            &["agreed_root", "agreed_slot"],
+        );
+        let mut health = crate::rpc_health::RpcHealth::default();
+        health.record_success("https://rpc.example", 10.0);
+        let report = health.report(0.0);
+        assert_matches("EndpointHealth", &report[0], &["last_error", "retry_in_ms"]);
+        let client = crate::LumenClient::from_state(
+            crate::tests::state([0; 32]),
+            lumen_core::types::network::NetworkConfig::mainnet(),
+        );
+        let health = client.health_report();
+        assert_matches("ClientHealth", &health, &[]);
+        assert_matches("P2pStatus", &health.p2p, &[]);
+        let stats = client.resource_report();
+        assert_matches("ResourceStats", &stats, &[]);
+        assert_matches("CacheStats", &stats.response_cache, &[]);
+
+        let error = crate::worker::WorkerError::from(error);
+        assert_matches("WorkerError", &error, &[]);
+
+        let metrics = crate::metrics::Metrics::default();
+        assert_matches("LumenMetrics", &metrics, &[]);
+        assert_matches("Histogram", &metrics.rpc_latency_ms, &[]);
+        assert_matches("HistogramBucket", &metrics.rpc_latency_ms.buckets[0], &[]);
+        assert_matches(
+            "LogRecord",
+            &crate::logging::LogRecord {
+                level: crate::logging::Level::Info,
+                target: "sync",
+                message: "",
+            },
+            &[],
+        );
+        assert_matches(
+            "SyncProgress",
+            &crate::sync::SyncProgress {
+                stage: crate::sync::SyncStage::Synced,
+                message: String::new(),
+                finalized_slot: Some(1),
+                done: None,
+                total: None,
+            },
+            &["done", "total"],
+        );
+    }
+}
@@ -0,0 +1,246 @@
+//! Transaction watcher: tracks transaction hashes until their receipts
+//! verify against finalized chain state.
+//!
+//! The client cannot run background tasks of its own: a long-running future
+//! holding `&LumenClient` would lock out `process_finality_update`, and the
+//! receipt can only verify once finality has advanced past its block. So the
+//! watcher is a state machine that the host drives, calling
+//! `poll_transactions` whenever it has fed the client a new finality update.
+//!
+//! Each transaction moves through
+//! `pending → included → finalized-verified`, or ends in `failed` if it
+//! reverted or was dropped. Only verified receipts settle a transaction: one
+//! that landed too far back to verify stays `included`. Only transitions are
+//! reported, so a poll that changes nothing yields no events.
+
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Polls without the transaction showing up in any block before it is
+/// reported as dropped. With one poll per finality update (~6.4 minutes),
+/// this is a little over an hour.
+pub const DROP_AFTER_POLLS: u32 = 10;
+
+/// Where a watched transaction currently stands.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TxStatus {
+    /// Not yet seen in a block.
+    Pending,
+    /// An RPC reports it in `block_number`, and its receipt has not verified
+    /// (yet). Unverified: the block may still be reorganized away.
+    Included { block_number: u64 },
+    /// The receipt verified against finalized state and the transaction succeeded.
+    FinalizedVerified {
+        block_number: u64,
+        receipt: serde_json::Value,
+    },
+    /// Terminal failure. `receipt` is set when the failure is a verified revert.
+    Failed {
+        reason: String,
+        receipt: Option<serde_json::Value>,
+    },
+}
+
+impl TxStatus {
+    pub fn name(&self) -> &'static str {
+        match self {
+            TxStatus::Pending => "pending",
+            TxStatus::Included { .. } => "included",
+            TxStatus::FinalizedVerified { .. } => "finalized-verified",
+            TxStatus::Failed { .. } => "failed",
+        }
+    }
+
+    pub fn is_terminal(&self) -> bool {
+        matches!(self, TxStatus::FinalizedVerified { .. } | TxStatus::Failed { .. })
+    }
+}
+
+/// A status change, as reported to the host.
+#[derive(Clone, Debug, Serialize, PartialEq, Eq)]
+pub struct TransactionEvent {
+    pub tx_hash: String,
+    pub status: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub block_number: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub receipt: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl TransactionEvent {
+    fn new(tx_hash: &str, status: &TxStatus) -> Self {
+        let (block_number, receipt, error) = match status {
+            TxStatus::Pending => (None, None, None),
+            TxStatus::Included { block_number } => (Some(*block_number), None, None),
+            TxStatus::FinalizedVerified {
+                block_number,
+                receipt,
+            } => (Some(*block_number), Some(receipt.clone()), None),
+            TxStatus::Failed { reason, receipt } => (None, receipt.clone(), Some(reason.clone())),
+        };
+        Self {
+            tx_hash: tx_hash.to_string(),
+            status: status.name(),
+            block_number,
+            receipt,
+            error,
+        }
+    }
+}
+
+/// A watched transaction.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WatchedTransaction {
+    /// The BLS-finalized execution block when watching started.
+    pub submitted_at_block: u64,
+    /// Polls that found the transaction in no block.
+    pub unseen_polls: u32,
+    pub status: TxStatus,
+}
+
+/// Watched transactions keyed by lowercase 0x-prefixed hash.
+/// Entries are removed once they reach a terminal status.
+#[derive(Debug, Default)]
+pub struct TransactionWatcher {
+    watched: BTreeMap<String, WatchedTransaction>,
+}
+
+impl TransactionWatcher {
+    /// Start watching `tx_hash`. Watching an already-watched hash is a no-op.
+    /// Returns the `pending` event if the hash is new.
+    pub fn watch(&mut self, tx_hash: &str, finalized_block: u64) -> Option<TransactionEvent> {
+        let key = normalize(tx_hash);
+        if self.watched.contains_key(&key) {
+            return None;
+        }
+        let event = TransactionEvent::new(&key, &TxStatus::Pending);
+        self.watched.insert(
+            key,
+            WatchedTransaction {
+                submitted_at_block: finalized_block,
+                unseen_polls: 0,
+                status: TxStatus::Pending,
+            },
+        );
+        Some(event)
+    }
+
+    /// Hashes of all transactions still being watched.
+    pub fn watched_hashes(&self) -> Vec<String> {
+        self.watched.keys().cloned().collect()
+    }
+
+    /// Record a poll that found the transaction in no block. After
+    /// [`DROP_AFTER_POLLS`] such polls the transaction is reported dropped.
+    pub fn record_unseen(&mut self, tx_hash: &str) -> Option<TransactionEvent> {
+        let entry = self.watched.get_mut(&normalize(tx_hash))?;
+        entry.unseen_polls += 1;
+        if entry.unseen_polls < DROP_AFTER_POLLS || entry.status != TxStatus::Pending {
+            return None;
+        }
+        let polls = entry.unseen_polls;
+        self.transition(
+            tx_hash,
+            TxStatus::Failed {
+                reason: format!("not seen in any block after {} polls; likely dropped", polls),
+                receipt: None,
+            },
+        )
+    }
+
+    /// Move `tx_hash` to `status`, returning an event if the status changed.
+    /// Terminal statuses stop the watch.
+    pub fn transition(&mut self, tx_hash: &str, status: TxStatus) -> Option<TransactionEvent> {
+        let key = normalize(tx_hash);
+        let entry = self.watched.get_mut(&key)?;
+        if entry.status == status {
+            return None;
+        }
+        let event = TransactionEvent::new(&key, &status);
+        if status.is_terminal() {
+            self.watched.remove(&key);
+        } else {
+            entry.status = status;
+        }
+        Some(event)
+    }
+
+    /// Stop watching `tx_hash`, returning its entry if it was watched.
+    pub fn unwatch(&mut self, tx_hash: &str) -> Option<WatchedTransaction> {
+        self.watched.remove(&normalize(tx_hash))
+    }
+
+    pub fn len(&self) -> usize {
+        self.watched.len()
+    }
+}
+
+fn normalize(tx_hash: &str) -> String {
+    let digits = tx_hash.strip_prefix("0x").unwrap_or(tx_hash);
+    format!("0x{}", digits.to_ascii_lowercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_watch_is_case_insensitive_and_idempotent() {
+        let mut watcher = TransactionWatcher::default();
+        assert_eq!(watcher.watch("0xABCD", 100).unwrap().status, "pending");
+        assert!(watcher.watch("abcd", 200).is_none());
+        assert_eq!(watcher.len(), 1);
+        assert_eq!(watcher.watched_hashes(), vec!["0xabcd".to_string()]);
+
+        assert_eq!(watcher.unwatch("0xAbCd").unwrap().submitted_at_block, 100);
+        assert!(watcher.unwatch("0xabcd").is_none());
+    }
+
+    #[test]
+    fn test_lifecycle_reports_only_transitions() {
+        let mut watcher = TransactionWatcher::default();
+        watcher.watch("0x01", 100);
+
+        let included = TxStatus::Included { block_number: 105 };
+        let event = watcher.transition("0x01", included.clone()).unwrap();
+        assert_eq!(event.status, "included");
+        assert_eq!(event.block_number, Some(105));
+        assert!(watcher.transition("0x01", included).is_none());
+
+        let receipt = serde_json::json!({"status": "0x1", "verified": true});
+        let event = watcher
+            .transition(
+                "0x01",
+                TxStatus::FinalizedVerified {
+                    block_number: 105,
+                    receipt: receipt.clone(),
+                },
+            )
+            .unwrap();
+        assert_eq!(event.status, "finalized-verified");
+        assert_eq!(event.receipt, Some(receipt));
+        assert_eq!(watcher.len(), 0);
+    }
+
+    #[test]
+    fn test_unseen_transaction_is_dropped() {
+        let mut watcher = TransactionWatcher::default();
+        watcher.watch("0x02", 100);
+        for _ in 1..DROP_AFTER_POLLS {
+            assert!(watcher.record_unseen("0x02").is_none());
+        }
+        let event = watcher.record_unseen("0x02").unwrap();
+        assert_eq!(event.status, "failed");
+        assert!(event.error.unwrap().contains("dropped"));
+        assert_eq!(watcher.len(), 0);
+    }
+
+    #[test]
+    fn test_event_serialization_skips_empty_fields() {
+        let event = TransactionEvent::new("0x03", &TxStatus::Pending);
+        let json = serde_json::to_value(&event).unwrap();
+        assert_eq!(json, serde_json::json!({"tx_hash": "0x03", "status": "pending"}));
+    }
+}
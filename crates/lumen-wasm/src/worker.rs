@@ -0,0 +1,401 @@
+//! The main-thread ↔ worker message protocol.
+//!
+//! A worker hosts one client per chain and is driven entirely through
+//! `dispatch_worker_message`: the main thread posts a command as UTF-8 JSON
+//! bytes, and the promise resolves to the response bytes to post back. The
+//! glue in the worker is one line:
+//!
+//! ```js
+//! self.onmessage = async (e) => self.postMessage(await dispatch_worker_message(e.data))
+//! ```
+//!
+//! Commands are `{ v, id, command: { type, ... } }`. Everything the worker
+//! sends is `{ v, type, ... }`: a `response` to the command with the same
+//! `id`, or an unsolicited `event` (a `ClientEvent`) or `progress` (a
+//! `SyncProgress` while `init` runs). Requests go to the active chain, the
+//! one last initialized or switched to with `wallet_switchEthereumChain`;
+//! polling and sync state commands take an optional `chain_id` instead.
+//! An `init` still syncing is aborted by a `cancel` naming its `id`, and
+//! polling by `stop_polling`; neither leaves work running in the worker.
+//! `v` is [`PROTOCOL_VERSION`]; a command
+//! for another version is refused rather than guessed at, so a stale worker
+//! bundle fails loudly.
+
+use crate::error::{ErrorCode, LumenError};
+use crate::provider::{JsonRpcRequest, JsonRpcResponse};
+use crate::registry::{LumenRegistry, Route};
+use crate::{LumenClient, SyncStateResponse};
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::rc::Rc;
+use wasm_bindgen::prelude::*;
+
+/// Bumped whenever a command or message changes shape.
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// A command from the main thread.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum WorkerCommand {
+    /// Sync a client for `network` from `beacon_endpoints` (empty: the
+    /// network's built-in providers), answering RPCs through `rpc_endpoints`.
+    /// It replaces any client for the same chain and becomes the active one.
+    Init {
+        #[serde(default = "default_network")]
+        network: String,
+        #[serde(default)]
+        beacon_endpoints: Vec<String>,
+        #[serde(default)]
+        rpc_endpoints: Vec<String>,
+    },
+    /// An EIP-1193 request, answered as by `LumenRegistry.handle_request`.
+    Rpc { request: JsonRpcRequest },
+    /// The commands below act on `chain_id`, or the active chain if unset.
+    StartPolling {
+        interval_ms: u32,
+        #[serde(default)]
+        chain_id: Option<u64>,
+    },
+    StopPolling {
+        #[serde(default)]
+        chain_id: Option<u64>,
+    },
+    GetSyncState {
+        #[serde(default)]
+        chain_id: Option<u64>,
+    },
+    /// Abort the `init` with id `target`, which then answers with a
+    /// `cancelled` error. Done even if it has already finished.
+    Cancel { target: u64 },
+}
+
+fn default_network() -> String {
+    "mainnet".to_string()
+}
+
+#[derive(Debug, Deserialize)]
+struct Envelope {
+    v: u32,
+    id: u64,
+    command: serde_json::Value,
+}
+
+/// What a command produced.
+#[derive(Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum CommandResult {
+    Ready { chain_id: u64, head_slot: u64 },
+    Rpc { response: JsonRpcResponse },
+    SyncState { state: SyncStateResponse },
+    Done,
+    Error { error: WorkerError },
+}
+
+/// A failed command, with the fields a thrown `LumenError` carries.
+#[derive(Debug, Serialize, PartialEq)]
+pub struct WorkerError {
+    pub code: u16,
+    pub kind: String,
+    pub category: String,
+    pub message: String,
+}
+
+impl From<LumenError> for WorkerError {
+    fn from(error: LumenError) -> Self {
+        let info = error.info();
+        // Both serialize to plain strings.
+        let name = |value: serde_json::Value| value.as_str().unwrap_or_default().to_string();
+        WorkerError {
+            code: info.code,
+            kind: name(serde_json::to_value(info.kind).unwrap_or_default()),
+            category: name(serde_json::to_value(info.category).unwrap_or_default()),
+            message: info.message,
+        }
+    }
+}
+
+impl WorkerError {
+    /// Read back a `LumenError` thrown by a client method.
+    fn from_js(error: &JsValue) -> Self {
+        let get = |key: &str| js_sys::Reflect::get(error, &JsValue::from_str(key)).ok();
+        let text = |key: &str| get(key).and_then(|v| v.as_string()).unwrap_or_default();
+        WorkerError {
+            code: get("code").and_then(|v| v.as_f64()).unwrap_or(0.0) as u16,
+            kind: text("kind"),
+            category: text("category"),
+            message: get("message")
+                .and_then(|v| v.as_string())
+                .unwrap_or_else(|| format!("{:?}", error)),
+        }
+    }
+}
+
+/// Everything the worker sends to the main thread.
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum WorkerMessage {
+    /// The answer to command `id`; null if the command was too malformed to have one.
+    Response { id: Option<u64>, result: CommandResult },
+    /// A `ClientEvent` of the client for `chain_id`.
+    Event { chain_id: u64, event: serde_json::Value },
+    Progress { progress: serde_json::Value },
+}
+
+#[derive(Serialize)]
+struct Outgoing<'a> {
+    v: u32,
+    #[serde(flatten)]
+    message: &'a WorkerMessage,
+}
+
+pub fn encode(message: &WorkerMessage) -> Vec<u8> {
+    serde_json::to_vec(&Outgoing {
+        v: PROTOCOL_VERSION,
+        message,
+    })
+    .unwrap_or_default()
+}
+
+/// Decode a command, checking its version. On failure, returns the command
+/// id if it could be read, for the error response.
+pub fn decode(bytes: &[u8]) -> Result<(u64, WorkerCommand), (Option<u64>, LumenError)> {
+    let envelope: Envelope = serde_json::from_slice(bytes).map_err(|e| {
+        let id = serde_json::from_slice::<serde_json::Value>(bytes)
+            .ok()
+            .and_then(|value| value.get("id").and_then(|id| id.as_u64()));
+        (id, LumenError::new(ErrorCode::InvalidJson, format!("Invalid worker message: {}", e)))
+    })?;
+    let id = envelope.id;
+    if envelope.v != PROTOCOL_VERSION {
+        return Err((
+            Some(id),
+            LumenError::new(
+                ErrorCode::UnsupportedProtocol,
+                format!("Worker protocol version {}, expected {}", envelope.v, PROTOCOL_VERSION),
+            ),
+        ));
+    }
+    let command = serde_json::from_value(envelope.command)
+        .map_err(|e| (Some(id), LumenError::new(ErrorCode::InvalidJson, format!("Invalid command: {}", e))))?;
+    Ok((id, command))
+}
+
+/// The worker's clients, where each one's polling fetches from, and the
+/// cancellable commands still running, by id.
+#[derive(Default)]
+struct Sessions {
+    registry: LumenRegistry,
+    beacon_endpoints_json: Vec<(u64, String)>,
+    running: Vec<(u64, crate::cancel::Cancellation)>,
+}
+
+thread_local! {
+    static SESSIONS: RefCell<Sessions> = RefCell::new(Sessions::default());
+}
+
+/// The client for `chain_id` (default: the active one) and its beacon endpoints.
+fn session(chain_id: Option<u64>) -> Result<(Rc<LumenClient>, String), LumenError> {
+    SESSIONS.with(|sessions| {
+        let sessions = sessions.borrow();
+        let client = match chain_id {
+            Some(chain_id) => sessions.registry.get(chain_id).ok_or_else(|| {
+                LumenError::new(ErrorCode::InvalidInput, format!("No client for chain {}; send init first", chain_id))
+            })?,
+            None => sessions
+                .registry
+                .active()
+                .ok_or_else(|| LumenError::new(ErrorCode::NotSynced, "Worker not initialized; send init first"))?,
+        };
+        let endpoints = sessions
+            .beacon_endpoints_json
+            .iter()
+            .find(|(id, _)| *id == client.network.chain_id)
+            .map(|(_, endpoints)| endpoints.clone())
+            .unwrap_or_default();
+        Ok((client, endpoints))
+    })
+}
+
+/// Post a message to the main thread from inside the worker.
+fn post(message: &WorkerMessage) {
+    let bytes = js_sys::Uint8Array::from(encode(message).as_slice());
+    if let Some(post_message) = crate::network::global_function("postMessage") {
+        let _ = post_message.call1(&JsValue::NULL, &bytes);
+    }
+}
+
+/// A JS callback that forwards what it is called with as `wrap(value)`.
+fn forwarder(wrap: impl Fn(serde_json::Value) -> WorkerMessage + 'static) -> js_sys::Function {
+    Closure::<dyn Fn(JsValue)>::new(move |value: JsValue| {
+        if let Ok(value) = serde_wasm_bindgen::from_value(value) {
+            post(&wrap(value));
+        }
+    })
+    .into_js_value()
+    .unchecked_into()
+}
+
+/// Run command `id`.
+async fn run(id: u64, command: WorkerCommand) -> Result<CommandResult, WorkerError> {
+    match command {
+        WorkerCommand::Init {
+            network,
+            beacon_endpoints,
+            rpc_endpoints,
+        } => {
+            let beacon_endpoints_json = serde_json::to_string(&beacon_endpoints).map_err(LumenError::serialization)?;
+            let on_progress = forwarder(|progress| WorkerMessage::Progress { progress });
+            let cancellation = crate::cancel::Cancellation::default();
+            SESSIONS.with(|sessions| sessions.borrow_mut().running.push((id, cancellation.clone())));
+            let synced =
+                LumenClient::sync_to_head_until(&beacon_endpoints_json, Some(on_progress), Some(network), &cancellation)
+                    .await;
+            SESSIONS.with(|sessions| sessions.borrow_mut().running.retain(|(running, _)| *running != id));
+            let mut client = synced.map_err(|e| WorkerError::from_js(&e))?;
+            let chain_id = client.network.chain_id;
+            client.rpc_endpoints = rpc_endpoints;
+            client.set_event_handler(Some(forwarder(move |event| WorkerMessage::Event { chain_id, event })));
+            let head_slot = client.head_slot();
+            SESSIONS.with(|sessions| {
+                let mut sessions = sessions.borrow_mut();
+                // Replaces, and stops the polling of, an earlier client for the chain.
+                sessions.registry.insert(Rc::new(client));
+                sessions.registry.activate(chain_id);
+                sessions.beacon_endpoints_json.retain(|(id, _)| *id != chain_id);
+                sessions.beacon_endpoints_json.push((chain_id, beacon_endpoints_json));
+            });
+            Ok(CommandResult::Ready { chain_id, head_slot })
+        }
+        WorkerCommand::Rpc { request } => {
+            let route = SESSIONS.with(|sessions| sessions.borrow().registry.route(&request));
+            let response = match route {
+                Route::Client(client) => client.dispatch(&request).await,
+                Route::Answered(response) => response,
+            };
+            Ok(CommandResult::Rpc { response })
+        }
+        WorkerCommand::StartPolling { interval_ms, chain_id } => {
+            let (client, beacon_endpoints_json) = session(chain_id)?;
+            if interval_ms < crate::sync::MIN_POLL_INTERVAL_MS {
+                return Err(LumenError::new(
+                    ErrorCode::InvalidInput,
+                    format!("interval_ms must be at least {}", crate::sync::MIN_POLL_INTERVAL_MS),
+                )
+                .into());
+            }
+            // Runs until stop_polling or the next init; rounds report through events.
+            wasm_bindgen_futures::spawn_local(async move {
+                let _ = client.start_finality_polling(&beacon_endpoints_json, interval_ms, None).await;
+            });
+            Ok(CommandResult::Done)
+        }
+        WorkerCommand::StopPolling { chain_id } => {
+            session(chain_id)?.0.stop_finality_polling();
+            Ok(CommandResult::Done)
+        }
+        WorkerCommand::GetSyncState { chain_id } => Ok(CommandResult::SyncState {
+            state: session(chain_id)?.0.sync_state(),
+        }),
+        WorkerCommand::Cancel { target } => {
+            SESSIONS.with(|sessions| {
+                let sessions = sessions.borrow();
+                if let Some((_, cancellation)) = sessions.running.iter().find(|(running, _)| *running == target) {
+                    cancellation.cancel();
+                }
+            });
+            Ok(CommandResult::Done)
+        }
+    }
+}
+
+/// Handle one message from the main thread and resolve to the response
+/// bytes to post back. Never rejects: failures are `response`s too.
+#[wasm_bindgen(unchecked_return_type = "Uint8Array")]
+pub async fn dispatch_worker_message(message: Vec<u8>) -> JsValue {
+    let (id, result) = match decode(&message) {
+        Ok((id, command)) => (Some(id), run(id, command).await.unwrap_or_else(|error| CommandResult::Error { error })),
+        Err((id, error)) => (id, CommandResult::Error { error: error.into() }),
+    };
+    js_sys::Uint8Array::from(encode(&WorkerMessage::Response { id, result }).as_slice()).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_commands() {
+        let (id, command) =
+            decode(br#"{"v":1,"id":7,"command":{"type":"init","beacon_endpoints":["https://beacon.example"]}}"#)
+                .unwrap();
+        assert_eq!(id, 7);
+        assert!(matches!(
+            command,
+            WorkerCommand::Init { network, beacon_endpoints, rpc_endpoints }
+                if network == "mainnet" && beacon_endpoints.len() == 1 && rpc_endpoints.is_empty()
+        ));
+
+        let (_, command) = decode(br#"{"v":1,"id":8,"command":{"type":"start_polling","interval_ms":12000}}"#).unwrap();
+        assert!(matches!(command, WorkerCommand::StartPolling { interval_ms: 12_000, chain_id: None }));
+        let (_, command) =
+            decode(br#"{"v":1,"id":8,"command":{"type":"get_sync_state","chain_id":11155111}}"#).unwrap();
+        assert!(matches!(command, WorkerCommand::GetSyncState { chain_id: Some(11_155_111) }));
+        let (_, command) = decode(br#"{"v":1,"id":8,"command":{"type":"stop_polling"}}"#).unwrap();
+        assert!(matches!(command, WorkerCommand::StopPolling { chain_id: None }));
+        let (_, command) = decode(br#"{"v":1,"id":10,"command":{"type":"cancel","target":7}}"#).unwrap();
+        assert!(matches!(command, WorkerCommand::Cancel { target: 7 }));
+        let (_, command) =
+            decode(br#"{"v":1,"id":9,"command":{"type":"rpc","request":{"method":"eth_chainId"}}}"#).unwrap();
+        assert!(matches!(command, WorkerCommand::Rpc { request } if request.method == "eth_chainId"));
+    }
+
+    #[test]
+    fn test_decode_rejects() {
+        let (id, error) = decode(br#"{"v":2,"id":3,"command":{"type":"stop_polling"}}"#).unwrap_err();
+        assert_eq!((id, error.code), (Some(3), ErrorCode::UnsupportedProtocol));
+
+        let (id, error) = decode(br#"{"v":1,"id":4,"command":{"type":"reboot"}}"#).unwrap_err();
+        assert_eq!((id, error.code), (Some(4), ErrorCode::InvalidJson));
+
+        let (id, _) = decode(br#"{"v":1,"id":5}"#).unwrap_err();
+        assert_eq!(id, Some(5));
+        let (id, _) = decode(b"not json").unwrap_err();
+        assert_eq!(id, None);
+    }
+
+    #[test]
+    fn test_message_shape() {
+        let error = LumenError::new(ErrorCode::NotSynced, "send init first");
+        let message = WorkerMessage::Response {
+            id: Some(4),
+            result: CommandResult::Error { error: error.into() },
+        };
+        let json: serde_json::Value = serde_json::from_slice(&encode(&message)).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "v": 1,
+                "type": "response",
+                "id": 4,
+                "result": {
+                    "kind": "error",
+                    "error": {
+                        "code": 2001,
+                        "kind": "not_synced",
+                        "category": "consensus",
+                        "message": "send init first",
+                    },
+                },
+            })
+        );
+
+        let event = WorkerMessage::Event {
+            chain_id: 1,
+            event: serde_json::json!({"type": "head_advanced", "finalized_slot": 1, "period": 0}),
+        };
+        let json: serde_json::Value = serde_json::from_slice(&encode(&event)).unwrap();
+        assert_eq!(json["type"], "event");
+        assert_eq!(json["event"]["type"], "head_advanced");
+        assert_eq!(json["chain_id"], 1);
+    }
+}
@@ -32,12 +32,11 @@ const BEACON_APIS = [
   },
 ];
 
-// P2P gossip topics (from lumen-p2p::beacon_gossip)
-const GOSSIP_TOPICS = {
-  FINALITY_UPDATE:
-    '/eth2/b5303f2a/light_client_finality_update/ssz_snappy',
-  OPTIMISTIC_UPDATE:
-    '/eth2/b5303f2a/light_client_optimistic_update/ssz_snappy',
+// P2P gossip topic names (from lumen-p2p::beacon_gossip). Topics are
+// /eth2/{fork_digest}/{name}/ssz_snappy, with the digest changing at each fork.
+const GOSSIP_TOPIC_NAMES = {
+  FINALITY_UPDATE: 'light_client_finality_update',
+  OPTIMISTIC_UPDATE: 'light_client_optimistic_update',
 };
 
 // Message types between worker and main thread
@@ -169,10 +168,11 @@ async function pollForUpdates(): Promise<void> {
  * For now, this is a placeholder that documents the intended flow.
  */
 function _onGossipMessage(topic: string, _data: Uint8Array): void {
+  const name = topic.split('/')[3];
   const msgType =
-    topic === GOSSIP_TOPICS.FINALITY_UPDATE
+    name === GOSSIP_TOPIC_NAMES.FINALITY_UPDATE
       ? 'finality_update'
-      : topic === GOSSIP_TOPICS.OPTIMISTIC_UPDATE
+      : name === GOSSIP_TOPIC_NAMES.OPTIMISTIC_UPDATE
         ? 'optimistic_update'
         : 'unknown';
 
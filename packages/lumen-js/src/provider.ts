@@ -350,10 +350,15 @@ export class LumenProvider implements EIP1193Provider {
     const slot = params[1] as string;
 
     if (this.options.fallbackRpc) {
-      const proof = await this.fetchStorageProof(address, slot);
+      const proof = (await this.fetchStorageProof(address, slot)) as { storageProof?: unknown[] };
       const result = (await sendToWorker({
         type: 'verify_storage',
-        payload: { address, slot, proofJson: JSON.stringify(proof) },
+        payload: {
+          address,
+          slot,
+          accountProofJson: JSON.stringify(proof),
+          storageProofJson: JSON.stringify(proof.storageProof?.[0] ?? null),
+        },
       })) as { value: string };
 
       return result.value;
@@ -78,7 +78,8 @@ export async function initWasmWorker(): Promise<Worker> {
             const value = lumenClient.verify_storage(
               payload.address,
               payload.slot,
-              payload.proofJson
+              payload.accountProofJson,
+              payload.storageProofJson
             );
             self.postMessage({ id, type: 'success', payload: value });
             break;